@@ -0,0 +1,257 @@
+//! Azure Event Hubs message source
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::{Headers, Message};
+use rdkafka::Offset;
+use sse_gateway::{ConnectionManager, DecodeError, DispatchError, IncomingMessage, MessageHandler, MessageSource};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::sas::ConnectionString;
+
+/// Port of the Kafka endpoint on an Event Hubs namespace
+const KAFKA_PORT: u16 = 9093;
+
+/// Longest wait between attempts after a failed receive or dispatch
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Where a consumer group without a checkpoint starts reading
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StartPosition {
+    /// Only events enqueued after the group first connects
+    #[default]
+    Latest,
+    /// The oldest events the event hub still retains
+    Earliest,
+}
+
+/// Azure Event Hubs message source
+///
+/// Consumes an event hub through the namespace's Kafka endpoint (Standard
+/// tier and above) as a member of a consumer group, so several gateway
+/// instances sharing a group split the partitions between them. Progress is
+/// checkpointed as committed consumer group offsets: an event counts once it
+/// has been fanned out and stored, and offsets are committed every
+/// `checkpoint_interval` and when the source stops. When dispatch fails the
+/// partition is rewound to that event and retried with backoff.
+///
+/// # Application Properties
+///
+/// Event Hubs hands application properties to Kafka consumers as headers,
+/// which are read the same way the Pub/Sub adapter reads attributes:
+/// - `channel_id`: Target channel (optional, omit for broadcast)
+/// - `event_type`: Event type (defaults to "message")
+/// - `id`: Business message ID (defaults to `partition-offset`)
+/// - `expires_at`: RFC 3339 expiry (optional)
+/// - `priority`: `high`, `normal` or `low` (optional)
+///
+/// CloudEvents are also accepted, in binary mode (`ce-*` properties) or
+/// structured mode (content type `application/cloudevents+json`).
+pub struct EventHubsSource {
+    connection_string: String,
+    event_hub: String,
+    consumer_group: String,
+    start_position: StartPosition,
+    checkpoint_interval: Duration,
+    kafka_options: Vec<(String, String)>,
+}
+
+impl EventHubsSource {
+    /// Consume `event_hub` as a member of `consumer_group`
+    pub fn new(
+        connection_string: impl Into<String>,
+        event_hub: impl Into<String>,
+        consumer_group: impl Into<String>,
+    ) -> Self {
+        Self {
+            connection_string: connection_string.into(),
+            event_hub: event_hub.into(),
+            consumer_group: consumer_group.into(),
+            start_position: StartPosition::default(),
+            checkpoint_interval: Duration::from_secs(5),
+            kafka_options: Vec::new(),
+        }
+    }
+
+    /// Where to start when the consumer group has no checkpoint (default: latest)
+    pub fn start_position(mut self, position: StartPosition) -> Self {
+        self.start_position = position;
+        self
+    }
+
+    /// How often delivered offsets are committed (default: 5s)
+    ///
+    /// Events delivered since the last checkpoint are redelivered if the
+    /// gateway crashes; clients deduplicate them by ID.
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval.max(Duration::from_millis(100));
+        self
+    }
+
+    /// Set a librdkafka consumer property, overriding the Event Hubs defaults
+    pub fn kafka_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.kafka_options.push((key.into(), value.into()));
+        self
+    }
+
+    /// Consumer settings for the namespace's Kafka endpoint
+    fn client_config(&self) -> anyhow::Result<ClientConfig> {
+        let connection = ConnectionString::parse(&self.connection_string)?;
+        let host = connection
+            .endpoint
+            .split_once("://")
+            .map_or(connection.endpoint.as_str(), |(_, host)| host);
+
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", format!("{}:{}", host, KAFKA_PORT))
+            .set("security.protocol", "SASL_SSL")
+            .set("sasl.mechanism", "PLAIN")
+            .set("sasl.username", "$ConnectionString")
+            .set("sasl.password", &self.connection_string)
+            .set("group.id", &self.consumer_group)
+            // Offsets are stored once an event is delivered and committed
+            // in the background
+            .set("enable.auto.offset.store", "false")
+            .set("enable.auto.commit", "true")
+            .set(
+                "auto.commit.interval.ms",
+                self.checkpoint_interval.as_millis().to_string(),
+            )
+            .set(
+                "auto.offset.reset",
+                match self.start_position {
+                    StartPosition::Latest => "latest",
+                    StartPosition::Earliest => "earliest",
+                },
+            )
+            // Event Hubs closes connections idle for 240s
+            .set("socket.keepalive.enable", "true")
+            .set("metadata.max.age.ms", "180000");
+        for (key, value) in &self.kafka_options {
+            config.set(key, value);
+        }
+        Ok(config)
+    }
+}
+
+/// Map a received event's headers and body onto an [`IncomingMessage`]
+fn to_incoming(message: &impl Message) -> anyhow::Result<IncomingMessage> {
+    let attributes: HashMap<String, String> = message
+        .headers()
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|header| Some((header.key.to_string(), header_value(header.value?)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut incoming = IncomingMessage::from_attributes(&attributes, message.payload().unwrap_or_default())?;
+    if incoming.id.is_none() {
+        incoming.id = Some(format!("{}-{}", message.partition(), message.offset()));
+    }
+    Ok(incoming)
+}
+
+/// A header value as a string
+///
+/// Properties set by AMQP producers arrive AMQP-encoded; strings are
+/// unwrapped from their `str8`/`str32` framing.
+fn header_value(value: &[u8]) -> Option<String> {
+    let text = match value {
+        [0xa1, len, rest @ ..] if *len as usize == rest.len() => rest,
+        [0xb1, a, b, c, d, rest @ ..] if u32::from_be_bytes([*a, *b, *c, *d]) as usize == rest.len() => rest,
+        _ => value,
+    };
+    std::str::from_utf8(text).ok().map(str::to_string)
+}
+
+#[async_trait]
+impl MessageSource for EventHubsSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let consumer: StreamConsumer = self.client_config()?.create()?;
+        consumer.subscribe(&[&self.event_hub])?;
+        info!(event_hub = %self.event_hub, consumer_group = %self.consumer_group, "Starting Azure Event Hubs");
+
+        let mut delay = Duration::from_secs(1);
+        loop {
+            let received = tokio::select! {
+                _ = cancel.cancelled() => break,
+                received = consumer.recv() => received,
+            };
+
+            let failure = match received {
+                Ok(message) => {
+                    let checkpoint = match to_incoming(&message) {
+                        Ok(incoming) => {
+                            debug!(channel = ?incoming.channel_id, partition = message.partition(), "Received event");
+                            match handler.dispatch(incoming).await {
+                                // Redelivering an invalid or oversized payload won't fix it
+                                Ok(_) | Err(DispatchError::Invalid(_) | DispatchError::TooLarge { .. }) => true,
+                                Err(e) => {
+                                    warn!(error = %e, partition = message.partition(), "Dispatch failed, rewinding partition");
+                                    false
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            // Poison events won't decode on redelivery either
+                            let payload = message.payload().unwrap_or_default().to_vec();
+                            handler.decode_failed(DecodeError::new(self.name(), payload, e));
+                            true
+                        }
+                    };
+
+                    if checkpoint {
+                        delay = Duration::from_secs(1);
+                        if let Err(e) = consumer.store_offset_from_message(&message) {
+                            // The partition was revoked; its new owner redelivers the event
+                            warn!(error = %e, partition = message.partition(), "Failed to store Event Hubs offset");
+                        }
+                        continue;
+                    }
+                    let rewind = consumer.seek(
+                        message.topic(),
+                        message.partition(),
+                        Offset::Offset(message.offset()),
+                        Duration::ZERO,
+                    );
+                    match rewind {
+                        Ok(()) => "dispatch failed".to_string(),
+                        Err(e) => format!("failed to rewind partition: {}", e),
+                    }
+                }
+                // librdkafka reconnects and rejoins the group on its own
+                Err(e) => e.to_string(),
+            };
+
+            warn!(error = %failure, delay_ms = delay.as_millis() as u64, "Event Hubs consumer backing off");
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+
+        // Leaving the group commits the offsets stored since the last
+        // checkpoint, which blocks until the broker answers
+        tokio::task::spawn_blocking(move || drop(consumer)).await?;
+        info!("Azure Event Hubs stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Azure Event Hubs"
+    }
+}
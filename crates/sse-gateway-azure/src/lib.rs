@@ -0,0 +1,23 @@
+//! Azure Service Bus and Event Hubs adapters for SSE Gateway
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use sse_gateway::Gateway;
+//! use sse_gateway_azure::{EventHubsSource, ServiceBusSource};
+//!
+//! Gateway::builder()
+//!     .source(ServiceBusSource::subscription(connection_string, "events", "gateway"))
+//!     // or EventHubsSource::new(connection_string, "events", "gateway")
+//!     .storage(sse_gateway::MemoryStorage::default())
+//!     .build()?
+//!     .run()
+//!     .await
+//! ```
+
+mod event_hubs;
+mod sas;
+mod service_bus;
+
+pub use event_hubs::{EventHubsSource, StartPosition};
+pub use service_bus::ServiceBusSource;
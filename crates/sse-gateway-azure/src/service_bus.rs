@@ -0,0 +1,252 @@
+//! Azure Service Bus message source
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_LENGTH, LOCATION};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sse_gateway::{ConnectionManager, DecodeError, DispatchError, IncomingMessage, MessageHandler, MessageSource};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::sas::ConnectionString;
+
+/// Lifetime of the SAS tokens signed for each request
+const TOKEN_TTL: Duration = Duration::from_secs(3600);
+
+/// Longest wait between attempts after a failed receive
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Azure Service Bus message source
+///
+/// Receives from a queue or a topic subscription in peek-lock mode over the
+/// Service Bus REST API. A message is completed once it has been fanned out
+/// and stored, and abandoned (so Service Bus redelivers it) when dispatch
+/// fails; Service Bus dead-letters it after the entity's max delivery count.
+///
+/// # Application Properties
+///
+/// Read the same way the Pub/Sub adapter reads attributes:
+/// - `channel_id`: Target channel (optional, omit for broadcast)
+/// - `event_type`: Event type (defaults to "message")
+/// - `id`: Business message ID (defaults to the Service Bus `MessageId`)
+/// - `expires_at`: RFC 3339 expiry (optional)
+/// - `priority`: `high`, `normal` or `low` (optional)
+///
+/// CloudEvents are also accepted, in binary mode (`ce-*` properties) or
+/// structured mode (content type `application/cloudevents+json`).
+pub struct ServiceBusSource {
+    connection_string: String,
+    /// Entity path: `queue` or `topic/subscriptions/subscription`
+    entity: String,
+    wait_time: Duration,
+}
+
+impl ServiceBusSource {
+    /// Receive from `queue`
+    pub fn queue(connection_string: impl Into<String>, queue: impl Into<String>) -> Self {
+        Self {
+            connection_string: connection_string.into(),
+            entity: queue.into(),
+            wait_time: Duration::from_secs(30),
+        }
+    }
+
+    /// Receive from `subscription` of `topic`
+    pub fn subscription(
+        connection_string: impl Into<String>,
+        topic: impl Into<String>,
+        subscription: impl Into<String>,
+    ) -> Self {
+        Self::queue(
+            connection_string,
+            format!("{}/subscriptions/{}", topic.into(), subscription.into()),
+        )
+    }
+
+    /// How long each receive waits for a message before asking again (default: 30s)
+    pub fn wait_time(mut self, wait_time: Duration) -> Self {
+        self.wait_time = wait_time.max(Duration::from_secs(1));
+        self
+    }
+}
+
+/// The `BrokerProperties` header of a received message
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BrokerProperties {
+    message_id: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Map a received message's headers and body onto an [`IncomingMessage`]
+///
+/// Application properties arrive as headers, with string values quoted.
+fn to_incoming(headers: &HeaderMap, body: &[u8]) -> anyhow::Result<IncomingMessage> {
+    let broker: BrokerProperties = headers
+        .get("brokerproperties")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default();
+
+    let mut attributes: HashMap<String, String> = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    if let Some(content_type) = broker.content_type {
+        attributes.insert("content-type".to_string(), content_type);
+    }
+
+    let mut incoming = IncomingMessage::from_attributes(&attributes, body)?;
+    if incoming.id.is_none() {
+        incoming.id = broker.message_id;
+    }
+    Ok(incoming)
+}
+
+impl ServiceBusSource {
+    /// Complete (`DELETE`) or abandon (`PUT`) a locked message
+    async fn settle(
+        &self,
+        client: &reqwest::Client,
+        connection: &ConnectionString,
+        location: &str,
+        complete: bool,
+    ) {
+        let request = if complete {
+            client.delete(location)
+        } else {
+            client.put(location)
+        };
+        let result = request
+            .header(AUTHORIZATION, connection.token(&connection.endpoint, TOKEN_TTL))
+            .header(CONTENT_LENGTH, 0)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            // The lock expires and Service Bus redelivers the message
+            error!(error = %e, complete, "Failed to settle Service Bus message");
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSource for ServiceBusSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let connection = ConnectionString::parse(&self.connection_string)?;
+        info!(endpoint = %connection.endpoint, entity = %self.entity, "Starting Azure Service Bus");
+
+        let client = reqwest::Client::builder()
+            .timeout(self.wait_time + Duration::from_secs(10))
+            .build()?;
+        let receive_url = format!(
+            "{}/{}/messages/head?timeout={}",
+            connection.endpoint,
+            self.entity,
+            self.wait_time.as_secs()
+        );
+
+        let mut delay = Duration::from_secs(1);
+        loop {
+            let request = client
+                .post(&receive_url)
+                .header(AUTHORIZATION, connection.token(&connection.endpoint, TOKEN_TTL))
+                .header(CONTENT_LENGTH, 0)
+                .send();
+            let response = tokio::select! {
+                _ = cancel.cancelled() => break,
+                response = request => response,
+            };
+
+            let failure = match response {
+                Ok(response) if response.status() == StatusCode::CREATED => {
+                    delay = Duration::from_secs(1);
+                    let headers = response.headers().clone();
+                    let location = headers
+                        .get(LOCATION)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                    let body = match response.bytes().await {
+                        Ok(body) => body,
+                        Err(e) => {
+                            // Unsettled, so the lock expires and it comes back
+                            warn!(error = %e, "Failed to read Service Bus message");
+                            continue;
+                        }
+                    };
+                    let Some(location) = location else {
+                        warn!("Service Bus message has no lock location, skipping settlement");
+                        continue;
+                    };
+
+                    let complete = match to_incoming(&headers, &body) {
+                        Ok(incoming) => {
+                            debug!(channel = ?incoming.channel_id, "Received message");
+                            match handler.dispatch(incoming).await {
+                                // Redelivering an invalid or oversized payload won't fix it
+                                Ok(_) | Err(DispatchError::Invalid(_) | DispatchError::TooLarge { .. }) => true,
+                                Err(e) => {
+                                    warn!(error = %e, "Dispatch failed, abandoning message");
+                                    false
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            // Poison messages won't decode on redelivery either
+                            handler.decode_failed(DecodeError::new(self.name(), body.to_vec(), e));
+                            true
+                        }
+                    };
+                    self.settle(&client, &connection, &location, complete).await;
+                    continue;
+                }
+                // No message arrived within the wait time
+                Ok(response) if response.status() == StatusCode::NO_CONTENT => {
+                    delay = Duration::from_secs(1);
+                    continue;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    if matches!(
+                        status,
+                        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+                    ) {
+                        // Retrying won't fix credentials or a missing entity
+                        anyhow::bail!("Service Bus rejected receive from '{}': {}", self.entity, status);
+                    }
+                    status.to_string()
+                }
+                Err(e) => e.to_string(),
+            };
+
+            warn!(error = %failure, delay_ms = delay.as_millis() as u64, "Service Bus receive failed, retrying");
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+
+        info!("Azure Service Bus stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Azure Service Bus"
+    }
+}
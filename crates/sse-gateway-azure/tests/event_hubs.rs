@@ -0,0 +1,204 @@
+//! Event Hubs source against a plain Kafka broker standing in for the
+//! namespace's Kafka endpoint
+//!
+//! Requires a running Kafka. Run with:
+//! `KAFKA_BROKERS=localhost:9092 cargo test -p sse-gateway-azure -- --ignored`
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use sse_gateway::testkit::{eventually, SourceFixture};
+use sse_gateway::{ConnectionManager, DispatchError, IncomingMessage, MessageHandler, MessageSource, Priority};
+use sse_gateway_azure::{EventHubsSource, StartPosition};
+use tokio_util::sync::CancellationToken;
+
+const CONNECTION_STRING: &str =
+    "Endpoint=sb://localhost/;SharedAccessKeyName=RootManageSharedAccessKey;SharedAccessKey=c2VjcmV0a2V5PQ==";
+
+fn brokers() -> String {
+    std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string())
+}
+
+/// A topic and consumer group no other test uses
+fn unique(prefix: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    format!("{}-{}-{}", prefix, std::process::id(), nanos)
+}
+
+fn source(event_hub: &str, consumer_group: &str) -> EventHubsSource {
+    EventHubsSource::new(CONNECTION_STRING, event_hub, consumer_group)
+        .start_position(StartPosition::Earliest)
+        .checkpoint_interval(Duration::from_millis(100))
+        .kafka_option("bootstrap.servers", brokers())
+        .kafka_option("security.protocol", "plaintext")
+}
+
+fn producer() -> FutureProducer {
+    ClientConfig::new()
+        .set("bootstrap.servers", brokers())
+        .create()
+        .expect("Kafka producer")
+}
+
+async fn send(producer: &FutureProducer, topic: &str, headers: &[(&str, &[u8])], body: &str) {
+    let headers = headers.iter().fold(OwnedHeaders::new(), |headers, (key, value)| {
+        headers.insert(Header {
+            key,
+            value: Some(*value),
+        })
+    });
+    producer
+        .send(
+            FutureRecord::<(), _>::to(topic).payload(body).headers(headers),
+            Duration::from_secs(5),
+        )
+        .await
+        .map_err(|(e, _)| e)
+        .expect("Kafka send failed");
+}
+
+fn spawn(
+    source: EventHubsSource,
+    handler: MessageHandler,
+) -> (CancellationToken, tokio::task::JoinHandle<anyhow::Result<()>>) {
+    let cancel = CancellationToken::new();
+    let task = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move { source.start(handler, ConnectionManager::new("test"), cancel).await })
+    };
+    (cancel, task)
+}
+
+sse_gateway::source_conformance!(#[ignore = "requires Kafka"] event_hubs_source, async {
+    let topic = unique("conformance");
+    let source = source(&topic, &unique("gateway"));
+    let producer = producer();
+    SourceFixture::new(source, move |msg: IncomingMessage| {
+        let producer = producer.clone();
+        let topic = topic.clone();
+        async move {
+            let channel = msg.channel_id.unwrap_or_default();
+            send(
+                &producer,
+                &topic,
+                &[("channel_id", channel.as_bytes()), ("event_type", msg.event_type.as_bytes())],
+                &msg.data,
+            )
+            .await;
+            Ok(())
+        }
+    })
+});
+
+#[tokio::test]
+#[ignore = "requires Kafka"]
+async fn maps_headers_onto_messages() {
+    let topic = unique("mapping");
+    let producer = producer();
+    send(
+        &producer,
+        &topic,
+        &[
+            // As Event Hubs forwards a property set by an AMQP producer
+            ("channel_id", b"\xa1\x06user-1"),
+            ("event_type", b"order.shipped"),
+            ("priority", b"high"),
+        ],
+        r#"{"order":42}"#,
+    )
+    .await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = MessageHandler::from_fn(move |msg| {
+        let _ = tx.send(msg);
+    });
+    let (cancel, task) = spawn(source(&topic, &unique("gateway")), handler);
+
+    let message = rx.recv().await.unwrap();
+    assert_eq!(message.channel_id.as_deref(), Some("user-1"));
+    assert_eq!(message.event_type, "order.shipped");
+    assert_eq!(message.data, r#"{"order":42}"#);
+    assert_eq!(message.priority, Priority::High);
+    // Falls back to the event's partition and offset
+    assert_eq!(message.id.as_deref(), Some("0-0"));
+
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires Kafka"]
+async fn resumes_from_the_checkpoint() {
+    let topic = unique("checkpoint");
+    let group = unique("gateway");
+    let producer = producer();
+    send(&producer, &topic, &[("channel_id", b"room")], "first").await;
+    send(&producer, &topic, &[("channel_id", b"room")], "second").await;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = MessageHandler::from_fn(move |msg: IncomingMessage| {
+        let _ = tx.send(msg.data);
+    });
+    let (cancel, task) = spawn(source(&topic, &group), handler.clone());
+    assert_eq!(rx.recv().await.unwrap(), "first");
+    assert_eq!(rx.recv().await.unwrap(), "second");
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+
+    // The same consumer group picks up after what it already delivered
+    send(&producer, &topic, &[("channel_id", b"room")], "third").await;
+    let (cancel, task) = spawn(source(&topic, &group), handler);
+    assert_eq!(rx.recv().await.unwrap(), "third");
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires Kafka"]
+async fn redelivers_events_whose_dispatch_fails() {
+    let topic = unique("rewind");
+    let producer = producer();
+    send(&producer, &topic, &[("channel_id", b"room")], "hello").await;
+
+    let attempts = Arc::new(Mutex::new(Vec::new()));
+    let handler = {
+        let attempts = attempts.clone();
+        MessageHandler::new(move |msg: IncomingMessage| {
+            let attempts = attempts.clone();
+            async move {
+                let mut attempts = attempts.lock().unwrap();
+                attempts.push(msg.data);
+                // Throttled once, then delivered
+                if attempts.len() == 1 {
+                    Err(DispatchError::Throttled { retry_after: None })
+                } else {
+                    Ok(Default::default())
+                }
+            }
+        })
+    };
+    let (cancel, task) = spawn(source(&topic, &unique("gateway")), handler);
+
+    assert!(eventually(|| async { attempts.lock().unwrap().len() == 2 }).await);
+    assert_eq!(*attempts.lock().unwrap(), ["hello", "hello"]);
+
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn invalid_connection_string_stops_the_source() {
+    let source = EventHubsSource::new("SharedAccessKey=abc", "events", "gateway");
+    let handler = MessageHandler::from_fn(|_| {});
+
+    let result = source
+        .start(handler, ConnectionManager::new("test"), CancellationToken::new())
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,288 @@
+//! Service Bus source against an in-process stand-in for the REST API
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, post};
+use axum::Router;
+use sse_gateway::testkit::{eventually, SourceFixture};
+use sse_gateway::{ConnectionManager, DispatchError, IncomingMessage, MessageHandler, MessageSource, Priority};
+use sse_gateway_azure::ServiceBusSource;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+struct Message {
+    seq: u64,
+    properties: Vec<(String, String)>,
+    body: String,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<Message>,
+    next_seq: u64,
+    completed: Vec<u64>,
+    abandoned: Vec<u64>,
+    locked: Vec<Message>,
+}
+
+#[derive(Clone)]
+struct MockBus {
+    url: String,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl MockBus {
+    async fn start() -> Self {
+        let queue = Arc::new(Mutex::new(Queue::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/{queue}/messages/head", post(receive))
+            .route("/{queue}/messages/{seq}/{lock}", delete(complete).put(abandon))
+            .with_state(queue.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Self { url, queue }
+    }
+
+    fn connection_string(&self) -> String {
+        format!(
+            "Endpoint={}/;SharedAccessKeyName=RootManageSharedAccessKey;SharedAccessKey=c2VjcmV0a2V5PQ==",
+            self.url
+        )
+    }
+
+    fn send(&self, properties: &[(&str, &str)], body: &str) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.next_seq += 1;
+        let message = Message {
+            seq: queue.next_seq,
+            properties: properties
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        };
+        queue.pending.push_back(message);
+    }
+
+    fn completed(&self) -> Vec<u64> {
+        self.queue.lock().unwrap().completed.clone()
+    }
+
+    fn abandoned(&self) -> Vec<u64> {
+        self.queue.lock().unwrap().abandoned.clone()
+    }
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.starts_with("SharedAccessSignature sr=http%3A%2F%2F127") && !value.ends_with("&skn=")
+        })
+}
+
+async fn receive(
+    State(queue): State<Arc<Mutex<Queue>>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if !authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    // Lock locations are absolute, as Service Bus returns them
+    let host = headers["host"].to_str().unwrap().to_string();
+    for _ in 0..50 {
+        let next = {
+            let mut queue = queue.lock().unwrap();
+            let next = queue.pending.pop_front();
+            if let Some(message) = &next {
+                queue.locked.push(message.clone());
+            }
+            next
+        };
+        if let Some(message) = next {
+            let mut response = message.body.into_response();
+            let headers = response.headers_mut();
+            headers.insert(
+                "BrokerProperties",
+                format!(r#"{{"MessageId":"sb-{}","SequenceNumber":{},"DeliveryCount":1}}"#, message.seq, message.seq)
+                    .parse()
+                    .unwrap(),
+            );
+            headers.insert(
+                "Location",
+                format!("http://{}/{}/messages/{}/lock-{}", host, name, message.seq, message.seq)
+                    .parse()
+                    .unwrap(),
+            );
+            for (property, value) in &message.properties {
+                headers.insert(
+                    axum::http::HeaderName::from_bytes(property.as_bytes()).unwrap(),
+                    value.parse().unwrap(),
+                );
+            }
+            *response.status_mut() = StatusCode::CREATED;
+            return response;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn complete(
+    State(queue): State<Arc<Mutex<Queue>>>,
+    Path((_, seq, _)): Path<(String, u64, String)>,
+    headers: HeaderMap,
+) -> StatusCode {
+    if !authorized(&headers) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let mut queue = queue.lock().unwrap();
+    queue.locked.retain(|message| message.seq != seq);
+    queue.completed.push(seq);
+    StatusCode::OK
+}
+
+async fn abandon(
+    State(queue): State<Arc<Mutex<Queue>>>,
+    Path((_, seq, _)): Path<(String, u64, String)>,
+    headers: HeaderMap,
+) -> StatusCode {
+    if !authorized(&headers) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let mut queue = queue.lock().unwrap();
+    queue.abandoned.push(seq);
+    if let Some(position) = queue.locked.iter().position(|message| message.seq == seq) {
+        let message = queue.locked.remove(position);
+        queue.pending.push_front(message);
+    }
+    StatusCode::OK
+}
+
+fn source(bus: &MockBus) -> ServiceBusSource {
+    ServiceBusSource::queue(bus.connection_string(), "events").wait_time(Duration::from_secs(1))
+}
+
+sse_gateway::source_conformance!(service_bus_source, async {
+    let bus = MockBus::start().await;
+    let source = source(&bus);
+    SourceFixture::new(source, move |msg: IncomingMessage| {
+        let bus = bus.clone();
+        async move {
+            let channel = format!("\"{}\"", msg.channel_id.unwrap_or_default());
+            let event_type = format!("\"{}\"", msg.event_type);
+            bus.send(&[("channel_id", &channel), ("event_type", &event_type)], &msg.data);
+            Ok(())
+        }
+    })
+});
+
+#[tokio::test]
+async fn maps_properties_and_completes_delivered_messages() {
+    let bus = MockBus::start().await;
+    bus.send(
+        &[
+            ("channel_id", "\"user-1\""),
+            ("event_type", "\"order.shipped\""),
+            ("priority", "\"high\""),
+        ],
+        r#"{"order":42}"#,
+    );
+    bus.send(
+        &[
+            ("ce-specversion", "\"1.0\""),
+            ("ce-id", "\"evt-1\""),
+            ("ce-source", "\"/orders\""),
+            ("ce-type", "\"order.cancelled\""),
+            ("ce-subject", "\"user-2\""),
+        ],
+        "cancelled",
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = MessageHandler::from_fn(move |msg| {
+        let _ = tx.send(msg);
+    });
+    let cancel = CancellationToken::new();
+    let source = source(&bus);
+    let task = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move { source.start(handler, ConnectionManager::new("test"), cancel).await })
+    };
+
+    let first = rx.recv().await.unwrap();
+    assert_eq!(first.channel_id.as_deref(), Some("user-1"));
+    assert_eq!(first.event_type, "order.shipped");
+    assert_eq!(first.data, r#"{"order":42}"#);
+    assert_eq!(first.priority, Priority::High);
+    // Falls back to the broker's MessageId
+    assert_eq!(first.id.as_deref(), Some("sb-1"));
+
+    let second = rx.recv().await.unwrap();
+    assert_eq!(second.channel_id.as_deref(), Some("user-2"));
+    assert_eq!(second.event_type, "order.cancelled");
+    assert_eq!(second.id.as_deref(), Some("evt-1"));
+
+    assert!(eventually(|| async { bus.completed() == vec![1, 2] }).await);
+    assert!(bus.abandoned().is_empty());
+
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn abandons_messages_whose_dispatch_fails() {
+    let bus = MockBus::start().await;
+    bus.send(&[("channel_id", "\"user-1\"")], "hello");
+
+    let attempts = Arc::new(Mutex::new(0));
+    let handler = {
+        let attempts = attempts.clone();
+        MessageHandler::new(move |_msg| {
+            let attempts = attempts.clone();
+            async move {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                // Throttled once, then delivered
+                if *attempts == 1 {
+                    Err(DispatchError::Throttled { retry_after: None })
+                } else {
+                    Ok(Default::default())
+                }
+            }
+        })
+    };
+    let cancel = CancellationToken::new();
+    let source = source(&bus);
+    let task = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move { source.start(handler, ConnectionManager::new("test"), cancel).await })
+    };
+
+    assert!(eventually(|| async { bus.completed() == vec![1] }).await);
+    assert_eq!(bus.abandoned(), vec![1]);
+    assert_eq!(*attempts.lock().unwrap(), 2);
+
+    cancel.cancel();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn rejected_credentials_stop_the_source() {
+    let bus = MockBus::start().await;
+    let source = ServiceBusSource::queue(format!("Endpoint={}/;SharedAccessKeyName=;SharedAccessKey=", bus.url), "events");
+    let handler = MessageHandler::from_fn(|_| {});
+
+    let result = source
+        .start(handler, ConnectionManager::new("test"), CancellationToken::new())
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,405 @@
+//! Reconnecting subscriber
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use reqwest::StatusCode;
+use sse_gateway::SseEvent;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::parser::{Frame, SseParser};
+use crate::typed::TypedStream;
+
+/// Event the gateway sends on each heartbeat tick by default
+pub const HEARTBEAT_EVENT: &str = "heartbeat";
+/// Sent once a subscription is registered; `data` may carry `{"connection_id": "..."}`
+pub const CONNECTED_EVENT: &str = "connected";
+/// Sent before the gateway closes the stream to shut down; the client reconnects
+pub const SHUTDOWN_EVENT: &str = "shutdown";
+
+const DEFAULT_PATH: &str = "/sse/connect";
+const DEFAULT_CHANNEL_PARAM: &str = "channel_id";
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// Events buffered between the connection task and the consumer
+const EVENT_BUFFER: usize = 256;
+
+/// Why an [`EventStream`] ended
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ClientError {
+    /// The gateway refused the subscription (e.g. `401`, `403`); not retried
+    #[error("subscription rejected with status {0}")]
+    Rejected(u16),
+    /// `max_retries` consecutive connection attempts failed
+    #[error("gave up after {0} failed attempts: {1}")]
+    RetriesExhausted(u32, String),
+    /// The request could not be built (e.g. an invalid URL or header)
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+/// Subscriber configuration
+///
+/// ```rust,ignore
+/// let mut events = SseClient::new("http://localhost:8080", "user123")
+///     .bearer_token(token)
+///     .connect();
+///
+/// while let Some(event) = events.next().await {
+///     println!("{}: {}", event.event_type, event.data);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SseClient {
+    http: reqwest::Client,
+    base_url: String,
+    path: String,
+    channel_param: String,
+    channel_id: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    last_event_id: Option<String>,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    idle_timeout: Option<Duration>,
+    heartbeat_event: String,
+    max_retries: Option<u32>,
+}
+
+impl SseClient {
+    /// Subscribe to `channel_id` on the gateway at `base_url`
+    pub fn new(base_url: impl Into<String>, channel_id: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            path: DEFAULT_PATH.to_string(),
+            channel_param: DEFAULT_CHANNEL_PARAM.to_string(),
+            channel_id: channel_id.into(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            last_event_id: None,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            max_reconnect_delay: DEFAULT_MAX_RECONNECT_DELAY,
+            idle_timeout: None,
+            heartbeat_event: HEARTBEAT_EVENT.to_string(),
+            max_retries: None,
+        }
+    }
+
+    /// Use this HTTP client (for proxies, TLS roots, timeouts)
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// SSE endpoint path (default `/sse/connect`)
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Query parameter carrying the channel (default `channel_id`)
+    pub fn channel_param(mut self, param: impl Into<String>) -> Self {
+        self.channel_param = param.into();
+        self
+    }
+
+    /// Add a query parameter (e.g. `consumer_id`, `group`, connection attributes)
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    /// Send a header with every connection attempt
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send `Authorization: Bearer <token>` with every connection attempt
+    pub fn bearer_token(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.header("authorization", value)
+    }
+
+    /// Resume after this event on the first connection
+    pub fn last_event_id(mut self, id: impl Into<String>) -> Self {
+        self.last_event_id = Some(id.into());
+        self
+    }
+
+    /// Delay before reconnecting (default 1s); doubles after each failed attempt
+    ///
+    /// A `retry:` field from the gateway replaces it.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Upper bound for the reconnect backoff (default 30s)
+    pub fn max_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.max_reconnect_delay = delay;
+        self
+    }
+
+    /// Reconnect if nothing, not even a heartbeat, arrives for this long
+    ///
+    /// Set it comfortably above the gateway's heartbeat interval.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Event type the gateway uses for heartbeats (default `heartbeat`)
+    pub fn heartbeat_event(mut self, event_type: impl Into<String>) -> Self {
+        self.heartbeat_event = event_type.into();
+        self
+    }
+
+    /// End the stream after this many consecutive failed attempts (default: retry forever)
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    /// Start subscribing in the background
+    ///
+    /// Must be called from within a Tokio runtime. Dropping the stream closes
+    /// the connection.
+    pub fn connect(self) -> EventStream {
+        let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+        let state = Arc::new(Mutex::new(State {
+            last_event_id: self.last_event_id.clone(),
+            ..State::default()
+        }));
+        let task = tokio::spawn(run(self, tx, state.clone()));
+        EventStream { rx, state, task }
+    }
+
+    fn request(&self, last_event_id: Option<&str>) -> reqwest::RequestBuilder {
+        let mut request = self
+            .http
+            .get(format!("{}{}", self.base_url, self.path))
+            .query(&[(&self.channel_param, &self.channel_id)])
+            .query(&self.query)
+            .header("accept", "text/event-stream");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id);
+        }
+        request
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    last_event_id: Option<String>,
+    connection_id: Option<String>,
+    connected: bool,
+    error: Option<ClientError>,
+}
+
+/// Live events of a channel, across reconnects
+///
+/// Heartbeat, `connected` and `shutdown` events are handled internally and
+/// not yielded. The stream ends when the gateway rejects the subscription or
+/// retries run out; [`error`](Self::error) says why.
+pub struct EventStream {
+    rx: mpsc::Receiver<SseEvent>,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl EventStream {
+    /// ID of the last event received, sent as `Last-Event-ID` on reconnect
+    pub fn last_event_id(&self) -> Option<String> {
+        self.state.lock().unwrap().last_event_id.clone()
+    }
+
+    /// Connection ID the gateway announced in its `connected` event, if any
+    pub fn connection_id(&self) -> Option<String> {
+        self.state.lock().unwrap().connection_id.clone()
+    }
+
+    /// Whether a connection is currently open
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    /// Why the stream ended, if it did
+    pub fn error(&self) -> Option<ClientError> {
+        self.state.lock().unwrap().error.clone()
+    }
+
+    /// Deserialize events by event type (see [`TypedStream`])
+    pub fn typed<T>(self) -> TypedStream<T> {
+        TypedStream::new(self)
+    }
+}
+
+impl Stream for EventStream {
+    type Item = SseEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SseEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// How one connection attempt ended
+enum Outcome {
+    /// The stream was open and then ended (closed, idle, or `shutdown`)
+    Ended,
+    /// The attempt failed before or while connecting
+    Failed(String),
+    /// Don't reconnect
+    Fatal(ClientError),
+    /// The consumer dropped the stream
+    Dropped,
+}
+
+async fn run(client: SseClient, tx: mpsc::Sender<SseEvent>, state: Arc<Mutex<State>>) {
+    let mut failures = 0;
+    let mut delay = client.reconnect_delay;
+    loop {
+        let outcome = connect_once(&client, &tx, &state, &mut delay).await;
+        state.lock().unwrap().connected = false;
+        match outcome {
+            Outcome::Ended => {
+                failures = 0;
+                tracing::debug!(channel_id = %client.channel_id, "SSE stream ended, reconnecting");
+            }
+            Outcome::Failed(reason) => {
+                failures += 1;
+                tracing::warn!(channel_id = %client.channel_id, attempt = failures, reason, "SSE connection failed");
+                if client.max_retries.is_some_and(|max| failures > max) {
+                    state.lock().unwrap().error = Some(ClientError::RetriesExhausted(failures, reason));
+                    return;
+                }
+            }
+            Outcome::Fatal(error) => {
+                tracing::error!(channel_id = %client.channel_id, %error, "SSE subscription stopped");
+                state.lock().unwrap().error = Some(error);
+                return;
+            }
+            Outcome::Dropped => return,
+        }
+
+        let wait = if failures == 0 {
+            delay
+        } else {
+            delay
+                .saturating_mul(1 << (failures - 1).min(16))
+                .min(client.max_reconnect_delay)
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tx.closed() => return,
+        }
+    }
+}
+
+async fn connect_once(
+    client: &SseClient,
+    tx: &mpsc::Sender<SseEvent>,
+    state: &Arc<Mutex<State>>,
+    delay: &mut Duration,
+) -> Outcome {
+    let last_event_id = state.lock().unwrap().last_event_id.clone();
+    let request = match client.request(last_event_id.as_deref()).build() {
+        Ok(request) => request,
+        Err(e) => return Outcome::Fatal(ClientError::InvalidRequest(e.to_string())),
+    };
+
+    let response = tokio::select! {
+        response = client.http.execute(request) => response,
+        _ = tx.closed() => return Outcome::Dropped,
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    let status = response.status();
+    if !status.is_success() {
+        let retryable = status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS;
+        return if retryable {
+            Outcome::Failed(format!("status {}", status.as_u16()))
+        } else {
+            Outcome::Fatal(ClientError::Rejected(status.as_u16()))
+        };
+    }
+
+    state.lock().unwrap().connected = true;
+    tracing::debug!(channel_id = %client.channel_id, last_event_id = ?last_event_id, "SSE connected");
+
+    let mut body = response.bytes_stream();
+    let mut parser = SseParser::new();
+    loop {
+        let next = async {
+            match client.idle_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, body.next()).await.ok(),
+                None => Some(body.next().await),
+            }
+        };
+        let chunk = tokio::select! {
+            chunk = next => chunk,
+            _ = tx.closed() => return Outcome::Dropped,
+        };
+        let chunk = match chunk {
+            None => {
+                tracing::debug!(channel_id = %client.channel_id, "SSE stream idle, reconnecting");
+                return Outcome::Ended;
+            }
+            Some(None) => return Outcome::Ended,
+            Some(Some(Err(e))) => return Outcome::Failed(e.to_string()),
+            Some(Some(Ok(chunk))) => chunk,
+        };
+
+        let frames = parser.feed(&chunk);
+        if let Some(retry) = parser.retry() {
+            *delay = Duration::from_millis(retry);
+        }
+        for frame in frames {
+            if let Some(id) = &frame.id {
+                state.lock().unwrap().last_event_id = Some(id.clone());
+            }
+            match frame.event.as_deref() {
+                Some(event_type) if event_type == client.heartbeat_event => {}
+                Some(CONNECTED_EVENT) => {
+                    let connection_id = serde_json::from_str::<serde_json::Value>(&frame.data)
+                        .ok()
+                        .and_then(|data| data.get("connection_id")?.as_str().map(str::to_string));
+                    state.lock().unwrap().connection_id = connection_id;
+                }
+                Some(SHUTDOWN_EVENT) => {
+                    tracing::info!(channel_id = %client.channel_id, "Gateway shutting down, reconnecting");
+                    return Outcome::Ended;
+                }
+                _ => {
+                    if tx.send(to_event(frame)).await.is_err() {
+                        return Outcome::Dropped;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn to_event(frame: Frame) -> SseEvent {
+    let mut event = SseEvent::raw(frame.event.unwrap_or_else(|| "message".to_string()), frame.data);
+    event.id = frame.id;
+    event
+}
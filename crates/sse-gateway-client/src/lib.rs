@@ -0,0 +1,44 @@
+//! Reconnecting SSE client for SSE Gateway
+//!
+//! Subscribes to a gateway channel and yields its events as a
+//! `Stream<Item = SseEvent>`. Dropped connections are re-established with
+//! `Last-Event-ID`, so missed events are replayed; heartbeats and the
+//! gateway's `connected`/`shutdown` control events are handled internally.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use futures::StreamExt;
+//! use sse_gateway_client::SseClient;
+//!
+//! let mut events = SseClient::new("http://localhost:8080", "user123")
+//!     .bearer_token("secret")
+//!     .connect();
+//!
+//! while let Some(event) = events.next().await {
+//!     println!("{} {}: {}", event.id.unwrap_or_default(), event.event_type, event.data);
+//! }
+//! ```
+//!
+//! Backends publish with [`GatewayPublisher`], which wraps the push and
+//! channel-status endpoints with retries and instance discovery:
+//!
+//! ```rust,ignore
+//! use sse_gateway_client::{GatewayPublisher, PushMessage};
+//!
+//! let publisher = GatewayPublisher::new("http://localhost:8080");
+//! publisher
+//!     .push(&PushMessage::new("user123", "notification", serde_json::json!({"msg": "hi"})))
+//!     .await?;
+//! ```
+
+pub mod client;
+pub mod parser;
+pub mod publisher;
+pub mod typed;
+
+pub use client::{ClientError, EventStream, SseClient};
+pub use parser::{Frame, SseParser};
+pub use publisher::{ChannelStatus, GatewayPublisher, PublishError, PushMessage, PushResult};
+pub use sse_gateway::{InstanceInfo, IntoSseEvent, Priority, SseEvent};
+pub use typed::{DecodeError, TypedStream};
@@ -0,0 +1,97 @@
+//! Incremental `text/event-stream` parser
+
+/// One dispatched SSE message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    /// `event:` field; `None` means the default `message` type
+    pub event: Option<String>,
+    /// `data:` lines joined with `\n`
+    pub data: String,
+    /// `id:` field of this message, if it had one
+    pub id: Option<String>,
+}
+
+/// Parses SSE messages from arbitrarily split chunks
+///
+/// Lines may end in `\n` or `\r\n`; comments (`: keep-alive`) are skipped.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseParser {
+    /// Create an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last `id:` seen, which the spec keeps even across messages without one
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Last `retry:` reconnection delay seen, in milliseconds
+    pub fn retry(&self) -> Option<u64> {
+        self.retry
+    }
+
+    /// Feed a chunk, returning the messages it completed
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Frame> {
+        self.buffer.extend_from_slice(chunk);
+        let mut frames = Vec::new();
+        // Only complete lines are decoded, so multi-byte characters can't be split
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if let Some(frame) = self.line(&String::from_utf8_lossy(&line)) {
+                frames.push(frame);
+            }
+        }
+        frames
+    }
+
+    fn line(&mut self, line: &str) -> Option<Frame> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => {
+                self.id = Some(value.to_string());
+                self.last_event_id = Some(value.to_string());
+            }
+            "retry" => self.retry = value.parse().ok().or(self.retry),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Frame> {
+        let event = self.event.take();
+        let id = self.id.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(Frame {
+            event: event.filter(|event| !event.is_empty()),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id,
+        })
+    }
+}
@@ -0,0 +1,526 @@
+//! Publisher for the gateway's HTTP push API
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sse_gateway::{InstanceInfo, IntoSseEvent, Priority};
+
+const DEFAULT_PUSH_PATH: &str = "/push";
+const DEFAULT_STATUS_PATH: &str = "/channel";
+const DEFAULT_INSTANCES_PATH: &str = "/instances";
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Idle keep-alive connections kept per gateway instance
+const DEFAULT_POOL_IDLE_PER_HOST: usize = 32;
+/// Messages per `/push/batch` request, matching the gateway's limit
+const BATCH_LIMIT: usize = 1000;
+
+/// Why a publish or query failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PublishError {
+    /// The gateway answered with a non-retryable status (e.g. `400`, `401`)
+    #[error("request rejected with status {status}: {body}")]
+    Rejected { status: u16, body: String },
+    /// Every attempt failed with a transport error or a retryable status
+    #[error("gave up after {0} failed attempts: {1}")]
+    Unavailable(u32, String),
+    /// The gateway's answer could not be decoded
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    /// The request could not be built (e.g. an invalid URL or header)
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+/// One event to publish, as accepted by `POST /push`
+#[derive(Debug, Clone, Serialize)]
+pub struct PushMessage {
+    /// Target channel; `None` broadcasts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// SSE event type
+    pub event_type: String,
+    /// Event payload
+    pub data: serde_json::Value,
+    /// Business ID, used as the SSE `id` when the event is not stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Drop the event this many seconds after it was pushed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+    /// Delivery priority
+    pub priority: Priority,
+}
+
+impl PushMessage {
+    /// Event for `channel_id`
+    pub fn new(
+        channel_id: impl Into<String>,
+        event_type: impl Into<String>,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            channel_id: Some(channel_id.into()),
+            ..Self::broadcast(event_type, data)
+        }
+    }
+
+    /// Event for every connection
+    pub fn broadcast(event_type: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            channel_id: None,
+            event_type: event_type.into(),
+            data,
+            id: None,
+            ttl_secs: None,
+            priority: Priority::default(),
+        }
+    }
+
+    /// `payload` for `channel_id`, as its [`IntoSseEvent::EVENT_TYPE`]
+    pub fn typed<D: IntoSseEvent>(
+        channel_id: impl Into<String>,
+        payload: &D,
+    ) -> serde_json::Result<Self> {
+        Ok(Self::new(channel_id, D::EVENT_TYPE, serde_json::to_value(payload)?))
+    }
+
+    /// Set the business ID
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Drop the event if it is not delivered within `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl_secs = Some(ttl.as_secs());
+        self
+    }
+
+    /// Set the delivery priority
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Outcome of one pushed event
+///
+/// Fields the gateway doesn't report (e.g. the standalone push server has no
+/// `delivered` count) default to zero values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PushResult {
+    /// Event was delivered to at least one connection or stored for replay
+    pub success: bool,
+    /// The channel had connections when the event arrived
+    pub online: bool,
+    /// Number of connections the event was sent to
+    pub delivered: usize,
+    /// Replay cursor, if the event was stored
+    pub stream_id: Option<String>,
+    /// Event was written to storage
+    pub stored: bool,
+    /// Why the event was not accepted
+    pub error: Option<String>,
+}
+
+/// Where a channel's subscribers are, as reported by `GET /channel/{id}`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ChannelStatus {
+    /// Channel ID
+    pub channel_id: String,
+    /// Some instance holds subscribers on the channel
+    pub online: bool,
+    /// Instance that owns the channel
+    pub instance_id: Option<String>,
+    /// Address of the owning instance
+    pub instance_address: Option<String>,
+    /// Every instance holding subscribers on the channel
+    pub instance_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<PushResult>,
+}
+
+#[derive(Deserialize)]
+struct InstancesResponse {
+    instances: Vec<InstanceInfo>,
+}
+
+/// HTTP client for publishing to the gateway
+///
+/// Wraps `POST /push`, `POST /push/batch` and the channel-status queries so
+/// backends don't re-implement the plumbing. Requests share one pooled
+/// HTTP client, are spread round-robin over the known instances, and are
+/// retried on another instance after a transport error or a `408`, `429`
+/// or `5xx` answer.
+///
+/// A retried push may be delivered twice if the first attempt reached the
+/// gateway; set [`PushMessage::with_id`] so subscribers can de-duplicate.
+///
+/// ```rust,ignore
+/// let publisher = GatewayPublisher::new("http://localhost:8080")
+///     .bearer_token(token);
+///
+/// let result = publisher
+///     .push(&PushMessage::new("user123", "notification", json!({"msg": "hi"})))
+///     .await?;
+/// ```
+///
+/// In cluster mode, [`discover`](Self::discover) points it at the instance
+/// registry instead of a fixed address.
+#[derive(Clone)]
+pub struct GatewayPublisher {
+    http: reqwest::Client,
+    endpoints: Arc<RwLock<Vec<String>>>,
+    next: Arc<AtomicUsize>,
+    registry: Option<String>,
+    push_path: String,
+    status_path: String,
+    instances_path: String,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl GatewayPublisher {
+    /// Publish to the gateway at `base_url`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .pool_max_idle_per_host(DEFAULT_POOL_IDLE_PER_HOST)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            endpoints: Arc::new(RwLock::new(vec![normalize(&base_url.into())])),
+            next: Arc::new(AtomicUsize::new(0)),
+            registry: None,
+            push_path: DEFAULT_PUSH_PATH.to_string(),
+            status_path: DEFAULT_STATUS_PATH.to_string(),
+            instances_path: DEFAULT_INSTANCES_PATH.to_string(),
+            headers: Vec::new(),
+            timeout: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    /// Also publish to these instances, round-robin
+    pub fn endpoints<I, E>(self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        self.endpoints
+            .write()
+            .unwrap()
+            .extend(endpoints.into_iter().map(|e| normalize(&e.into())));
+        self
+    }
+
+    /// Find instances through the registry at `registry_url` (cluster mode)
+    ///
+    /// The registry serves `GET /instances` (see [`instances_path`](Self::instances_path)),
+    /// listing each instance's advertised address. The list is loaded on
+    /// [`refresh_instances`](Self::refresh_instances) and re-loaded whenever
+    /// every known instance has failed.
+    pub fn discover(mut self, registry_url: impl Into<String>) -> Self {
+        self.registry = Some(normalize(&registry_url.into()));
+        self
+    }
+
+    /// Use this HTTP client (for proxies, TLS roots, pool sizing)
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Push endpoint path (default `/push`); batches go to `{path}/batch`
+    pub fn push_path(mut self, path: impl Into<String>) -> Self {
+        self.push_path = path.into();
+        self
+    }
+
+    /// Channel status path (default `/channel`); queried as `{path}/{channel_id}`
+    pub fn status_path(mut self, path: impl Into<String>) -> Self {
+        self.status_path = path.into();
+        self
+    }
+
+    /// Instance list path (default `/instances`)
+    pub fn instances_path(mut self, path: impl Into<String>) -> Self {
+        self.instances_path = path.into();
+        self
+    }
+
+    /// Send a header with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request
+    pub fn bearer_token(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.header("authorization", value)
+    }
+
+    /// Give up on a single attempt after `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries after the first attempt (default 3)
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry (default 100ms); doubles after each one
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Instances requests are currently spread over
+    pub fn current_endpoints(&self) -> Vec<String> {
+        self.endpoints.read().unwrap().clone()
+    }
+
+    /// Publish one event
+    ///
+    /// An event the gateway refused (expired, filtered, not allowed) is an
+    /// `Ok` result with `success == false` and the reason in `error`.
+    pub async fn push(&self, message: &PushMessage) -> Result<PushResult, PublishError> {
+        let body = encode(message)?;
+        let (status, bytes) = self
+            .request(Method::POST, &self.push_path, Some(body))
+            .await?;
+        if !status.is_success() && status != StatusCode::UNPROCESSABLE_ENTITY {
+            return Err(rejected(status, &bytes));
+        }
+        decode(&bytes)
+    }
+
+    /// Publish many events, one result per message in order
+    ///
+    /// Sent as `{path}/batch` requests of at most 1000 messages each.
+    pub async fn push_batch(
+        &self,
+        messages: &[PushMessage],
+    ) -> Result<Vec<PushResult>, PublishError> {
+        let path = format!("{}/batch", self.push_path.trim_end_matches('/'));
+        let mut results = Vec::with_capacity(messages.len());
+        for chunk in messages.chunks(BATCH_LIMIT) {
+            let body = encode(chunk)?;
+            let (status, bytes) = self.request(Method::POST, &path, Some(body)).await?;
+            if !status.is_success() {
+                return Err(rejected(status, &bytes));
+            }
+            results.extend(decode::<BatchResponse>(&bytes)?.results);
+        }
+        Ok(results)
+    }
+
+    /// Which instances hold subscribers on `channel_id`
+    pub async fn channel_status(&self, channel_id: &str) -> Result<ChannelStatus, PublishError> {
+        let path = format!(
+            "{}/{}",
+            self.status_path.trim_end_matches('/'),
+            encode_segment(channel_id)
+        );
+        let (status, bytes) = self.request(Method::GET, &path, None).await?;
+        if !status.is_success() {
+            return Err(rejected(status, &bytes));
+        }
+        decode(&bytes)
+    }
+
+    /// Registered gateway instances
+    ///
+    /// Asks the registry if one was set with [`discover`](Self::discover),
+    /// otherwise any known instance.
+    pub async fn instances(&self) -> Result<Vec<InstanceInfo>, PublishError> {
+        match &self.registry {
+            Some(registry) => self.registry_instances(registry).await,
+            None => {
+                let (status, bytes) = self
+                    .request(Method::GET, &self.instances_path, None)
+                    .await?;
+                if !status.is_success() {
+                    return Err(rejected(status, &bytes));
+                }
+                Ok(decode::<InstancesResponse>(&bytes)?.instances)
+            }
+        }
+    }
+
+    /// Replace the endpoint list with the instances' advertised addresses
+    ///
+    /// Instances without an address are skipped; if none have one, the list
+    /// is left as it was. Returns the number of endpoints now in use.
+    pub async fn refresh_instances(&self) -> Result<usize, PublishError> {
+        let instances = self.instances().await?;
+        Ok(self.use_instances(instances))
+    }
+
+    /// One registry query, without retries (those are driven by [`request`](Self::request))
+    async fn registry_instances(&self, registry: &str) -> Result<Vec<InstanceInfo>, PublishError> {
+        let (status, bytes) = self
+            .attempt(registry, Method::GET, &self.instances_path, None)
+            .await?;
+        if !status.is_success() {
+            return Err(rejected(status, &bytes));
+        }
+        Ok(decode::<InstancesResponse>(&bytes)?.instances)
+    }
+
+    fn use_instances(&self, instances: Vec<InstanceInfo>) -> usize {
+        let addresses: Vec<String> = instances
+            .into_iter()
+            .filter_map(|instance| instance.address)
+            .map(|address| normalize(&address))
+            .collect();
+        let mut endpoints = self.endpoints.write().unwrap();
+        if !addresses.is_empty() {
+            tracing::debug!(count = addresses.len(), "Discovered gateway instances");
+            *endpoints = addresses;
+        }
+        endpoints.len()
+    }
+
+    /// Send with retries, rotating over endpoints
+    ///
+    /// Returns the first answer that isn't worth retrying.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<(StatusCode, Vec<u8>), PublishError> {
+        let mut delay = self.retry_delay;
+        let mut last_error = String::new();
+        let mut failed_in_pass = 0;
+        for attempt in 0..=self.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+            let (endpoint, known) = {
+                let endpoints = self.endpoints.read().unwrap();
+                let index = self.next.fetch_add(1, Ordering::Relaxed) % endpoints.len();
+                (endpoints[index].clone(), endpoints.len())
+            };
+
+            match self
+                .attempt(&endpoint, method.clone(), path, body.clone())
+                .await
+            {
+                Ok((status, bytes)) if !retryable(status) => return Ok((status, bytes)),
+                Ok((status, _)) => last_error = format!("{} answered {}", endpoint, status),
+                Err(PublishError::Unavailable(_, error)) => last_error = error,
+                Err(e) => return Err(e),
+            }
+            tracing::debug!(attempt, error = %last_error, "Gateway request failed");
+
+            failed_in_pass += 1;
+            if let (true, Some(registry)) = (failed_in_pass >= known, &self.registry) {
+                failed_in_pass = 0;
+                match self.registry_instances(registry).await {
+                    Ok(instances) => {
+                        self.use_instances(instances);
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Instance discovery failed"),
+                }
+            }
+        }
+        Err(PublishError::Unavailable(self.max_retries + 1, last_error))
+    }
+
+    /// One request to one endpoint; transport errors come back as `Unavailable`
+    async fn attempt(
+        &self,
+        endpoint: &str,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<(StatusCode, Vec<u8>), PublishError> {
+        let url = Url::parse(&format!("{}{}", endpoint, path))
+            .map_err(|e| PublishError::InvalidRequest(e.to_string()))?;
+        let mut request = self.http.request(method, url);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(body) = body {
+            request = request
+                .header("content-type", "application/json")
+                .body(body);
+        }
+
+        let unavailable = |e: reqwest::Error| PublishError::Unavailable(1, e.to_string());
+        let response = request.send().await.map_err(|e| {
+            if e.is_builder() {
+                PublishError::InvalidRequest(e.to_string())
+            } else {
+                unavailable(e)
+            }
+        })?;
+        let status = response.status();
+        let bytes = response.bytes().await.map_err(unavailable)?;
+        Ok((status, bytes.to_vec()))
+    }
+}
+
+/// Statuses another attempt (possibly on another instance) may fix
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// Trim the trailing slash, defaulting to `http://` for bare `host:port` addresses
+fn normalize(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    }
+}
+
+/// Percent-encode a single path segment
+fn encode_segment(segment: &str) -> String {
+    let mut url = Url::parse("http://localhost").expect("static URL is valid");
+    url.path_segments_mut()
+        .expect("http URLs have path segments")
+        .push(segment);
+    url.path()[1..].to_string()
+}
+
+fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, PublishError> {
+    serde_json::to_vec(value).map_err(|e| PublishError::InvalidRequest(e.to_string()))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, PublishError> {
+    serde_json::from_slice(bytes).map_err(|e| PublishError::InvalidResponse(e.to_string()))
+}
+
+fn rejected(status: StatusCode, body: &[u8]) -> PublishError {
+    PublishError::Rejected {
+        status: status.as_u16(),
+        body: String::from_utf8_lossy(body).into_owned(),
+    }
+}
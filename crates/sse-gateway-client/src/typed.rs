@@ -0,0 +1,127 @@
+//! Typed subscriptions
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use sse_gateway::{IntoSseEvent, SseEvent};
+
+use crate::client::{ClientError, EventStream};
+
+type Decoder<T> = Box<dyn Fn(&SseEvent) -> serde_json::Result<T> + Send + Sync>;
+
+/// An event whose data didn't match the type mapped to its event type
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid `{event_type}` event {id:?}: {message}")]
+pub struct DecodeError {
+    /// Event type of the event
+    pub event_type: String,
+    /// Event ID, if it had one
+    pub id: Option<String>,
+    /// Why deserialization failed
+    pub message: String,
+}
+
+/// Events of a channel, deserialized by event type
+///
+/// Each event type is mapped to a payload type and a constructor for `T`,
+/// usually an enum variant:
+///
+/// ```rust,ignore
+/// enum OrderEvent {
+///     Created(OrderCreated),
+///     Shipped(OrderShipped),
+/// }
+///
+/// let mut events = SseClient::new("http://localhost:8080", "orders")
+///     .connect()
+///     .typed()
+///     .on_event(OrderEvent::Created)          // OrderCreated::EVENT_TYPE
+///     .on("order_shipped", OrderEvent::Shipped);
+///
+/// while let Some(event) = events.next().await {
+///     match event {
+///         Ok(OrderEvent::Created(order)) => { /* ... */ }
+///         Ok(OrderEvent::Shipped(shipment)) => { /* ... */ }
+///         Err(e) => tracing::warn!(%e, "Skipping malformed event"),
+///     }
+/// }
+/// ```
+///
+/// Events of unmapped types are skipped. An event whose data doesn't
+/// deserialize is yielded as a [`DecodeError`] so the stream can go on.
+pub struct TypedStream<T> {
+    events: EventStream,
+    decoders: HashMap<String, Decoder<T>>,
+}
+
+impl<T> TypedStream<T> {
+    pub(crate) fn new(events: EventStream) -> Self {
+        Self {
+            events,
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Deserialize `event_type` events' data as `D` and wrap it with `map`
+    pub fn on<D, F>(mut self, event_type: impl Into<String>, map: F) -> Self
+    where
+        D: DeserializeOwned,
+        F: Fn(D) -> T + Send + Sync + 'static,
+    {
+        let decoder = move |event: &SseEvent| event.data_as::<D>().map(&map);
+        self.decoders.insert(event_type.into(), Box::new(decoder));
+        self
+    }
+
+    /// Like [`on`](Self::on), for the event type `D` is published as
+    pub fn on_event<D, F>(self, map: F) -> Self
+    where
+        D: IntoSseEvent + DeserializeOwned,
+        F: Fn(D) -> T + Send + Sync + 'static,
+    {
+        self.on(D::EVENT_TYPE, map)
+    }
+
+    /// The underlying stream, for its cursor and connection state
+    pub fn events(&self) -> &EventStream {
+        &self.events
+    }
+
+    /// Why the stream ended, if it did
+    pub fn error(&self) -> Option<ClientError> {
+        self.events.error()
+    }
+
+    fn decode(&self, event: SseEvent) -> Option<Result<T, DecodeError>> {
+        let Some(decoder) = self.decoders.get(&event.event_type) else {
+            tracing::debug!(event_type = %event.event_type, "Skipping unmapped event type");
+            return None;
+        };
+        Some(decoder(&event).map_err(|e| DecodeError {
+            message: e.to_string(),
+            event_type: event.event_type,
+            id: event.id,
+        }))
+    }
+}
+
+impl<T> Stream for TypedStream<T> {
+    type Item = Result<T, DecodeError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.events).poll_next(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let Some(item) = self.decode(event) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
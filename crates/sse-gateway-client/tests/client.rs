@@ -0,0 +1,192 @@
+//! Tests for sse-gateway-client
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use futures::StreamExt;
+use sse_gateway_client::{ClientError, Frame, IntoSseEvent, SseClient, SseParser};
+
+#[test]
+fn test_parser_handles_split_chunks() {
+    let mut parser = SseParser::new();
+    let mut frames = Vec::new();
+    let input = ": keep-alive\r\nretry: 500\r\n\r\nid: 7\r\nevent: update\r\ndata: héllo\r\ndata:world\r\n\r\ndata: {}\n\n";
+    // Byte-sized chunks split lines and multi-byte characters
+    for byte in input.as_bytes() {
+        frames.extend(parser.feed(std::slice::from_ref(byte)));
+    }
+
+    assert_eq!(
+        frames,
+        vec![
+            Frame {
+                event: Some("update".to_string()),
+                data: "héllo\nworld".to_string(),
+                id: Some("7".to_string()),
+            },
+            Frame {
+                event: None,
+                data: "{}".to_string(),
+                id: None,
+            },
+        ]
+    );
+    assert_eq!(parser.retry(), Some(500));
+    assert_eq!(parser.last_event_id(), Some("7"));
+}
+
+/// Scripted responses, and the headers each request arrived with
+#[derive(Clone, Default)]
+struct Script {
+    requests: Arc<Mutex<Vec<HeaderMap>>>,
+}
+
+async fn scripted(State(script): State<Script>, headers: HeaderMap) -> axum::response::Response {
+    let attempt = {
+        let mut requests = script.requests.lock().unwrap();
+        requests.push(headers);
+        requests.len()
+    };
+    let body = match attempt {
+        1 => concat!(
+            "retry: 20\n\n",
+            "event: connected\ndata: {\"connection_id\":\"conn-1\"}\n\n",
+            "id: 1\nevent: update\ndata: one\n\n",
+            "event: heartbeat\ndata: {\"ts\":1}\n\n",
+            "id: 2\ndata: two\ndata: lines\n\n",
+        ),
+        2 => "id: 3\ndata: three\n\nevent: shutdown\ndata: {}\n\n",
+        _ => return StatusCode::FORBIDDEN.into_response(),
+    };
+    ([(header::CONTENT_TYPE, "text/event-stream")], body).into_response()
+}
+
+#[tokio::test]
+async fn test_client_reconnects_with_last_event_id() {
+    let script = Script::default();
+    let app = axum::Router::new()
+        .route("/sse/connect", axum::routing::get(scripted))
+        .with_state(script.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let mut events = SseClient::new(format!("http://{addr}"), "room")
+        .bearer_token("secret")
+        .connect();
+    let mut received = Vec::new();
+    while let Ok(Some(event)) = tokio::time::timeout(Duration::from_secs(5), events.next()).await {
+        received.push((event.event_type, event.data.to_string(), event.id));
+    }
+
+    // Control events are consumed, not yielded
+    assert_eq!(
+        received,
+        vec![
+            ("update".to_string(), "one".to_string(), Some("1".to_string())),
+            ("message".to_string(), "two\nlines".to_string(), Some("2".to_string())),
+            ("message".to_string(), "three".to_string(), Some("3".to_string())),
+        ]
+    );
+    assert_eq!(events.error(), Some(ClientError::Rejected(403)));
+    assert_eq!(events.connection_id().as_deref(), Some("conn-1"));
+    assert_eq!(events.last_event_id().as_deref(), Some("3"));
+
+    let requests = script.requests.lock().unwrap();
+    let cursors: Vec<_> = requests
+        .iter()
+        .map(|headers| headers.get("last-event-id").map(|v| v.to_str().unwrap().to_string()))
+        .collect();
+    assert_eq!(cursors, vec![None, Some("2".to_string()), Some("3".to_string())]);
+    assert!(requests.iter().all(|headers| headers["authorization"] == "Bearer secret"));
+}
+
+#[tokio::test]
+async fn test_client_receives_gateway_events() {
+    let (app, handle) = sse_gateway::Gateway::builder()
+        .source(sse_gateway::NoopSource)
+        .storage(sse_gateway::NoopStorage)
+        .build()
+        .unwrap()
+        .into_router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let mut events = SseClient::new(format!("http://{addr}"), "user123").connect();
+    let manager = handle.connection_manager();
+    let connected = async {
+        while manager.channel_connection_count("user123") == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), connected).await.unwrap();
+
+    manager.send_heartbeat();
+    manager
+        .send_to_channel("user123", sse_gateway::SseEvent::raw("notification", "hi").with_id("n-1"))
+        .await;
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.event_type, "notification");
+    assert_eq!(event.data.to_string(), "hi");
+    assert_eq!(event.id.as_deref(), Some("n-1"));
+    assert!(events.is_connected());
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct OrderCreated {
+    order_id: String,
+}
+
+impl IntoSseEvent for OrderCreated {
+    const EVENT_TYPE: &'static str = "order_created";
+}
+
+#[derive(Debug, PartialEq)]
+enum OrderEvent {
+    Created(OrderCreated),
+    Cancelled(serde_json::Value),
+}
+
+#[tokio::test]
+async fn test_typed_stream_maps_event_types() {
+    let body = concat!(
+        "event: order_created\ndata: {\"order_id\":\"o-1\"}\n\n",
+        "event: unrelated\ndata: {}\n\n",
+        "id: 5\nevent: order_created\ndata: {\"id\":1}\n\n",
+        "event: order_cancelled\ndata: {\"reason\":\"oos\"}\n\n",
+    );
+    let app = axum::Router::new().route(
+        "/sse/connect",
+        axum::routing::get(move || async move { ([(header::CONTENT_TYPE, "text/event-stream")], body) }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let mut events = SseClient::new(format!("http://{addr}"), "orders")
+        .connect()
+        .typed()
+        .on_event(OrderEvent::Created)
+        .on("order_cancelled", OrderEvent::Cancelled);
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let next = tokio::time::timeout(Duration::from_secs(5), events.next()).await;
+        received.push(next.unwrap().unwrap());
+    }
+
+    assert_eq!(
+        received[0],
+        Ok(OrderEvent::Created(OrderCreated { order_id: "o-1".to_string() }))
+    );
+    // Malformed data is reported without ending the stream; unmapped types are skipped
+    let error = received[1].as_ref().unwrap_err();
+    assert_eq!((error.event_type.as_str(), error.id.as_deref()), ("order_created", Some("5")));
+    assert_eq!(received[2], Ok(OrderEvent::Cancelled(serde_json::json!({"reason": "oos"}))));
+}
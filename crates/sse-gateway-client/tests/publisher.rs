@@ -0,0 +1,123 @@
+//! Tests for GatewayPublisher
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+use sse_gateway_client::{GatewayPublisher, Priority, PublishError, PushMessage};
+
+async fn serve(app: axum::Router) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+#[tokio::test]
+async fn test_publisher_pushes_to_gateway() {
+    let (app, _handle) = sse_gateway::Gateway::builder()
+        .source(sse_gateway::NoopSource)
+        .storage(sse_gateway::MemoryStorage::default())
+        .enable_push_endpoint("/push")
+        .build()
+        .unwrap()
+        .into_router();
+    let addr = serve(app).await;
+    let publisher = GatewayPublisher::new(format!("http://{addr}/"));
+
+    let result = publisher
+        .push(
+            &PushMessage::new("user123", "notification", json!({"msg": "hi"}))
+                .with_priority(Priority::High),
+        )
+        .await
+        .unwrap();
+    assert!(result.success && result.stored && !result.online);
+    assert!(result.stream_id.is_some());
+
+    let messages = vec![
+        PushMessage::new("a", "update", json!(1)),
+        PushMessage::new("b", "update", json!(2)).with_ttl(Duration::from_secs(60)),
+    ];
+    let results = publisher.push_batch(&messages).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.success));
+
+    // Unknown routes are not retried
+    let err = publisher.channel_status("user123").await.unwrap_err();
+    assert!(matches!(err, PublishError::Rejected { status: 404, .. }));
+}
+
+#[tokio::test]
+async fn test_publisher_discovers_instances_and_retries() {
+    // An instance that fails its first push
+    let pushes = Arc::new(AtomicUsize::new(0));
+    let instance = axum::Router::new()
+        .route(
+            "/push",
+            axum::routing::post(|State(pushes): State<Arc<AtomicUsize>>| async move {
+                if pushes.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return StatusCode::SERVICE_UNAVAILABLE.into_response();
+                }
+                Json(json!({"success": true, "online": true, "stream_id": "1-0"})).into_response()
+            }),
+        )
+        .route(
+            "/channel/{id}",
+            axum::routing::get(|Path(id): Path<String>| async move {
+                Json(json!({"channel_id": id, "online": true, "instance_id": "gw-1", "instance_ids": ["gw-1"]}))
+            }),
+        )
+        .with_state(pushes.clone());
+    let instance_addr = serve(instance).await;
+
+    let registry = axum::Router::new().route(
+        "/instances",
+        axum::routing::get(move || async move {
+            Json(json!({
+                "instances": [
+                    {"id": "gw-1", "address": instance_addr.to_string(), "last_seen": 0},
+                    {"id": "gw-2", "address": null, "last_seen": 0},
+                ],
+                "count": 2
+            }))
+        }),
+    );
+    let registry_addr = serve(registry).await;
+
+    // The seed address is down; discovery finds the live instance
+    let publisher = GatewayPublisher::new("http://127.0.0.1:1")
+        .discover(format!("http://{registry_addr}"))
+        .retry_delay(Duration::from_millis(10));
+    assert_eq!(publisher.refresh_instances().await.unwrap(), 1);
+    assert_eq!(
+        publisher.current_endpoints(),
+        vec![format!("http://{instance_addr}")]
+    );
+
+    let result = publisher
+        .push(&PushMessage::new("room 1", "update", json!({})))
+        .await
+        .unwrap();
+    assert!(result.success && result.online);
+    assert_eq!(result.stream_id.as_deref(), Some("1-0"));
+    assert_eq!(pushes.load(Ordering::SeqCst), 2);
+
+    let status = publisher.channel_status("room 1").await.unwrap();
+    assert_eq!(status.channel_id, "room 1");
+    assert_eq!(status.instance_ids, vec!["gw-1".to_string()]);
+
+    let down = GatewayPublisher::new("127.0.0.1:1")
+        .max_retries(1)
+        .retry_delay(Duration::from_millis(1));
+    let err = down
+        .push(&PushMessage::broadcast("update", json!({})))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PublishError::Unavailable(2, _)));
+}
@@ -16,9 +16,9 @@
 
 use async_trait::async_trait;
 use google_cloud_pubsub::client::{Client, ClientConfig};
-use sse_gateway::{ConnectionManager, IncomingMessage, MessageHandler, MessageSource};
+use sse_gateway::{ConnectionManager, DecodeError, DispatchError, IncomingMessage, MessageHandler, MessageSource};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Google Cloud Pub/Sub message source
 ///
@@ -28,6 +28,10 @@ use tracing::{error, info};
 /// - `channel_id`: Target channel (optional, omit for broadcast)
 /// - `event_type`: Event type (defaults to "message")
 /// - `id`: Business message ID (optional)
+/// - `priority`: `high`, `normal` or `low` (optional)
+///
+/// CloudEvents are also accepted, in binary mode (`ce-*` attributes) or
+/// structured mode (`content-type: application/cloudevents+json`).
 pub struct GcpPubSubSource {
     project_id: String,
     subscription_id: String,
@@ -63,32 +67,45 @@ impl MessageSource for GcpPubSubSource {
 
         info!("Connected to GCP Pub/Sub");
 
+        let source = self.name();
         subscription
             .receive(
                 move |message, _cancel| {
                     let handler = handler.clone();
                     async move {
-                        let msg = &message.message;
-
-                        let channel_id = msg.attributes.get("channel_id").map(|s| s.to_string());
-                        let event_type = msg
-                            .attributes
-                            .get("event_type")
-                            .map(|s| s.as_str())
-                            .unwrap_or("message")
-                            .to_string();
-                        let id = msg.attributes.get("id").map(|s| s.to_string());
-                        let data = String::from_utf8_lossy(&msg.data).to_string();
+                        let incoming = match IncomingMessage::from_attributes(&message.message.attributes, &message.message.data) {
+                            Ok(incoming) => incoming,
+                            Err(e) => {
+                                // Poison messages won't decode on redelivery either
+                                let mut poison = DecodeError::new(source, message.message.data.clone(), e);
+                                if let Some(channel_id) = message.message.attributes.get("channel_id") {
+                                    poison = poison.with_channel(channel_id);
+                                }
+                                handler.decode_failed(poison);
+                                if let Err(e) = message.ack().await {
+                                    error!(error = %e, "Failed to ack message");
+                                }
+                                return;
+                            }
+                        };
 
-                        handler(IncomingMessage {
-                            channel_id,
-                            event_type,
-                            data,
-                            id,
-                        });
+                        let result = handler.dispatch(incoming).await;
 
-                        if let Err(e) = message.ack().await {
-                            error!(error = %e, "Failed to ack message");
+                        // Ack only once the event has been fanned out and stored;
+                        // NACK so Pub/Sub redelivers it otherwise
+                        match result {
+                            // Redelivering an invalid or oversized payload won't fix it
+                            Ok(_) | Err(DispatchError::Invalid(_) | DispatchError::TooLarge { .. }) => {
+                                if let Err(e) = message.ack().await {
+                                    error!(error = %e, "Failed to ack message");
+                                }
+                            }
+                            Err(e) => {
+                                warn!(error = %e, "Dispatch failed, nacking message");
+                                if let Err(e) = message.nack().await {
+                                    error!(error = %e, "Failed to nack message");
+                                }
+                            }
                         }
                     }
                 },
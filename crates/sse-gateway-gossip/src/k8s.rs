@@ -0,0 +1,320 @@
+//! Kubernetes EndpointSlice discovery
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use kube::api::{Api, ListParams};
+use sse_gateway::{ChannelPause, ClusterCoordinator, ClusterMessage, ConnectionManager, InstanceInfo};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::GossipCluster;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// [`ClusterCoordinator`] for gateways running as pods behind a headless
+/// Kubernetes service
+///
+/// Instances are discovered from the ready endpoints in the service's
+/// EndpointSlices, so no seed list or Redis registry is needed; state is
+/// then exchanged with [`GossipCluster`] on `gossip_port` of each pod.
+/// Only pods that are currently ready endpoints count as cluster members, so
+/// a pod leaves the cluster as soon as Kubernetes takes it out of the
+/// service.
+///
+/// The pod's service account needs `list` on `endpointslices` in the
+/// `discovery.k8s.io` API group.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sse_gateway_gossip::K8sCoordinator;
+///
+/// // POD_IP set from `status.podIP` through the downward API
+/// let cluster = K8sCoordinator::bind("sse-gateway-headless", 7946)?
+///     .address(format!("http://{}:8080", std::env::var("POD_IP")?));
+///
+/// Gateway::builder()
+///     .cluster(cluster)
+/// ```
+#[derive(Clone)]
+pub struct K8sCoordinator {
+    gossip: GossipCluster,
+    service: String,
+    namespace: Option<String>,
+    gossip_port: u16,
+    refresh_interval: Duration,
+}
+
+impl K8sCoordinator {
+    /// Discover instances through `service`, gossiping on `gossip_port`
+    /// (bound on all interfaces)
+    pub fn bind(service: impl Into<String>, gossip_port: u16) -> std::io::Result<Self> {
+        let gossip = GossipCluster::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), gossip_port))?;
+        let coordinator = Self {
+            gossip,
+            service: service.into(),
+            namespace: None,
+            gossip_port,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        };
+        Ok(match std::env::var("POD_IP").ok().and_then(|ip| ip.parse().ok()) {
+            Some(ip) => coordinator.pod_ip(ip),
+            None => coordinator,
+        })
+    }
+
+    /// Namespace of the service (default: the client's namespace, i.e. the pod's)
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// This pod's IP (default: the `POD_IP` environment variable)
+    ///
+    /// Announced to peers as this instance's gossip address.
+    pub fn pod_ip(mut self, ip: IpAddr) -> Self {
+        self.gossip = self.gossip.advertise(SocketAddr::new(ip, self.gossip_port));
+        self
+    }
+
+    /// Address clients or peers reach this instance's gateway at
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.gossip = self.gossip.address(address);
+        self
+    }
+
+    /// How often the EndpointSlices are listed (default: 5s)
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// How often state is gossiped (default: 1s)
+    pub fn gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip = self.gossip.gossip_interval(interval);
+        self
+    }
+
+    /// How long a silent instance stays a member (default: 10s)
+    pub fn dead_after(mut self, dead_after: Duration) -> Self {
+        self.gossip = self.gossip.dead_after(dead_after);
+        self
+    }
+
+    /// Keep the gossip peers in line with the service's ready endpoints
+    async fn discover(&self, api: Api<EndpointSlice>, pod_ip: IpAddr, cancel: CancellationToken) {
+        let params = ListParams::default().labels(&format!("kubernetes.io/service-name={}", self.service));
+        let mut interval = tokio::time::interval(self.refresh_interval);
+        let mut known = BTreeSet::new();
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            let slices = match api.list(&params).await {
+                Ok(slices) => slices,
+                Err(e) => {
+                    warn!(error = %e, service = %self.service, "Failed to list EndpointSlices");
+                    continue;
+                }
+            };
+            let ready = ready_endpoints(&slices.items);
+            if ready != known {
+                info!(service = %self.service, endpoints = ready.len(), "Service endpoints changed");
+                known = ready;
+            } else {
+                debug!(service = %self.service, endpoints = known.len(), "Service endpoints unchanged");
+            }
+            self.gossip.set_discovered(peers(&known, pod_ip, self.gossip_port));
+        }
+    }
+}
+
+/// Ready endpoint addresses across all of the service's slices
+fn ready_endpoints(slices: &[EndpointSlice]) -> BTreeSet<IpAddr> {
+    slices.iter().flat_map(ready_ips).collect()
+}
+
+/// Gossip addresses of the ready endpoints other than this pod
+fn peers(ready: &BTreeSet<IpAddr>, pod_ip: IpAddr, gossip_port: u16) -> Vec<SocketAddr> {
+    ready
+        .iter()
+        .filter(|ip| **ip != pod_ip)
+        .map(|ip| SocketAddr::new(*ip, gossip_port))
+        .collect()
+}
+
+/// Addresses of the slice's endpoints that aren't known to be unready
+fn ready_ips(slice: &EndpointSlice) -> impl Iterator<Item = IpAddr> + '_ {
+    slice
+        .endpoints
+        .iter()
+        .filter(|endpoint| {
+            endpoint
+                .conditions
+                .as_ref()
+                .and_then(|conditions| conditions.ready)
+                .unwrap_or(true)
+        })
+        .flat_map(|endpoint| endpoint.addresses.iter())
+        .filter_map(|address| address.parse().ok())
+}
+
+#[async_trait]
+impl ClusterCoordinator for K8sCoordinator {
+    async fn start(
+        &self,
+        connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let pod_ip = self.gossip.advertise.ip();
+        if pod_ip.is_unspecified() {
+            anyhow::bail!("Pod IP unknown; set POD_IP or call K8sCoordinator::pod_ip");
+        }
+        let client = kube::Client::try_default().await?;
+        let api: Api<EndpointSlice> = match &self.namespace {
+            Some(namespace) => Api::namespaced(client, namespace),
+            None => Api::default_namespaced(client),
+        };
+
+        // Admit only this pod until the first listing
+        self.gossip.set_discovered(Vec::new());
+        info!(service = %self.service, pod_ip = %pod_ip, "Discovering instances from Kubernetes");
+
+        // Discovery stops with gossip, even if gossip fails to start
+        let stop_discovery = cancel.child_token();
+        let discovery = self.discover(api, pod_ip, stop_discovery.clone());
+        let gossip = async {
+            let result = self.gossip.start(connection_manager, cancel).await;
+            stop_discovery.cancel();
+            result
+        };
+        tokio::join!(discovery, gossip).1
+    }
+
+    async fn claim_channel(&self, instance_id: &str, channel_id: &str) {
+        self.gossip.claim_channel(instance_id, channel_id).await
+    }
+
+    async fn release_channel(&self, instance_id: &str, channel_id: &str) {
+        self.gossip.release_channel(instance_id, channel_id).await
+    }
+
+    async fn channel_owners(&self, channel_id: &str) -> anyhow::Result<Vec<String>> {
+        self.gossip.channel_owners(channel_id).await
+    }
+
+    async fn instances(&self) -> anyhow::Result<Vec<InstanceInfo>> {
+        self.gossip.instances().await
+    }
+
+    async fn channels(&self) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        self.gossip.channels().await
+    }
+
+    async fn evict(&self, instance: &InstanceInfo) -> anyhow::Result<Option<Vec<String>>> {
+        self.gossip.evict(instance).await
+    }
+
+    async fn set_channel_pause(&self, channel_id: &str, pause: Option<&ChannelPause>) -> anyhow::Result<()> {
+        self.gossip.set_channel_pause(channel_id, pause).await
+    }
+
+    async fn channel_pauses(&self) -> anyhow::Result<HashMap<String, ChannelPause>> {
+        self.gossip.channel_pauses().await
+    }
+
+    async fn forward(&self, instance_id: &str, message: &ClusterMessage) -> anyhow::Result<()> {
+        self.gossip.forward(instance_id, message).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Kubernetes"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// An EndpointSlice of `endpoints`, each `(address, ready)`; `None`
+    /// leaves the readiness condition out
+    fn slice(name: &str, endpoints: &[(&str, Option<bool>)]) -> EndpointSlice {
+        let endpoints: Vec<_> = endpoints
+            .iter()
+            .map(|(address, ready)| match ready {
+                Some(ready) => json!({"addresses": [address], "conditions": {"ready": ready}}),
+                None => json!({"addresses": [address]}),
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "apiVersion": "discovery.k8s.io/v1",
+            "kind": "EndpointSlice",
+            "metadata": {"name": name, "labels": {"kubernetes.io/service-name": "sse-gateway"}},
+            "addressType": "IPv4",
+            "endpoints": endpoints,
+        }))
+        .unwrap()
+    }
+
+    fn ips(addresses: &[&str]) -> BTreeSet<IpAddr> {
+        addresses.iter().map(|address| address.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn ready_endpoints_across_slices() {
+        let slices = [
+            slice("a", &[("10.0.0.1", Some(true)), ("10.0.0.2", Some(false)), ("10.0.0.3", None)]),
+            slice("b", &[("10.0.0.4", Some(true)), ("10.0.0.1", Some(true)), ("not-an-ip", Some(true))]),
+        ];
+        assert_eq!(ready_endpoints(&slices), ips(&["10.0.0.1", "10.0.0.3", "10.0.0.4"]));
+        assert!(ready_endpoints(&[]).is_empty());
+    }
+
+    #[test]
+    fn peers_leave_out_this_pod() {
+        let ready = ips(&["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+        let peers = peers(&ready, "10.0.0.2".parse().unwrap(), 7946);
+        let expected: Vec<SocketAddr> = vec!["10.0.0.1:7946".parse().unwrap(), "10.0.0.3:7946".parse().unwrap()];
+        assert_eq!(peers, expected);
+    }
+
+    #[test]
+    fn endpoint_changes_are_detected_regardless_of_slice_order() {
+        let before = ready_endpoints(&[
+            slice("a", &[("10.0.0.1", Some(true))]),
+            slice("b", &[("10.0.0.2", Some(true))]),
+        ]);
+        let reordered = ready_endpoints(&[
+            slice("b", &[("10.0.0.2", Some(true))]),
+            slice("a", &[("10.0.0.1", Some(true))]),
+        ]);
+        assert_eq!(before, reordered);
+
+        // A pod going unready drops out of the peers; a new one joins them
+        let after = ready_endpoints(&[
+            slice("a", &[("10.0.0.1", Some(false)), ("10.0.0.5", Some(true))]),
+            slice("b", &[("10.0.0.2", Some(true))]),
+        ]);
+        assert_ne!(before, after);
+        let pod_ip = "10.0.0.2".parse().unwrap();
+        assert_eq!(peers(&after, pod_ip, 7946), ["10.0.0.5:7946".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn discovered_peers_are_the_only_admitted_members() {
+        let gossip = GossipCluster::bind("127.0.0.1:0".parse().unwrap())
+            .unwrap()
+            .advertise("10.0.0.2:7946".parse().unwrap());
+        let ready = ready_endpoints(&[slice("a", &[("10.0.0.1", Some(true)), ("10.0.0.2", Some(true))])]);
+        gossip.set_discovered(peers(&ready, gossip.advertise.ip(), 7946));
+
+        assert_eq!(*gossip.seeds.lock().unwrap(), ["10.0.0.1:7946".parse::<SocketAddr>().unwrap()]);
+        assert_eq!(gossip.lock().admitted, Some(ips(&["10.0.0.1", "10.0.0.2"]).into_iter().collect()));
+    }
+}
@@ -0,0 +1,616 @@
+//! Gossip-based cluster coordinator for SSE Gateway
+//!
+//! [`GossipCluster`] implements [`ClusterCoordinator`] without Redis or any
+//! other shared service: instances exchange their state over UDP, in the
+//! style of Scuttlebutt/SWIM heartbeat gossip.
+//!
+//! - **Membership**: every instance periodically bumps a heartbeat counter in
+//!   its own state and sends the states it knows to a few peers, which merge
+//!   the newer ones. New instances join through one or more seed addresses.
+//! - **Failure detection**: an instance whose heartbeat hasn't advanced for
+//!   `dead_after` is considered dead; a stopping instance announces that it
+//!   left so peers drop it at once.
+//! - **Channel ownership**: each instance's state lists the channels it holds
+//!   subscribers on, so ownership is announced with the next gossip round.
+//! - **Channel pauses**: every instance gossips its view of the paused
+//!   channels; views merge per channel, the latest change winning.
+//!
+//! Forwarded messages are sent straight to the target instance's gossip
+//! address. Both use single UDP datagrams, so an instance's state (mostly its
+//! channel list) and a forwarded event must each fit in about 60 KB; pair
+//! with [`PayloadLimit`](sse_gateway::payload::PayloadLimit) if events can be
+//! larger. Ownership spreads within a few gossip intervals rather than
+//! immediately, so messages published right after a subscriber connects to
+//! another instance may miss it.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use sse_gateway_gossip::GossipCluster;
+//!
+//! let cluster = GossipCluster::bind("0.0.0.0:7946".parse()?)?
+//!     .advertise("10.0.0.5:7946".parse()?)
+//!     .seeds(["10.0.0.6:7946".parse()?, "10.0.0.7:7946".parse()?])
+//!     .address("http://10.0.0.5:8080");
+//!
+//! Gateway::builder()
+//!     .cluster(cluster)
+//!     .source(source)
+//!     .storage(storage)
+//!     .build()?
+//!     .run()
+//!     .await
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sse_gateway::{ChannelPause, ClusterCoordinator, ClusterMessage, ConnectionManager, InstanceInfo};
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "kube")]
+mod k8s;
+#[cfg(feature = "kube")]
+pub use k8s::K8sCoordinator;
+
+const DEFAULT_GOSSIP_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_DEAD_AFTER: Duration = Duration::from_secs(10);
+const DEFAULT_FANOUT: usize = 3;
+
+/// Largest datagram sent; leaves room under the 65507-byte UDP limit
+const MAX_DATAGRAM: usize = 60_000;
+
+/// What an instance announces about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MemberState {
+    id: String,
+    /// Where the instance receives gossip and forwarded messages
+    gossip_addr: SocketAddr,
+    /// Address clients or peers reach the gateway at
+    address: Option<String>,
+    /// Start time of the instance (unix millis), so a restart with the same
+    /// ID supersedes the old state
+    generation: i64,
+    heartbeat: u64,
+    /// Set in the last state of an instance that stopped
+    #[serde(default)]
+    left: bool,
+    connections: usize,
+    capacity: Option<usize>,
+    cpu: Option<u8>,
+    channels: BTreeSet<String>,
+    /// The instance's view of the cluster's channel pauses
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pauses: BTreeMap<String, PauseRecord>,
+}
+
+/// The latest pause or resume of a channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PauseRecord {
+    /// `None` once resumed; kept for a while so the resume spreads
+    pause: Option<ChannelPause>,
+    /// Unix millis of the change
+    changed_at: i64,
+}
+
+impl MemberState {
+    fn version(&self) -> (i64, u64) {
+        (self.generation, self.heartbeat)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Datagram {
+    States { states: Vec<MemberState> },
+    Forward { to: String, message: ClusterMessage },
+}
+
+/// A member as known locally
+struct Member {
+    state: MemberState,
+    /// When its heartbeat last advanced
+    updated: Instant,
+    /// Unix seconds of `updated`
+    last_seen: i64,
+    /// Evicted by failover; cleared when its heartbeat advances
+    evicted: bool,
+}
+
+/// The local view of the cluster
+struct Members {
+    members: HashMap<String, Member>,
+    /// Round-robin position for picking gossip targets
+    next_target: usize,
+    /// If discovered externally, the only gossip IPs counted as live
+    admitted: Option<HashSet<IpAddr>>,
+    /// Channel pauses, merged from every state received
+    pauses: BTreeMap<String, PauseRecord>,
+}
+
+impl Members {
+    fn live(&self, dead_after: Duration) -> impl Iterator<Item = &Member> {
+        self.members.values().filter(move |m| {
+            !m.state.left
+                && !m.evicted
+                && m.updated.elapsed() < dead_after
+                && self
+                    .admitted
+                    .as_ref()
+                    .is_none_or(|ips| ips.contains(&m.state.gossip_addr.ip()))
+        })
+    }
+
+    /// Merge a received state, returning whether it was newer
+    fn merge(&mut self, state: MemberState, local_id: &str) -> bool {
+        if state.id == local_id {
+            return false;
+        }
+        // Older states may still carry news about other channels
+        for (channel_id, record) in &state.pauses {
+            if self
+                .pauses
+                .get(channel_id)
+                .is_none_or(|known| known.changed_at < record.changed_at)
+            {
+                self.pauses.insert(channel_id.clone(), record.clone());
+            }
+        }
+        if let Some(known) = self.members.get(&state.id) {
+            if known.state.version() >= state.version() {
+                return false;
+            }
+        }
+        if state.left {
+            info!(instance_id = %state.id, "Instance left the cluster");
+        } else if !self.members.contains_key(&state.id) {
+            info!(instance_id = %state.id, gossip_addr = %state.gossip_addr, "Instance joined the cluster");
+        }
+        self.members.insert(
+            state.id.clone(),
+            Member {
+                state,
+                updated: Instant::now(),
+                last_seen: chrono::Utc::now().timestamp(),
+                evicted: false,
+            },
+        );
+        true
+    }
+}
+
+/// Gossip-based [`ClusterCoordinator`]
+///
+/// See the [crate docs](crate) for how it works.
+#[derive(Clone)]
+pub struct GossipCluster {
+    socket: Arc<std::net::UdpSocket>,
+    /// The socket once the coordinator has started
+    started: Arc<OnceLock<(String, Arc<UdpSocket>)>>,
+    members: Arc<Mutex<Members>>,
+    advertise: SocketAddr,
+    seeds: Arc<Mutex<Vec<SocketAddr>>>,
+    address: Option<String>,
+    gossip_interval: Duration,
+    dead_after: Duration,
+    fanout: usize,
+}
+
+impl GossipCluster {
+    /// Bind the gossip socket to `addr`
+    ///
+    /// Port `0` picks a free port; see [`local_addr`](Self::local_addr).
+    pub fn bind(addr: SocketAddr) -> std::io::Result<Self> {
+        let socket = std::net::UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        let advertise = socket.local_addr()?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            started: Arc::new(OnceLock::new()),
+            members: Arc::new(Mutex::new(Members {
+                members: HashMap::new(),
+                next_target: 0,
+                admitted: None,
+                pauses: BTreeMap::new(),
+            })),
+            advertise,
+            seeds: Arc::new(Mutex::new(Vec::new())),
+            address: None,
+            gossip_interval: DEFAULT_GOSSIP_INTERVAL,
+            dead_after: DEFAULT_DEAD_AFTER,
+            fanout: DEFAULT_FANOUT,
+        })
+    }
+
+    /// Address the gossip socket is bound to
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Gossip address announced to peers (default: the bound address)
+    ///
+    /// Needed when binding to `0.0.0.0` or behind NAT.
+    pub fn advertise(mut self, addr: SocketAddr) -> Self {
+        self.advertise = addr;
+        self
+    }
+
+    /// Gossip addresses of instances to join through
+    ///
+    /// Any running instance will do; listing several keeps joining working
+    /// while one is down. Seeds are contacted whenever no peer is known.
+    pub fn seeds(self, seeds: impl IntoIterator<Item = SocketAddr>) -> Self {
+        *self.seeds.lock().unwrap_or_else(|e| e.into_inner()) = seeds.into_iter().collect();
+        self
+    }
+
+    /// Address clients or peers can reach this gateway at
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Time between gossip rounds (default: 1s)
+    pub fn gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip_interval = interval;
+        self
+    }
+
+    /// How long without a heartbeat before an instance is considered dead (default: 10s)
+    ///
+    /// Should span several gossip intervals.
+    pub fn dead_after(mut self, dead_after: Duration) -> Self {
+        self.dead_after = dead_after;
+        self
+    }
+
+    /// Peers gossiped to per round (default: 3)
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout.max(1);
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Members> {
+        self.members.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Use externally discovered instances: gossip with `peers`, and count
+    /// only members at those IPs (or this instance's) as live
+    #[cfg_attr(not(feature = "kube"), allow(dead_code))]
+    fn set_discovered(&self, peers: Vec<SocketAddr>) {
+        let mut admitted: HashSet<IpAddr> = peers.iter().map(SocketAddr::ip).collect();
+        admitted.insert(self.advertise.ip());
+        self.lock().admitted = Some(admitted);
+        *self.seeds.lock().unwrap_or_else(|e| e.into_inner()) = peers;
+    }
+
+    /// Refresh this instance's state from `connection_manager` and bump its heartbeat
+    fn beat(&self, local_id: &str, connection_manager: &ConnectionManager) {
+        let mut members = self.lock();
+        let pauses = members.pauses.clone();
+        if let Some(local) = members.members.get_mut(local_id) {
+            local.state.heartbeat += 1;
+            local.state.pauses = pauses;
+            local.state.connections = connection_manager.connection_count();
+            local.state.capacity = connection_manager.capacity();
+            local.state.cpu = connection_manager.cpu_hint();
+            local.state.channels = connection_manager.channel_ids().into_iter().collect();
+            local.updated = Instant::now();
+            local.last_seen = chrono::Utc::now().timestamp();
+        }
+    }
+
+    /// States worth spreading: live members, and the tombstones of those that left
+    fn gossip_states(&self) -> Vec<MemberState> {
+        let members = self.lock();
+        members
+            .members
+            .values()
+            .filter(|m| m.updated.elapsed() < self.dead_after && !m.evicted)
+            .map(|m| m.state.clone())
+            .collect()
+    }
+
+    /// The next `fanout` live peers round-robin, or the seeds while none is known
+    fn gossip_targets(&self, local_id: &str) -> Vec<SocketAddr> {
+        let mut members = self.lock();
+        let mut peers: Vec<SocketAddr> = members
+            .live(self.dead_after)
+            .filter(|m| m.state.id != local_id)
+            .map(|m| m.state.gossip_addr)
+            .collect();
+        if peers.is_empty() {
+            return self.seeds.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        }
+        peers.sort();
+        let start = members.next_target;
+        members.next_target = start.wrapping_add(self.fanout);
+        (0..self.fanout.min(peers.len()))
+            .map(|i| peers[(start + i) % peers.len()])
+            .collect()
+    }
+
+    /// Send `states` to `targets`, packed into as few datagrams as fit
+    async fn send_states(&self, socket: &UdpSocket, states: Vec<MemberState>, targets: &[SocketAddr]) {
+        if targets.is_empty() {
+            return;
+        }
+        let mut batches: Vec<Vec<u8>> = Vec::new();
+        let mut batch: Vec<MemberState> = Vec::new();
+        let mut size = 0;
+        for state in states {
+            let Ok(encoded) = serde_json::to_vec(&state) else { continue };
+            if encoded.len() + 64 > MAX_DATAGRAM {
+                warn!(instance_id = %state.id, size = encoded.len(), "Instance state too large to gossip");
+                continue;
+            }
+            if size + encoded.len() + 64 > MAX_DATAGRAM {
+                batches.extend(encode(std::mem::take(&mut batch)));
+                size = 0;
+            }
+            size += encoded.len() + 1;
+            batch.push(state);
+        }
+        batches.extend(encode(batch));
+        for target in targets {
+            for datagram in &batches {
+                if let Err(e) = socket.send_to(datagram, target).await {
+                    debug!(error = %e, target = %target, "Failed to send gossip");
+                }
+            }
+        }
+    }
+
+    async fn receive(&self, local_id: &str, datagram: &[u8], connection_manager: &ConnectionManager) {
+        match serde_json::from_slice::<Datagram>(datagram) {
+            Ok(Datagram::States { states }) => {
+                let mut members = self.lock();
+                for state in states {
+                    members.merge(state, local_id);
+                }
+            }
+            Ok(Datagram::Forward { to, message }) if to == local_id => {
+                let sent = message.deliver(connection_manager).await;
+                debug!(sent, "Delivered forwarded message");
+            }
+            Ok(Datagram::Forward { to, .. }) => {
+                debug!(to = %to, "Ignoring message forwarded to another instance");
+            }
+            Err(e) => warn!(error = %e, "Ignoring malformed gossip datagram"),
+        }
+    }
+
+    /// Drop members dead for long enough that no peer still gossips them,
+    /// and resumes as old
+    fn forget_dead(&self) {
+        let forget_after = self.dead_after * 3;
+        let resumed_before = chrono::Utc::now().timestamp_millis() - forget_after.as_millis() as i64;
+        let mut members = self.lock();
+        members.members.retain(|_, m| m.updated.elapsed() < forget_after);
+        members
+            .pauses
+            .retain(|_, record| record.pause.is_some() || record.changed_at > resumed_before);
+    }
+
+    fn to_info(member: &Member) -> InstanceInfo {
+        InstanceInfo {
+            id: member.state.id.clone(),
+            address: member.state.address.clone(),
+            last_seen: member.last_seen,
+            connections: Some(member.state.connections),
+            capacity: member.state.capacity,
+            cpu: member.state.cpu,
+        }
+    }
+}
+
+/// Encode a batch of states as one datagram, unless empty
+fn encode(states: Vec<MemberState>) -> Option<Vec<u8>> {
+    if states.is_empty() {
+        return None;
+    }
+    serde_json::to_vec(&Datagram::States { states }).ok()
+}
+
+#[async_trait]
+impl ClusterCoordinator for GossipCluster {
+    async fn start(
+        &self,
+        connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let local_id = connection_manager.instance_id().to_string();
+        let socket = Arc::new(UdpSocket::from_std(self.socket.try_clone()?)?);
+        if self.started.set((local_id.clone(), socket.clone())).is_err() {
+            anyhow::bail!("Gossip cluster already started");
+        }
+
+        self.lock().members.insert(
+            local_id.clone(),
+            Member {
+                state: MemberState {
+                    id: local_id.clone(),
+                    gossip_addr: self.advertise,
+                    address: self.address.clone(),
+                    generation: chrono::Utc::now().timestamp_millis(),
+                    heartbeat: 0,
+                    left: false,
+                    connections: 0,
+                    capacity: None,
+                    cpu: None,
+                    channels: BTreeSet::new(),
+                    pauses: BTreeMap::new(),
+                },
+                updated: Instant::now(),
+                last_seen: chrono::Utc::now().timestamp(),
+                evicted: false,
+            },
+        );
+        info!(instance_id = %local_id, gossip_addr = %self.advertise, "Joined gossip cluster");
+
+        let mut interval = tokio::time::interval(self.gossip_interval);
+        let mut buf = vec![0u8; 65_536];
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+
+                _ = interval.tick() => {
+                    self.beat(&local_id, &connection_manager);
+                    let targets = self.gossip_targets(&local_id);
+                    self.send_states(&socket, self.gossip_states(), &targets).await;
+                    self.forget_dead();
+                }
+
+                received = socket.recv_from(&mut buf) => {
+                    match received {
+                        Ok((len, _)) => self.receive(&local_id, &buf[..len], &connection_manager).await,
+                        Err(e) => debug!(error = %e, "Gossip receive failed"),
+                    }
+                }
+            }
+        }
+
+        // Announce the departure to every live peer
+        let (farewell, peers) = {
+            let mut members = self.lock();
+            let peers: Vec<SocketAddr> = members
+                .live(self.dead_after)
+                .filter(|m| m.state.id != local_id)
+                .map(|m| m.state.gossip_addr)
+                .collect();
+            let local = members.members.get_mut(&local_id).map(|local| {
+                local.state.heartbeat += 1;
+                local.state.left = true;
+                local.state.channels.clear();
+                local.state.clone()
+            });
+            (local, peers)
+        };
+        if let Some(farewell) = farewell {
+            self.send_states(&socket, vec![farewell], &peers).await;
+        }
+        info!(instance_id = %local_id, "Left gossip cluster");
+        Ok(())
+    }
+
+    /// Ownership of this instance's channels is announced with its next gossip round
+    async fn claim_channel(&self, instance_id: &str, channel_id: &str) {
+        if let Some(local) = self.lock().members.get_mut(instance_id) {
+            local.state.channels.insert(channel_id.to_string());
+        }
+    }
+
+    async fn release_channel(&self, instance_id: &str, channel_id: &str) {
+        if let Some(local) = self.lock().members.get_mut(instance_id) {
+            local.state.channels.remove(channel_id);
+        }
+    }
+
+    async fn channel_owners(&self, channel_id: &str) -> anyhow::Result<Vec<String>> {
+        let members = self.lock();
+        let mut owners: Vec<String> = members
+            .live(self.dead_after)
+            .filter(|m| m.state.channels.contains(channel_id))
+            .map(|m| m.state.id.clone())
+            .collect();
+        owners.sort();
+        Ok(owners)
+    }
+
+    async fn instances(&self) -> anyhow::Result<Vec<InstanceInfo>> {
+        let members = self.lock();
+        let mut instances: Vec<InstanceInfo> = members.live(self.dead_after).map(Self::to_info).collect();
+        instances.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(instances)
+    }
+
+    async fn channels(&self) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        let members = self.lock();
+        let mut channels: HashMap<String, Vec<String>> = HashMap::new();
+        for member in members.live(self.dead_after) {
+            for channel_id in &member.state.channels {
+                channels.entry(channel_id.clone()).or_default().push(member.state.id.clone());
+            }
+        }
+        for owners in channels.values_mut() {
+            owners.sort();
+        }
+        Ok(channels)
+    }
+
+    /// Local only: each instance detects failures itself, so eviction just
+    /// stops this instance from using the member until its heartbeat advances
+    async fn evict(&self, instance: &InstanceInfo) -> anyhow::Result<Option<Vec<String>>> {
+        let mut members = self.lock();
+        let Some(member) = members.members.get_mut(&instance.id) else {
+            return Ok(None);
+        };
+        if member.evicted || member.last_seen != instance.last_seen {
+            return Ok(None);
+        }
+        member.evicted = true;
+        Ok(Some(member.state.channels.iter().cloned().collect()))
+    }
+
+    /// Spreads with the next gossip rounds
+    async fn set_channel_pause(&self, channel_id: &str, pause: Option<&ChannelPause>) -> anyhow::Result<()> {
+        let mut members = self.lock();
+        // Strictly after any change already seen, even with clock skew
+        let changed_at = members
+            .pauses
+            .get(channel_id)
+            .map_or(0, |known| known.changed_at + 1)
+            .max(chrono::Utc::now().timestamp_millis());
+        members.pauses.insert(
+            channel_id.to_string(),
+            PauseRecord {
+                pause: pause.cloned(),
+                changed_at,
+            },
+        );
+        Ok(())
+    }
+
+    async fn channel_pauses(&self) -> anyhow::Result<HashMap<String, ChannelPause>> {
+        Ok(self
+            .lock()
+            .pauses
+            .iter()
+            .filter_map(|(channel_id, record)| Some((channel_id.clone(), record.pause.clone()?)))
+            .collect())
+    }
+
+    async fn forward(&self, instance_id: &str, message: &ClusterMessage) -> anyhow::Result<()> {
+        let (_, socket) = self
+            .started
+            .get()
+            .ok_or_else(|| anyhow::anyhow!("Gossip cluster not started"))?;
+        let target = self
+            .lock()
+            .live(self.dead_after)
+            .find(|m| m.state.id == instance_id)
+            .map(|m| m.state.gossip_addr)
+            .ok_or_else(|| anyhow::anyhow!("Unknown instance {}", instance_id))?;
+        let datagram = serde_json::to_vec(&Datagram::Forward {
+            to: instance_id.to_string(),
+            message: message.clone(),
+        })?;
+        if datagram.len() > MAX_DATAGRAM {
+            anyhow::bail!("Forwarded message of {} bytes exceeds the datagram limit", datagram.len());
+        }
+        socket.send_to(&datagram, target).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Gossip"
+    }
+}
@@ -0,0 +1,156 @@
+//! Gossip membership over loopback UDP
+
+use std::time::Duration;
+
+use sse_gateway::{
+    CancellationToken, ChannelPause, ClusterCoordinator, ClusterMessage, ConnectionManager, PausePolicy, SseEvent,
+};
+use sse_gateway_gossip::GossipCluster;
+
+struct Node {
+    cluster: GossipCluster,
+    manager: ConnectionManager,
+    cancel: CancellationToken,
+    task: tokio::task::JoinHandle<anyhow::Result<()>>,
+}
+
+fn start(id: &str, seeds: &[&Node]) -> Node {
+    let cluster = GossipCluster::bind("127.0.0.1:0".parse().unwrap())
+        .unwrap()
+        .seeds(seeds.iter().map(|seed| seed.cluster.local_addr().unwrap()))
+        .address(format!("http://{}.local:8080", id))
+        .gossip_interval(Duration::from_millis(50))
+        .dead_after(Duration::from_millis(500));
+    let manager = ConnectionManager::new(id);
+    let cancel = CancellationToken::new();
+    let task = tokio::spawn({
+        let (cluster, manager, cancel) = (cluster.clone(), manager.clone(), cancel.clone());
+        async move { cluster.start(manager, cancel).await }
+    });
+    Node {
+        cluster,
+        manager,
+        cancel,
+        task,
+    }
+}
+
+async fn eventually<F, Fut>(what: &str, mut check: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    for _ in 0..200 {
+        if check().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    panic!("timed out waiting for {}", what);
+}
+
+async fn ids(node: &Node) -> Vec<String> {
+    node.cluster.instances().await.unwrap().into_iter().map(|i| i.id).collect()
+}
+
+#[tokio::test]
+async fn members_join_own_channels_and_leave() {
+    let a = start("a", &[]);
+    let b = start("b", &[&a]);
+    let c = start("c", &[&a]);
+    for node in [&a, &b, &c] {
+        eventually("membership to converge", || async { ids(node).await == ["a", "b", "c"] }).await;
+    }
+    let info = a.cluster.instances().await.unwrap().into_iter().find(|i| i.id == "b").unwrap();
+    assert_eq!(info.address.as_deref(), Some("http://b.local:8080"));
+
+    // Ownership is announced with b's state
+    let (_connection, mut rx) = b.manager.register("room".to_string(), None, None);
+    eventually("ownership to spread", || async {
+        a.cluster.channel_owners("room").await.unwrap() == ["b"]
+    })
+    .await;
+    assert_eq!(a.cluster.channels().await.unwrap()["room"], ["b"]);
+
+    // Forwarded messages go straight to the owner
+    let message = ClusterMessage {
+        channel_id: Some("room".to_string()),
+        event: SseEvent::raw("message", "hello"),
+    };
+    a.cluster.forward("b", &message).await.unwrap();
+    let event = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event.data.to_string(), "hello");
+
+    // A stopping instance says goodbye; peers drop it without waiting for the timeout
+    c.cancel.cancel();
+    c.task.await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(ids(&a).await, ["a", "b"]);
+
+    // A crashed one is detected once its heartbeat stalls
+    b.task.abort();
+    eventually("failure detection", || async { ids(&a).await == ["a"] }).await;
+    assert!(a.cluster.channel_owners("room").await.unwrap().is_empty());
+    assert!(a.cluster.forward("b", &message).await.is_err());
+
+    a.cancel.cancel();
+}
+
+#[tokio::test]
+async fn evicted_member_returns_when_its_heartbeat_advances() {
+    let a = start("a", &[]);
+    let b = start("b", &[&a]);
+    eventually("membership to converge", || async { ids(&a).await == ["a", "b"] }).await;
+    let _connection = b.manager.register("room".to_string(), None, None);
+    eventually("ownership to spread", || async {
+        a.cluster.channel_owners("room").await.unwrap() == ["b"]
+    })
+    .await;
+
+    // A stale view doesn't evict
+    let mut seen = a.cluster.instances().await.unwrap().into_iter().find(|i| i.id == "b").unwrap();
+    seen.last_seen -= 1;
+    assert_eq!(a.cluster.evict(&seen).await.unwrap(), None);
+
+    seen.last_seen += 1;
+    // The heartbeat may have advanced meanwhile; then the eviction is refused
+    if let Some(channels) = a.cluster.evict(&seen).await.unwrap() {
+        assert_eq!(channels, ["room"]);
+    }
+    eventually("b to be seen again", || async { ids(&a).await == ["a", "b"] }).await;
+
+    a.cancel.cancel();
+    b.cancel.cancel();
+}
+
+#[tokio::test]
+async fn channel_pauses_spread_and_latest_change_wins() {
+    let a = start("a", &[]);
+    let b = start("b", &[&a]);
+    let c = start("c", &[&a]);
+    for node in [&a, &b, &c] {
+        eventually("membership to converge", || async { ids(node).await == ["a", "b", "c"] }).await;
+    }
+
+    let pause = ChannelPause::new(PausePolicy::Drop).with_reason("bad data");
+    b.cluster.set_channel_pause("orders", Some(&pause)).await.unwrap();
+    for node in [&a, &c] {
+        eventually("the pause to spread", || async {
+            node.cluster.channel_pauses().await.unwrap().get("orders") == Some(&pause)
+        })
+        .await;
+    }
+
+    // Resumed elsewhere; the resume supersedes the pause everywhere
+    c.cluster.set_channel_pause("orders", None).await.unwrap();
+    for node in [&a, &b] {
+        eventually("the resume to spread", || async {
+            node.cluster.channel_pauses().await.unwrap().is_empty()
+        })
+        .await;
+    }
+
+    for node in [a, b, c] {
+        node.cancel.cancel();
+    }
+}
@@ -0,0 +1,363 @@
+//! Postgres outbox source for SSE Gateway
+//!
+//! Producers insert a row into an outbox table in the same transaction as
+//! their business change; the [`OutboxSource`] publishes committed rows and
+//! marks them processed. Clients are notified exactly when the change
+//! commits, without writing to a second system.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use sse_gateway::Gateway;
+//! use sse_gateway_postgres::OutboxSource;
+//!
+//! Gateway::builder()
+//!     .source(OutboxSource::new("postgres://app@localhost/app").notify_channel("sse_outbox"))
+//!     .storage(sse_gateway::MemoryStorage::default())
+//!     .build()?
+//!     .run()
+//!     .await
+//! ```
+//!
+//! ```sql
+//! BEGIN;
+//! UPDATE orders SET status = 'shipped' WHERE id = 42;
+//! INSERT INTO sse_outbox (channel_id, event_type, data)
+//!     VALUES ('user-7', 'order.shipped', '{"order": 42}');
+//! COMMIT;
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use sse_gateway::{ConnectionManager, DispatchError, IncomingMessage, MessageHandler, MessageSource};
+use tokio::sync::Notify;
+use tokio_postgres::{AsyncMessage, Client, NoTls, Row};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Default outbox table
+pub const DEFAULT_TABLE: &str = "sse_outbox";
+
+/// Longest wait between reconnect attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Publishes rows of a Postgres outbox table
+///
+/// # Columns
+///
+/// | Column | Type | Description |
+/// |--------|------|-------------|
+/// | `id` | `BIGSERIAL` | Publish order |
+/// | `channel_id` | `TEXT NULL` | Target channel; `NULL` broadcasts |
+/// | `event_type` | `TEXT` | SSE event type |
+/// | `data` | `TEXT` | Event data |
+/// | `message_id` | `TEXT NULL` | Business message ID |
+/// | `expires_at` | `TIMESTAMPTZ NULL` | Dropped instead of delivered after this time |
+/// | `priority` | `TEXT NULL` | `high`, `normal` or `low` |
+/// | `processed_at` | `TIMESTAMPTZ NULL` | Set once published |
+///
+/// [`schema`](Self::schema) returns the DDL. Pending rows are claimed with
+/// `FOR UPDATE SKIP LOCKED`, so several gateway instances can share one
+/// table; each row is published by one of them, which makes the outbox a
+/// shared queue that needs cluster mode to reach every subscriber.
+///
+/// A row is marked processed once it has been dispatched; rows whose
+/// dispatch fails (throttled, shutting down) stay pending and are retried on
+/// the next poll. Invalid payloads are marked processed, as retrying them
+/// won't help.
+pub struct OutboxSource {
+    database_url: String,
+    table: String,
+    poll_interval: Duration,
+    batch_size: i64,
+    notify_channel: Option<String>,
+    delete_processed: bool,
+}
+
+impl OutboxSource {
+    /// Create a source reading [`DEFAULT_TABLE`] from `database_url`
+    pub fn new(database_url: impl Into<String>) -> Self {
+        Self {
+            database_url: database_url.into(),
+            table: DEFAULT_TABLE.to_string(),
+            poll_interval: Duration::from_secs(1),
+            batch_size: 100,
+            notify_channel: None,
+            delete_processed: false,
+        }
+    }
+
+    /// Read from `table`, optionally schema-qualified (`events.outbox`)
+    ///
+    /// # Panics
+    ///
+    /// If `table` is not a plain identifier.
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        let table = table.into();
+        assert!(is_identifier(&table), "invalid outbox table name: {}", table);
+        self.table = table;
+        self
+    }
+
+    /// Time between polls when nothing wakes the source (default: 1s)
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Rows claimed per transaction (default: 100)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1) as i64;
+        self
+    }
+
+    /// `LISTEN` on `channel` and poll as soon as it is notified
+    ///
+    /// [`schema`](Self::schema) then includes a trigger that notifies on
+    /// every insert, so rows are published right after commit instead of on
+    /// the next poll.
+    ///
+    /// # Panics
+    ///
+    /// If `channel` is not a plain identifier.
+    pub fn notify_channel(mut self, channel: impl Into<String>) -> Self {
+        let channel = channel.into();
+        assert!(
+            is_identifier(&channel) && !channel.contains('.'),
+            "invalid notify channel: {}",
+            channel
+        );
+        self.notify_channel = Some(channel);
+        self
+    }
+
+    /// Delete published rows instead of setting `processed_at`
+    pub fn delete_processed(mut self, delete: bool) -> Self {
+        self.delete_processed = delete;
+        self
+    }
+
+    /// DDL for the outbox table, plus the notify trigger if a notify channel is set
+    pub fn schema(&self) -> String {
+        let table = &self.table;
+        let name = table.replace('.', "_");
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+    id BIGSERIAL PRIMARY KEY,
+    channel_id TEXT,
+    event_type TEXT NOT NULL DEFAULT 'message',
+    data TEXT NOT NULL,
+    message_id TEXT,
+    expires_at TIMESTAMPTZ,
+    priority TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    processed_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS {name}_pending ON {table} (id) WHERE processed_at IS NULL;
+"
+        );
+        if let Some(channel) = &self.notify_channel {
+            sql.push_str(&format!(
+                "CREATE OR REPLACE FUNCTION {name}_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('{channel}', '');
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS {name}_notify ON {table};
+CREATE TRIGGER {name}_notify AFTER INSERT ON {table}
+    FOR EACH STATEMENT EXECUTE FUNCTION {name}_notify();
+"
+            ));
+        }
+        sql
+    }
+
+    /// Create the outbox table (and notify trigger) if it doesn't exist
+    pub async fn migrate(&self) -> anyhow::Result<()> {
+        let (client, connection) = tokio_postgres::connect(&self.database_url, NoTls).await?;
+        let driver = tokio::spawn(connection);
+        let result = client.batch_execute(&self.schema()).await;
+        drop(client);
+        let _ = driver.await;
+        Ok(result?)
+    }
+
+    /// Claim, publish and settle one batch of pending rows
+    ///
+    /// Returns whether the batch was full, i.e. more rows may be pending.
+    async fn drain(&self, client: &mut Client, handler: &MessageHandler) -> anyhow::Result<bool> {
+        let transaction = client.transaction().await?;
+        let rows = transaction
+            .query(
+                &format!(
+                    "SELECT id, channel_id, event_type, data, message_id, expires_at, priority
+                     FROM {} WHERE processed_at IS NULL
+                     ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED",
+                    self.table
+                ),
+                &[&self.batch_size],
+            )
+            .await?;
+
+        let mut published: Vec<i64> = Vec::with_capacity(rows.len());
+        let mut failed = false;
+        for row in &rows {
+            let id: i64 = row.try_get("id")?;
+            match handler.dispatch(to_incoming(row)?).await {
+                Ok(_) => published.push(id),
+                Err(DispatchError::Invalid(reason)) => {
+                    warn!(id, reason = %reason, "Invalid outbox row, marking processed");
+                    published.push(id);
+                }
+                Err(e @ DispatchError::TooLarge { .. }) => {
+                    warn!(id, error = %e, "Oversized outbox row, marking processed");
+                    published.push(id);
+                }
+                Err(e) => {
+                    // Later rows wait too, so the table stays in publish order
+                    warn!(id, error = %e, "Dispatch failed, leaving outbox row pending");
+                    failed = true;
+                    break;
+                }
+            }
+        }
+
+        if !published.is_empty() {
+            let settle = if self.delete_processed {
+                format!("DELETE FROM {} WHERE id = ANY($1)", self.table)
+            } else {
+                format!("UPDATE {} SET processed_at = now() WHERE id = ANY($1)", self.table)
+            };
+            transaction.execute(&settle, &[&published]).await?;
+        }
+        transaction.commit().await?;
+
+        debug!(published = published.len(), "Drained outbox batch");
+        Ok(!failed && rows.len() as i64 == self.batch_size)
+    }
+
+    /// Publish rows over one connection until cancelled or the connection fails
+    async fn run(&self, handler: &MessageHandler, cancel: &CancellationToken) -> anyhow::Result<()> {
+        let (mut client, mut connection) = tokio_postgres::connect(&self.database_url, NoTls).await?;
+
+        // The connection has to be polled for queries to make progress;
+        // notifications arrive through it too
+        let wake = Arc::new(Notify::new());
+        let driver = {
+            let wake = wake.clone();
+            tokio::spawn(async move {
+                let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+                while let Some(message) = messages.next().await {
+                    match message {
+                        Ok(AsyncMessage::Notification(_)) => wake.notify_one(),
+                        Ok(_) => {}
+                        Err(e) => {
+                            error!(error = %e, "Postgres connection failed");
+                            break;
+                        }
+                    }
+                }
+                // Unblock the poll loop so the next query reports the failure
+                wake.notify_one();
+            })
+        };
+
+        let result = async {
+            if let Some(channel) = &self.notify_channel {
+                client.batch_execute(&format!("LISTEN {}", channel)).await?;
+            }
+            info!(table = %self.table, "Connected to Postgres outbox");
+
+            loop {
+                if cancel.is_cancelled() {
+                    return Ok(());
+                }
+                if self.drain(&mut client, handler).await? {
+                    continue;
+                }
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = wake.notified() => {}
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                }
+            }
+        }
+        .await;
+
+        driver.abort();
+        result
+    }
+}
+
+/// Whether `name` is a plain, optionally schema-qualified, SQL identifier
+fn is_identifier(name: &str) -> bool {
+    let mut parts = name.split('.');
+    let valid = |part: &str| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(name), None, _) => valid(name),
+        (Some(schema), Some(name), None) => valid(schema) && valid(name),
+        _ => false,
+    }
+}
+
+/// Map an outbox row onto an [`IncomingMessage`]
+fn to_incoming(row: &Row) -> anyhow::Result<IncomingMessage> {
+    let priority: Option<String> = row.try_get("priority")?;
+    let channel_id: Option<String> = row.try_get("channel_id")?;
+    Ok(IncomingMessage {
+        channel_id: channel_id.map(Into::into),
+        event_type: row.try_get("event_type")?,
+        data: row.try_get("data")?,
+        id: row.try_get("message_id")?,
+        expires_at: row.try_get("expires_at")?,
+        priority: priority.and_then(|p| p.parse().ok()).unwrap_or_default(),
+        report: None,
+    })
+}
+
+#[async_trait]
+impl MessageSource for OutboxSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        info!(table = %self.table, notify = ?self.notify_channel, "Starting Postgres outbox");
+
+        let mut delay = Duration::from_secs(1);
+        loop {
+            let started = Instant::now();
+            match self.run(&handler, &cancel).await {
+                Ok(()) => break,
+                Err(e) => {
+                    // A connection that held up for a while starts the backoff over
+                    if started.elapsed() > MAX_RECONNECT_DELAY {
+                        delay = Duration::from_secs(1);
+                    }
+                    warn!(error = %e, delay_ms = delay.as_millis() as u64, "Postgres outbox failed, reconnecting");
+                    tokio::select! {
+                        _ = cancel.cancelled() => break,
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+
+        info!("Postgres outbox stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Postgres outbox"
+    }
+}
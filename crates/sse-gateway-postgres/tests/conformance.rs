@@ -0,0 +1,59 @@
+//! Conformance battery for the Postgres outbox source
+//!
+//! Requires a running Postgres. Run with:
+//! `DATABASE_URL=postgres://postgres@localhost/postgres cargo test -p sse-gateway-postgres -- --ignored`
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sse_gateway::testkit::SourceFixture;
+use sse_gateway_postgres::OutboxSource;
+use tokio_postgres::NoTls;
+
+fn database_url() -> String {
+    std::env::var("DATABASE_URL").unwrap_or_else(|_| "postgres://postgres@localhost/postgres".to_string())
+}
+
+/// Outbox table unique to this test run, so tests can run in parallel
+fn table_name() -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "sse_outbox_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+async fn outbox() -> (OutboxSource, String, tokio_postgres::Client) {
+    let table = table_name();
+    let source = OutboxSource::new(database_url()).table(&table).notify_channel(&table);
+    source.migrate().await.expect("Postgres migration failed");
+
+    let (client, connection) = tokio_postgres::connect(&database_url(), NoTls)
+        .await
+        .expect("Postgres connection failed");
+    tokio::spawn(connection);
+    client
+        .batch_execute(&format!("TRUNCATE {}", table))
+        .await
+        .expect("truncate failed");
+    (source, table, client)
+}
+
+sse_gateway::source_conformance!(#[ignore = "requires Postgres"] postgres_outbox_source, async {
+    let (source, table, client) = outbox().await;
+    let insert = format!(
+        "INSERT INTO {} (channel_id, event_type, data, message_id) VALUES ($1, $2, $3, $4)",
+        table
+    );
+    let client = std::sync::Arc::new(client);
+    SourceFixture::new(source, move |msg| {
+        let client = client.clone();
+        let insert = insert.clone();
+        async move {
+            client
+                .execute(&insert, &[&msg.channel_id.as_deref(), &msg.event_type, &msg.data, &msg.id])
+                .await?;
+            Ok(())
+        }
+    })
+});
@@ -0,0 +1,25 @@
+//! Outbox source configuration, exercised without a Postgres server
+
+use sse_gateway_postgres::OutboxSource;
+
+#[test]
+fn schema_creates_table_and_notify_trigger() {
+    let plain = OutboxSource::new("postgres://localhost/app").schema();
+    assert!(plain.contains("CREATE TABLE IF NOT EXISTS sse_outbox ("));
+    assert!(plain.contains("WHERE processed_at IS NULL"));
+    assert!(!plain.contains("pg_notify"));
+
+    let notifying = OutboxSource::new("postgres://localhost/app")
+        .table("events.outbox")
+        .notify_channel("outbox_ready")
+        .schema();
+    assert!(notifying.contains("CREATE TABLE IF NOT EXISTS events.outbox ("));
+    assert!(notifying.contains("pg_notify('outbox_ready', '')"));
+    assert!(notifying.contains("CREATE TRIGGER events_outbox_notify AFTER INSERT ON events.outbox"));
+}
+
+#[test]
+#[should_panic(expected = "invalid outbox table name")]
+fn table_name_must_be_an_identifier() {
+    let _ = OutboxSource::new("postgres://localhost/app").table("outbox; DROP TABLE users");
+}
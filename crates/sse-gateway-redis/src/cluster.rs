@@ -0,0 +1,507 @@
+//! Redis cluster coordinator
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use sse_gateway::{
+    ChannelPause, ClusterCoordinator, ClusterMessage, InstanceInfo, InstancePresence, PresenceConnection,
+};
+use tokio::sync::RwLock;
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const DEFAULT_PREFIX: &str = "gateway";
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_INSTANCE_TTL: Duration = Duration::from_secs(30);
+
+/// Remove an instance, its claims and its presence entries, if its
+/// `last_seen` still equals ARGV[3] (unconditionally when ARGV[3] is empty)
+///
+/// KEYS: instances ZSET, instance HASH, instance claims SET.
+/// ARGV: instance ID, key prefix, expected `last_seen`.
+/// Returns the channels the instance held, or nil if nothing was removed.
+const EVICT_SCRIPT: &str = r#"
+if ARGV[3] ~= '' and redis.call('HGET', KEYS[2], 'last_seen') ~= ARGV[3] then
+  return false
+end
+local channels = redis.call('SMEMBERS', KEYS[3])
+for _, channel in ipairs(channels) do
+  redis.call('ZREM', ARGV[2] .. ':channel:' .. channel .. ':instances', ARGV[1])
+  redis.call('HDEL', ARGV[2] .. ':presence:' .. channel, ARGV[1])
+end
+redis.call('ZREM', KEYS[1], ARGV[1])
+redis.call('DEL', KEYS[2], KEYS[3])
+return channels
+"#;
+
+/// Redis-backed [`ClusterCoordinator`]
+///
+/// Instances heartbeat into Redis, claim the channels they hold subscribers
+/// for, and receive forwarded messages on a per-instance Pub/Sub channel.
+/// Claims and registrations expire after the instance TTL, so a crashed
+/// instance drops out of the cluster on its own.
+///
+/// Redis keys (with the default `gateway` prefix):
+/// - `gateway:instances` (ZSET): instance IDs scored by registration expiry
+/// - `gateway:instance:{id}` (HASH): `address`, `last_seen`, `registered_at`, `connections`,
+///   `capacity`, `cpu`
+/// - `gateway:instance:{id}:channels` (SET): channels the instance claimed, for eviction
+/// - `gateway:channel:{channel_id}:instances` (ZSET): owning instances scored by claim expiry
+/// - `gateway:presence:{channel_id}` (HASH): instance ID → JSON list of its connections
+/// - `gateway:inbox:{id}` (Pub/Sub channel): messages forwarded to the instance
+/// - `gateway:pauses` (HASH): channel ID → JSON of its cluster-wide pause
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sse_gateway_redis::RedisCluster;
+///
+/// Gateway::builder()
+///     .cluster(RedisCluster::new("redis://localhost:6379").address("10.0.0.5:8080"))
+/// ```
+#[derive(Clone)]
+pub struct RedisCluster {
+    redis_url: String,
+    redis: Arc<RwLock<Option<ConnectionManager>>>,
+    prefix: String,
+    address: Option<String>,
+    heartbeat_interval: Duration,
+    instance_ttl: Duration,
+}
+
+impl RedisCluster {
+    /// Create a coordinator; it connects when the gateway starts
+    pub fn new(redis_url: impl Into<String>) -> Self {
+        Self {
+            redis_url: redis_url.into(),
+            redis: Arc::new(RwLock::new(None)),
+            prefix: DEFAULT_PREFIX.to_string(),
+            address: None,
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            instance_ttl: DEFAULT_INSTANCE_TTL,
+        }
+    }
+
+    /// Key prefix, to run several clusters on one Redis (default: `gateway`)
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Address this instance advertises to peers and clients
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// How often the registration and channel claims are refreshed (default: 10s)
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// How long registrations and claims outlive the last heartbeat (default: 30s)
+    ///
+    /// Should be well over twice the heartbeat interval.
+    pub fn instance_ttl(mut self, ttl: Duration) -> Self {
+        self.instance_ttl = ttl;
+        self
+    }
+
+    fn instances_key(&self) -> String {
+        format!("{}:instances", self.prefix)
+    }
+
+    fn instance_key(&self, instance_id: &str) -> String {
+        format!("{}:instance:{}", self.prefix, instance_id)
+    }
+
+    fn claims_key(&self, instance_id: &str) -> String {
+        format!("{}:instance:{}:channels", self.prefix, instance_id)
+    }
+
+    fn channel_key(&self, channel_id: &str) -> String {
+        format!("{}:channel:{}:instances", self.prefix, channel_id)
+    }
+
+    fn presence_key(&self, channel_id: &str) -> String {
+        format!("{}:presence:{}", self.prefix, channel_id)
+    }
+
+    fn inbox_key(&self, instance_id: &str) -> String {
+        format!("{}:inbox:{}", self.prefix, instance_id)
+    }
+
+    fn pauses_key(&self) -> String {
+        format!("{}:pauses", self.prefix)
+    }
+
+    async fn conn(&self) -> Option<ConnectionManager> {
+        self.redis.read().await.clone()
+    }
+
+    /// Score after which a registration or claim made now expires
+    fn expiry(&self) -> i64 {
+        chrono::Utc::now().timestamp() + self.instance_ttl.as_secs() as i64
+    }
+
+    /// Members of a ZSET whose expiry score is still in the future
+    async fn live_members(&self, conn: &mut ConnectionManager, key: &str) -> redis::RedisResult<Vec<String>> {
+        redis::cmd("ZRANGEBYSCORE")
+            .arg(key)
+            .arg(chrono::Utc::now().timestamp())
+            .arg("+inf")
+            .query_async(conn)
+            .await
+    }
+
+    /// Refresh the registration, re-claim every locally held channel and
+    /// rewrite its presence
+    async fn heartbeat(&self, connection_manager: &sse_gateway::ConnectionManager) {
+        let Some(mut conn) = self.conn().await else { return };
+
+        let instance_id = connection_manager.instance_id();
+        let channels = connection_manager.channel_ids();
+        let now = chrono::Utc::now().timestamp();
+        let expiry = self.expiry();
+        let ttl = self.instance_ttl.as_secs();
+        let instance_key = self.instance_key(instance_id);
+
+        let mut pipe = redis::pipe();
+        pipe.cmd("ZADD").arg(self.instances_key()).arg(expiry).arg(instance_id).ignore();
+        pipe.cmd("ZREMRANGEBYSCORE")
+            .arg(self.instances_key())
+            .arg("-inf")
+            .arg(format!("({}", now))
+            .ignore();
+        pipe.cmd("HSET").arg(&instance_key).arg("last_seen").arg(now).ignore();
+        pipe.cmd("HSET")
+            .arg(&instance_key)
+            .arg("connections")
+            .arg(connection_manager.connection_count())
+            .ignore();
+        if let Some(capacity) = connection_manager.capacity() {
+            pipe.cmd("HSET").arg(&instance_key).arg("capacity").arg(capacity).ignore();
+        }
+        if let Some(cpu) = connection_manager.cpu_hint() {
+            pipe.cmd("HSET").arg(&instance_key).arg("cpu").arg(cpu).ignore();
+        }
+        pipe.cmd("HSETNX").arg(&instance_key).arg("registered_at").arg(now).ignore();
+        if let Some(address) = &self.address {
+            pipe.cmd("HSET").arg(&instance_key).arg("address").arg(address).ignore();
+        }
+        pipe.cmd("EXPIRE").arg(&instance_key).arg(ttl).ignore();
+        let claims_key = self.claims_key(instance_id);
+        for channel_id in &channels {
+            let key = self.channel_key(channel_id);
+            pipe.cmd("ZADD").arg(&key).arg(expiry).arg(instance_id).ignore();
+            pipe.cmd("EXPIRE").arg(&key).arg(ttl).ignore();
+            pipe.cmd("SADD").arg(&claims_key).arg(channel_id).ignore();
+
+            let presence = InstancePresence::local(connection_manager, channel_id);
+            let Ok(connections) = serde_json::to_string(&presence.connections) else { continue };
+            let key = self.presence_key(channel_id);
+            pipe.cmd("HSET").arg(&key).arg(instance_id).arg(connections).ignore();
+            pipe.cmd("EXPIRE").arg(&key).arg(ttl).ignore();
+        }
+
+        pipe.cmd("EXPIRE").arg(&claims_key).arg(ttl).ignore();
+
+        match pipe.query_async::<()>(&mut conn).await {
+            Ok(()) => debug!(instance_id, channels = channels.len(), "Cluster heartbeat sent"),
+            Err(e) => warn!(error = %e, "Cluster heartbeat failed"),
+        }
+    }
+
+    /// Run [`EVICT_SCRIPT`] for `instance_id`
+    async fn evict_if(
+        &self,
+        conn: &mut ConnectionManager,
+        instance_id: &str,
+        last_seen: Option<i64>,
+    ) -> redis::RedisResult<Option<Vec<String>>> {
+        redis::cmd("EVAL")
+            .arg(EVICT_SCRIPT)
+            .arg(3)
+            .arg(self.instances_key())
+            .arg(self.instance_key(instance_id))
+            .arg(self.claims_key(instance_id))
+            .arg(instance_id)
+            .arg(&self.prefix)
+            .arg(last_seen.map(|t| t.to_string()).unwrap_or_default())
+            .query_async(conn)
+            .await
+    }
+
+    /// Remove the registration and this instance's channel claims
+    async fn unregister(&self, instance_id: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+
+        match self.evict_if(&mut conn, instance_id, None).await {
+            Ok(_) => info!(instance_id, "Instance left the cluster"),
+            Err(e) => warn!(error = %e, "Failed to unregister instance"),
+        }
+    }
+}
+
+#[async_trait]
+impl ClusterCoordinator for RedisCluster {
+    async fn start(
+        &self,
+        connection_manager: sse_gateway::ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let instance_id = connection_manager.instance_id().to_string();
+
+        let client = redis::Client::open(self.redis_url.as_str())?;
+        *self.redis.write().await = Some(ConnectionManager::new(client.clone()).await?);
+
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(self.inbox_key(&instance_id)).await?;
+        let mut inbox = pubsub.into_on_message();
+
+        info!(instance_id = %instance_id, address = ?self.address, "Joined cluster");
+
+        let mut interval = tokio::time::interval(self.heartbeat_interval);
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = cancel.cancelled() => break,
+
+                _ = interval.tick() => {
+                    self.heartbeat(&connection_manager).await;
+                }
+
+                msg = inbox.next() => {
+                    let Some(msg) = msg else {
+                        warn!("Cluster inbox closed");
+                        break;
+                    };
+                    let message = msg
+                        .get_payload::<String>()
+                        .map_err(anyhow::Error::from)
+                        .and_then(|payload| Ok(serde_json::from_str::<ClusterMessage>(&payload)?));
+                    match message {
+                        Ok(message) => {
+                            let sent = message.deliver(&connection_manager).await;
+                            debug!(sent, "Delivered forwarded message");
+                        }
+                        Err(e) => warn!(error = %e, "Ignoring malformed forwarded message"),
+                    }
+                }
+            }
+        }
+
+        self.unregister(&instance_id).await;
+        Ok(())
+    }
+
+    async fn claim_channel(&self, instance_id: &str, channel_id: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+
+        let key = self.channel_key(channel_id);
+        let claims_key = self.claims_key(instance_id);
+        let ttl = self.instance_ttl.as_secs();
+        let mut pipe = redis::pipe();
+        pipe.cmd("ZADD").arg(&key).arg(self.expiry()).arg(instance_id).ignore();
+        pipe.cmd("EXPIRE").arg(&key).arg(ttl).ignore();
+        pipe.cmd("SADD").arg(&claims_key).arg(channel_id).ignore();
+        pipe.cmd("EXPIRE").arg(&claims_key).arg(ttl).ignore();
+        if let Err(e) = pipe.query_async::<()>(&mut conn).await {
+            warn!(error = %e, channel_id, "Failed to claim channel");
+        }
+    }
+
+    async fn release_channel(&self, instance_id: &str, channel_id: &str) {
+        let Some(mut conn) = self.conn().await else { return };
+
+        let mut pipe = redis::pipe();
+        pipe.cmd("ZREM").arg(self.channel_key(channel_id)).arg(instance_id).ignore();
+        pipe.cmd("SREM").arg(self.claims_key(instance_id)).arg(channel_id).ignore();
+        if let Err(e) = pipe.query_async::<()>(&mut conn).await {
+            warn!(error = %e, channel_id, "Failed to release channel");
+        }
+    }
+
+    async fn channel_owners(&self, channel_id: &str) -> anyhow::Result<Vec<String>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        Ok(self.live_members(&mut conn, &self.channel_key(channel_id)).await?)
+    }
+
+    async fn set_presence(&self, instance_id: &str, channel_id: &str, connections: &[PresenceConnection]) {
+        let Some(mut conn) = self.conn().await else { return };
+
+        let key = self.presence_key(channel_id);
+        let mut pipe = redis::pipe();
+        if connections.is_empty() {
+            pipe.cmd("HDEL").arg(&key).arg(instance_id).ignore();
+        } else {
+            let Ok(connections) = serde_json::to_string(connections) else { return };
+            pipe.cmd("HSET").arg(&key).arg(instance_id).arg(connections).ignore();
+            pipe.cmd("EXPIRE").arg(&key).arg(self.instance_ttl.as_secs()).ignore();
+        }
+        if let Err(e) = pipe.query_async::<()>(&mut conn).await {
+            warn!(error = %e, channel_id, "Failed to update presence");
+        }
+    }
+
+    async fn presence(&self, channel_id: &str) -> anyhow::Result<Vec<InstancePresence>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+
+        // Only live owners count; a crashed instance's entry lingers until the key expires
+        let owners = self.live_members(&mut conn, &self.channel_key(channel_id)).await?;
+        if owners.is_empty() {
+            return Ok(Vec::new());
+        }
+        let entries: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(self.presence_key(channel_id))
+            .arg(&owners)
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(owners
+            .into_iter()
+            .zip(entries)
+            .map(|(instance_id, entry)| InstancePresence {
+                instance_id,
+                connections: entry
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn instances(&self) -> anyhow::Result<Vec<InstanceInfo>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+
+        let ids = self.live_members(&mut conn, &self.instances_key()).await?;
+        let mut instances = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (address, last_seen, connections, capacity, cpu): (
+                Option<String>,
+                Option<i64>,
+                Option<usize>,
+                Option<usize>,
+                Option<u8>,
+            ) = redis::cmd("HMGET")
+                .arg(self.instance_key(&id))
+                .arg("address")
+                .arg("last_seen")
+                .arg("connections")
+                .arg("capacity")
+                .arg("cpu")
+                .query_async(&mut conn)
+                .await?;
+            instances.push(InstanceInfo {
+                id,
+                address,
+                last_seen: last_seen.unwrap_or_default(),
+                connections,
+                capacity,
+                cpu,
+            });
+        }
+        Ok(instances)
+    }
+
+    /// Compares `last_seen` and removes in one script, so a heartbeat from
+    /// an instance that is alive after all always wins
+    async fn evict(&self, instance: &InstanceInfo) -> anyhow::Result<Option<Vec<String>>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        Ok(self.evict_if(&mut conn, &instance.id, Some(instance.last_seen)).await?)
+    }
+
+    /// Scans the keyspace, so keep it to debugging and dashboards
+    async fn channels(&self) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+
+        let pattern = self.channel_key("*");
+        let mut keys: Vec<String> = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(100)
+                .query_async(&mut conn)
+                .await?;
+            keys.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        let (head, tail) = pattern.split_once('*').unwrap_or((&pattern, ""));
+        let mut channels = HashMap::new();
+        for key in keys {
+            let Some(channel_id) = key.strip_prefix(head).and_then(|k| k.strip_suffix(tail)) else {
+                continue;
+            };
+            let owners = self.live_members(&mut conn, &key).await?;
+            if !owners.is_empty() {
+                channels.insert(channel_id.to_string(), owners);
+            }
+        }
+        Ok(channels)
+    }
+
+    async fn set_channel_pause(&self, channel_id: &str, pause: Option<&ChannelPause>) -> anyhow::Result<()> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        match pause {
+            Some(pause) => {
+                redis::cmd("HSET")
+                    .arg(self.pauses_key())
+                    .arg(channel_id)
+                    .arg(serde_json::to_string(pause)?)
+                    .query_async::<()>(&mut conn)
+                    .await?
+            }
+            None => {
+                redis::cmd("HDEL")
+                    .arg(self.pauses_key())
+                    .arg(channel_id)
+                    .query_async::<()>(&mut conn)
+                    .await?
+            }
+        }
+        Ok(())
+    }
+
+    async fn channel_pauses(&self) -> anyhow::Result<HashMap<String, ChannelPause>> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        let entries: HashMap<String, String> =
+            redis::cmd("HGETALL").arg(self.pauses_key()).query_async(&mut conn).await?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(channel_id, json)| match serde_json::from_str(&json) {
+                Ok(pause) => Some((channel_id, pause)),
+                Err(e) => {
+                    warn!(error = %e, channel_id, "Ignoring malformed channel pause");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn forward(&self, instance_id: &str, message: &ClusterMessage) -> anyhow::Result<()> {
+        let mut conn = self.conn().await.ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        redis::cmd("PUBLISH")
+            .arg(self.inbox_key(instance_id))
+            .arg(serde_json::to_string(message)?)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Redis"
+    }
+}
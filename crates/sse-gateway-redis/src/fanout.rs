@@ -0,0 +1,192 @@
+//! Redis broadcast bus between gateway instances
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use sse_gateway::{ClusterFanout, ClusterMessage};
+use tokio::sync::RwLock;
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const DEFAULT_CHANNEL: &str = "gateway:fanout";
+const DEFAULT_DEDUP_WINDOW: usize = 1024;
+
+/// A message on the bus, tagged with the instance that published it
+#[derive(Serialize, Deserialize)]
+struct Envelope<M> {
+    origin: String,
+    #[serde(flatten)]
+    message: M,
+}
+
+/// Redis Pub/Sub [`ClusterFanout`]
+///
+/// Every message dispatched on an instance is published to one Redis Pub/Sub
+/// channel (`gateway:fanout` by default), and every other instance with
+/// local subscribers on the message's channel delivers it. Messages an
+/// instance has already delivered, by channel and stream ID, are skipped.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sse_gateway_redis::RedisFanout;
+///
+/// Gateway::builder()
+///     .cluster_fanout(RedisFanout::new("redis://localhost:6379"))
+/// ```
+#[derive(Clone)]
+pub struct RedisFanout {
+    redis_url: String,
+    redis: Arc<RwLock<Option<ConnectionManager>>>,
+    instance_id: Arc<OnceLock<String>>,
+    channel: String,
+    dedup_window: usize,
+}
+
+impl RedisFanout {
+    /// Create a fanout bus; it connects when the gateway starts
+    pub fn new(redis_url: impl Into<String>) -> Self {
+        Self {
+            redis_url: redis_url.into(),
+            redis: Arc::new(RwLock::new(None)),
+            instance_id: Arc::new(OnceLock::new()),
+            channel: DEFAULT_CHANNEL.to_string(),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+
+    /// Pub/Sub channel, to run several clusters on one Redis (default: `gateway:fanout`)
+    pub fn channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel = channel.into();
+        self
+    }
+
+    /// How many recently delivered stream IDs to remember (default: 1024)
+    pub fn dedup_window(mut self, size: usize) -> Self {
+        self.dedup_window = size;
+        self
+    }
+}
+
+/// Recently delivered `(channel, stream ID)` pairs
+struct Delivered {
+    order: VecDeque<(Option<String>, String)>,
+    seen: HashSet<(Option<String>, String)>,
+    capacity: usize,
+}
+
+impl Delivered {
+    fn new(capacity: usize) -> Self {
+        Self {
+            order: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a delivery, returning false if it was already recorded
+    fn insert(&mut self, key: (Option<String>, String)) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(key);
+        true
+    }
+}
+
+#[async_trait]
+impl ClusterFanout for RedisFanout {
+    async fn start(
+        &self,
+        connection_manager: sse_gateway::ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let instance_id = self
+            .instance_id
+            .get_or_init(|| connection_manager.instance_id().to_string())
+            .clone();
+
+        let client = redis::Client::open(self.redis_url.as_str())?;
+        *self.redis.write().await = Some(ConnectionManager::new(client.clone()).await?);
+
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(&self.channel).await?;
+        let mut messages = pubsub.into_on_message();
+
+        info!(instance_id = %instance_id, channel = %self.channel, "Joined fanout bus");
+
+        let mut delivered = Delivered::new(self.dedup_window);
+        loop {
+            let msg = tokio::select! {
+                _ = cancel.cancelled() => break,
+                msg = messages.next() => msg,
+            };
+            let Some(msg) = msg else {
+                warn!("Fanout subscription closed");
+                break;
+            };
+            let envelope = msg
+                .get_payload::<String>()
+                .map_err(anyhow::Error::from)
+                .and_then(|payload| Ok(serde_json::from_str::<Envelope<ClusterMessage>>(&payload)?));
+            let Envelope { origin, message } = match envelope {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    warn!(error = %e, "Ignoring malformed fanout message");
+                    continue;
+                }
+            };
+            if origin == instance_id {
+                continue;
+            }
+            let subscribed = match &message.channel_id {
+                Some(channel_id) => connection_manager.channel_connection_count(channel_id) > 0,
+                None => connection_manager.connection_count() > 0,
+            };
+            if !subscribed {
+                continue;
+            }
+            if let Some(stream_id) = &message.event.stream_id {
+                if !delivered.insert((message.channel_id.clone(), stream_id.clone())) {
+                    continue;
+                }
+            }
+            let sent = message.deliver(&connection_manager).await;
+            debug!(sent, origin = %origin, "Delivered fanout message");
+        }
+        Ok(())
+    }
+
+    async fn publish(&self, message: &ClusterMessage) -> anyhow::Result<()> {
+        let mut conn = self
+            .redis
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Redis not connected"))?;
+        let origin = self.instance_id.get().cloned().unwrap_or_default();
+        let payload = serde_json::to_string(&Envelope { origin, message })?;
+        redis::cmd("PUBLISH")
+            .arg(&self.channel)
+            .arg(payload)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "RedisFanout"
+    }
+}
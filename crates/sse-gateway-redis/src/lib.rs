@@ -3,9 +3,17 @@
 //! This crate provides:
 //! - `RedisPubSubSource`: Receive messages from Redis Pub/Sub
 //! - `RedisStorage`: Store messages in Redis Streams for replay
+//! - `RedisCluster`: Instance registry and cross-instance forwarding (cluster mode)
+//! - `RedisFanout`: Broadcast bus delivering every message on every instance
 
+mod cluster;
+mod fanout;
 mod pubsub;
+mod replay_cache;
 mod storage;
 
+pub use cluster::RedisCluster;
+pub use fanout::RedisFanout;
 pub use pubsub::RedisPubSubSource;
-pub use storage::RedisStorage;
+pub use replay_cache::ReplayCacheStats;
+pub use storage::{RedisStorage, WritePolicy, WriteStats};
@@ -1,7 +1,7 @@
 //! Redis Pub/Sub message source
 
 use async_trait::async_trait;
-use sse_gateway::{ConnectionManager, IncomingMessage, MessageHandler, MessageSource};
+use sse_gateway::{CloudEvent, ConnectionManager, DecodeError, IncomingMessage, MessageHandler, MessageSource};
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn, debug};
@@ -26,6 +26,14 @@ impl RedisPubSubSource {
     }
 }
 
+/// Parse `payload` as a structured-mode CloudEvent, if it claims to be one
+fn cloud_event(payload: &str) -> Option<anyhow::Result<CloudEvent>> {
+    if !payload.contains("\"specversion\"") {
+        return None;
+    }
+    Some(CloudEvent::from_json(payload))
+}
+
 #[async_trait]
 impl MessageSource for RedisPubSubSource {
     async fn start(
@@ -56,18 +64,40 @@ impl MessageSource for RedisPubSubSource {
                     match msg {
                         Some(msg) => {
                             let channel = msg.get_channel_name().to_string();
-                            if let Ok(payload) = msg.get_payload::<String>() {
-                                debug!(channel = %channel, "Received message");
-                                
-                                let incoming = IncomingMessage {
-                                    channel_id: Some(channel),
+                            debug!(channel = %channel, "Received message");
+                            let payload = match std::str::from_utf8(msg.get_payload_bytes()) {
+                                Ok(payload) => payload.to_string(),
+                                Err(e) => {
+                                    let poison = DecodeError::new(self.name(), msg.get_payload_bytes(), e);
+                                    handler.decode_failed(poison.with_channel(channel));
+                                    continue;
+                                }
+                            };
+
+                            let incoming = match cloud_event(&payload) {
+                                // CloudEvents without a subject go to the Redis channel
+                                Some(Ok(event)) => {
+                                    let mut incoming = IncomingMessage::from(event);
+                                    incoming.channel_id.get_or_insert(channel.into());
+                                    incoming
+                                }
+                                Some(Err(e)) => {
+                                    let reason = format!("malformed CloudEvents envelope: {}", e);
+                                    handler.decode_failed(DecodeError::new(self.name(), payload, reason).with_channel(channel));
+                                    continue;
+                                }
+                                None => IncomingMessage {
+                                    channel_id: Some(channel.into()),
                                     event_type: "message".to_string(),
                                     data: payload,
                                     id: None,
-                                };
-                                
-                                handler(incoming);
-                            }
+                                    expires_at: None,
+                                    priority: Default::default(),
+                                    report: None,
+                                },
+                            };
+
+                            handler.send(incoming);
                         }
                         None => {
                             warn!("Redis stream ended");
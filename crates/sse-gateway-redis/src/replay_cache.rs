@@ -0,0 +1,280 @@
+//! In-memory cache of recent stream tails for replay
+//!
+//! Each cached channel holds a contiguous run of the newest events of its
+//! stream together with the stream ID the run starts after, so any replay
+//! cursor at or past that point can be answered without an XRANGE. Segments
+//! start with this storage's writes, are extended back to the cursor by a
+//! replay that missed, and expire after a TTL so writes made by other
+//! instances show up within that time.
+
+use serde::Serialize;
+use sse_gateway::SseEvent;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Counters of the replay cache of a [`RedisStorage`](crate::RedisStorage)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReplayCacheStats {
+    /// Replays served from memory
+    pub hits: u64,
+    /// Replays that went to Redis
+    pub misses: u64,
+    /// Channels with a cached segment now
+    pub channels: usize,
+}
+
+/// Redis Stream ID as `(milliseconds, sequence)`, ordered like Redis orders them
+pub(crate) fn parse_stream_id(id: &str) -> Option<(u64, u64)> {
+    let (ms, seq) = id.split_once('-')?;
+    Some((ms.parse().ok()?, seq.parse().ok()?))
+}
+
+fn event_id(event: &SseEvent) -> Option<(u64, u64)> {
+    event.stream_id.as_deref().and_then(parse_stream_id)
+}
+
+enum State {
+    /// A replay is reading the stream; this storage's writes are collected
+    /// here, including any already cached that may not be flushed yet
+    Loading { token: u64, writes: Vec<SseEvent> },
+    Ready(Segment),
+}
+
+struct Segment {
+    /// Every event after this ID is in `events`
+    after: (u64, u64),
+    events: VecDeque<SseEvent>,
+    loaded_at: Instant,
+}
+
+impl Segment {
+    /// A segment starting at `events`, which are in stream order
+    fn new(after: (u64, u64), events: impl IntoIterator<Item = SseEvent>, per_channel: usize) -> Self {
+        let mut segment = Self {
+            after,
+            events: VecDeque::new(),
+            loaded_at: Instant::now(),
+        };
+        for event in events {
+            segment.push(event, per_channel);
+        }
+        segment
+    }
+
+    fn last(&self) -> (u64, u64) {
+        self.events.back().and_then(event_id).unwrap_or(self.after)
+    }
+
+    fn push(&mut self, event: SseEvent, per_channel: usize) {
+        self.events.push_back(event);
+        while self.events.len() > per_channel {
+            if let Some(id) = self.events.pop_front().as_ref().and_then(event_id) {
+                self.after = id;
+            }
+        }
+    }
+}
+
+struct Entry {
+    state: State,
+    last_used: Instant,
+}
+
+impl Entry {
+    /// Writes of this storage held by the entry, oldest first
+    fn take_events(&mut self) -> Vec<SseEvent> {
+        match &mut self.state {
+            State::Loading { writes, .. } => std::mem::take(writes),
+            State::Ready(segment) => std::mem::take(&mut segment.events).into(),
+        }
+    }
+}
+
+pub(crate) struct ReplayCache {
+    max_channels: usize,
+    per_channel: usize,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+    next_token: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ReplayCache {
+    pub(crate) fn new(max_channels: usize, per_channel: usize, ttl: Duration) -> Self {
+        Self {
+            max_channels,
+            per_channel,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            next_token: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ReplayCacheStats {
+        let entries = self.entries.lock().unwrap();
+        ReplayCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            channels: entries
+                .values()
+                .filter(|entry| matches!(entry.state, State::Ready(_)))
+                .count(),
+        }
+    }
+
+    /// Make room for one more channel
+    fn evict(&self, entries: &mut HashMap<String, Entry>) {
+        while entries.len() >= self.max_channels.max(1) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(channel, _)| channel.clone());
+            match oldest {
+                Some(channel) => entries.remove(&channel),
+                None => break,
+            };
+        }
+    }
+
+    /// Events after `after`, if the cached segment reaches back that far
+    ///
+    /// On a miss the channel is marked as loading and `Err(Some(token))` is
+    /// returned; the caller should read the stream and pass the result to
+    /// [`finish_load`](Self::finish_load). `Err(None)` means another replay
+    /// is already loading the channel.
+    pub(crate) fn get(&self, channel_id: &str, after: (u64, u64)) -> Result<Vec<SseEvent>, Option<u64>> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        let writes = match entries.get_mut(channel_id) {
+            Some(entry) => {
+                match &entry.state {
+                    State::Ready(segment)
+                        if now.duration_since(segment.loaded_at) < self.ttl && after >= segment.after =>
+                    {
+                        entry.last_used = now;
+                        self.hits.fetch_add(1, Ordering::Relaxed);
+                        return Ok(segment
+                            .events
+                            .iter()
+                            .filter(|event| event_id(event).is_some_and(|id| id > after))
+                            .cloned()
+                            .collect());
+                    }
+                    // A load that never finished (say, the replay was
+                    // cancelled) is taken over once it is older than the TTL
+                    State::Loading { .. } if now.duration_since(entry.last_used) < self.ttl => {
+                        self.misses.fetch_add(1, Ordering::Relaxed);
+                        return Err(None);
+                    }
+                    // Expired, or the cursor is older than the segment
+                    _ => {}
+                }
+                // Cached writes may not have reached Redis yet, so the load
+                // keeps them
+                entry.take_events()
+            }
+            None => {
+                self.evict(&mut entries);
+                Vec::new()
+            }
+        };
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        entries.insert(
+            channel_id.to_string(),
+            Entry {
+                state: State::Loading { token, writes },
+                last_used: now,
+            },
+        );
+        Err(Some(token))
+    }
+
+    /// Cache the result of a replay that missed
+    ///
+    /// `events` must be everything in the stream after `after`; pass `None`
+    /// when the read failed or was cut short so only this storage's own
+    /// writes stay cached.
+    pub(crate) fn finish_load(
+        &self,
+        channel_id: &str,
+        token: u64,
+        after: (u64, u64),
+        events: Option<&[SseEvent]>,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(channel_id) else {
+            return;
+        };
+        // Taken over by a later load
+        if !matches!(entry.state, State::Loading { token: current, .. } if current == token) {
+            return;
+        }
+        let writes = entry.take_events();
+
+        let segment = match events {
+            Some(events) => {
+                // Writes may or may not have been flushed before the read
+                let mut merged: Vec<SseEvent> = events
+                    .iter()
+                    .cloned()
+                    .chain(writes)
+                    .filter(|event| event_id(event).is_some_and(|id| id > after))
+                    .collect();
+                merged.sort_by_key(event_id);
+                merged.dedup_by_key(|event| event_id(event));
+                Segment::new(after, merged, self.per_channel)
+            }
+            None => match writes.first().and_then(event_id) {
+                Some(first) => Segment::new(first, writes, self.per_channel),
+                None => {
+                    entries.remove(channel_id);
+                    return;
+                }
+            },
+        };
+        entry.state = State::Ready(segment);
+    }
+
+    /// Forget a channel, e.g. after its stream was purged
+    pub(crate) fn invalidate(&self, channel_id: &str) {
+        self.entries.lock().unwrap().remove(channel_id);
+    }
+
+    /// Record a write to the stream of `channel_id`
+    ///
+    /// A channel without an entry gets one starting at this event, which
+    /// answers replays from any cursor at or past it.
+    pub(crate) fn append(&self, channel_id: &str, event: SseEvent) {
+        let Some(id) = event_id(&event) else {
+            return;
+        };
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(channel_id) else {
+            self.evict(&mut entries);
+            entries.insert(
+                channel_id.to_string(),
+                Entry {
+                    state: State::Ready(Segment::new(id, [event], self.per_channel)),
+                    last_used: Instant::now(),
+                },
+            );
+            return;
+        };
+        match &mut entry.state {
+            State::Loading { writes, .. } => writes.push(event),
+            State::Ready(segment) if id > segment.last() => segment.push(event, self.per_channel),
+            // Out of order: the segment may no longer match the stream
+            State::Ready(_) => {
+                entries.remove(channel_id);
+            }
+        }
+    }
+}
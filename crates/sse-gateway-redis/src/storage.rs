@@ -1,11 +1,16 @@
 //! Redis Streams message storage with batching support
 
+use crate::replay_cache::{parse_stream_id, ReplayCache, ReplayCacheStats};
 use async_trait::async_trait;
 use redis::aio::ConnectionManager;
 use redis::streams::{StreamId, StreamRangeReply};
-use sse_gateway::{EventData, MessageStorage, SseEvent};
+use sse_gateway::id::{IdGenerator, StreamIds};
+use sse_gateway::{DailyUsage, EventData, MessageStorage, PurgeBefore, SseEvent, UsageDelta};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{info, warn};
 
@@ -13,20 +18,130 @@ const MAX_MESSAGES_PER_CHANNEL: usize = 100;
 const BATCH_SIZE: usize = 100;
 const BATCH_FLUSH_INTERVAL_MS: u64 = 10;
 const DEFAULT_TTL_SECONDS: u64 = 3600; // 1 hour
+/// Capacity of each priority's write queue
+const QUEUE_CAPACITY: usize = 10_000;
+/// How long daily channel usage is kept
+const USAGE_TTL_SECONDS: u64 = 400 * 86_400;
+/// How long a day's subscriber set is kept to count unique subscribers
+const USAGE_SUBSCRIBERS_TTL_SECONDS: u64 = 2 * 86_400;
+
+/// How the batch writer handles Redis failures
+///
+/// A batch that fails with a connection error or times out is retried with
+/// exponential backoff. If it still fails, it is kept in an in-memory spill
+/// buffer and written once Redis is back, ahead of newer writes so stream IDs
+/// stay in order. Writes beyond the spill capacity are dropped and counted in
+/// [`WriteStats`]. Batches Redis rejects outright are not retried.
+#[derive(Debug, Clone)]
+pub struct WritePolicy {
+    /// Retries of a failed batch before it is spilled
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each further retry
+    pub retry_backoff: Duration,
+    /// Time a pipeline may take before it counts as failed
+    pub pipeline_timeout: Duration,
+    /// Writes held in memory while Redis is unavailable
+    pub spill_capacity: usize,
+}
+
+impl Default for WritePolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(50),
+            pipeline_timeout: Duration::from_millis(200),
+            spill_capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Default)]
+struct WriteCounters {
+    written: AtomicU64,
+    retries: AtomicU64,
+    spilled: AtomicU64,
+    spill_len: AtomicU64,
+    dropped_queue_full: AtomicU64,
+    dropped_spill_full: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// Write path counters of a [`RedisStorage`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WriteStats {
+    /// Events written to Redis
+    pub written: u64,
+    /// Batch retries after transient failures
+    pub retries: u64,
+    /// Events moved to the spill buffer, including any later dropped for capacity
+    pub spilled: u64,
+    /// Events waiting in the spill buffer now
+    pub spill_len: u64,
+    /// Events dropped because the write queue was full
+    pub dropped_queue_full: u64,
+    /// Events dropped because the spill buffer was full
+    pub dropped_spill_full: u64,
+    /// Events in batches Redis rejected
+    pub rejected: u64,
+}
+
+/// Moves a stream's entry at `ARGV[1]` into the snapshot hash and drops
+/// every entry up to it
+const COMPACT_SCRIPT: &str = r#"
+local entry = redis.call('XRANGE', KEYS[1], ARGV[1], ARGV[1])
+if #entry == 0 then return 0 end
+redis.call('DEL', KEYS[2])
+redis.call('HSET', KEYS[2], 'stream_id', ARGV[1], unpack(entry[1][2]))
+redis.call('EXPIRE', KEYS[2], ARGV[2])
+redis.call('XTRIM', KEYS[1], 'MINID', ARGV[1])
+redis.call('XDEL', KEYS[1], ARGV[1])
+return 1
+"#;
+
+/// Adds a usage delta to a day's hash (`KEYS[1]`), counting subscribers
+/// not yet in the day's set (`KEYS[2]`) as unique
+const USAGE_SCRIPT: &str = r#"
+redis.call('HINCRBY', KEYS[1], 'published', ARGV[1])
+redis.call('HINCRBY', KEYS[1], 'delivered', ARGV[2])
+local peak = tonumber(redis.call('HGET', KEYS[1], 'peak_subscribers') or '0')
+if tonumber(ARGV[3]) > peak then
+  redis.call('HSET', KEYS[1], 'peak_subscribers', ARGV[3])
+end
+local added = 0
+for i = 6, #ARGV do
+  added = added + redis.call('SADD', KEYS[2], ARGV[i])
+end
+redis.call('HINCRBY', KEYS[1], 'unique_subscribers', added)
+redis.call('EXPIRE', KEYS[1], ARGV[4])
+redis.call('EXPIRE', KEYS[2], ARGV[5])
+return added
+"#;
+
+/// What a queued write does
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WriteOp {
+    /// Append the event to the stream
+    Add,
+    /// Compact the stream up to `stream_id`
+    Compact,
+}
 
 /// Message to be stored
 struct StoreRequest {
+    op: WriteOp,
     channel_id: String,
     stream_id: String,
     event_type: String,
     data: String,
     id: Option<String>,
+    expires_at: Option<String>,
 }
 
 /// Redis Streams message storage with batching
 ///
 /// Uses a background task to batch multiple XADD commands into a single pipeline,
-/// reducing network round-trips and improving throughput.
+/// reducing network round-trips and improving throughput. Failed batches are
+/// retried and spilled to memory according to the [`WritePolicy`].
 ///
 /// # Example
 ///
@@ -48,11 +163,13 @@ struct StoreRequest {
 pub struct RedisStorage {
     redis: Arc<RwLock<Option<ConnectionManager>>>,
     max_per_channel: usize,
-    counter: Arc<AtomicU64>,
+    ids: Arc<StreamIds>,
     /// TTL for stream keys in seconds
     ttl_seconds: u64,
-    /// Channel for batching store requests
-    store_tx: mpsc::Sender<StoreRequest>,
+    /// Channels for batching store requests, one per priority (highest first)
+    store_tx: [mpsc::Sender<StoreRequest>; 3],
+    write_counters: Arc<WriteCounters>,
+    replay_cache: Option<Arc<ReplayCache>>,
 }
 
 impl RedisStorage {
@@ -68,132 +185,134 @@ impl RedisStorage {
 
     /// Create with custom max messages and TTL
     pub fn with_options(max_per_channel: usize, ttl_seconds: u64) -> Self {
-        let (store_tx, store_rx) = mpsc::channel(10000);
+        Self::with_write_policy(max_per_channel, ttl_seconds, WritePolicy::default())
+    }
+
+    /// Create with custom max messages, TTL and failure handling
+    pub fn with_write_policy(max_per_channel: usize, ttl_seconds: u64, policy: WritePolicy) -> Self {
+        let (high_tx, high_rx) = mpsc::channel(QUEUE_CAPACITY);
+        let (normal_tx, normal_rx) = mpsc::channel(QUEUE_CAPACITY);
+        let (low_tx, low_rx) = mpsc::channel(QUEUE_CAPACITY);
         let storage = Self {
             redis: Arc::new(RwLock::new(None)),
             max_per_channel,
-            counter: Arc::new(AtomicU64::new(0)),
+            ids: Arc::new(StreamIds::new()),
             ttl_seconds,
-            store_tx,
+            store_tx: [high_tx, normal_tx, low_tx],
+            write_counters: Arc::default(),
+            replay_cache: None,
         };
 
-        storage.start_batch_processor(store_rx);
+        storage.start_batch_processor([high_rx, normal_rx, low_rx], policy);
         storage
     }
 
+    /// Serve replays of recently active channels from memory
+    ///
+    /// The newest events of each channel this storage writes or replays (up
+    /// to the per-channel maximum) are kept in memory for `ttl`, so a burst
+    /// of reconnects costs at most one XRANGE instead of one per client. Up
+    /// to `channels` channels are cached, least recently used evicted first.
+    /// Writes made by other gateway instances are not seen until the cached
+    /// segment expires, so keep `ttl` short when several instances publish
+    /// to the same channels.
+    pub fn replay_cache(mut self, channels: usize, ttl: Duration) -> Self {
+        self.replay_cache = Some(Arc::new(ReplayCache::new(channels, self.max_per_channel, ttl)));
+        self
+    }
+
+    /// Hit and miss counters of the replay cache, if enabled
+    pub fn replay_cache_stats(&self) -> Option<ReplayCacheStats> {
+        self.replay_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Hand a request to the batch processor without blocking
+    ///
+    /// Returns `false` if the queue was full and the request was dropped.
+    fn enqueue(&self, lane: usize, req: StoreRequest) -> bool {
+        let Err(e) = self.store_tx[lane].try_send(req) else {
+            return true;
+        };
+        let dropped = self.write_counters.dropped_queue_full.fetch_add(1, Ordering::Relaxed);
+        // Log the first drop and every thousandth after it
+        if dropped.is_multiple_of(1000) {
+            warn!(error = %e, dropped = dropped + 1, "Store queue full, dropping message");
+        }
+        false
+    }
+
+    /// Counters of written, retried, spilled and dropped writes
+    pub fn write_stats(&self) -> WriteStats {
+        let counters = &self.write_counters;
+        WriteStats {
+            written: counters.written.load(Ordering::Relaxed),
+            retries: counters.retries.load(Ordering::Relaxed),
+            spilled: counters.spilled.load(Ordering::Relaxed),
+            spill_len: counters.spill_len.load(Ordering::Relaxed),
+            dropped_queue_full: counters.dropped_queue_full.load(Ordering::Relaxed),
+            dropped_spill_full: counters.dropped_spill_full.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+        }
+    }
+
     /// Start background task that batches and executes store requests
-    fn start_batch_processor(&self, mut rx: mpsc::Receiver<StoreRequest>) {
-        let redis = self.redis.clone();
-        let max_per_channel = self.max_per_channel;
-        let ttl_seconds = self.ttl_seconds;
+    ///
+    /// Queues are drained highest priority first, so a backlog of low
+    /// priority writes doesn't delay (or crowd out) high priority ones.
+    fn start_batch_processor(&self, rx: [mpsc::Receiver<StoreRequest>; 3], policy: WritePolicy) {
+        let mut writer = BatchWriter {
+            redis: self.redis.clone(),
+            max_per_channel: self.max_per_channel,
+            ttl_seconds: self.ttl_seconds,
+            policy,
+            counters: self.write_counters.clone(),
+            spill: VecDeque::new(),
+            refilled: 0,
+        };
 
         tokio::spawn(async move {
             let mut batch: Vec<StoreRequest> = Vec::with_capacity(BATCH_SIZE);
             let mut interval = tokio::time::interval(
-                std::time::Duration::from_millis(BATCH_FLUSH_INTERVAL_MS)
+                Duration::from_millis(BATCH_FLUSH_INTERVAL_MS)
             );
 
+            let [mut high, mut normal, mut low] = rx;
+
             loop {
-                tokio::select! {
-                    // Receive store requests
-                    msg = rx.recv() => {
-                        match msg {
-                            Some(req) => {
-                                batch.push(req);
-                                // Flush if batch is full
-                                if batch.len() >= BATCH_SIZE {
-                                    Self::flush_batch(&redis, &mut batch, max_per_channel, ttl_seconds).await;
-                                }
-                            }
-                            None => break, // Channel closed
-                        }
-                    }
+                let req = tokio::select! {
+                    biased;
+                    // Receive store requests, highest priority first
+                    Some(req) = high.recv() => req,
+                    Some(req) = normal.recv() => req,
+                    Some(req) = low.recv() => req,
                     // Periodic flush
                     _ = interval.tick() => {
+                        writer.refill(&mut batch);
                         if !batch.is_empty() {
-                            Self::flush_batch(&redis, &mut batch, max_per_channel, ttl_seconds).await;
+                            writer.flush(&mut batch).await;
                         }
+                        // All channels closed and drained
+                        if [&high, &normal, &low].iter().all(|rx| rx.is_closed() && rx.is_empty()) {
+                            if !writer.spill.is_empty() {
+                                warn!(count = writer.spill.len(), "Storage closed with unwritten events");
+                            }
+                            break;
+                        }
+                        continue;
                     }
+                };
+                // Newer writes queue behind spilled ones
+                if writer.spill.is_empty() {
+                    batch.push(req);
+                } else {
+                    writer.spill_back(req);
                 }
-            }
-        });
-    }
-
-    /// Flush batch using Redis pipeline
-    async fn flush_batch(
-        redis: &Arc<RwLock<Option<ConnectionManager>>>,
-        batch: &mut Vec<StoreRequest>,
-        max_per_channel: usize,
-        ttl_seconds: u64,
-    ) {
-        if batch.is_empty() {
-            return;
-        }
-
-        let manager = {
-            let conn = redis.read().await;
-            match &*conn {
-                Some(m) => m.clone(),
-                None => {
-                    batch.clear();
-                    return;
+                // Flush if batch is full
+                if batch.len() >= BATCH_SIZE {
+                    writer.flush(&mut batch).await;
                 }
             }
-        };
-
-        let mut conn = manager;
-        let mut pipe = redis::pipe();
-
-        // Collect unique channel keys to set TTL
-        let mut keys_to_expire: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-        for req in batch.iter() {
-            let key = Self::stream_key(&req.channel_id);
-            keys_to_expire.insert(key.clone());
-
-            // XADD command
-            pipe.cmd("XADD")
-                .arg(&key)
-                .arg("MAXLEN")
-                .arg("~")
-                .arg(max_per_channel)
-                .arg(&req.stream_id)
-                .arg("event_type")
-                .arg(&req.event_type)
-                .arg("data")
-                .arg(&req.data);
-
-            if let Some(ref id) = req.id {
-                pipe.arg("id").arg(id);
-            }
-
-            pipe.ignore();
-        }
-
-        // Set TTL for all affected keys (refresh on each write)
-        for key in keys_to_expire {
-            pipe.cmd("EXPIRE")
-                .arg(&key)
-                .arg(ttl_seconds)
-                .ignore();
-        }
-
-        let batch_size = batch.len();
-        batch.clear();
-
-        // Execute pipeline with timeout
-        let fut = pipe.query_async::<()>(&mut conn);
-        match tokio::time::timeout(std::time::Duration::from_millis(200), fut).await {
-            Ok(Ok(_)) => {
-                tracing::debug!(count = batch_size, "Batch stored to Redis");
-            }
-            Ok(Err(e)) => {
-                warn!(error = %e, count = batch_size, "Failed to store batch");
-            }
-            Err(_) => {
-                tracing::debug!(count = batch_size, "Batch store timeout");
-            }
-        }
+        });
     }
 
     /// Connect to Redis
@@ -209,14 +328,39 @@ impl RedisStorage {
         format!("sse:stream:{}", channel_id)
     }
 
-    /// Check if the ID is a valid Redis Stream ID format (timestamp-sequence)
-    fn is_valid_stream_id(id: &str) -> bool {
-        let parts: Vec<&str> = id.split('-').collect();
-        if parts.len() != 2 {
-            return false;
-        }
-        // Both parts should be numeric
-        parts[0].parse::<u64>().is_ok() && parts[1].parse::<u64>().is_ok()
+    /// Hash holding the event a channel was compacted into
+    fn snapshot_key(channel_id: &str) -> String {
+        format!("sse:snapshot:{}", channel_id)
+    }
+
+    /// Hash of consumer ID -> acknowledged stream ID for a channel
+    fn cursor_key(channel_id: &str) -> String {
+        format!("sse:cursors:{}", channel_id)
+    }
+
+    /// String holding a persisted resume session
+    fn session_key(token: &str) -> String {
+        format!("sse:session:{}", token)
+    }
+
+    /// Hash of a channel's usage totals for one day
+    fn usage_key(channel_id: &str, date: chrono::NaiveDate) -> String {
+        format!("sse:usage:{}:{}", channel_id, date)
+    }
+
+    /// Set of the subscribers counted for a channel on one day
+    fn usage_subscribers_key(channel_id: &str, date: chrono::NaiveDate) -> String {
+        format!("sse:usage_subscribers:{}:{}", channel_id, date)
+    }
+
+    /// Snapshot event from its hash, if the channel has one
+    fn parse_snapshot(mut map: HashMap<String, redis::Value>) -> Option<SseEvent> {
+        let id = match map.remove("stream_id")? {
+            redis::Value::BulkString(bytes) => String::from_utf8(bytes).ok()?,
+            redis::Value::SimpleString(s) => s,
+            _ => return None,
+        };
+        Self::parse_stream_entries(vec![StreamId { id, map, ..Default::default() }]).pop()
     }
 
     fn parse_stream_entries(entries: Vec<StreamId>) -> Vec<SseEvent> {
@@ -244,12 +388,25 @@ impl RedisStorage {
                     _ => None,
                 });
 
+                let expires_at = map
+                    .get("expires_at")
+                    .and_then(|v| match v {
+                        redis::Value::BulkString(bytes) => String::from_utf8(bytes.clone()).ok(),
+                        redis::Value::SimpleString(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+                    .map(|at| at.with_timezone(&chrono::Utc));
+
                 Some(SseEvent {
                     event_type,
                     data: EventData::Raw(data),
                     id,
                     stream_id: Some(stream_id),
                     retry: None,
+                    expires_at,
+                    // Replay is ordered by stream ID; priority only matters live
+                    priority: Default::default(),
                 })
             })
             .collect()
@@ -262,30 +419,248 @@ impl Default for RedisStorage {
     }
 }
 
+/// State of the background batch processor
+struct BatchWriter {
+    redis: Arc<RwLock<Option<ConnectionManager>>>,
+    max_per_channel: usize,
+    ttl_seconds: u64,
+    policy: WritePolicy,
+    counters: Arc<WriteCounters>,
+    /// Writes waiting for Redis, oldest first
+    spill: VecDeque<StoreRequest>,
+    /// Writes of the current batch taken from the spill buffer
+    refilled: usize,
+}
+
+impl BatchWriter {
+    /// Move spilled writes into `batch`, oldest first
+    ///
+    /// They still count as spilled until the batch is written.
+    fn refill(&mut self, batch: &mut Vec<StoreRequest>) {
+        while batch.len() < BATCH_SIZE {
+            let Some(req) = self.spill.pop_front() else { break };
+            batch.push(req);
+            self.refilled += 1;
+        }
+    }
+
+    /// Queue a write behind those already spilled
+    fn spill_back(&mut self, req: StoreRequest) {
+        if self.spill.len() < self.policy.spill_capacity {
+            self.spill.push_back(req);
+            self.counters.spilled.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.counters.dropped_spill_full.fetch_add(1, Ordering::Relaxed);
+        }
+        self.update_spill_len();
+    }
+
+    fn update_spill_len(&self) {
+        self.counters.spill_len.store(self.spill.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Write `batch`, retrying transient failures, and spill it if Redis stays unavailable
+    async fn flush(&mut self, batch: &mut Vec<StoreRequest>) {
+        let refilled = std::mem::take(&mut self.refilled);
+        let pipe = self.pipeline(batch);
+        let mut backoff = self.policy.retry_backoff;
+        for attempt in 0..=self.policy.max_retries {
+            if attempt > 0 {
+                self.counters.retries.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            match self.execute(&pipe).await {
+                Ok(()) => {
+                    tracing::debug!(count = batch.len(), "Batch stored to Redis");
+                    let added = batch.iter().filter(|req| req.op == WriteOp::Add).count();
+                    self.counters.written.fetch_add(added as u64, Ordering::Relaxed);
+                    batch.clear();
+                    self.update_spill_len();
+                    return;
+                }
+                Err(WriteError::Transient(reason)) => {
+                    tracing::debug!(attempt, reason = %reason, count = batch.len(), "Batch store failed");
+                }
+                Err(WriteError::Rejected(e)) => {
+                    warn!(error = %e, count = batch.len(), "Redis rejected batch");
+                    self.counters.rejected.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                    batch.clear();
+                    self.update_spill_len();
+                    return;
+                }
+            }
+        }
+
+        // Keep the batch ahead of newer spilled writes; the newest go if over capacity
+        warn!(count = batch.len(), spilled = self.spill.len(), "Redis unavailable, spilling batch");
+        self.counters.spilled.fetch_add((batch.len() - refilled) as u64, Ordering::Relaxed);
+        for req in batch.drain(..).rev() {
+            self.spill.push_front(req);
+        }
+        let excess = self.spill.len().saturating_sub(self.policy.spill_capacity);
+        if excess > 0 {
+            self.spill.truncate(self.policy.spill_capacity);
+            self.counters.dropped_spill_full.fetch_add(excess as u64, Ordering::Relaxed);
+            warn!(dropped = excess, "Spill buffer full, dropping newest events");
+        }
+        self.update_spill_len();
+    }
+
+    async fn execute(&self, pipe: &redis::Pipeline) -> Result<(), WriteError> {
+        let Some(mut conn) = self.redis.read().await.clone() else {
+            return Err(WriteError::Transient("not connected".to_string()));
+        };
+        match tokio::time::timeout(self.policy.pipeline_timeout, pipe.query_async::<()>(&mut conn)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e))
+                if e.is_io_error()
+                    || e.is_timeout()
+                    || e.is_connection_dropped()
+                    || e.is_connection_refusal() =>
+            {
+                Err(WriteError::Transient(e.to_string()))
+            }
+            Ok(Err(e)) => Err(WriteError::Rejected(e)),
+            Err(_) => Err(WriteError::Transient("timeout".to_string())),
+        }
+    }
+
+    /// XADD or compact for every request and refresh the TTL of the streams written to
+    fn pipeline(&self, batch: &[StoreRequest]) -> redis::Pipeline {
+        let mut pipe = redis::pipe();
+
+        // Collect unique channel keys to set TTL
+        let mut keys_to_expire: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+        for req in batch {
+            let key = RedisStorage::stream_key(&req.channel_id);
+            keys_to_expire.insert(key.clone());
+
+            if req.op == WriteOp::Compact {
+                pipe.cmd("EVAL")
+                    .arg(COMPACT_SCRIPT)
+                    .arg(2)
+                    .arg(&key)
+                    .arg(RedisStorage::snapshot_key(&req.channel_id))
+                    .arg(&req.stream_id)
+                    .arg(self.ttl_seconds)
+                    .ignore();
+                continue;
+            }
+
+            // XADD command
+            pipe.cmd("XADD")
+                .arg(&key)
+                .arg("MAXLEN")
+                .arg("~")
+                .arg(self.max_per_channel)
+                .arg(&req.stream_id)
+                .arg("event_type")
+                .arg(&req.event_type)
+                .arg("data")
+                .arg(&req.data);
+
+            if let Some(ref id) = req.id {
+                pipe.arg("id").arg(id);
+            }
+            if let Some(ref expires_at) = req.expires_at {
+                pipe.arg("expires_at").arg(expires_at);
+            }
+
+            pipe.ignore();
+        }
+
+        // Set TTL for all affected keys (refresh on each write)
+        for key in keys_to_expire {
+            pipe.cmd("EXPIRE")
+                .arg(&key)
+                .arg(self.ttl_seconds)
+                .ignore();
+        }
+        pipe
+    }
+}
+
+enum WriteError {
+    /// Redis may accept the batch later
+    Transient(String),
+    /// Redis refused the batch; retrying won't help
+    Rejected(redis::RedisError),
+}
+
 #[async_trait]
 impl MessageStorage for RedisStorage {
     fn generate_id(&self) -> String {
-        let ts = chrono::Utc::now().timestamp_millis();
-        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
-        format!("{}-{}", ts, seq)
+        self.ids.next_id()
     }
 
     async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
         // Send to batch processor (non-blocking)
         let req = StoreRequest {
+            op: WriteOp::Add,
             channel_id: channel_id.to_string(),
             stream_id: stream_id.to_string(),
             event_type: event.event_type.clone(),
             data: event.data.to_string(),
             id: event.id.clone(),
+            expires_at: event.expires_at.map(|at| at.to_rfc3339()),
         };
+        // Cached as a replay would read it back
+        let cached = self.replay_cache.as_ref().map(|cache| {
+            let event = SseEvent {
+                event_type: req.event_type.clone(),
+                data: EventData::Raw(req.data.clone()),
+                id: req.id.clone(),
+                stream_id: Some(req.stream_id.clone()),
+                retry: None,
+                expires_at: event.expires_at,
+                priority: Default::default(),
+            };
+            (cache, event)
+        });
+
+        if self.enqueue(event.priority.lane(), req) {
+            if let Some((cache, event)) = cached {
+                cache.append(channel_id, event);
+            }
+        }
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        let conn = self.redis.read().await;
+        let mut conn = conn.as_ref()?.clone();
 
-        // try_send to avoid blocking, drop if channel is full
-        if let Err(e) = self.store_tx.try_send(req) {
-            tracing::debug!(error = %e, "Store channel full, dropping message");
+        match redis::cmd("HGETALL")
+            .arg(Self::snapshot_key(channel_id))
+            .query_async::<HashMap<String, redis::Value>>(&mut conn)
+            .await
+        {
+            Ok(map) => Self::parse_snapshot(map),
+            Err(e) => {
+                warn!(error = %e, "Failed to get snapshot");
+                None
+            }
         }
     }
 
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        // The lowest priority queue is drained last, so the event being
+        // compacted to is written first whatever its priority
+        self.enqueue(
+            2,
+            StoreRequest {
+                op: WriteOp::Compact,
+                channel_id: channel_id.to_string(),
+                stream_id: up_to_id.to_string(),
+                event_type: String::new(),
+                data: String::new(),
+                id: None,
+                expires_at: None,
+            },
+        );
+    }
+
     async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
         let after_id = match after_id {
             Some(id) => id,
@@ -294,16 +669,27 @@ impl MessageStorage for RedisStorage {
 
         // Validate Redis Stream ID format: "timestamp-sequence" (e.g., "1234567890123-0")
         // If the ID is not in this format (e.g., UUID), we can't use it for XRANGE
-        if !Self::is_valid_stream_id(after_id) {
+        let Some(after) = parse_stream_id(after_id) else {
             warn!(
                 id = %after_id,
                 "Invalid Redis Stream ID format, skipping replay"
             );
             return vec![];
-        }
+        };
+
+        let load = match &self.replay_cache {
+            Some(cache) => match cache.get(channel_id, after) {
+                Ok(events) => return events,
+                Err(token) => token.map(|token| (cache, token)),
+            },
+            None => None,
+        };
 
         let conn = self.redis.read().await;
         let Some(ref manager) = *conn else {
+            if let Some((cache, token)) = load {
+                cache.finish_load(channel_id, token, after, None);
+            }
             return vec![];
         };
 
@@ -311,23 +697,298 @@ impl MessageStorage for RedisStorage {
         let key = Self::stream_key(channel_id);
         let start = format!("({}", after_id);
 
-        match redis::cmd("XRANGE")
+        match redis::pipe()
+            .cmd("HGETALL")
+            .arg(Self::snapshot_key(channel_id))
+            .cmd("XRANGE")
             .arg(&key)
             .arg(&start)
             .arg("+")
             .arg("COUNT")
             .arg(self.max_per_channel)
-            .query_async::<StreamRangeReply>(&mut conn)
+            .query_async::<(HashMap<String, redis::Value>, StreamRangeReply)>(&mut conn)
             .await
         {
-            Ok(reply) => Self::parse_stream_entries(reply.ids),
+            Ok((snapshot, reply)) => {
+                // A full page may not reach the end of the stream
+                let complete = reply.ids.len() < self.max_per_channel;
+                let mut events = Self::parse_stream_entries(reply.ids);
+                // Cursors from before the snapshot replay it first
+                let snapshot = Self::parse_snapshot(snapshot).filter(|snapshot| {
+                    snapshot
+                        .stream_id
+                        .as_deref()
+                        .and_then(parse_stream_id)
+                        .is_some_and(|id| id > after)
+                });
+                if let Some(snapshot) = snapshot {
+                    events.insert(0, snapshot);
+                }
+                if let Some((cache, token)) = load {
+                    cache.finish_load(channel_id, token, after, complete.then_some(&events[..]));
+                }
+                events
+            }
             Err(e) => {
                 warn!(error = %e, "Failed to get messages");
+                if let Some((cache, token)) = load {
+                    cache.finish_load(channel_id, token, after, None);
+                }
                 vec![]
             }
         }
     }
 
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        let conn = self.redis.read().await;
+        let mut conn = conn.as_ref()?.clone();
+
+        match redis::cmd("XREVRANGE")
+            .arg(Self::stream_key(channel_id))
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            .arg(1)
+            .query_async::<StreamRangeReply>(&mut conn)
+            .await
+        {
+            Ok(reply) => reply.ids.into_iter().next().map(|entry| entry.id),
+            Err(e) => {
+                warn!(error = %e, "Failed to get latest stream ID");
+                None
+            }
+        }
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        let conn = self.redis.read().await;
+        let Some(ref manager) = *conn else {
+            return vec![];
+        };
+        let mut conn = manager.clone();
+
+        match redis::cmd("XREVRANGE")
+            .arg(Self::stream_key(channel_id))
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            // Redis rejects counts beyond i64
+            .arg(limit.min(i64::MAX as usize))
+            .query_async::<StreamRangeReply>(&mut conn)
+            .await
+        {
+            Ok(reply) => {
+                let mut events = Self::parse_stream_entries(reply.ids);
+                events.reverse();
+                events
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to list recent messages");
+                vec![]
+            }
+        }
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return;
+        };
+
+        let key = Self::cursor_key(channel_id);
+        // Cursors live as long as the stream they point into
+        let result: Result<(), _> = redis::pipe()
+            .hset(&key, consumer_id, stream_id)
+            .ignore()
+            .expire(&key, self.ttl_seconds as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to save consumer cursor");
+        }
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        let conn = self.redis.read().await;
+        let mut conn = conn.as_ref()?.clone();
+
+        match redis::cmd("HGET")
+            .arg(Self::cursor_key(channel_id))
+            .arg(consumer_id)
+            .query_async::<Option<String>>(&mut conn)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                warn!(error = %e, "Failed to get consumer cursor");
+                None
+            }
+        }
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return;
+        };
+
+        let result: Result<(), _> = redis::cmd("SET")
+            .arg(Self::session_key(token))
+            .arg(session)
+            .arg("PX")
+            .arg(ttl.as_millis().max(1) as u64)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to save resume session");
+        }
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        let conn = self.redis.read().await;
+        let mut conn = conn.as_ref()?.clone();
+
+        match redis::cmd("GET")
+            .arg(Self::session_key(token))
+            .query_async::<Option<String>>(&mut conn)
+            .await
+        {
+            Ok(session) => session,
+            Err(e) => {
+                warn!(error = %e, "Failed to load resume session");
+                None
+            }
+        }
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return;
+        };
+
+        let result: Result<u64, _> = redis::cmd("EVAL")
+            .arg(USAGE_SCRIPT)
+            .arg(2)
+            .arg(Self::usage_key(channel_id, delta.date))
+            .arg(Self::usage_subscribers_key(channel_id, delta.date))
+            .arg(delta.published)
+            .arg(delta.delivered)
+            .arg(delta.peak_subscribers)
+            .arg(USAGE_TTL_SECONDS)
+            .arg(USAGE_SUBSCRIBERS_TTL_SECONDS)
+            .arg(&delta.subscribers)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to record channel usage");
+        }
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Vec<DailyUsage> {
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return Vec::new();
+        };
+
+        let dates: Vec<chrono::NaiveDate> = from.iter_days().take_while(|date| *date <= to).collect();
+        let mut pipe = redis::pipe();
+        for date in &dates {
+            pipe.hgetall(Self::usage_key(channel_id, *date));
+        }
+        let days: Vec<HashMap<String, u64>> = match pipe.query_async(&mut conn).await {
+            Ok(days) => days,
+            Err(e) => {
+                warn!(error = %e, "Failed to load channel usage");
+                return Vec::new();
+            }
+        };
+        dates
+            .into_iter()
+            .zip(days)
+            .filter(|(_, fields)| !fields.is_empty())
+            .map(|(date, fields)| {
+                let field = |name: &str| fields.get(name).copied().unwrap_or(0);
+                DailyUsage {
+                    date,
+                    published: field("published"),
+                    delivered: field("delivered"),
+                    unique_subscribers: field("unique_subscribers"),
+                    peak_subscribers: field("peak_subscribers"),
+                }
+            })
+            .collect()
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        if let Some(cache) = &self.replay_cache {
+            cache.invalidate(channel_id);
+        }
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return 0;
+        };
+
+        let key = Self::stream_key(channel_id);
+        match redis::pipe()
+            .cmd("XLEN")
+            .arg(&key)
+            .del(&key)
+            .ignore()
+            .del(Self::snapshot_key(channel_id))
+            .ignore()
+            .del(Self::cursor_key(channel_id))
+            .ignore()
+            .query_async::<(usize,)>(&mut conn)
+            .await
+        {
+            Ok((purged,)) => purged,
+            Err(e) => {
+                warn!(error = %e, "Failed to purge channel");
+                0
+            }
+        }
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        let Some(cutoff) = before.stream_id() else {
+            warn!(before = ?before, "Invalid Redis Stream ID format, skipping purge");
+            return 0;
+        };
+        if let Some(cache) = &self.replay_cache {
+            cache.invalidate(channel_id);
+        }
+        let conn = self.redis.read().await;
+        let Some(mut conn) = conn.as_ref().cloned() else {
+            return 0;
+        };
+
+        let snapshot_key = Self::snapshot_key(channel_id);
+        let result = redis::pipe()
+            .cmd("XTRIM")
+            .arg(Self::stream_key(channel_id))
+            .arg("MINID")
+            .arg(&cutoff)
+            .hget(&snapshot_key, "stream_id")
+            .query_async::<(usize, Option<String>)>(&mut conn)
+            .await;
+        match result {
+            Ok((purged, snapshot)) => {
+                if snapshot.is_some_and(|id| before.covers(&id)) {
+                    if let Err(e) = redis::cmd("DEL").arg(&snapshot_key).query_async::<()>(&mut conn).await {
+                        warn!(error = %e, "Failed to purge snapshot");
+                    }
+                }
+                purged
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to purge channel");
+                0
+            }
+        }
+    }
+
     async fn is_available(&self) -> bool {
         self.redis.read().await.is_some()
     }
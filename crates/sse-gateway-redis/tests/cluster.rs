@@ -0,0 +1,151 @@
+//! Failover of the Redis cluster coordinator
+//!
+//! Requires a running Redis. Run with:
+//! `REDIS_URL=redis://localhost:6379 cargo test -p sse-gateway-redis -- --ignored`
+
+use std::time::Duration;
+
+use sse_gateway::{
+    CancellationToken, ChannelPause, ClusterCoordinator, ConnectionManager, InstanceInfo, PausePolicy,
+};
+use sse_gateway_redis::RedisCluster;
+
+fn redis_url() -> String {
+    std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string())
+}
+
+/// Start an instance under `prefix`, once the coordinator has connected
+async fn join(prefix: &str, instance_id: &str, cancel: &CancellationToken) -> RedisCluster {
+    let cluster = RedisCluster::new(redis_url())
+        .prefix(prefix)
+        .heartbeat_interval(Duration::from_secs(1));
+    let coordinator = cluster.clone();
+    let manager = ConnectionManager::new(instance_id);
+    let cancel = cancel.clone();
+    tokio::spawn(async move { coordinator.start(manager, cancel).await });
+    while !registered(&cluster, instance_id).await {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    cluster
+}
+
+async fn registered(cluster: &RedisCluster, instance_id: &str) -> bool {
+    find(cluster, instance_id).await.is_some()
+}
+
+async fn find(cluster: &RedisCluster, instance_id: &str) -> Option<InstanceInfo> {
+    cluster
+        .instances()
+        .await
+        .ok()?
+        .into_iter()
+        .find(|i| i.id == instance_id)
+}
+
+#[tokio::test]
+#[ignore = "requires Redis"]
+async fn evict_is_conditional_on_last_seen() {
+    let prefix = format!("failover-test-{}", chrono::Utc::now().timestamp_micros());
+    let cancel = CancellationToken::new();
+    let a = join(&prefix, "a", &cancel).await;
+    let b = join(&prefix, "b", &cancel).await;
+    a.claim_channel("a", "room").await;
+    b.claim_channel("b", "room").await;
+
+    // b's view of a is older than a's last heartbeat: a is alive after all
+    let mut seen = find(&b, "a").await.unwrap();
+    seen.last_seen -= 1;
+    assert_eq!(b.evict(&seen).await.unwrap(), None);
+    assert!(registered(&b, "a").await);
+
+    // Two survivors racing to evict the same silent instance: one wins
+    let seen = find(&b, "a").await.unwrap();
+    let (first, second) = tokio::join!(a.evict(&seen), b.evict(&seen));
+    let mut results = [first.unwrap(), second.unwrap()];
+    results.sort();
+    assert_eq!(results, [None, Some(vec!["room".to_string()])]);
+    assert!(!registered(&b, "a").await);
+    assert_eq!(b.channel_owners("room").await.unwrap(), ["b"]);
+
+    // Still running, a rejoins at its next heartbeat
+    for _ in 0..100 {
+        if registered(&b, "a").await {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(registered(&b, "a").await);
+
+    cancel.cancel();
+}
+
+#[tokio::test]
+#[ignore = "requires Redis"]
+async fn channel_pauses_are_shared() {
+    let prefix = format!("pause-test-{}", chrono::Utc::now().timestamp_micros());
+    let cancel = CancellationToken::new();
+    let a = join(&prefix, "a", &cancel).await;
+    let b = join(&prefix, "b", &cancel).await;
+
+    let pause = ChannelPause::new(PausePolicy::Drop).with_reason("bad data");
+    a.set_channel_pause("orders", Some(&pause)).await.unwrap();
+    assert_eq!(b.channel_pauses().await.unwrap()["orders"], pause);
+
+    b.set_channel_pause("orders", None).await.unwrap();
+    assert!(a.channel_pauses().await.unwrap().is_empty());
+
+    cancel.cancel();
+}
+
+#[tokio::test]
+#[ignore = "requires Redis"]
+async fn fanout_delivers_peer_messages_once() {
+    use sse_gateway::{ClusterFanout, ClusterMessage, SseEvent};
+    use sse_gateway_redis::RedisFanout;
+
+    let channel = format!("fanout-test-{}", chrono::Utc::now().timestamp_micros());
+    let cancel = CancellationToken::new();
+    let a = RedisFanout::new(redis_url()).channel(&channel);
+    let b = RedisFanout::new(redis_url()).channel(&channel);
+    let manager_b = ConnectionManager::new("b");
+    tokio::spawn({
+        let (a, cancel) = (a.clone(), cancel.clone());
+        async move { a.start(ConnectionManager::new("a"), cancel).await }
+    });
+    tokio::spawn({
+        let (b, manager_b, cancel) = (b.clone(), manager_b.clone(), cancel.clone());
+        async move { b.start(manager_b, cancel).await }
+    });
+    let (_connection, mut rx) = manager_b.register("room".to_string(), None, None);
+
+    let message = |stream_id: usize| {
+        let mut event = SseEvent::raw("message", "hello");
+        event.stream_id = Some(format!("{}-0", stream_id));
+        ClusterMessage {
+            channel_id: Some("room".to_string()),
+            event,
+        }
+    };
+    // Probe until both have connected and b has subscribed
+    let mut probe = 0;
+    let received = loop {
+        probe += 1;
+        if a.publish(&message(probe)).await.is_ok() {
+            if let Ok(Some(event)) = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
+                break event;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    };
+    let message = message(probe);
+    assert_eq!(received.stream_id, message.event.stream_id);
+
+    // The same stream ID again, and b's own message, are not delivered
+    a.publish(&message).await.unwrap();
+    while b.publish(&message).await.is_err() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(tokio::time::timeout(Duration::from_millis(300), rx.recv()).await.is_err());
+
+    cancel.cancel();
+}
@@ -0,0 +1,78 @@
+//! Conformance battery for the Redis adapters
+//!
+//! Requires a running Redis. Run with:
+//! `REDIS_URL=redis://localhost:6379 cargo test -p sse-gateway-redis -- --ignored`
+
+use sse_gateway::testkit::SourceFixture;
+use sse_gateway_redis::{RedisPubSubSource, RedisStorage};
+
+fn redis_url() -> String {
+    std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string())
+}
+
+sse_gateway::storage_conformance!(#[ignore = "requires Redis"] redis_storage, async {
+    let storage = RedisStorage::new();
+    storage.connect(&redis_url()).await.expect("Redis connection failed");
+    storage
+});
+
+sse_gateway::storage_conformance!(#[ignore = "requires Redis"] redis_storage_cached, async {
+    let storage = RedisStorage::new().replay_cache(100, std::time::Duration::from_secs(5));
+    storage.connect(&redis_url()).await.expect("Redis connection failed");
+    storage
+});
+
+sse_gateway::source_conformance!(#[ignore = "requires Redis"] redis_pubsub_source, async {
+    let url = redis_url();
+    let source = RedisPubSubSource::new(url.clone(), vec!["conformance".to_string()]);
+    let client = redis::Client::open(url).expect("Invalid Redis URL");
+    SourceFixture::new(source, move |msg| {
+        let client = client.clone();
+        async move {
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            redis::cmd("PUBLISH")
+                .arg(msg.channel_id.as_deref().unwrap_or_default())
+                .arg(msg.data)
+                .query_async::<()>(&mut conn)
+                .await?;
+            Ok(())
+        }
+    })
+});
+
+#[tokio::test]
+#[ignore = "requires Redis"]
+async fn redis_storage_compaction() {
+    use sse_gateway::{MessageStorage, SseEvent};
+
+    let storage = RedisStorage::new();
+    storage.connect(&redis_url()).await.expect("Redis connection failed");
+    let channel = format!("compaction-{}", storage.generate_id());
+
+    let mut ids = Vec::new();
+    for (n, event_type) in ["delta", "state", "delta"].into_iter().enumerate() {
+        let id = storage.generate_id();
+        storage.store(&channel, &id, &SseEvent::raw(event_type, n.to_string())).await;
+        ids.push(id);
+        if event_type == "state" {
+            storage.compact(&channel, &ids[n]).await;
+        }
+    }
+
+    let compacted = sse_gateway::testkit::eventually(|| async {
+        storage.snapshot(&channel).await.is_some()
+            && storage.get_messages_after(&channel, Some(&ids[0])).await.len() == 2
+    })
+    .await;
+    assert!(compacted, "expected the channel to be compacted");
+
+    let snapshot = storage.snapshot(&channel).await.unwrap();
+    assert_eq!(snapshot.stream_id.as_deref(), Some(ids[1].as_str()));
+    let data: Vec<String> = storage
+        .get_messages_after(&channel, Some(&ids[0]))
+        .await
+        .iter()
+        .map(|e| e.data.to_string())
+        .collect();
+    assert_eq!(data, ["1", "2"]);
+}
@@ -0,0 +1,79 @@
+//! Write path and replay cache of the Redis storage, exercised without a Redis server
+
+use std::time::Duration;
+
+use sse_gateway::{MessageStorage, SseEvent};
+use sse_gateway_redis::{RedisStorage, ReplayCacheStats, WritePolicy};
+
+#[tokio::test]
+async fn unavailable_redis_spills_writes_up_to_capacity() {
+    let storage = RedisStorage::with_write_policy(
+        100,
+        60,
+        WritePolicy {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            spill_capacity: 5,
+            ..Default::default()
+        },
+    );
+
+    // Never connected, so every batch fails as if Redis were down
+    for n in 0..8 {
+        let event = SseEvent::raw("tick", n.to_string());
+        storage.store("room", &storage.generate_id(), &event).await;
+    }
+    let mut stats = storage.write_stats();
+    for _ in 0..100 {
+        if stats.spill_len == 5 && stats.dropped_spill_full == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stats = storage.write_stats();
+    }
+
+    assert_eq!(stats.written, 0);
+    assert_eq!(stats.spill_len, 5);
+    assert_eq!(stats.dropped_spill_full, 3);
+    assert!(stats.retries >= 2);
+    assert_eq!(stats.dropped_queue_full, 0);
+    assert_eq!(stats.rejected, 0);
+
+    // Spilled writes stay buffered across further failed attempts
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let later = storage.write_stats();
+    assert_eq!(later.spill_len, 5);
+    assert_eq!(later.spilled, stats.spilled);
+    assert!(later.retries > stats.retries);
+}
+
+#[tokio::test]
+async fn replay_cache_serves_recent_writes() {
+    let storage = RedisStorage::with_max_messages(3).replay_cache(1, Duration::from_secs(60));
+
+    let mut ids = Vec::new();
+    for n in 0..5 {
+        let id = storage.generate_id();
+        storage.store("room", &id, &SseEvent::raw("tick", n.to_string())).await;
+        ids.push(id);
+    }
+
+    // Served from memory even though Redis was never reached
+    let events = storage.get_messages_after("room", Some(&ids[2])).await;
+    let data: Vec<String> = events.iter().map(|e| e.data.to_string()).collect();
+    assert_eq!(data, vec!["3", "4"]);
+    assert_eq!(events[0].stream_id.as_deref(), Some(ids[3].as_str()));
+
+    // Older than the newest three events: only Redis has those
+    assert!(storage.get_messages_after("room", Some(&ids[0])).await.is_empty());
+    assert_eq!(storage.get_messages_after("room", Some(&ids[4])).await.len(), 0);
+    assert_eq!(
+        storage.replay_cache_stats().unwrap(),
+        ReplayCacheStats { hits: 2, misses: 1, channels: 1 }
+    );
+
+    // Writing another channel evicts the least recently used one
+    storage.store("lobby", &storage.generate_id(), &SseEvent::raw("tick", "x")).await;
+    assert!(storage.get_messages_after("room", Some(&ids[3])).await.is_empty());
+    assert_eq!(storage.replay_cache_stats().unwrap().misses, 2);
+}
@@ -0,0 +1,227 @@
+//! Micro-benchmarks for dispatch, fan-out, serialization and replay
+//!
+//! Run with:
+//!   cargo bench -p sse-gateway
+//!
+//! These cover the in-process hot paths; `examples/benchmark.rs` measures a
+//! running gateway end to end over HTTP.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use sse_gateway::{ConnectionManager, MemoryStorage, MessageStorage, SseEvent};
+use tokio::runtime::Runtime;
+
+const CHANNEL: &str = "bench";
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// A manager with `subscribers` connections on [`CHANNEL`], each drained so
+/// sends never wait on a full buffer
+fn manager_with(rt: &Runtime, subscribers: usize) -> ConnectionManager {
+    let manager = ConnectionManager::new("bench");
+    let _guard = rt.enter();
+    for _ in 0..subscribers {
+        let (_conn, mut rx) = manager.register(CHANNEL.to_string(), None, None);
+        tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    }
+    manager
+}
+
+fn payload() -> serde_json::Value {
+    serde_json::json!({
+        "order_id": "ord_123456",
+        "status": "shipped",
+        "items": [{"sku": "A-1", "qty": 2}, {"sku": "B-7", "qty": 1}],
+        "total": 42.5,
+    })
+}
+
+fn fan_out(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("send_to_channel");
+    for subscribers in [1, 100, 10_000] {
+        let manager = manager_with(&rt, subscribers);
+        let event = SseEvent::new("update", payload());
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, _| {
+                b.to_async(&rt)
+                    .iter(|| manager.send_to_channel(CHANNEL, event.clone()));
+            },
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("broadcast");
+    for subscribers in [100, 10_000] {
+        let manager = manager_with(&rt, subscribers);
+        let event = SseEvent::new("update", payload());
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, _| {
+                b.to_async(&rt).iter(|| manager.broadcast(event.clone()));
+            },
+        );
+    }
+    group.finish();
+}
+
+/// `connections` subscribers spread over `channels` channels, on `shards`
+/// locks or the default count
+fn sharded_manager(
+    rt: &Runtime,
+    shards: Option<usize>,
+    channels: usize,
+    connections: usize,
+) -> ConnectionManager {
+    let mut manager = ConnectionManager::new("bench");
+    if let Some(shards) = shards {
+        manager = manager.with_shards(shards);
+    }
+    let _guard = rt.enter();
+    for i in 0..connections {
+        let channel = format!("channel-{}", i % channels);
+        let (_conn, mut rx) = manager.register(channel, None, None);
+        tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    }
+    manager
+}
+
+/// Concurrent sends to many channels, with one lock vs the default shards
+fn sharding(c: &mut Criterion) {
+    const CHANNELS: usize = 1000;
+    const SENDERS: usize = 8;
+    const SENDS: usize = 64;
+
+    let rt = runtime();
+    let mut group = c.benchmark_group("concurrent_send");
+    group.throughput(Throughput::Elements((SENDERS * SENDS) as u64));
+    for connections in [10_000, 100_000] {
+        for (label, shards) in [("1_shard", Some(1)), ("sharded", None)] {
+            let manager = sharded_manager(&rt, shards, CHANNELS, connections);
+            let event = SseEvent::new("update", payload());
+            group.bench_with_input(
+                BenchmarkId::new(label, connections),
+                &connections,
+                |b, _| {
+                    b.to_async(&rt).iter(|| async {
+                        let senders = (0..SENDERS).map(|sender| {
+                            let manager = manager.clone();
+                            let event = event.clone();
+                            tokio::spawn(async move {
+                                for i in 0..SENDS {
+                                    let channel =
+                                        format!("channel-{}", (sender * SENDS + i) % CHANNELS);
+                                    manager.send_to_channel(&channel, event.clone()).await;
+                                }
+                            })
+                        });
+                        for sender in senders.collect::<Vec<_>>() {
+                            sender.await.unwrap();
+                        }
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Subscribing to one busy channel while events are being sent to it
+fn subscribe(c: &mut Criterion) {
+    const SEND_EVERY: usize = 5_000;
+
+    let rt = runtime();
+    let mut group = c.benchmark_group("subscribe");
+    group.sample_size(10);
+    for subscribers in [10_000, 100_000] {
+        let event = SseEvent::message("tick");
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, &subscribers| {
+                b.iter_batched(
+                    || ConnectionManager::new("bench"),
+                    |manager| {
+                        let _guard = rt.enter();
+                        let mut receivers = Vec::with_capacity(subscribers);
+                        for i in 0..subscribers {
+                            let (_conn, rx) = manager.register(CHANNEL.to_string(), None, None);
+                            receivers.push(rx);
+                            if i % SEND_EVERY == 0 {
+                                rt.block_on(manager.send_to_channel(CHANNEL, event.clone()));
+                            }
+                        }
+                        (manager, receivers)
+                    },
+                    BatchSize::PerIteration,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event");
+    let json = SseEvent::new("update", payload()).with_stream_id("1700000000000-0");
+    let raw = SseEvent::raw("update", payload().to_string()).with_stream_id("1700000000000-0");
+    let encoded = serde_json::to_string(&json).unwrap();
+
+    group.bench_function("json", |b| {
+        b.iter(|| SseEvent::json("update", &payload()).unwrap())
+    });
+    group.bench_function("data_to_string/value", |b| b.iter(|| json.data.to_string()));
+    group.bench_function("data_to_string/raw", |b| b.iter(|| raw.data.to_string()));
+    group.bench_function("encode", |b| {
+        b.iter(|| serde_json::to_string(&json).unwrap())
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| serde_json::from_str::<SseEvent>(&encoded).unwrap())
+    });
+    group.finish();
+}
+
+fn storage(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("memory_storage");
+    let event = SseEvent::new("update", payload());
+
+    group.bench_function("store", |b| {
+        let storage = MemoryStorage::default();
+        b.to_async(&rt).iter(|| async {
+            let id = storage.generate_id();
+            storage.store(CHANNEL, &id, &event).await;
+        });
+    });
+
+    for backlog in [10, 1000] {
+        // Replay reads don't change the stream, so one backlog serves every iteration
+        let storage = MemoryStorage::new(backlog + 1);
+        let first = storage.generate_id();
+        rt.block_on(async {
+            storage.store(CHANNEL, &first, &event).await;
+            for _ in 0..backlog {
+                storage.store(CHANNEL, &storage.generate_id(), &event).await;
+            }
+        });
+        group.throughput(Throughput::Elements(backlog as u64));
+        group.bench_with_input(BenchmarkId::new("replay", backlog), &backlog, |b, _| {
+            b.to_async(&rt)
+                .iter(|| storage.get_messages_after(CHANNEL, Some(&first)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fan_out, sharding, subscribe, serialization, storage);
+criterion_main!(benches);
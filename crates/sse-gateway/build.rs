@@ -0,0 +1,14 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/gateway.proto");
+        // Use the vendored protoc unless one is provided explicitly
+        if std::env::var_os("PROTOC").is_none() {
+            std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        }
+        tonic_prost_build::configure()
+            .build_transport(false)
+            .compile_protos(&["proto/gateway.proto"], &["proto"])?;
+    }
+    Ok(())
+}
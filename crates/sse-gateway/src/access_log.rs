@@ -0,0 +1,141 @@
+//! Per-connection access log
+//!
+//! With an access log configured, the gateway writes one [`AccessLogRecord`]
+//! when each subscriber connection closes: who was connected, for how long,
+//! how much was delivered and why it ended.
+//!
+//! ```rust,ignore
+//! use sse_gateway::access_log::{AccessLogRecord, FileAccessLog, StdoutAccessLog};
+//!
+//! // One JSON object per line on stdout
+//! Gateway::builder().access_log(StdoutAccessLog)
+//!
+//! // Or appended to a file
+//! Gateway::builder().access_log(FileAccessLog::open("/var/log/sse/access.log")?)
+//!
+//! // Or anywhere else
+//! Gateway::builder().access_log(move |record: &AccessLogRecord| {
+//!     let _ = records_tx.send(record.clone());
+//! })
+//! ```
+//!
+//! Records are written from the task that closes the connection, so sinks
+//! should be quick; hand records to a channel for anything slow.
+
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::connection::CloseReason;
+#[cfg(feature = "server")]
+use crate::connection::{ConnectionCounters, ConnectionMetadata};
+
+/// One closed connection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccessLogRecord {
+    /// When the connection closed
+    pub timestamp: DateTime<Utc>,
+    pub channel_id: String,
+    pub connection_id: String,
+    pub instance_id: String,
+    /// Authenticated identity, if an identity extractor is configured
+    pub identity: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub connected_at: DateTime<Utc>,
+    /// Time between connecting and closing
+    pub duration_ms: u64,
+    /// Events written to the client, including replayed ones
+    pub events_sent: u64,
+    /// Events not queued because the client's send buffer was full
+    pub events_dropped: u64,
+    /// Bytes of events and comments written, before compression
+    pub bytes_written: u64,
+    pub close_reason: CloseReason,
+}
+
+impl AccessLogRecord {
+    #[cfg(feature = "server")]
+    pub(crate) fn new(
+        connection_id: &str,
+        channel_id: &str,
+        metadata: &ConnectionMetadata,
+        counters: &ConnectionCounters,
+        close_reason: CloseReason,
+    ) -> Self {
+        let timestamp = Utc::now();
+        Self {
+            timestamp,
+            channel_id: channel_id.to_string(),
+            connection_id: connection_id.to_string(),
+            instance_id: metadata.instance_id.clone(),
+            identity: metadata.identity.clone(),
+            client_ip: metadata.client_ip.clone(),
+            user_agent: metadata.user_agent.clone(),
+            connected_at: metadata.connected_at,
+            duration_ms: (timestamp - metadata.connected_at).num_milliseconds().max(0) as u64,
+            events_sent: counters.events_sent(),
+            events_dropped: counters.events_dropped(),
+            bytes_written: counters.bytes_written(),
+            close_reason,
+        }
+    }
+}
+
+/// Destination of access log records
+pub trait AccessLogSink: Send + Sync + 'static {
+    fn write(&self, record: &AccessLogRecord);
+}
+
+impl<F> AccessLogSink for F
+where
+    F: Fn(&AccessLogRecord) + Send + Sync + 'static,
+{
+    fn write(&self, record: &AccessLogRecord) {
+        self(record)
+    }
+}
+
+/// Writes each record as a line of JSON to stdout
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutAccessLog;
+
+impl AccessLogSink for StdoutAccessLog {
+    fn write(&self, record: &AccessLogRecord) {
+        if let Ok(line) = serde_json::to_string(record) {
+            println!("{line}");
+        }
+    }
+}
+
+/// Appends each record as a line of JSON to a file
+#[derive(Debug)]
+pub struct FileAccessLog {
+    file: Mutex<LineWriter<File>>,
+}
+
+impl FileAccessLog {
+    /// Open `path` for appending, creating it if needed
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(LineWriter::new(file)),
+        })
+    }
+}
+
+impl AccessLogSink for FileAccessLog {
+    fn write(&self, record: &AccessLogRecord) {
+        let Ok(line) = serde_json::to_string(record) else {
+            return;
+        };
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{line}") {
+            tracing::warn!(error = %e, "Failed to write access log record");
+        }
+    }
+}
@@ -2,9 +2,16 @@
 //!
 //! Provides a simple callback-based authentication.
 
-use axum::http::{HeaderMap, Method, StatusCode, Uri};
+#[cfg(feature = "server")]
 use axum::response::{IntoResponse, Response};
+use http::{HeaderMap, Method, Uri};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use http::StatusCode;
+use std::collections::HashMap;
+#[cfg(feature = "server")]
 use std::future::Future;
+#[cfg(feature = "server")]
 use std::pin::Pin;
 use std::sync::Arc;
 
@@ -52,9 +59,7 @@ impl AuthRequest {
     pub fn query_param(&self, name: &str) -> Option<&str> {
         self.uri.query().and_then(|query| {
             query.split('&').find_map(|pair| {
-                let mut parts = pair.splitn(2, '=');
-                let key = parts.next()?;
-                let value = parts.next()?;
+                let (key, value) = pair.split_once('=')?;
                 if key == name { Some(value) } else { None }
             })
         })
@@ -62,16 +67,161 @@ impl AuthRequest {
 }
 
 /// Auth callback result - None means allowed, Some(Response) means denied
+#[cfg(feature = "server")]
 pub type AuthResponse = Option<Response>;
 
 /// Type alias for the async auth callback function
 ///
 /// Return `None` to allow the connection, or `Some(Response)` to deny with custom response.
+#[cfg(feature = "server")]
 pub type AuthFn = Arc<
     dyn Fn(AuthRequest) -> Pin<Box<dyn Future<Output = AuthResponse> + Send>> + Send + Sync,
 >;
 
+/// Extracts the caller's identity (e.g. a user ID) from an allowed request
+///
+/// The identity is recorded in the connection's metadata and reported by
+/// lifecycle webhooks and presence.
+pub type IdentityFn = Arc<dyn Fn(&AuthRequest) -> Option<String> + Send + Sync>;
+
+/// Derives custom attributes (e.g. tenant, device type) from an allowed request
+///
+/// The attributes are recorded in the connection's metadata and can be used
+/// to target events (see `ConnectionManager::send_to_attr`).
+pub type AttributesFn = Arc<dyn Fn(&AuthRequest) -> HashMap<String, String> + Send + Sync>;
+
+/// Decides which event types an allowed request may receive
+///
+/// Return `None` to allow all event types. The result is recorded in the
+/// connection's metadata and enforced on every event sent to it, replayed
+/// ones included.
+pub type EventTypesFn = Arc<dyn Fn(&AuthRequest) -> Option<EventTypeFilter> + Send + Sync>;
+
+/// Event types a connection may receive
+///
+/// Patterns are exact event types or prefixes ending in `*`, like channel
+/// config keys: `order_*` allows `order_created` and `order_shipped`. The
+/// gateway's own `migrate` event is always allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EventTypeFilter {
+    patterns: Vec<String>,
+}
+
+impl EventTypeFilter {
+    /// Allow event types matching any of `patterns`
+    pub fn new<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        Self {
+            patterns: patterns.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Allow the event types granted by `scopes`, per `grants`
+    ///
+    /// Scopes without a grant allow nothing.
+    ///
+    /// ```rust
+    /// use sse_gateway::auth::EventTypeFilter;
+    ///
+    /// let grants = [("read:orders", &["order_*"][..]), ("read:users", &["user_*"][..])];
+    /// let filter = EventTypeFilter::from_scopes(["read:orders"], &grants);
+    /// assert!(filter.allows("order_created"));
+    /// assert!(!filter.allows("user_deleted"));
+    /// ```
+    pub fn from_scopes<I, S>(scopes: I, grants: &[(&str, &[&str])]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut patterns = Vec::new();
+        for scope in scopes {
+            for (_, granted) in grants.iter().filter(|(name, _)| *name == scope.as_ref()) {
+                patterns.extend(granted.iter().map(|pattern| pattern.to_string()));
+            }
+        }
+        Self { patterns }
+    }
+
+    /// The allowed patterns
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Whether `event_type` may be sent
+    pub fn allows(&self, event_type: &str) -> bool {
+        // Clients must hear where to reconnect, whatever else they may read
+        event_type == MIGRATE_EVENT
+            || self.patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => event_type.starts_with(prefix),
+                None => pattern == event_type,
+            })
+    }
+}
+
+/// Same as `crate::MIGRATE_EVENT`, which is only built with the server feature
+const MIGRATE_EVENT: &str = "migrate";
+
+/// Channel derived from the caller's identity, e.g. `user:{identity}`
+///
+/// Subscribers can only reach their own channel: the requested channel may
+/// be left out, and naming any other channel is refused (or ignored, with
+/// [`ignore_requested`](Self::ignore_requested)).
+///
+/// ```rust
+/// use sse_gateway::auth::IdentityChannel;
+///
+/// let channels = IdentityChannel::new("user:{identity}");
+/// assert_eq!(channels.channel_for("42"), "user:42");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityChannel {
+    template: String,
+    ignore_requested: bool,
+}
+
+impl IdentityChannel {
+    /// Channel named by `template`, with `{identity}` replaced by the caller's identity
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            ignore_requested: false,
+        }
+    }
+
+    /// Subscribe callers to their own channel whatever channel they name
+    /// (default: false, naming another channel gets `403 Forbidden`)
+    ///
+    /// For clients that still send a channel of an older scheme.
+    pub fn ignore_requested(mut self, ignore: bool) -> Self {
+        self.ignore_requested = ignore;
+        self
+    }
+
+    /// The channel of `identity`
+    pub fn channel_for(&self, identity: &str) -> String {
+        self.template.replace("{identity}", identity)
+    }
+
+    /// The channel to use for a request for `requested` (empty if none) by `identity`
+    #[cfg(feature = "server")]
+    pub(crate) fn resolve(&self, identity: Option<&str>, requested: &str) -> Result<String, (StatusCode, &'static str)> {
+        let Some(identity) = identity else {
+            return Err((StatusCode::UNAUTHORIZED, "No identity for the caller"));
+        };
+        let channel_id = self.channel_for(identity);
+        if !requested.is_empty() && requested != channel_id && !self.ignore_requested {
+            return Err((StatusCode::FORBIDDEN, "Channel belongs to another identity"));
+        }
+        Ok(channel_id)
+    }
+}
+
 /// Helper to create an auth callback from a closure
+#[cfg(feature = "server")]
 pub fn auth_fn<F, Fut>(f: F) -> AuthFn
 where
     F: Fn(AuthRequest) -> Fut + Send + Sync + 'static,
@@ -81,11 +231,13 @@ where
 }
 
 /// Helper to create a simple error response
+#[cfg(feature = "server")]
 pub fn deny(status: StatusCode, message: impl Into<String>) -> Response {
     (status, message.into()).into_response()
 }
 
 /// Helper to create a JSON error response
+#[cfg(feature = "server")]
 pub fn deny_json(status: StatusCode, body: impl serde::Serialize) -> Response {
     (status, axum::Json(body)).into_response()
 }
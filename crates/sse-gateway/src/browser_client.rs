@@ -0,0 +1,90 @@
+//! Browser client script
+//!
+//! `GET /sse/client.js` serves a small `SseGatewayClient` class wrapping
+//! `EventSource` with this gateway's conventions: it reconnects with
+//! exponential backoff from the last event ID, resumes sessions with the
+//! token from the `connected` event, refreshes signed URLs before each
+//! connect, follows `migrate` hints and tracks replay markers. The SSE path,
+//! channel parameter and replay marker names are filled in from the builder.
+//!
+//! ```html
+//! <script src="https://gateway.example/sse/client.js"></script>
+//! <script>
+//!   const client = new SseGatewayClient({
+//!     baseUrl: 'https://gateway.example',
+//!     channel: 'user:42',
+//!     signUrl: url => fetch('/sign?url=' + encodeURIComponent(url)).then(r => r.text()),
+//!   });
+//!   client.on('order.created', order => render(order));
+//! </script>
+//! ```
+
+use std::sync::Arc;
+
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+/// Path the client script is served at
+pub const CLIENT_SCRIPT_PATH: &str = "/sse/client.js";
+
+const TEMPLATE: &str = include_str!("browser_client/client.js");
+
+/// Placeholder in the template replaced with the gateway settings
+const CONFIG_PLACEHOLDER: &str = "__GATEWAY_CONFIG__";
+
+/// Gateway settings the script needs
+#[derive(Serialize)]
+struct ClientConfig<'a> {
+    version: &'a str,
+    path: &'a str,
+    channel_param: &'a str,
+    replay_start: &'a str,
+    replay_end: &'a str,
+}
+
+/// The client script, rendered for one gateway
+#[derive(Clone)]
+pub(crate) struct ClientScript {
+    script: Arc<str>,
+    /// Changes with the gateway version and settings
+    etag: Arc<str>,
+}
+
+impl ClientScript {
+    pub(crate) fn new(path: &str, channel_param: &str, replay_start: &str, replay_end: &str) -> Self {
+        let config = ClientConfig {
+            version: env!("CARGO_PKG_VERSION"),
+            path,
+            channel_param,
+            replay_start,
+            replay_end,
+        };
+        let json = serde_json::to_string(&config).expect("client config serializes");
+        let script = TEMPLATE.replace(CONFIG_PLACEHOLDER, &json);
+        let etag = format!("\"{}-{:x}\"", env!("CARGO_PKG_VERSION"), fingerprint(&script));
+        Self {
+            script: script.into(),
+            etag: etag.into(),
+        }
+    }
+
+    pub(crate) fn response(&self) -> Response {
+        (
+            [
+                (header::CONTENT_TYPE, "text/javascript; charset=utf-8"),
+                (header::CACHE_CONTROL, "public, max-age=3600"),
+                (header::ETAG, &*self.etag),
+            ],
+            self.script.to_string(),
+        )
+            .into_response()
+    }
+}
+
+/// FNV-1a hash of the rendered script
+fn fingerprint(script: &str) -> u64 {
+    script
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
@@ -0,0 +1,188 @@
+/*! sse-gateway browser client, served by the gateway at /sse/client.js */
+(function (global) {
+    'use strict';
+
+    // Filled in by the gateway that serves this file
+    const GATEWAY = __GATEWAY_CONFIG__;
+
+    const CONNECTED = 'connected';
+    const MIGRATE = 'migrate';
+
+    // Wraps EventSource with the gateway's conventions:
+    // - reconnects with exponential backoff and jitter, resuming from the
+    //   last event ID and, if the gateway issues them, the resume token
+    // - calls `signUrl(url)` before every connect, so expiring signed URLs
+    //   are refreshed instead of replayed
+    // - follows `migrate` hints to another instance on shutdown
+    // - reports `replaying` between replay markers
+    class SseGatewayClient {
+        constructor(options) {
+            if (!options || !options.channel) {
+                throw new Error('SseGatewayClient: channel is required');
+            }
+            this.options = Object.assign({
+                baseUrl: '',
+                params: {},
+                withCredentials: false,
+                signUrl: null,
+                initialDelay: 1000,
+                maxDelay: 30000,
+            }, options);
+            this.baseUrl = this.options.baseUrl;
+            this.lastEventId = this.options.lastEventId || null;
+            this.resumeToken = null;
+            this.replaying = false;
+            this.connected = false;
+            this.attempt = 0;
+            this.source = null;
+            this.timer = null;
+            this.closed = false;
+            this.listeners = {};
+            this.connect();
+        }
+
+        // Listen for an event type; `handler(data, event)` gets parsed JSON
+        // data when possible. Besides the channel's own event types there
+        // are `open`, `error`, `connected`, `replay_start`, `replay_end`
+        // and `migrate`.
+        on(type, handler) {
+            if (!this.listeners[type]) {
+                this.listeners[type] = [];
+                if (this.source) {
+                    this.listen(this.source, type);
+                }
+            }
+            this.listeners[type].push(handler);
+            return this;
+        }
+
+        off(type, handler) {
+            this.listeners[type] = (this.listeners[type] || []).filter(h => h !== handler);
+            return this;
+        }
+
+        close() {
+            this.closed = true;
+            clearTimeout(this.timer);
+            if (this.source) {
+                this.source.close();
+                this.source = null;
+            }
+            this.connected = false;
+        }
+
+        url() {
+            const url = new URL(this.baseUrl.replace(/\/$/, '') + GATEWAY.path, global.location && global.location.href);
+            url.searchParams.set(GATEWAY.channel_param, this.options.channel);
+            Object.keys(this.options.params).forEach(key => url.searchParams.set(key, this.options.params[key]));
+            if (this.resumeToken) {
+                url.searchParams.set('resume', this.resumeToken);
+            }
+            if (this.lastEventId) {
+                url.searchParams.set('last_event_id', this.lastEventId);
+            }
+            return url.toString();
+        }
+
+        async connect() {
+            if (this.closed) {
+                return;
+            }
+            let url = this.url();
+            if (this.options.signUrl) {
+                try {
+                    url = await this.options.signUrl(url);
+                } catch (error) {
+                    this.emit('error', error);
+                    this.reconnect();
+                    return;
+                }
+            }
+            if (this.closed) {
+                return;
+            }
+            const source = new EventSource(url, { withCredentials: this.options.withCredentials });
+            this.source = source;
+            source.onopen = () => {
+                this.attempt = 0;
+                this.connected = true;
+                this.emit('open');
+            };
+            // EventSource would retry the same, possibly expired, URL
+            source.onerror = error => {
+                if (source !== this.source) {
+                    return;
+                }
+                source.close();
+                this.source = null;
+                this.connected = false;
+                this.replaying = false;
+                this.emit('error', error);
+                this.reconnect();
+            };
+            source.onmessage = event => this.dispatch('message', event);
+            source.addEventListener(CONNECTED, event => {
+                const data = parse(event.data);
+                if (data && data.resume_token) {
+                    this.resumeToken = data.resume_token;
+                }
+            });
+            source.addEventListener(GATEWAY.replay_start, () => { this.replaying = true; });
+            source.addEventListener(GATEWAY.replay_end, () => { this.replaying = false; });
+            source.addEventListener(MIGRATE, event => {
+                const data = parse(event.data) || {};
+                if (data.url) {
+                    this.baseUrl = data.url;
+                }
+                source.close();
+                this.source = null;
+                this.connected = false;
+                // Spread the instance's subscribers out over the reconnect
+                this.reconnect(Math.random() * this.options.initialDelay);
+            });
+            Object.keys(this.listeners).forEach(type => this.listen(source, type));
+        }
+
+        reconnect(delay) {
+            if (this.closed) {
+                return;
+            }
+            if (delay === undefined) {
+                const max = Math.min(this.options.maxDelay, this.options.initialDelay * Math.pow(2, this.attempt));
+                delay = max / 2 + Math.random() * max / 2;
+                this.attempt += 1;
+            }
+            clearTimeout(this.timer);
+            this.timer = setTimeout(() => this.connect(), delay);
+        }
+
+        listen(source, type) {
+            if (type === 'open' || type === 'error' || type === 'message') {
+                return;
+            }
+            source.addEventListener(type, event => this.dispatch(type, event));
+        }
+
+        dispatch(type, event) {
+            if (event.lastEventId) {
+                this.lastEventId = event.lastEventId;
+            }
+            this.emit(type, parse(event.data), event);
+        }
+
+        emit(type, data, event) {
+            (this.listeners[type] || []).forEach(handler => handler(data, event));
+        }
+    }
+
+    function parse(data) {
+        try {
+            return JSON.parse(data);
+        } catch (_) {
+            return data;
+        }
+    }
+
+    SseGatewayClient.VERSION = GATEWAY.version;
+    global.SseGatewayClient = SseGatewayClient;
+})(typeof window !== 'undefined' ? window : globalThis);
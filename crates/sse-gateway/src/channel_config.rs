@@ -0,0 +1,195 @@
+//! Per-channel configuration overrides
+//!
+//! Channels share the gateway-wide behavior unless a [`ChannelConfig`]
+//! matches them. Keys are channel IDs, or prefixes ending in `*`
+//! (`"room:*"`); an exact match wins over the longest matching prefix.
+//!
+//! ```rust,ignore
+//! use sse_gateway::{Backpressure, ChannelConfig};
+//!
+//! Gateway::builder()
+//!     .channel_config("ticker:*", ChannelConfig::new().storage(false).backpressure(Backpressure::DropNewest))
+//!     .channel_config("support", ChannelConfig::new().replay_depth(50).max_subscribers(20))
+//! ```
+//!
+//! Configs can also be changed at runtime through
+//! [`ConnectionManager::channel_configs`](crate::ConnectionManager::channel_configs)
+//! or the dashboard's `/api/channels/{id}/config` endpoint.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// What to do when a subscriber's buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backpressure {
+    /// Wait for the subscriber to catch up (default)
+    #[default]
+    Wait,
+    /// Skip the event for that subscriber
+    DropNewest,
+    /// Close the subscriber's connection; it can reconnect and replay
+    Disconnect,
+}
+
+/// Overrides for the channels a key matches; unset fields keep the gateway default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelConfig {
+    /// Replay at most this many of the newest missed events
+    pub replay_depth: Option<usize>,
+    /// Don't replay events older than this many seconds
+    pub retention_secs: Option<u64>,
+    /// Refuse subscribers beyond this many local connections
+    pub max_subscribers: Option<usize>,
+    /// Only accept these event types
+    pub allowed_event_types: Option<Vec<String>>,
+    /// Slow subscriber policy, fixed when a connection opens
+    pub backpressure: Option<Backpressure>,
+    /// Write events to storage for replay
+    pub storage: Option<bool>,
+}
+
+impl ChannelConfig {
+    /// Config with no overrides
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replay at most `depth` of the newest missed events
+    pub fn replay_depth(mut self, depth: usize) -> Self {
+        self.replay_depth = Some(depth);
+        self
+    }
+
+    /// Don't replay events older than `retention`
+    ///
+    /// Ages come from the stream ID, which both built-in storages start with
+    /// a millisecond timestamp; events with other IDs are always replayed.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention_secs = Some(retention.as_secs());
+        self
+    }
+
+    /// Refuse subscribers beyond `limit` local connections with `429`
+    pub fn max_subscribers(mut self, limit: usize) -> Self {
+        self.max_subscribers = Some(limit);
+        self
+    }
+
+    /// Only accept these event types; others are filtered out
+    pub fn allow_event_types<I, T>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.allowed_event_types = Some(event_types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the slow subscriber policy
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = Some(backpressure);
+        self
+    }
+
+    /// Enable or disable storage (and therefore replay)
+    pub fn storage(mut self, enabled: bool) -> Self {
+        self.storage = Some(enabled);
+        self
+    }
+
+    /// Whether `event_type` may be published
+    pub fn allows(&self, event_type: &str) -> bool {
+        self.allowed_event_types
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|t| t == event_type))
+    }
+
+    /// Whether events are written to storage
+    pub fn stores(&self) -> bool {
+        self.storage.unwrap_or(true)
+    }
+
+    /// Apply retention and depth limits to replayed events (oldest first)
+    #[cfg(feature = "server")]
+    pub(crate) fn trim_replay(&self, events: &mut Vec<crate::SseEvent>) {
+        if let Some(retention) = self.retention_secs {
+            let cutoff = chrono::Utc::now().timestamp_millis() - (retention as i64).saturating_mul(1000);
+            events.retain(|event| {
+                event
+                    .stream_id
+                    .as_deref()
+                    .and_then(stream_id_millis)
+                    .is_none_or(|millis| millis >= cutoff)
+            });
+        }
+        if let Some(depth) = self.replay_depth {
+            let excess = events.len().saturating_sub(depth);
+            events.drain(..excess);
+        }
+    }
+}
+
+/// Leading millisecond timestamp of a `{millis}-{seq}` stream ID
+#[cfg(feature = "server")]
+fn stream_id_millis(stream_id: &str) -> Option<i64> {
+    stream_id.split('-').next()?.parse().ok()
+}
+
+/// Registry of channel configs, shared by every clone
+#[derive(Clone, Default)]
+pub struct ChannelConfigs {
+    configs: Arc<DashMap<String, ChannelConfig>>,
+}
+
+impl ChannelConfigs {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the config for `key` (a channel ID or a `prefix*` pattern)
+    pub fn set(&self, key: impl Into<String>, config: ChannelConfig) {
+        self.configs.insert(key.into(), config);
+    }
+
+    /// Remove the config for `key`, returning it
+    pub fn remove(&self, key: &str) -> Option<ChannelConfig> {
+        self.configs.remove(key).map(|(_, config)| config)
+    }
+
+    /// Config registered under exactly `key`
+    pub fn get(&self, key: &str) -> Option<ChannelConfig> {
+        self.configs.get(key).map(|config| config.clone())
+    }
+
+    /// Config that applies to `channel_id`, or the default
+    pub fn resolve(&self, channel_id: &str) -> ChannelConfig {
+        if self.configs.is_empty() {
+            return ChannelConfig::default();
+        }
+        if let Some(config) = self.configs.get(channel_id) {
+            return config.clone();
+        }
+        self.configs
+            .iter()
+            .filter_map(|entry| {
+                let prefix = entry.key().strip_suffix('*')?;
+                channel_id.starts_with(prefix).then(|| (prefix.len(), entry.value().clone()))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, config)| config)
+            .unwrap_or_default()
+    }
+
+    /// All registered keys and configs
+    pub fn list(&self) -> Vec<(String, ChannelConfig)> {
+        self.configs
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+//! Channel aliasing and rewrite rules
+//!
+//! Rewrites map channel IDs to other IDs when clients subscribe and when
+//! messages are dispatched, so a channel naming scheme can change without
+//! breaking deployed clients that still use the old names:
+//!
+//! ```rust,ignore
+//! let rewrites = ChannelRewrites::with_rules(vec![
+//!     RewriteRule::exact("global_feed", "feed:global"),
+//!     RewriteRule::pattern(r"^user_(\d+)$", "user:$1")?,
+//! ]);
+//! Gateway::builder().channel_rewrites(rewrites.clone())
+//!
+//! // Later, while running
+//! rewrites.set_rules(new_rules);
+//! ```
+//!
+//! The first matching rule wins and is applied once; the result is not
+//! rewritten again. A pattern rule replaces the part of the ID it matches,
+//! so anchor it (`^...$`) to map whole IDs. In a config file:
+//!
+//! ```yaml
+//! channel_rewrites:
+//!   - from: global_feed
+//!     to: feed:global
+//!   - pattern: '^user_(\d+)$'
+//!     to: user:$1
+//! ```
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A single channel rewrite: an exact alias or a regex replacement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RuleSpec", into = "RuleSpec")]
+pub struct RewriteRule {
+    matcher: Matcher,
+    to: String,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl PartialEq for RewriteRule {
+    fn eq(&self, other: &Self) -> bool {
+        let same_matcher = match (&self.matcher, &other.matcher) {
+            (Matcher::Exact(a), Matcher::Exact(b)) => a == b,
+            (Matcher::Pattern(a), Matcher::Pattern(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        };
+        same_matcher && self.to == other.to
+    }
+}
+
+impl RewriteRule {
+    /// Rewrite the channel `from` to `to`
+    pub fn exact(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            matcher: Matcher::Exact(from.into()),
+            to: to.into(),
+        }
+    }
+
+    /// Replace the first match of `pattern` with `replacement`, which can
+    /// refer to capture groups (`$1`, `${name}`)
+    pub fn pattern(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            matcher: Matcher::Pattern(Regex::new(pattern)?),
+            to: replacement.into(),
+        })
+    }
+
+    /// `channel_id` rewritten, if the rule matches it
+    pub fn apply(&self, channel_id: &str) -> Option<String> {
+        match &self.matcher {
+            Matcher::Exact(from) => (from == channel_id).then(|| self.to.clone()),
+            Matcher::Pattern(regex) => regex
+                .is_match(channel_id)
+                .then(|| regex.replace(channel_id, self.to.as_str()).into_owned()),
+        }
+    }
+}
+
+/// Serialized form of a [`RewriteRule`]: `from` or `pattern`, and `to`
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    to: String,
+}
+
+impl TryFrom<RuleSpec> for RewriteRule {
+    type Error = String;
+
+    fn try_from(spec: RuleSpec) -> Result<Self, Self::Error> {
+        match (spec.from, spec.pattern) {
+            (Some(from), None) => Ok(Self::exact(from, spec.to)),
+            (None, Some(pattern)) => Self::pattern(&pattern, spec.to).map_err(|e| e.to_string()),
+            _ => Err("a channel rewrite needs exactly one of `from` and `pattern`".to_string()),
+        }
+    }
+}
+
+impl From<RewriteRule> for RuleSpec {
+    fn from(rule: RewriteRule) -> Self {
+        let (from, pattern) = match rule.matcher {
+            Matcher::Exact(from) => (Some(from), None),
+            Matcher::Pattern(regex) => (None, Some(regex.as_str().to_string())),
+        };
+        Self {
+            from,
+            pattern,
+            to: rule.to,
+        }
+    }
+}
+
+/// Rewrite rules, shared by every clone and replaceable while running
+#[derive(Clone, Default)]
+pub struct ChannelRewrites {
+    rules: Arc<ArcSwap<Vec<RewriteRule>>>,
+}
+
+impl ChannelRewrites {
+    /// No rules: channel IDs are used as they are
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with `rules`, tried in order
+    pub fn with_rules(rules: Vec<RewriteRule>) -> Self {
+        let rewrites = Self::new();
+        rewrites.set_rules(rules);
+        rewrites
+    }
+
+    /// Replace the rules; subscriptions and messages from now on use them
+    pub fn set_rules(&self, rules: Vec<RewriteRule>) {
+        self.rules.store(Arc::new(rules));
+    }
+
+    /// Append a rule, tried after the existing ones
+    pub fn push(&self, rule: RewriteRule) {
+        self.rules.rcu(|rules| {
+            let mut rules = Vec::clone(rules);
+            rules.push(rule.clone());
+            rules
+        });
+    }
+
+    /// The rules in effect
+    pub fn rules(&self) -> Vec<RewriteRule> {
+        Vec::clone(&self.rules.load())
+    }
+
+    /// `channel_id` as rewritten by the first matching rule
+    pub fn rewrite<'a>(&self, channel_id: &'a str) -> Cow<'a, str> {
+        let rules = self.rules.load();
+        match rules.iter().find_map(|rule| rule.apply(channel_id)) {
+            Some(rewritten) => Cow::Owned(rewritten),
+            None => Cow::Borrowed(channel_id),
+        }
+    }
+}
+
+impl std::fmt::Debug for ChannelRewrites {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.rules.load().iter()).finish()
+    }
+}
@@ -0,0 +1,184 @@
+//! Consistent-hash channel ownership
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::cluster::{ClusterCoordinator, InstanceInfo};
+
+const DEFAULT_VIRTUAL_NODES: usize = 100;
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Query parameter marking a request that was already redirected once
+#[cfg(feature = "server")]
+pub(crate) const ROUTED_PARAM: &str = "routed";
+
+/// Assigns each channel to one gateway instance by consistent hashing
+///
+/// The ring is built from the cluster membership (see
+/// [`ClusterCoordinator::instances`]), so every instance computes the same
+/// owner for a channel, and a membership change only moves the channels of
+/// the instance that joined or left.
+///
+/// With [`redirect`](Self::redirect) enabled, `/sse/connect` answers `307
+/// Temporary Redirect` to the owning instance's advertised address, which
+/// keeps a channel's subscribers together on deployments without
+/// cookie-based load balancer stickiness. Addresses without a scheme are
+/// treated as `http://`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sse_gateway::ChannelRouter;
+///
+/// let router = ChannelRouter::new().redirect(true);
+///
+/// Gateway::builder()
+///     .cluster(RedisCluster::new(redis_url).address("http://10.0.0.5:8080"))
+///     .channel_router(router.clone())
+///
+/// // Later: which instance should hold "user123"?
+/// let owner = router.owner_of("user123");
+/// ```
+#[derive(Clone)]
+pub struct ChannelRouter {
+    ring: Arc<RwLock<Ring>>,
+    virtual_nodes: usize,
+    redirect: bool,
+    refresh_interval: Duration,
+}
+
+#[derive(Default)]
+struct Ring {
+    members: Vec<InstanceInfo>,
+    /// Sorted `(hash, index into members)` points
+    points: Vec<(u64, usize)>,
+}
+
+impl Default for ChannelRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChannelRouter {
+    /// Create a router with no members
+    pub fn new() -> Self {
+        Self {
+            ring: Arc::new(RwLock::new(Ring::default())),
+            virtual_nodes: DEFAULT_VIRTUAL_NODES,
+            redirect: false,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+
+    /// Points per instance on the ring (default: 100)
+    ///
+    /// More points spread channels more evenly.
+    pub fn virtual_nodes(mut self, virtual_nodes: usize) -> Self {
+        self.virtual_nodes = virtual_nodes.max(1);
+        self
+    }
+
+    /// Redirect `/sse/connect` to the owning instance (default: false)
+    pub fn redirect(mut self, enabled: bool) -> Self {
+        self.redirect = enabled;
+        self
+    }
+
+    /// How often the gateway reloads membership from the coordinator (default: 5s)
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Replace the ring membership
+    pub fn set_members(&self, mut members: Vec<InstanceInfo>) {
+        members.sort_by(|a, b| a.id.cmp(&b.id));
+        members.dedup_by(|a, b| a.id == b.id);
+
+        let mut points = Vec::with_capacity(members.len() * self.virtual_nodes);
+        for (index, member) in members.iter().enumerate() {
+            for replica in 0..self.virtual_nodes {
+                points.push((hash(&format!("{}#{}", member.id, replica)), index));
+            }
+        }
+        points.sort_unstable();
+
+        let mut ring = self.ring.write().unwrap_or_else(|e| e.into_inner());
+        *ring = Ring { members, points };
+    }
+
+    /// Reload membership from `coordinator`
+    pub async fn refresh(&self, coordinator: &dyn ClusterCoordinator) -> anyhow::Result<()> {
+        self.set_members(coordinator.instances().await?);
+        Ok(())
+    }
+
+    /// Current members, sorted by ID
+    pub fn members(&self) -> Vec<InstanceInfo> {
+        self.ring.read().unwrap_or_else(|e| e.into_inner()).members.clone()
+    }
+
+    /// Instance that owns `channel_id`, or `None` while the ring is empty
+    pub fn owner_of(&self, channel_id: &str) -> Option<InstanceInfo> {
+        let ring = self.ring.read().unwrap_or_else(|e| e.into_inner());
+        if ring.points.is_empty() {
+            return None;
+        }
+        let key = hash(channel_id);
+        let position = ring.points.partition_point(|(point, _)| *point < key);
+        let (_, index) = ring.points[position % ring.points.len()];
+        Some(ring.members[index].clone())
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn refresh_every(&self) -> Duration {
+        self.refresh_interval
+    }
+
+    /// Where to send a subscriber of `channel_id` that reached `instance_id`
+    ///
+    /// `None` serves the request locally: redirects are disabled, this
+    /// instance owns the channel, the owner has no address, or the request
+    /// was already redirected once (membership views can briefly disagree).
+    #[cfg(feature = "server")]
+    pub(crate) fn redirect_location(
+        &self,
+        instance_id: &str,
+        channel_id: &str,
+        path: &str,
+        query: Option<&str>,
+    ) -> Option<String> {
+        if !self.redirect {
+            return None;
+        }
+        let query = query.unwrap_or_default();
+        if query.split('&').any(|pair| pair.split('=').next() == Some(ROUTED_PARAM)) {
+            return None;
+        }
+        let owner = self.owner_of(channel_id)?;
+        if owner.id == instance_id {
+            return None;
+        }
+        let base = owner.base_url()?;
+        let query = if query.is_empty() {
+            format!("{}=1", ROUTED_PARAM)
+        } else {
+            format!("{}&{}=1", query, ROUTED_PARAM)
+        };
+        Some(format!("{}{}?{}", base, path, query))
+    }
+}
+
+/// FNV-1a with a final mix, stable across processes and builds
+fn hash(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash
+}
@@ -0,0 +1,151 @@
+//! Per-channel usage counters with daily rollups
+//!
+//! With [`GatewayBuilder::channel_usage`](crate::GatewayBuilder::channel_usage),
+//! the gateway counts per channel and UTC day how many messages were
+//! published and delivered, how many distinct subscribers connected and how
+//! many were connected at once. Counts are kept in memory and added to the
+//! storage's totals every flush interval (and on shutdown), so every instance
+//! of a cluster contributes to the same daily rows.
+//!
+//! ```rust,ignore
+//! Gateway::builder()
+//!     .storage(storage)
+//!     .channel_usage(Duration::from_secs(60))
+//! ```
+//!
+//! `GET /api/channels/{id}/stats?from=2026-10-01&to=2026-10-07` returns the
+//! stored days in that range, oldest first.
+
+use std::sync::Arc;
+
+use chrono::{NaiveDate, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use crate::manager::ConnectionManager;
+#[cfg(feature = "server")]
+use crate::storage::MessageStorage;
+
+/// Counts to add to a channel's totals for one day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageDelta {
+    /// The UTC day counted
+    pub date: NaiveDate,
+    /// Messages published to the channel
+    pub published: u64,
+    /// Events queued to the channel's subscribers on this instance
+    pub delivered: u64,
+    /// Subscribers that connected, by identity or else connection ID
+    pub subscribers: Vec<String>,
+    /// Most subscribers connected at once on this instance
+    pub peak_subscribers: u64,
+}
+
+impl UsageDelta {
+    /// Nothing counted yet for `date`
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            published: 0,
+            delivered: 0,
+            subscribers: Vec::new(),
+            peak_subscribers: 0,
+        }
+    }
+}
+
+/// A channel's totals for one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub published: u64,
+    pub delivered: u64,
+    /// Distinct subscribers that connected during the day
+    pub unique_subscribers: u64,
+    /// Most subscribers connected at once on any one instance
+    pub peak_subscribers: u64,
+}
+
+impl DailyUsage {
+    /// An empty day
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            published: 0,
+            delivered: 0,
+            unique_subscribers: 0,
+            peak_subscribers: 0,
+        }
+    }
+
+    /// Add `delta`, of which `new_subscribers` weren't counted for the day before
+    pub fn add(&mut self, delta: &UsageDelta, new_subscribers: u64) {
+        self.published += delta.published;
+        self.delivered += delta.delivered;
+        self.unique_subscribers += new_subscribers;
+        self.peak_subscribers = self.peak_subscribers.max(delta.peak_subscribers);
+    }
+}
+
+/// Collects usage between flushes
+#[derive(Clone, Default)]
+pub(crate) struct UsageRecorder {
+    pending: Arc<DashMap<(String, NaiveDate), UsageDelta>>,
+}
+
+impl UsageRecorder {
+    fn update(&self, channel_id: &str, change: impl FnOnce(&mut UsageDelta)) {
+        let today = Utc::now().date_naive();
+        let mut delta = self
+            .pending
+            .entry((channel_id.to_string(), today))
+            .or_insert_with(|| UsageDelta::new(today));
+        change(&mut delta);
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn record_published(&self, channel_id: &str) {
+        self.update(channel_id, |delta| delta.published += 1);
+    }
+
+    pub(crate) fn record_delivered(&self, channel_id: &str, count: usize) {
+        if count > 0 {
+            self.update(channel_id, |delta| delta.delivered += count as u64);
+        }
+    }
+
+    /// Count a subscriber that connected while `concurrent` were on the channel
+    pub(crate) fn record_subscriber(&self, channel_id: &str, subscriber: &str, concurrent: usize) {
+        self.update(channel_id, |delta| {
+            delta.subscribers.push(subscriber.to_string());
+            delta.peak_subscribers = delta.peak_subscribers.max(concurrent as u64);
+        });
+    }
+
+    /// Add the counts collected since the last flush to `storage`
+    ///
+    /// Channels with subscribers are counted as of now, so a day's peak
+    /// includes connections opened on an earlier day.
+    #[cfg(feature = "server")]
+    pub(crate) async fn flush<S: MessageStorage>(&self, storage: &S, connection_manager: &ConnectionManager) {
+        for (channel_id, subscribers) in connection_manager.channel_subscriber_counts() {
+            self.update(&channel_id, |delta| {
+                delta.peak_subscribers = delta.peak_subscribers.max(subscribers as u64);
+            });
+        }
+        let keys: Vec<(String, NaiveDate)> = self.pending.iter().map(|entry| entry.key().clone()).collect();
+        for key in keys {
+            if let Some(((channel_id, _), delta)) = self.pending.remove(&key) {
+                storage.record_channel_usage(&channel_id, &delta).await;
+            }
+        }
+    }
+}
+
+/// Parse a `from`/`to` query value (`YYYY-MM-DD`)
+#[cfg(feature = "server")]
+pub(crate) fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| format!("invalid date {:?}: {}", value, e))
+}
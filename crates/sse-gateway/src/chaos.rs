@@ -0,0 +1,404 @@
+//! Fault injection for resilience testing
+//!
+//! A [`ChaosLayer`] disrupts a running gateway the way production does:
+//! dispatches are delayed, live deliveries are lost, connections are killed
+//! and storage calls fail. It exists to exercise client reconnect and replay
+//! logic; never enable it on a gateway serving real traffic.
+//!
+//! ```rust,ignore
+//! use sse_gateway::chaos::{ChaosLayer, ChaosSettings};
+//!
+//! let chaos = ChaosLayer::new(ChaosSettings {
+//!     drop_rate: 0.05,
+//!     kill_rate: 0.01,
+//!     ..Default::default()
+//! });
+//!
+//! Gateway::builder()
+//!     .storage(chaos.storage(MemoryStorage::default()))
+//!     .chaos(chaos)
+//! ```
+//!
+//! Settings can be changed at runtime through the admin endpoint:
+//!
+//! ```bash
+//! curl localhost:8080/api/chaos
+//! curl -X PUT localhost:8080/api/chaos -d '{"delay_rate": 0.5, "max_delay_ms": 2000}'
+//! curl -X POST localhost:8080/api/chaos/kill -d '{"fraction": 0.5}'
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::response::Json;
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::channel_usage::{DailyUsage, UsageDelta};
+use crate::event::SseEvent;
+use crate::manager::ConnectionManager;
+use crate::metrics::GatewayMetrics;
+use crate::retry::random;
+use crate::storage::{MessageStorage, PurgeBefore};
+
+/// Default time between connection-killing rounds
+pub const DEFAULT_KILL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to disrupt, and how often
+///
+/// Rates are probabilities between `0.0` and `1.0`. All default to zero, so
+/// a default layer changes nothing until configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChaosSettings {
+    /// Share of dispatches held back before fan-out
+    pub delay_rate: f64,
+    /// Upper bound of a dispatch delay; each delay is uniform up to it
+    pub max_delay_ms: u64,
+    /// Share of events not delivered live (they are still stored, so replay recovers them)
+    pub drop_rate: f64,
+    /// Share of connections killed in each round
+    pub kill_rate: f64,
+    /// Time between connection-killing rounds
+    pub kill_interval_ms: u64,
+    /// Share of storage calls that fail (writes lost, replays empty)
+    pub storage_failure_rate: f64,
+}
+
+impl Default for ChaosSettings {
+    fn default() -> Self {
+        Self {
+            delay_rate: 0.0,
+            max_delay_ms: 1000,
+            drop_rate: 0.0,
+            kill_rate: 0.0,
+            kill_interval_ms: DEFAULT_KILL_INTERVAL.as_millis() as u64,
+            storage_failure_rate: 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    delayed: AtomicU64,
+    dropped: AtomicU64,
+    killed: AtomicU64,
+    storage_failures: AtomicU64,
+}
+
+/// Disruptions injected so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChaosStats {
+    pub delayed: u64,
+    pub dropped: u64,
+    pub killed: u64,
+    pub storage_failures: u64,
+}
+
+/// Shared fault injection settings for one gateway
+///
+/// Clones share settings and counters. Register it with
+/// [`GatewayBuilder::chaos`](crate::GatewayBuilder::chaos) and wrap the
+/// storage with [`storage`](Self::storage).
+#[derive(Clone, Default)]
+pub struct ChaosLayer {
+    settings: Arc<RwLock<ChaosSettings>>,
+    counters: Arc<Counters>,
+}
+
+impl ChaosLayer {
+    /// Create a layer with `settings`
+    pub fn new(settings: ChaosSettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Current settings
+    pub fn settings(&self) -> ChaosSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replace the settings; takes effect immediately
+    pub fn set(&self, settings: ChaosSettings) {
+        tracing::warn!(?settings, "Chaos settings changed");
+        *self.settings.write().unwrap() = settings;
+    }
+
+    /// Disruptions injected so far
+    pub fn stats(&self) -> ChaosStats {
+        let counters = &self.counters;
+        ChaosStats {
+            delayed: counters.delayed.load(Ordering::Relaxed),
+            dropped: counters.dropped.load(Ordering::Relaxed),
+            killed: counters.killed.load(Ordering::Relaxed),
+            storage_failures: counters.storage_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Wrap `storage` so its calls fail at `storage_failure_rate`
+    pub fn storage<S: MessageStorage>(&self, storage: S) -> ChaosStorage<S> {
+        ChaosStorage {
+            inner: storage,
+            chaos: self.clone(),
+        }
+    }
+
+    /// Kill `fraction` of the current connections, returning how many were closed
+    ///
+    /// Killed streams end as if the network dropped them, so clients reconnect
+    /// with `Last-Event-ID`.
+    pub fn kill_connections(&self, manager: &ConnectionManager, fraction: f64) -> usize {
+        let mut killed = 0;
+        for connection in manager.list_connections() {
+            if chance(fraction) {
+                manager.unregister(&connection.id);
+                killed += 1;
+            }
+        }
+        if killed > 0 {
+            tracing::warn!(killed, "Chaos killed connections");
+            self.counters.killed.fetch_add(killed as u64, Ordering::Relaxed);
+        }
+        killed
+    }
+
+    /// Maybe delay a dispatch, then decide whether its live delivery is lost
+    pub(crate) async fn disrupt_dispatch(&self) -> bool {
+        let (delay_rate, max_delay_ms, drop_rate) = {
+            let settings = self.settings.read().unwrap();
+            (settings.delay_rate, settings.max_delay_ms, settings.drop_rate)
+        };
+        if chance(delay_rate) {
+            GatewayMetrics::incr(&self.counters.delayed);
+            let delay = (random() * max_delay_ms as f64) as u64;
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+        let dropped = chance(drop_rate);
+        if dropped {
+            GatewayMetrics::incr(&self.counters.dropped);
+        }
+        dropped
+    }
+
+    /// Kill connections at `kill_rate` every `kill_interval_ms`
+    pub(crate) fn spawn_killer(
+        &self,
+        manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> tokio::task::JoinHandle<()> {
+        let chaos = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let interval = chaos.settings.read().unwrap().kill_interval_ms.max(1);
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(Duration::from_millis(interval)) => {}
+                }
+                let rate = chaos.settings.read().unwrap().kill_rate;
+                if rate > 0.0 {
+                    chaos.kill_connections(&manager, rate);
+                }
+            }
+        })
+    }
+
+    fn storage_fails(&self) -> bool {
+        let rate = self.settings.read().unwrap().storage_failure_rate;
+        let failed = chance(rate);
+        if failed {
+            GatewayMetrics::incr(&self.counters.storage_failures);
+        }
+        failed
+    }
+
+    /// Admin routes: `GET`/`PUT /api/chaos` and `POST /api/chaos/kill`
+    pub(crate) fn router(&self, manager: ConnectionManager) -> Router {
+        Router::new()
+            .route("/api/chaos", get(get_chaos).put(put_chaos))
+            .route("/api/chaos/kill", post(kill))
+            .with_state((self.clone(), manager))
+    }
+}
+
+/// Storage wrapper that fails calls at the layer's `storage_failure_rate`
+///
+/// `MessageStorage` can't return errors, so a failed call looks the way an
+/// outage does to the gateway: writes are lost, replays come back empty,
+/// cursor lookups find nothing and `is_available` reports `false`.
+#[derive(Clone)]
+pub struct ChaosStorage<S> {
+    inner: S,
+    chaos: ChaosLayer,
+}
+
+#[async_trait]
+impl<S: MessageStorage> MessageStorage for ChaosStorage<S> {
+    fn generate_id(&self) -> String {
+        self.inner.generate_id()
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        if !self.chaos.storage_fails() {
+            self.inner.store(channel_id, stream_id, event).await;
+        }
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        if self.chaos.storage_fails() {
+            return Vec::new();
+        }
+        self.inner.get_messages_after(channel_id, after_id).await
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        if self.chaos.storage_fails() {
+            return None;
+        }
+        self.inner.latest_id(channel_id).await
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        if self.chaos.storage_fails() {
+            return Vec::new();
+        }
+        self.inner.recent_messages(channel_id, limit).await
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        if !self.chaos.storage_fails() {
+            self.inner.save_cursor(channel_id, consumer_id, stream_id).await;
+        }
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        if self.chaos.storage_fails() {
+            return None;
+        }
+        self.inner.get_cursor(channel_id, consumer_id).await
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        if !self.chaos.storage_fails() {
+            self.inner.save_session(token, session, ttl).await;
+        }
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        if self.chaos.storage_fails() {
+            return None;
+        }
+        self.inner.load_session(token).await
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        self.inner.cleanup_expired_sessions().await
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        if !self.chaos.storage_fails() {
+            self.inner.record_channel_usage(channel_id, delta).await;
+        }
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Vec<DailyUsage> {
+        if self.chaos.storage_fails() {
+            return Vec::new();
+        }
+        self.inner.channel_usage(channel_id, from, to).await
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        if self.chaos.storage_fails() {
+            return None;
+        }
+        self.inner.snapshot(channel_id).await
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        if !self.chaos.storage_fails() {
+            self.inner.compact(channel_id, up_to_id).await;
+        }
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        if self.chaos.storage_fails() {
+            return 0;
+        }
+        self.inner.purge(channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        if self.chaos.storage_fails() {
+            return 0;
+        }
+        self.inner.purge_before(channel_id, before).await
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.chaos.storage_fails() && self.inner.is_available().await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+/// `GET /api/chaos` and `PUT /api/chaos` response body
+#[derive(Debug, Serialize)]
+pub struct ChaosResponse {
+    pub settings: ChaosSettings,
+    pub stats: ChaosStats,
+}
+
+/// `POST /api/chaos/kill` request body
+#[derive(Debug, Deserialize)]
+pub struct KillRequest {
+    /// Share of connections to kill (default: all)
+    #[serde(default = "all")]
+    pub fraction: f64,
+}
+
+fn all() -> f64 {
+    1.0
+}
+
+type ChaosState = (ChaosLayer, ConnectionManager);
+
+fn describe(chaos: &ChaosLayer) -> Json<ChaosResponse> {
+    Json(ChaosResponse {
+        settings: chaos.settings(),
+        stats: chaos.stats(),
+    })
+}
+
+async fn get_chaos(State((chaos, _)): State<ChaosState>) -> Json<ChaosResponse> {
+    describe(&chaos)
+}
+
+async fn put_chaos(
+    State((chaos, _)): State<ChaosState>,
+    Json(settings): Json<ChaosSettings>,
+) -> Json<ChaosResponse> {
+    chaos.set(settings);
+    describe(&chaos)
+}
+
+async fn kill(
+    State((chaos, manager)): State<ChaosState>,
+    Json(request): Json<KillRequest>,
+) -> Json<serde_json::Value> {
+    let killed = chaos.kill_connections(&manager, request.fraction);
+    Json(serde_json::json!({ "killed": killed }))
+}
+
+/// Whether an event with probability `rate` happens
+fn chance(rate: f64) -> bool {
+    rate > 0.0 && random() < rate
+}
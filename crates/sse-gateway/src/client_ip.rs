@@ -0,0 +1,204 @@
+//! Client address resolution
+//!
+//! Listeners started by [`Gateway::run`](crate::Gateway::run) attach the
+//! socket peer to every request as axum's `ConnectInfo<SocketAddr>`. Behind a
+//! reverse proxy that peer is the proxy, so the client address comes from
+//! `X-Forwarded-For` instead; [`TrustedProxies`] decides whose forwarding
+//! headers to believe:
+//!
+//! ```rust,ignore
+//! // Only the load balancer subnet may set X-Forwarded-For
+//! Gateway::builder().trusted_proxies(TrustedProxies::new(["10.0.0.0/8"])?)
+//!
+//! // Only reachable through the proxy: believe every peer
+//! Gateway::builder().trusted_proxies(TrustedProxies::any())
+//! ```
+//!
+//! The forwarded chain is walked from the right, skipping trusted proxies;
+//! the first untrusted hop is the client. By default no proxy is trusted and
+//! forwarding headers are ignored. Routers from `into_router` served without
+//! connect info have no peer, and only use forwarded headers when every
+//! address is trusted ([`TrustedProxies::any`]).
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+/// Proxies whose `X-Forwarded-For` header is believed
+///
+/// Defaults to [`none`](Self::none): any client can send the header, so
+/// it is only believed from peers listed here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct TrustedProxies {
+    ranges: Arc<[IpRange]>,
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl TrustedProxies {
+    /// Trust forwarding headers from any peer
+    ///
+    /// Only for gateways that can't be reached except through a proxy;
+    /// otherwise clients choose their own address.
+    pub fn any() -> Self {
+        Self {
+            ranges: Arc::new([
+                IpRange::new(IpAddr::from([0, 0, 0, 0]), 0),
+                IpRange::new(IpAddr::from([0u16; 8]), 0),
+            ]),
+        }
+    }
+
+    /// Ignore forwarding headers; the socket peer is the client
+    pub fn none() -> Self {
+        Self { ranges: Arc::new([]) }
+    }
+
+    /// Trust peers in `ranges`: addresses (`10.0.0.1`) or CIDR blocks (`10.0.0.0/8`)
+    pub fn new<I, S>(ranges: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(|range| range.as_ref().parse())
+            .collect::<anyhow::Result<Vec<IpRange>>>()?;
+        Ok(Self { ranges: ranges.into() })
+    }
+
+    /// Whether `ip` is a trusted proxy
+    pub fn trusts(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    fn trusts_all(&self) -> bool {
+        self.ranges.iter().any(|range| range.prefix == 0)
+    }
+
+    /// The client address of a request from `peer` carrying `headers`
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<String> {
+        let peer_ip = peer.map(|peer| peer.ip().to_canonical());
+        let trusted = match peer_ip {
+            Some(ip) => self.trusts(ip),
+            None => self.trusts_all(),
+        };
+        if !trusted {
+            return peer_ip.map(|ip| ip.to_string());
+        }
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|hop| !hop.is_empty())
+            .collect();
+        for hop in forwarded.iter().rev() {
+            match hop.parse::<IpAddr>() {
+                Ok(ip) if self.trusts(ip) => continue,
+                Ok(ip) => return Some(ip.to_canonical().to_string()),
+                // Not an address, so not a proxy we know
+                Err(_) => return Some(hop.to_string()),
+            }
+        }
+        // Every hop is trusted: the first one is as close to the client as it gets
+        forwarded
+            .first()
+            .map(|hop| hop.to_string())
+            .or_else(|| peer_ip.map(|ip| ip.to_string()))
+    }
+}
+
+impl TryFrom<Vec<String>> for TrustedProxies {
+    type Error = anyhow::Error;
+
+    fn try_from(ranges: Vec<String>) -> Result<Self, Self::Error> {
+        Self::new(ranges)
+    }
+}
+
+impl From<TrustedProxies> for Vec<String> {
+    fn from(proxies: TrustedProxies) -> Self {
+        proxies.ranges.iter().map(ToString::to_string).collect()
+    }
+}
+
+/// An address block in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    fn new(addr: IpAddr, prefix: u8) -> Self {
+        Self { addr, prefix }
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.trim().split_once('/').map_or((s.trim(), None), |(a, p)| (a, Some(p)));
+        let addr: IpAddr = addr
+            .parse::<IpAddr>()
+            .map_err(|e| anyhow::anyhow!("invalid proxy address {s:?}: {e}"))?
+            .to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| anyhow::anyhow!("invalid prefix length in {s:?}"))?,
+            None => max,
+        };
+        Ok(Self::new(addr, prefix))
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The socket peer of a request, if the server attached `ConnectInfo`
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PeerAddr(pub Option<SocketAddr>);
+
+impl<S: Send + Sync> FromRequestParts<S> for PeerAddr {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|info| info.0)))
+    }
+}
@@ -0,0 +1,207 @@
+//! CloudEvents 1.0 support
+//!
+//! Incoming CloudEvents map onto [`IncomingMessage`] as follows:
+//!
+//! | CloudEvents | IncomingMessage |
+//! |-------------|-----------------|
+//! | `type`      | `event_type`    |
+//! | `subject`   | `channel_id` (absent = broadcast) |
+//! | `id`        | `id`            |
+//! | `data`      | `data` (strings as-is, other JSON serialized) |
+//!
+//! Both the structured mode (`application/cloudevents+json` body) and the
+//! binary mode (`ce-*` headers or message attributes) are supported.
+//! [`CloudEventsEmitter`] wraps outgoing SSE payloads in a structured envelope.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::interceptor::{Decision, EventInterceptor};
+use crate::source::IncomingMessage;
+
+/// Supported CloudEvents spec version
+pub const SPEC_VERSION: &str = "1.0";
+
+/// Content type of a structured-mode CloudEvent
+pub const CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Content type of a batch of structured-mode CloudEvents
+pub const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+
+/// A CloudEvents 1.0 event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudEvent {
+    pub specversion: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datacontenttype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataschema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+    /// Extension attributes
+    #[serde(flatten)]
+    pub extensions: HashMap<String, Value>,
+}
+
+impl CloudEvent {
+    /// Parse a structured-mode CloudEvent from JSON
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let event: CloudEvent = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid CloudEvent: {}", e))?;
+        event.check_version()?;
+        Ok(event)
+    }
+
+    /// Parse a batch of structured-mode CloudEvents from a JSON array
+    pub fn batch_from_json(json: &str) -> anyhow::Result<Vec<Self>> {
+        let events: Vec<CloudEvent> = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid CloudEvents batch: {}", e))?;
+        for event in &events {
+            event.check_version()?;
+        }
+        Ok(events)
+    }
+
+    /// Parse a binary-mode CloudEvent
+    ///
+    /// `attribute` looks up a context attribute by its prefixed, lowercase
+    /// name (e.g. `ce-type`), as found in HTTP headers or Pub/Sub attributes.
+    /// The payload is taken as the event data.
+    pub fn from_binary<'a>(
+        attribute: impl Fn(&str) -> Option<&'a str>,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> anyhow::Result<Self> {
+        let required = |name: &str| {
+            attribute(name)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("Missing CloudEvents attribute '{}'", name))
+        };
+        let optional = |name: &str| attribute(name).map(str::to_string);
+
+        let text = std::str::from_utf8(data)
+            .map_err(|e| anyhow::anyhow!("CloudEvent data is not valid UTF-8: {}", e))?;
+        let is_json = content_type.is_none_or(|ct| ct.contains("json"));
+        let data = if data.is_empty() {
+            None
+        } else if is_json {
+            Some(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+        } else {
+            Some(Value::String(text.to_string()))
+        };
+
+        let event = CloudEvent {
+            specversion: required("ce-specversion")?,
+            id: required("ce-id")?,
+            source: required("ce-source")?,
+            event_type: required("ce-type")?,
+            subject: optional("ce-subject"),
+            time: optional("ce-time"),
+            datacontenttype: content_type.map(str::to_string),
+            dataschema: optional("ce-dataschema"),
+            data,
+            data_base64: None,
+            extensions: HashMap::new(),
+        };
+        event.check_version()?;
+        Ok(event)
+    }
+
+    /// Parse a binary-mode CloudEvent from a string attribute map
+    pub fn from_attributes(attributes: &HashMap<String, String>, data: &[u8]) -> anyhow::Result<Self> {
+        let content_type = attributes
+            .get("ce-datacontenttype")
+            .or_else(|| attributes.get("content-type"))
+            .map(String::as_str);
+        Self::from_binary(|name| attributes.get(name).map(String::as_str), content_type, data)
+    }
+
+    /// Payload as the string carried in SSE `data`
+    ///
+    /// String data is used verbatim, other JSON is serialized, and
+    /// `data_base64` is passed through still encoded.
+    pub fn data_string(&self) -> String {
+        match (&self.data, &self.data_base64) {
+            (Some(Value::String(s)), _) => s.clone(),
+            (Some(value), _) => value.to_string(),
+            (None, Some(encoded)) => encoded.clone(),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn check_version(&self) -> anyhow::Result<()> {
+        if self.specversion != SPEC_VERSION {
+            anyhow::bail!("Unsupported CloudEvents specversion '{}'", self.specversion);
+        }
+        Ok(())
+    }
+}
+
+impl From<CloudEvent> for IncomingMessage {
+    fn from(event: CloudEvent) -> Self {
+        let mut msg = IncomingMessage::new(event.event_type.clone(), event.data_string()).with_id(event.id);
+        if let Some(subject) = event.subject.filter(|s| !s.is_empty()) {
+            msg = msg.with_channel(subject);
+        }
+        msg
+    }
+}
+
+/// Interceptor that emits SSE payloads as structured CloudEvents JSON
+///
+/// The envelope is built once per message before dispatch, so stored and
+/// replayed events carry it too.
+pub struct CloudEventsEmitter {
+    source: String,
+}
+
+impl CloudEventsEmitter {
+    /// Emit CloudEvents with the given `source` attribute (a URI reference)
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+
+    /// Wrap a message in a CloudEvent
+    pub fn wrap(&self, msg: &IncomingMessage) -> CloudEvent {
+        let data = serde_json::from_str(&msg.data).unwrap_or_else(|_| Value::String(msg.data.clone()));
+        CloudEvent {
+            specversion: SPEC_VERSION.to_string(),
+            id: msg
+                .id
+                .clone()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            source: self.source.clone(),
+            event_type: msg.event_type.clone(),
+            subject: msg.channel_id.as_deref().map(str::to_string),
+            time: Some(chrono::Utc::now().to_rfc3339()),
+            datacontenttype: Some("application/json".to_string()),
+            dataschema: None,
+            data: Some(data),
+            data_base64: None,
+            extensions: HashMap::new(),
+        }
+    }
+}
+
+impl EventInterceptor for CloudEventsEmitter {
+    fn before_dispatch(&self, msg: &mut IncomingMessage) -> Decision {
+        let envelope = self.wrap(msg);
+        msg.id = Some(envelope.id.clone());
+        msg.data = serde_json::to_string(&envelope).unwrap_or_default();
+        Decision::Continue
+    }
+}
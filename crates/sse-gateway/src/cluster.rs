@@ -0,0 +1,558 @@
+//! Cross-instance fan-out (cluster mode)
+//!
+//! Behind a load balancer, a channel's subscribers may be connected to any
+//! gateway instance, while a message published through a source that
+//! delivers to a single instance (the push endpoint, a shared queue
+//! subscription) arrives at only one of them. A [`ClusterCoordinator`] keeps a
+//! registry of live instances and of which instances hold each channel, and
+//! forwards messages to the instances that own the target channel.
+//!
+//! ```rust,ignore
+//! use sse_gateway_redis::RedisCluster;
+//!
+//! Gateway::builder()
+//!     .source(my_queue_source)
+//!     .storage(storage)
+//!     .cluster(RedisCluster::new("redis://localhost:6379"))
+//!     .build()?
+//!     .run()
+//!     .await
+//! ```
+//!
+//! Fan-out sources that already deliver every message to every instance
+//! (e.g. Redis Pub/Sub) don't need cluster mode. Where every instance may as
+//! well see every message, a [`ClusterFanout`] bus (e.g. `RedisFanout`) is
+//! the simpler alternative to a coordinator.
+
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::connection::{ConnectionMetadata, SseConnection};
+use crate::event::SseEvent;
+use crate::manager::ConnectionManager;
+use crate::pause::ChannelPause;
+
+/// A live gateway instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    /// Instance ID
+    pub id: String,
+    /// Address clients or peers can reach the instance at, if advertised
+    pub address: Option<String>,
+    /// Unix timestamp (seconds) of the last heartbeat
+    pub last_seen: i64,
+    /// Connections on the instance at its last heartbeat, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connections: Option<usize>,
+    /// Connections the instance is sized for, if advertised
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<usize>,
+    /// CPU utilization hint (percent) at its last heartbeat, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<u8>,
+}
+
+impl InstanceInfo {
+    /// URL of the advertised address, without a trailing slash
+    ///
+    /// Addresses without a scheme are treated as `http://`.
+    pub fn base_url(&self) -> Option<String> {
+        let address = self.address.as_deref()?;
+        Some(if address.contains("://") {
+            address.trim_end_matches('/').to_string()
+        } else {
+            format!("http://{}", address.trim_end_matches('/'))
+        })
+    }
+
+    /// Whether the instance can take another connection
+    ///
+    /// Instances that don't advertise a capacity always can.
+    pub fn has_capacity(&self) -> bool {
+        match self.capacity {
+            Some(capacity) => self.connections.unwrap_or(0) < capacity,
+            None => true,
+        }
+    }
+
+    /// How busy the instance is, in percent: the higher of its CPU hint and
+    /// its share of capacity in use
+    ///
+    /// Unreported values count as idle.
+    pub fn utilization(&self) -> u8 {
+        let connections = match self.capacity {
+            Some(0) => 100,
+            Some(capacity) => (self.connections.unwrap_or(0) * 100 / capacity).min(100) as u8,
+            None => 0,
+        };
+        connections.max(self.cpu.unwrap_or(0).min(100))
+    }
+
+    /// Overwrite the reported load with `connection_manager`'s current values
+    ///
+    /// For this instance's own entry, which the registry only updates at the
+    /// next heartbeat.
+    #[cfg(feature = "server")]
+    pub(crate) fn refresh_load(&mut self, connection_manager: &ConnectionManager) {
+        self.connections = Some(connection_manager.connection_count());
+        self.capacity = connection_manager.capacity();
+        self.cpu = connection_manager.cpu_hint();
+    }
+}
+
+/// The least loaded of `instances` that can take another connection
+///
+/// Ranked by [`utilization`](InstanceInfo::utilization), then connections,
+/// then ID so every instance picks the same one.
+#[cfg(feature = "server")]
+pub(crate) fn least_loaded(instances: impl IntoIterator<Item = InstanceInfo>) -> Option<InstanceInfo> {
+    instances
+        .into_iter()
+        .filter(InstanceInfo::has_capacity)
+        .min_by(|a, b| {
+            (a.utilization(), a.connections.unwrap_or(0), &a.id)
+                .cmp(&(b.utilization(), b.connections.unwrap_or(0), &b.id))
+        })
+}
+
+/// A subscriber connection, as shared with the cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceConnection {
+    /// Connection ID
+    pub connection_id: String,
+    /// Connection metadata
+    #[serde(flatten)]
+    pub metadata: ConnectionMetadata,
+}
+
+impl From<&SseConnection> for PresenceConnection {
+    fn from(connection: &SseConnection) -> Self {
+        Self {
+            connection_id: connection.id.to_string(),
+            metadata: connection.metadata.clone(),
+        }
+    }
+}
+
+/// One instance's subscribers on a channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstancePresence {
+    /// Instance ID
+    pub instance_id: String,
+    /// The instance's connections on the channel
+    pub connections: Vec<PresenceConnection>,
+}
+
+impl InstancePresence {
+    /// The local connections on `channel_id`
+    pub fn local(connection_manager: &ConnectionManager, channel_id: &str) -> Self {
+        Self {
+            instance_id: connection_manager.instance_id().to_string(),
+            connections: connection_manager
+                .channel_connections(channel_id)
+                .iter()
+                .map(PresenceConnection::from)
+                .collect(),
+        }
+    }
+}
+
+/// An event forwarded from the instance that received it
+///
+/// Forwarded events have already been stored and passed `before_dispatch`
+/// on the originating instance; the receiver only delivers them locally.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterMessage {
+    /// Target channel; `None` broadcasts
+    pub channel_id: Option<String>,
+    /// The event, including its stream ID
+    pub event: SseEvent,
+}
+
+impl ClusterMessage {
+    /// Deliver to this instance's local connections
+    ///
+    /// Returns the number of connections the event was sent to.
+    pub async fn deliver(self, connection_manager: &ConnectionManager) -> usize {
+        match &self.channel_id {
+            Some(channel_id) => connection_manager.send_to_channel(channel_id, self.event).await,
+            None => connection_manager.broadcast(self.event).await,
+        }
+    }
+}
+
+/// Instance registry, channel ownership and message forwarding
+///
+/// Implementations must tolerate calls before [`start`](Self::start) has
+/// connected (e.g. by doing nothing), since connections may arrive first.
+#[async_trait]
+pub trait ClusterCoordinator: Send + Sync + 'static {
+    /// Register the instance and run until cancelled
+    ///
+    /// Keeps the registration alive, periodically re-claims the channels in
+    /// `connection_manager` (so lost claims heal), and delivers messages
+    /// forwarded to this instance with [`ClusterMessage::deliver`]. Should
+    /// unregister the instance on cancellation.
+    async fn start(
+        &self,
+        connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()>;
+
+    /// Record that `instance_id` has subscribers on `channel_id`
+    async fn claim_channel(&self, instance_id: &str, channel_id: &str);
+
+    /// Record that `instance_id` no longer has subscribers on `channel_id`
+    async fn release_channel(&self, instance_id: &str, channel_id: &str);
+
+    /// Live instances holding subscribers on `channel_id`
+    async fn channel_owners(&self, channel_id: &str) -> anyhow::Result<Vec<String>>;
+
+    /// Record `instance_id`'s connections on `channel_id` (empty once none remain)
+    ///
+    /// Called on every connect and disconnect. The default does nothing.
+    async fn set_presence(&self, _instance_id: &str, _channel_id: &str, _connections: &[PresenceConnection]) {}
+
+    /// Subscribers on `channel_id`, per live instance
+    ///
+    /// The default lists the [`channel_owners`](Self::channel_owners) without
+    /// connection details.
+    async fn presence(&self, channel_id: &str) -> anyhow::Result<Vec<InstancePresence>> {
+        Ok(self
+            .channel_owners(channel_id)
+            .await?
+            .into_iter()
+            .map(|instance_id| InstancePresence {
+                instance_id,
+                connections: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// All live instances
+    async fn instances(&self) -> anyhow::Result<Vec<InstanceInfo>>;
+
+    /// Remove a silent instance and its channel claims, unless it has
+    /// heartbeated since `instance.last_seen`
+    ///
+    /// Returns the channels it held, or `None` if it turned out to be alive
+    /// or was already gone. The check and the removal must be atomic: when
+    /// two instances each take the other for dead (split brain), a heartbeat
+    /// that lands first must keep its instance registered. An instance
+    /// evicted while alive re-registers and re-claims its channels at its
+    /// next heartbeat. The default never evicts; silent instances then drop
+    /// out once their registration expires.
+    async fn evict(&self, _instance: &InstanceInfo) -> anyhow::Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// Every channel with live owners, mapped to its owning instances
+    ///
+    /// Used by the dashboard's cluster view; may scan the whole registry.
+    /// The default reports that listing isn't supported.
+    async fn channels(&self) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        anyhow::bail!("{} does not list channels", self.name())
+    }
+
+    /// Pause `channel_id` on every instance, or resume it with `None`
+    ///
+    /// Instances pick the change up from [`channel_pauses`](Self::channel_pauses).
+    /// The default does nothing, so pauses stay on the instance they were
+    /// set on.
+    async fn set_channel_pause(&self, _channel_id: &str, _pause: Option<&ChannelPause>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Channels paused cluster-wide
+    ///
+    /// Polled by every instance, which pauses and resumes its own delivery
+    /// to match. The default reports that pauses aren't replicated; instances
+    /// then keep their local pauses.
+    async fn channel_pauses(&self) -> anyhow::Result<HashMap<String, ChannelPause>> {
+        anyhow::bail!("{} does not replicate channel pauses", self.name())
+    }
+
+    /// Send a message to another instance
+    async fn forward(&self, instance_id: &str, message: &ClusterMessage) -> anyhow::Result<()>;
+
+    /// Return the coordinator name (for logging)
+    fn name(&self) -> &'static str;
+}
+
+/// Broadcast bus between instances
+///
+/// The alternative to a [`ClusterCoordinator`] when every instance may as
+/// well see every message: each message dispatched on an instance is
+/// [`publish`](Self::publish)ed, and the other instances deliver it to their
+/// local subscribers.
+#[async_trait]
+pub trait ClusterFanout: Send + Sync + 'static {
+    /// Receive other instances' messages until cancelled
+    ///
+    /// Delivers them with [`ClusterMessage::deliver`], skipping this
+    /// instance's own messages and ones already delivered.
+    async fn start(
+        &self,
+        connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()>;
+
+    /// Send a message dispatched on this instance to all others
+    async fn publish(&self, message: &ClusterMessage) -> anyhow::Result<()>;
+
+    /// Return the fanout name (for logging)
+    fn name(&self) -> &'static str;
+}
+
+/// A coordinator bound to this instance, used by the dispatch paths
+#[derive(Clone)]
+#[cfg(feature = "server")]
+pub(crate) struct Cluster {
+    coordinator: Arc<dyn ClusterCoordinator>,
+    instance_id: String,
+}
+
+#[cfg(feature = "server")]
+impl Cluster {
+    pub(crate) fn new(coordinator: Arc<dyn ClusterCoordinator>, instance_id: impl Into<String>) -> Self {
+        Self {
+            coordinator,
+            instance_id: instance_id.into(),
+        }
+    }
+
+    pub(crate) fn coordinator(&self) -> &Arc<dyn ClusterCoordinator> {
+        &self.coordinator
+    }
+
+    pub(crate) fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Other instances that should receive a message for `channel_id`
+    ///
+    /// Broadcasts go to every other live instance. Lookup failures are
+    /// logged and treated as no remote subscribers.
+    pub(crate) async fn peers_for(&self, channel_id: Option<&str>) -> Vec<String> {
+        let peers = match channel_id {
+            Some(channel_id) => self.coordinator.channel_owners(channel_id).await,
+            None => self
+                .coordinator
+                .instances()
+                .await
+                .map(|instances| instances.into_iter().map(|i| i.id).collect()),
+        };
+        match peers {
+            Ok(mut peers) => {
+                peers.retain(|id| *id != self.instance_id);
+                peers
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, channel_id = ?channel_id, "Cluster lookup failed");
+                Vec::new()
+            }
+        }
+    }
+
+    /// The least loaded other instance with an advertised address
+    ///
+    /// See [`least_loaded`] for the ranking.
+    pub(crate) async fn migration_target(&self) -> Option<InstanceInfo> {
+        match self.coordinator.instances().await {
+            Ok(instances) => least_loaded(
+                instances
+                    .into_iter()
+                    .filter(|i| i.id != self.instance_id && i.address.is_some()),
+            ),
+            Err(e) => {
+                tracing::warn!(error = %e, "Cluster lookup failed");
+                None
+            }
+        }
+    }
+
+    /// The instance a new subscriber should connect to
+    ///
+    /// Only instances with an advertised address and spare capacity are
+    /// eligible. For `channel_id`, the least loaded eligible instance that
+    /// already holds subscribers on the channel is preferred, keeping them
+    /// together; otherwise the least loaded eligible instance is chosen.
+    pub(crate) async fn route(
+        &self,
+        connection_manager: &ConnectionManager,
+        channel_id: Option<&str>,
+    ) -> anyhow::Result<Option<InstanceInfo>> {
+        let mut instances = self.instances(connection_manager).await?;
+        instances.retain(|i| i.address.is_some() && i.has_capacity());
+        if let Some(channel_id) = channel_id {
+            let owners = self.coordinator.channel_owners(channel_id).await?;
+            let owning = instances.iter().filter(|i| owners.contains(&i.id)).cloned();
+            if let Some(owner) = least_loaded(owning) {
+                return Ok(Some(owner));
+            }
+        }
+        Ok(least_loaded(instances))
+    }
+
+    /// Pause or resume the local delivery of channels to match the cluster
+    pub(crate) async fn sync_pauses(&self, connection_manager: &ConnectionManager) {
+        let pauses = match self.coordinator.channel_pauses().await {
+            Ok(pauses) => pauses,
+            Err(e) => {
+                tracing::debug!(error = %e, "Channel pauses not synced");
+                return;
+            }
+        };
+        for (channel_id, pause) in &pauses {
+            if connection_manager.channel_pause(channel_id).as_ref() != Some(pause) {
+                connection_manager.pause_channel(channel_id, pause.clone());
+            }
+        }
+        for paused in connection_manager.paused_channels() {
+            if !pauses.contains_key(&paused.channel_id) {
+                connection_manager.resume_channel(&paused.channel_id).await;
+            }
+        }
+    }
+
+    /// All live instances, with this instance's load taken from local state
+    pub(crate) async fn instances(&self, connection_manager: &ConnectionManager) -> anyhow::Result<Vec<InstanceInfo>> {
+        let mut instances = self.coordinator.instances().await?;
+        for instance in &mut instances {
+            if instance.id == self.instance_id {
+                instance.refresh_load(connection_manager);
+            }
+        }
+        Ok(instances)
+    }
+
+    /// Evict other instances silent for longer than `silent_for`, and claim
+    /// their channels that have local subscribers
+    ///
+    /// Returns the number of instances evicted.
+    pub(crate) async fn take_over(&self, connection_manager: &ConnectionManager, silent_for: Duration) -> usize {
+        let instances = match self.coordinator.instances().await {
+            Ok(instances) => instances,
+            Err(e) => {
+                tracing::warn!(error = %e, "Cluster lookup failed");
+                return 0;
+            }
+        };
+        let cutoff = chrono::Utc::now().timestamp() - silent_for.as_secs() as i64;
+        let mut evicted = 0;
+        for instance in instances {
+            if instance.id == self.instance_id || instance.last_seen >= cutoff {
+                continue;
+            }
+            let channels = match self.coordinator.evict(&instance).await {
+                Ok(Some(channels)) => channels,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(error = %e, instance_id = %instance.id, "Failed to evict instance");
+                    continue;
+                }
+            };
+            evicted += 1;
+            let mut claimed = 0;
+            for channel_id in &channels {
+                if connection_manager.channel_connection_count(channel_id) > 0 {
+                    self.coordinator.claim_channel(&self.instance_id, channel_id).await;
+                    claimed += 1;
+                }
+            }
+            tracing::warn!(
+                instance_id = %instance.id,
+                last_seen = instance.last_seen,
+                channels = channels.len(),
+                claimed,
+                "Evicted silent instance"
+            );
+        }
+        evicted
+    }
+
+    /// Forward `event` to `peers`, logging failures
+    pub(crate) async fn forward(&self, peers: &[String], channel_id: Option<&str>, event: &SseEvent) {
+        if peers.is_empty() {
+            return;
+        }
+        let message = ClusterMessage {
+            channel_id: channel_id.map(str::to_string),
+            event: event.clone(),
+        };
+        for peer in peers {
+            if let Err(e) = self.coordinator.forward(peer, &message).await {
+                tracing::warn!(error = %e, instance_id = %peer, "Failed to forward message");
+            }
+        }
+    }
+
+    /// Claim a channel when its first local connection arrives, and update presence
+    pub(crate) fn on_connect(&self, connection_manager: &ConnectionManager, channel_id: &str) {
+        let first = connection_manager.channel_connection_count(channel_id) == 1;
+        let cluster = self.clone();
+        let connection_manager = connection_manager.clone();
+        let channel_id = channel_id.to_string();
+        tokio::spawn(async move {
+            if first {
+                cluster
+                    .coordinator
+                    .claim_channel(&cluster.instance_id, &channel_id)
+                    .await;
+            }
+            cluster.report_presence(&connection_manager, &channel_id).await;
+        });
+    }
+
+    /// Release a channel once its last local connection is gone, and update presence
+    ///
+    /// Checked when the release runs, so a quick reconnect keeps the claim.
+    pub(crate) fn on_disconnect(&self, connection_manager: &ConnectionManager, channel_id: &str) {
+        let cluster = self.clone();
+        let connection_manager = connection_manager.clone();
+        let channel_id = channel_id.to_string();
+        tokio::spawn(async move {
+            if connection_manager.channel_connection_count(&channel_id) == 0 {
+                cluster
+                    .coordinator
+                    .release_channel(&cluster.instance_id, &channel_id)
+                    .await;
+            }
+            cluster.report_presence(&connection_manager, &channel_id).await;
+        });
+    }
+
+    /// Publish the current local connections on `channel_id`
+    async fn report_presence(&self, connection_manager: &ConnectionManager, channel_id: &str) {
+        let local = InstancePresence::local(connection_manager, channel_id);
+        self.coordinator
+            .set_presence(&self.instance_id, channel_id, &local.connections)
+            .await;
+    }
+
+    /// Subscribers on `channel_id` across the cluster
+    ///
+    /// This instance's entry comes from local state rather than the
+    /// coordinator, which may lag behind.
+    pub(crate) async fn presence(
+        &self,
+        connection_manager: &ConnectionManager,
+        channel_id: &str,
+    ) -> anyhow::Result<Vec<InstancePresence>> {
+        let mut presence = self.coordinator.presence(channel_id).await?;
+        presence.retain(|p| p.instance_id != self.instance_id);
+        let local = InstancePresence::local(connection_manager, channel_id);
+        if !local.connections.is_empty() {
+            presence.push(local);
+        }
+        presence.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+        Ok(presence)
+    }
+}
@@ -0,0 +1,139 @@
+//! Coalescing of SSE writes
+//!
+//! Each event is normally its own body frame, and so its own write to the
+//! socket. On tick-style channels a connection often has several events
+//! queued at once; with coalescing enabled, frames that are already waiting
+//! are joined into one chunk, up to a size limit. Optionally the stream
+//! also waits a short time for more, trading that much latency for fewer,
+//! larger writes:
+//!
+//! ```rust,ignore
+//! Gateway::builder().write_coalescing(
+//!     WriteCoalescing::new().max_bytes(32 * 1024).max_delay(Duration::from_millis(5)),
+//! )
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::{
+    body::{Body, BodyDataStream, Bytes},
+    response::Response,
+};
+use futures::Stream;
+use tokio::time::Sleep;
+
+/// Default size at which a coalesced chunk is written
+pub const DEFAULT_COALESCE_BYTES: usize = 16 * 1024;
+
+/// Settings for joining queued SSE frames into fewer writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteCoalescing {
+    max_bytes: usize,
+    max_delay: Duration,
+}
+
+impl Default for WriteCoalescing {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_COALESCE_BYTES,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+impl WriteCoalescing {
+    /// Join frames already queued, up to 16 KiB, without waiting for more
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write once a chunk reaches `bytes`
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes.max(1);
+        self
+    }
+
+    /// Wait up to `delay` after a frame for more to join it (default: none)
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Coalesce the frames of `response`'s body
+    pub(crate) fn apply(&self, response: Response) -> Response {
+        let (parts, body) = response.into_parts();
+        let stream = Coalesce {
+            inner: body.into_data_stream(),
+            buffer: Vec::new(),
+            settings: *self,
+            deadline: None,
+            error: None,
+            done: false,
+        };
+        Response::from_parts(parts, Body::from_stream(stream))
+    }
+}
+
+/// Body stream joining ready frames into one chunk
+struct Coalesce {
+    inner: BodyDataStream,
+    buffer: Vec<u8>,
+    settings: WriteCoalescing,
+    /// When the buffered chunk is written at the latest, if waiting for more
+    deadline: Option<Pin<Box<Sleep>>>,
+    /// Error from the body, passed on after the buffered chunk
+    error: Option<axum::Error>,
+    done: bool,
+}
+
+impl Coalesce {
+    fn flush(&mut self) -> Poll<Option<Result<Bytes, axum::Error>>> {
+        self.deadline = None;
+        Poll::Ready(Some(Ok(Bytes::from(std::mem::take(&mut self.buffer)))))
+    }
+}
+
+impl Stream for Coalesce {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done || this.error.is_some() {
+                if !this.buffer.is_empty() {
+                    return this.flush();
+                }
+                return Poll::Ready(this.error.take().map(Err));
+            }
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(data))) => {
+                    // Large frames on their own go out as they are, uncopied
+                    if this.buffer.is_empty() && data.len() >= this.settings.max_bytes {
+                        return Poll::Ready(Some(Ok(data)));
+                    }
+                    this.buffer.extend_from_slice(&data);
+                    if this.buffer.len() >= this.settings.max_bytes {
+                        return this.flush();
+                    }
+                    if this.deadline.is_none() && !this.settings.max_delay.is_zero() {
+                        this.deadline = Some(Box::pin(tokio::time::sleep(this.settings.max_delay)));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => this.error = Some(e),
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending if this.buffer.is_empty() => return Poll::Pending,
+                Poll::Pending => {
+                    // Nothing more is queued: write now, or when the wait is over
+                    let waiting = this
+                        .deadline
+                        .as_mut()
+                        .is_some_and(|deadline| deadline.as_mut().poll(cx).is_pending());
+                    return if waiting { Poll::Pending } else { this.flush() };
+                }
+            }
+        }
+    }
+}
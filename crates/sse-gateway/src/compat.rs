@@ -0,0 +1,98 @@
+//! Compatibility mode for legacy clients and buffering proxies
+//!
+//! Some EventSource polyfills (and old IE XHR streaming) only hand data to
+//! the page once a couple of kilobytes have arrived, and reverse proxies
+//! such as nginx buffer responses unless told not to. In compatibility
+//! mode a connection starts with a 2 KiB comment, the response carries
+//! `X-Accel-Buffering: no` and `Cache-Control: no-cache, no-transform`, and
+//! each event is written on its own rather than coalesced.
+//!
+//! Clients opt in with `?compat=1`, or the gateway enables it for everyone:
+//!
+//! ```rust,ignore
+//! // Every connection, unless it asks for `?compat=0`
+//! Gateway::builder().compat_mode(CompatMode::always())
+//!
+//! // Only `?compat=1` connections, with a larger preamble
+//! Gateway::builder().compat_mode(CompatMode::new().padding(4096))
+//! ```
+
+use axum::http::{header, HeaderValue};
+use axum::response::Response;
+
+/// Query parameter turning compatibility mode on (`1`, `true`) or off (`0`, `false`)
+pub const COMPAT_PARAM: &str = "compat";
+
+/// Default size of the comment sent first on a compatibility connection
+pub const DEFAULT_PADDING_BYTES: usize = 2048;
+
+/// Settings for compatibility-mode connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompatMode {
+    always: bool,
+    padding: usize,
+    chunk_events: bool,
+}
+
+impl Default for CompatMode {
+    fn default() -> Self {
+        Self {
+            always: false,
+            padding: DEFAULT_PADDING_BYTES,
+            chunk_events: true,
+        }
+    }
+}
+
+impl CompatMode {
+    /// Compatibility mode for connections asking for `?compat=1`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compatibility mode for every connection not asking for `?compat=0`
+    pub fn always() -> Self {
+        Self {
+            always: true,
+            ..Self::default()
+        }
+    }
+
+    /// Size of the leading comment in bytes (default: 2048, 0 for none)
+    pub fn padding(mut self, bytes: usize) -> Self {
+        self.padding = bytes;
+        self
+    }
+
+    /// Write each event on its own, even with write coalescing (default: on)
+    pub fn chunk_events(mut self, enable: bool) -> Self {
+        self.chunk_events = enable;
+        self
+    }
+
+    /// Whether a connection with the given `compat` parameter uses the mode
+    pub(crate) fn applies(&self, param: Option<&str>) -> bool {
+        match param {
+            Some("1" | "true") => true,
+            Some("0" | "false") => false,
+            _ => self.always,
+        }
+    }
+
+    /// Text of the leading comment, if padding is enabled
+    pub(crate) fn padding_comment(&self) -> Option<String> {
+        (self.padding > 0).then(|| " ".repeat(self.padding))
+    }
+
+    pub(crate) fn chunks_events(&self) -> bool {
+        self.chunk_events
+    }
+
+    /// Add the headers that stop proxies buffering or rewriting the stream
+    pub(crate) fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        headers.insert("x-accel-buffering", HeaderValue::from_static("no"));
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache, no-transform"));
+        response
+    }
+}
@@ -0,0 +1,195 @@
+//! Compression of SSE responses
+//!
+//! Generic HTTP compression buffers output until enough has accumulated,
+//! which stalls an event stream. Here each event is compressed and flushed
+//! on its own (a sync flush), so clients see it immediately while the
+//! compression context is kept across the stream and repeated JSON keys
+//! still compress well.
+
+use std::io::{self, Write};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue},
+    response::Response,
+};
+use futures::StreamExt;
+
+/// Compression settings for SSE responses
+///
+/// The encoding is negotiated per request from `Accept-Encoding`; Brotli is
+/// preferred over gzip when the client accepts both.
+///
+/// ```rust,ignore
+/// Gateway::builder().compression(Compression::new().br(false).level(4))
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    gzip: bool,
+    br: bool,
+    level: u32,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            br: true,
+            level: 5,
+        }
+    }
+}
+
+impl Compression {
+    /// gzip and Brotli at level 5
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offer gzip
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = enable;
+        self
+    }
+
+    /// Offer Brotli
+    pub fn br(mut self, enable: bool) -> Self {
+        self.br = enable;
+        self
+    }
+
+    /// Compression level (capped at 9 for gzip and 11 for Brotli)
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Pick an encoding the client accepts
+    pub(crate) fn negotiate(&self, headers: &HeaderMap) -> Option<Encoding> {
+        let accepted: Vec<&str> = headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let name = parts.next()?.trim();
+                let refused = parts.any(|p| {
+                    p.trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| q == 0.0)
+                });
+                (!refused).then_some(name)
+            })
+            .collect();
+        let accepts = |name: &str| accepted.iter().any(|a| a.eq_ignore_ascii_case(name));
+
+        if self.br && accepts("br") {
+            Some(Encoding::Brotli)
+        } else if self.gzip && accepts("gzip") {
+            Some(Encoding::Gzip)
+        } else {
+            None
+        }
+    }
+
+    /// Compress `response` for `headers`, or return it unchanged
+    pub(crate) fn apply(&self, headers: &HeaderMap, response: Response) -> Response {
+        let Some(encoding) = self.negotiate(headers) else {
+            return response;
+        };
+
+        let (mut parts, body) = response.into_parts();
+        parts
+            .headers
+            .insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.name()));
+        parts
+            .headers
+            .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        parts.headers.remove(header::CONTENT_LENGTH);
+
+        let encoder = Encoder::new(encoding, self.level);
+        let stream = futures::stream::unfold(
+            (body.into_data_stream(), Some(encoder)),
+            |(mut body, mut encoder)| async move {
+                let active = encoder.as_mut()?;
+                let item = match body.next().await {
+                    Some(Ok(data)) => active.chunk(&data).map_err(axum::Error::new),
+                    Some(Err(e)) => {
+                        encoder = None;
+                        Err(e)
+                    }
+                    None => encoder.take()?.finish().map_err(axum::Error::new),
+                };
+                Some((item, (body, encoder)))
+            },
+        );
+        Response::from_parts(parts, Body::from_stream(stream))
+    }
+}
+
+/// Negotiated content encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Brotli => "br",
+        }
+    }
+}
+
+/// Streaming encoder that flushes after every chunk
+enum Encoder {
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding, level: u32) -> Self {
+        match encoding {
+            Encoding::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::new(level.min(9)),
+            )),
+            Encoding::Brotli => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                4096,
+                level.min(11),
+                22,
+            ))),
+        }
+    }
+
+    /// Compress one event and flush it out
+    fn chunk(&mut self, data: &[u8]) -> io::Result<Bytes> {
+        let output = match self {
+            Encoder::Gzip(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Encoder::Brotli(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(std::mem::take(output)))
+    }
+
+    /// Write the stream trailer
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Brotli(encoder) => encoder.into_inner(),
+        };
+        Ok(Bytes::from(output))
+    }
+}
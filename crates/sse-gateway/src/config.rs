@@ -0,0 +1,927 @@
+//! Gateway configuration from files and environment variables
+//!
+//! Deployments can tune the gateway without code changes: settings are read
+//! from a YAML or TOML file (chosen by extension), then overridden by
+//! `SSE_GATEWAY_*` environment variables. Anything left unset keeps the
+//! [`GatewayBuilder`] default.
+//!
+//! ```yaml
+//! port: 8080
+//! dashboard: false
+//! metrics: true
+//! heartbeat:
+//!   interval_secs: 15
+//!   style: comment
+//!   idle_only: true
+//! cleanup_interval_secs: 60
+//! keep_alive_interval_secs: 15
+//! throttle:
+//!   events_per_sec: 100
+//!   action: delay
+//! auth:
+//!   mode: bearer
+//!   tokens: ["s3cret"]
+//! trusted_proxies: ["10.0.0.0/8"]
+//! storage:
+//!   type: memory
+//!   max_per_channel: 500
+//! ids:
+//!   type: snowflake
+//!   node: 3
+//! source:
+//!   type: push
+//!   path: /push
+//! channels:
+//!   "ticker:*":
+//!     storage: false
+//!     backpressure: drop_newest
+//! channel_rewrites:
+//!   - pattern: '^user_(\d+)$'
+//!     to: user:$1
+//! ```
+//!
+//! Environment variables name a setting by its path, upper-cased, with `__`
+//! between levels: `SSE_GATEWAY_PORT=9090`,
+//! `SSE_GATEWAY_HEARTBEAT__INTERVAL_SECS=10`,
+//! `SSE_GATEWAY_AUTH__TOKENS='["a","b"]'`. Values are parsed as JSON when
+//! they can be and taken as strings otherwise.
+//!
+//! ```rust,ignore
+//! // Reads the file named by SSE_GATEWAY_CONFIG, if any, plus the environment
+//! GatewayBuilder::from_env()?
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Or keep a custom source and storage, configuring everything else
+//! let config = GatewayConfig::load("gateway.yaml")?;
+//! config.apply(Gateway::builder()).source(my_source).storage(my_storage)
+//! ```
+//!
+//! # Reloading
+//!
+//! Gateways built with [`GatewayBuilder::from_config`] or
+//! [`GatewayBuilder::live_config`] re-read their file on SIGHUP and on
+//! `POST /admin/reload`. Throttle limits, channel policies, channel
+//! rewrites, auth tokens and the log level change in place; connections stay open. Other settings are
+//! only read at startup, and changing them logs a warning.
+//!
+//! ```rust,ignore
+//! let (filter, reload_handle) = tracing_subscriber::reload::Layer::new(EnvFilter::new("info"));
+//! let live = LiveConfig::load("gateway.yaml")?
+//!     .watch(Duration::from_secs(10))
+//!     .on_log_level(move |level| Ok(reload_handle.reload(EnvFilter::try_new(level)?)?));
+//!
+//! Gateway::builder().source(my_source).storage(my_storage).live_config(live)
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::post;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq};
+use tokio_util::sync::CancellationToken;
+
+use crate::auth::{deny, AuthRequest, AuthResponse};
+use crate::channel_rewrite::{ChannelRewrites, RewriteRule};
+use crate::channel_usage::{DailyUsage, UsageDelta};
+use crate::client_ip::TrustedProxies;
+use crate::dispatch_queue::QueueOverflow;
+use crate::event::SseEvent;
+use crate::heartbeat::Heartbeat;
+use crate::id::{SnowflakeIds, StreamIds};
+use crate::storage::{MemoryStorage, MessageStorage, NoopStorage, PurgeBefore};
+use crate::throttle::{Throttle, ThrottleAction, ThrottlePolicy};
+use crate::{ChannelConfig, ChannelConfigs, GatewayBuilder, MessageSource};
+
+/// Prefix of environment variable overrides
+pub const ENV_PREFIX: &str = "SSE_GATEWAY_";
+
+/// Environment variable naming the config file read by [`GatewayConfig::from_env`]
+pub const CONFIG_PATH_ENV: &str = "SSE_GATEWAY_CONFIG";
+
+/// Gateway settings; unset fields keep the builder default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GatewayConfig {
+    /// Listening port
+    pub port: Option<u16>,
+    /// Instance ID (default: random UUID)
+    pub instance_id: Option<String>,
+    /// Path of the SSE endpoint
+    pub sse_path: Option<String>,
+    /// Serve the dashboard and its `/api/*` routes
+    pub dashboard: Option<bool>,
+    /// Serve `/api/metrics` (default: with the dashboard)
+    pub metrics: Option<bool>,
+    /// Heartbeat format and interval
+    pub heartbeat: Option<HeartbeatConfig>,
+    /// Seconds between stale connection sweeps
+    pub cleanup_interval_secs: Option<u64>,
+    /// Idle seconds before a keep-alive comment; 0 disables them
+    pub keep_alive_interval_secs: Option<u64>,
+    /// Send `replay_start`/`replay_end` events around replayed events
+    pub replay_markers: Option<bool>,
+    /// Per-connection duplicate suppression window
+    pub dedup_window: Option<usize>,
+    /// Concurrent fire-and-forget dispatches
+    pub dispatch_concurrency: Option<usize>,
+    /// Messages queued per dispatch worker and priority
+    pub dispatch_queue_capacity: Option<usize>,
+    /// What happens to messages sent to a full dispatch queue
+    pub dispatch_overflow: Option<QueueOverflow>,
+    /// Per-channel publish quota
+    pub throttle: Option<ThrottleConfig>,
+    /// Subscriber authentication
+    pub auth: AuthConfig,
+    /// Proxies trusted to set `X-Forwarded-For`, as addresses or CIDR blocks
+    /// (default: none)
+    pub trusted_proxies: Option<TrustedProxies>,
+    /// Built-in storage used by [`GatewayBuilder::from_config`]
+    pub storage: StorageConfig,
+    /// Stream ID generation (default: the storage's)
+    pub ids: Option<IdConfig>,
+    /// Built-in source used by [`GatewayBuilder::from_config`]
+    pub source: SourceConfig,
+    /// Channel policies by channel ID or `prefix*`
+    pub channels: BTreeMap<String, ChannelConfig>,
+    /// Channel aliases and rewrites, tried in order
+    pub channel_rewrites: Vec<RewriteRule>,
+    /// Log filter (e.g. `info,sse_gateway=debug`), handed to
+    /// [`LiveConfig::on_log_level`] when it changes
+    pub log_level: Option<String>,
+}
+
+/// Heartbeat settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    /// Seconds between heartbeats
+    pub interval_secs: Option<u64>,
+    /// Named event (default) or SSE comment
+    pub style: HeartbeatStyle,
+    /// Event name, or comment text (default: `heartbeat`)
+    pub name: Option<String>,
+    /// Payload template of event heartbeats; `{ts}` is the timestamp
+    pub template: Option<String>,
+    /// Skip connections that received an event during the interval
+    pub idle_only: bool,
+}
+
+/// How heartbeats are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartbeatStyle {
+    #[default]
+    Event,
+    Comment,
+}
+
+/// Per-channel publish quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThrottleConfig {
+    pub events_per_sec: Option<u32>,
+    pub bytes_per_sec: Option<u64>,
+    /// What happens to events over the quota
+    #[serde(default = "default_throttle_action")]
+    pub action: ThrottleAction,
+    /// Longest a `delay` may hold an event
+    pub max_delay_ms: Option<u64>,
+}
+
+fn default_throttle_action() -> ThrottleAction {
+    ThrottleAction::Drop
+}
+
+/// Subscriber authentication
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AuthConfig {
+    /// Anyone may connect
+    #[default]
+    None,
+    /// Require one of `tokens` as a bearer token, or in the `query_param`
+    /// query parameter for browsers' `EventSource`
+    Bearer {
+        tokens: Vec<String>,
+        #[serde(default)]
+        query_param: Option<String>,
+    },
+}
+
+/// Built-in storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StorageConfig {
+    /// [`MemoryStorage`] keeping `max_per_channel` events per channel
+    Memory {
+        #[serde(default = "default_max_per_channel")]
+        max_per_channel: usize,
+    },
+    /// No replay
+    None,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::Memory {
+            max_per_channel: default_max_per_channel(),
+        }
+    }
+}
+
+fn default_max_per_channel() -> usize {
+    100
+}
+
+/// Stream ID generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum IdConfig {
+    /// [`StreamIds`], numbered within each millisecond
+    Stream,
+    /// [`SnowflakeIds`] for `node`, unique per instance
+    Snowflake { node: u16 },
+}
+
+/// Built-in source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SourceConfig {
+    /// No source; events arrive through the API or programmatically
+    #[default]
+    None,
+    /// Accept events over HTTP at `path` (see [`PushEndpoint`](crate::PushEndpoint))
+    Push {
+        #[serde(default = "default_push_path")]
+        path: String,
+    },
+}
+
+fn default_push_path() -> String {
+    "/push".to_string()
+}
+
+/// Config file syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Format implied by a file extension (`.yaml`, `.yml`, `.toml`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Read `path`, then apply `SSE_GATEWAY_*` environment overrides
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown config format for {} (expected .yaml, .yml or .toml)",
+                path.display()
+            )
+        })?;
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Self::resolve(parse(&contents, format)?, std::env::vars())
+    }
+
+    /// Read the file named by `SSE_GATEWAY_CONFIG` if set, then apply
+    /// `SSE_GATEWAY_*` environment overrides
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => Self::load(path),
+            None => Self::resolve(serde_json::Value::Null, std::env::vars()),
+        }
+    }
+
+    /// Parse `contents` and apply overrides from `vars` (`SSE_GATEWAY_*` entries)
+    pub fn parse<I>(contents: &str, format: ConfigFormat, vars: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        Self::resolve(parse(contents, format)?, vars)
+    }
+
+    fn resolve<I>(mut value: serde_json::Value, vars: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        if value.is_null() {
+            value = serde_json::Value::Object(Default::default());
+        }
+        for (key, raw) in vars {
+            let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if key == CONFIG_PATH_ENV || name.is_empty() {
+                continue;
+            }
+            let path: Vec<String> = name.split("__").map(str::to_lowercase).collect();
+            let parsed = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+            set_path(&mut value, &path, parsed)
+                .map_err(|e| anyhow::anyhow!("Invalid override {}: {}", key, e))?;
+        }
+        let config: Self = serde_json::from_value(value)
+            .map_err(|e| anyhow::anyhow!("Invalid gateway config: {}", e))?;
+        if let Some(IdConfig::Snowflake { node }) = config.ids {
+            if node > SnowflakeIds::MAX_NODE {
+                anyhow::bail!("Invalid gateway config: snowflake node must be at most {}", SnowflakeIds::MAX_NODE);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The configured built-in storage
+    pub fn build_storage(&self) -> BuiltinStorage {
+        match &self.storage {
+            StorageConfig::Memory { max_per_channel } => {
+                BuiltinStorage::Memory(MemoryStorage::new(*max_per_channel))
+            }
+            StorageConfig::None => BuiltinStorage::None(NoopStorage),
+        }
+    }
+
+    /// Apply every setting except storage to `builder`
+    ///
+    /// A push source enables the push endpoint; the builder's own source is kept.
+    pub fn apply<Source: MessageSource, Storage: MessageStorage>(
+        &self,
+        mut builder: GatewayBuilder<Source, Storage>,
+    ) -> GatewayBuilder<Source, Storage> {
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(id) = &self.instance_id {
+            builder = builder.instance_id(id);
+        }
+        if let Some(path) = &self.sse_path {
+            builder = builder.sse_path(path);
+        }
+        if let Some(enable) = self.dashboard {
+            builder = builder.dashboard(enable);
+        }
+        if let Some(enable) = self.metrics {
+            builder = builder.metrics(enable);
+        }
+        if let Some(heartbeat) = &self.heartbeat {
+            if let Some(secs) = heartbeat.interval_secs {
+                builder = builder.heartbeat_interval(Duration::from_secs(secs));
+            }
+            builder = builder.heartbeat(heartbeat.build());
+        }
+        if let Some(secs) = self.cleanup_interval_secs {
+            builder = builder.cleanup_interval(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.keep_alive_interval_secs {
+            builder = builder.keep_alive_interval(Duration::from_secs(secs));
+        }
+        if let Some(enable) = self.replay_markers {
+            builder = builder.replay_markers(enable);
+        }
+        if let Some(size) = self.dedup_window {
+            builder = builder.dedup_window(size);
+        }
+        if let Some(limit) = self.dispatch_concurrency {
+            builder = builder.dispatch_concurrency(limit);
+        }
+        if let Some(capacity) = self.dispatch_queue_capacity {
+            builder = builder.dispatch_queue_capacity(capacity);
+        }
+        if let Some(overflow) = self.dispatch_overflow {
+            builder = builder.dispatch_overflow(overflow);
+        }
+        if let Some(throttle) = &self.throttle {
+            builder = builder.throttle(throttle.policy());
+        }
+        match self.ids {
+            Some(IdConfig::Stream) => builder = builder.id_generator(StreamIds::new()),
+            Some(IdConfig::Snowflake { node }) => builder = builder.id_generator(SnowflakeIds::new(node)),
+            None => {}
+        }
+        if self.auth != AuthConfig::None {
+            let auth = self.auth.clone();
+            builder = builder.auth(move |req: AuthRequest| std::future::ready(auth.check(&req)));
+        }
+        if let Some(proxies) = &self.trusted_proxies {
+            builder = builder.trusted_proxies(proxies.clone());
+        }
+        if let SourceConfig::Push { path } = &self.source {
+            builder = builder.enable_push_endpoint(path);
+        }
+        for (key, config) in &self.channels {
+            builder = builder.channel_config(key, config.clone());
+        }
+        for rule in &self.channel_rewrites {
+            builder = builder.channel_rewrite(rule.clone());
+        }
+        builder
+    }
+}
+
+impl AuthConfig {
+    fn check(&self, req: &AuthRequest) -> AuthResponse {
+        let AuthConfig::Bearer {
+            tokens,
+            query_param,
+        } = self
+        else {
+            return None;
+        };
+        let token = req
+            .bearer_token()
+            .or_else(|| query_param.as_deref().and_then(|p| req.query_param(p)));
+        match token {
+            Some(token) if token_matches(tokens, token) => None,
+            _ => Some(deny(StatusCode::UNAUTHORIZED, "Invalid or missing token")),
+        }
+    }
+}
+
+/// Whether `token` is one of `tokens`, compared in constant time
+fn token_matches(tokens: &[String], token: &str) -> bool {
+    // Every token is checked, so timing reveals neither how much of a token
+    // matched nor which one did
+    tokens
+        .iter()
+        .fold(Choice::from(0), |found, t| found | t.as_bytes().ct_eq(token.as_bytes()))
+        .into()
+}
+
+impl HeartbeatConfig {
+    fn build(&self) -> Heartbeat {
+        let name = self.name.clone().unwrap_or_else(|| "heartbeat".to_string());
+        let heartbeat = match self.style {
+            HeartbeatStyle::Event => Heartbeat::event(name),
+            HeartbeatStyle::Comment => Heartbeat::comment(name),
+        };
+        let heartbeat = match &self.template {
+            Some(template) => heartbeat.template(template),
+            None => heartbeat,
+        };
+        heartbeat.idle_only(self.idle_only)
+    }
+}
+
+impl ThrottleConfig {
+    fn policy(&self) -> ThrottlePolicy {
+        let mut policy = ThrottlePolicy::new(self.action);
+        policy.events_per_sec = self.events_per_sec;
+        policy.bytes_per_sec = self.bytes_per_sec;
+        if let Some(ms) = self.max_delay_ms {
+            policy.max_delay = Duration::from_millis(ms);
+        }
+        policy
+    }
+}
+
+type LogLevelFn = Arc<dyn Fn(&str) -> anyhow::Result<()> + Send + Sync>;
+
+/// What a reload updates in a running gateway
+struct Targets {
+    throttle: Option<Throttle>,
+    channels: ChannelConfigs,
+    /// Channel keys set from the config, removed again when they disappear from it
+    keys: BTreeSet<String>,
+    rewrites: ChannelRewrites,
+}
+
+/// A [`GatewayConfig`] that can be replaced while the gateway runs
+///
+/// Register it with [`GatewayBuilder::live_config`]. Clones share the
+/// current config; see the [module docs](self#reloading) for what a reload
+/// changes.
+#[derive(Clone)]
+pub struct LiveConfig {
+    current: Arc<ArcSwap<GatewayConfig>>,
+    path: Option<PathBuf>,
+    watch_interval: Option<Duration>,
+    log_level: Option<LogLevelFn>,
+    targets: Arc<Mutex<Option<Targets>>>,
+}
+
+impl LiveConfig {
+    /// Start from `config`; only [`set`](Self::set) can change it
+    pub fn new(config: GatewayConfig) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(config)),
+            path: None,
+            watch_interval: None,
+            log_level: None,
+            targets: Arc::default(),
+        }
+    }
+
+    /// Load `path` (see [`GatewayConfig::load`]) and re-read it on reload
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let mut live = Self::new(GatewayConfig::load(&path)?);
+        live.path = Some(path);
+        Ok(live)
+    }
+
+    /// Like [`GatewayConfig::from_env`]; reloads re-read the file named by
+    /// `SSE_GATEWAY_CONFIG`, if there is one
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => Self::load(path),
+            None => Ok(Self::new(GatewayConfig::from_env()?)),
+        }
+    }
+
+    /// Also reload when the file's modification time changes, checked every `interval`
+    pub fn watch(mut self, interval: Duration) -> Self {
+        self.watch_interval = Some(interval);
+        self
+    }
+
+    /// Call `f` with the new `log_level` whenever a reload changes it
+    ///
+    /// The gateway doesn't own the tracing subscriber; `f` typically feeds a
+    /// `tracing_subscriber::reload` handle.
+    pub fn on_log_level<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> anyhow::Result<()> + Send + Sync + 'static,
+    {
+        self.log_level = Some(Arc::new(f));
+        self
+    }
+
+    /// The config in effect
+    pub fn current(&self) -> Arc<GatewayConfig> {
+        self.current.load_full()
+    }
+
+    /// Re-read the config file and apply it
+    ///
+    /// On error the previous config stays in effect.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No config file to reload"))?;
+        self.set(GatewayConfig::load(path)?);
+        tracing::info!(path = %path.display(), "Configuration reloaded");
+        Ok(())
+    }
+
+    /// Replace the config, applying its reloadable settings
+    pub fn set(&self, config: GatewayConfig) {
+        let previous = self.current.swap(Arc::new(config));
+        let config = self.current();
+        if startup_only(&previous) != startup_only(&config) {
+            tracing::warn!("Configuration changed settings that only take effect after a restart");
+        }
+
+        if let Some(targets) = self.targets.lock().unwrap().as_mut() {
+            if let Some(throttle) = &targets.throttle {
+                throttle.set_policy(throttle_policy(&config));
+            }
+            for key in &targets.keys {
+                if !config.channels.contains_key(key) {
+                    targets.channels.remove(key);
+                }
+            }
+            for (key, channel) in &config.channels {
+                targets.channels.set(key, channel.clone());
+            }
+            targets.keys = config.channels.keys().cloned().collect();
+            if previous.channel_rewrites != config.channel_rewrites {
+                targets.rewrites.set_rules(config.channel_rewrites.clone());
+            }
+        }
+
+        if let (Some(f), Some(level)) = (&self.log_level, &config.log_level) {
+            if previous.log_level.as_ref() != Some(level) {
+                if let Err(e) = f(level) {
+                    tracing::warn!(error = %e, level = %level, "Failed to apply log level");
+                }
+            }
+        }
+    }
+
+    /// Apply the current config to `builder`, with auth and throttling
+    /// following later reloads
+    pub(crate) fn apply<Source: MessageSource, Storage: MessageStorage>(
+        &self,
+        builder: GatewayBuilder<Source, Storage>,
+    ) -> GatewayBuilder<Source, Storage> {
+        let config = self.current();
+        let live = self.current.clone();
+        config
+            .apply(builder)
+            .throttle(throttle_policy(&config))
+            .auth(move |req: AuthRequest| std::future::ready(live.load().auth.check(&req)))
+    }
+
+    /// Connect reloads to the running gateway's throttle, channel configs
+    /// and channel rewrites
+    pub(crate) fn attach(&self, throttle: Option<Throttle>, channels: ChannelConfigs, rewrites: ChannelRewrites) {
+        let keys = self.current().channels.keys().cloned().collect();
+        *self.targets.lock().unwrap() = Some(Targets {
+            throttle,
+            channels,
+            keys,
+            rewrites,
+        });
+    }
+
+    /// Reload on SIGHUP and, if watching, when the file changes
+    pub(crate) fn spawn_watcher(&self, cancel: CancellationToken) -> tokio::task::JoinHandle<()> {
+        let live = self.clone();
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            let mut hangup = match tokio::signal::unix::signal(
+                tokio::signal::unix::SignalKind::hangup(),
+            ) {
+                Ok(signal) => Some(signal),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to install SIGHUP handler for config reload");
+                    None
+                }
+            };
+
+            let mut interval = live.watch_interval.map(|period| {
+                let mut interval = tokio::time::interval(period);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            });
+            let mut modified = live.modified();
+
+            loop {
+                let tick = async {
+                    match interval.as_mut() {
+                        Some(interval) => {
+                            interval.tick().await;
+                        }
+                        None => std::future::pending().await,
+                    }
+                };
+
+                #[cfg(unix)]
+                let sighup = async {
+                    match hangup.as_mut() {
+                        Some(signal) => {
+                            signal.recv().await;
+                        }
+                        None => std::future::pending().await,
+                    }
+                };
+                #[cfg(not(unix))]
+                let sighup = std::future::pending::<()>();
+
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tick => {
+                        let current = live.modified();
+                        if current == modified {
+                            continue;
+                        }
+                        modified = current;
+                    }
+                    _ = sighup => tracing::info!("Received SIGHUP, reloading configuration"),
+                }
+
+                if let Err(e) = live.reload() {
+                    tracing::warn!(error = %e, "Configuration reload failed, keeping previous configuration");
+                }
+            }
+        })
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(self.path.as_ref()?).ok()?.modified().ok()
+    }
+
+    /// `POST /admin/reload`
+    pub(crate) fn router(&self) -> Router {
+        Router::new()
+            .route("/admin/reload", post(reload))
+            .with_state(self.clone())
+    }
+}
+
+async fn reload(State(live): State<LiveConfig>) -> Response {
+    match live.reload() {
+        Ok(()) => Json(serde_json::json!({ "reloaded": true })).into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Configuration reload failed, keeping previous configuration");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "reloaded": false, "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The throttle policy of `config`; unlimited if it sets none, so a reload
+/// can add one. An unlimited throttle allows everything without tracking
+/// channels.
+fn throttle_policy(config: &GatewayConfig) -> ThrottlePolicy {
+    config
+        .throttle
+        .as_ref()
+        .map(ThrottleConfig::policy)
+        .unwrap_or_else(|| ThrottlePolicy::new(ThrottleAction::Drop))
+}
+
+/// `config` without the settings a reload can change
+fn startup_only(config: &GatewayConfig) -> GatewayConfig {
+    GatewayConfig {
+        throttle: None,
+        channels: BTreeMap::new(),
+        channel_rewrites: Vec::new(),
+        auth: AuthConfig::None,
+        log_level: None,
+        ..config.clone()
+    }
+}
+
+fn parse(contents: &str, format: ConfigFormat) -> anyhow::Result<serde_json::Value> {
+    let value = match format {
+        ConfigFormat::Yaml => serde_yaml::from_str(contents)?,
+        ConfigFormat::Toml => toml::from_str(contents)?,
+    };
+    Ok(value)
+}
+
+/// Set `value` at `path`, creating objects along the way
+fn set_path(
+    root: &mut serde_json::Value,
+    path: &[String],
+    value: serde_json::Value,
+) -> anyhow::Result<()> {
+    let (last, parents) = path.split_last().expect("path is never empty");
+    let mut node = root;
+    for key in parents {
+        let object = node
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not a table", key))?;
+        node = object
+            .entry(key.clone())
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if node.is_null() {
+            *node = serde_json::Value::Object(Default::default());
+        }
+    }
+    node.as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("`{}` is not in a table", last))?
+        .insert(last.clone(), value);
+    Ok(())
+}
+
+/// The storage selected by [`StorageConfig`]
+#[derive(Clone)]
+pub enum BuiltinStorage {
+    Memory(MemoryStorage),
+    None(NoopStorage),
+}
+
+#[async_trait]
+impl MessageStorage for BuiltinStorage {
+    fn generate_id(&self) -> String {
+        match self {
+            Self::Memory(s) => s.generate_id(),
+            Self::None(s) => s.generate_id(),
+        }
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        match self {
+            Self::Memory(s) => s.store(channel_id, stream_id, event).await,
+            Self::None(s) => s.store(channel_id, stream_id, event).await,
+        }
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        match self {
+            Self::Memory(s) => s.get_messages_after(channel_id, after_id).await,
+            Self::None(s) => s.get_messages_after(channel_id, after_id).await,
+        }
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        match self {
+            Self::Memory(s) => s.latest_id(channel_id).await,
+            Self::None(s) => s.latest_id(channel_id).await,
+        }
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        match self {
+            Self::Memory(s) => s.recent_messages(channel_id, limit).await,
+            Self::None(s) => s.recent_messages(channel_id, limit).await,
+        }
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        match self {
+            Self::Memory(s) => s.save_cursor(channel_id, consumer_id, stream_id).await,
+            Self::None(s) => s.save_cursor(channel_id, consumer_id, stream_id).await,
+        }
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        match self {
+            Self::Memory(s) => s.get_cursor(channel_id, consumer_id).await,
+            Self::None(s) => s.get_cursor(channel_id, consumer_id).await,
+        }
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        match self {
+            Self::Memory(s) => s.save_session(token, session, ttl).await,
+            Self::None(s) => s.save_session(token, session, ttl).await,
+        }
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        match self {
+            Self::Memory(s) => s.load_session(token).await,
+            Self::None(s) => s.load_session(token).await,
+        }
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        match self {
+            Self::Memory(s) => s.cleanup_expired_sessions().await,
+            Self::None(s) => s.cleanup_expired_sessions().await,
+        }
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        match self {
+            Self::Memory(s) => s.record_channel_usage(channel_id, delta).await,
+            Self::None(s) => s.record_channel_usage(channel_id, delta).await,
+        }
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Vec<DailyUsage> {
+        match self {
+            Self::Memory(s) => s.channel_usage(channel_id, from, to).await,
+            Self::None(s) => s.channel_usage(channel_id, from, to).await,
+        }
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        match self {
+            Self::Memory(s) => s.snapshot(channel_id).await,
+            Self::None(s) => s.snapshot(channel_id).await,
+        }
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        match self {
+            Self::Memory(s) => s.compact(channel_id, up_to_id).await,
+            Self::None(s) => s.compact(channel_id, up_to_id).await,
+        }
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        match self {
+            Self::Memory(s) => s.purge(channel_id).await,
+            Self::None(s) => s.purge(channel_id).await,
+        }
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        match self {
+            Self::Memory(s) => s.purge_before(channel_id, before).await,
+            Self::None(s) => s.purge_before(channel_id, before).await,
+        }
+    }
+
+    async fn is_available(&self) -> bool {
+        match self {
+            Self::Memory(s) => s.is_available().await,
+            Self::None(s) => s.is_available().await,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Memory(s) => s.name(),
+            Self::None(s) => s.name(),
+        }
+    }
+}
@@ -1,10 +1,19 @@
 //! SSE Connection types
 
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use crate::event::SseEvent;
+use crate::auth::EventTypeFilter;
+use crate::channel_config::Backpressure;
+use crate::dedup::DedupWindow;
+use crate::event::{Priority, SseEvent};
 
 /// Metadata about a connection
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConnectionMetadata {
     /// When the connection was established
     pub connected_at: chrono::DateTime<chrono::Utc>,
@@ -12,21 +21,175 @@ pub struct ConnectionMetadata {
     pub instance_id: String,
     /// Client IP address (if available)
     pub client_ip: Option<String>,
+    /// Socket peer address, which is the proxy when behind one (if available)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_addr: Option<SocketAddr>,
     /// User agent (if available)
     pub user_agent: Option<String>,
+    /// Authenticated identity (if an identity extractor is configured)
+    pub identity: Option<String>,
+    /// Custom key/value attributes (e.g. tenant, device type) for targeting
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, String>,
+    /// Consumer group; each event on the channel reaches one member of a group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Event types the connection may receive (if restricted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_types: Option<EventTypeFilter>,
+}
+
+/// Why a connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// The client went away
+    ClientDisconnect,
+    /// The gateway shut down
+    ServerShutdown,
+    /// Closed by the application or an operator
+    /// (e.g. [`ConnectionManager::unregister`](crate::ConnectionManager::unregister))
+    Kicked,
+    /// Closed by the application for inactivity
+    IdleTimeout,
+    /// The send queue was full under [`Backpressure::Disconnect`]
+    SendQueueOverflow,
+    /// Closed by the application because the client's credentials expired
+    AuthExpired,
+}
+
+impl CloseReason {
+    /// Snake-case name, as used in JSON and metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::ClientDisconnect => "client_disconnect",
+            CloseReason::ServerShutdown => "server_shutdown",
+            CloseReason::Kicked => "kicked",
+            CloseReason::IdleTimeout => "idle_timeout",
+            CloseReason::SendQueueOverflow => "send_queue_overflow",
+            CloseReason::AuthExpired => "auth_expired",
+        }
+    }
+}
+
+impl std::fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Delivery counters of one connection, or the totals of a channel
+///
+/// Shared by every clone of the connection, so they keep counting after it
+/// is looked up through the manager. A connection's counters also add to
+/// those of its channel.
+#[derive(Debug, Default)]
+pub struct ConnectionCounters {
+    events_sent: AtomicU64,
+    bytes_written: AtomicU64,
+    events_dropped: AtomicU64,
+    /// Milliseconds since the Unix epoch of the last event written; 0 if none
+    last_event_at: AtomicI64,
+    /// Totals of the connection's channel
+    channel: Option<Arc<ConnectionCounters>>,
+}
+
+/// Point-in-time copy of [`ConnectionCounters`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendStats {
+    pub events_sent: u64,
+    pub bytes_written: u64,
+    pub events_dropped: u64,
+    /// When the last event was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_event_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ConnectionCounters {
+    /// Counters that also add to `channel`
+    pub(crate) fn for_channel(channel: Arc<ConnectionCounters>) -> Self {
+        Self {
+            channel: Some(channel),
+            ..Self::default()
+        }
+    }
+
+    /// Events written to the transport, including replayed ones
+    pub fn events_sent(&self) -> u64 {
+        self.events_sent.load(Ordering::Relaxed)
+    }
+
+    /// Bytes of events and comments written, before compression
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// Events not queued because the send buffer was full
+    pub fn events_dropped(&self) -> u64 {
+        self.events_dropped.load(Ordering::Relaxed)
+    }
+
+    /// When the last event was written, if any
+    pub fn last_event_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.last_event_at.load(Ordering::Relaxed) {
+            0 => None,
+            millis => chrono::DateTime::from_timestamp_millis(millis),
+        }
+    }
+
+    /// Copy all counters
+    pub fn snapshot(&self) -> SendStats {
+        SendStats {
+            events_sent: self.events_sent(),
+            bytes_written: self.bytes_written(),
+            events_dropped: self.events_dropped(),
+            last_event_at: self.last_event_at(),
+        }
+    }
+
+    /// Record an event (or comment, with `event: false`) of `bytes` written
+    #[cfg(feature = "server")]
+    pub(crate) fn record_write(&self, event: bool, bytes: usize) {
+        if event {
+            self.events_sent.fetch_add(1, Ordering::Relaxed);
+            self.last_event_at
+                .store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+        }
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+        if let Some(channel) = &self.channel {
+            channel.record_write(event, bytes);
+        }
+    }
+
+    pub(crate) fn record_drop(&self) {
+        self.events_dropped.fetch_add(1, Ordering::Relaxed);
+        if let Some(channel) = &self.channel {
+            channel.record_drop();
+        }
+    }
 }
 
 /// Represents an SSE connection
 #[derive(Debug)]
 pub struct SseConnection {
     /// Unique connection ID
-    pub id: String,
+    pub id: Arc<str>,
     /// Channel ID this connection is subscribed to
-    pub channel_id: String,
+    pub channel_id: Arc<str>,
     /// Sender for pushing events to this connection
     pub sender: mpsc::Sender<SseEvent>,
     /// Connection metadata
     pub metadata: ConnectionMetadata,
+    /// Recently sent event keys, if deduplication is enabled
+    pub(crate) dedup: Option<Arc<DedupWindow>>,
+    /// What to do when the send buffer is full
+    pub(crate) backpressure: Backpressure,
+    /// Separate queue for high priority events, if the reader drains one
+    pub(crate) urgent: Option<mpsc::Sender<SseEvent>>,
+    /// Events and bytes sent, events dropped
+    pub(crate) counters: Arc<ConnectionCounters>,
+    /// Set when the gateway closes the connection; unset means the client left
+    pub(crate) close_reason: Arc<OnceLock<CloseReason>>,
 }
 
 impl SseConnection {
@@ -39,27 +202,116 @@ impl SseConnection {
     ) -> (Self, mpsc::Receiver<SseEvent>) {
         let (sender, receiver) = mpsc::channel(100);
         let connection = Self {
-            id: uuid::Uuid::new_v4().to_string(),
-            channel_id,
+            id: uuid::Uuid::new_v4().to_string().into(),
+            channel_id: channel_id.into(),
             sender,
             metadata: ConnectionMetadata {
                 connected_at: chrono::Utc::now(),
                 instance_id,
                 client_ip,
+                peer_addr: None,
                 user_agent,
+                identity: None,
+                attributes: HashMap::new(),
+                group: None,
+                event_types: None,
             },
+            dedup: None,
+            backpressure: Backpressure::default(),
+            urgent: None,
+            counters: Arc::default(),
+            close_reason: Arc::default(),
         };
         (connection, receiver)
     }
 
+    /// Record the socket peer address
+    pub(crate) fn with_peer_addr(mut self, peer_addr: Option<SocketAddr>) -> Self {
+        self.metadata.peer_addr = peer_addr;
+        self
+    }
+
+    /// Record the authenticated identity
+    pub(crate) fn with_identity(mut self, identity: Option<String>) -> Self {
+        self.metadata.identity = identity;
+        self
+    }
+
+    /// Record custom attributes
+    pub(crate) fn with_attributes(mut self, attributes: HashMap<String, String>) -> Self {
+        self.metadata.attributes = attributes;
+        self
+    }
+
+    /// Restrict the event types sent to this connection
+    pub(crate) fn with_event_types(mut self, event_types: Option<EventTypeFilter>) -> Self {
+        self.metadata.event_types = event_types;
+        self
+    }
+
+    /// Whether events of `event_type` may be sent to this connection
+    pub fn allows_event_type(&self, event_type: &str) -> bool {
+        self.metadata
+            .event_types
+            .as_ref()
+            .is_none_or(|filter| filter.allows(event_type))
+    }
+
+    /// Value of attribute `key`, if set
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.metadata.attributes.get(key).map(String::as_str)
+    }
+
+    /// Consumer group this connection belongs to, if any
+    pub fn group(&self) -> Option<&str> {
+        self.metadata.group.as_deref()
+    }
+
+    /// Add this connection's counters to the channel totals `channel`
+    pub(crate) fn with_channel_counters(mut self, channel: Arc<ConnectionCounters>) -> Self {
+        self.counters = Arc::new(ConnectionCounters::for_channel(channel));
+        self
+    }
+
+    /// Set the slow consumer policy
+    pub(crate) fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Suppress events whose key was among the last `capacity` sent
+    pub(crate) fn with_dedup_window(mut self, capacity: usize) -> Self {
+        self.dedup = (capacity > 0).then(|| Arc::new(DedupWindow::new(capacity)));
+        self
+    }
+
+    /// Number of duplicate events suppressed on this connection
+    pub fn duplicates(&self) -> u64 {
+        self.dedup.as_ref().map_or(0, |dedup| dedup.duplicates())
+    }
+
+    /// Delivery counters of this connection
+    pub fn counters(&self) -> &ConnectionCounters {
+        &self.counters
+    }
+
     /// Check if the connection is still active
     pub fn is_active(&self) -> bool {
         !self.sender.is_closed()
     }
 
+    /// Queue that `event` is sent on: high priority events skip the
+    /// regular queue when the connection has an urgent one
+    pub(crate) fn sender_for(&self, event: &SseEvent) -> &mpsc::Sender<SseEvent> {
+        match &self.urgent {
+            Some(urgent) if event.priority == Priority::High => urgent,
+            _ => &self.sender,
+        }
+    }
+
     /// Send an event to this connection
     pub async fn send(&self, event: SseEvent) -> bool {
-        self.sender.send(event).await.is_ok()
+        self.sender_for(&event).send(event).await.is_ok()
     }
 }
 
@@ -70,6 +322,11 @@ impl Clone for SseConnection {
             channel_id: self.channel_id.clone(),
             sender: self.sender.clone(),
             metadata: self.metadata.clone(),
+            dedup: self.dedup.clone(),
+            backpressure: self.backpressure,
+            urgent: self.urgent.clone(),
+            counters: self.counters.clone(),
+            close_reason: self.close_reason.clone(),
         }
     }
 }
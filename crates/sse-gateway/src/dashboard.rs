@@ -0,0 +1,277 @@
+//! Operator dashboard
+//!
+//! The dashboard page, its stylesheet, script and translations are embedded
+//! in the crate and served under `/dashboard`. [`DashboardConfig`] sets the
+//! branding, default language and theme, and adds or overrides translations:
+//!
+//! ```rust,ignore
+//! use sse_gateway::{DashboardConfig, DashboardTheme};
+//!
+//! Gateway::builder()
+//!     .dashboard_config(
+//!         DashboardConfig::new()
+//!             .title("Acme Realtime")
+//!             .logo_url("https://acme.example/logo.svg")
+//!             .accent_color("#e11d48")
+//!             .default_language("zh")
+//!             .theme(DashboardTheme::Auto),
+//!     )
+//! ```
+//!
+//! English (`en`) and Chinese (`zh`) ship with the crate. The page picks the
+//! `?lang=` query parameter, then the user's last choice, then the browser
+//! language, then the configured default; missing keys fall back to the
+//! default language.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+
+const PAGE: &str = include_str!("dashboard/index.html");
+const STYLESHEET: &str = include_str!("dashboard/dashboard.css");
+const SCRIPT: &str = include_str!("dashboard/dashboard.js");
+
+/// Built-in translations: (language code, JSON bundle)
+const BUILTIN_TRANSLATIONS: &[(&str, &str)] = &[
+    ("en", include_str!("dashboard/i18n/en.json")),
+    ("zh", include_str!("dashboard/i18n/zh.json")),
+];
+
+/// Translation key holding a language's display name
+const LANGUAGE_NAME_KEY: &str = "language.name";
+
+/// Color scheme the dashboard starts in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DashboardTheme {
+    #[default]
+    Dark,
+    Light,
+    /// Follow the browser's `prefers-color-scheme`
+    Auto,
+}
+
+/// Branding, language and theme of the dashboard
+///
+/// Users can still switch language and theme in the page; their choice is
+/// remembered by the browser.
+#[derive(Debug, Clone)]
+pub struct DashboardConfig {
+    title: String,
+    logo_url: Option<String>,
+    accent_color: Option<String>,
+    default_language: String,
+    theme: DashboardTheme,
+    translations: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            title: "SSE Gateway".to_string(),
+            logo_url: None,
+            accent_color: None,
+            default_language: "en".to_string(),
+            theme: DashboardTheme::default(),
+            translations: BTreeMap::new(),
+        }
+    }
+}
+
+impl DashboardConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Page title and heading (default: "SSE Gateway")
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Logo shown next to the title
+    pub fn logo_url(mut self, url: impl Into<String>) -> Self {
+        self.logo_url = Some(url.into());
+        self
+    }
+
+    /// Accent color of buttons and charts, as a CSS color
+    pub fn accent_color(mut self, color: impl Into<String>) -> Self {
+        self.accent_color = Some(color.into());
+        self
+    }
+
+    /// Language used when the browser asks for none of the available ones (default: `en`)
+    pub fn default_language(mut self, code: impl Into<String>) -> Self {
+        self.default_language = code.into();
+        self
+    }
+
+    /// Theme before the user picks one (default: dark)
+    pub fn theme(mut self, theme: DashboardTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Add a language or override strings of an existing one
+    ///
+    /// Keys are those of the built-in `en` bundle; `language.name` sets the
+    /// name shown in the language picker.
+    pub fn translations<K, V>(
+        mut self,
+        code: impl Into<String>,
+        messages: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.translations
+            .entry(code.into())
+            .or_default()
+            .extend(messages.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+}
+
+/// Settings passed to the page script
+#[derive(Serialize)]
+struct PageConfig<'a> {
+    languages: Vec<Language<'a>>,
+    default_language: &'a str,
+    default_theme: DashboardTheme,
+    accent: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Language<'a> {
+    code: &'a str,
+    name: &'a str,
+}
+
+/// Rendered dashboard page and translation bundles
+#[derive(Clone)]
+pub(crate) struct Dashboard {
+    page: Arc<str>,
+    /// Language code -> JSON bundle
+    bundles: Arc<BTreeMap<String, String>>,
+}
+
+impl Dashboard {
+    pub(crate) fn new(config: &DashboardConfig) -> Self {
+        let mut languages: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for (code, bundle) in BUILTIN_TRANSLATIONS {
+            let messages = serde_json::from_str(bundle)
+                .expect("built-in dashboard translations are valid JSON");
+            languages.insert(code.to_string(), messages);
+        }
+        for (code, messages) in &config.translations {
+            languages
+                .entry(code.clone())
+                .or_default()
+                .extend(messages.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        let default_language = if languages.contains_key(&config.default_language) {
+            config.default_language.as_str()
+        } else {
+            tracing::warn!(language = %config.default_language, "Unknown dashboard language, using en");
+            "en"
+        };
+        let page_config = PageConfig {
+            languages: languages
+                .iter()
+                .map(|(code, messages)| Language {
+                    code,
+                    name: messages.get(LANGUAGE_NAME_KEY).map_or(code, String::as_str),
+                })
+                .collect(),
+            default_language,
+            default_theme: config.theme,
+            accent: config.accent_color.as_deref(),
+        };
+        // Keep the JSON from closing the inline <script>
+        let page_config = serde_json::to_string(&page_config)
+            .expect("dashboard config serializes")
+            .replace('<', "\\u003c");
+        let logo = config
+            .logo_url
+            .as_deref()
+            .map(|url| format!(r#"<img src="{}" alt="">"#, escape_html(url)))
+            .unwrap_or_default();
+        let theme = match config.theme {
+            DashboardTheme::Light => "light",
+            _ => "dark",
+        };
+        let page = PAGE
+            .replace("{{lang}}", default_language)
+            .replace("{{theme}}", theme)
+            .replace("{{title}}", &escape_html(&config.title))
+            .replace("{{logo}}", &logo)
+            .replace("{{config}}", &page_config);
+
+        let bundles = languages
+            .into_iter()
+            .map(|(code, messages)| {
+                let json = serde_json::to_string(&messages).expect("translations serialize");
+                (code, json)
+            })
+            .collect();
+        Self {
+            page: page.into(),
+            bundles: Arc::new(bundles),
+        }
+    }
+
+    /// `/dashboard` and its assets
+    pub(crate) fn router(&self) -> Router {
+        Router::new()
+            .route("/dashboard", get(page))
+            .route("/dashboard/assets/dashboard.css", get(stylesheet))
+            .route("/dashboard/assets/dashboard.js", get(script))
+            .route("/dashboard/assets/i18n/{file}", get(translations))
+            .with_state(self.clone())
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+async fn page(State(dashboard): State<Dashboard>) -> Html<String> {
+    Html(dashboard.page.to_string())
+}
+
+async fn stylesheet() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+        STYLESHEET,
+    )
+}
+
+async fn script() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        SCRIPT,
+    )
+}
+
+async fn translations(State(dashboard): State<Dashboard>, Path(file): Path<String>) -> Response {
+    let bundle = file
+        .strip_suffix(".json")
+        .and_then(|code| dashboard.bundles.get(code));
+    match bundle {
+        Some(json) => ([(header::CONTENT_TYPE, "application/json")], json.clone()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
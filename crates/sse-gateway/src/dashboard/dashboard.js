@@ -0,0 +1,179 @@
+(() => {
+    const config = window.DASHBOARD;
+    const $ = id => document.getElementById(id);
+    const escape = s => String(s ?? '').replace(/[&<>"']/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;'})[c]);
+    const store = {
+        get: key => { try { return localStorage.getItem('dashboard.' + key); } catch { return null; } },
+        set: (key, value) => { try { localStorage.setItem('dashboard.' + key, value); } catch {} },
+    };
+
+    // ---- i18n ----
+    let messages = {};
+    const t = (key, vars = {}) => (messages[key] ?? key).replace(/\{(\w+)\}/g, (_, name) => vars[name] ?? '');
+    const codes = config.languages.map(l => l.code);
+    const pickLanguage = () => {
+        const requested = [new URLSearchParams(location.search).get('lang'), store.get('lang')];
+        const browser = (navigator.languages || [navigator.language]).map(l => l && l.toLowerCase().split('-')[0]);
+        return [...requested, ...browser].find(code => codes.includes(code)) || config.default_language;
+    };
+    const loadBundle = code => fetch('dashboard/assets/i18n/' + code + '.json').then(r => r.ok ? r.json() : {}).catch(() => ({}));
+    const applyLanguage = async code => {
+        const [fallback, bundle] = await Promise.all([loadBundle(config.default_language), code === config.default_language ? {} : loadBundle(code)]);
+        messages = {...fallback, ...bundle};
+        document.documentElement.lang = code;
+        document.querySelectorAll('[data-i18n]').forEach(el => el.textContent = t(el.dataset.i18n));
+        document.querySelectorAll('[data-i18n-placeholder]').forEach(el => el.placeholder = t(el.dataset.i18nPlaceholder));
+        setStatus(es !== null && es.readyState === EventSource.OPEN);
+        refresh();
+        refreshCluster();
+    };
+    const language = $('language');
+    language.innerHTML = config.languages.map(l => `<option value="${escape(l.code)}">${escape(l.name)}</option>`).join('');
+    language.addEventListener('change', () => {
+        store.set('lang', language.value);
+        applyLanguage(language.value);
+    });
+
+    // ---- Theme ----
+    const root = document.documentElement;
+    const applyTheme = theme => {
+        if (theme === 'auto') {
+            theme = matchMedia('(prefers-color-scheme: light)').matches ? 'light' : 'dark';
+        }
+        root.dataset.theme = theme;
+    };
+    $('theme').addEventListener('click', () => {
+        const theme = root.dataset.theme === 'dark' ? 'light' : 'dark';
+        store.set('theme', theme);
+        applyTheme(theme);
+        refreshCharts();
+    });
+    if (config.accent) {
+        root.style.setProperty('--accent', config.accent);
+        root.style.setProperty('--accent-hover', config.accent);
+    }
+
+    // ---- Channels and history ----
+    const refresh = () => {
+        fetch('api/stats').then(r => r.json()).then(d => $('count').textContent = d.total_connections);
+        fetch('api/channels').then(r => r.json()).then(d => {
+            const selected = $('historyChannel').value;
+            $('channelCount').textContent = d.total_channels;
+            $('channels').innerHTML = d.channels.length
+                ? d.channels.map(c => `<tr class="channel${c.channel_id === selected ? ' selected' : ''}" data-channel="${escape(c.channel_id)}"><td class="mono">${escape(c.channel_id)}</td><td>${c.subscribers}</td><td>${c.events_sent}</td><td>${c.events_dropped}</td></tr>`).join('')
+                : `<tr><td class="empty" colspan="4">${escape(t('channels.empty'))}</td></tr>`;
+        });
+    };
+    const loadHistory = () => {
+        const channel = $('historyChannel').value;
+        if (!channel) return;
+        fetch('api/channels/' + encodeURIComponent(channel) + '/messages?limit=100').then(r => r.json()).then(d => {
+            $('history').innerHTML = d.messages.length
+                ? d.messages.slice().reverse().map(m => `<tr><td class="mono">${escape(m.stream_id)}</td><td>${m.timestamp ? new Date(m.timestamp).toLocaleString(root.lang) : ''}</td><td>${escape(m.event_type)}</td><td class="mono">${escape(m.id)}</td><td class="mono">${escape(m.data)}</td></tr>`).join('')
+                : `<tr><td class="empty" colspan="5">${escape(t('history.empty'))}</td></tr>`;
+        });
+    };
+    $('loadHistory').addEventListener('click', loadHistory);
+    $('channels').addEventListener('click', e => {
+        const row = e.target.closest('tr.channel');
+        if (!row) return;
+        $('historyChannel').value = row.dataset.channel;
+        document.querySelectorAll('tr.channel').forEach(r => r.classList.toggle('selected', r === row));
+        loadHistory();
+    });
+
+    // ---- Activity charts ----
+    const sparkline = (id, values, color) => {
+        const canvas = $(id);
+        const width = canvas.width = canvas.clientWidth * devicePixelRatio;
+        const height = canvas.height = canvas.clientHeight * devicePixelRatio;
+        const ctx = canvas.getContext('2d');
+        if (values.length < 2) return;
+        const max = Math.max(...values, 1);
+        const x = i => i / (values.length - 1) * width;
+        const y = v => height - 4 - v / max * (height - 8);
+        ctx.beginPath();
+        values.forEach((v, i) => i ? ctx.lineTo(x(i), y(v)) : ctx.moveTo(x(i), y(v)));
+        ctx.strokeStyle = color;
+        ctx.lineWidth = 2 * devicePixelRatio;
+        ctx.stroke();
+        ctx.lineTo(width, height);
+        ctx.lineTo(0, height);
+        ctx.fillStyle = color + '20';
+        ctx.fill();
+    };
+    const refreshCharts = () => fetch('api/metrics/history').then(r => r.ok ? r.json() : Promise.reject()).then(d => {
+        const last = d.samples[d.samples.length - 1];
+        const accent = getComputedStyle(root).getPropertyValue('--accent').trim();
+        $('connectionsNow').textContent = last ? last.connections : '-';
+        $('eventsNow').textContent = last ? last.events_per_sec.toFixed(1) : '-';
+        $('dropsNow').textContent = last ? last.drops_per_sec.toFixed(1) : '-';
+        sparkline('connectionsChart', d.samples.map(s => s.connections), /^#[0-9a-f]{6}$/i.test(accent) ? accent : '#3b82f6');
+        sparkline('eventsChart', d.samples.map(s => s.events_per_sec), '#22c55e');
+        sparkline('dropsChart', d.samples.map(s => s.drops_per_sec), '#ef4444');
+    }).catch(() => $('activity').style.display = 'none');
+
+    // ---- Cluster ----
+    const ago = secs => secs > 0 ? t('cluster.ago', {secs: Math.max(0, Math.round(Date.now() / 1000 - secs))}) : '-';
+    const refreshCluster = () => fetch('api/cluster').then(r => r.ok ? r.json() : Promise.reject()).then(d => {
+        $('cluster').style.display = '';
+        $('clusterSummary').textContent = t('cluster.summary', {count: d.instances.length, coordinator: d.coordinator});
+        $('instances').innerHTML = d.instances.map(i => `<tr><td class="mono">${escape(i.id)}${i.local ? `<span class="badge">${escape(t('cluster.this'))}</span>` : ''}</td><td class="mono">${escape(i.address ?? '-')}</td><td>${i.connections ?? '-'}</td><td>${i.channels ?? '-'}</td><td>${ago(i.last_seen)}</td></tr>`).join('');
+    }).catch(() => $('cluster').style.display = 'none');
+    $('lookupOwner').addEventListener('click', () => {
+        const channel = $('ownerChannel').value;
+        if (!channel) return;
+        const result = $('ownerResult');
+        fetch('api/cluster/channels/' + encodeURIComponent(channel)).then(r => r.ok ? r.json() : Promise.reject()).then(d => {
+            const owners = d.owners.map(o => `<span class="mono">${escape(o.id)}</span>${o.address ? ' (' + escape(o.address) + ')' : ''}`).join(', ');
+            // Bundles are trusted markup; only values from the API are escaped
+            result.innerHTML = (d.online ? t('cluster.owned_by', {owners}) : t('cluster.no_owner')) +
+                (d.assigned ? ' &middot; ' + t('cluster.assigned', {id: `<span class="mono">${escape(d.assigned.id)}</span>`}) : '');
+        }).catch(() => result.textContent = t('cluster.lookup_failed'));
+    });
+
+    // ---- Test connection ----
+    let es = null;
+    const setStatus = connected => {
+        $('status').className = 'status ' + (connected ? 'connected' : 'disconnected');
+        $('status').textContent = t(connected ? 'status.connected' : 'status.disconnected');
+    };
+    const addEvent = (type, data) => {
+        $('events').insertAdjacentHTML('afterbegin', `<div class="event"><b>${escape(type)}:</b> ${escape(data)}</div>`);
+    };
+    $('connect').addEventListener('click', () => {
+        if (es) es.close();
+        es = new EventSource('sse/connect?channel_id=' + encodeURIComponent($('channelId').value));
+        es.onopen = () => { setStatus(true); refresh(); };
+        es.onerror = () => setStatus(false);
+        es.onmessage = e => addEvent('message', e.data);
+        ['notification', 'heartbeat'].forEach(type => es.addEventListener(type, e => addEvent(type, e.data)));
+    });
+    $('disconnect').addEventListener('click', () => {
+        if (es) { es.close(); es = null; }
+        setStatus(false);
+        setTimeout(refresh, 500);
+    });
+    $('send').addEventListener('click', () => {
+        let data;
+        try { data = JSON.parse($('data').value); } catch { return alert(t('send.invalid_json')); }
+        fetch('api/send', {
+            method: 'POST',
+            headers: {'Content-Type': 'application/json'},
+            body: JSON.stringify({
+                channel_id: $('targetChannel').value || null,
+                event_type: $('eventType').value,
+                data,
+            })
+        });
+    });
+
+    applyTheme(store.get('theme') || config.default_theme);
+    const initial = pickLanguage();
+    language.value = initial;
+    applyLanguage(initial);
+    setInterval(refresh, 3000);
+    setInterval(refreshCharts, 2000);
+    setInterval(refreshCluster, 5000);
+    refreshCharts();
+})();
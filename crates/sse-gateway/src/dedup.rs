@@ -0,0 +1,66 @@
+//! Per-connection duplicate suppression
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[cfg(feature = "server")]
+use crate::event::SseEvent;
+
+/// Bounded window of recently sent event keys
+///
+/// Events are keyed on their publisher `id`, falling back to the `stream_id`,
+/// so a retried publish (new stream ID, same `id`) is caught as well as a
+/// replayed event arriving again live. Events with neither always pass.
+#[derive(Debug)]
+pub(crate) struct DedupWindow {
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    capacity: usize,
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    seen: Mutex<Seen>,
+    duplicates: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
+struct Seen {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl DedupWindow {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: Mutex::new(Seen::default()),
+            duplicates: AtomicU64::new(0),
+        }
+    }
+
+    /// Record `event` and return whether it should be sent
+    #[cfg(feature = "server")]
+    pub(crate) fn admit(&self, event: &SseEvent) -> bool {
+        let Some(key) = event.id.as_ref().or(event.stream_id.as_ref()) else {
+            return true;
+        };
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if seen.keys.contains(key) {
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        if seen.order.len() >= self.capacity {
+            if let Some(oldest) = seen.order.pop_front() {
+                seen.keys.remove(&oldest);
+            }
+        }
+        seen.keys.insert(key.clone());
+        seen.order.push_back(key.clone());
+        true
+    }
+
+    /// Events suppressed so far
+    pub(crate) fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+}
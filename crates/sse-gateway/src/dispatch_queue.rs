@@ -0,0 +1,274 @@
+//! Bounded queue behind `MessageHandler::send`
+//!
+//! Fire-and-forget sends are handed to a fixed pool of worker tasks rather
+//! than a task per message. Each channel hashes to one worker, so a
+//! channel's messages are dispatched in the order they were sent (within a
+//! priority), while channels on different workers dispatch concurrently.
+//! Each worker drains its queued high priority messages before normal ones,
+//! and normal before low.
+//!
+//! A worker dispatches one message at a time and waits for it to be
+//! delivered, so a subscriber that is slow to drain under
+//! [`Backpressure::Wait`](crate::Backpressure::Wait) holds up every channel
+//! hashed to the same worker. Give such channels a dropping backpressure
+//! policy or raise `dispatch_concurrency` to spread them out.
+//!
+//! A worker queues up to `capacity` messages per priority; a message that
+//! finds its queue full is handled per the [`QueueOverflow`] policy. The
+//! number queued, the overflows and the drops are reported in
+//! [`GatewayMetrics`]:
+//!
+//! ```rust,ignore
+//! Gateway::builder()
+//!     .dispatch_concurrency(64)
+//!     .dispatch_queue_capacity(4096)
+//!     .dispatch_overflow(QueueOverflow::Drop)
+//! ```
+
+use std::hash::BuildHasher;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::metrics::GatewayMetrics;
+use crate::source::{DeliveryReport, DispatchFn, IncomingMessage};
+
+/// Default messages queued per worker and priority
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// What happens to a message sent while its worker's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+    /// Discard it, reporting it as throttled; the messages that are
+    /// dispatched keep their order (default)
+    #[default]
+    Drop,
+    /// Dispatch it in a task of its own, keeping the message but not its order
+    Spawn,
+}
+
+/// Settings for the queue behind [`MessageHandler::send`](crate::MessageHandler::send)
+#[derive(Clone)]
+pub struct DispatchQueue {
+    workers: usize,
+    capacity: usize,
+    overflow: QueueOverflow,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl DispatchQueue {
+    /// Dispatch with `workers` tasks, each one message at a time
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow: QueueOverflow::default(),
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Queue up to `capacity` messages per worker and priority
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Handle messages beyond the capacity per `overflow`
+    pub fn with_overflow(mut self, overflow: QueueOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Report queue depth and overflows to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<GatewayMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Start the workers; they stop once every sender is dropped
+    pub(crate) fn spawn(self, dispatch: Arc<DispatchFn>) -> QueueSender {
+        let workers = (0..self.workers)
+            .map(|_| {
+                let (high_tx, high) = mpsc::channel(self.capacity);
+                let (normal_tx, normal) = mpsc::channel(self.capacity);
+                let (low_tx, low) = mpsc::channel(self.capacity);
+                tokio::spawn(work(dispatch.clone(), [high, normal, low], self.metrics.clone()));
+                [high_tx, normal_tx, low_tx]
+            })
+            .collect();
+        QueueSender {
+            workers,
+            hasher: Default::default(),
+            overflow: self.overflow,
+            metrics: self.metrics,
+            dispatch,
+        }
+    }
+}
+
+/// Dispatch queued messages one at a time, highest priority first
+async fn work(
+    dispatch: Arc<DispatchFn>,
+    [mut high, mut normal, mut low]: [mpsc::Receiver<IncomingMessage>; 3],
+    metrics: Arc<GatewayMetrics>,
+) {
+    loop {
+        let msg = tokio::select! {
+            biased;
+            Some(msg) = high.recv() => msg,
+            Some(msg) = normal.recv() => msg,
+            Some(msg) = low.recv() => msg,
+            else => break,
+        };
+        metrics.dispatch_queue_depth.fetch_sub(1, Ordering::Relaxed);
+        let (channel_id, event_type) = (msg.channel_id.clone(), msg.event_type.clone());
+        if let Err(e) = dispatch(msg).await {
+            GatewayMetrics::incr(&metrics.dispatch_failures);
+            tracing::warn!(channel_id = ?channel_id, event_type = %event_type, error = %e, "Queued message not dispatched");
+        }
+    }
+}
+
+/// Sending side of a started [`DispatchQueue`]
+pub(crate) struct QueueSender {
+    /// Each worker's queues, highest priority first
+    workers: Vec<[mpsc::Sender<IncomingMessage>; 3]>,
+    hasher: std::collections::hash_map::RandomState,
+    overflow: QueueOverflow,
+    metrics: Arc<GatewayMetrics>,
+    dispatch: Arc<DispatchFn>,
+}
+
+impl QueueSender {
+    /// Queue `msg` on its channel's worker
+    pub(crate) fn send(&self, msg: IncomingMessage) {
+        let worker = self.hasher.hash_one(msg.channel_id.as_deref()) as usize % self.workers.len();
+        // Counted before sending, so the worker's decrement can't run first
+        self.metrics.dispatch_queue_depth.fetch_add(1, Ordering::Relaxed);
+        match self.workers[worker][msg.priority.lane()].try_send(msg) {
+            Ok(()) => {}
+            Err(TrySendError::Full(msg)) => {
+                self.metrics.dispatch_queue_depth.fetch_sub(1, Ordering::Relaxed);
+                GatewayMetrics::incr(&self.metrics.dispatch_queue_overflows);
+                self.overflow(msg);
+            }
+            Err(TrySendError::Closed(_)) => {
+                self.metrics.dispatch_queue_depth.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn overflow(&self, msg: IncomingMessage) {
+        match self.overflow {
+            QueueOverflow::Drop => {
+                GatewayMetrics::incr(&self.metrics.dispatch_queue_dropped);
+                tracing::warn!(channel_id = ?msg.channel_id, event_type = %msg.event_type, "Dispatch queue full, message dropped");
+                if let Some(reporter) = &msg.report {
+                    reporter.report(DeliveryReport {
+                        throttled: true,
+                        ..Default::default()
+                    });
+                }
+            }
+            QueueOverflow::Spawn => {
+                let (channel_id, event_type) = (msg.channel_id.clone(), msg.event_type.clone());
+                let dispatch = (self.dispatch)(msg);
+                let metrics = self.metrics.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = dispatch.await {
+                        GatewayMetrics::incr(&metrics.dispatch_failures);
+                        tracing::warn!(channel_id = ?channel_id, event_type = %event_type, error = %e, "Queued message not dispatched");
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{DispatchError, DispatchResult};
+    use std::future::Future;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    fn dispatch_fn<F, Fut>(f: F) -> Arc<DispatchFn>
+    where
+        F: Fn(IncomingMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DispatchResult> + Send + 'static,
+    {
+        Arc::new(move |msg| Box::pin(f(msg)))
+    }
+
+    async fn until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("condition not met");
+    }
+
+    #[tokio::test]
+    async fn overflow_keeps_the_order_of_dispatched_messages() {
+        let metrics = Arc::new(GatewayMetrics::default());
+        let dispatched = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(Semaphore::new(0));
+        let sender = DispatchQueue::new(1).with_capacity(2).with_metrics(metrics.clone()).spawn({
+            let (dispatched, gate) = (dispatched.clone(), gate.clone());
+            dispatch_fn(move |msg| {
+                let (dispatched, gate) = (dispatched.clone(), gate.clone());
+                async move {
+                    dispatched.lock().unwrap().push(msg.data);
+                    gate.acquire().await.unwrap().forget();
+                    Ok(DeliveryReport::default())
+                }
+            })
+        });
+
+        // The worker holds "0" while "1" and "2" fill the queue
+        sender.send(IncomingMessage::new("tick", "0").with_channel("a"));
+        until(|| dispatched.lock().unwrap().len() == 1).await;
+        for n in 1..=5 {
+            sender.send(IncomingMessage::new("tick", n.to_string()).with_channel("a"));
+        }
+        gate.add_permits(10);
+        until(|| metrics.dispatch_queue_depth.load(Ordering::Relaxed) == 0).await;
+        sender.send(IncomingMessage::new("tick", "6").with_channel("a"));
+        until(|| dispatched.lock().unwrap().len() == 4).await;
+
+        assert_eq!(*dispatched.lock().unwrap(), ["0", "1", "2", "6"]);
+        assert_eq!(metrics.dispatch_queue_overflows.load(Ordering::Relaxed), 3);
+        assert_eq!(metrics.dispatch_queue_dropped.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn failed_dispatches_are_counted() {
+        let metrics = Arc::new(GatewayMetrics::default());
+        let dispatched = Arc::new(Mutex::new(Vec::new()));
+        let sender = DispatchQueue::new(1).with_metrics(metrics.clone()).spawn({
+            let dispatched = dispatched.clone();
+            dispatch_fn(move |msg| {
+                let dispatched = dispatched.clone();
+                async move {
+                    if msg.data == "bad" {
+                        return Err(DispatchError::Invalid("bad".into()));
+                    }
+                    dispatched.lock().unwrap().push(msg.data);
+                    Ok(DeliveryReport::default())
+                }
+            })
+        });
+
+        for data in ["bad", "ok", "bad"] {
+            sender.send(IncomingMessage::new("tick", data).with_channel("a"));
+        }
+        until(|| dispatched.lock().unwrap().len() == 1 && metrics.dispatch_failures.load(Ordering::Relaxed) == 2).await;
+    }
+}
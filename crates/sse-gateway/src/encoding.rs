@@ -0,0 +1,130 @@
+//! Per-connection encoding of SSE `data` fields
+//!
+//! By default an event's data is written as it was published: raw strings
+//! as they are, structured values as JSON. An [`EventEncoder`] chosen per
+//! connection can re-encode it, so different clients get different wire
+//! formats from the same channel:
+//!
+//! ```rust,ignore
+//! // `?fields=id,status` gets only those fields of JSON object payloads
+//! Gateway::builder().event_encoder(encoding::fields_param("fields"))
+//!
+//! // Anything else, e.g. MessagePack + base64 for clients that ask for it
+//! Gateway::builder().event_encoder(|req: &AuthRequest| {
+//!     req.headers.get("x-wire-format")
+//!         .is_some_and(|v| v == "msgpack")
+//!         .then(|| Arc::new(MsgpackBase64) as Arc<dyn EventEncoder>)
+//! })
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::Query;
+
+use crate::auth::AuthRequest;
+use crate::event::{EventData, SseEvent};
+
+/// Encodes the `data` field of events written to a connection
+pub trait EventEncoder: Send + Sync {
+    /// The text sent as the event's `data`; newlines split it into several `data:` lines
+    fn encode(&self, event: &SseEvent) -> String;
+}
+
+/// Picks the encoder for a new SSE connection, `None` for the default
+pub type EncoderFn = Arc<dyn Fn(&AuthRequest) -> Option<Arc<dyn EventEncoder>> + Send + Sync>;
+
+/// Data as published: raw strings as they are, structured values as JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PassThrough;
+
+impl EventEncoder for PassThrough {
+    fn encode(&self, event: &SseEvent) -> String {
+        event.data.to_string()
+    }
+}
+
+/// JSON without insignificant whitespace
+///
+/// Raw data that isn't valid JSON is passed through.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactJson;
+
+impl EventEncoder for CompactJson {
+    fn encode(&self, event: &SseEvent) -> String {
+        match &event.data {
+            EventData::Raw(raw) => match serde_json::from_str::<serde_json::Value>(raw) {
+                Ok(value) => value.to_string(),
+                Err(_) => raw.clone(),
+            },
+            EventData::Value(value) => value.to_string(),
+        }
+    }
+}
+
+/// Only the listed top-level fields of JSON object payloads
+///
+/// Other payloads (arrays, scalars, non-JSON text) are passed through.
+#[derive(Debug, Clone, Default)]
+pub struct FieldProjection {
+    fields: Vec<String>,
+}
+
+impl FieldProjection {
+    /// Keep `fields`
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            fields: fields.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl EventEncoder for FieldProjection {
+    fn encode(&self, event: &SseEvent) -> String {
+        let parsed;
+        let value = match &event.data {
+            EventData::Value(value) => value,
+            EventData::Raw(raw) => match serde_json::from_str(raw) {
+                Ok(value) => {
+                    parsed = value;
+                    &parsed
+                }
+                Err(_) => return raw.clone(),
+            },
+        };
+        match value {
+            serde_json::Value::Object(object) => {
+                let projected: serde_json::Map<_, _> = object
+                    .iter()
+                    .filter(|(key, _)| self.fields.iter().any(|field| field == *key))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                serde_json::Value::Object(projected).to_string()
+            }
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Project payloads to the comma-separated fields of query parameter `param`
+///
+/// Connections without the parameter get the default encoding.
+pub fn fields_param(
+    param: impl Into<String>,
+) -> impl Fn(&AuthRequest) -> Option<Arc<dyn EventEncoder>> + Send + Sync + 'static {
+    let param = param.into();
+    move |req: &AuthRequest| {
+        let Query(query) = Query::<HashMap<String, String>>::try_from_uri(&req.uri).ok()?;
+        let fields: Vec<&str> = query
+            .get(&param)?
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect();
+        (!fields.is_empty()).then(|| Arc::new(FieldProjection::new(fields)) as Arc<dyn EventEncoder>)
+    }
+}
@@ -1,5 +1,6 @@
 //! SSE Event types
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 /// Event data can be raw string or structured JSON
@@ -12,12 +13,22 @@ pub enum EventData {
     Value(serde_json::Value),
 }
 
+impl std::fmt::Display for EventData {
+    /// Format as the string sent in the SSE `data` field
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventData::Raw(s) => f.write_str(s),
+            EventData::Value(v) => f.write_str(&serde_json::to_string(v).unwrap_or_default()),
+        }
+    }
+}
+
 impl EventData {
-    /// Convert to string representation
-    pub fn to_string(&self) -> String {
+    /// Deserialize the payload into `T`; raw data is parsed as JSON
+    pub fn parse<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
         match self {
-            EventData::Raw(s) => s.clone(),
-            EventData::Value(v) => serde_json::to_string(v).unwrap_or_default(),
+            EventData::Raw(s) => serde_json::from_str(s),
+            EventData::Value(v) => T::deserialize(v),
         }
     }
 }
@@ -40,6 +51,51 @@ impl From<serde_json::Value> for EventData {
     }
 }
 
+/// Delivery priority of an event
+///
+/// Higher priority events are dispatched, queued to subscribers and written
+/// to storage ahead of lower priority ones waiting in the same queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Bulk traffic such as telemetry
+    Low,
+    /// Everything else (default)
+    #[default]
+    Normal,
+    /// Alerts that must overtake backlogs
+    High,
+}
+
+impl Priority {
+    /// Index of this priority's queue when queues are kept highest first
+    pub fn lane(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+
+    pub(crate) fn is_normal(&self) -> bool {
+        *self == Priority::Normal
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "high" => Ok(Priority::High),
+            "normal" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            other => Err(format!("unknown priority: {other}")),
+        }
+    }
+}
+
 /// SSE Event to be sent to clients
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SseEvent {
@@ -61,6 +117,14 @@ pub struct SseEvent {
     /// Optional retry interval in milliseconds
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry: Option<u32>,
+
+    /// After this time the event is neither delivered nor replayed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Delivery priority
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
 }
 
 impl SseEvent {
@@ -72,6 +136,8 @@ impl SseEvent {
             id: Some(uuid::Uuid::new_v4().to_string()),
             stream_id: None,
             retry: None,
+            expires_at: None,
+            priority: Priority::Normal,
         }
     }
 
@@ -83,9 +149,23 @@ impl SseEvent {
             id: Some(uuid::Uuid::new_v4().to_string()),
             stream_id: None,
             retry: None,
+            expires_at: None,
+            priority: Priority::Normal,
         }
     }
 
+    /// Create a new SSE event with `data` serialized as JSON
+    ///
+    /// ```rust,ignore
+    /// let event = SseEvent::json("order_created", &order)?;
+    /// ```
+    pub fn json<T: Serialize + ?Sized>(
+        event_type: impl Into<String>,
+        data: &T,
+    ) -> serde_json::Result<Self> {
+        Ok(Self::new(event_type, serde_json::to_value(data)?))
+    }
+
     /// Create a simple message event
     pub fn message(data: impl Into<String>) -> Self {
         Self::raw("message", data)
@@ -108,4 +188,58 @@ impl SseEvent {
         self.retry = Some(retry_ms);
         self
     }
+
+    /// Stop delivering and replaying the event after `expires_at`
+    pub fn with_expiry(mut self, expires_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set the delivery priority
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Whether the event's expiry has passed
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= chrono::Utc::now())
+    }
+
+    /// Deserialize the event's data into `T`
+    pub fn data_as<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        self.data.parse()
+    }
+}
+
+/// A payload type that is always sent as the same event type
+///
+/// Sharing these types between publishers and subscribers keeps event types
+/// and payload shapes in one place instead of in string literals:
+///
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct OrderCreated {
+///     order_id: String,
+/// }
+///
+/// impl IntoSseEvent for OrderCreated {
+///     const EVENT_TYPE: &'static str = "order_created";
+/// }
+///
+/// manager.send_to_channel("user123", order.into_sse_event()?).await;
+/// ```
+///
+/// The trait only needs the constant, so it is straightforward to derive.
+pub trait IntoSseEvent: Serialize {
+    /// SSE event type this payload is sent as
+    const EVENT_TYPE: &'static str;
+
+    /// Build an event with this value as JSON data
+    fn into_sse_event(self) -> serde_json::Result<SseEvent>
+    where
+        Self: Sized,
+    {
+        SseEvent::json(Self::EVENT_TYPE, &self)
+    }
 }
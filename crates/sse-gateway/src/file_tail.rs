@@ -0,0 +1,436 @@
+//! Log file and journald source
+//!
+//! A [`FileTailSource`] follows log files the way `tail -F` does, and
+//! journald units through `journalctl --follow`, emitting each new line as
+//! a message. Lines are sent as-is by default, or parsed with a regex or as
+//! JSON to pick their channel, event type and payload.
+//!
+//! ```rust,ignore
+//! use sse_gateway::file_tail::FileTailSource;
+//!
+//! // Stream each build's log to the browsers watching it
+//! let source = FileTailSource::new()
+//!     .file("/var/log/builds/1234.log", "build-1234")
+//!     .journald(["runner.service"], "runner")
+//!     .event_type("log");
+//!
+//! Gateway::builder().source(source)
+//! ```
+//!
+//! Rotation is detected both when a file is renamed away and replaced
+//! (the rest of the old file is read first) and when it is truncated in
+//! place (`copytruncate`).
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader, SeekFrom};
+use tokio_util::sync::CancellationToken;
+
+use crate::manager::ConnectionManager;
+use crate::source::{IncomingMessage, MessageHandler, MessageSource};
+
+/// How lines become messages
+#[derive(Debug, Clone, Default)]
+pub enum LineFormat {
+    /// The whole line is the payload
+    #[default]
+    Plain,
+    /// Named groups `channel`, `event`, `id` and `data` pick the fields;
+    /// without a `data` group the whole line is the payload. Lines that
+    /// don't match are skipped.
+    Regex(Regex),
+    /// JSON objects with optional `channel_id`, `event_type`, `id` and
+    /// `data` fields; without `data` the whole line is the payload. Lines
+    /// that aren't JSON objects are skipped.
+    Json,
+}
+
+enum Input {
+    File { path: PathBuf, channel_id: String },
+    Journald { units: Vec<String>, channel_id: String },
+}
+
+/// Source following log files and journald units
+pub struct FileTailSource {
+    inputs: Vec<Input>,
+    format: LineFormat,
+    event_type: String,
+    poll_interval: Duration,
+    from_start: bool,
+}
+
+impl Default for FileTailSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileTailSource {
+    /// A source with no inputs yet
+    pub fn new() -> Self {
+        Self {
+            inputs: Vec::new(),
+            format: LineFormat::Plain,
+            event_type: "log".to_string(),
+            poll_interval: Duration::from_millis(250),
+            from_start: false,
+        }
+    }
+
+    /// Follow the file at `path`, sending its lines to `channel_id`
+    ///
+    /// The file doesn't have to exist yet.
+    pub fn file(mut self, path: impl Into<PathBuf>, channel_id: impl Into<String>) -> Self {
+        self.inputs.push(Input::File {
+            path: path.into(),
+            channel_id: channel_id.into(),
+        });
+        self
+    }
+
+    /// Follow the journal of `units` (all units if empty), sending entries to `channel_id`
+    ///
+    /// Entries' `MESSAGE` field is parsed like a file line. Needs
+    /// `journalctl` on the `PATH`.
+    pub fn journald<I, U>(mut self, units: I, channel_id: impl Into<String>) -> Self
+    where
+        I: IntoIterator<Item = U>,
+        U: Into<String>,
+    {
+        self.inputs.push(Input::Journald {
+            units: units.into_iter().map(Into::into).collect(),
+            channel_id: channel_id.into(),
+        });
+        self
+    }
+
+    /// Parse lines with `format`
+    pub fn format(mut self, format: LineFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Parse lines with a regex (see [`LineFormat::Regex`])
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` is not a valid regex.
+    pub fn regex(self, pattern: &str) -> Self {
+        let regex = Regex::new(pattern).unwrap_or_else(|e| panic!("invalid line regex '{}': {}", pattern, e));
+        self.format(LineFormat::Regex(regex))
+    }
+
+    /// Parse lines as JSON (see [`LineFormat::Json`])
+    pub fn json(self) -> Self {
+        self.format(LineFormat::Json)
+    }
+
+    /// Event type of lines that don't carry one (default: `log`)
+    pub fn event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.event_type = event_type.into();
+        self
+    }
+
+    /// How often files are checked for new lines (default: 250ms)
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Emit the existing contents of files and journals when starting,
+    /// instead of only what is written afterwards
+    ///
+    /// Files that appear after start are always read from the beginning.
+    pub fn from_start(mut self, from_start: bool) -> Self {
+        self.from_start = from_start;
+        self
+    }
+
+    /// Message for `line`, or `None` if it should be skipped
+    pub fn parse(&self, line: &str, channel_id: &str) -> Option<IncomingMessage> {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            return None;
+        }
+        let (channel, event_type, id, data) = match &self.format {
+            LineFormat::Plain => (None, None, None, line.to_string()),
+            LineFormat::Regex(regex) => {
+                let captures = regex.captures(line)?;
+                let group = |name| captures.name(name).map(|m| m.as_str().to_string());
+                let data = group("data").unwrap_or_else(|| line.to_string());
+                (group("channel"), group("event"), group("id"), data)
+            }
+            LineFormat::Json => {
+                let Value::Object(fields) = serde_json::from_str(line).ok()? else {
+                    return None;
+                };
+                let text = |name| fields.get(name).and_then(Value::as_str).map(str::to_string);
+                let data = match fields.get("data") {
+                    Some(Value::String(data)) => data.clone(),
+                    Some(data) => data.to_string(),
+                    None => line.to_string(),
+                };
+                (text("channel_id"), text("event_type"), text("id"), data)
+            }
+        };
+        Some(IncomingMessage {
+            channel_id: Some(channel.map_or_else(|| channel_id.into(), Into::into)),
+            id,
+            ..IncomingMessage::new(event_type.unwrap_or_else(|| self.event_type.clone()), data)
+        })
+    }
+
+    async fn emit(&self, handler: &MessageHandler, line: &str, channel_id: &str) {
+        if let Some(msg) = self.parse(line, channel_id) {
+            // Awaited so lines arrive in order
+            if let Err(e) = handler.dispatch(msg).await {
+                tracing::warn!(channel_id, error = %e, "Dropping log line");
+            }
+        }
+    }
+
+    /// Follow every file input until cancelled
+    async fn follow_files(&self, handler: &MessageHandler, cancel: &CancellationToken) {
+        let mut tails: Vec<Tail> = self
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                Input::File { path, channel_id } => Some(Tail::new(path.clone(), channel_id.clone(), self.from_start)),
+                Input::Journald { .. } => None,
+            })
+            .collect();
+        if tails.is_empty() {
+            return;
+        }
+
+        loop {
+            for tail in &mut tails {
+                for line in tail.read_lines().await {
+                    self.emit(handler, &line, &tail.channel_id).await;
+                }
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+        }
+    }
+
+    /// Follow one journal until cancelled, restarting `journalctl` if it exits
+    async fn follow_journal(
+        &self,
+        units: &[String],
+        channel_id: &str,
+        handler: &MessageHandler,
+        cancel: &CancellationToken,
+    ) {
+        let mut lines = if self.from_start { "all" } else { "0" };
+        loop {
+            let mut command = tokio::process::Command::new("journalctl");
+            command
+                .args(["--follow", "--output=json", "--lines", lines])
+                .args(units.iter().map(|unit| format!("--unit={}", unit)))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .kill_on_drop(true);
+
+            match command.spawn() {
+                Ok(mut child) => {
+                    let stdout = child.stdout.take().expect("stdout is piped");
+                    let mut entries = BufReader::new(stdout).lines();
+                    loop {
+                        let entry = tokio::select! {
+                            _ = cancel.cancelled() => return,
+                            entry = entries.next_line() => entry,
+                        };
+                        match entry {
+                            Ok(Some(entry)) => {
+                                if let Some(message) = journal_message(&entry) {
+                                    self.emit(handler, &message, channel_id).await;
+                                }
+                            }
+                            Ok(None) => break,
+                            Err(e) => {
+                                tracing::warn!(error = %e, "Failed to read journal");
+                                break;
+                            }
+                        }
+                    }
+                    tracing::warn!(?units, "journalctl exited, restarting");
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to run journalctl"),
+            }
+            // Entries from before the restart were already emitted
+            lines = "0";
+
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            }
+        }
+    }
+}
+
+/// `MESSAGE` of a `journalctl --output=json` entry
+///
+/// Messages that aren't valid UTF-8 are encoded as byte arrays.
+fn journal_message(entry: &str) -> Option<String> {
+    let entry: Value = serde_json::from_str(entry).ok()?;
+    match entry.get("MESSAGE")? {
+        Value::String(message) => Some(message.clone()),
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes.iter().filter_map(|b| b.as_u64().map(|b| b as u8)).collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Identity of the file behind a path, to notice it being replaced
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Read position in one followed file
+struct Tail {
+    path: PathBuf,
+    channel_id: String,
+    file: Option<tokio::fs::File>,
+    id: Option<(u64, u64)>,
+    offset: u64,
+    /// Bytes after the last newline read
+    partial: Vec<u8>,
+    /// Skip what the file holds when it is first opened
+    skip_existing: bool,
+}
+
+impl Tail {
+    fn new(path: PathBuf, channel_id: String, from_start: bool) -> Self {
+        Self {
+            path,
+            channel_id,
+            file: None,
+            id: None,
+            offset: 0,
+            partial: Vec::new(),
+            skip_existing: !from_start,
+        }
+    }
+
+    /// Complete lines written since the last call
+    async fn read_lines(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        // At most twice: the old file's remainder, then its replacement
+        for _ in 0..2 {
+            let metadata = tokio::fs::metadata(&self.path).await.ok();
+
+            if self.file.is_none() {
+                let Some(metadata) = &metadata else {
+                    break;
+                };
+                let Ok(mut file) = tokio::fs::File::open(&self.path).await else {
+                    break;
+                };
+                // Only the first open may skip; rotated and late files are read whole
+                self.offset = if std::mem::take(&mut self.skip_existing) {
+                    metadata.len()
+                } else {
+                    0
+                };
+                if file.seek(SeekFrom::Start(self.offset)).await.is_err() {
+                    break;
+                }
+                self.id = file_id(metadata);
+                self.partial.clear();
+                self.file = Some(file);
+            }
+            let file = self.file.as_mut().expect("opened above");
+            let mut buf = Vec::new();
+            match file.read_to_end(&mut buf).await {
+                Ok(read) => self.offset += read as u64,
+                Err(e) => tracing::warn!(path = %self.path.display(), error = %e, "Failed to read log file"),
+            }
+            self.split(&buf, &mut lines);
+
+            match metadata {
+                // Replaced: the old file is drained, so switch to the new one
+                Some(metadata) if file_id(&metadata) != self.id => {
+                    if !self.partial.is_empty() {
+                        lines.push(String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned());
+                    }
+                    self.file = None;
+                    continue;
+                }
+                // Truncated in place
+                Some(metadata) if metadata.len() < self.offset => {
+                    if let Some(file) = self.file.as_mut() {
+                        if file.seek(SeekFrom::Start(0)).await.is_ok() {
+                            self.offset = 0;
+                            self.partial.clear();
+                        }
+                    }
+                }
+                // Unchanged, or moved away and not replaced yet
+                _ => {}
+            }
+            break;
+        }
+        lines
+    }
+
+    /// Append complete lines of `partial` + `buf` to `lines`
+    fn split(&mut self, buf: &[u8], lines: &mut Vec<String>) {
+        self.partial.extend_from_slice(buf);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return;
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        lines.extend(
+            complete[..end]
+                .split(|&b| b == b'\n')
+                .map(|line| String::from_utf8_lossy(line).into_owned()),
+        );
+    }
+}
+
+#[async_trait]
+impl MessageSource for FileTailSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        tracing::info!(inputs = self.inputs.len(), "Starting log tail");
+
+        let journals = self.inputs.iter().filter_map(|input| match input {
+            Input::Journald { units, channel_id } => Some(self.follow_journal(units, channel_id, &handler, &cancel)),
+            Input::File { .. } => None,
+        });
+        futures::future::join(
+            self.follow_files(&handler, &cancel),
+            futures::future::join_all(journals),
+        )
+        .await;
+
+        tracing::info!("Log tail stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "File tail"
+    }
+}
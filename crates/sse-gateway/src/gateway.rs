@@ -1,5 +1,6 @@
 //! Gateway builder and runner
 
+use std::borrow::Cow;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,15 +13,89 @@ use tower_http::{
 };
 
 // Error types now use anyhow for better ergonomics
-use crate::{auth::AuthFn, handler};
+use crate::{auth::{AttributesFn, AuthFn, EventTypesFn, IdentityChannel, IdentityFn}, handler};
 use crate::manager::ConnectionManager;
-use crate::source::{ConnectionInfo, IncomingMessage, MessageSource, NoopSource};
-use crate::storage::{MemoryStorage, MessageStorage, NoopStorage};
+use crate::source::{
+    ConnectionInfo, DecodeError, DecodeErrorFn, DeliveryReport, DispatchError, DispatchResult, IncomingMessage,
+    MessageHandler, MessageSource, NoopSource,
+};
+use crate::storage::{store_event, FailoverStorage, MemoryStorage, MessageStorage, NoopStorage};
 use crate::event::SseEvent;
+use crate::heartbeat::Heartbeat;
+use crate::dashboard::{Dashboard, DashboardConfig};
+use crate::metrics::{GatewayMetrics, MetricsHistory};
+use crate::browser_client::{ClientScript, CLIENT_SCRIPT_PATH};
+use crate::coalesce::WriteCoalescing;
+use crate::compat::CompatMode;
+use crate::retry::RetryPolicy;
+use crate::channel_rewrite::{ChannelRewrites, RewriteRule};
+use crate::client_ip::TrustedProxies;
+use crate::encoding::{EncoderFn, EventEncoder};
+use crate::dispatch_queue::{DispatchQueue, QueueOverflow, DEFAULT_QUEUE_CAPACITY};
+use crate::payload::PayloadLimit;
+use crate::presence::PresenceEvents;
+use crate::cloudevents::CloudEventsEmitter;
+use crate::channel_config::{ChannelConfig, ChannelConfigs};
+use crate::channel_usage::UsageRecorder;
+use crate::channel_router::ChannelRouter;
+use crate::cluster::{Cluster, ClusterCoordinator, ClusterFanout, ClusterMessage, InstanceInfo};
+use crate::interceptor::{Decision, EventInterceptor, InterceptorChain};
+use crate::publisher::Publisher;
+use crate::push::{self, PushEndpoint};
+use crate::resume::ResumeSessions;
+use crate::serve::{self, ServerOptions};
+use crate::supervisor::{self, RestartPolicy, SourceHealth, SourceState, SourceStateHook};
+use crate::tenancy::Tenancy;
+use crate::throttle::{Throttle, ThrottleDecision, ThrottlePolicy};
+use crate::access_log::AccessLogSink;
+use crate::id::{next_stream_id, IdGenerator};
+use crate::connection::CloseReason;
+use crate::offline::OfflineFallback;
+#[cfg(feature = "schema")]
+use crate::schema::{DeadLetterFn, SchemaValidator};
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosLayer;
+#[cfg(feature = "config")]
+use crate::config::{BuiltinStorage, LiveConfig};
+#[cfg(feature = "tls")]
+use crate::tls::{TlsConfig, TlsListener};
 
 /// Connection lifecycle callback type
 pub type LifecycleCallback = Arc<dyn Fn(&ConnectionInfo) + Send + Sync>;
 
+/// Awaited connect hook; an error may refuse the connection
+pub type ConnectHook = Arc<
+    dyn Fn(ConnectionInfo) -> std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Default idle time before an SSE stream gets a keep-alive comment
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Default limit on concurrent fire-and-forget dispatches
+const DEFAULT_DISPATCH_CONCURRENCY: usize = 256;
+
+/// How often channel pauses are synced from the cluster
+const PAUSE_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Event type of the shutdown hints sent with [`GatewayBuilder::migration_hints`]
+pub const MIGRATE_EVENT: &str = "migrate";
+
+/// Default event type sent before replayed events, see [`GatewayBuilder::replay_markers`]
+pub const REPLAY_START_EVENT: &str = "replay_start";
+
+/// Default event type sent after replayed events, see [`GatewayBuilder::replay_markers`]
+pub const REPLAY_END_EVENT: &str = "replay_end";
+
+/// Event type carrying an array of replayed events, sent to clients that
+/// connect with `?replay_batch=<size>`
+pub const REPLAY_BATCH_EVENT: &str = "replay_batch";
+
+/// Deferred `Router::layer` call registered on the builder, applied to the
+/// router of each listener
+type RouterLayer = Box<dyn Fn(Router) -> Router + Send + Sync>;
+
 /// Gateway configuration and runner
 pub struct Gateway<Source: MessageSource, Storage: MessageStorage> {
     port: u16,
@@ -28,18 +103,116 @@ pub struct Gateway<Source: MessageSource, Storage: MessageStorage> {
     storage: Storage,
     connection_manager: ConnectionManager,
     enable_dashboard: bool,
+    dashboard: DashboardConfig,
+    enable_metrics: Option<bool>,
+    metrics_history: MetricsHistory,
     heartbeat_interval: Duration,
+    keep_alive_interval: Duration,
+    pub(crate) heartbeat: Arc<Heartbeat>,
     cleanup_interval: Duration,
     auth: Option<AuthFn>,
+    admin_auth: Option<AuthFn>,
+    identity: Option<IdentityFn>,
+    attribute_params: Vec<String>,
+    attributes: Option<AttributesFn>,
+    event_types: Option<EventTypesFn>,
+    event_encoder: Option<EncoderFn>,
+    tenancy: Option<Tenancy>,
+    dispatch_concurrency: usize,
+    dispatch_queue_capacity: usize,
+    dispatch_overflow: QueueOverflow,
+    write_coalescing: Option<WriteCoalescing>,
+    compat: CompatMode,
+    replay_markers: Option<handler::ReplayMarkers>,
+    channel_rewrites: ChannelRewrites,
+    identity_channel: Option<IdentityChannel>,
+    trusted_proxies: TrustedProxies,
+    reject_on_connect_error: bool,
+    throttle: Option<Throttle>,
+    access_log: Option<Arc<dyn AccessLogSink>>,
+    ids: Option<Arc<dyn IdGenerator>>,
+    compact_on: Vec<String>,
+    payload_limit: Option<PayloadLimit>,
+    stored_payload_limit: Option<PayloadLimit>,
+    resume_ttl: Option<Duration>,
+    channel_usage: Option<Duration>,
+    persist_resume: bool,
+    migration_retry: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    failover_after: Option<Duration>,
+    decode_dead_letter: Option<DecodeErrorFn>,
+    restart_policy: RestartPolicy,
+    source_state_hook: Option<SourceStateHook>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+    binds: Vec<Bind>,
+    extra_routes: Router,
+    layers: Vec<RouterLayer>,
+    server_options: ServerOptions,
+    pub(crate) sse_path: String,
+    pub(crate) channel_param: String,
+    channel_in_path: bool,
+    #[cfg(feature = "ws")]
+    ws_path: String,
+    #[cfg(feature = "grpc")]
+    enable_grpc: bool,
+    #[cfg(feature = "compression")]
+    compression: Option<crate::compression::Compression>,
+    push: Option<PushEndpoint>,
+    import_body_limit: usize,
+    cluster: Option<Arc<dyn ClusterCoordinator>>,
+    fanout: Option<Arc<dyn ClusterFanout>>,
+    offline_fallback: Option<Arc<dyn OfflineFallback>>,
+    channel_router: Option<ChannelRouter>,
+    presence_events: Option<String>,
+    #[cfg(feature = "webhooks")]
+    lifecycle_webhook: Option<crate::webhook::LifecycleWebhook>,
+    #[cfg(feature = "webhooks")]
+    webhook_subscribers: Vec<(String, crate::webhook::WebhookSubscriber)>,
+    #[cfg(feature = "schema")]
+    schema: Option<Arc<SchemaValidator>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<ChaosLayer>,
+    #[cfg(feature = "config")]
+    live_config: Option<LiveConfig>,
 }
 
 impl<Source: MessageSource, Storage: MessageStorage> Gateway<Source, Storage> {
-    /// Run the gateway server
-    pub async fn run(self) -> anyhow::Result<()> {
+    /// Start background tasks and return the gateway's routes without serving them
+    ///
+    /// Use this to embed the SSE endpoint, dashboard and API routes into an
+    /// existing Axum application. The returned handle owns the message source,
+    /// heartbeat and cleanup tasks; call [`GatewayHandle::shutdown`] when the
+    /// host application stops. Must be called from within a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (gateway_routes, handle) = Gateway::builder()
+    ///     .source(NoopSource)
+    ///     .storage(MemoryStorage::default())
+    ///     .build()?
+    ///     .into_router();
+    ///
+    /// let app = Router::new()
+    ///     .route("/", get(index))
+    ///     .nest("/realtime", gateway_routes);
+    ///
+    /// axum::serve(listener, app).await?;
+    /// handle.shutdown().await;
+    /// ```
+    pub fn into_router(self) -> (Router, GatewayHandle) {
+        let (routers, handle) = self.into_routers();
+        (routers.router(Routes::All), handle)
+    }
+
+    /// Like [`into_router`](Self::into_router), with the routes kept apart so
+    /// each listener can serve its own selection
+    fn into_routers(self) -> (GatewayRouters, GatewayHandle) {
         let cancel = CancellationToken::new();
+        let mut tasks = Vec::new();
 
         tracing::info!(
-            port = self.port,
             source = self.source.name(),
             storage = self.storage.name(),
             "Starting SSE Gateway"
@@ -48,15 +221,142 @@ impl<Source: MessageSource, Storage: MessageStorage> Gateway<Source, Storage> {
         // Wrap source in Arc for sharing
         let source = Arc::new(self.source);
 
-        // Create lifecycle callbacks that delegate to source
+        let cluster = self
+            .cluster
+            .map(|coordinator| Cluster::new(coordinator, self.connection_manager.instance_id()));
+
+        let dispatcher = Dispatcher::new(
+            self.connection_manager.clone(),
+            self.storage.clone(),
+            self.throttle.clone(),
+            cancel.clone(),
+        )
+        .with_cluster(cluster.clone())
+        .with_fanout(self.fanout.clone())
+        .with_offline_fallback(self.offline_fallback.clone())
+        .with_tenancy(self.tenancy.clone())
+        .with_ids(self.ids.clone())
+        .with_compaction(self.compact_on.clone().into())
+        .with_payload_limits(self.payload_limit.clone(), self.stored_payload_limit.clone())
+        .with_rewrites(self.channel_rewrites.clone());
+        #[cfg(feature = "schema")]
+        let dispatcher = dispatcher.with_schema(self.schema.clone());
+        #[cfg(feature = "chaos")]
+        let dispatcher = dispatcher.with_chaos(self.chaos.clone());
+        let decode_manager = self.connection_manager.clone();
+        let decode_dead_letter = self.decode_dead_letter.clone();
+        let handler = dispatcher
+            .into_handler()
+            .with_dispatch_queue(
+                DispatchQueue::new(self.dispatch_concurrency)
+                    .with_capacity(self.dispatch_queue_capacity)
+                    .with_overflow(self.dispatch_overflow)
+                    .with_metrics(self.connection_manager.shared_metrics()),
+            )
+            .with_decode_errors(move |error| {
+                let metrics = decode_manager.metrics();
+                metrics.record_decode_failure(&error.source);
+                if let Some(dead_letter) = &decode_dead_letter {
+                    GatewayMetrics::incr(&metrics.dead_lettered);
+                    dead_letter(error);
+                }
+            });
+
+        let presence = self.presence_events.map(|template| {
+            let (presence, task) = PresenceEvents::spawn(template, handler.clone(), cancel.clone());
+            tasks.push(task);
+            Arc::new(presence)
+        });
+
+        #[cfg(feature = "webhooks")]
+        let webhook = self.lifecycle_webhook.map(|config| {
+            let (notifier, task) = crate::webhook::WebhookNotifier::spawn(config, cancel.clone());
+            tasks.push(task);
+            Arc::new(notifier)
+        });
+
+        #[cfg(feature = "webhooks")]
+        let webhook_subscribers = {
+            let subscribers = crate::webhook::WebhookSubscribers::new(
+                self.connection_manager.clone(),
+                cluster.clone(),
+                cancel.clone(),
+            );
+            for (channel_id, subscriber) in self.webhook_subscribers {
+                if let Err(e) = subscribers.subscribe(&channel_id, subscriber) {
+                    tracing::error!(error = %e, channel_id, "Failed to register webhook subscriber");
+                }
+            }
+            subscribers
+        };
+
+        // The source's connect hook is awaited by the handler
         let source_for_connect = source.clone();
+        let connect_hook: ConnectHook = Arc::new(move |info| {
+            let source = source_for_connect.clone();
+            Box::pin(async move { source.on_connect(&info).await })
+        });
+
+        // Create lifecycle callbacks for built-in features (and claim channels in the cluster)
+        let cluster_for_connect = cluster.clone();
+        let presence_for_connect = presence.clone();
+        #[cfg(feature = "webhooks")]
+        let webhook_for_connect = webhook.clone();
+        let manager_for_connect = self.connection_manager.clone();
         let on_connect: LifecycleCallback = Arc::new(move |info| {
-            source_for_connect.on_connect(info);
+            if let Some(cluster) = &cluster_for_connect {
+                cluster.on_connect(&manager_for_connect, &info.channel_id);
+            }
+            if let Some(presence) = &presence_for_connect {
+                presence.join(&manager_for_connect, info);
+            }
+            #[cfg(feature = "webhooks")]
+            if let Some(webhook) = &webhook_for_connect {
+                webhook.connect(&manager_for_connect, info);
+            }
         });
 
         let source_for_disconnect = source.clone();
+        let cluster_for_disconnect = cluster.clone();
+        let presence_for_disconnect = presence;
+        let manager_for_disconnect = self.connection_manager.clone();
         let on_disconnect: LifecycleCallback = Arc::new(move |info| {
-            source_for_disconnect.on_disconnect(info);
+            if let Some(cluster) = &cluster_for_disconnect {
+                cluster.on_disconnect(&manager_for_disconnect, &info.channel_id);
+            }
+            if let Some(presence) = &presence_for_disconnect {
+                presence.leave(info);
+            }
+            #[cfg(feature = "webhooks")]
+            if let Some(webhook) = &webhook {
+                webhook.disconnect(info);
+            }
+            let source = source_for_disconnect.clone();
+            let info = info.clone();
+            tokio::spawn(async move {
+                if let Err(e) = source.on_disconnect(&info).await {
+                    tracing::warn!(error = %e, connection_id = %info.connection_id, "on_disconnect hook failed");
+                }
+            });
+        });
+
+        // Render the browser client before the settings move into the state
+        let markers = self.replay_markers.clone().unwrap_or_default();
+        let client_script = ClientScript::new(&self.sse_path, &self.channel_param, &markers.start, &markers.end);
+        #[cfg(feature = "openapi")]
+        let openapi = crate::openapi::OpenApiDocument::new(&crate::openapi::ApiSurface {
+            sse_path: &self.sse_path,
+            channel_param: &self.channel_param,
+            channel_in_path: self.channel_in_path,
+            push_path: self.push.as_ref().map(|push| push.path.as_str()),
+            #[cfg(feature = "ws")]
+            ws_path: Some(&self.ws_path),
+            #[cfg(not(feature = "ws"))]
+            ws_path: None,
+            metrics: self.enable_metrics.unwrap_or(self.enable_dashboard),
+            dashboard: self.enable_dashboard,
+            cluster: cluster.is_some(),
+            webhooks: cfg!(feature = "webhooks"),
         });
 
         // Create shared state
@@ -64,28 +364,173 @@ impl<Source: MessageSource, Storage: MessageStorage> Gateway<Source, Storage> {
             connection_manager: self.connection_manager.clone(),
             storage: self.storage.clone(),
             auth: self.auth.clone(),
+            admin_auth: self.admin_auth.clone(),
+            identity: self.identity.clone(),
+            attribute_params: self.attribute_params.clone().into(),
+            attributes: self.attributes.clone(),
+            event_types: self.event_types.clone(),
+            event_encoder: self.event_encoder.clone(),
+            tenancy: self.tenancy.clone(),
+            connect_hook: Some(connect_hook),
+            reject_on_connect_error: self.reject_on_connect_error,
             on_connect: Some(on_connect),
             on_disconnect: Some(on_disconnect),
+            access_log: self.access_log.clone(),
+            publisher: Publisher::new(handler.clone()),
+            ids: self.ids.clone(),
+            compact_on: self.compact_on.clone().into(),
+            payload_limit: self.payload_limit.clone(),
+            stored_payload_limit: self.stored_payload_limit.clone(),
+            throttle: self.throttle.clone(),
+            channel_param: self.channel_param.into(),
+            push: self.push.clone().map(Arc::new),
+            heartbeat: self.heartbeat.clone(),
+            keep_alive: (!self.keep_alive_interval.is_zero()).then_some(self.keep_alive_interval),
+            retry_policy: self.retry_policy,
+            cluster: cluster.clone(),
+            channel_router: self.channel_router.clone(),
+            write_coalescing: self.write_coalescing,
+            compat: self.compat,
+            replay_markers: self.replay_markers,
+            channel_rewrites: self.channel_rewrites.clone(),
+            identity_channel: self.identity_channel.clone(),
+            trusted_proxies: self.trusted_proxies.clone(),
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+            #[cfg(feature = "schema")]
+            schema: self.schema.clone(),
+            resume: self.resume_ttl.map(|ttl| {
+                let sessions = ResumeSessions::new(ttl);
+                if !self.persist_resume {
+                    return Arc::new(sessions);
+                }
+                let storage = self.storage.clone();
+                Arc::new(sessions.persisted(Arc::new(move |token, session| {
+                    let storage = storage.clone();
+                    Box::pin(async move { storage.save_session(&token, &session, ttl).await })
+                })))
+            }),
         };
 
-        // Start message source
-        let dispatcher = Dispatcher::new(self.connection_manager.clone(), self.storage.clone());
-        let handler = dispatcher.to_handler();
-        let source_cancel = cancel.clone();
-        let source_name = source.name();
-        let source_connection_manager = self.connection_manager.clone();
+        // Join the cluster
+        if let Some(cluster) = &cluster {
+            let coordinator = cluster.coordinator().clone();
+            let cluster_manager = self.connection_manager.clone();
+            let cluster_cancel = cancel.clone();
+            tracing::info!(coordinator = coordinator.name(), "Cluster mode enabled");
+            tasks.push(tokio::spawn(async move {
+                if let Err(e) = coordinator.start(cluster_manager, cluster_cancel).await {
+                    tracing::error!(error = %e, coordinator = coordinator.name(), "Cluster coordinator error");
+                }
+            }));
 
-        tokio::spawn(async move {
-            if let Err(e) = source.start(handler, source_connection_manager, source_cancel).await {
-                tracing::error!(error = %e, source = source_name, "Message source error");
+            // Take over from instances that stopped heartbeating
+            if let Some(silent_for) = self.failover_after {
+                let cluster = cluster.clone();
+                let failover_manager = self.connection_manager.clone();
+                let failover_cancel = cancel.clone();
+                tasks.push(tokio::spawn(async move {
+                    let mut interval = tokio::time::interval((silent_for / 2).max(Duration::from_secs(1)));
+                    loop {
+                        tokio::select! {
+                            _ = failover_cancel.cancelled() => break,
+                            _ = interval.tick() => {
+                                cluster.take_over(&failover_manager, silent_for).await;
+                            }
+                        }
+                    }
+                }));
             }
-        });
+
+            // Follow channel pauses set on other instances
+            let pause_cluster = cluster.clone();
+            let pause_manager = self.connection_manager.clone();
+            let pause_cancel = cancel.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(PAUSE_SYNC_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = pause_cancel.cancelled() => break,
+                        _ = interval.tick() => {
+                            pause_cluster.sync_pauses(&pause_manager).await;
+                        }
+                    }
+                }
+            }));
+
+            // Keep the channel router's ring in sync with the membership
+            if let Some(router) = self.channel_router.clone() {
+                let coordinator = cluster.coordinator().clone();
+                let router_cancel = cancel.clone();
+                tasks.push(tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(router.refresh_every());
+                    loop {
+                        tokio::select! {
+                            _ = router_cancel.cancelled() => break,
+                            _ = interval.tick() => {
+                                if let Err(e) = router.refresh(coordinator.as_ref()).await {
+                                    tracing::warn!(error = %e, "Failed to refresh channel router");
+                                }
+                            }
+                        }
+                    }
+                }));
+            }
+        }
+
+        // Receive other instances' messages over the fanout bus
+        if let Some(fanout) = self.fanout.clone() {
+            let fanout_manager = self.connection_manager.clone();
+            let fanout_cancel = cancel.clone();
+            tracing::info!(fanout = fanout.name(), "Cluster fanout enabled");
+            tasks.push(tokio::spawn(async move {
+                if let Err(e) = fanout.start(fanout_manager, fanout_cancel).await {
+                    tracing::error!(error = %e, fanout = fanout.name(), "Cluster fanout error");
+                }
+            }));
+        }
+
+        #[cfg(feature = "config")]
+        if let Some(live) = &self.live_config {
+            live.attach(
+                self.throttle.clone(),
+                self.connection_manager.channel_configs().clone(),
+                self.channel_rewrites.clone(),
+            );
+            tasks.push(live.spawn_watcher(cancel.clone()));
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.chaos {
+            tracing::warn!("Chaos fault injection enabled");
+            tasks.push(chaos.spawn_killer(self.connection_manager.clone(), cancel.clone()));
+        }
+
+        // Start message source under the supervisor
+        let source_health = SourceHealth::new(source.name());
+        let handler_for_handle = handler.clone();
+        tasks.push(tokio::spawn(supervisor::supervise(
+            source,
+            handler,
+            self.connection_manager.clone(),
+            self.restart_policy.clone(),
+            source_health.clone(),
+            self.source_state_hook.clone(),
+            cancel.clone(),
+        )));
 
         // Start cleanup task
         let cleanup_manager = self.connection_manager.clone();
         let cleanup_cancel = cancel.clone();
         let cleanup_interval = self.cleanup_interval;
-        tokio::spawn(async move {
+        let cleanup_throttle = self.throttle.clone();
+        let cleanup_tenancy = self.tenancy.clone();
+        let cleanup_resume = state.resume.clone();
+        let cleanup_storage = self.storage.clone();
+        let cleanup_cluster = cluster.clone();
+        let migration_retry = self.migration_retry;
+        let retry_policy = self.retry_policy;
+        tasks.push(tokio::spawn(async move {
             let mut interval = tokio::time::interval(cleanup_interval);
             loop {
                 tokio::select! {
@@ -99,16 +544,88 @@ impl<Source: MessageSource, Storage: MessageStorage> Gateway<Source, Storage> {
                             cleaned = before.saturating_sub(after),
                             "Connection cleanup"
                         );
+                        if let Some(throttle) = &cleanup_throttle {
+                            throttle.cleanup_idle(cleanup_interval);
+                        }
+                        if let Some(tenancy) = &cleanup_tenancy {
+                            tenancy.cleanup_idle(cleanup_interval);
+                        }
+                        if let Some(sessions) = &cleanup_resume {
+                            sessions.cleanup_expired();
+                            cleanup_storage.cleanup_expired_sessions().await;
+                            // Refresh stored copies of long-lived sessions
+                            sessions.persist(true).await;
+                        }
                     }
                 }
             }
-        });
+            // Point subscribers at a healthy instance before their streams end
+            if let Some(retry) = migration_retry {
+                let target = match &cleanup_cluster {
+                    Some(cluster) => cluster.migration_target().await,
+                    None => None,
+                };
+                let sent = match retry_policy {
+                    // Each subscriber gets its own delay so they don't reconnect in lockstep
+                    Some(policy) => {
+                        let mut sent = 0;
+                        for info in cleanup_manager.list_connections() {
+                            let event = migrate_event(target.as_ref(), policy.pick());
+                            if cleanup_manager.send_to_connection(&info.id, event).await {
+                                sent += 1;
+                            }
+                        }
+                        sent
+                    }
+                    None => cleanup_manager.broadcast(migrate_event(target.as_ref(), retry)).await,
+                };
+                tracing::info!(sent, target = ?target.map(|t| t.id), "Sent migration hints");
+            }
+            // End open streams so servers can drain, reporting why they closed
+            let closed = cleanup_manager.close_all(CloseReason::ServerShutdown);
+            if closed > 0 {
+                tracing::info!(closed, "Closed connections for shutdown");
+            }
+            // Keep sessions resumable by the next run
+            if let Some(sessions) = &cleanup_resume {
+                sessions.persist(false).await;
+            }
+        }));
+
+        // Add channel usage to the stored daily totals
+        if let (Some(usage), Some(flush_interval)) = (self.connection_manager.usage().cloned(), self.channel_usage) {
+            let usage_manager = self.connection_manager.clone();
+            let usage_storage = self.storage.clone();
+            let usage_cancel = cancel.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(flush_interval);
+                interval.tick().await;
+                loop {
+                    tokio::select! {
+                        _ = usage_cancel.cancelled() => break,
+                        _ = interval.tick() => {
+                            usage.flush(&usage_storage, &usage_manager).await;
+                        }
+                    }
+                }
+                usage.flush(&usage_storage, &usage_manager).await;
+            }));
+        }
+
+        // Sample metrics for the history endpoint
+        let serve_metrics = self.enable_metrics.unwrap_or(self.enable_dashboard);
+        if serve_metrics {
+            tasks.push(
+                self.metrics_history
+                    .spawn_sampler(self.connection_manager.clone(), cancel.clone()),
+            );
+        }
 
         // Start heartbeat task
         let heartbeat_manager = self.connection_manager.clone();
         let heartbeat_cancel = cancel.clone();
         let heartbeat_interval = self.heartbeat_interval;
-        tokio::spawn(async move {
+        tasks.push(tokio::spawn(async move {
             let mut interval = tokio::time::interval(heartbeat_interval);
             loop {
                 tokio::select! {
@@ -118,71 +635,471 @@ impl<Source: MessageSource, Storage: MessageStorage> Gateway<Source, Storage> {
                     }
                 }
             }
-        });
+        }));
 
-        // Build router
-        let mut app = Router::new()
+        // Build routers: probes on every listener, then subscriber-facing and admin routes
+        let probes = Router::new()
             .route("/health", get(|| async { "OK" }))
-            .route("/ready", get(|| async { "READY" }))
-            .route("/sse/connect", get(handler::sse_connect::<Storage>));
+            .route("/ready", get(handler::ready).with_state(source_health.clone()));
+        let mut app = Router::new()
+            .route(&self.sse_path, get(handler::sse_connect::<Storage>))
+            .route("/api/channels/{channel_id}/cursor", get(handler::get_cursor::<Storage>))
+            .route("/sse/ack", axum::routing::post(handler::ack::<Storage>))
+            .route("/api/presence/{channel_id}", get(handler::get_presence::<Storage>));
+
+        app = app.route(CLIENT_SCRIPT_PATH, get(move || async move { client_script.response() }));
+
+        #[cfg(feature = "openapi")]
+        {
+            app = app.route(crate::openapi::OPENAPI_PATH, get(move || async move { openapi.response() }));
+        }
+
+        if cluster.is_some() {
+            app = app.route("/route", get(handler::route::<Storage>));
+        }
+
+        if self.channel_in_path {
+            let path = format!("{}/{{channel_id}}", self.sse_path.trim_end_matches('/'));
+            app = app.route(&path, get(handler::sse_connect_path::<Storage>));
+        }
+
+        #[cfg(feature = "ws")]
+        {
+            tracing::info!(path = %self.ws_path, "WebSocket endpoint enabled");
+            app = app.route(&self.ws_path, get(crate::ws::ws_connect::<Storage>));
+        }
+
+        #[cfg(feature = "grpc")]
+        if self.enable_grpc {
+            use crate::grpc::{proto::subscriber_server::SubscriberServer, SubscriberService, SERVICE_PATH};
+
+            tracing::info!(path = SERVICE_PATH, "gRPC subscriber endpoint enabled");
+            let service = SubscriberServer::new(SubscriberService::new(state.clone()));
+            app = app.route_service(&format!("{}/{{*rpc}}", SERVICE_PATH), service);
+        }
+
+        if let Some(push) = &self.push {
+            tracing::info!(path = %push.path, "Push endpoint enabled");
+            let batch_path = format!("{}/batch", push.path.trim_end_matches('/'));
+            app = app
+                .route(&push.path, axum::routing::post(push::push_message::<Storage>))
+                .route(&batch_path, axum::routing::post(push::push_batch::<Storage>));
+        }
+
+        let mut admin = Router::new();
+        if serve_metrics {
+            admin = admin.route("/api/metrics", get(handler::get_metrics::<Storage>));
+        }
 
         if self.enable_dashboard {
             tracing::info!("Dashboard enabled at /dashboard");
-            app = app
-                .route("/dashboard", get(handler::dashboard_page))
+            let manage = Router::new()
+                .route("/admin/broadcast", axum::routing::post(handler::admin_broadcast::<Storage>))
+                .route(
+                    "/api/channels/{channel_id}/messages",
+                    axum::routing::delete(handler::purge_channel_messages::<Storage>),
+                )
+                .route("/api/channels/{channel_id}/pause", axum::routing::post(handler::pause_channel::<Storage>))
+                .route("/api/channels/{channel_id}/resume", axum::routing::post(handler::resume_channel::<Storage>))
+                .route(
+                    "/api/channels/{channel_id}/import",
+                    axum::routing::post(handler::import_channel::<Storage>)
+                        .layer(axum::extract::DefaultBodyLimit::max(self.import_body_limit)),
+                )
+                .route(
+                    "/api/channels/{channel_id}/config",
+                    axum::routing::put(handler::put_channel_config::<Storage>)
+                        .delete(handler::delete_channel_config::<Storage>),
+                )
+                .route_layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    handler::authorize_admin::<Storage>,
+                ));
+            admin = admin
                 .route("/api/stats", get(handler::get_stats::<Storage>))
-                .route("/api/send", axum::routing::post(handler::send_message::<Storage>));
+                .route("/api/send", axum::routing::post(handler::send_message::<Storage>))
+                .route("/api/channels", get(handler::list_channels::<Storage>))
+                .route("/api/channels/{channel_id}/messages", get(handler::get_channel_messages::<Storage>))
+                .route("/api/channels/{channel_id}/export", get(handler::export_channel::<Storage>))
+                .route("/api/channels/{channel_id}/stats", get(handler::get_channel_usage::<Storage>))
+                .route("/api/channels/{channel_id}/config", get(handler::get_channel_config::<Storage>))
+                .merge(manage);
+            if cluster.is_some() {
+                admin = admin
+                    .route("/api/cluster", get(handler::get_cluster::<Storage>))
+                    .route(
+                        "/api/cluster/channels/{channel_id}",
+                        get(handler::get_channel_owners::<Storage>),
+                    );
+            }
+        }
+
+        let public = app.with_state(state.clone()).merge(self.extra_routes);
+        let mut admin = admin.with_state(state);
+        if serve_metrics {
+            admin = admin.merge(self.metrics_history.router());
+        }
+        if self.enable_dashboard {
+            admin = admin.merge(Dashboard::new(&self.dashboard).router());
+        }
+        #[cfg(feature = "webhooks")]
+        if self.enable_dashboard {
+            admin = admin.merge(webhook_subscribers.router());
+        }
+        #[cfg(feature = "chaos")]
+        let admin = match &self.chaos {
+            Some(chaos) => admin.merge(chaos.router(self.connection_manager.clone())),
+            None => admin,
+        };
+        #[cfg(feature = "config")]
+        let admin = match &self.live_config {
+            Some(live) => admin.merge(live.router()),
+            None => admin,
+        };
+
+        let routers = GatewayRouters {
+            probes,
+            public,
+            admin,
+            layers: self.layers,
+        };
+
+        let handle = GatewayHandle {
+            cancel,
+            connection_manager: self.connection_manager,
+            handler: handler_for_handle,
+            source_health,
+            fail_fast: self.restart_policy.fail_fast,
+            local_addrs: Vec::new(),
+            server: None,
+            tasks,
+        };
+
+        (routers, handle)
+    }
+
+    /// Bind the listeners and serve in the background
+    ///
+    /// Unlike [`run`](Self::run), this returns as soon as every address is
+    /// bound and installs no signal handlers, so the gateway can live inside
+    /// a larger service: publish with [`GatewayHandle::publish`] and stop it
+    /// with [`GatewayHandle::shutdown`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let gateway = Gateway::builder()
+    ///     .port(8080)
+    ///     .source(NoopSource)
+    ///     .storage(MemoryStorage::default())
+    ///     .build()?
+    ///     .start()
+    ///     .await?;
+    ///
+    /// gateway.await_ready().await;
+    /// gateway.publish(IncomingMessage::new("order.created", payload).with_channel("orders")).await?;
+    /// // ...
+    /// gateway.shutdown().await;
+    /// ```
+    pub async fn start(mut self) -> anyhow::Result<GatewayHandle> {
+        let binds = if self.binds.is_empty() {
+            vec![Bind::Tcp(SocketAddr::from(([0, 0, 0, 0], self.port)), Routes::All)]
+        } else {
+            std::mem::take(&mut self.binds)
+        };
+        #[cfg(feature = "tls")]
+        let tls = self.tls.take();
+        let options = self.server_options;
+
+        let (routers, mut handle) = self.into_routers();
+        let cancel = handle.cancellation_token();
+
+        // Bind everything up front so a bad address fails before serving starts
+        let mut servers = tokio::task::JoinSet::new();
+        let mut local_addrs = Vec::new();
+        let bound: anyhow::Result<()> = async {
+            for bind in binds {
+                match bind {
+                    Bind::Tcp(addr, routes) => {
+                        let app = routers.router(routes);
+                        let listener = tokio::net::TcpListener::bind(addr).await?;
+                        let addr = listener.local_addr()?;
+                        local_addrs.push(addr);
+
+                        #[cfg(feature = "tls")]
+                        if let Some(tls) = &tls {
+                            tracing::info!(?routes, "Listening on {} (TLS)", addr);
+                            let listener = TlsListener::new(listener, tls.clone(), cancel.clone())?;
+                            let shutdown = cancel.clone().cancelled_owned();
+                            servers.spawn(serve::serve(listener, app, options, shutdown));
+                            continue;
+                        }
+
+                        tracing::info!(?routes, "Listening on {}", addr);
+                        let shutdown = cancel.clone().cancelled_owned();
+                        servers.spawn(serve::serve(listener, app, options, shutdown));
+                    }
+                    #[cfg(unix)]
+                    Bind::Unix(path, routes) => {
+                        remove_stale_socket(&path)?;
+                        let listener = tokio::net::UnixListener::bind(&path)?;
+                        tracing::info!(?routes, "Listening on unix:{}", path.display());
+
+                        let shutdown = cancel.clone().cancelled_owned();
+                        let server = serve::serve(listener, routers.router(routes), options, shutdown);
+                        servers.spawn(async move {
+                            let result = server.await;
+                            std::fs::remove_file(&path).ok();
+                            result
+                        });
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = bound {
+            handle.shutdown().await;
+            servers.join_all().await;
+            return Err(e);
+        }
+
+        handle.local_addrs = local_addrs;
+        handle.server = Some(tokio::spawn(async move {
+            let mut result = Ok(());
+            while let Some(joined) = servers.join_next().await {
+                if let Err(e) = joined.map_err(anyhow::Error::from).and_then(|r| r.map_err(Into::into)) {
+                    tracing::error!(error = %e, "Listener failed, shutting down");
+                    cancel.cancel();
+                    result = Err(e);
+                }
+            }
+            result
+        }));
+        Ok(handle)
+    }
+
+    /// Run the gateway server until Ctrl+C, SIGTERM or a fatal error
+    pub async fn run(self) -> anyhow::Result<()> {
+        let handle = self.start().await?;
+        let cancel = handle.cancellation_token();
+        tokio::spawn(shutdown_signal(cancel.clone()));
+
+        cancel.cancelled().await;
+        let result = handle.stop().await;
+        tracing::info!("Gateway shutdown complete");
+        result
+    }
+}
+
+/// Handle to a running gateway
+///
+/// Returned by [`Gateway::start`] and [`Gateway::into_router`]. Dropping the
+/// handle does not stop the gateway; call [`shutdown`](Self::shutdown) to stop
+/// the listeners, source, heartbeat and cleanup tasks.
+pub struct GatewayHandle {
+    cancel: CancellationToken,
+    connection_manager: ConnectionManager,
+    handler: MessageHandler,
+    source_health: SourceHealth,
+    fail_fast: bool,
+    local_addrs: Vec<SocketAddr>,
+    server: Option<tokio::task::JoinHandle<anyhow::Result<()>>>,
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl GatewayHandle {
+    /// Get the connection manager shared with the gateway routes
+    pub fn connection_manager(&self) -> &ConnectionManager {
+        &self.connection_manager
+    }
+
+    /// Get the handler the message source publishes through
+    ///
+    /// Messages sent here are dispatched exactly like the source's own, which
+    /// lets host code and tests publish without a custom source.
+    pub fn message_handler(&self) -> &MessageHandler {
+        &self.handler
+    }
+
+    /// Get a publisher that stores and delivers like the message source
+    pub fn publisher(&self) -> Publisher {
+        Publisher::new(self.handler.clone())
+    }
+
+    /// Publish a message from the host application and wait for the outcome
+    ///
+    /// The message is stored and delivered exactly like one from the source.
+    pub async fn publish(&self, msg: IncomingMessage) -> DispatchResult {
+        self.handler.dispatch(msg).await
+    }
+
+    /// Get the supervised message source's state
+    pub fn source_health(&self) -> &SourceHealth {
+        &self.source_health
+    }
+
+    /// Wait until the message source is running
+    ///
+    /// Returns immediately if it already is. If the source has failed for
+    /// good, this waits until the gateway shuts down.
+    pub async fn await_ready(&self) {
+        let mut states = self.source_health.subscribe();
+        tokio::select! {
+            _ = states.wait_for(SourceState::is_ready) => {}
+            _ = self.cancel.cancelled() => {}
+        }
+    }
+
+    /// TCP addresses the listeners are bound to (empty for [`Gateway::into_router`])
+    ///
+    /// Useful with port `0` to learn the port the OS picked.
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// Get the token that is cancelled when the gateway shuts down
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Stop the listeners and background tasks and wait for them to finish
+    pub async fn shutdown(self) {
+        if let Err(e) = self.stop().await {
+            tracing::error!(error = %e, "Gateway stopped with an error");
+        }
+    }
+
+    /// Stop everything, returning the first listener error or, with
+    /// fail-fast, the source's error
+    async fn stop(mut self) -> anyhow::Result<()> {
+        self.cancel.cancel();
+        let mut result = match self.server.take() {
+            Some(server) => server.await.map_err(anyhow::Error::from).and_then(|r| r),
+            None => Ok(()),
+        };
+        for task in self.tasks {
+            let _ = task.await;
+        }
+        if let SourceState::Failed { error } = self.source_health.state() {
+            if self.fail_fast && result.is_ok() {
+                result = Err(anyhow::anyhow!("message source failed: {}", error));
+            }
         }
+        result
+    }
+}
+
+/// Address the gateway server listens on, and the routes served there
+#[derive(Debug, Clone)]
+enum Bind {
+    Tcp(SocketAddr, Routes),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf, Routes),
+}
 
-        let app = app
+/// Which of the gateway's routes a listener serves
+///
+/// `/health` and `/ready` are served on every listener.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Routes {
+    /// Every route
+    #[default]
+    All,
+    /// Subscriber and publisher routes: the SSE, WebSocket and gRPC
+    /// endpoints, push, presence, cursors, acks and custom routes
+    Public,
+    /// The dashboard, metrics and management APIs
+    Admin,
+}
+
+/// The gateway's routes, kept apart so each listener gets its selection
+struct GatewayRouters {
+    probes: Router,
+    public: Router,
+    admin: Router,
+    layers: Vec<RouterLayer>,
+}
+
+impl GatewayRouters {
+    /// A router serving `routes`, wrapped in the built-in and registered layers
+    fn router(&self, routes: Routes) -> Router {
+        let app = match routes {
+            Routes::All => self.probes.clone().merge(self.public.clone()).merge(self.admin.clone()),
+            Routes::Public => self.probes.clone().merge(self.public.clone()),
+            Routes::Admin => self.probes.clone().merge(self.admin.clone()),
+        };
+        let mut app = app
             .layer(
                 CorsLayer::new()
                     .allow_origin(Any)
                     .allow_methods(Any)
                     .allow_headers(Any),
             )
-            .layer(TraceLayer::new_for_http())
-            .with_state(state);
-
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.port));
-        tracing::info!("Listening on {}", addr);
+            .layer(TraceLayer::new_for_http());
 
-        let listener = tokio::net::TcpListener::bind(addr).await?;
+        for layer in &self.layers {
+            app = layer(app);
+        }
+        app
+    }
+}
 
-        let cancel_for_shutdown = cancel.clone();
-        let shutdown_signal = async move {
-            let ctrl_c = async {
-                tokio::signal::ctrl_c()
-                    .await
-                    .expect("Failed to install Ctrl+C handler");
-            };
+/// Remove a socket file left behind by a previous run at `path`
+///
+/// Anything else at `path` is an error rather than being deleted.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
 
-            #[cfg(unix)]
-            let terminate = async {
-                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-                    .expect("Failed to install SIGTERM handler")
-                    .recv()
-                    .await;
-            };
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => Ok(std::fs::remove_file(path)?),
+        Ok(_) => anyhow::bail!("{} exists and is not a Unix socket", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
 
-            #[cfg(not(unix))]
-            let terminate = std::future::pending::<()>();
+/// The `migrate` event sent to subscribers on shutdown
+///
+/// Its `retry` field sets how long `EventSource` waits before reconnecting.
+fn migrate_event(target: Option<&InstanceInfo>, retry: Duration) -> SseEvent {
+    let mut data = serde_json::Map::new();
+    if let Some(target) = target {
+        data.insert("instance_id".into(), target.id.clone().into());
+        data.insert("url".into(), target.base_url().into());
+    }
+    SseEvent::new(MIGRATE_EVENT, serde_json::Value::Object(data))
+        .with_retry(retry.as_millis().min(u32::MAX as u128) as u32)
+}
 
-            tokio::select! {
-                _ = ctrl_c => tracing::info!("Received Ctrl+C"),
-                _ = terminate => tracing::info!("Received SIGTERM"),
-            }
+/// Wait for Ctrl+C or SIGTERM, then cancel the gateway
+async fn shutdown_signal(cancel: CancellationToken) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
 
-            cancel_for_shutdown.cancel();
-        };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
 
-        axum::serve(listener, app)
-            .with_graceful_shutdown(shutdown_signal)
-            .await?;
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-        tracing::info!("Gateway shutdown complete");
-        Ok(())
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl+C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+        _ = cancel.cancelled() => return,
     }
+
+    cancel.cancel();
 }
 
 /// Builder for Gateway
@@ -192,9 +1109,86 @@ pub struct GatewayBuilder<Source = NoopSource, Storage = NoopStorage> {
     storage: Option<Storage>,
     instance_id: Option<String>,
     enable_dashboard: bool,
+    dashboard: DashboardConfig,
+    enable_metrics: Option<bool>,
+    metrics_history: MetricsHistory,
     heartbeat_interval: Duration,
+    keep_alive_interval: Duration,
+    heartbeat: Heartbeat,
+    dedup_window: usize,
     cleanup_interval: Duration,
     auth: Option<AuthFn>,
+    admin_auth: Option<AuthFn>,
+    identity: Option<IdentityFn>,
+    attribute_params: Vec<String>,
+    attributes: Option<AttributesFn>,
+    event_types: Option<EventTypesFn>,
+    event_encoder: Option<EncoderFn>,
+    tenancy: Option<Tenancy>,
+    dispatch_concurrency: usize,
+    dispatch_queue_capacity: usize,
+    dispatch_overflow: QueueOverflow,
+    write_coalescing: Option<WriteCoalescing>,
+    compat: CompatMode,
+    replay_markers: Option<handler::ReplayMarkers>,
+    channel_rewrites: ChannelRewrites,
+    identity_channel: Option<IdentityChannel>,
+    trusted_proxies: TrustedProxies,
+    reject_on_connect_error: bool,
+    throttle: Option<ThrottlePolicy>,
+    access_log: Option<Arc<dyn AccessLogSink>>,
+    ids: Option<Arc<dyn IdGenerator>>,
+    compact_on: Vec<String>,
+    payload_limit: Option<PayloadLimit>,
+    stored_payload_limit: Option<PayloadLimit>,
+    resume_ttl: Option<Duration>,
+    channel_usage: Option<Duration>,
+    persist_resume: bool,
+    migration_retry: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    failover_after: Option<Duration>,
+    decode_dead_letter: Option<DecodeErrorFn>,
+    capacity: Option<usize>,
+    cpu_hint: Option<Arc<dyn Fn() -> u8 + Send + Sync>>,
+    restart_policy: RestartPolicy,
+    source_state_hook: Option<SourceStateHook>,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+    binds: Vec<Bind>,
+    extra_routes: Router,
+    layers: Vec<RouterLayer>,
+    server_options: ServerOptions,
+    sse_path: String,
+    channel_param: String,
+    channel_in_path: bool,
+    #[cfg(feature = "ws")]
+    ws_path: String,
+    #[cfg(feature = "grpc")]
+    enable_grpc: bool,
+    #[cfg(feature = "compression")]
+    compression: Option<crate::compression::Compression>,
+    push: Option<PushEndpoint>,
+    import_body_limit: usize,
+    cluster: Option<Arc<dyn ClusterCoordinator>>,
+    fanout: Option<Arc<dyn ClusterFanout>>,
+    offline_fallback: Option<Arc<dyn OfflineFallback>>,
+    channel_router: Option<ChannelRouter>,
+    presence_events: Option<String>,
+    #[cfg(feature = "webhooks")]
+    lifecycle_webhook: Option<crate::webhook::LifecycleWebhook>,
+    #[cfg(feature = "webhooks")]
+    webhook_subscribers: Vec<(String, crate::webhook::WebhookSubscriber)>,
+    interceptors: Vec<Arc<dyn EventInterceptor>>,
+    channel_configs: ChannelConfigs,
+    cloudevents_source: Option<String>,
+    #[cfg(feature = "schema")]
+    schemas: Vec<(String, serde_json::Value)>,
+    #[cfg(feature = "schema")]
+    dead_letter: Option<DeadLetterFn>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<ChaosLayer>,
+    #[cfg(feature = "config")]
+    live_config: Option<LiveConfig>,
 }
 
 impl Default for GatewayBuilder {
@@ -205,9 +1199,86 @@ impl Default for GatewayBuilder {
             storage: None,
             instance_id: None,
             enable_dashboard: true,
+            dashboard: DashboardConfig::default(),
+            enable_metrics: None,
+            metrics_history: MetricsHistory::default(),
             heartbeat_interval: Duration::from_secs(30),
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            heartbeat: Heartbeat::default(),
+            dedup_window: 0,
             cleanup_interval: Duration::from_secs(30),
             auth: None,
+            admin_auth: None,
+            identity: None,
+            attribute_params: Vec::new(),
+            attributes: None,
+            event_types: None,
+            event_encoder: None,
+            tenancy: None,
+            dispatch_concurrency: DEFAULT_DISPATCH_CONCURRENCY,
+            dispatch_queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            dispatch_overflow: QueueOverflow::default(),
+            write_coalescing: None,
+            compat: CompatMode::default(),
+            replay_markers: None,
+            channel_rewrites: ChannelRewrites::default(),
+            identity_channel: None,
+            trusted_proxies: TrustedProxies::default(),
+            reject_on_connect_error: false,
+            throttle: None,
+            access_log: None,
+            ids: None,
+            compact_on: Vec::new(),
+            payload_limit: None,
+            stored_payload_limit: None,
+            resume_ttl: None,
+            channel_usage: None,
+            persist_resume: false,
+            migration_retry: None,
+            retry_policy: None,
+            failover_after: None,
+            decode_dead_letter: None,
+            capacity: None,
+            cpu_hint: None,
+            restart_policy: RestartPolicy::default(),
+            source_state_hook: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            binds: Vec::new(),
+            extra_routes: Router::new(),
+            layers: Vec::new(),
+            server_options: ServerOptions::default(),
+            sse_path: "/sse/connect".to_string(),
+            channel_param: "channel_id".to_string(),
+            channel_in_path: false,
+            #[cfg(feature = "ws")]
+            ws_path: "/ws/connect".to_string(),
+            #[cfg(feature = "grpc")]
+            enable_grpc: false,
+            #[cfg(feature = "compression")]
+            compression: None,
+            push: None,
+            import_body_limit: 64 * 1024 * 1024,
+            cluster: None,
+            fanout: None,
+            offline_fallback: None,
+            channel_router: None,
+            presence_events: None,
+            #[cfg(feature = "webhooks")]
+            lifecycle_webhook: None,
+            #[cfg(feature = "webhooks")]
+            webhook_subscribers: Vec::new(),
+            interceptors: Vec::new(),
+            channel_configs: ChannelConfigs::default(),
+            cloudevents_source: None,
+            #[cfg(feature = "schema")]
+            schemas: Vec::new(),
+            #[cfg(feature = "schema")]
+            dead_letter: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+            #[cfg(feature = "config")]
+            live_config: None,
         }
     }
 }
@@ -219,13 +1290,87 @@ impl Gateway<NoopSource, MemoryStorage> {
     }
 }
 
+#[cfg(feature = "config")]
+impl GatewayBuilder<NoopSource, BuiltinStorage> {
+    /// Builder configured from a YAML or TOML file plus `SSE_GATEWAY_*` overrides
+    ///
+    /// Storage and source are the built-in ones the file selects; replace
+    /// them with [`storage`](Self::storage) and [`source`](Self::source)
+    /// to keep the rest of the configuration. The file is re-read on
+    /// SIGHUP and `POST /admin/reload`. See [`config`](crate::config).
+    pub fn from_config(path: impl Into<std::path::PathBuf>) -> anyhow::Result<Self> {
+        Ok(Self::configured(LiveConfig::load(path)?))
+    }
+
+    /// Like [`from_config`](Self::from_config), reading the file named by
+    /// `SSE_GATEWAY_CONFIG` if set and the environment only otherwise
+    pub fn from_env() -> anyhow::Result<Self> {
+        Ok(Self::configured(LiveConfig::from_env()?))
+    }
+
+    fn configured(live: LiveConfig) -> Self {
+        let storage = live.current().build_storage();
+        GatewayBuilder::default()
+            .source(NoopSource)
+            .storage(storage)
+            .live_config(live)
+    }
+}
+
 impl<Source, Storage> GatewayBuilder<Source, Storage> {
     /// Set the server port
+    ///
+    /// Used to listen on `0.0.0.0:{port}` when no explicit [`bind`](Self::bind)
+    /// or [`bind_uds`](Self::bind_uds) addresses are configured.
     pub fn port(mut self, port: u16) -> Self {
         self.port = port;
         self
     }
 
+    /// Listen on a TCP address (may be called repeatedly)
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .bind(([0, 0, 0, 0], 8080))      // public SSE port
+    ///     .bind(([127, 0, 0, 1], 9090))    // localhost-only
+    /// ```
+    pub fn bind(self, addr: impl Into<SocketAddr>) -> Self {
+        self.bind_routes(addr, Routes::All)
+    }
+
+    /// Listen on a TCP address serving only `routes`
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .bind_routes(([0, 0, 0, 0], 8080), Routes::Public)   // subscribers
+    ///     .bind_routes(([127, 0, 0, 1], 9090), Routes::Admin)  // dashboard and APIs
+    /// ```
+    pub fn bind_routes(mut self, addr: impl Into<SocketAddr>, routes: Routes) -> Self {
+        self.binds.push(Bind::Tcp(addr.into(), routes));
+        self
+    }
+
+    /// Listen on a Unix domain socket (may be called repeatedly)
+    ///
+    /// A stale socket at `path` is removed before binding and the file is
+    /// deleted again on shutdown; binding fails if `path` is anything other
+    /// than a socket. TLS is not applied to Unix sockets.
+    #[cfg(unix)]
+    pub fn bind_uds(self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.bind_uds_routes(path, Routes::All)
+    }
+
+    /// Listen on a Unix domain socket serving only `routes`
+    #[cfg(unix)]
+    pub fn bind_uds_routes(mut self, path: impl Into<std::path::PathBuf>, routes: Routes) -> Self {
+        self.binds.push(Bind::Unix(path.into(), routes));
+        self
+    }
+
     /// Set the message source
     pub fn source<S: MessageSource>(self, source: S) -> GatewayBuilder<S, Storage> {
         GatewayBuilder {
@@ -234,9 +1379,86 @@ impl<Source, Storage> GatewayBuilder<Source, Storage> {
             storage: self.storage,
             instance_id: self.instance_id,
             enable_dashboard: self.enable_dashboard,
+            dashboard: self.dashboard,
+            enable_metrics: self.enable_metrics,
+            metrics_history: self.metrics_history,
             heartbeat_interval: self.heartbeat_interval,
+            keep_alive_interval: self.keep_alive_interval,
+            heartbeat: self.heartbeat,
+            dedup_window: self.dedup_window,
             cleanup_interval: self.cleanup_interval,
             auth: self.auth,
+            admin_auth: self.admin_auth,
+            identity: self.identity,
+            attribute_params: self.attribute_params,
+            attributes: self.attributes,
+            event_types: self.event_types,
+            event_encoder: self.event_encoder,
+            tenancy: self.tenancy,
+            dispatch_concurrency: self.dispatch_concurrency,
+            dispatch_queue_capacity: self.dispatch_queue_capacity,
+            dispatch_overflow: self.dispatch_overflow,
+            write_coalescing: self.write_coalescing,
+            compat: self.compat,
+            replay_markers: self.replay_markers,
+            channel_rewrites: self.channel_rewrites,
+            identity_channel: self.identity_channel,
+            trusted_proxies: self.trusted_proxies,
+            reject_on_connect_error: self.reject_on_connect_error,
+            throttle: self.throttle,
+            access_log: self.access_log,
+            ids: self.ids,
+            compact_on: self.compact_on,
+            payload_limit: self.payload_limit,
+            stored_payload_limit: self.stored_payload_limit,
+            resume_ttl: self.resume_ttl,
+            channel_usage: self.channel_usage,
+            persist_resume: self.persist_resume,
+            migration_retry: self.migration_retry,
+            retry_policy: self.retry_policy,
+            failover_after: self.failover_after,
+            decode_dead_letter: self.decode_dead_letter,
+            capacity: self.capacity,
+            cpu_hint: self.cpu_hint,
+            restart_policy: self.restart_policy,
+            source_state_hook: self.source_state_hook,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+            binds: self.binds,
+            extra_routes: self.extra_routes,
+            layers: self.layers,
+            server_options: self.server_options,
+            sse_path: self.sse_path,
+            channel_param: self.channel_param,
+            channel_in_path: self.channel_in_path,
+            #[cfg(feature = "ws")]
+            ws_path: self.ws_path,
+            #[cfg(feature = "grpc")]
+            enable_grpc: self.enable_grpc,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+            push: self.push,
+            import_body_limit: self.import_body_limit,
+            cluster: self.cluster,
+            fanout: self.fanout,
+            offline_fallback: self.offline_fallback,
+            channel_router: self.channel_router,
+            presence_events: self.presence_events,
+            #[cfg(feature = "webhooks")]
+            lifecycle_webhook: self.lifecycle_webhook,
+            #[cfg(feature = "webhooks")]
+            webhook_subscribers: self.webhook_subscribers,
+            interceptors: self.interceptors,
+            channel_configs: self.channel_configs,
+            cloudevents_source: self.cloudevents_source,
+            #[cfg(feature = "schema")]
+            schemas: self.schemas,
+            #[cfg(feature = "schema")]
+            dead_letter: self.dead_letter,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos,
+            #[cfg(feature = "config")]
+            live_config: self.live_config,
         }
     }
 
@@ -248,12 +1470,107 @@ impl<Source, Storage> GatewayBuilder<Source, Storage> {
             storage: Some(storage),
             instance_id: self.instance_id,
             enable_dashboard: self.enable_dashboard,
+            dashboard: self.dashboard,
+            enable_metrics: self.enable_metrics,
+            metrics_history: self.metrics_history,
             heartbeat_interval: self.heartbeat_interval,
+            keep_alive_interval: self.keep_alive_interval,
+            heartbeat: self.heartbeat,
+            dedup_window: self.dedup_window,
             cleanup_interval: self.cleanup_interval,
             auth: self.auth,
+            admin_auth: self.admin_auth,
+            identity: self.identity,
+            attribute_params: self.attribute_params,
+            attributes: self.attributes,
+            event_types: self.event_types,
+            event_encoder: self.event_encoder,
+            tenancy: self.tenancy,
+            dispatch_concurrency: self.dispatch_concurrency,
+            dispatch_queue_capacity: self.dispatch_queue_capacity,
+            dispatch_overflow: self.dispatch_overflow,
+            write_coalescing: self.write_coalescing,
+            compat: self.compat,
+            replay_markers: self.replay_markers,
+            channel_rewrites: self.channel_rewrites,
+            identity_channel: self.identity_channel,
+            trusted_proxies: self.trusted_proxies,
+            reject_on_connect_error: self.reject_on_connect_error,
+            throttle: self.throttle,
+            access_log: self.access_log,
+            ids: self.ids,
+            compact_on: self.compact_on,
+            payload_limit: self.payload_limit,
+            stored_payload_limit: self.stored_payload_limit,
+            resume_ttl: self.resume_ttl,
+            channel_usage: self.channel_usage,
+            persist_resume: self.persist_resume,
+            migration_retry: self.migration_retry,
+            retry_policy: self.retry_policy,
+            failover_after: self.failover_after,
+            decode_dead_letter: self.decode_dead_letter,
+            capacity: self.capacity,
+            cpu_hint: self.cpu_hint,
+            restart_policy: self.restart_policy,
+            source_state_hook: self.source_state_hook,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+            binds: self.binds,
+            extra_routes: self.extra_routes,
+            layers: self.layers,
+            server_options: self.server_options,
+            sse_path: self.sse_path,
+            channel_param: self.channel_param,
+            channel_in_path: self.channel_in_path,
+            #[cfg(feature = "ws")]
+            ws_path: self.ws_path,
+            #[cfg(feature = "grpc")]
+            enable_grpc: self.enable_grpc,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+            push: self.push,
+            import_body_limit: self.import_body_limit,
+            cluster: self.cluster,
+            fanout: self.fanout,
+            offline_fallback: self.offline_fallback,
+            channel_router: self.channel_router,
+            presence_events: self.presence_events,
+            #[cfg(feature = "webhooks")]
+            lifecycle_webhook: self.lifecycle_webhook,
+            #[cfg(feature = "webhooks")]
+            webhook_subscribers: self.webhook_subscribers,
+            interceptors: self.interceptors,
+            channel_configs: self.channel_configs,
+            cloudevents_source: self.cloudevents_source,
+            #[cfg(feature = "schema")]
+            schemas: self.schemas,
+            #[cfg(feature = "schema")]
+            dead_letter: self.dead_letter,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos,
+            #[cfg(feature = "config")]
+            live_config: self.live_config,
         }
     }
 
+    /// Use `primary` for storage, falling back to `fallback` while it is unavailable
+    ///
+    /// Shorthand for [`storage`](Self::storage) with a [`FailoverStorage`];
+    /// build the `FailoverStorage` yourself to change its probe interval.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder().storage_with_fallback(redis_storage, MemoryStorage::default())
+    /// ```
+    pub fn storage_with_fallback<P, F>(self, primary: P, fallback: F) -> GatewayBuilder<Source, FailoverStorage<P, F>>
+    where
+        P: MessageStorage,
+        F: MessageStorage,
+    {
+        self.storage(FailoverStorage::new(primary, fallback))
+    }
+
     /// Set the authentication callback
     ///
     /// The callback receives an `AuthRequest` containing headers, channel_id, and client_ip.
@@ -290,51 +1607,1122 @@ impl<Source, Storage> GatewayBuilder<Source, Storage> {
         self
     }
 
-    /// Set the instance ID
+    /// Authorize requests to the dashboard's management endpoints
+    ///
+    /// Runs before broadcasting from `/admin/broadcast` and before purging,
+    /// importing, pausing, resuming and reconfiguring channels, with the
+    /// channel from the path (empty where there is none).
+    /// Without it, those endpoints run the [`auth`](Self::auth) callback;
+    /// with neither, they are open.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .admin_auth(|req: AuthRequest| async move {
+    ///         match req.bearer_token() {
+    ///             Some(token) if token == "admin-secret" => None,
+    ///             _ => Some(deny(StatusCode::UNAUTHORIZED, "Admin token required")),
+    ///         }
+    ///     })
+    /// ```
+    pub fn admin_auth<F, Fut>(mut self, auth_fn: F) -> Self
+    where
+        F: Fn(crate::auth::AuthRequest) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = crate::auth::AuthResponse> + Send + 'static,
+    {
+        self.admin_auth = Some(crate::auth::auth_fn(auth_fn));
+        self
+    }
+
+    /// Record an identity for each allowed connection
+    ///
+    /// Runs after the auth callback on the same [`AuthRequest`](crate::auth::AuthRequest);
+    /// the result is stored in [`ConnectionMetadata::identity`](crate::ConnectionMetadata)
+    /// and reported by presence and lifecycle webhooks.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .identity(|req: &AuthRequest| req.header("x-user-id").map(str::to_string))
+    /// ```
+    pub fn identity<F>(mut self, identity_fn: F) -> Self
+    where
+        F: Fn(&crate::auth::AuthRequest) -> Option<String> + Send + Sync + 'static,
+    {
+        self.identity = Some(Arc::new(identity_fn));
+        self
+    }
+
+    /// Subscribe each caller to the channel of its identity
+    ///
+    /// The channel is derived from the [`identity`](Self::identity) of the
+    /// request instead of trusting the channel parameter, so users can't
+    /// subscribe to each other's channels by guessing IDs. The parameter
+    /// becomes optional; naming another channel gets `403 Forbidden`, and a
+    /// request without an identity `401 Unauthorized`. The cursor and ack
+    /// endpoints check their channel the same way.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .identity(|req: &AuthRequest| verify_jwt(req.bearer_token()?).ok().map(|claims| claims.sub))
+    ///     .identity_channel(IdentityChannel::new("user:{identity}"))
+    /// ```
+    pub fn identity_channel(mut self, identity_channel: IdentityChannel) -> Self {
+        self.identity_channel = Some(identity_channel);
+        self
+    }
+
+    /// Proxies allowed to report the client address in `X-Forwarded-For`
+    ///
+    /// The client IP given to auth callbacks, access logs and stats is the
+    /// socket peer unless it is a trusted proxy. Defaults to trusting no
+    /// peer; use [`TrustedProxies::any`] for a gateway only reachable
+    /// through a proxy. See [`crate::client_ip`].
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder().trusted_proxies(TrustedProxies::new(["10.0.0.0/8", "fd00::/8"])?)
+    /// ```
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    /// Override the gateway defaults for channels matching `key`
+    ///
+    /// `key` is a channel ID or a prefix ending in `*`. See
+    /// [`channel_config`](crate::channel_config).
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .channel_config("ticker:*", ChannelConfig::new().storage(false))
+    ///     .channel_config("support", ChannelConfig::new().replay_depth(50))
+    /// ```
+    pub fn channel_config(self, key: impl Into<String>, config: ChannelConfig) -> Self {
+        self.channel_configs.set(key, config);
+        self
+    }
+
+    /// Rewrite channel IDs when clients subscribe and when messages are dispatched
+    ///
+    /// Keep a clone of `rewrites` to change the rules while the gateway
+    /// runs; see [`crate::channel_rewrite`]. Replaces rules added with
+    /// [`channel_rewrite`](Self::channel_rewrite).
+    ///
+    /// ```rust,ignore
+    /// let rewrites = ChannelRewrites::with_rules(vec![RewriteRule::pattern(r"^user_(\d+)$", "user:$1")?]);
+    /// Gateway::builder().channel_rewrites(rewrites.clone())
+    /// ```
+    pub fn channel_rewrites(mut self, rewrites: ChannelRewrites) -> Self {
+        self.channel_rewrites = rewrites;
+        self
+    }
+
+    /// Add a channel rewrite rule, tried after those already added
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder().channel_rewrite(RewriteRule::exact("global_feed", "feed:global"))
+    /// ```
+    pub fn channel_rewrite(self, rule: RewriteRule) -> Self {
+        self.channel_rewrites.push(rule);
+        self
+    }
+
+    /// Isolate tenants in their own channel namespaces
+    ///
+    /// Subscribers, cursor/presence lookups and push requests are scoped to
+    /// the tenant resolved from their request (`orders` becomes
+    /// `acme/orders`); requests without a tenant get `403 Forbidden`. See
+    /// [`tenancy`](crate::tenancy) for details and per-tenant quotas.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .tenancy(Tenancy::new(|req: &AuthRequest| req.header("x-tenant").map(str::to_string)))
+    /// ```
+    pub fn tenancy(mut self, tenancy: Tenancy) -> Self {
+        self.tenancy = Some(tenancy);
+        self
+    }
+
+    /// Limit concurrent fire-and-forget dispatches (default 256)
+    ///
+    /// Messages a source [`send`](crate::MessageHandler::send)s are
+    /// dispatched by this many workers, each channel's in order by one of
+    /// them. Beyond that they queue up by [`Priority`](crate::Priority), so
+    /// high priority alerts overtake a backlog of low priority telemetry.
+    ///
+    /// A worker waits for each message to be delivered before taking the
+    /// next, so a slow subscriber on a [`Backpressure::Wait`](crate::Backpressure::Wait)
+    /// channel delays the other channels sharing its worker.
+    pub fn dispatch_concurrency(mut self, limit: usize) -> Self {
+        self.dispatch_concurrency = limit;
+        self
+    }
+
+    /// Messages queued per dispatch worker and priority (default 1024)
+    pub fn dispatch_queue_capacity(mut self, capacity: usize) -> Self {
+        self.dispatch_queue_capacity = capacity;
+        self
+    }
+
+    /// What happens to messages sent while their dispatch queue is full
+    /// (default: dropped)
+    pub fn dispatch_overflow(mut self, overflow: QueueOverflow) -> Self {
+        self.dispatch_overflow = overflow;
+        self
+    }
+
+    /// Copy these query parameters into each connection's attributes
+    ///
+    /// Stored in [`ConnectionMetadata::attributes`](crate::ConnectionMetadata),
+    /// e.g. `/sse/connect?channel_id=u1&device=ios` records `device=ios`.
+    /// Parameters not listed here are ignored.
+    pub fn attribute_params<I, P>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.attribute_params.extend(params.into_iter().map(Into::into));
+        self
+    }
+
+    /// Derive attributes for each allowed connection from its request
+    ///
+    /// Runs after the auth callback, like [`identity`](Self::identity). Its
+    /// attributes override query parameters of the same name, so clients
+    /// cannot claim e.g. another tenant.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .attributes(|req: &AuthRequest| {
+    ///         req.bearer_token()
+    ///             .map(|token| HashMap::from([("tenant".to_string(), tenant_of(token))]))
+    ///             .unwrap_or_default()
+    ///     })
+    /// ```
+    pub fn attributes<F>(mut self, attributes_fn: F) -> Self
+    where
+        F: Fn(&crate::auth::AuthRequest) -> std::collections::HashMap<String, String> + Send + Sync + 'static,
+    {
+        self.attributes = Some(Arc::new(attributes_fn));
+        self
+    }
+
+    /// Restrict the event types each allowed connection receives
+    ///
+    /// Runs after the auth callback, like [`identity`](Self::identity).
+    /// Events of other types are never written to the connection, whether
+    /// live or replayed; `None` allows all types. The filter is stored in
+    /// [`ConnectionMetadata::event_types`](crate::ConnectionMetadata).
+    ///
+    /// ```rust,ignore
+    /// let grants = [("read:orders", &["order_*"][..]), ("read:users", &["user_*"][..])];
+    /// Gateway::builder()
+    ///     .event_types(move |req: &AuthRequest| {
+    ///         let scopes = req.bearer_token().map(scopes_of).unwrap_or_default();
+    ///         Some(EventTypeFilter::from_scopes(scopes, &grants))
+    ///     })
+    /// ```
+    pub fn event_types<F>(mut self, event_types_fn: F) -> Self
+    where
+        F: Fn(&crate::auth::AuthRequest) -> Option<crate::auth::EventTypeFilter> + Send + Sync + 'static,
+    {
+        self.event_types = Some(Arc::new(event_types_fn));
+        self
+    }
+
+    /// Pick how each SSE connection's `data` fields are encoded
+    ///
+    /// Runs on the same [`AuthRequest`](crate::auth::AuthRequest) as the
+    /// auth callback; `None` keeps the default (data as published). The
+    /// gateway's own events (heartbeats, `migrate`, replay markers) are not
+    /// re-encoded. See [`crate::encoding`].
+    ///
+    /// ```rust,ignore
+    /// // ?fields=id,status
+    /// Gateway::builder().event_encoder(encoding::fields_param("fields"))
+    /// ```
+    pub fn event_encoder<F>(mut self, encoder_fn: F) -> Self
+    where
+        F: Fn(&crate::auth::AuthRequest) -> Option<Arc<dyn EventEncoder>> + Send + Sync + 'static,
+    {
+        self.event_encoder = Some(Arc::new(encoder_fn));
+        self
+    }
+
+    /// Refuse connections whose `MessageSource::on_connect` hook fails (default: false)
+    ///
+    /// Refused clients get `503 Service Unavailable`; otherwise the error is
+    /// only logged.
+    pub fn reject_on_connect_error(mut self, reject: bool) -> Self {
+        self.reject_on_connect_error = reject;
+        self
+    }
+
+    /// Set the instance ID
     pub fn instance_id(mut self, id: impl Into<String>) -> Self {
         self.instance_id = Some(id.into());
         self
     }
 
-    /// Enable or disable the dashboard
-    pub fn dashboard(mut self, enable: bool) -> Self {
-        self.enable_dashboard = enable;
+    /// Set the SSE endpoint path (default: `/sse/connect`)
+    pub fn sse_path(mut self, path: impl Into<String>) -> Self {
+        self.sse_path = path.into();
+        self
+    }
+
+    /// Set the query parameter carrying the channel ID (default: `channel_id`)
+    pub fn channel_param(mut self, name: impl Into<String>) -> Self {
+        self.channel_param = name.into();
+        self
+    }
+
+    /// Also accept the channel as a path segment, e.g. `/events/{channel_id}`
+    ///
+    /// The query-parameter form on [`sse_path`](Self::sse_path) keeps working.
+    pub fn channel_in_path(mut self, enable: bool) -> Self {
+        self.channel_in_path = enable;
+        self
+    }
+
+    /// Set the WebSocket fallback endpoint path (default: `/ws/connect`)
+    #[cfg(feature = "ws")]
+    pub fn ws_path(mut self, path: impl Into<String>) -> Self {
+        self.ws_path = path.into();
+        self
+    }
+
+    /// Join events queued for a connection into fewer, larger writes
+    /// (default: one write per event)
+    ///
+    /// Helps throughput on high-frequency channels; see [`crate::coalesce`].
+    pub fn write_coalescing(mut self, coalescing: WriteCoalescing) -> Self {
+        self.write_coalescing = Some(coalescing);
+        self
+    }
+
+    /// Pad, and mark as unbufferable, the streams of legacy clients
+    /// (default: only for `?compat=1`, with 2 KiB of padding)
+    ///
+    /// For EventSource polyfills and buffering reverse proxies; see
+    /// [`crate::compat`].
+    pub fn compat_mode(mut self, compat: CompatMode) -> Self {
+        self.compat = compat;
+        self
+    }
+
+    /// Send `replay_start` and `replay_end` events around replayed events
+    /// (default: off)
+    ///
+    /// Lets clients tell catch-up from live data, e.g. to show a syncing
+    /// state. `replay_start` carries `{"count": 12, "from_id": "..."}`
+    /// (`from_id` is the client's `Last-Event-ID`, or null), `replay_end`
+    /// `{"count": 12, "last_id": "..."}`. Nothing is sent when there is
+    /// nothing to replay.
+    pub fn replay_markers(mut self, enable: bool) -> Self {
+        self.replay_markers = match (enable, self.replay_markers.take()) {
+            (true, markers) => Some(markers.unwrap_or_default()),
+            (false, _) => None,
+        };
+        self
+    }
+
+    /// Send replay markers with these event types instead of
+    /// `replay_start` and `replay_end`
+    ///
+    /// Enables [`replay_markers`](Self::replay_markers).
+    pub fn replay_marker_events(mut self, start: impl Into<String>, end: impl Into<String>) -> Self {
+        self.replay_markers = Some(handler::ReplayMarkers {
+            start: start.into(),
+            end: end.into(),
+        });
+        self
+    }
+
+    /// Compress SSE responses for clients that accept gzip or Brotli
+    ///
+    /// Each event is flushed as soon as it is compressed, so latency is unchanged.
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: crate::compression::Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Serve the gRPC `Subscriber` service on the gateway's port (default: disabled)
+    ///
+    /// gRPC needs HTTP/2; plaintext listeners accept it via prior knowledge (h2c).
+    #[cfg(feature = "grpc")]
+    pub fn grpc(mut self, enable: bool) -> Self {
+        self.enable_grpc = enable;
+        self
+    }
+
+    /// Enable the built-in HTTP push endpoint at `path` (e.g. `/push`)
+    ///
+    /// Accepts `POST` with a JSON body `{"channel_id", "event_type", "data", "id"}`
+    /// and responds with delivery and online status; `{path}/batch` takes an
+    /// array of such bodies. Use
+    /// [`push_endpoint`](Self::push_endpoint) for auth and storage options.
+    pub fn enable_push_endpoint(mut self, path: impl Into<String>) -> Self {
+        self.push = Some(PushEndpoint::new(path));
+        self
+    }
+
+    /// Enable the built-in HTTP push endpoint with full configuration
+    pub fn push_endpoint(mut self, endpoint: PushEndpoint) -> Self {
+        self.push = Some(endpoint);
+        self
+    }
+
+    /// Largest body the dashboard's channel import accepts (default: 64 MiB)
+    ///
+    /// Larger imports are refused with `413 Payload Too Large`.
+    pub fn import_body_limit(mut self, bytes: usize) -> Self {
+        self.import_body_limit = bytes;
+        self
+    }
+
+    /// Validate payloads of `event_type` against a JSON Schema
+    ///
+    /// Invalid messages are not delivered: sources get
+    /// [`DispatchError::Invalid`](crate::DispatchError::Invalid) and the push
+    /// endpoint responds with 422. The schema is compiled in [`build`](Self::build).
+    #[cfg(feature = "schema")]
+    pub fn schema(mut self, event_type: impl Into<String>, schema: serde_json::Value) -> Self {
+        self.schemas.push((event_type.into(), schema));
+        self
+    }
+
+    /// Hand payloads that fail schema validation to `f` (e.g. to publish to a dead-letter queue)
+    #[cfg(feature = "schema")]
+    pub fn dead_letter<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&IncomingMessage, &str) + Send + Sync + 'static,
+    {
+        self.dead_letter = Some(Arc::new(f));
+        self
+    }
+
+    /// Hand payloads a source couldn't decode to `f` (e.g. to publish to a dead-letter queue)
+    ///
+    /// Sources report them with [`MessageHandler::decode_failed`] instead of
+    /// forwarding garbled messages: bad UTF-8, malformed CloudEvents
+    /// envelopes and the like. `f` receives the original bytes and the
+    /// reason. Failures are counted per source in `/api/metrics` either way.
+    pub fn decode_dead_letter<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DecodeError) + Send + Sync + 'static,
+    {
+        self.decode_dead_letter = Some(Arc::new(f));
+        self
+    }
+
+    /// Inject faults for resilience testing (see [`chaos`](crate::chaos))
+    ///
+    /// Delays and drops apply to dispatches, and connections are killed at the
+    /// configured rate. Storage failures need the storage wrapped with
+    /// [`ChaosLayer::storage`]. Also serves `GET`/`PUT /api/chaos` and
+    /// `POST /api/chaos/kill`.
+    #[cfg(feature = "chaos")]
+    pub fn chaos(mut self, chaos: ChaosLayer) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Add an event interceptor (may be called repeatedly; runs in order)
+    ///
+    /// See [`EventInterceptor`] for the hook points.
+    pub fn interceptor(mut self, interceptor: impl EventInterceptor) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Emit SSE payloads as structured CloudEvents JSON with the given `source` attribute
+    ///
+    /// The envelope is applied after any user [`interceptor`](Self::interceptor)s.
+    pub fn emit_cloudevents(mut self, source: impl Into<String>) -> Self {
+        self.cloudevents_source = Some(source.into());
+        self
+    }
+
+    /// Add a custom route to the gateway's router
+    ///
+    /// Custom routes are served alongside the built-in endpoints and are wrapped
+    /// by the same CORS, tracing and [`layer`](Self::layer) middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use axum::routing::post;
+    ///
+    /// Gateway::builder()
+    ///     .route("/push", post(handle_push))
+    ///     .route("/version", get(|| async { env!("CARGO_PKG_VERSION") }))
+    /// ```
+    pub fn route(mut self, path: &str, method_router: axum::routing::MethodRouter) -> Self {
+        self.extra_routes = self.extra_routes.route(path, method_router);
+        self
+    }
+
+    /// Merge a router (with its own state already applied) into the gateway
+    pub fn merge(mut self, router: Router) -> Self {
+        self.extra_routes = self.extra_routes.merge(router);
+        self
+    }
+
+    /// Wrap the whole gateway router in a tower layer
+    ///
+    /// Layers are applied in call order after the built-in CORS and tracing
+    /// layers, so the last registered layer is the outermost.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use tower_http::compression::CompressionLayer;
+    ///
+    /// Gateway::builder()
+    ///     .layer(CompressionLayer::new())
+    /// ```
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<axum::routing::Route> + Clone + Send + Sync + 'static,
+        L::Service: tower::Service<axum::extract::Request> + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<axum::extract::Request>>::Response:
+            axum::response::IntoResponse + 'static,
+        <L::Service as tower::Service<axum::extract::Request>>::Error:
+            Into<std::convert::Infallible> + 'static,
+        <L::Service as tower::Service<axum::extract::Request>>::Future: Send + 'static,
+    {
+        self.layers.push(Box::new(move |router: Router| router.layer(layer.clone())));
+        self
+    }
+
+    /// Tune the HTTP server started by [`run`](Gateway::run)
+    ///
+    /// See [`ServerOptions`] for HTTP/2, keepalive and timeout settings.
+    pub fn server_options(mut self, options: ServerOptions) -> Self {
+        self.server_options = options;
+        self
+    }
+
+    /// Enable or disable the dashboard
+    pub fn dashboard(mut self, enable: bool) -> Self {
+        self.enable_dashboard = enable;
+        self
+    }
+
+    /// Dashboard title, logo, accent color, default language and theme
+    pub fn dashboard_config(mut self, config: DashboardConfig) -> Self {
+        self.dashboard = config;
+        self
+    }
+
+    /// Serve `/api/metrics` and `/api/metrics/history` (default: when the dashboard is enabled)
+    pub fn metrics(mut self, enable: bool) -> Self {
+        self.enable_metrics = Some(enable);
+        self
+    }
+
+    /// Sampling interval and retention of `/api/metrics/history`
+    ///
+    /// Default: a sample every 2 seconds, the newest 300 kept.
+    pub fn metrics_history(mut self, history: MetricsHistory) -> Self {
+        self.metrics_history = history;
+        self
+    }
+
+    /// Set the heartbeat interval
+    ///
+    /// Use [`Heartbeat::idle_only`] to skip heartbeats on connections that
+    /// are receiving events anyway.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Idle time before an SSE stream gets a `: keep-alive` comment
+    /// (default: 10s, `Duration::ZERO` disables them)
+    ///
+    /// The timer restarts with every event or heartbeat written, so only
+    /// streams that went quiet are padded.
+    pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = interval;
+        self
+    }
+
+    /// Set the heartbeat format (default: a `heartbeat` event with `{"ts":...}`)
+    ///
+    /// ```rust,ignore
+    /// // SSE comment heartbeats, skipped for connections that just received an event
+    /// Gateway::builder().heartbeat(Heartbeat::comment("ping").idle_only(true))
+    /// ```
+    pub fn heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Drop events already sent on a connection (default: 0, disabled)
+    ///
+    /// Each connection remembers the keys of its last `size` events, the
+    /// publisher `id` or else the stream ID, so retried publishes and
+    /// replay/live overlaps are sent once. Suppressed events are counted per
+    /// connection in `/api/stats`.
+    pub fn dedup_window(mut self, size: usize) -> Self {
+        self.dedup_window = size;
+        self
+    }
+
+    /// Forward messages to the other instances subscribed to their channel
+    ///
+    /// See the [`cluster`](crate::cluster) module. The coordinator also
+    /// fills in [`DeliveryReport::cluster_online`].
+    pub fn cluster(mut self, coordinator: impl ClusterCoordinator) -> Self {
+        self.cluster = Some(Arc::new(coordinator));
+        self
+    }
+
+    /// Publish `presence.join` / `presence.leave` events for every channel
+    ///
+    /// `channel` names the channel the events go to, with `{channel_id}`
+    /// standing for the joined channel, e.g. `presence:{channel_id}`. The
+    /// data is the connection's ID and metadata plus `channel_id`. Events are
+    /// dispatched like published messages (stored, forwarded in cluster mode),
+    /// and connections to presence channels themselves are not announced.
+    pub fn presence_events(mut self, channel: impl Into<String>) -> Self {
+        self.presence_events = Some(channel.into());
+        self
+    }
+
+    /// POST connect/disconnect notifications to a webhook
+    ///
+    /// See the [`webhook`](crate::webhook) module. Pair with
+    /// [`identity`](Self::identity) to report who connected.
+    #[cfg(feature = "webhooks")]
+    pub fn lifecycle_webhook(mut self, webhook: crate::webhook::LifecycleWebhook) -> Self {
+        self.lifecycle_webhook = Some(webhook);
+        self
+    }
+
+    /// POST every event on `channel_id` to a webhook, as if it were a subscriber
+    ///
+    /// See [`WebhookSubscriber`](crate::webhook::WebhookSubscriber). More can
+    /// be added at runtime through `/api/channels/{id}/webhooks` when the
+    /// dashboard is enabled.
+    #[cfg(feature = "webhooks")]
+    pub fn webhook_subscriber(
+        mut self,
+        channel_id: impl Into<String>,
+        subscriber: crate::webhook::WebhookSubscriber,
+    ) -> Self {
+        self.webhook_subscribers.push((channel_id.into(), subscriber));
+        self
+    }
+
+    /// Publish every dispatched message to all other instances
+    ///
+    /// The simple alternative to [`cluster`](Self::cluster): there is no
+    /// registry or channel ownership, every instance sees every message and
+    /// delivers it to its local subscribers. Good for small clusters; use a
+    /// coordinator once the fan-out traffic matters. The two can't be
+    /// combined. See [`ClusterFanout`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway_redis::RedisFanout;
+    ///
+    /// Gateway::builder()
+    ///     .source(my_queue_source)
+    ///     .storage(storage)
+    ///     .cluster_fanout(RedisFanout::new("redis://localhost:6379"))
+    /// ```
+    pub fn cluster_fanout(mut self, fanout: impl ClusterFanout) -> Self {
+        self.fanout = Some(Arc::new(fanout));
+        self
+    }
+
+    /// Hand messages that reach no subscriber to `fallback`
+    ///
+    /// For messages to a channel with no subscribers on any instance, after
+    /// they have been stored. See the [`offline`](crate::offline) module.
+    pub fn offline_fallback(mut self, fallback: impl OfflineFallback) -> Self {
+        self.offline_fallback = Some(Arc::new(fallback));
+        self
+    }
+
+    /// Assign channels to instances by consistent hashing over the cluster
+    ///
+    /// The gateway keeps the router's membership in sync with the
+    /// coordinator; keep a clone to call [`ChannelRouter::owner_of`]. With
+    /// redirects enabled, subscribers reaching the wrong instance are sent to
+    /// the owner. Requires [`cluster`](Self::cluster).
+    pub fn channel_router(mut self, router: ChannelRouter) -> Self {
+        self.channel_router = Some(router);
+        self
+    }
+
+    /// Set the cleanup interval
+    pub fn cleanup_interval(mut self, interval: Duration) -> Self {
+        self.cleanup_interval = interval;
+        self
+    }
+
+    /// Enforce a per-channel publish quota
+    ///
+    /// Each channel gets its own token bucket. Events over the quota are
+    /// dropped, delayed or rejected according to the policy's action.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway::{ThrottleAction, ThrottlePolicy};
+    ///
+    /// Gateway::builder()
+    ///     .throttle(
+    ///         ThrottlePolicy::new(ThrottleAction::Drop)
+    ///             .events_per_sec(50)
+    ///             .bytes_per_sec(256 * 1024),
+    ///     )
+    /// ```
+    pub fn throttle(mut self, policy: ThrottlePolicy) -> Self {
+        self.throttle = Some(policy);
+        self
+    }
+
+    /// Write one record per closed subscriber connection to `sink`
+    ///
+    /// See [`access_log`](crate::access_log) for the built-in sinks.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway::access_log::StdoutAccessLog;
+    ///
+    /// Gateway::builder().access_log(StdoutAccessLog)
+    /// ```
+    pub fn access_log(mut self, sink: impl AccessLogSink) -> Self {
+        self.access_log = Some(Arc::new(sink));
+        self
+    }
+
+    /// Generate stream IDs with `ids` instead of the storage
+    ///
+    /// Applies to events published through sources, the send API and the
+    /// push endpoint. With several instances writing the same channels, use
+    /// [`SnowflakeIds`](crate::id::SnowflakeIds) so IDs stay unique and
+    /// ordered across instances. See [`id`](crate::id).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway::id::SnowflakeIds;
+    ///
+    /// Gateway::builder().id_generator(SnowflakeIds::new(3))
+    /// ```
+    pub fn id_generator(mut self, ids: impl IdGenerator) -> Self {
+        self.ids = Some(Arc::new(ids));
+        self
+    }
+
+    /// Compact a channel's stored history whenever an `event_type` event is stored
+    ///
+    /// For state-style channels whose `event_type` events carry the full
+    /// state: older events are dropped and the event becomes the channel's
+    /// snapshot, which reconnecting clients receive before newer deltas.
+    /// Needs a storage that supports [`MessageStorage::compact`]. Can be
+    /// called multiple times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder().compact_on("document.state")
+    /// ```
+    pub fn compact_on(mut self, event_type: impl Into<String>) -> Self {
+        self.compact_on.push(event_type.into());
+        self
+    }
+
+    /// Limit the payload size of incoming messages
+    ///
+    /// Applies to messages from the source, the push endpoint and
+    /// [`Publisher`]s, as they leave the interceptors. See
+    /// [`payload`](crate::payload) for what happens to oversized payloads.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway::payload::{OversizedPolicy, PayloadLimit};
+    ///
+    /// Gateway::builder().payload_limit(PayloadLimit::new(256 << 10).oversized(OversizedPolicy::Truncate))
+    /// ```
+    pub fn payload_limit(mut self, limit: PayloadLimit) -> Self {
+        self.payload_limit = Some(limit);
         self
     }
 
-    /// Set the heartbeat interval
-    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
-        self.heartbeat_interval = interval;
+    /// Limit the payload size of events written to storage
+    ///
+    /// Live subscribers still receive the full event; replay gets the
+    /// truncated or externalized one, or nothing with
+    /// [`OversizedPolicy::Reject`](crate::payload::OversizedPolicy::Reject).
+    pub fn stored_payload_limit(mut self, limit: PayloadLimit) -> Self {
+        self.stored_payload_limit = Some(limit);
         self
     }
 
-    /// Set the cleanup interval
-    pub fn cleanup_interval(mut self, interval: Duration) -> Self {
-        self.cleanup_interval = interval;
+    /// Issue resume tokens to SSE subscribers
+    ///
+    /// Each connection then starts with a `connected` event whose data is
+    /// `{"resume_token": "..."}`. Connecting with `?resume=<token>` restores
+    /// the channel, attribute parameters, consumer, group and cursor of the
+    /// session; sessions expire `ttl` after their last connection closed.
+    /// See [`resume`](crate::resume).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder().resume_tokens(Duration::from_secs(300))
+    /// ```
+    pub fn resume_tokens(mut self, ttl: Duration) -> Self {
+        self.resume_ttl = Some(ttl);
+        self
+    }
+
+    /// Save resume sessions in the storage backend
+    ///
+    /// Sessions then survive restarts and can be resumed on any instance
+    /// sharing the storage, if it supports sessions
+    /// ([`MessageStorage::save_session`]). Resuming on the session's channel
+    /// reuses the cached results of the auth, identity, attribute and event
+    /// type callbacks, so the token acts as a credential until the session
+    /// expires. Requires [`resume_tokens`](Self::resume_tokens).
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .storage(RedisStorage::new(redis_url).await?)
+    ///     .resume_tokens(Duration::from_secs(300))
+    ///     .persist_resume_sessions(true)
+    /// ```
+    pub fn persist_resume_sessions(mut self, enable: bool) -> Self {
+        self.persist_resume = enable;
+        self
+    }
+
+    /// Record per-channel usage, adding it to the storage's daily totals every `flush_interval`
+    ///
+    /// Counts messages published and delivered, distinct subscribers and
+    /// peak concurrent subscribers per channel and UTC day, served by
+    /// `GET /api/channels/{id}/stats` when the dashboard is enabled. The
+    /// storage must support usage ([`MessageStorage::record_channel_usage`]).
+    /// See [`channel_usage`](crate::channel_usage).
+    pub fn channel_usage(mut self, flush_interval: Duration) -> Self {
+        self.channel_usage = Some(flush_interval);
+        self
+    }
+
+    /// Send subscribers a `migrate` event when the gateway shuts down
+    ///
+    /// The event's data names the least loaded other instance in the
+    /// cluster, `{"instance_id": "...", "url": "http://10.0.0.6:8080"}`
+    /// (empty without cluster mode or another instance with an address),
+    /// and its `retry` field tells `EventSource` to reconnect after `retry`
+    /// rather than its default backoff. Clients can reconnect to the URL
+    /// directly instead of waiting for the load balancer to notice the
+    /// instance is gone.
+    ///
+    /// With a [`retry_policy`](Self::retry_policy) set, each subscriber's
+    /// `retry` is picked from the policy instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .cluster(RedisCluster::new(redis_url).address("http://10.0.0.5:8080"))
+    ///     .migration_hints(Duration::from_millis(500))
+    /// ```
+    pub fn migration_hints(mut self, retry: Duration) -> Self {
+        self.migration_retry = Some(retry);
+        self
+    }
+
+    /// Advertise a reconnect delay to clients in the SSE `retry:` field
+    ///
+    /// Each stream starts with a `retry:` picked from the policy (unless
+    /// [`RetryPolicy::on_connect`] is disabled), and shutdown `migrate`
+    /// events use it too. A jittered range keeps subscribers of a restarted
+    /// instance from all reconnecting at the same moment.
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .retry_policy(RetryPolicy::jittered(Duration::from_secs(1), Duration::from_secs(5)))
+    /// ```
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Take over from cluster instances silent for longer than `silent_for`
+    ///
+    /// A crashed instance's registration and channel claims otherwise
+    /// linger until they expire, and messages keep being forwarded to it.
+    /// With failover, every instance checks the registry every
+    /// `silent_for / 2`, evicts instances whose last heartbeat is older
+    /// than `silent_for`, and claims their channels it holds subscribers
+    /// for. Eviction is conditional on the instance not having heartbeated
+    /// meanwhile (see [`ClusterCoordinator::evict`]), and an instance evicted
+    /// while alive rejoins at its next heartbeat.
+    ///
+    /// `silent_for` should span several heartbeats of the coordinator, and
+    /// stay below its registration TTL to make a difference.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Gateway::builder()
+    ///     .cluster(RedisCluster::new(redis_url).heartbeat_interval(Duration::from_secs(5)))
+    ///     .failover(Duration::from_secs(15))
+    /// ```
+    pub fn failover(mut self, silent_for: Duration) -> Self {
+        self.failover_after = Some(silent_for);
+        self
+    }
+
+    /// Advertise that this instance is sized for `max_connections`
+    ///
+    /// Published to the cluster registry with each heartbeat, together with
+    /// the current connection count. `GET /route` skips instances at
+    /// capacity and ranks the rest by how much of it is in use. Connections
+    /// beyond it are still accepted.
+    pub fn capacity(mut self, max_connections: usize) -> Self {
+        self.capacity = Some(max_connections);
+        self
+    }
+
+    /// Advertise CPU utilization as reported by `hint`, in percent
+    ///
+    /// Called at each cluster heartbeat and for `GET /route`, so it should
+    /// be cheap (e.g. read a value sampled elsewhere). `GET /route` ranks
+    /// instances by the higher of this and their share of
+    /// [`capacity`](Self::capacity) in use.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let cpu = Arc::new(AtomicU8::new(0));
+    /// // ...a sampler task stores the current utilization in `cpu`
+    /// Gateway::builder()
+    ///     .capacity(10_000)
+    ///     .cpu_hint(move || cpu.load(Ordering::Relaxed))
+    /// ```
+    pub fn cpu_hint(mut self, hint: impl Fn() -> u8 + Send + Sync + 'static) -> Self {
+        self.cpu_hint = Some(Arc::new(hint));
+        self
+    }
+
+    /// Restart the message source according to `policy` when it fails
+    ///
+    /// By default a failed source is restarted with exponential backoff
+    /// (1s doubling up to 30s) for as long as the gateway runs. See
+    /// [`supervisor`](crate::supervisor).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use sse_gateway::supervisor::RestartPolicy;
+    ///
+    /// Gateway::builder().restart_policy(RestartPolicy::default().max_retries(5).fail_fast(true))
+    /// ```
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Called whenever the message source starts, fails, restarts or stops
+    pub fn on_source_state<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&SourceState) + Send + Sync + 'static,
+    {
+        self.source_state_hook = Some(Arc::new(f));
+        self
+    }
+
+    /// Serve HTTPS using a PEM certificate chain and private key
+    ///
+    /// The files are re-read on SIGHUP (Unix) and, if set, on
+    /// [`tls_reload_interval`](Self::tls_reload_interval).
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, cert_path: impl Into<std::path::PathBuf>, key_path: impl Into<std::path::PathBuf>) -> Self {
+        self.tls = Some(TlsConfig::from_pem_files(cert_path, key_path));
+        self
+    }
+
+    /// Serve HTTPS using a pre-built rustls server configuration
+    #[cfg(feature = "tls")]
+    pub fn tls_config(mut self, config: Arc<crate::tls::ServerConfig>) -> Self {
+        self.tls = Some(TlsConfig::Rustls(config));
+        self
+    }
+
+    /// Periodically reload file-based TLS certificates
+    ///
+    /// Has no effect when TLS is configured with [`tls_config`](Self::tls_config).
+    #[cfg(feature = "tls")]
+    pub fn tls_reload_interval(mut self, interval: Duration) -> Self {
+        if let Some(TlsConfig::Files { reload_interval, .. }) = &mut self.tls {
+            *reload_interval = Some(interval);
+        }
         self
     }
 }
 
 impl<Source: MessageSource, Storage: MessageStorage> GatewayBuilder<Source, Storage> {
+    /// Apply `config` and keep following it as it is reloaded
+    ///
+    /// Settings are applied like [`GatewayConfig::apply`](crate::config::GatewayConfig::apply);
+    /// throttle limits, channel policies, auth tokens and the log level also
+    /// change on reload (SIGHUP, `POST /admin/reload`, or file changes with
+    /// [`LiveConfig::watch`]). Storage and source selections are ignored.
+    #[cfg(feature = "config")]
+    pub fn live_config(self, config: LiveConfig) -> Self {
+        let mut builder = config.apply(self);
+        builder.live_config = Some(config);
+        builder
+    }
+
     /// Build the gateway
     pub fn build(self) -> anyhow::Result<Gateway<Source, Storage>> {
         let source = self.source.ok_or_else(|| anyhow::anyhow!("Source is required"))?;
         let storage = self.storage.ok_or_else(|| anyhow::anyhow!("Storage is required"))?;
+        if self.channel_router.is_some() && self.cluster.is_none() {
+            anyhow::bail!("Channel router requires a cluster coordinator");
+        }
+        if self.fanout.is_some() && self.cluster.is_some() {
+            anyhow::bail!("Cluster fanout and a cluster coordinator are mutually exclusive");
+        }
+        if self.identity_channel.is_some() && self.identity.is_none() {
+            anyhow::bail!("Identity channels require an identity callback");
+        }
+        if self.persist_resume && self.resume_ttl.is_none() {
+            anyhow::bail!("Persisted resume sessions require resume tokens");
+        }
         let instance_id = self.instance_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
         if self.auth.is_some() {
             tracing::info!("Authentication enabled for SSE connections");
         }
 
+        #[cfg(feature = "schema")]
+        let schema = if self.schemas.is_empty() {
+            None
+        } else {
+            let mut validator = SchemaValidator::new();
+            for (event_type, schema) in &self.schemas {
+                validator.register(event_type.as_str(), schema)?;
+            }
+            if let Some(dead_letter) = self.dead_letter {
+                validator = validator.dead_letter(dead_letter);
+            }
+            Some(Arc::new(validator))
+        };
+
+        // Idle-only heartbeats measure idleness against the tick interval
+        let mut heartbeat = self.heartbeat;
+        heartbeat.interval = self.heartbeat_interval;
+
+        let mut interceptors = self.interceptors;
+        if let Some(source) = self.cloudevents_source {
+            interceptors.push(Arc::new(CloudEventsEmitter::new(source)));
+        }
+
+        let mut connection_manager = ConnectionManager::new(instance_id)
+            .with_interceptors(InterceptorChain::new(interceptors))
+            .with_dedup_window(self.dedup_window)
+            .with_channel_configs(self.channel_configs);
+        if let Some(capacity) = self.capacity {
+            connection_manager = connection_manager.with_capacity(capacity);
+        }
+        if let Some(hint) = self.cpu_hint {
+            connection_manager = connection_manager.with_cpu_hint(move || hint());
+        }
+        if self.channel_usage.is_some() {
+            connection_manager = connection_manager.with_usage(UsageRecorder::default());
+        }
+
         Ok(Gateway {
             port: self.port,
             source,
             storage,
-            connection_manager: ConnectionManager::new(instance_id),
+            connection_manager,
             enable_dashboard: self.enable_dashboard,
+            dashboard: self.dashboard,
+            enable_metrics: self.enable_metrics,
+            metrics_history: self.metrics_history,
             heartbeat_interval: self.heartbeat_interval,
+            keep_alive_interval: self.keep_alive_interval,
+            heartbeat: Arc::new(heartbeat),
             cleanup_interval: self.cleanup_interval,
             auth: self.auth,
+            admin_auth: self.admin_auth,
+            identity: self.identity,
+            attribute_params: self.attribute_params,
+            attributes: self.attributes,
+            event_types: self.event_types,
+            event_encoder: self.event_encoder,
+            tenancy: self.tenancy,
+            dispatch_concurrency: self.dispatch_concurrency,
+            dispatch_queue_capacity: self.dispatch_queue_capacity,
+            dispatch_overflow: self.dispatch_overflow,
+            write_coalescing: self.write_coalescing,
+            compat: self.compat,
+            replay_markers: self.replay_markers,
+            channel_rewrites: self.channel_rewrites,
+            identity_channel: self.identity_channel,
+            trusted_proxies: self.trusted_proxies,
+            reject_on_connect_error: self.reject_on_connect_error,
+            throttle: self.throttle.map(Throttle::new),
+            access_log: self.access_log,
+            ids: self.ids,
+            compact_on: self.compact_on,
+            payload_limit: self.payload_limit,
+            stored_payload_limit: self.stored_payload_limit,
+            resume_ttl: self.resume_ttl,
+            channel_usage: self.channel_usage,
+            persist_resume: self.persist_resume,
+            migration_retry: self.migration_retry,
+            retry_policy: self.retry_policy,
+            failover_after: self.failover_after,
+            decode_dead_letter: self.decode_dead_letter,
+            restart_policy: self.restart_policy,
+            source_state_hook: self.source_state_hook,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+            binds: self.binds,
+            extra_routes: self.extra_routes,
+            layers: self.layers,
+            server_options: self.server_options,
+            sse_path: self.sse_path,
+            channel_param: self.channel_param,
+            channel_in_path: self.channel_in_path,
+            #[cfg(feature = "ws")]
+            ws_path: self.ws_path,
+            #[cfg(feature = "grpc")]
+            enable_grpc: self.enable_grpc,
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+            push: self.push,
+            import_body_limit: self.import_body_limit,
+            cluster: self.cluster,
+            fanout: self.fanout,
+            offline_fallback: self.offline_fallback,
+            channel_router: self.channel_router,
+            presence_events: self.presence_events,
+            #[cfg(feature = "webhooks")]
+            lifecycle_webhook: self.lifecycle_webhook,
+            #[cfg(feature = "webhooks")]
+            webhook_subscribers: self.webhook_subscribers,
+            #[cfg(feature = "schema")]
+            schema,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos,
+            #[cfg(feature = "config")]
+            live_config: self.live_config,
         })
     }
 }
@@ -343,63 +2731,525 @@ impl<Source: MessageSource, Storage: MessageStorage> GatewayBuilder<Source, Stor
 struct Dispatcher<S: MessageStorage> {
     connection_manager: ConnectionManager,
     storage: S,
+    throttle: Option<Throttle>,
+    cancel: CancellationToken,
+    cluster: Option<Cluster>,
+    fanout: Option<Arc<dyn ClusterFanout>>,
+    offline_fallback: Option<Arc<dyn OfflineFallback>>,
+    tenancy: Option<Tenancy>,
+    ids: Option<Arc<dyn IdGenerator>>,
+    compact_on: Arc<[String]>,
+    payload_limit: Option<PayloadLimit>,
+    stored_payload_limit: Option<PayloadLimit>,
+    rewrites: ChannelRewrites,
+    #[cfg(feature = "schema")]
+    schema: Option<Arc<SchemaValidator>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<ChaosLayer>,
 }
 
 impl<S: MessageStorage> Dispatcher<S> {
-    fn new(connection_manager: ConnectionManager, storage: S) -> Self {
+    fn new(
+        connection_manager: ConnectionManager,
+        storage: S,
+        throttle: Option<Throttle>,
+        cancel: CancellationToken,
+    ) -> Self {
         Self {
             connection_manager,
             storage,
+            throttle,
+            cancel,
+            cluster: None,
+            fanout: None,
+            offline_fallback: None,
+            tenancy: None,
+            ids: None,
+            compact_on: Arc::new([]),
+            payload_limit: None,
+            stored_payload_limit: None,
+            rewrites: ChannelRewrites::default(),
+            #[cfg(feature = "schema")]
+            schema: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+
+    fn with_cluster(mut self, cluster: Option<Cluster>) -> Self {
+        self.cluster = cluster;
+        self
+    }
+
+    fn with_fanout(mut self, fanout: Option<Arc<dyn ClusterFanout>>) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    fn with_offline_fallback(mut self, fallback: Option<Arc<dyn OfflineFallback>>) -> Self {
+        self.offline_fallback = fallback;
+        self
+    }
+
+    fn with_tenancy(mut self, tenancy: Option<Tenancy>) -> Self {
+        self.tenancy = tenancy;
+        self
+    }
+
+    fn with_ids(mut self, ids: Option<Arc<dyn IdGenerator>>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    fn with_compaction(mut self, compact_on: Arc<[String]>) -> Self {
+        self.compact_on = compact_on;
+        self
+    }
+
+    fn with_payload_limits(mut self, incoming: Option<PayloadLimit>, stored: Option<PayloadLimit>) -> Self {
+        self.payload_limit = incoming;
+        self.stored_payload_limit = stored;
+        self
+    }
+
+    fn with_rewrites(mut self, rewrites: ChannelRewrites) -> Self {
+        self.rewrites = rewrites;
+        self
+    }
+
+    #[cfg(feature = "schema")]
+    fn with_schema(mut self, schema: Option<Arc<SchemaValidator>>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    #[cfg(feature = "chaos")]
+    fn with_chaos(mut self, chaos: Option<ChaosLayer>) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    async fn handle(&self, msg: IncomingMessage) -> DispatchResult {
+        let result = self.dispatch(&msg).await;
+        if let Some(reporter) = &msg.report {
+            let report = match &result {
+                Ok(report) => report.clone(),
+                Err(error) => DeliveryReport {
+                    throttled: matches!(error, DispatchError::Throttled { .. }),
+                    error: Some(error.clone()),
+                    ..Default::default()
+                },
+            };
+            reporter.report(report);
         }
+        result
     }
 
-    async fn handle(&self, msg: IncomingMessage) {
+    async fn dispatch(&self, msg: &IncomingMessage) -> DispatchResult {
+        if self.cancel.is_cancelled() {
+            return Err(DispatchError::ShuttingDown);
+        }
+        if msg.is_expired() {
+            tracing::debug!(channel_id = ?msg.channel_id, event_type = %msg.event_type, "Expired message dropped");
+            return Ok(DeliveryReport {
+                expired: true,
+                ..Default::default()
+            });
+        }
+
+        // Validate what the producer sent, before interceptors rewrite it
+        #[cfg(feature = "schema")]
+        if let Some(schema) = &self.schema {
+            schema
+                .check(msg, self.connection_manager.metrics())
+                .map_err(DispatchError::Invalid)?;
+        }
+
+        // Legacy channel names map to their current ones before anything looks at the channel
+        let renamed;
+        let msg = match msg.channel_id.as_deref().map(|channel_id| self.rewrites.rewrite(channel_id)) {
+            Some(Cow::Owned(channel_id)) => {
+                let mut rewritten = msg.clone();
+                rewritten.channel_id = Some(channel_id.into());
+                renamed = rewritten;
+                &renamed
+            }
+            _ => msg,
+        };
+
+        let interceptors = self.connection_manager.interceptors();
+        let intercepted;
+        let msg = if interceptors.is_empty() {
+            msg
+        } else {
+            let mut rewritten = msg.clone();
+            if interceptors.before_dispatch(&mut rewritten) == Decision::Drop {
+                tracing::debug!(channel_id = ?msg.channel_id, "Message dropped by interceptor");
+                return Ok(DeliveryReport {
+                    filtered: true,
+                    ..Default::default()
+                });
+            }
+            intercepted = rewritten;
+            &intercepted
+        };
+
+        let config = msg
+            .channel_id
+            .as_deref()
+            .map(|channel_id| self.connection_manager.channel_configs().resolve(channel_id))
+            .unwrap_or_default();
+        if !config.allows(&msg.event_type) {
+            tracing::debug!(channel_id = ?msg.channel_id, event_type = %msg.event_type, "Event type not allowed on channel");
+            return Ok(DeliveryReport {
+                filtered: true,
+                ..Default::default()
+            });
+        }
+
+        // Limit what subscribers receive, after interceptors had their say
+        let resized;
+        let msg = match &self.payload_limit {
+            Some(limit) => {
+                let metrics = self.connection_manager.metrics();
+                match limit
+                    .apply(msg.channel_id.as_deref(), &msg.event_type, &msg.data, metrics)
+                    .await?
+                {
+                    Some(data) => {
+                        let mut rewritten = msg.clone();
+                        rewritten.data = data;
+                        resized = rewritten;
+                        &resized
+                    }
+                    None => msg,
+                }
+            }
+            None => msg,
+        };
+
+        if let Some(channel_id) = &msg.channel_id {
+            let metrics = self.connection_manager.metrics();
+            let size = msg.data.len();
+            let decisions = [
+                self.tenancy.as_ref().map(|t| t.check(channel_id, size, metrics)),
+                self.throttle.as_ref().map(|t| t.check_and_record(channel_id, size, metrics)),
+            ];
+            for decision in decisions.into_iter().flatten() {
+                match decision {
+                    ThrottleDecision::Allow => {}
+                    ThrottleDecision::Delay(wait) => {
+                        return Err(DispatchError::Throttled { retry_after: Some(wait) });
+                    }
+                    ThrottleDecision::Drop => {
+                        return Ok(DeliveryReport {
+                            throttled: true,
+                            ..Default::default()
+                        });
+                    }
+                    ThrottleDecision::Reject => return Err(DispatchError::Throttled { retry_after: None }),
+                }
+            }
+        }
+
+        // Chaos-dropped events skip live delivery but are still stored for replay
+        #[cfg(feature = "chaos")]
+        let live = match &self.chaos {
+            Some(chaos) => !chaos.disrupt_dispatch().await,
+            None => true,
+        };
+        #[cfg(not(feature = "chaos"))]
+        let live = true;
+
         let mut event = SseEvent::raw(&msg.event_type, msg.data.clone());
-        if let Some(id) = msg.id {
-            event.id = Some(id);
+        if let Some(id) = &msg.id {
+            event.id = Some(id.clone());
         }
+        event.expires_at = msg.expires_at;
+        event.priority = msg.priority;
 
-        let sent = match &msg.channel_id {
+        let mut report = DeliveryReport::default();
+        report.delivered = match &msg.channel_id {
             Some(channel_id) => {
-                // Generate ID first
-                let stream_id = self.storage.generate_id();
-                if !stream_id.is_empty() {
-                    event.stream_id = Some(stream_id.clone());
+                report.online = self.connection_manager.channel_connection_count(channel_id) > 0;
+                if let Some(usage) = self.connection_manager.usage() {
+                    usage.record_published(channel_id);
                 }
 
-                // Send to clients immediately
-                let sent = self.connection_manager.send_to_channel(channel_id, event.clone()).await;
-
-                // Store in background (fire-and-forget, don't block sending)
-                let storage = self.storage.clone();
-                let channel_id = channel_id.clone();
-                tokio::spawn(async move {
-                    storage.store(&channel_id, &stream_id, &event).await;
-                });
+                if config.stores() {
+                    // Generate ID first
+                    let stream_id = next_stream_id(&self.ids, &self.storage);
+                    if !stream_id.is_empty() {
+                        event.stream_id = Some(stream_id.clone());
+                        report.stream_id = Some(stream_id.clone());
+                    }
 
-                sent
+                    // Send to clients immediately, then persist before reporting back
+                    let sent = if live {
+                        self.connection_manager.send_to_channel(channel_id, event.clone()).await
+                    } else {
+                        0
+                    };
+                    store_event(
+                        &self.storage,
+                        &self.compact_on,
+                        self.stored_payload_limit.as_ref(),
+                        self.connection_manager.metrics(),
+                        channel_id,
+                        &stream_id,
+                        &event,
+                    )
+                    .await;
+                    sent
+                } else if live {
+                    self.connection_manager.send_to_channel(channel_id, event.clone()).await
+                } else {
+                    0
+                }
+            }
+            None if live => {
+                report.online = self.connection_manager.connection_count() > 0;
+                self.connection_manager.broadcast(event.clone()).await
+            }
+            None => {
+                report.online = self.connection_manager.connection_count() > 0;
+                0
             }
-            None => self.connection_manager.broadcast(event).await,
         };
 
+        // Forward to the instances holding the channel's other subscribers
+        if let Some(cluster) = self.cluster.as_ref().filter(|_| live) {
+            let peers = cluster.peers_for(msg.channel_id.as_deref()).await;
+            cluster.forward(&peers, msg.channel_id.as_deref(), &event).await;
+            report.cluster_online = Some(report.online || !peers.is_empty());
+        }
+        if let Some(fanout) = self.fanout.as_ref().filter(|_| live) {
+            let message = ClusterMessage {
+                channel_id: msg.channel_id.as_deref().map(str::to_string),
+                event: event.clone(),
+            };
+            if let Err(e) = fanout.publish(&message).await {
+                tracing::warn!(error = %e, fanout = fanout.name(), "Failed to publish to cluster fanout");
+            }
+        }
+
+        // Nobody anywhere got it: let the application reach the user another way
+        if let (Some(fallback), Some(channel_id)) = (&self.offline_fallback, &msg.channel_id) {
+            if !report.cluster_online.unwrap_or(report.online) {
+                self.run_offline_fallback(fallback.clone(), channel_id.to_string(), msg);
+            }
+        }
+        GatewayMetrics::incr(&self.connection_manager.metrics().messages_dispatched);
+
         tracing::debug!(
             channel_id = ?msg.channel_id,
             event_type = %msg.event_type,
-            sent_count = sent,
+            sent_count = report.delivered,
             "Message dispatched"
         );
+        Ok(report)
+    }
+
+    fn run_offline_fallback(&self, fallback: Arc<dyn OfflineFallback>, channel_id: String, msg: &IncomingMessage) {
+        let mut message = msg.clone();
+        message.report = None;
+        let metrics = self.connection_manager.shared_metrics();
+        GatewayMetrics::incr(&metrics.offline_fallbacks);
+        tokio::spawn(async move {
+            if let Err(e) = fallback.on_offline(&channel_id, &message).await {
+                GatewayMetrics::incr(&metrics.offline_fallback_failures);
+                tracing::warn!(error = %e, fallback = fallback.name(), channel_id, "Offline fallback failed");
+            }
+        });
     }
 
-    fn to_handler(self) -> Arc<dyn Fn(IncomingMessage) + Send + Sync>
+    fn into_handler(self) -> MessageHandler
     where
         S: 'static,
     {
         let dispatcher = Arc::new(self);
-        Arc::new(move |msg| {
+        MessageHandler::new(move |msg| {
             let dispatcher = dispatcher.clone();
-            tokio::spawn(async move {
-                dispatcher.handle(msg).await;
-            });
+            async move { dispatcher.handle(msg).await }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::throttle::ThrottleAction;
+    use tower::ServiceExt;
+
+    fn dispatcher() -> Dispatcher<MemoryStorage> {
+        Dispatcher::new(
+            ConnectionManager::new("test"),
+            MemoryStorage::default(),
+            None,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Dispatch `msg` through `dispatcher`, returning the result and the report
+    async fn dispatch(dispatcher: &Dispatcher<MemoryStorage>, msg: IncomingMessage) -> (DispatchResult, DeliveryReport) {
+        let (msg, report) = msg.with_report();
+        let result = dispatcher.handle(msg).await;
+        (result, report.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn too_large_is_reported_but_not_as_throttled() {
+        let dispatcher = dispatcher().with_payload_limits(Some(PayloadLimit::new(4)), None);
+        let (result, report) = dispatch(&dispatcher, IncomingMessage::new("chat", "hello").with_channel("room")).await;
+
+        let error = DispatchError::TooLarge { size: 5, limit: 4 };
+        assert_eq!(result, Err(error.clone()));
+        assert_eq!(report.error, Some(error));
+        assert!(!report.throttled);
+    }
+
+    #[tokio::test]
+    async fn shutting_down_is_reported_but_not_as_throttled() {
+        let dispatcher = dispatcher();
+        dispatcher.cancel.cancel();
+        let (result, report) = dispatch(&dispatcher, IncomingMessage::new("chat", "late").with_channel("room")).await;
+
+        assert_eq!(result, Err(DispatchError::ShuttingDown));
+        assert_eq!(report.error, Some(DispatchError::ShuttingDown));
+        assert!(!report.throttled);
+    }
+
+    #[cfg(feature = "schema")]
+    #[tokio::test]
+    async fn invalid_is_reported_but_not_as_throttled() {
+        let mut schema = SchemaValidator::new();
+        schema.register("order", &serde_json::json!({"required": ["id"]})).unwrap();
+        let dispatcher = dispatcher().with_schema(Some(Arc::new(schema)));
+        let (result, report) = dispatch(&dispatcher, IncomingMessage::new("order", "{}").with_channel("room")).await;
+
+        assert!(matches!(result, Err(DispatchError::Invalid(_))));
+        assert!(matches!(report.error, Some(DispatchError::Invalid(_))));
+        assert!(!report.throttled);
+    }
+
+    #[tokio::test]
+    async fn throttle_rejection_is_reported_as_throttled() {
+        let throttle = Throttle::new(ThrottlePolicy::new(ThrottleAction::Reject).events_per_sec(1));
+        let dispatcher = Dispatcher::new(
+            ConnectionManager::new("test"),
+            MemoryStorage::default(),
+            Some(throttle),
+            CancellationToken::new(),
+        );
+        let (result, _) = dispatch(&dispatcher, IncomingMessage::new("chat", "first").with_channel("room")).await;
+        assert!(result.is_ok());
+
+        let (result, report) = dispatch(&dispatcher, IncomingMessage::new("chat", "second").with_channel("room")).await;
+        let error = DispatchError::Throttled { retry_after: None };
+        assert_eq!(result, Err(error.clone()));
+        assert_eq!(report.error, Some(error));
+        assert!(report.throttled);
+    }
+
+    #[tokio::test]
+    async fn oversized_import_is_refused() {
+        let storage = MemoryStorage::default();
+        let (router, handle) = Gateway::builder()
+            .source(NoopSource)
+            .storage(storage.clone())
+            .import_body_limit(32)
+            .build()
+            .unwrap()
+            .into_router();
+        let import = |body: &'static str| {
+            let request = axum::http::Request::post("/api/channels/room/import")
+                .body(axum::body::Body::from(body))
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let response = import(r#"{"event":"chat","data":"hello, world"}"#).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(storage.recent_messages("room", 10).await.is_empty());
+
+        let response = import(r#"{"event":"a","data":"b"}"#).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        handle.shutdown().await;
+    }
+
+    #[test]
+    fn import_body_limit_defaults_to_64_mib() {
+        let gateway = Gateway::builder().source(NoopSource).storage(NoopStorage).build().unwrap();
+        assert_eq!(gateway.import_body_limit, 64 * 1024 * 1024);
+    }
+
+    #[cfg(unix)]
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("sse-gateway-{}-{name}", uuid::Uuid::new_v4()))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_socket_is_removed() {
+        let path = temp_path("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn missing_socket_path_is_fine() {
+        remove_stale_socket(&temp_path("missing.sock")).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn regular_file_is_kept() {
+        let path = temp_path("config.toml");
+        std::fs::write(&path, "port = 8080").unwrap();
+
+        let error = remove_stale_socket(&path).unwrap_err();
+        assert!(error.to_string().contains("not a Unix socket"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "port = 8080");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_is_kept() {
+        let path = temp_path("dir");
+        std::fs::create_dir(&path).unwrap();
+
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(path.is_dir());
+        std::fs::remove_dir(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_to_a_socket_is_kept() {
+        let target = temp_path("target.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&target).unwrap();
+        let link = temp_path("link.sock");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(remove_stale_socket(&link).is_err());
+        assert!(std::fs::symlink_metadata(&link).is_ok());
+        std::fs::remove_file(&link).unwrap();
+        std::fs::remove_file(&target).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_through_a_file_is_an_error() {
+        // A path through a regular file can't be looked up at all
+        let file = temp_path("file");
+        std::fs::write(&file, "").unwrap();
+
+        assert!(remove_stale_socket(&file.join("gateway.sock")).is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+//! gRPC server-streaming subscriber endpoint
+//!
+//! Serves `sse_gateway.v1.Subscriber/Subscribe` (see `proto/gateway.proto`)
+//! on the gateway's HTTP port, alongside SSE. Subscribers go through the same
+//! registration, [`auth`](crate::GatewayBuilder::auth) and replay as SSE
+//! clients; the auth hook sees the gRPC metadata as request headers.
+//!
+//! ```rust,ignore
+//! use sse_gateway::grpc::proto::{subscriber_client::SubscriberClient, SubscribeRequest};
+//!
+//! let channel = tonic::transport::Channel::from_static("http://localhost:8080").connect().await?;
+//! let mut stream = SubscriberClient::new(channel)
+//!     .subscribe(SubscribeRequest { channel_id: "user123".into(), last_event_id: None })
+//!     .await?
+//!     .into_inner();
+//! while let Some(event) = stream.message().await? {
+//!     println!("{}: {}", event.event, event.data);
+//! }
+//! ```
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use axum::extract::ConnectInfo;
+use axum::http::{Method, StatusCode, Uri};
+use futures::Stream;
+use tokio_stream::StreamExt;
+use tonic::{Request, Response, Status};
+
+use crate::event::SseEvent;
+use crate::handler::{self, CleanupStream, GatewayState, Subscription};
+use crate::heartbeat::Outgoing;
+use crate::storage::MessageStorage;
+
+/// Generated protobuf types, client and server
+pub mod proto {
+    tonic::include_proto!("sse_gateway.v1");
+}
+
+use proto::subscriber_server::Subscriber;
+
+/// Path prefix the service is routed under
+pub(crate) const SERVICE_PATH: &str = "/sse_gateway.v1.Subscriber";
+
+impl From<SseEvent> for proto::Event {
+    fn from(event: SseEvent) -> Self {
+        Self {
+            data: event.data.to_string(),
+            id: event.stream_id.or(event.id),
+            retry: event.retry,
+            event: event.event_type,
+        }
+    }
+}
+
+/// Map an auth denial onto the closest gRPC status
+fn denied(status: StatusCode) -> Status {
+    match status {
+        StatusCode::UNAUTHORIZED => Status::unauthenticated("Connection denied"),
+        StatusCode::TOO_MANY_REQUESTS => Status::resource_exhausted("Connection denied"),
+        _ => Status::permission_denied("Connection denied"),
+    }
+}
+
+/// `Subscriber` service backed by the gateway's connection manager and storage
+pub struct SubscriberService<S: MessageStorage> {
+    state: GatewayState<S>,
+}
+
+impl<S: MessageStorage> SubscriberService<S> {
+    pub(crate) fn new(state: GatewayState<S>) -> Self {
+        Self { state }
+    }
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl<S: MessageStorage> Subscriber for SubscriberService<S> {
+    type SubscribeStream = EventStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<proto::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        // Attached by the gateway's listeners; tonic keeps HTTP extensions
+        let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+        let request = request.into_inner();
+        if request.channel_id.is_empty() {
+            return Err(Status::invalid_argument("Missing channel_id"));
+        }
+
+        let uri = Uri::from_static("/sse_gateway.v1.Subscriber/Subscribe");
+        let Subscription { replay, live, guard, .. } = handler::subscribe(
+            &self.state,
+            Method::POST,
+            uri,
+            request.channel_id,
+            &headers,
+            peer,
+            request.last_event_id,
+            None,
+        )
+        .await
+        .map_err(|response| denied(response.status()))?;
+
+        // Comment heartbeats are SSE-specific; HTTP/2 keeps the stream alive
+        let realtime_stream = live.filter_map(|outgoing| match outgoing {
+            Outgoing::Event(event) => Some(event),
+            Outgoing::Comment(_) => None,
+        });
+        let counters = guard.counters().clone();
+        let events = futures::stream::iter(replay)
+            .chain(realtime_stream)
+            .map(move |event| {
+                let event = proto::Event::from(event);
+                counters.record_write(true, prost::Message::encoded_len(&event));
+                Ok(event)
+            });
+
+        let stream = CleanupStream {
+            inner: Box::pin(events),
+            _guard: guard,
+        };
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -1,27 +1,49 @@
 //! HTTP handlers for the SSE gateway
 
 use axum::{
-    extract::{OriginalUri, Query, State},
+    extract::{OriginalUri, Path, Query, State},
     http::{header, Method, StatusCode},
-    response::{sse::Event, Html, IntoResponse, Json, Sse},
+    response::{sse::Event, IntoResponse, Json, Sse},
 };
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     convert::Infallible,
     pin::Pin,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
     task::{Context, Poll},
     time::Duration,
 };
-use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 
-use crate::auth::{AuthFn, AuthRequest};
-use crate::event::SseEvent;
-use crate::gateway::LifecycleCallback;
+use crate::access_log::{AccessLogRecord, AccessLogSink};
+use crate::id::IdGenerator;
+use crate::auth::{AttributesFn, AuthFn, AuthRequest, EventTypeFilter, EventTypesFn, IdentityFn};
+use crate::channel_config::ChannelConfig;
+use crate::channel_usage::{self, DailyUsage};
+use crate::client_ip::PeerAddr;
+use crate::compat::COMPAT_PARAM;
+use crate::cluster::{InstanceInfo, InstancePresence};
+use crate::connection::{CloseReason, ConnectionCounters, ConnectionMetadata, SendStats, SseConnection};
+use crate::encoding::EventEncoder;
+use crate::event::{EventData, Priority, SseEvent};
+use crate::heartbeat::{Heartbeat, Outgoing};
+use crate::interceptor::Decision;
+use crate::gateway::{ConnectHook, LifecycleCallback};
 use crate::manager::ConnectionManager;
-use crate::source::ConnectionInfo;
-use crate::storage::MessageStorage;
+use crate::metrics::{GatewayMetrics, MetricsSnapshot};
+use crate::pause::{ChannelPause, PausePolicy, PausedChannelStats};
+use crate::payload::PayloadLimit;
+use crate::publisher::Publisher;
+use crate::push::PushEndpoint;
+use crate::resume::{CachedAuth, ResumeSession, ResumeSessions, SessionLease, CONNECTED_EVENT, RESUME_PARAM};
+use crate::source::{ConnectionInfo, DispatchError, IncomingMessage};
+use crate::storage::{self, stream_id_timestamp, MessageStorage, PurgeBefore};
+use crate::supervisor::{SourceHealth, SourceState};
+use crate::tenancy::{self, Tenancy};
+use crate::throttle::{Throttle, ThrottleDecision};
 
 /// Shared state for handlers
 #[derive(Clone)]
@@ -29,190 +51,834 @@ pub struct GatewayState<S: MessageStorage> {
     pub connection_manager: ConnectionManager,
     pub storage: S,
     pub auth: Option<AuthFn>,
+    /// Auth hook of the management endpoints, if set
+    pub(crate) admin_auth: Option<AuthFn>,
+    /// Identity extractor for allowed requests
+    pub identity: Option<IdentityFn>,
+    /// Query parameters copied into connection attributes
+    pub attribute_params: Arc<[String]>,
+    /// Attribute extractor for allowed requests
+    pub attributes: Option<AttributesFn>,
+    /// Event type filter for allowed requests
+    pub event_types: Option<EventTypesFn>,
+    /// Picks the `data` encoding of each SSE connection, if set
+    pub(crate) event_encoder: Option<crate::encoding::EncoderFn>,
+    /// Tenant namespaces, if multi-tenancy is enabled
+    pub tenancy: Option<Tenancy>,
+    /// The source's connect hook, awaited before the subscription starts
+    pub connect_hook: Option<ConnectHook>,
+    /// Refuse the connection when the connect hook fails
+    pub reject_on_connect_error: bool,
     pub on_connect: Option<LifecycleCallback>,
     pub on_disconnect: Option<LifecycleCallback>,
+    /// Where to record closed connections, if anywhere
+    pub access_log: Option<Arc<dyn AccessLogSink>>,
+    /// Publishes through the dispatcher, like the message source
+    pub publisher: Publisher,
+    /// Stream ID generator; the storage generates IDs when unset
+    pub ids: Option<Arc<dyn IdGenerator>>,
+    /// Event types that compact their channel when stored
+    pub compact_on: Arc<[String]>,
+    /// Payload limit of pushed messages
+    pub payload_limit: Option<PayloadLimit>,
+    /// Payload limit of stored events
+    pub stored_payload_limit: Option<PayloadLimit>,
+    pub throttle: Option<Throttle>,
+    /// Query parameter carrying the channel ID on the SSE endpoint
+    pub channel_param: Arc<str>,
+    /// Built-in push endpoint settings, if enabled
+    pub push: Option<Arc<PushEndpoint>>,
+    /// Heartbeat format
+    pub heartbeat: Arc<Heartbeat>,
+    /// Idle time before a keep-alive comment, if enabled
+    pub(crate) keep_alive: Option<Duration>,
+    /// Reconnect delay advertised at the start of each stream, if set
+    pub(crate) retry_policy: Option<crate::retry::RetryPolicy>,
+    /// Cross-instance forwarding, if cluster mode is enabled
+    pub(crate) cluster: Option<crate::cluster::Cluster>,
+    /// Channel ownership, for redirecting subscribers to the owning instance
+    pub(crate) channel_router: Option<crate::ChannelRouter>,
+    /// Coalescing of queued events into fewer writes, if enabled
+    pub(crate) write_coalescing: Option<crate::coalesce::WriteCoalescing>,
+    /// Padding and proxy headers for legacy clients
+    pub(crate) compat: crate::compat::CompatMode,
+    /// Events sent around replayed events, if enabled
+    pub(crate) replay_markers: Option<ReplayMarkers>,
+    /// Channel aliases applied to subscriptions
+    pub(crate) channel_rewrites: crate::channel_rewrite::ChannelRewrites,
+    /// Channel derived from the caller's identity, if enabled
+    pub(crate) identity_channel: Option<crate::auth::IdentityChannel>,
+    /// Proxies whose forwarding headers give the client address
+    pub(crate) trusted_proxies: crate::client_ip::TrustedProxies,
+    /// SSE response compression, if enabled
+    #[cfg(feature = "compression")]
+    pub compression: Option<crate::compression::Compression>,
+    /// Payload validator, if schemas are registered
+    #[cfg(feature = "schema")]
+    pub schema: Option<Arc<crate::schema::SchemaValidator>>,
+    /// Resume sessions, if resume tokens are enabled
+    pub(crate) resume: Option<Arc<ResumeSessions>>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SseConnectParams {
-    pub channel_id: String,
-}
+/// Query parameter carrying the replay cursor, for clients that can't set `Last-Event-ID`
+pub(crate) const LAST_EVENT_ID_PARAM: &str = "last_event_id";
+
+/// Query parameter naming the consumer whose acknowledged cursor to resume from
+pub(crate) const CONSUMER_ID_PARAM: &str = "consumer_id";
 
-fn sse_event_to_axum(sse_event: SseEvent) -> Event {
-    let data = sse_event.data.to_string();
+/// Query parameter naming the consumer group to join
+pub(crate) const GROUP_PARAM: &str = "group";
+
+/// Query parameter asking for replayed events in batches of the given size
+pub(crate) const REPLAY_BATCH_PARAM: &str = "replay_batch";
+
+/// Largest accepted `replay_batch` size
+const MAX_REPLAY_BATCH: usize = 1000;
+
+/// Convert an event to an SSE frame, counting it as written to the connection
+fn sse_event_to_axum(sse_event: SseEvent, counters: &ConnectionCounters, encoder: Option<&ConnectionEncoder>) -> Event {
+    let data = encoder
+        .and_then(|encoder| encoder.encode(&sse_event))
+        .unwrap_or_else(|| sse_event.data.to_string());
+    // Lines of the frame: "event: ", "data: " per data line, "id: ", "retry: ", blank
+    let mut len = "event: \n".len() + sse_event.event_type.len();
+    len += data.split('\n').map(|line| "data: \n".len() + line.len()).sum::<usize>();
     let event = Event::default().event(&sse_event.event_type).data(data);
 
-    let event = if let Some(stream_id) = &sse_event.stream_id {
-        event.id(stream_id.clone())
-    } else if let Some(id) = &sse_event.id {
-        event.id(id.clone())
+    let event = if let Some(id) = sse_event.stream_id.or(sse_event.id) {
+        len += "id: \n".len() + id.len();
+        event.id(id)
     } else {
         event
     };
 
-    if let Some(retry) = sse_event.retry {
-        event.retry(Duration::from_millis(retry as u64))
+    let event = if let Some(retry) = sse_event.retry {
+        let millis = retry as u64;
+        len += "retry: \n".len() + millis.to_string().len();
+        event.retry(Duration::from_millis(millis))
     } else {
         event
+    };
+    counters.record_write(true, len + 1);
+    event
+}
+
+/// Client IP per the trusted proxies: a forwarded address or the socket peer
+pub(crate) fn client_ip<S: MessageStorage>(
+    state: &GatewayState<S>,
+    headers: &axum::http::HeaderMap,
+    peer: Option<SocketAddr>,
+) -> Option<String> {
+    state.trusted_proxies.client_ip(headers, peer)
+}
+
+/// Apply the publish throttle to an HTTP-published event
+///
+/// Returns the status to respond with if the event must not be delivered,
+/// and when to retry for an event the quota allows later.
+pub(crate) fn throttle<S: MessageStorage>(
+    state: &GatewayState<S>,
+    channel_id: Option<&str>,
+    size: usize,
+) -> Result<(), (StatusCode, Option<Duration>)> {
+    let Some(channel_id) = channel_id else {
+        return Ok(());
+    };
+    let metrics = state.connection_manager.metrics();
+    let decisions = [
+        state.tenancy.as_ref().map(|t| t.check(channel_id, size, metrics)),
+        state.throttle.as_ref().map(|t| t.check_and_record(channel_id, size, metrics)),
+    ];
+    for decision in decisions.into_iter().flatten() {
+        match decision {
+            ThrottleDecision::Allow => {}
+            ThrottleDecision::Delay(wait) => return Err((StatusCode::TOO_MANY_REQUESTS, Some(wait))),
+            ThrottleDecision::Drop => return Err((StatusCode::OK, None)),
+            ThrottleDecision::Reject => return Err((StatusCode::TOO_MANY_REQUESTS, None)),
+        }
     }
+    Ok(())
 }
 
-/// SSE connection endpoint
+/// `response` with a `Retry-After` header of `retry_after`, rounded up to
+/// whole seconds
+fn with_retry_after(mut response: axum::response::Response, retry_after: Option<Duration>) -> axum::response::Response {
+    if let Some(wait) = retry_after {
+        let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        response.headers_mut().insert(header::RETRY_AFTER, seconds.into());
+    }
+    response
+}
+
+/// Channel `channel_id` in the tenant namespace of `auth_request`
+///
+/// Returns the channel unchanged without tenancy, or the rejection for a
+/// request whose tenant can't be resolved.
+pub(crate) fn tenant_channel<S: MessageStorage>(
+    state: &GatewayState<S>,
+    auth_request: Option<&AuthRequest>,
+    channel_id: String,
+) -> Result<String, (StatusCode, &'static str)> {
+    let Some(tenancy) = &state.tenancy else {
+        return Ok(channel_id);
+    };
+    match auth_request.and_then(|req| tenancy.resolve(req)) {
+        Some(tenant) => Ok(tenancy::scoped_channel(&tenant, &channel_id)),
+        None => {
+            tracing::warn!(channel_id = %channel_id, "Request refused: no tenant");
+            Err((StatusCode::FORBIDDEN, "Unknown tenant"))
+        }
+    }
+}
+
+/// SSE connection endpoint (channel in the query string)
 pub async fn sse_connect<S: MessageStorage>(
     State(state): State<GatewayState<S>>,
     method: Method,
     OriginalUri(uri): OriginalUri,
-    Query(params): Query<SseConnectParams>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+    PeerAddr(peer): PeerAddr,
+) -> axum::response::Response {
+    // A resume token stands in for the channel
+    let channel_id = query.get(&*state.channel_param).filter(|c| !c.is_empty()).cloned();
+    let resume_token = query.get(RESUME_PARAM).filter(|t| !t.is_empty()).cloned();
+    let last_event_id = query.get(LAST_EVENT_ID_PARAM).cloned();
+    let compat = state.compat.applies(query.get(COMPAT_PARAM).map(String::as_str));
+    connect(state, method, uri, channel_id, headers, peer, last_event_id, resume_token, compat).await
+}
+
+/// SSE connection endpoint (channel as the last path segment)
+pub async fn sse_connect_path<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    Path(channel_id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
     headers: axum::http::HeaderMap,
+    PeerAddr(peer): PeerAddr,
 ) -> axum::response::Response {
-    let client_ip = headers
-        .get("x-forwarded-for")
+    let resume_token = query.get(RESUME_PARAM).filter(|t| !t.is_empty()).cloned();
+    let last_event_id = query.get(LAST_EVENT_ID_PARAM).cloned();
+    let compat = state.compat.applies(query.get(COMPAT_PARAM).map(String::as_str));
+    connect(state, method, uri, Some(channel_id), headers, peer, last_event_id, resume_token, compat).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn connect<S: MessageStorage>(
+    state: GatewayState<S>,
+    method: Method,
+    uri: axum::http::Uri,
+    channel_id: Option<String>,
+    headers: axum::http::HeaderMap,
+    peer: Option<SocketAddr>,
+    query_last_event_id: Option<String>,
+    resume_token: Option<String>,
+    compat: bool,
+) -> axum::response::Response {
+    // An unknown token is only fatal when there is nothing else to go on
+    let resumed = match (&state.resume, resume_token) {
+        (Some(sessions), Some(token)) => {
+            let session = match sessions.get(&token) {
+                Some(session) => Some(session),
+                // Saved by an earlier run or another instance
+                None if sessions.is_persisted() => {
+                    state.storage.load_session(&token).await.as_deref().and_then(ResumeSessions::parse)
+                }
+                None => None,
+            };
+            match session {
+                Some(session) => Some((token, session)),
+                None if channel_id.is_none() => {
+                    return (StatusCode::GONE, "Unknown or expired resume token").into_response();
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    // With identity channels the channel comes from the caller, not the request
+    let Some(channel_id) = channel_id
+        .or_else(|| resumed.as_ref().map(|(_, session)| session.channel_id.clone()))
+        .or_else(|| state.identity_channel.as_ref().map(|_| String::new()))
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Missing {} parameter", state.channel_param),
+        )
+            .into_response();
+    };
+
+    // Send the subscriber to the instance that owns the channel; it runs auth
+    if let Some(router) = state.channel_router.as_ref().filter(|_| !channel_id.is_empty()) {
+        let instance_id = state.connection_manager.instance_id();
+        let owned = state.channel_rewrites.rewrite(&channel_id);
+        if let Some(location) = router.redirect_location(instance_id, &owned, uri.path(), uri.query()) {
+            tracing::debug!(channel_id = %channel_id, location = %location, "Redirecting to channel owner");
+            return axum::response::Redirect::temporary(&location).into_response();
+        }
+    }
+
+    // EventSource sends the header on reconnect, which is newer than the
+    // cursor in the original URL or the resumed session
+    let last_event_id = headers
+        .get("last-event-id")
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string());
+        .map(|s| s.to_string())
+        .or(query_last_event_id)
+        .or_else(|| resumed.as_ref().and_then(|(_, session)| session.cursor.clone()));
+
+    let (token, resumed) = resumed.unzip();
+    let subscription =
+        match subscribe(&state, method, uri, channel_id, &headers, peer, last_event_id, resumed.as_ref()).await {
+            Ok(subscription) => subscription,
+            Err(response) => return response,
+        };
+    let Subscription {
+        replay,
+        live,
+        guard,
+        session,
+        encoder,
+    } = subscription;
+
+    let lease = state
+        .resume
+        .as_ref()
+        .map(|sessions| Arc::new(sessions.open(token, session)));
+    // Polyfills hand nothing to the page until enough bytes have arrived
+    let padding = compat.then(|| state.compat.padding_comment()).flatten().map(|text| {
+        // ": " + text + "\n\n"
+        guard.counters().record_write(false, text.len() + 4);
+        Ok::<_, Infallible>(Event::default().comment(text))
+    });
+    let retry = state
+        .retry_policy
+        .filter(|policy| policy.sends_on_connect())
+        .map(|policy| Ok::<_, Infallible>(retry_event(&policy, guard.counters())));
+    let connected = lease
+        .as_ref()
+        .map(|lease| Ok::<_, Infallible>(connected_event(lease, guard.counters())));
+
+    let counters = guard.counters().clone();
+    let replay_lease = lease.clone();
+    let replay_encoder = encoder.clone();
+    let preamble = padding.into_iter().chain(retry).chain(connected);
+    let replay_stream = futures::stream::iter(preamble).chain(futures::stream::iter(
+        replay.into_iter().map(move |event| {
+            record_cursor(replay_lease.as_deref(), &event);
+            Ok::<_, Infallible>(sse_event_to_axum(event, &counters, replay_encoder.as_ref()))
+        }),
+    ));
+
+    let counters = guard.counters().clone();
+    let realtime_stream = live.map(move |outgoing| {
+        Ok::<_, Infallible>(match outgoing {
+            Outgoing::Event(event) => {
+                record_cursor(lease.as_deref(), &event);
+                sse_event_to_axum(event, &counters, encoder.as_ref())
+            }
+            Outgoing::Comment(text) => {
+                // ":" + text + "\n\n"
+                counters.record_write(false, text.len() + 3);
+                Event::default().comment(text)
+            }
+        })
+    });
+    let merged_stream = replay_stream.chain(realtime_stream);
+
+    let final_stream = CleanupStream {
+        inner: Box::pin(merged_stream),
+        _guard: guard,
+    };
+
+    // Axum restarts the keep-alive timer on every frame, so busy streams get none
+    let sse = Sse::new(final_stream);
+    let response = match state.keep_alive {
+        Some(interval) => sse
+            .keep_alive(axum::response::sse::KeepAlive::new().interval(interval).text("keep-alive"))
+            .into_response(),
+        None => sse.into_response(),
+    };
+    let response = match &state.write_coalescing {
+        Some(coalescing) if !(compat && state.compat.chunks_events()) => coalescing.apply(response),
+        _ => response,
+    };
+
+    #[cfg(feature = "compression")]
+    let response = match &state.compression {
+        Some(compression) => compression.apply(&headers, response),
+        None => response,
+    };
+    if compat {
+        return state.compat.apply(response);
+    }
+    response
+}
+
+/// A `retry:`-only frame setting this client's reconnect delay
+fn retry_event(policy: &crate::retry::RetryPolicy, counters: &ConnectionCounters) -> Event {
+    let retry = policy.pick();
+    // "retry: " + millis + "\n\n"
+    counters.record_write(false, "retry: \n".len() + retry.as_millis().to_string().len() + 1);
+    Event::default().retry(retry)
+}
+
+/// The `connected` event announcing the session's resume token
+fn connected_event(lease: &SessionLease, counters: &ConnectionCounters) -> Event {
+    let data = serde_json::json!({ "resume_token": lease.token() }).to_string();
+    counters.record_write(false, "event: \n".len() + CONNECTED_EVENT.len() + "data: \n".len() + data.len() + 1);
+    Event::default().event(CONNECTED_EVENT).data(data)
+}
+
+/// Remember the stream ID of an event about to be written as the session's cursor
+fn record_cursor(lease: Option<&SessionLease>, event: &SseEvent) {
+    if let (Some(lease), Some(stream_id)) = (lease, &event.stream_id) {
+        lease.record(stream_id);
+    }
+}
+
+/// Event types of the markers sent around replayed events
+#[derive(Debug, Clone)]
+pub(crate) struct ReplayMarkers {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for ReplayMarkers {
+    fn default() -> Self {
+        Self {
+            start: crate::gateway::REPLAY_START_EVENT.to_string(),
+            end: crate::gateway::REPLAY_END_EVENT.to_string(),
+        }
+    }
+}
+
+impl ReplayMarkers {
+    /// Surround `replay`, holding `count` replayed events, with start and
+    /// end markers, unless it's empty
+    fn wrap(&self, from_id: Option<&str>, count: usize, replay: &mut Vec<SseEvent>) {
+        if replay.is_empty() {
+            return;
+        }
+        let last_id = replay.last().and_then(|event| event.stream_id.clone().or_else(|| event.id.clone()));
+        // Without an ID, markers don't move the client's Last-Event-ID
+        let marker = |event_type: &str, data| SseEvent { id: None, ..SseEvent::new(event_type, data) };
+        replay.insert(0, marker(&self.start, serde_json::json!({ "count": count, "from_id": from_id })));
+        replay.push(marker(&self.end, serde_json::json!({ "count": count, "last_id": last_id })));
+    }
+}
+
+/// Pack replayed events into `replay_batch` events of up to `size` each
+///
+/// Each batch carries the stream ID of its last event, so `Last-Event-ID`
+/// and resume cursors advance as with individual events.
+fn batch_replay(replay: Vec<SseEvent>, size: usize, encoder: Option<&ConnectionEncoder>) -> Vec<SseEvent> {
+    replay
+        .chunks(size)
+        .map(|chunk| {
+            let items = chunk
+                .iter()
+                .map(|event| {
+                    let mut item = serde_json::Map::new();
+                    item.insert("event".into(), event.event_type.clone().into());
+                    // Data that is JSON is embedded as such, so clients parse the batch once
+                    let data = match (encoder.and_then(|encoder| encoder.encode(event)), &event.data) {
+                        (Some(text), _) => serde_json::from_str(&text).unwrap_or_else(|_| text.into()),
+                        (None, EventData::Value(value)) => value.clone(),
+                        (None, EventData::Raw(raw)) => serde_json::from_str(raw).unwrap_or_else(|_| raw.clone().into()),
+                    };
+                    item.insert("data".into(), data);
+                    if let Some(id) = event.stream_id.as_ref().or(event.id.as_ref()) {
+                        item.insert("id".into(), id.clone().into());
+                    }
+                    serde_json::Value::Object(item)
+                })
+                .collect();
+            let last = chunk.last().expect("chunks are never empty");
+            SseEvent {
+                id: last.id.clone(),
+                stream_id: last.stream_id.clone(),
+                ..SseEvent::new(crate::gateway::REPLAY_BATCH_EVENT, serde_json::Value::Array(items))
+            }
+        })
+        .collect()
+}
+
+/// A registered subscriber, independent of the transport serving it
+pub(crate) struct Subscription {
+    /// Missed events to send before live ones
+    pub replay: Vec<SseEvent>,
+    /// Live events and heartbeats for this connection
+    pub live: crate::heartbeat::LiveStream,
+    /// Unregisters the connection when dropped
+    pub guard: ConnectionGuard,
+    /// What a resume token for this connection would restore
+    pub session: ResumeSession,
+    /// Encoding of `data` fields, if not the default
+    pub encoder: Option<ConnectionEncoder>,
+}
+
+/// A connection's `data` encoder, leaving the gateway's own events as they are
+#[derive(Clone)]
+pub(crate) struct ConnectionEncoder {
+    encoder: Arc<dyn EventEncoder>,
+    /// Event types written without encoding: heartbeats, `migrate`, replay markers
+    control: Arc<[String]>,
+}
+
+impl ConnectionEncoder {
+    /// The encoded `data` of `event`, `None` to write it as published
+    fn encode(&self, event: &SseEvent) -> Option<String> {
+        (!self.control.contains(&event.event_type)).then(|| self.encoder.encode(event))
+    }
+}
+
+/// Authenticate, register and load replay for a new subscriber
+///
+/// Shared by every subscriber transport. Returns the response to send
+/// instead if the connection is denied.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn subscribe<S: MessageStorage>(
+    state: &GatewayState<S>,
+    method: Method,
+    uri: axum::http::Uri,
+    channel_id: String,
+    headers: &axum::http::HeaderMap,
+    peer: Option<SocketAddr>,
+    last_event_id: Option<String>,
+    resumed: Option<&ResumeSession>,
+) -> Result<Subscription, axum::response::Response> {
+    let channel_id = state.channel_rewrites.rewrite(&channel_id).into_owned();
+    let client_ip = client_ip(state, headers, peer);
 
     let user_agent = headers
         .get(header::USER_AGENT)
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    let last_event_id = headers
-        .get("last-event-id")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+    // Callback results cached for this channel stand in for the callbacks
+    let persisted = state.resume.as_ref().is_some_and(|sessions| sessions.is_persisted());
+    let cached = resumed
+        .filter(|session| persisted && session.channel_id == channel_id)
+        .and_then(|session| session.auth.clone());
 
-    // Perform authentication if configured
-    if let Some(auth_fn) = &state.auth {
-        let auth_request = AuthRequest {
-            method: method.clone(),
-            uri: uri.clone(),
-            headers: headers.clone(),
-            channel_id: params.channel_id.clone(),
-            client_ip: client_ip.clone(),
-        };
+    // Start from the resumed session; parameters sent now take precedence
+    let mut session = ResumeSession {
+        channel_id: channel_id.clone(),
+        auth: None,
+        ..resumed.cloned().unwrap_or_default()
+    };
+    if let Ok(Query(query)) = Query::<HashMap<String, String>>::try_from_uri(&uri) {
+        if let Some(consumer_id) = query.get(CONSUMER_ID_PARAM).filter(|id| !id.is_empty()) {
+            session.consumer_id = Some(consumer_id.clone());
+        }
+        if let Some(group) = query.get(GROUP_PARAM).filter(|group| !group.is_empty()) {
+            session.group = Some(group.clone());
+        }
+        if let Some(size) = query.get(REPLAY_BATCH_PARAM).and_then(|size| size.parse::<usize>().ok()) {
+            session.replay_batch = (size > 0).then_some(size.min(MAX_REPLAY_BATCH));
+        }
+        session
+            .attributes
+            .extend(query.into_iter().filter(|(key, _)| state.attribute_params.contains(key)));
+    }
+    let mut attributes = session.attributes.clone();
+    let consumer_id = session.consumer_id.clone();
+    let group = session.group.clone();
+
+    let needs_request = state.auth.is_some()
+        || state.identity.is_some()
+        || state.attributes.is_some()
+        || state.event_types.is_some()
+        || state.event_encoder.is_some()
+        || state.tenancy.is_some();
+    let auth_request = needs_request.then(|| AuthRequest {
+        method,
+        uri,
+        headers: headers.clone(),
+        channel_id: channel_id.clone(),
+        client_ip: client_ip.clone(),
+    });
 
+    // Perform authentication if configured
+    if let (Some(auth_fn), Some(auth_request), None) = (&state.auth, &auth_request, &cached) {
         // If auth returns Some(response), deny the connection
-        if let Some(response) = auth_fn(auth_request).await {
+        if let Some(response) = auth_fn(auth_request.clone()).await {
             tracing::warn!(
-                channel_id = %params.channel_id,
+                channel_id = %channel_id,
                 client_ip = ?client_ip,
                 "SSE connection denied"
             );
-            return response;
+            return Err(response);
+        }
+    }
+
+    let identity = match (&cached, &state.identity, &auth_request) {
+        (Some(cached), _, _) => cached.identity.clone(),
+        (None, Some(identity_fn), Some(auth_request)) => identity_fn(auth_request),
+        _ => None,
+    };
+    let channel_id = match &state.identity_channel {
+        Some(identity_channel) => identity_channel
+            .resolve(identity.as_deref(), &channel_id)
+            .map_err(|(status, message)| {
+                tracing::warn!(channel_id = %channel_id, identity = ?identity, "SSE connection denied: {message}");
+                (status, message).into_response()
+            })?,
+        None => channel_id,
+    };
+    let granted = match (&cached, &state.attributes, &auth_request) {
+        (Some(cached), _, _) => cached.attributes.clone(),
+        (None, Some(attributes_fn), Some(auth_request)) => attributes_fn(auth_request),
+        _ => HashMap::new(),
+    };
+    attributes.extend(granted.clone());
+    let encoder = match (&state.event_encoder, &auth_request) {
+        (Some(encoder_fn), Some(auth_request)) => encoder_fn(auth_request).map(|encoder| {
+            let mut control = vec![crate::gateway::MIGRATE_EVENT.to_string(), crate::gateway::REPLAY_BATCH_EVENT.to_string()];
+            control.extend(state.heartbeat.event_name().map(str::to_string));
+            if let Some(markers) = &state.replay_markers {
+                control.extend([markers.start.clone(), markers.end.clone()]);
+            }
+            ConnectionEncoder {
+                encoder,
+                control: control.into(),
+            }
+        }),
+        _ => None,
+    };
+    let event_types = match (&cached, &state.event_types, &auth_request) {
+        (Some(cached), _, _) => cached.event_types.clone(),
+        (None, Some(event_types_fn), Some(auth_request)) => event_types_fn(auth_request),
+        _ => None,
+    };
+    if persisted {
+        session.auth = Some(CachedAuth {
+            identity: identity.clone(),
+            attributes: granted,
+            event_types: event_types.clone(),
+        });
+    }
+
+    let channel_id =
+        tenant_channel(state, auth_request.as_ref(), channel_id).map_err(IntoResponse::into_response)?;
+    if let (Some(tenancy), Some(tenant)) = (&state.tenancy, tenancy::tenant_of(&channel_id)) {
+        if !tenancy.admits(state.connection_manager.tenant_connection_count(tenant)) {
+            tracing::warn!(tenant, "Connection refused: tenant connection limit reached");
+            return Err((StatusCode::TOO_MANY_REQUESTS, "Tenant connection limit reached").into_response());
+        }
+    }
+
+    // Resume from the consumer's acknowledged cursor when the client sent none
+    let last_event_id = match (last_event_id, &consumer_id) {
+        (None, Some(consumer_id)) => state.storage.get_cursor(&channel_id, consumer_id).await,
+        (last_event_id, _) => last_event_id,
+    };
+    session.cursor = last_event_id.clone();
+
+    let channel_config = state.connection_manager.channel_configs().resolve(&channel_id);
+    if let Some(limit) = channel_config.max_subscribers {
+        if state.connection_manager.channel_connection_count(&channel_id) >= limit {
+            tracing::warn!(channel_id = %channel_id, limit, "Connection refused: channel is full");
+            return Err((StatusCode::TOO_MANY_REQUESTS, "Channel subscriber limit reached").into_response());
         }
     }
 
     tracing::info!(
-        channel_id = %params.channel_id,
+        channel_id = %channel_id,
         client_ip = ?client_ip,
         last_event_id = ?last_event_id,
         "New SSE connection"
     );
 
-    let (connection, receiver) = state.connection_manager.register(
-        params.channel_id.clone(),
+    let (connection, mut receiver) = state.connection_manager.register_restricted(
+        channel_id.clone(),
         client_ip,
+        peer,
         user_agent,
+        identity,
+        attributes,
+        event_types,
     );
+    let mut urgent = state.connection_manager.attach_urgent(&connection.id);
+    if let Some(group) = group {
+        state.connection_manager.join_group(&connection.id, group);
+    }
 
-    let connection_id = connection.id.clone();
-    let instance_id = state.connection_manager.instance_id().to_string();
-    let connection_manager = state.connection_manager.clone();
-
-    // Call on_connect callback
     let conn_info = ConnectionInfo {
-        channel_id: params.channel_id.clone(),
-        connection_id: connection_id.clone(),
-        instance_id: instance_id.clone(),
+        channel_id: channel_id.clone(),
+        connection_id: connection.id.to_string(),
+        instance_id: state.connection_manager.instance_id().to_string(),
+        close_reason: None,
     };
+
+    // Await the source's hook; events published meanwhile are buffered
+    if let Some(ref connect_hook) = state.connect_hook {
+        if let Err(e) = connect_hook(conn_info.clone()).await {
+            if state.reject_on_connect_error {
+                tracing::warn!(error = %e, channel_id = %channel_id, "Connection refused: on_connect hook failed");
+                state.connection_manager.unregister(&connection.id);
+                return Err((StatusCode::SERVICE_UNAVAILABLE, "Connection setup failed").into_response());
+            }
+            tracing::warn!(error = %e, channel_id = %channel_id, "on_connect hook failed");
+        }
+    }
+
+    // Call on_connect callback
     if let Some(ref on_connect) = state.on_connect {
         on_connect(&conn_info);
     }
+    let guard = ConnectionGuard {
+        connection_manager: state.connection_manager.clone(),
+        info: conn_info,
+        on_disconnect: state.on_disconnect.clone(),
+        counters: connection.counters.clone(),
+        close_reason: connection.close_reason.clone(),
+        access_log: state
+            .access_log
+            .clone()
+            .map(|sink| (sink, connection.metadata.clone())),
+    };
 
-    // Replay missed messages
-    let replay_messages = state
+    // Replay missed messages. The connection is registered first, so events
+    // published from here on are buffered in `receiver` rather than lost.
+    let mut replay_messages = state
         .storage
-        .get_messages_after(&params.channel_id, last_event_id.as_deref())
+        .get_messages_after(&channel_id, last_event_id.as_deref())
         .await;
+    replay_messages.retain(|event| !event.is_expired());
+    channel_config.trim_replay(&mut replay_messages);
 
     if !replay_messages.is_empty() {
         tracing::info!(
-            channel_id = %params.channel_id,
+            channel_id = %channel_id,
             count = replay_messages.len(),
             "Replaying messages"
         );
     }
 
-    let replay_stream = futures::stream::iter(
-        replay_messages
-            .into_iter()
-            .map(|event| Ok::<_, Infallible>(sse_event_to_axum(event))),
-    );
-
-    let event_stream = ReceiverStream::new(receiver)
-        .map(|event| Ok::<_, Infallible>(sse_event_to_axum(event)));
+    // Events are sent before they're stored, so only those already buffered
+    // can also have been returned by the replay query
+    let replayed_ids: HashSet<&str> = replay_messages
+        .iter()
+        .filter_map(|event| event.stream_id.as_deref())
+        .collect();
+    let mut buffered = Vec::new();
+    if !replayed_ids.is_empty() {
+        for receiver in urgent.iter_mut().chain([&mut receiver]) {
+            for _ in 0..receiver.len() {
+                let Ok(event) = receiver.try_recv() else { break };
+                if !event.is_expired()
+                    && !event
+                        .stream_id
+                        .as_deref()
+                        .is_some_and(|id| replayed_ids.contains(id))
+                {
+                    buffered.push(event);
+                }
+            }
+        }
+    }
 
-    let heartbeat_stream = tokio_stream::wrappers::BroadcastStream::new(
-        state.connection_manager.subscribe_heartbeat(),
-    )
-    .filter_map(|r| r.ok())
-    .map(|ts| {
-        Ok::<_, Infallible>(
-            Event::default()
-                .event("heartbeat")
-                .data(serde_json::json!({"ts": ts}).to_string()),
-        )
-    });
+    // Replayed events pass through the same per-connection event type filter
+    // and interceptors; buffered live events already have
+    let interceptors = state.connection_manager.interceptors();
+    let dedup = connection.dedup.clone();
+    let admit = |event: &SseEvent| dedup.as_ref().is_none_or(|dedup| dedup.admit(event));
+    let mut replay: Vec<SseEvent> = replay_messages
+        .into_iter()
+        .filter(|event| connection.allows_event_type(&event.event_type))
+        .filter_map(|mut event| {
+            (interceptors.before_send(&connection, &mut event) == Decision::Continue)
+                .then_some(event)
+        })
+        .filter(admit)
+        .collect();
+    let replayed = replay.len();
+    if let Some(size) = session.replay_batch {
+        replay = batch_replay(replay, size, encoder.as_ref());
+    }
+    if let Some(markers) = &state.replay_markers {
+        markers.wrap(last_event_id.as_deref(), replayed, &mut replay);
+    }
+    replay.extend(buffered.into_iter().filter(admit));
 
-    let realtime_stream = futures::stream::select(event_stream, heartbeat_stream);
-    let merged_stream = replay_stream.chain(realtime_stream);
+    let mut live = state
+        .heartbeat
+        .live_stream(receiver, urgent, state.connection_manager.subscribe_heartbeat());
+    if let Some(dedup) = dedup {
+        live = Box::pin(live.filter(move |outgoing| match outgoing {
+            Outgoing::Event(event) => dedup.admit(event),
+            Outgoing::Comment(_) => true,
+        }));
+    }
 
-    let cleanup_id = connection_id.clone();
-    let cleanup_channel = params.channel_id.clone();
-    let cleanup_instance = instance_id.clone();
-    let on_disconnect = state.on_disconnect.clone();
-    let final_stream = CleanupStream {
-        inner: Box::pin(merged_stream),
-        connection_id: connection_id.clone(),
-        cleanup: Some(Box::new(move || {
-            tracing::info!(connection_id = %cleanup_id, channel_id = %cleanup_channel, "Connection closed");
-            connection_manager.unregister(&cleanup_id);
-            
-            // Call on_disconnect callback
-            if let Some(ref callback) = on_disconnect {
-                let info = ConnectionInfo {
-                    channel_id: cleanup_channel.clone(),
-                    connection_id: cleanup_id.clone(),
-                    instance_id: cleanup_instance,
-                };
-                callback(&info);
-            }
-        })),
-    };
+    Ok(Subscription {
+        replay,
+        live,
+        guard,
+        session,
+        encoder,
+    })
+}
 
-    Sse::new(final_stream)
-        .keep_alive(
-            axum::response::sse::KeepAlive::new()
-                .interval(Duration::from_secs(10))
-                .text("keep-alive"),
-        )
-        .into_response()
+/// Unregisters a connection and fires `on_disconnect` when dropped
+pub(crate) struct ConnectionGuard {
+    connection_manager: ConnectionManager,
+    info: ConnectionInfo,
+    on_disconnect: Option<LifecycleCallback>,
+    counters: Arc<ConnectionCounters>,
+    /// Set by the manager if the gateway closed the connection
+    close_reason: Arc<OnceLock<CloseReason>>,
+    /// Sink and connection details, if an access log is configured
+    access_log: Option<(Arc<dyn AccessLogSink>, ConnectionMetadata)>,
 }
 
-struct CleanupStream<S> {
-    inner: Pin<Box<S>>,
-    cleanup: Option<Box<dyn FnOnce() + Send>>,
-    #[allow(dead_code)]
-    connection_id: String,
+impl ConnectionGuard {
+    /// Delivery counters of the guarded connection
+    pub(crate) fn counters(&self) -> &Arc<ConnectionCounters> {
+        &self.counters
+    }
 }
 
-impl<S> Drop for CleanupStream<S> {
+impl Drop for ConnectionGuard {
     fn drop(&mut self) {
-        if let Some(cleanup) = self.cleanup.take() {
-            cleanup();
+        // Unset means the transport ended first, i.e. the client left
+        let close_reason = *self.close_reason.get_or_init(|| CloseReason::ClientDisconnect);
+        tracing::info!(
+            connection_id = %self.info.connection_id,
+            channel_id = %self.info.channel_id,
+            %close_reason,
+            "Connection closed"
+        );
+        self.connection_manager.close(&self.info.connection_id, close_reason);
+        self.connection_manager.metrics().record_disconnect(close_reason);
+        self.info.close_reason = Some(close_reason);
+
+        if let Some((sink, metadata)) = &self.access_log {
+            sink.write(&AccessLogRecord::new(
+                &self.info.connection_id,
+                &self.info.channel_id,
+                metadata,
+                &self.counters,
+                close_reason,
+            ));
+        }
+
+        // Call on_disconnect callback
+        if let Some(ref callback) = self.on_disconnect {
+            callback(&self.info);
         }
     }
 }
 
+/// Event stream that unregisters its connection when dropped
+pub(crate) struct CleanupStream<S> {
+    pub inner: Pin<Box<S>>,
+    pub _guard: ConnectionGuard,
+}
+
 impl<S: Stream + Unpin> Stream for CleanupStream<S> {
     type Item = S::Item;
 
@@ -226,6 +892,22 @@ impl<S: Stream + Unpin> Stream for CleanupStream<S> {
 pub struct StatsResponse {
     pub total_connections: usize,
     pub connections: Vec<ConnectionStats>,
+    /// Per-tenant totals, if multi-tenancy is enabled
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tenants: BTreeMap<String, TenantStats>,
+    /// Send totals per channel with subscribers
+    pub channels: BTreeMap<String, SendStats>,
+    /// Channels whose live delivery is paused
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paused_channels: Vec<PausedChannelStats>,
+}
+
+#[derive(Default, Serialize)]
+pub struct TenantStats {
+    /// Connections on this instance
+    pub connections: usize,
+    /// Events published to the tenant's channels
+    pub published: u64,
 }
 
 #[derive(Serialize)]
@@ -234,6 +916,29 @@ pub struct ConnectionStats {
     pub channel_id: String,
     pub connected_at: String,
     pub is_active: bool,
+    /// Duplicate events suppressed by the dedup window
+    pub duplicates: u64,
+    /// Client address, from forwarding headers or the socket peer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+    /// Socket peer address, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_addr: Option<SocketAddr>,
+    /// Authenticated identity, if recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Custom connection attributes
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, String>,
+    /// Consumer group, if the connection joined one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Event types the connection may receive, if restricted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_types: Option<EventTypeFilter>,
+    /// Events and bytes sent, events dropped, last event time
+    #[serde(flatten)]
+    pub sent: SendStats,
 }
 
 pub async fn get_stats<S: MessageStorage>(
@@ -244,28 +949,714 @@ pub async fn get_stats<S: MessageStorage>(
         .list_connections()
         .into_iter()
         .map(|c| ConnectionStats {
-            id: c.id.clone(),
-            channel_id: c.channel_id.clone(),
+            id: c.id.to_string(),
+            channel_id: c.channel_id.to_string(),
             connected_at: c.metadata.connected_at.to_rfc3339(),
             is_active: c.is_active(),
+            duplicates: c.duplicates(),
+            client_ip: c.metadata.client_ip.clone(),
+            peer_addr: c.metadata.peer_addr,
+            identity: c.metadata.identity.clone(),
+            attributes: c.metadata.attributes.clone(),
+            group: c.metadata.group.clone(),
+            event_types: c.metadata.event_types.clone(),
+            sent: c.counters().snapshot(),
         })
         .collect();
 
+    let mut tenants: BTreeMap<String, TenantStats> = BTreeMap::new();
+    if let Some(tenancy) = &state.tenancy {
+        for connection in &connections {
+            if let Some(tenant) = tenancy::tenant_of(&connection.channel_id) {
+                tenants.entry(tenant.to_string()).or_default().connections += 1;
+            }
+        }
+        for tenant in tenancy.publishing_tenants() {
+            tenants.entry(tenant).or_default();
+        }
+        for (tenant, stats) in tenants.iter_mut() {
+            stats.published = tenancy.published(tenant);
+        }
+    }
+
     Json(StatsResponse {
         total_connections: connections.len(),
         connections,
+        tenants,
+        channels: state.connection_manager.all_channel_stats(),
+        paused_channels: state.connection_manager.paused_channels(),
+    })
+}
+
+// Channel list endpoint
+#[derive(Serialize)]
+pub struct ChannelsResponse {
+    pub total_channels: usize,
+    /// Channels with subscribers on this instance, busiest first
+    pub channels: Vec<ChannelSummary>,
+}
+
+#[derive(Serialize)]
+pub struct ChannelSummary {
+    pub channel_id: String,
+    pub subscribers: usize,
+    /// Whether a channel policy (exact or prefix) applies
+    pub configured: bool,
+    /// Send totals of the channel's subscribers
+    #[serde(flatten)]
+    pub sent: SendStats,
+}
+
+pub async fn list_channels<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+) -> Json<ChannelsResponse> {
+    let manager = &state.connection_manager;
+    let configs = manager.channel_configs();
+    let mut channels: Vec<ChannelSummary> = manager
+        .channel_ids()
+        .into_iter()
+        .map(|channel_id| ChannelSummary {
+            subscribers: manager.channel_connection_count(&channel_id),
+            configured: configs.resolve(&channel_id) != ChannelConfig::default(),
+            sent: manager.channel_stats(&channel_id).unwrap_or_default(),
+            channel_id,
+        })
+        .collect();
+    channels.sort_by(|a, b| {
+        b.subscribers
+            .cmp(&a.subscribers)
+            .then_with(|| a.channel_id.cmp(&b.channel_id))
+    });
+
+    Json(ChannelsResponse {
+        total_channels: channels.len(),
+        channels,
+    })
+}
+
+/// Default and maximum number of messages returned by the history endpoint
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+const MAX_HISTORY_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub limit: Option<usize>,
+}
+
+// Message history endpoint
+#[derive(Serialize)]
+pub struct ChannelMessagesResponse {
+    pub channel_id: String,
+    pub subscribers: usize,
+    /// Newest stored messages, oldest first
+    pub messages: Vec<StoredMessage>,
+}
+
+#[derive(Serialize)]
+pub struct StoredMessage {
+    pub stream_id: Option<String>,
+    /// When the message was stored, if the stream ID encodes it
+    pub timestamp: Option<String>,
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Payload as sent in the SSE `data` field
+    pub data: String,
+}
+
+pub async fn get_channel_messages<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<ChannelMessagesResponse> {
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT);
+    let messages = state
+        .storage
+        .recent_messages(&channel_id, limit)
+        .await
+        .into_iter()
+        .map(|event| StoredMessage {
+            timestamp: event
+                .stream_id
+                .as_deref()
+                .and_then(stream_id_timestamp)
+                .map(|at| at.to_rfc3339()),
+            stream_id: event.stream_id,
+            data: event.data.to_string(),
+            event_type: event.event_type,
+            id: event.id,
+        })
+        .collect();
+
+    Json(ChannelMessagesResponse {
+        subscribers: state.connection_manager.channel_connection_count(&channel_id),
+        channel_id,
+        messages,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct PurgeQuery {
+    /// Stream ID or RFC 3339 time; only older events are deleted
+    pub before: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PurgeResponse {
+    pub channel_id: String,
+    /// Events deleted
+    pub purged: usize,
+}
+
+// Purge endpoint: everything stored for the channel, or only events before a cutoff
+pub async fn purge_channel_messages<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    Query(query): Query<PurgeQuery>,
+) -> Result<Json<PurgeResponse>, (StatusCode, String)> {
+    let purged = match query.before {
+        Some(before) => {
+            let before: PurgeBefore = before.parse().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            state.storage.purge_before(&channel_id, &before).await
+        }
+        None => state.storage.purge(&channel_id).await,
+    };
+    tracing::info!(channel_id = %channel_id, purged, "Purged stored messages");
+    Ok(Json(PurgeResponse { channel_id, purged }))
+}
+
+// History export: the channel's stored events as NDJSON, oldest first
+pub async fn export_channel<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+) -> impl IntoResponse {
+    let events = storage::export_history(&state.storage, &channel_id).await;
+    tracing::info!(channel_id = %channel_id, events = events.len(), "Exported channel history");
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        storage::to_ndjson(&events),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    /// First day, `YYYY-MM-DD` (default: 29 days before `to`)
+    pub from: Option<String>,
+    /// Last day, `YYYY-MM-DD` (default: today, UTC)
+    pub to: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UsageResponse {
+    pub channel_id: String,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    /// Days with usage, oldest first
+    pub days: Vec<DailyUsage>,
+}
+
+/// Longest range `GET /api/channels/{id}/stats` returns, in days
+const MAX_USAGE_DAYS: i64 = 366;
+
+// Channel usage: stored daily totals between two dates
+pub async fn get_channel_usage<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<UsageResponse>, (StatusCode, String)> {
+    let bad_request = |e| (StatusCode::BAD_REQUEST, e);
+    let to = match &query.to {
+        Some(to) => channel_usage::parse_date(to).map_err(bad_request)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let from = match &query.from {
+        Some(from) => channel_usage::parse_date(from).map_err(bad_request)?,
+        None => to - chrono::Days::new(29),
+    };
+    if from > to {
+        return Err(bad_request("from is after to".to_string()));
+    }
+    if (to - from).num_days() >= MAX_USAGE_DAYS {
+        return Err(bad_request(format!("at most {} days per request", MAX_USAGE_DAYS)));
+    }
+    let days = state.storage.channel_usage(&channel_id, from, to).await;
+    Ok(Json(UsageResponse { channel_id, from, to, days }))
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportResponse {
+    pub channel_id: String,
+    /// Events stored
+    pub imported: usize,
+    /// Events skipped as not newer than the channel's history
+    pub skipped: usize,
+}
+
+// History import: NDJSON from the export endpoint, stored under the original stream IDs
+pub async fn import_channel<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    body: String,
+) -> Result<Json<ImportResponse>, (StatusCode, String)> {
+    let events = storage::from_ndjson(&body).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let total = events.len();
+    let imported = storage::import_history(&state.storage, &channel_id, events).await;
+    tracing::info!(channel_id = %channel_id, imported, "Imported channel history");
+    Ok(Json(ImportResponse {
+        channel_id,
+        imported,
+        skipped: total - imported,
+    }))
+}
+
+/// Readiness of the gateway's message source
+#[derive(Serialize)]
+pub struct ReadyResponse {
+    pub ready: bool,
+    pub source: &'static str,
+    /// Current supervisor state, e.g. `{"state": "restarting", "attempt": 2, "error": "..."}`
+    #[serde(flatten)]
+    pub state: SourceState,
+}
+
+// Readiness endpoint: 503 while the source is restarting or has failed
+pub async fn ready(State(health): State<SourceHealth>) -> impl IntoResponse {
+    let state = health.state();
+    let ready = state.is_ready();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(ReadyResponse { ready, source: health.name(), state }))
+}
+
+// Metrics endpoint
+pub async fn get_metrics<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+) -> Json<MetricsSnapshot> {
+    Json(state.connection_manager.metrics().snapshot())
+}
+
+/// Replay cursor of a channel
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CursorResponse {
+    pub channel_id: String,
+    /// Newest stored stream ID, usable as `Last-Event-ID`; null if none is stored
+    pub cursor: Option<String>,
+}
+
+/// Authorize a read of `channel_id` like a subscription, returning the channel
+/// to read (scoped to the caller's tenant if tenancy is enabled)
+async fn authorize_channel<S: MessageStorage>(
+    state: &GatewayState<S>,
+    method: Method,
+    uri: axum::http::Uri,
+    headers: &axum::http::HeaderMap,
+    peer: Option<SocketAddr>,
+    channel_id: &str,
+) -> Result<String, axum::response::Response> {
+    let channel_id = &*state.channel_rewrites.rewrite(channel_id);
+    if state.auth.is_none() && state.tenancy.is_none() && state.identity_channel.is_none() {
+        return Ok(channel_id.to_string());
+    }
+    let auth_request = AuthRequest {
+        method,
+        uri,
+        headers: headers.clone(),
+        channel_id: channel_id.to_string(),
+        client_ip: client_ip(state, headers, peer),
+    };
+    if let Some(auth_fn) = &state.auth {
+        if let Some(response) = auth_fn(auth_request.clone()).await {
+            return Err(response);
+        }
+    }
+    let channel_id = match &state.identity_channel {
+        Some(identity_channel) => {
+            let identity = state.identity.as_ref().and_then(|identity_fn| identity_fn(&auth_request));
+            identity_channel
+                .resolve(identity.as_deref(), channel_id)
+                .map_err(IntoResponse::into_response)?
+        }
+        None => channel_id.to_string(),
+    };
+    tenant_channel(state, Some(&auth_request), channel_id).map_err(IntoResponse::into_response)
+}
+
+/// Run the admin auth hook, or the auth callback without one, before a
+/// management endpoint
+pub(crate) async fn authorize_admin<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    OriginalUri(uri): OriginalUri,
+    PeerAddr(peer): PeerAddr,
+    path: Option<Path<HashMap<String, String>>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(auth_fn) = state.admin_auth.as_ref().or(state.auth.as_ref()) else {
+        return next.run(request).await;
+    };
+    let channel_id = path
+        .and_then(|Path(mut params)| params.remove("channel_id"))
+        .unwrap_or_default();
+    let auth_request = AuthRequest {
+        method: request.method().clone(),
+        uri,
+        headers: request.headers().clone(),
+        client_ip: client_ip(&state, request.headers(), peer),
+        channel_id,
+    };
+    if let Some(response) = auth_fn(auth_request).await {
+        tracing::warn!(path = %request.uri().path(), "Admin request denied");
+        return response;
+    }
+    next.run(request).await
+}
+
+// Cursor endpoint, authorized like a subscription to the channel
+pub async fn get_cursor<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    Path(channel_id): Path<String>,
+    headers: axum::http::HeaderMap,
+    PeerAddr(peer): PeerAddr,
+) -> axum::response::Response {
+    let scoped = match authorize_channel(&state, method, uri, &headers, peer, &channel_id).await {
+        Ok(scoped) => scoped,
+        Err(response) => return response,
+    };
+
+    let cursor = state.storage.latest_id(&scoped).await;
+    Json(CursorResponse { channel_id, cursor }).into_response()
+}
+
+/// Client acknowledgement of processed events
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AckRequest {
+    pub channel_id: String,
+    /// Stable ID of the consumer (e.g. a browser tab or device)
+    pub consumer_id: String,
+    /// Last stream ID the consumer processed
+    pub stream_id: String,
+}
+
+/// Recorded acknowledgement
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AckResponse {
+    pub channel_id: String,
+    pub consumer_id: String,
+    pub cursor: String,
+    /// Stored events after the cursor
+    pub lag: u64,
+}
+
+// Ack endpoint, authorized like a subscription to the channel
+pub async fn ack<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: axum::http::HeaderMap,
+    PeerAddr(peer): PeerAddr,
+    Json(req): Json<AckRequest>,
+) -> axum::response::Response {
+    if req.consumer_id.is_empty() || req.stream_id.is_empty() {
+        return (StatusCode::BAD_REQUEST, "consumer_id and stream_id are required").into_response();
+    }
+    let scoped = match authorize_channel(&state, method, uri, &headers, peer, &req.channel_id).await {
+        Ok(scoped) => scoped,
+        Err(response) => return response,
+    };
+
+    state.storage.save_cursor(&scoped, &req.consumer_id, &req.stream_id).await;
+    let lag = state
+        .storage
+        .get_messages_after(&scoped, Some(&req.stream_id))
+        .await
+        .len() as u64;
+    let metrics = state.connection_manager.metrics();
+    GatewayMetrics::incr(&metrics.acks);
+    metrics.record_lag(&scoped, &req.consumer_id, lag);
+
+    Json(AckResponse {
+        channel_id: req.channel_id,
+        consumer_id: req.consumer_id,
+        cursor: req.stream_id,
+        lag,
+    })
+    .into_response()
+}
+
+// Presence endpoint
+#[derive(Serialize)]
+pub struct PresenceResponse {
+    pub channel_id: String,
+    /// The channel has subscribers on any instance
+    pub online: bool,
+    /// Subscriber count across instances
+    pub connections: usize,
+    /// Subscribers per instance
+    pub instances: Vec<InstancePresence>,
+}
+
+// Presence endpoint, authorized like a subscription to the channel
+//
+// Cluster-wide when cluster mode is enabled, otherwise this instance only.
+pub async fn get_presence<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    Path(channel_id): Path<String>,
+    headers: axum::http::HeaderMap,
+    PeerAddr(peer): PeerAddr,
+) -> axum::response::Response {
+    let scoped = match authorize_channel(&state, method, uri, &headers, peer, &channel_id).await {
+        Ok(scoped) => scoped,
+        Err(response) => return response,
+    };
+
+    let instances = match &state.cluster {
+        Some(cluster) => match cluster.presence(&state.connection_manager, &scoped).await {
+            Ok(instances) => instances,
+            Err(e) => {
+                tracing::warn!(error = %e, channel_id = %channel_id, "Presence lookup failed");
+                return (StatusCode::SERVICE_UNAVAILABLE, "Presence lookup failed").into_response();
+            }
+        },
+        None => {
+            let local = InstancePresence::local(&state.connection_manager, &scoped);
+            if local.connections.is_empty() {
+                Vec::new()
+            } else {
+                vec![local]
+            }
+        }
+    };
+
+    Json(PresenceResponse {
+        channel_id,
+        online: !instances.is_empty(),
+        connections: instances.iter().map(|i| i.connections.len()).sum(),
+        instances,
     })
+    .into_response()
+}
+
+// Cluster overview endpoint
+#[derive(Serialize)]
+pub struct ClusterResponse {
+    /// The instance serving this request
+    pub instance_id: String,
+    /// Coordinator name
+    pub coordinator: &'static str,
+    pub instances: Vec<ClusterInstance>,
+    /// Channel -> owning instances; null if the coordinator can't list them
+    pub channels: Option<BTreeMap<String, Vec<String>>>,
+    /// Why `channels` is null
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels_error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ClusterInstance {
+    #[serde(flatten)]
+    pub info: InstanceInfo,
+    /// Whether this is the instance serving the request
+    pub local: bool,
+    /// Channels the instance owns, if the channel listing is available
+    pub channels: Option<usize>,
+}
+
+pub async fn get_cluster<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+) -> axum::response::Response {
+    let Some(cluster) = &state.cluster else {
+        return (StatusCode::NOT_FOUND, "Cluster mode is not enabled").into_response();
+    };
+    let coordinator = cluster.coordinator();
+    let instances = match coordinator.instances().await {
+        Ok(instances) => instances,
+        Err(e) => {
+            tracing::warn!(error = %e, "Instance lookup failed");
+            return (StatusCode::SERVICE_UNAVAILABLE, "Instance lookup failed").into_response();
+        }
+    };
+    let (channels, channels_error) = match coordinator.channels().await {
+        Ok(channels) => (Some(channels.into_iter().collect::<BTreeMap<_, _>>()), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let owned = |id: &str| {
+        channels
+            .as_ref()
+            .map(|channels| channels.values().filter(|owners| owners.iter().any(|o| o == id)).count())
+    };
+    let local_id = cluster.instance_id();
+    let mut instances: Vec<ClusterInstance> = instances
+        .into_iter()
+        .map(|mut info| {
+            let local = info.id == local_id;
+            if local {
+                info.refresh_load(&state.connection_manager);
+            }
+            ClusterInstance {
+                channels: owned(&info.id),
+                info,
+                local,
+            }
+        })
+        .collect();
+    instances.sort_by(|a, b| a.info.id.cmp(&b.info.id));
+
+    Json(ClusterResponse {
+        instance_id: local_id.to_string(),
+        coordinator: coordinator.name(),
+        instances,
+        channels,
+        channels_error,
+    })
+    .into_response()
+}
+
+// Channel ownership endpoint
+#[derive(Serialize)]
+pub struct ChannelOwnersResponse {
+    pub channel_id: String,
+    pub online: bool,
+    /// Instances holding subscribers on the channel
+    pub owners: Vec<InstanceInfo>,
+    /// Instance the channel router assigns the channel to, if routing is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assigned: Option<InstanceInfo>,
+}
+
+pub async fn get_channel_owners<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+) -> axum::response::Response {
+    let Some(cluster) = &state.cluster else {
+        return (StatusCode::NOT_FOUND, "Cluster mode is not enabled").into_response();
+    };
+    let coordinator = cluster.coordinator();
+    let lookup = async {
+        let owners = coordinator.channel_owners(&channel_id).await?;
+        let instances = coordinator.instances().await?;
+        anyhow::Ok((owners, instances))
+    };
+    let (owners, instances) = match lookup.await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!(error = %e, channel_id = %channel_id, "Ownership lookup failed");
+            return (StatusCode::SERVICE_UNAVAILABLE, "Ownership lookup failed").into_response();
+        }
+    };
+
+    let owners: Vec<InstanceInfo> = owners
+        .into_iter()
+        .map(|id| {
+            instances.iter().find(|i| i.id == id).cloned().unwrap_or(InstanceInfo {
+                id,
+                address: None,
+                last_seen: 0,
+                connections: None,
+                capacity: None,
+                cpu: None,
+            })
+        })
+        .collect();
+    Json(ChannelOwnersResponse {
+        online: !owners.is_empty(),
+        assigned: state.channel_router.as_ref().and_then(|router| router.owner_of(&channel_id)),
+        owners,
+        channel_id,
+    })
+    .into_response()
+}
+
+// Load-aware routing endpoint
+#[derive(Deserialize)]
+pub struct RouteQuery {
+    pub channel_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RouteResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// URL of the chosen instance
+    pub url: Option<String>,
+    /// The chosen instance and its advertised load
+    pub instance: InstanceInfo,
+}
+
+/// Pick the instance a new subscriber should connect to
+///
+/// A channel router's assignment wins. Otherwise instances with an address
+/// and spare capacity are eligible, and the least loaded one already holding
+/// the channel's subscribers is preferred over the least loaded overall.
+pub async fn route<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Query(query): Query<RouteQuery>,
+) -> axum::response::Response {
+    let Some(cluster) = &state.cluster else {
+        return (StatusCode::NOT_FOUND, "Cluster mode is not enabled").into_response();
+    };
+    let channel_id = query.channel_id.filter(|id| !id.is_empty());
+    let assigned = match (&state.channel_router, &channel_id) {
+        (Some(router), Some(channel_id)) => router.owner_of(channel_id),
+        _ => None,
+    };
+    let chosen = match assigned {
+        // Report the assigned instance's current load rather than the ring's copy
+        Some(assigned) => cluster
+            .instances(&state.connection_manager)
+            .await
+            .map(|instances| Some(instances.into_iter().find(|i| i.id == assigned.id).unwrap_or(assigned))),
+        None => cluster.route(&state.connection_manager, channel_id.as_deref()).await,
+    };
+    match chosen {
+        Ok(Some(instance)) => Json(RouteResponse {
+            channel_id,
+            url: instance.base_url(),
+            instance,
+        })
+        .into_response(),
+        Ok(None) => (StatusCode::SERVICE_UNAVAILABLE, "No instance has spare capacity").into_response(),
+        Err(e) => {
+            tracing::warn!(error = %e, channel_id = ?channel_id, "Route lookup failed");
+            (StatusCode::SERVICE_UNAVAILABLE, "Route lookup failed").into_response()
+        }
+    }
 }
 
 // Send message endpoint
 #[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SendMessageRequest {
     pub channel_id: Option<String>,
+    /// Send to connections with this attribute instead of a channel
+    #[serde(default)]
+    pub attribute: Option<AttributeFilter>,
     pub event_type: String,
     pub data: serde_json::Value,
+    /// Delivery priority (`high`, `normal` or `low`)
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+/// Matches connections whose attribute `key` equals `value`
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AttributeFilter {
+    pub key: String,
+    pub value: String,
 }
 
 #[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SendMessageResponse {
     pub success: bool,
     pub sent_count: usize,
@@ -275,41 +1666,204 @@ pub async fn send_message<S: MessageStorage>(
     State(state): State<GatewayState<S>>,
     Json(req): Json<SendMessageRequest>,
 ) -> impl IntoResponse {
-    let mut event = SseEvent::new(&req.event_type, req.data);
-
-    let sent_count = match &req.channel_id {
-        Some(channel_id) if !channel_id.is_empty() => {
-            // Generate ID first
-            let stream_id = state.storage.generate_id();
-            if !stream_id.is_empty() {
-                event.stream_id = Some(stream_id.clone());
-            }
+    let response = |status: StatusCode, sent_count: usize| {
+        (
+            status,
+            Json(SendMessageResponse {
+                success: sent_count > 0,
+                sent_count,
+            }),
+        )
+            .into_response()
+    };
+
+    // Attribute targets aren't channels, so they skip the dispatcher
+    if let Some(filter) = &req.attribute {
+        let size = req.data.to_string().len();
+        if let Err((status, retry_after)) = throttle(&state, req.channel_id.as_deref(), size) {
+            return with_retry_after(response(status, 0), retry_after);
+        }
+        let event = SseEvent::new(&req.event_type, req.data).with_priority(req.priority);
+        let sent_count = state.connection_manager.send_to_attr(&filter.key, &filter.value, event).await;
+        GatewayMetrics::incr(&state.connection_manager.metrics().messages_dispatched);
+        return response(StatusCode::OK, sent_count);
+    }
 
-            // Send to clients immediately
-            let sent = state.connection_manager.send_to_channel(channel_id, event.clone()).await;
+    let mut msg = IncomingMessage::new(req.event_type, req.data.to_string()).with_priority(req.priority);
+    msg.channel_id = req.channel_id.filter(|channel_id| !channel_id.is_empty()).map(Into::into);
+    match state.publisher.publish(msg).await {
+        Ok(report) if report.filtered => response(StatusCode::UNPROCESSABLE_ENTITY, 0),
+        Ok(report) => response(StatusCode::OK, report.delivered),
+        Err(DispatchError::Throttled { retry_after }) => {
+            with_retry_after(response(StatusCode::TOO_MANY_REQUESTS, 0), retry_after)
+        }
+        Err(DispatchError::Invalid(_)) => response(StatusCode::UNPROCESSABLE_ENTITY, 0),
+        Err(DispatchError::TooLarge { .. }) => response(StatusCode::PAYLOAD_TOO_LARGE, 0),
+        Err(DispatchError::ShuttingDown) => response(StatusCode::SERVICE_UNAVAILABLE, 0),
+    }
+}
 
-            // Store in background (fire-and-forget)
-            let storage = state.storage.clone();
-            let channel_id = channel_id.clone();
-            tokio::spawn(async move {
-                storage.store(&channel_id, &stream_id, &event).await;
-            });
+// Admin broadcast endpoint
+/// Which connections an admin broadcast reaches
+///
+/// `"all"`, `{"channel": "acme/*"}` (a channel ID or a prefix ending in
+/// `*`), `{"attribute": {"key": "tenant", "value": "acme"}}` or
+/// `{"identity": "u1"}`.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastTarget {
+    All,
+    Channel(String),
+    Attribute(AttributeFilter),
+    Identity(String),
+}
 
-            sent
+impl BroadcastTarget {
+    /// Whether `connection` is targeted
+    pub fn matches(&self, connection: &SseConnection) -> bool {
+        match self {
+            Self::All => true,
+            Self::Channel(pattern) => match pattern.strip_suffix('*') {
+                Some(prefix) => connection.channel_id.starts_with(prefix),
+                None => *connection.channel_id == **pattern,
+            },
+            Self::Attribute(filter) => connection.attribute(&filter.key) == Some(filter.value.as_str()),
+            Self::Identity(identity) => connection.metadata.identity.as_ref() == Some(identity),
         }
-        _ => state.connection_manager.broadcast(event).await,
-    };
+    }
+}
 
-    (
-        StatusCode::OK,
-        Json(SendMessageResponse {
-            success: sent_count > 0,
-            sent_count,
-        }),
-    )
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BroadcastRequest {
+    pub target: BroadcastTarget,
+    pub event_type: String,
+    pub data: serde_json::Value,
+    /// Delivery priority (`high`, `normal` or `low`)
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+/// `POST /admin/broadcast`: send an event to the targeted local connections
+pub async fn admin_broadcast<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Json(req): Json<BroadcastRequest>,
+) -> Json<SendMessageResponse> {
+    let event = SseEvent::new(&req.event_type, req.data).with_priority(req.priority);
+    let target = req.target;
+    let sent_count = state
+        .connection_manager
+        .send_where(|connection| target.matches(connection), event)
+        .await;
+    GatewayMetrics::incr(&state.connection_manager.metrics().messages_dispatched);
+    tracing::info!(selector = ?target, event_type = %req.event_type, sent_count, "Admin broadcast");
+    Json(SendMessageResponse {
+        success: sent_count > 0,
+        sent_count,
+    })
+}
+
+// Channel config endpoints
+#[derive(Serialize)]
+pub struct ChannelConfigResponse {
+    pub channel_id: String,
+    /// Config registered under exactly this key, if any
+    pub config: Option<ChannelConfig>,
+    /// Config in effect for a channel with this ID (after prefix matching)
+    pub effective: ChannelConfig,
+}
+
+pub async fn get_channel_config<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+) -> Json<ChannelConfigResponse> {
+    let configs = state.connection_manager.channel_configs();
+    Json(ChannelConfigResponse {
+        config: configs.get(&channel_id),
+        effective: configs.resolve(&channel_id),
+        channel_id,
+    })
+}
+
+pub async fn put_channel_config<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    Json(config): Json<ChannelConfig>,
+) -> Json<ChannelConfigResponse> {
+    tracing::info!(channel_id = %channel_id, ?config, "Channel config updated");
+    let configs = state.connection_manager.channel_configs();
+    configs.set(channel_id.clone(), config.clone());
+    Json(ChannelConfigResponse {
+        effective: configs.resolve(&channel_id),
+        config: Some(config),
+        channel_id,
+    })
+}
+
+// Channel pause endpoints
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PauseRequest {
+    #[serde(default)]
+    pub policy: PausePolicy,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResumeResponse {
+    pub channel_id: String,
+    /// Buffered events sent on by this instance
+    pub flushed: usize,
+}
+
+/// Replicate a pause change to the cluster, if there is one
+async fn replicate_pause<S: MessageStorage>(
+    state: &GatewayState<S>,
+    channel_id: &str,
+    pause: Option<&ChannelPause>,
+) -> Result<(), axum::response::Response> {
+    let Some(cluster) = &state.cluster else { return Ok(()) };
+    cluster
+        .coordinator()
+        .set_channel_pause(channel_id, pause)
+        .await
+        .map_err(|e| {
+            tracing::warn!(error = %e, channel_id, "Failed to replicate channel pause");
+            (StatusCode::SERVICE_UNAVAILABLE, "Failed to replicate channel pause").into_response()
+        })
+}
+
+/// `POST /api/channels/{id}/pause`: pause live delivery to the channel
+pub async fn pause_channel<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+    Json(req): Json<PauseRequest>,
+) -> Result<Json<ChannelPause>, axum::response::Response> {
+    let mut pause = ChannelPause::new(req.policy);
+    pause.reason = req.reason;
+    replicate_pause(&state, &channel_id, Some(&pause)).await?;
+    state.connection_manager.pause_channel(&channel_id, pause.clone());
+    Ok(Json(pause))
 }
 
-// Dashboard
-pub async fn dashboard_page() -> Html<&'static str> {
-    Html(include_str!("dashboard.html"))
+/// `POST /api/channels/{id}/resume`: resume live delivery to the channel
+pub async fn resume_channel<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+) -> Result<Json<ResumeResponse>, axum::response::Response> {
+    replicate_pause(&state, &channel_id, None).await?;
+    let flushed = state.connection_manager.resume_channel(&channel_id).await.unwrap_or(0);
+    Ok(Json(ResumeResponse { channel_id, flushed }))
+}
+
+pub async fn delete_channel_config<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    Path(channel_id): Path<String>,
+) -> StatusCode {
+    match state.connection_manager.channel_configs().remove(&channel_id) {
+        Some(_) => StatusCode::NO_CONTENT,
+        None => StatusCode::NOT_FOUND,
+    }
 }
@@ -0,0 +1,164 @@
+//! Heartbeat format
+//!
+//! Heartbeats keep idle streams alive through proxies and let clients detect
+//! dead connections. By default every connection receives a
+//! `heartbeat` event with a `{"ts":<unix millis>}` payload on each tick.
+
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::stream::PollNext;
+use futures::Stream;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
+use tokio_stream::StreamExt;
+
+use crate::event::SseEvent;
+
+/// Placeholder replaced with the tick's Unix timestamp in milliseconds
+pub const TS_PLACEHOLDER: &str = "{ts}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Style {
+    Event { name: String, template: String },
+    Comment(String),
+}
+
+/// How heartbeats are sent
+///
+/// ```rust,ignore
+/// // `event: ping` with a custom payload, only to connections idle for a full interval
+/// Heartbeat::event("ping").template(r#"{"time":{ts}}"#).idle_only(true)
+///
+/// // An SSE comment line (`: keepalive`), invisible to EventSource listeners
+/// Heartbeat::comment("keepalive")
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heartbeat {
+    style: Style,
+    idle_only: bool,
+    pub(crate) interval: Duration,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::event("heartbeat")
+    }
+}
+
+impl Heartbeat {
+    /// Named event with the default `{"ts":{ts}}` payload
+    pub fn event(name: impl Into<String>) -> Self {
+        Self {
+            style: Style::Event {
+                name: name.into(),
+                template: r#"{"ts":{ts}}"#.to_string(),
+            },
+            idle_only: false,
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    /// SSE comment line (`: text`) instead of an event
+    ///
+    /// Over WebSocket this becomes a ping frame; gRPC streams skip it.
+    pub fn comment(text: impl Into<String>) -> Self {
+        Self {
+            style: Style::Comment(text.into()),
+            idle_only: false,
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    /// Payload template for event heartbeats; `{ts}` is replaced with the timestamp
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        if let Style::Event { template: t, .. } = &mut self.style {
+            *t = template.into();
+        }
+        self
+    }
+
+    /// Only send heartbeats to connections that received no event during
+    /// the last heartbeat interval
+    pub fn idle_only(mut self, enable: bool) -> Self {
+        self.idle_only = enable;
+        self
+    }
+
+    /// Event type of the heartbeats, or `None` if they are comments
+    pub(crate) fn event_name(&self) -> Option<&str> {
+        match &self.style {
+            Style::Event { name, .. } => Some(name),
+            Style::Comment(_) => None,
+        }
+    }
+
+    /// Render a heartbeat for the tick at `ts`
+    pub(crate) fn render(&self, ts: i64) -> Outgoing {
+        match &self.style {
+            Style::Event { name, template } => {
+                let data = template.replace(TS_PLACEHOLDER, &ts.to_string());
+                Outgoing::Event(SseEvent::raw(name, data))
+            }
+            Style::Comment(text) => Outgoing::Comment(text.clone()),
+        }
+    }
+
+    /// Merge a connection's events with heartbeat ticks
+    ///
+    /// Events waiting in `urgent` are always written before those in `receiver`.
+    pub(crate) fn live_stream(
+        &self,
+        receiver: mpsc::Receiver<SseEvent>,
+        urgent: Option<mpsc::Receiver<SseEvent>>,
+        ticks: broadcast::Receiver<i64>,
+    ) -> LiveStream {
+        // The stream ends once the connection's event channel closes
+        let regular = ReceiverStream::new(receiver)
+            .map(Item::Event)
+            .chain(futures::stream::once(async { Item::Closed }));
+        let urgent = futures::StreamExt::flat_map(futures::stream::iter(urgent), ReceiverStream::new)
+            .map(Item::Event);
+        let events = futures::stream::select_with_strategy(urgent, regular, |_: &mut ()| PollNext::Left);
+        let ticks = BroadcastStream::new(ticks)
+            .filter_map(|r| r.ok())
+            .map(Item::Tick);
+
+        let heartbeat = self.clone();
+        // Heartbeats within this long of the last event are skipped in idle-only mode
+        let idle_after = self.interval.saturating_sub(self.interval / 10);
+        let mut last_event = Instant::now();
+        let stream = futures::stream::select(events, ticks)
+            .take_while(|item| !matches!(item, Item::Closed))
+            .filter_map(move |item| match item {
+                // Went stale while queued behind a slow client
+                Item::Event(event) if event.is_expired() => None,
+                Item::Event(event) => {
+                    last_event = Instant::now();
+                    Some(Outgoing::Event(event))
+                }
+                Item::Tick(_) if heartbeat.idle_only && last_event.elapsed() < idle_after => None,
+                Item::Tick(ts) => Some(heartbeat.render(ts)),
+                Item::Closed => None,
+            });
+        Box::pin(stream)
+    }
+}
+
+enum Item {
+    Event(SseEvent),
+    Tick(i64),
+    Closed,
+}
+
+/// Something to write to a subscriber
+#[derive(Debug, Clone)]
+pub(crate) enum Outgoing {
+    Event(SseEvent),
+    /// Transport-level keep-alive with no event semantics
+    Comment(String),
+}
+
+/// Live events and heartbeats for one connection
+pub(crate) type LiveStream = Pin<Box<dyn Stream<Item = Outgoing> + Send>>;
@@ -0,0 +1,330 @@
+//! HTTP polling source
+//!
+//! Bridges REST feeds that can't push into SSE: an [`HttpPollSource`] GETs a
+//! URL on an interval and emits the items that weren't in the previous
+//! response. Conditional requests (`ETag`, `Last-Modified`) keep unchanged
+//! feeds cheap, and JSONPath expressions pick the items and their fields.
+//!
+//! ```rust,ignore
+//! use sse_gateway::http_poll::HttpPollSource;
+//!
+//! // {"orders": [{"id": 7, "customer": "user-1", "status": "shipped"}, ...]}
+//! let source = HttpPollSource::new("https://legacy.internal/api/orders")
+//!     .interval(Duration::from_secs(5))
+//!     .items("$.orders[*]")
+//!     .id("$.id")
+//!     .channel("$.customer")
+//!     .default_event_type("order.updated");
+//!
+//! Gateway::builder().source(source)
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use tokio_util::sync::CancellationToken;
+
+use crate::manager::ConnectionManager;
+use crate::source::{DispatchError, IncomingMessage, MessageHandler, MessageSource};
+
+/// Source emitting new items of a polled JSON feed
+///
+/// Each item is keyed by its [`id`](Self::id) if set, or by its content
+/// otherwise; an item is emitted when its key wasn't in the previous
+/// response. Without [`items`](Self::items) the whole response is one item,
+/// so every change to the document is emitted.
+pub struct HttpPollSource {
+    url: String,
+    interval: Duration,
+    timeout: Duration,
+    headers: Vec<(String, String)>,
+    items: Option<JsonPath>,
+    channel: Option<JsonPath>,
+    event_type: Option<JsonPath>,
+    id: Option<JsonPath>,
+    data: Option<JsonPath>,
+    default_channel: Option<String>,
+    default_event_type: String,
+    skip_initial: bool,
+}
+
+/// Parse a JSONPath given to a builder method
+fn path(kind: &str, expression: &str) -> JsonPath {
+    JsonPath::parse(expression).unwrap_or_else(|e| panic!("invalid {} JSONPath '{}': {}", kind, expression, e))
+}
+
+impl HttpPollSource {
+    /// Poll `url` every 10 seconds
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+            headers: Vec::new(),
+            items: None,
+            channel: None,
+            event_type: None,
+            id: None,
+            data: None,
+            default_channel: None,
+            default_event_type: "message".to_string(),
+            skip_initial: false,
+        }
+    }
+
+    /// Time between polls
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Request timeout (default: 30s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send `name: value` with every request, e.g. an `Authorization` header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// JSONPath selecting the items of a response, e.g. `$.data[*]`
+    ///
+    /// # Panics
+    ///
+    /// If `expression` is not a valid JSONPath.
+    pub fn items(mut self, expression: &str) -> Self {
+        self.items = Some(path("items", expression));
+        self
+    }
+
+    /// JSONPath of an item's target channel; items without one go to the
+    /// [default channel](Self::default_channel)
+    ///
+    /// # Panics
+    ///
+    /// If `expression` is not a valid JSONPath.
+    pub fn channel(mut self, expression: &str) -> Self {
+        self.channel = Some(path("channel", expression));
+        self
+    }
+
+    /// JSONPath of an item's event type
+    ///
+    /// # Panics
+    ///
+    /// If `expression` is not a valid JSONPath.
+    pub fn event_type(mut self, expression: &str) -> Self {
+        self.event_type = Some(path("event type", expression));
+        self
+    }
+
+    /// JSONPath of an item's ID, used as the message ID and to recognize
+    /// items already emitted even if their content changes
+    ///
+    /// # Panics
+    ///
+    /// If `expression` is not a valid JSONPath.
+    pub fn id(mut self, expression: &str) -> Self {
+        self.id = Some(path("id", expression));
+        self
+    }
+
+    /// JSONPath of an item's payload (default: the whole item)
+    ///
+    /// # Panics
+    ///
+    /// If `expression` is not a valid JSONPath.
+    pub fn data(mut self, expression: &str) -> Self {
+        self.data = Some(path("data", expression));
+        self
+    }
+
+    /// Channel for items without one (default: broadcast)
+    pub fn default_channel(mut self, channel: impl Into<String>) -> Self {
+        self.default_channel = Some(channel.into());
+        self
+    }
+
+    /// Event type for items without one (default: `message`)
+    pub fn default_event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.default_event_type = event_type.into();
+        self
+    }
+
+    /// Don't emit the items of the first response, only later changes
+    pub fn skip_initial(mut self, skip: bool) -> Self {
+        self.skip_initial = skip;
+        self
+    }
+
+    /// Key identifying `item` across polls
+    fn key(&self, item: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self.id.as_ref().and_then(|id| field(id, item)) {
+            Some(id) => ("id", id).hash(&mut hasher),
+            None => ("item", item.to_string()).hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+
+    fn to_incoming(&self, item: &Value) -> IncomingMessage {
+        let data = match self.data.as_ref() {
+            Some(data) => data.query(item).first().map(as_string).unwrap_or_default(),
+            None => as_string(item),
+        };
+        let event_type = self
+            .event_type
+            .as_ref()
+            .and_then(|path| field(path, item))
+            .unwrap_or_else(|| self.default_event_type.clone());
+        IncomingMessage {
+            channel_id: self
+                .channel
+                .as_ref()
+                .and_then(|path| field(path, item))
+                .or_else(|| self.default_channel.clone())
+                .map(Into::into),
+            id: self.id.as_ref().and_then(|path| field(path, item)),
+            ..IncomingMessage::new(event_type, data)
+        }
+    }
+}
+
+/// First match of `path` in `item`, as a string
+fn field(path: &JsonPath, item: &Value) -> Option<String> {
+    path.query(item)
+        .first()
+        .filter(|value| !value.is_null())
+        .map(as_string)
+}
+
+/// Strings as-is, other JSON serialized
+fn as_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// What is remembered between polls
+#[derive(Default)]
+struct PollState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Keys of the previous response's items
+    seen: HashSet<u64>,
+    polled: bool,
+}
+
+impl HttpPollSource {
+    /// Fetch the feed once and emit new items
+    async fn poll(
+        &self,
+        client: &reqwest::Client,
+        state: &mut PollState,
+        handler: &MessageHandler,
+    ) -> anyhow::Result<usize> {
+        let mut request = client.get(&self.url);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(etag) = &state.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(0);
+        }
+        let response = response.error_for_status()?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let body: Value = serde_json::from_slice(&response.bytes().await?)?;
+
+        let items: Vec<&Value> = match &self.items {
+            Some(items) => items.query(&body).all(),
+            None => vec![&body],
+        };
+        let emit = state.polled || !self.skip_initial;
+        let mut seen = HashSet::with_capacity(items.len());
+        let mut emitted = 0;
+        let mut failed = false;
+        for item in items {
+            let key = self.key(item);
+            if !seen.insert(key) || state.seen.contains(&key) || !emit {
+                continue;
+            }
+            match handler.dispatch(self.to_incoming(item)).await {
+                Ok(_) | Err(DispatchError::Invalid(_) | DispatchError::TooLarge { .. }) => emitted += 1,
+                Err(e) => {
+                    // Forgotten, so the next poll emits it again
+                    tracing::warn!(url = %self.url, error = %e, "Dispatch of polled item failed");
+                    seen.remove(&key);
+                    failed = true;
+                }
+            }
+        }
+
+        // A failed item has to come back in full on the next poll
+        state.etag = etag.filter(|_| !failed);
+        state.last_modified = last_modified.filter(|_| !failed);
+        state.seen = seen;
+        state.polled = true;
+        Ok(emitted)
+    }
+}
+
+#[async_trait]
+impl MessageSource for HttpPollSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        tracing::info!(url = %self.url, interval_ms = self.interval.as_millis() as u64, "Starting HTTP polling");
+
+        let client = reqwest::Client::builder().timeout(self.timeout).build()?;
+        let mut state = PollState::default();
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                result = self.poll(&client, &mut state, &handler) => match result {
+                    Ok(emitted) if emitted > 0 => tracing::debug!(url = %self.url, emitted, "Polled feed"),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(url = %self.url, error = %e, "Polling failed"),
+                },
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(self.interval) => {}
+            }
+        }
+
+        tracing::info!(url = %self.url, "HTTP polling stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "HTTP polling"
+    }
+}
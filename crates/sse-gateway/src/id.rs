@@ -0,0 +1,138 @@
+//! Stream ID generation
+//!
+//! Every stored event gets a stream ID before it is sent, which clients echo
+//! back as `Last-Event-ID`. By default the storage picks the ID
+//! ([`MessageStorage::generate_id`](crate::MessageStorage::generate_id));
+//! an [`IdGenerator`] set on the builder takes over for the dispatcher, the
+//! send API and the push endpoint alike.
+//!
+//! Both built-in generators produce `<millis>-<seq>` IDs that Redis Streams
+//! accept as explicit IDs and that sort the same way Redis sorts them:
+//!
+//! - [`StreamIds`] numbers events within each millisecond, like Redis's own
+//!   auto IDs. Unique within one process.
+//! - [`SnowflakeIds`] also folds a node number into the sequence, so
+//!   gateway instances with different nodes never hand out the same ID.
+//!
+//! ```rust,ignore
+//! use sse_gateway::id::SnowflakeIds;
+//!
+//! Gateway::builder()
+//!     .instance_id("gateway-3")
+//!     .id_generator(SnowflakeIds::new(3))
+//! ```
+
+use std::sync::Mutex;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+
+#[cfg(feature = "server")]
+use crate::storage::MessageStorage;
+
+/// Source of stream IDs
+pub trait IdGenerator: Send + Sync + 'static {
+    /// The next ID; later calls must return IDs that order after earlier ones
+    fn next_id(&self) -> String;
+}
+
+impl<F> IdGenerator for F
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    fn next_id(&self) -> String {
+        self()
+    }
+}
+
+/// `<millis>-<seq>` IDs numbered within each millisecond, as Redis assigns them
+///
+/// If the clock steps back, IDs keep counting from the last millisecond
+/// issued, so they never go backwards.
+#[derive(Debug, Default)]
+pub struct StreamIds {
+    /// Last issued (millis, seq)
+    last: Mutex<(u64, u64)>,
+}
+
+impl StreamIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Next (millis, seq) pair
+    fn next(&self) -> (u64, u64) {
+        let now = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        let mut last = self.last.lock().unwrap();
+        *last = if now > last.0 { (now, 0) } else { (last.0, last.1 + 1) };
+        *last
+    }
+}
+
+impl IdGenerator for StreamIds {
+    fn next_id(&self) -> String {
+        let (millis, seq) = self.next();
+        format!("{}-{}", millis, seq)
+    }
+}
+
+/// Instance-aware `<millis>-<seq>` IDs
+///
+/// The low 10 bits of the sequence hold the node number, so up to 1024
+/// instances can publish to the same channels without colliding. IDs from
+/// different instances interleave by time, then by per-millisecond count.
+#[derive(Debug)]
+pub struct SnowflakeIds {
+    node: u64,
+    ids: StreamIds,
+}
+
+impl SnowflakeIds {
+    /// Highest node number
+    pub const MAX_NODE: u16 = 1023;
+
+    /// Generator for node `node`, which must be unique among instances
+    ///
+    /// # Panics
+    ///
+    /// If `node` is above [`MAX_NODE`](Self::MAX_NODE).
+    pub fn new(node: u16) -> Self {
+        assert!(node <= Self::MAX_NODE, "snowflake node must be at most {}", Self::MAX_NODE);
+        Self {
+            node: node as u64,
+            ids: StreamIds::new(),
+        }
+    }
+
+    /// Generator whose node is derived from an instance ID
+    ///
+    /// Convenient when instance IDs are stable but not numbered; two
+    /// instances can still land on the same node, so prefer [`new`](Self::new)
+    /// when node numbers can be assigned.
+    pub fn for_instance(instance_id: &str) -> Self {
+        // FNV-1a, so the node stays the same across restarts and builds
+        let hash = instance_id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        Self::new((hash % (Self::MAX_NODE as u64 + 1)) as u16)
+    }
+
+    pub fn node(&self) -> u16 {
+        self.node as u16
+    }
+}
+
+impl IdGenerator for SnowflakeIds {
+    fn next_id(&self) -> String {
+        let (millis, seq) = self.ids.next();
+        format!("{}-{}", millis, seq << 10 | self.node)
+    }
+}
+
+/// Stream ID for a new event, from `ids` if set and the storage otherwise
+#[cfg(feature = "server")]
+pub(crate) fn next_stream_id<S: MessageStorage>(ids: &Option<Arc<dyn IdGenerator>>, storage: &S) -> String {
+    match ids {
+        Some(ids) => ids.next_id(),
+        None => storage.generate_id(),
+    }
+}
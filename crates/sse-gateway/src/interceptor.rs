@@ -0,0 +1,104 @@
+//! Event interceptors
+//!
+//! Interceptors can rewrite or drop events at two points: once per message
+//! before it is dispatched, and once per connection right before the event is
+//! queued for that client.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use sse_gateway::{Decision, EventInterceptor, IncomingMessage, SseConnection, SseEvent};
+//!
+//! struct Timestamp;
+//!
+//! impl EventInterceptor for Timestamp {
+//!     fn before_dispatch(&self, msg: &mut IncomingMessage) -> Decision {
+//!         if msg.event_type == "internal" {
+//!             return Decision::Drop;
+//!         }
+//!         msg.event_type = format!("app.{}", msg.event_type);
+//!         Decision::Continue
+//!     }
+//!
+//!     fn before_send(&self, connection: &SseConnection, event: &mut SseEvent) -> Decision {
+//!         event.id.get_or_insert_with(|| chrono::Utc::now().to_rfc3339());
+//!         Decision::Continue
+//!     }
+//! }
+//!
+//! Gateway::builder().interceptor(Timestamp)
+//! ```
+
+use std::sync::Arc;
+
+use crate::connection::SseConnection;
+use crate::event::SseEvent;
+use crate::source::IncomingMessage;
+
+/// Whether an event continues through the pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Pass the (possibly modified) event on
+    Continue,
+    /// Discard the event
+    Drop,
+}
+
+/// Hook for rewriting or filtering events
+///
+/// Both methods default to passing the event through unchanged. They run on
+/// the dispatch path, so keep them fast and non-blocking.
+pub trait EventInterceptor: Send + Sync + 'static {
+    /// Called once per message, before storage and fan-out
+    fn before_dispatch(&self, _msg: &mut IncomingMessage) -> Decision {
+        Decision::Continue
+    }
+
+    /// Called for each connection before the event is queued for it
+    ///
+    /// Also runs on events replayed from storage. Changes apply to this
+    /// connection only; stored events are unaffected.
+    fn before_send(&self, _connection: &SseConnection, _event: &mut SseEvent) -> Decision {
+        Decision::Continue
+    }
+}
+
+/// Ordered set of interceptors; the first `Drop` stops the chain
+#[derive(Clone, Default)]
+pub struct InterceptorChain {
+    interceptors: Arc<[Arc<dyn EventInterceptor>]>,
+}
+
+impl InterceptorChain {
+    /// Create a chain that runs `interceptors` in order
+    pub fn new(interceptors: Vec<Arc<dyn EventInterceptor>>) -> Self {
+        Self {
+            interceptors: interceptors.into(),
+        }
+    }
+
+    /// Whether the chain has no interceptors
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    /// Run `before_dispatch` on every interceptor
+    pub fn before_dispatch(&self, msg: &mut IncomingMessage) -> Decision {
+        for interceptor in self.interceptors.iter() {
+            if interceptor.before_dispatch(msg) == Decision::Drop {
+                return Decision::Drop;
+            }
+        }
+        Decision::Continue
+    }
+
+    /// Run `before_send` on every interceptor
+    pub fn before_send(&self, connection: &SseConnection, event: &mut SseEvent) -> Decision {
+        for interceptor in self.interceptors.iter() {
+            if interceptor.before_send(connection, event) == Decision::Drop {
+                return Decision::Drop;
+            }
+        }
+        Decision::Continue
+    }
+}
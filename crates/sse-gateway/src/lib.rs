@@ -8,8 +8,31 @@
 //! - **Pluggable Storage**: Implement `MessageStorage` for message replay on reconnection
 //! - **Channel-based Routing**: Route messages to specific channels or broadcast to all
 //! - **Built-in Server**: Optional Axum-based HTTP server with SSE endpoint
+//! - **Native TLS**: Optional HTTPS termination with certificate reload (`tls` feature)
 //! - **Memory Efficient**: Designed for high-concurrency with minimal memory footprint
 //! - **Flexible Authentication**: Support for custom auth callbacks with channel-level permissions
+//! - **Publish Throttling**: Per-channel events/sec and bytes/sec quotas
+//! - **HTTP Push Endpoint**: Optional `POST /push` for publishing without a custom source
+//! - **Event Interceptors**: Rewrite or drop events before dispatch and per connection
+//! - **Payload Validation**: Per-event-type JSON Schema checks (`schema` feature)
+//! - **Payload Limits**: Reject, truncate or externalize oversized payloads
+//! - **CloudEvents**: Accept CloudEvents 1.0 envelopes and optionally emit them
+//! - **WebSocket Fallback**: Same event stream over `/ws/connect` (`ws` feature)
+//! - **Compression**: Per-event gzip/Brotli for SSE responses (`compression` feature)
+//! - **Legacy Client Mode**: 2 KiB padding and no-buffering headers for polyfills and proxies (`?compat=1`)
+//! - **Cluster Mode**: Cross-instance forwarding and consistent-hash channel ownership, or a simple broadcast bus
+//! - **Lifecycle Webhooks**: Signed, batched connect/disconnect notifications, and URLs subscribed to channels (`webhooks` feature)
+//! - **gRPC Streaming**: Typed `Subscribe` stream for internal consumers (`grpc` feature)
+//! - **Access Log**: One structured record per closed connection, to stdout, a file or a custom sink
+//! - **Stream IDs**: Pluggable ID generation, including instance-aware snowflake IDs
+//! - **HTTP Polling**: Bridge JSON feeds that can't push, with conditional requests and JSONPath (`http-poll` feature)
+//! - **Log Tailing**: Follow log files and journald units, with rotation handling (`file-tail` feature)
+//! - **Source Supervision**: Failed sources restart with backoff; their state is reported by `/ready`
+//! - **Offline Fallback**: Hand messages no subscriber received to push notifications or email
+//! - **Channel Usage**: Daily published/delivered/subscriber rollups persisted to storage
+//! - **OpenAPI**: Document of the served HTTP endpoints at `/api/openapi.json` (`openapi` feature)
+//! - **Browser Client**: `/sse/client.js` wraps `EventSource` with backoff, resume tokens and signed URL refresh
+//! - **Resume Tokens**: Reconnect with `?resume=<token>` to restore a subscriber's session
 //!
 //! ## Quick Start
 //!
@@ -73,7 +96,7 @@
 //!         loop {
 //!             tokio::select! {
 //!                 _ = cancel.cancelled() => break,
-//!                 // msg = your_receiver.recv() => { handler(msg.into()); }
+//!                 // msg = your_receiver.recv() => { handler.send(msg.into()); }
 //!             }
 //!         }
 //!         Ok(())
@@ -83,29 +106,149 @@
 //! }
 //! ```
 
+pub mod access_log;
 pub mod auth;
+pub mod channel_config;
+pub mod channel_usage;
+mod channel_router;
+pub mod cloudevents;
+pub mod cluster;
+pub mod dispatch_queue;
 mod connection;
+mod dedup;
 mod error;
 mod event;
+pub mod id;
+pub mod interceptor;
 mod manager;
+pub mod metrics;
+pub mod offline;
+pub mod pause;
+pub mod payload;
+pub mod publisher;
+pub mod retry;
 pub mod source;
 pub mod storage;
+pub mod tenancy;
+pub mod testkit;
+pub mod throttle;
 
+#[cfg(feature = "server")]
+pub mod browser_client;
+#[cfg(feature = "server")]
+pub mod channel_rewrite;
+#[cfg(feature = "server")]
+pub mod client_ip;
+#[cfg(feature = "server")]
+pub mod coalesce;
+#[cfg(feature = "server")]
+pub mod compat;
+#[cfg(feature = "server")]
+mod dashboard;
+#[cfg(feature = "server")]
+pub mod encoding;
 #[cfg(feature = "server")]
 mod gateway;
 #[cfg(feature = "server")]
 mod handler;
+#[cfg(feature = "server")]
+mod heartbeat;
+#[cfg(feature = "server")]
+mod presence;
+#[cfg(feature = "server")]
+pub mod push;
+#[cfg(feature = "server")]
+pub mod testing;
+#[cfg(feature = "server")]
+pub mod resume;
+#[cfg(feature = "server")]
+mod serve;
+#[cfg(feature = "server")]
+pub mod supervisor;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "file-tail")]
+pub mod file_tail;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http-poll")]
+pub mod http_poll;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "synthetic")]
+pub mod synthetic;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
+#[cfg(feature = "ws")]
+mod ws;
 
 // Re-exports
-pub use connection::{SseConnection, ConnectionMetadata};
+pub use connection::{CloseReason, ConnectionCounters, ConnectionMetadata, SendStats, SseConnection};
 pub use error::{Error, Result};
-pub use event::{SseEvent, EventData};
+pub use event::{SseEvent, EventData, IntoSseEvent, Priority};
+pub use id::{IdGenerator, SnowflakeIds, StreamIds};
 pub use manager::ConnectionManager;
-pub use source::{MessageSource, MessageHandler, IncomingMessage, NoopSource, ChannelSource, ConnectionInfo};
-pub use storage::{MessageStorage, MemoryStorage, NoopStorage};
+pub use source::{
+    MessageSource, MessageHandler, MessageCallback, IncomingMessage, NoopSource, ChannelSource,
+    ConnectionInfo, DecodeError, DeliveryReport, DeliveryReporter, DispatchError, DispatchResult,
+};
+pub use storage::{
+    MessageStorage, MemoryStorage, NoopStorage, FailoverStorage, TeeStorage, PurgeBefore, export_history,
+    import_history,
+};
+pub use publisher::Publisher;
+pub use payload::{BlobStore, OversizedPolicy, PayloadLimit};
+pub use pause::{ChannelPause, PausePolicy};
+pub use retry::RetryPolicy;
+pub use dispatch_queue::{DispatchQueue, QueueOverflow};
+pub use metrics::{ChannelSubscribers, GatewayMetrics, MetricsHistory, MetricsSample, MetricsSnapshot};
+pub use throttle::{Throttle, ThrottleAction, ThrottlePolicy};
+pub use tenancy::Tenancy;
+pub use offline::OfflineFallback;
+pub use cloudevents::{CloudEvent, CloudEventsEmitter};
+pub use cluster::{
+    ClusterCoordinator, ClusterFanout, ClusterMessage, InstanceInfo, InstancePresence,
+    PresenceConnection,
+};
+pub use channel_config::{Backpressure, ChannelConfig, ChannelConfigs};
+pub use channel_usage::{DailyUsage, UsageDelta};
+pub use channel_router::ChannelRouter;
+pub use interceptor::{Decision, EventInterceptor, InterceptorChain};
 
 #[cfg(feature = "server")]
-pub use gateway::{Gateway, GatewayBuilder};
+pub use dashboard::{DashboardConfig, DashboardTheme};
+#[cfg(feature = "server")]
+pub use gateway::{Gateway, GatewayBuilder, GatewayHandle, Routes, MIGRATE_EVENT, REPLAY_BATCH_EVENT, REPLAY_END_EVENT, REPLAY_START_EVENT};
+#[cfg(feature = "server")]
+pub use heartbeat::Heartbeat;
+#[cfg(feature = "server")]
+pub use presence::{PRESENCE_JOIN, PRESENCE_LEAVE};
+#[cfg(feature = "server")]
+pub use push::{PushEndpoint, PushStore};
+#[cfg(feature = "server")]
+pub use serve::ServerOptions;
+#[cfg(feature = "server")]
+pub use coalesce::WriteCoalescing;
+#[cfg(feature = "server")]
+pub use compat::CompatMode;
+#[cfg(feature = "server")]
+pub use channel_rewrite::{ChannelRewrites, RewriteRule};
+#[cfg(feature = "server")]
+pub use client_ip::TrustedProxies;
+#[cfg(feature = "server")]
+pub use encoding::EventEncoder;
+#[cfg(feature = "server")]
+pub use supervisor::{RestartPolicy, SourceHealth, SourceState};
+#[cfg(feature = "compression")]
+pub use compression::Compression;
 
 // Re-export commonly used types from dependencies
 pub use async_trait::async_trait;
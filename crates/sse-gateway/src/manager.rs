@@ -1,24 +1,228 @@
 //! Connection Manager for handling SSE connections
 
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tokio::sync::{broadcast, mpsc};
 use tracing::info;
 
-use crate::connection::SseConnection;
+use crate::auth::EventTypeFilter;
+use crate::channel_config::{Backpressure, ChannelConfigs};
+use crate::channel_usage::UsageRecorder;
+use crate::connection::{CloseReason, ConnectionCounters, SendStats, SseConnection};
 use crate::event::SseEvent;
+use crate::interceptor::{Decision, InterceptorChain};
+use crate::metrics::GatewayMetrics;
+use crate::pause::{ChannelPause, PausedChannel, PausedChannelStats};
+
+/// Capacity of a connection's high priority queue
+#[cfg(feature = "server")]
+const URGENT_BUFFER: usize = 32;
+
+/// Reports CPU utilization in percent
+type CpuHint = Arc<dyn Fn() -> u8 + Send + Sync>;
+
+/// Members of a consumer group and whose turn is next
+#[derive(Default)]
+struct ConsumerGroup {
+    members: Vec<Arc<SseConnection>>,
+    /// Turns taken so far; advances under a shard read lock
+    next: AtomicUsize,
+}
+
+impl ConsumerGroup {
+    /// The member whose turn it is
+    fn take_turn(&self) -> Option<&Arc<SseConnection>> {
+        // Skip members whose client has gone but that aren't cleaned up yet
+        for _ in 0..self.members.len() {
+            let turn = self.next.fetch_add(1, Ordering::Relaxed);
+            let member = &self.members[turn % self.members.len()];
+            if member.is_active() {
+                return Some(member);
+            }
+        }
+        None
+    }
+}
+
+/// A channel's local subscribers, stored inline in its shard
+#[derive(Default)]
+struct Channel {
+    /// Subscribers outside consumer groups
+    connections: Vec<Arc<SseConnection>>,
+    /// Index of each of `connections` by connection ID
+    positions: HashMap<Arc<str>, usize>,
+    /// `connections` as sends see them, copied on the first send after a
+    /// change so a burst of subscribes costs one copy rather than one each
+    snapshot: OnceLock<Arc<[Arc<SseConnection>]>>,
+    /// Consumer groups by name
+    groups: HashMap<String, ConsumerGroup>,
+    /// Send totals across current and recent subscribers
+    counters: Arc<ConnectionCounters>,
+}
+
+impl Channel {
+    fn len(&self) -> usize {
+        self.connections.len()
+            + self
+                .groups
+                .values()
+                .map(|group| group.members.len())
+                .sum::<usize>()
+    }
+
+    fn all(&self) -> impl Iterator<Item = &Arc<SseConnection>> {
+        self.connections
+            .iter()
+            .chain(self.groups.values().flat_map(|group| group.members.iter()))
+    }
+
+    fn insert(&mut self, connection: Arc<SseConnection>) {
+        match connection.group() {
+            Some(group) => self
+                .groups
+                .entry(group.to_string())
+                .or_default()
+                .members
+                .push(connection),
+            None => {
+                self.positions
+                    .insert(connection.id.clone(), self.connections.len());
+                self.connections.push(connection);
+                self.snapshot.take();
+            }
+        }
+    }
+
+    /// Put `connection` in the place of the subscriber with the same ID
+    ///
+    /// Keeps its position in the fanout order and its group's rotation, unless
+    /// it moved to another group.
+    fn replace(&mut self, connection: Arc<SseConnection>) {
+        if let Some(&position) = self.positions.get(&connection.id) {
+            if connection.group().is_none() {
+                self.connections[position] = connection;
+                self.snapshot.take();
+                return;
+            }
+        } else if let Some(member) = connection
+            .group()
+            .and_then(|group| self.groups.get_mut(group))
+            .and_then(|group| group.members.iter_mut().find(|m| m.id == connection.id))
+        {
+            *member = connection;
+            return;
+        }
+        self.remove(&connection.id);
+        self.insert(connection);
+    }
+
+    /// The subscriber with `connection_id`
+    fn get(&self, connection_id: &str) -> Option<&Arc<SseConnection>> {
+        match self.positions.get(connection_id) {
+            Some(&position) => self.connections.get(position),
+            None => self.all().find(|c| &*c.id == connection_id),
+        }
+    }
+
+    fn remove(&mut self, connection_id: &str) -> Option<Arc<SseConnection>> {
+        if let Some(position) = self.positions.remove(connection_id) {
+            let removed = self.connections.swap_remove(position);
+            if let Some(moved) = self.connections.get(position) {
+                self.positions.insert(moved.id.clone(), position);
+            }
+            self.snapshot.take();
+            return Some(removed);
+        }
+        let mut removed = None;
+        self.groups.retain(|_, group| {
+            if let Some(position) = group.members.iter().position(|m| &*m.id == connection_id) {
+                removed = Some(group.members.remove(position));
+            }
+            !group.members.is_empty()
+        });
+        removed
+    }
+
+    /// Members of each consumer group whose turn it is
+    fn group_turns(&self) -> impl Iterator<Item = &Arc<SseConnection>> {
+        self.groups.values().filter_map(ConsumerGroup::take_turn)
+    }
+
+    /// Ungrouped subscribers, shared by every send until the next change
+    fn snapshot(&self) -> Arc<[Arc<SseConnection>]> {
+        self.snapshot
+            .get_or_init(|| self.connections.as_slice().into())
+            .clone()
+    }
+
+    /// Connections an event sent to the channel goes to: every ungrouped
+    /// subscriber, and one member of each group
+    fn targets(&self) -> (Arc<[Arc<SseConnection>]>, Vec<Arc<SseConnection>>) {
+        (self.snapshot(), self.group_turns().cloned().collect())
+    }
+}
+
+/// Channels whose IDs hash to the same lock
+type Shard = RwLock<HashMap<Arc<str>, Channel>>;
+
+fn read(shard: &Shard) -> RwLockReadGuard<'_, HashMap<Arc<str>, Channel>> {
+    shard.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write(shard: &Shard) -> RwLockWriteGuard<'_, HashMap<Arc<str>, Channel>> {
+    shard.write().unwrap_or_else(|e| e.into_inner())
+}
+
+fn new_shards(count: usize) -> Arc<[Shard]> {
+    (0..count.max(1)).map(|_| Shard::default()).collect()
+}
+
+/// Default shard count: 4 per CPU, so concurrent sends rarely share a lock
+fn default_shard_count() -> usize {
+    std::thread::available_parallelism().map_or(4, |cpus| cpus.get()) * 4
+}
 
 /// Manages all SSE connections
+///
+/// Channels are spread over shards by a hash of their ID. Each shard holds
+/// its channels' subscribers inline behind one lock, so sending to a channel
+/// takes a single read lock and no per-connection lookups, and sends to
+/// channels on different shards never contend.
 #[derive(Clone)]
 pub struct ConnectionManager {
-    /// All active connections: connection_id -> connection
-    connections: Arc<DashMap<String, SseConnection>>,
-    /// Index: channel_id -> [connection_ids]
-    channel_index: Arc<DashMap<String, Vec<String>>>,
+    /// Channels with their subscribers, groups and send totals
+    shards: Arc<[Shard]>,
+    /// Picks a channel's shard
+    hasher: RandomState,
+    /// Every connection by ID, for lookups outside the channel send path
+    by_id: Arc<DashMap<Arc<str>, Arc<SseConnection>>>,
+    /// Number of connections, counted without taking any lock
+    connection_count: Arc<AtomicUsize>,
     /// Heartbeat broadcaster
     heartbeat_tx: broadcast::Sender<i64>,
     /// Gateway instance ID
     instance_id: String,
+    /// Shared gateway counters
+    metrics: Arc<GatewayMetrics>,
+    /// Per-connection event hooks
+    interceptors: InterceptorChain,
+    /// Per-connection deduplication window size (0 = disabled)
+    dedup_window: usize,
+    /// Per-channel overrides
+    channel_configs: ChannelConfigs,
+    /// Connections the instance is sized for, advertised to the cluster
+    capacity: Option<usize>,
+    /// CPU utilization hint advertised to the cluster
+    cpu_hint: Option<CpuHint>,
+    /// Channels whose live delivery is paused
+    paused: Arc<DashMap<String, PausedChannel>>,
+    /// Per-channel usage since the last flush, if recorded
+    usage: Option<UsageRecorder>,
 }
 
 impl ConnectionManager {
@@ -26,10 +230,154 @@ impl ConnectionManager {
     pub fn new(instance_id: impl Into<String>) -> Self {
         let (heartbeat_tx, _) = broadcast::channel(16);
         Self {
-            connections: Arc::new(DashMap::new()),
-            channel_index: Arc::new(DashMap::new()),
+            shards: new_shards(default_shard_count()),
+            hasher: RandomState::new(),
+            by_id: Arc::new(DashMap::new()),
+            connection_count: Arc::new(AtomicUsize::new(0)),
             heartbeat_tx,
             instance_id: instance_id.into(),
+            metrics: Arc::new(GatewayMetrics::default()),
+            interceptors: InterceptorChain::default(),
+            dedup_window: 0,
+            channel_configs: ChannelConfigs::default(),
+            capacity: None,
+            cpu_hint: None,
+            paused: Arc::new(DashMap::new()),
+            usage: None,
+        }
+    }
+
+    /// Spread channels over `shards` locks (default: 4 per CPU)
+    ///
+    /// # Panics
+    ///
+    /// If connections are already registered.
+    pub fn with_shards(mut self, shards: usize) -> Self {
+        assert!(
+            self.by_id.is_empty(),
+            "ConnectionManager::with_shards called after connections were registered"
+        );
+        self.shards = new_shards(shards);
+        self
+    }
+
+    fn shard(&self, channel_id: &str) -> &Shard {
+        &self.shards[self.hasher.hash_one(channel_id) as usize % self.shards.len()]
+    }
+
+    /// Every connection, collected so no lock is held while sending to them
+    fn all_connections(&self) -> Vec<Arc<SseConnection>> {
+        self.by_id
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Run `interceptors` before each event is queued for a connection
+    pub fn with_interceptors(mut self, interceptors: InterceptorChain) -> Self {
+        self.interceptors = interceptors;
+        self
+    }
+
+    /// Suppress duplicate events per connection, remembering the last `size` keys
+    ///
+    /// See [`GatewayBuilder::dedup_window`](crate::GatewayBuilder::dedup_window).
+    pub fn with_dedup_window(mut self, size: usize) -> Self {
+        self.dedup_window = size;
+        self
+    }
+
+    /// Use `configs` for per-channel overrides
+    pub fn with_channel_configs(mut self, configs: ChannelConfigs) -> Self {
+        self.channel_configs = configs;
+        self
+    }
+
+    /// Advertise `capacity` as the connections this instance is sized for
+    ///
+    /// See [`GatewayBuilder::capacity`](crate::GatewayBuilder::capacity).
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Advertise the CPU utilization `hint` returns, in percent
+    ///
+    /// See [`GatewayBuilder::cpu_hint`](crate::GatewayBuilder::cpu_hint).
+    pub fn with_cpu_hint(mut self, hint: impl Fn() -> u8 + Send + Sync + 'static) -> Self {
+        self.cpu_hint = Some(Arc::new(hint));
+        self
+    }
+
+    /// Count channel usage in `usage`
+    #[cfg(feature = "server")]
+    pub(crate) fn with_usage(mut self, usage: UsageRecorder) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Channel usage recorder, if usage is recorded
+    #[cfg(feature = "server")]
+    pub(crate) fn usage(&self) -> Option<&UsageRecorder> {
+        self.usage.as_ref()
+    }
+
+    /// Connections this instance is sized for, if advertised
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Current CPU utilization hint in percent, if configured
+    pub fn cpu_hint(&self) -> Option<u8> {
+        self.cpu_hint.as_ref().map(|hint| hint().min(100))
+    }
+
+    /// Get the interceptor chain
+    pub fn interceptors(&self) -> &InterceptorChain {
+        &self.interceptors
+    }
+
+    /// Per-channel config registry; changes apply to new events and connections
+    pub fn channel_configs(&self) -> &ChannelConfigs {
+        &self.channel_configs
+    }
+
+    /// Queue an event for one connection, applying interceptors and its backpressure policy
+    async fn deliver(&self, connection: &SseConnection, event: SseEvent) -> bool {
+        if event.is_expired() || !connection.allows_event_type(&event.event_type) {
+            return false;
+        }
+        let mut event = event;
+        if !self.interceptors.is_empty()
+            && self.interceptors.before_send(connection, &mut event) == Decision::Drop
+        {
+            return false;
+        }
+        match connection.backpressure {
+            Backpressure::Wait => connection.send(event).await,
+            Backpressure::DropNewest => match connection.sender_for(&event).try_send(event) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    GatewayMetrics::incr(&self.metrics.deliveries_dropped);
+                    connection.counters.record_drop();
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            },
+            Backpressure::Disconnect => match connection.sender_for(&event).try_send(event) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    GatewayMetrics::incr(&self.metrics.deliveries_dropped);
+                    connection.counters.record_drop();
+                    tracing::warn!(connection_id = %connection.id, "Slow subscriber disconnected");
+                    // The caller may hold a map entry of this connection
+                    let manager = self.clone();
+                    let connection_id = connection.id.clone();
+                    tokio::spawn(async move { manager.close(&connection_id, CloseReason::SendQueueOverflow) });
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            },
         }
     }
 
@@ -39,74 +387,305 @@ impl ConnectionManager {
         channel_id: String,
         client_ip: Option<String>,
         user_agent: Option<String>,
+    ) -> (SseConnection, mpsc::Receiver<SseEvent>) {
+        self.register_identified(channel_id, client_ip, user_agent, None)
+    }
+
+    /// Register a new connection on behalf of an authenticated identity
+    pub fn register_identified(
+        &self,
+        channel_id: String,
+        client_ip: Option<String>,
+        user_agent: Option<String>,
+        identity: Option<String>,
+    ) -> (SseConnection, mpsc::Receiver<SseEvent>) {
+        self.register_with_attributes(channel_id, client_ip, user_agent, identity, HashMap::new())
+    }
+
+    /// Register a new connection with custom attributes
+    pub fn register_with_attributes(
+        &self,
+        channel_id: String,
+        client_ip: Option<String>,
+        user_agent: Option<String>,
+        identity: Option<String>,
+        attributes: HashMap<String, String>,
+    ) -> (SseConnection, mpsc::Receiver<SseEvent>) {
+        self.register_restricted(channel_id, client_ip, None, user_agent, identity, attributes, None)
+    }
+
+    /// Register a new connection that only receives `event_types`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn register_restricted(
+        &self,
+        channel_id: String,
+        client_ip: Option<String>,
+        peer_addr: Option<SocketAddr>,
+        user_agent: Option<String>,
+        identity: Option<String>,
+        attributes: HashMap<String, String>,
+        event_types: Option<EventTypeFilter>,
     ) -> (SseConnection, mpsc::Receiver<SseEvent>) {
         let (connection, receiver) =
-            SseConnection::new(channel_id.clone(), self.instance_id.clone(), client_ip, user_agent);
+            SseConnection::new(channel_id, self.instance_id.clone(), client_ip, user_agent);
+        let backpressure = self.channel_configs.resolve(&connection.channel_id).backpressure.unwrap_or_default();
+        let connection = connection
+            .with_peer_addr(peer_addr)
+            .with_identity(identity)
+            .with_attributes(attributes)
+            .with_event_types(event_types)
+            .with_backpressure(backpressure)
+            .with_dedup_window(self.dedup_window);
+
+        let (stored, concurrent) = {
+            let mut shard = write(self.shard(&connection.channel_id));
+            let channel = shard.entry(connection.channel_id.clone()).or_default();
+            let stored = Arc::new(connection.with_channel_counters(channel.counters.clone()));
+            channel.insert(stored.clone());
+            (stored, channel.len())
+        };
+        self.by_id.insert(stored.id.clone(), stored.clone());
+        self.connection_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(usage) = &self.usage {
+            let subscriber = stored.metadata.identity.as_deref().unwrap_or(&stored.id);
+            usage.record_subscriber(&stored.channel_id, subscriber, concurrent);
+        }
+
+        ((*stored).clone(), receiver)
+    }
 
-        let connection_id = connection.id.clone();
+    /// Replace a registered connection with a changed copy
+    ///
+    /// Returns `None` if the connection isn't registered (any more).
+    fn update(&self, connection_id: &str, change: impl FnOnce(&mut SseConnection)) -> Option<()> {
+        let channel_id = self.by_id.get(connection_id)?.channel_id.clone();
+        let updated = {
+            let mut shard = write(self.shard(&channel_id));
+            let channel = shard.get_mut(&channel_id)?;
+            let mut connection = (**channel.get(connection_id)?).clone();
+            change(&mut connection);
+            let updated = Arc::new(connection);
+            channel.replace(updated.clone());
+            updated
+        };
+        // Unless it was closed meanwhile
+        if let Some(mut entry) = self.by_id.get_mut(connection_id) {
+            *entry = updated;
+        }
+        Some(())
+    }
 
-        // Store connection
-        self.connections.insert(connection_id.clone(), connection.clone());
+    /// Give a registered connection a separate queue for high priority
+    /// events, returning its receiver
+    ///
+    /// The reader should drain it before the regular receiver so alerts
+    /// overtake events already queued behind a slow client.
+    #[cfg(feature = "server")]
+    pub(crate) fn attach_urgent(&self, connection_id: &str) -> Option<mpsc::Receiver<SseEvent>> {
+        let (sender, receiver) = mpsc::channel(URGENT_BUFFER);
+        self.update(connection_id, |connection| connection.urgent = Some(sender))?;
+        Some(receiver)
+    }
 
-        // Update channel index
-        self.channel_index
-            .entry(channel_id)
-            .or_default()
-            .push(connection_id);
+    /// Add a registered connection to consumer group `group` on its channel
+    ///
+    /// Each event sent to the channel goes to every ungrouped connection and
+    /// to one member of each group, taking turns. Returns `false` if the
+    /// connection isn't registered or already belongs to a group.
+    pub fn join_group(&self, connection_id: &str, group: impl Into<String>) -> bool {
+        let group = group.into();
+        let mut joined = false;
+        self.update(connection_id, |connection| {
+            if connection.metadata.group.is_none() {
+                connection.metadata.group = Some(group);
+                joined = true;
+            }
+        });
+        joined
+    }
 
-        (connection, receiver)
+    /// Connections an event sent to `channel_id` goes to
+    fn channel_targets(&self, channel_id: &str) -> (Arc<[Arc<SseConnection>]>, Vec<Arc<SseConnection>>) {
+        read(self.shard(channel_id))
+            .get(channel_id)
+            .map(Channel::targets)
+            .unwrap_or_default()
     }
 
-    /// Unregister a connection
+    /// Unregister a connection, reporting it as [`CloseReason::Kicked`]
     pub fn unregister(&self, connection_id: &str) {
-        if let Some((_, connection)) = self.connections.remove(connection_id) {
-            // Remove from channel index
-            if let Some(mut ids) = self.channel_index.get_mut(&connection.channel_id) {
-                ids.retain(|id| id != connection_id);
+        self.close(connection_id, CloseReason::Kicked);
+    }
+
+    /// Unregister a connection, reporting `reason` to its disconnect hooks
+    ///
+    /// The subscriber's stream ends once it has sent what was already queued.
+    pub fn close(&self, connection_id: &str, reason: CloseReason) {
+        if let Some((_, connection)) = self.by_id.remove(connection_id) {
+            self.connection_count.fetch_sub(1, Ordering::Relaxed);
+            // A connection closes once; the first reason wins
+            let _ = connection.close_reason.set(reason);
+            if let Some(channel) =
+                write(self.shard(&connection.channel_id)).get_mut(&connection.channel_id)
+            {
+                channel.remove(connection_id);
             }
-            info!(connection_id, channel_id = %connection.channel_id, "Connection unregistered");
+            info!(connection_id, channel_id = %connection.channel_id, %reason, "Connection unregistered");
         }
     }
 
+    /// Close every connection with `reason`, returning how many were closed
+    pub fn close_all(&self, reason: CloseReason) -> usize {
+        let ids: Vec<Arc<str>> = self.by_id.iter().map(|e| e.key().clone()).collect();
+        for id in &ids {
+            self.close(id, reason);
+        }
+        ids.len()
+    }
+
     /// Send event to a specific channel
+    ///
+    /// Consumer groups on the channel receive the event once per group.
+    /// Only local connections are reached and nothing is stored; use a
+    /// [`Publisher`](crate::Publisher) for events clients should be able to replay.
     pub async fn send_to_channel(&self, channel_id: &str, event: SseEvent) -> usize {
-        let connection_ids = self
-            .channel_index
-            .get(channel_id)
-            .map(|ids| ids.clone())
-            .unwrap_or_default();
-
+        let Some(event) = self.hold(channel_id, event) else { return 0 };
+        let (connections, turns) = self.channel_targets(channel_id);
         let mut sent = 0;
-        for conn_id in connection_ids {
-            if let Some(conn) = self.connections.get(&conn_id) {
-                if conn.send(event.clone()).await {
-                    sent += 1;
-                }
+        for conn in connections.iter().chain(&turns) {
+            if self.deliver(conn, event.clone()).await {
+                sent += 1;
             }
         }
+        if let Some(usage) = &self.usage {
+            usage.record_delivered(channel_id, sent);
+        }
         sent
     }
 
     /// Send event to a specific connection
     pub async fn send_to_connection(&self, connection_id: &str, event: SseEvent) -> bool {
-        if let Some(conn) = self.connections.get(connection_id) {
-            conn.send(event).await
-        } else {
-            false
+        let Some(conn) = self.by_id.get(connection_id).map(|c| c.value().clone()) else {
+            return false;
+        };
+        self.deliver(&conn, event).await
+    }
+
+    /// Send event to every connection whose attribute `key` equals `value`
+    ///
+    /// ```rust,ignore
+    /// manager.send_to_attr("tenant", "acme", SseEvent::raw("notice", "maintenance at 2am")).await;
+    /// ```
+    pub async fn send_to_attr(&self, key: &str, value: &str, event: SseEvent) -> usize {
+        self.send_where(|c| c.attribute(key) == Some(value), event).await
+    }
+
+    /// Send event to every connection matching `predicate`
+    ///
+    /// Consumer groups don't apply: each matching member receives the event.
+    /// Only local connections are reached and nothing is stored.
+    ///
+    /// ```rust,ignore
+    /// manager
+    ///     .send_where(|c| c.metadata.identity.as_deref() == Some("u1"), SseEvent::raw("notice", "hi"))
+    ///     .await;
+    /// ```
+    pub async fn send_where<F>(&self, predicate: F, event: SseEvent) -> usize
+    where
+        F: Fn(&SseConnection) -> bool,
+    {
+        // Collected first, so no map entry is held while a send waits
+        let connections: Vec<Arc<SseConnection>> = self
+            .by_id
+            .iter()
+            .filter(|c| predicate(c.value()))
+            .map(|c| c.value().clone())
+            .collect();
+        let mut sent = 0;
+        for connection in connections {
+            if self.deliver(&connection, event.clone()).await {
+                sent += 1;
+            }
         }
+        sent
+    }
+
+    /// Connections whose attribute `key` equals `value`
+    pub fn connections_with_attr(&self, key: &str, value: &str) -> Vec<SseConnection> {
+        self.by_id
+            .iter()
+            .filter(|c| c.attribute(key) == Some(value))
+            .map(|c| (**c.value()).clone())
+            .collect()
     }
 
     /// Broadcast event to all connections
     pub async fn broadcast(&self, event: SseEvent) -> usize {
         let mut sent = 0;
-        for entry in self.connections.iter() {
-            if entry.send(event.clone()).await {
+        for connection in self.all_connections() {
+            if self.deliver(&connection, event.clone()).await {
                 sent += 1;
             }
         }
         sent
     }
 
+    /// Send a batch of events in one pass
+    ///
+    /// Each item is `(channel_id, event)`, where `None` broadcasts. Channel
+    /// membership is resolved once per batch and events reach each connection
+    /// in batch order; consumer groups still take turns per event. Returns the
+    /// number of connections each event was sent to.
+    pub async fn send_batch<C: Into<Arc<str>>>(&self, events: Vec<(Option<C>, SseEvent)>) -> Vec<usize> {
+        let mut targets: HashMap<Option<Arc<str>>, Arc<[Arc<SseConnection>]>> = HashMap::new();
+        let mut results = Vec::with_capacity(events.len());
+
+        for (channel_id, event) in events {
+            let channel_id: Option<Arc<str>> = channel_id.map(Into::into);
+            let event = match &channel_id {
+                Some(channel_id) => self.hold(channel_id, event),
+                None => Some(event),
+            };
+            let Some(event) = event else {
+                results.push(0);
+                continue;
+            };
+            // Ungrouped subscribers, or everyone for broadcasts
+            let connections = targets
+                .entry(channel_id.clone())
+                .or_insert_with_key(|channel_id| match channel_id {
+                    Some(channel_id) => read(self.shard(channel_id))
+                        .get(channel_id)
+                        .map(Channel::snapshot)
+                        .unwrap_or_default(),
+                    None => self.all_connections().into(),
+                });
+
+            let mut sent = 0;
+            for connection in connections.iter() {
+                if self.deliver(connection, event.clone()).await {
+                    sent += 1;
+                }
+            }
+            if let Some(channel_id) = &channel_id {
+                let turns: Vec<Arc<SseConnection>> = read(self.shard(channel_id))
+                    .get(channel_id)
+                    .map(|channel| channel.group_turns().cloned().collect())
+                    .unwrap_or_default();
+                for connection in turns {
+                    if self.deliver(&connection, event.clone()).await {
+                        sent += 1;
+                    }
+                }
+                if let Some(usage) = &self.usage {
+                    usage.record_delivered(channel_id, sent);
+                }
+            }
+            results.push(sent);
+        }
+        results
+    }
+
     /// Send heartbeat to all connections
     pub fn send_heartbeat(&self) {
         let ts = chrono::Utc::now().timestamp_millis();
@@ -120,38 +699,266 @@ impl ConnectionManager {
 
     /// Get total connection count
     pub fn connection_count(&self) -> usize {
-        self.connections.len()
+        self.connection_count.load(Ordering::Relaxed)
     }
 
     /// Get connections for a specific channel
     pub fn channel_connection_count(&self, channel_id: &str) -> usize {
-        self.channel_index
+        read(self.shard(channel_id))
+            .get(channel_id)
+            .map_or(0, Channel::len)
+    }
+
+    /// Subscribers in each shard, for checking how evenly channels spread
+    pub fn connections_per_shard(&self) -> Vec<usize> {
+        self.shards
+            .iter()
+            .map(|shard| read(shard).values().map(Channel::len).sum())
+            .collect()
+    }
+
+    /// Local connections subscribed to `channel_id`
+    pub fn channel_connections(&self, channel_id: &str) -> Vec<SseConnection> {
+        read(self.shard(channel_id))
             .get(channel_id)
-            .map(|ids| ids.len())
-            .unwrap_or(0)
+            .map(|channel| channel.all().map(|c| (**c).clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Local connections on `tenant`'s channels (see [`crate::tenancy`])
+    pub fn tenant_connection_count(&self, tenant: &str) -> usize {
+        self.by_id
+            .iter()
+            .filter(|c| crate::tenancy::tenant_of(&c.channel_id) == Some(tenant))
+            .count()
+    }
+
+    /// Pause live delivery to `channel_id`; see [`pause`](crate::pause)
+    ///
+    /// Pausing a paused channel replaces its pause and keeps what it buffered.
+    pub fn pause_channel(&self, channel_id: &str, pause: ChannelPause) {
+        info!(channel_id, policy = ?pause.policy, reason = ?pause.reason, "Channel paused");
+        self.paused
+            .entry(channel_id.to_string())
+            .and_modify(|paused| paused.pause = pause.clone())
+            .or_insert_with(|| PausedChannel::new(pause));
+    }
+
+    /// Resume live delivery to `channel_id`, sending what it buffered
+    ///
+    /// Returns the number of buffered events sent on, or `None` if the
+    /// channel wasn't paused. Events published while the buffer is being
+    /// sent may arrive before the last buffered ones.
+    pub async fn resume_channel(&self, channel_id: &str) -> Option<usize> {
+        let (_, paused) = self.paused.remove(channel_id)?;
+        info!(
+            channel_id,
+            buffered = paused.buffer.len(),
+            dropped = paused.dropped,
+            "Channel resumed"
+        );
+        let flushed = paused.buffer.len();
+        for event in paused.buffer {
+            self.send_to_channel(channel_id, event).await;
+        }
+        Some(flushed)
+    }
+
+    /// The pause of `channel_id`, if paused
+    pub fn channel_pause(&self, channel_id: &str) -> Option<ChannelPause> {
+        self.paused.get(channel_id).map(|paused| paused.pause.clone())
+    }
+
+    /// Paused channels with what they buffered and dropped, by channel ID
+    pub fn paused_channels(&self) -> Vec<PausedChannelStats> {
+        let mut channels: Vec<PausedChannelStats> = self
+            .paused
+            .iter()
+            .map(|entry| PausedChannelStats {
+                channel_id: entry.key().clone(),
+                pause: entry.pause.clone(),
+                buffered: entry.buffer.len(),
+                dropped: entry.dropped,
+            })
+            .collect();
+        channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+        channels
+    }
+
+    /// Hand `event` back unless `channel_id` is paused, in which case the
+    /// pause holds it
+    fn hold(&self, channel_id: &str, event: SseEvent) -> Option<SseEvent> {
+        if self.paused.is_empty() {
+            return Some(event);
+        }
+        match self.paused.get_mut(channel_id) {
+            Some(mut paused) => {
+                paused.hold(event);
+                None
+            }
+            None => Some(event),
+        }
+    }
+
+    /// Channels with at least one local connection
+    pub fn channel_ids(&self) -> Vec<String> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                read(shard)
+                    .iter()
+                    .filter(|(_, channel)| channel.len() > 0)
+                    .map(|(channel_id, _)| channel_id.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Local subscriber count of every channel with subscribers
+    pub fn channel_subscriber_counts(&self) -> Vec<(String, usize)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                read(shard)
+                    .iter()
+                    .filter(|(_, channel)| channel.len() > 0)
+                    .map(|(channel_id, channel)| (channel_id.to_string(), channel.len()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     /// List all connections
     pub fn list_connections(&self) -> Vec<SseConnection> {
-        self.connections.iter().map(|e| e.value().clone()).collect()
+        self.by_id.iter().map(|e| (**e.value()).clone()).collect()
     }
 
     /// Clean up dead connections
     pub fn cleanup_dead_connections(&self) {
-        let dead_ids: Vec<String> = self
-            .connections
+        let dead_ids: Vec<Arc<str>> = self
+            .by_id
             .iter()
             .filter(|e| !e.value().is_active())
             .map(|e| e.key().clone())
             .collect();
 
+        // Their receivers are gone, so the client already left
         for id in dead_ids {
-            self.unregister(&id);
+            self.close(&id, CloseReason::ClientDisconnect);
+        }
+
+        for shard in self.shards.iter() {
+            write(shard).retain(|_, channel| channel.len() > 0);
+        }
+    }
+
+    /// Send totals of `channel_id` across its current and recent subscribers
+    ///
+    /// Totals are kept while the channel has subscribers and dropped at the
+    /// next cleanup after the last one leaves.
+    pub fn channel_stats(&self, channel_id: &str) -> Option<SendStats> {
+        read(self.shard(channel_id))
+            .get(channel_id)
+            .map(|channel| channel.counters.snapshot())
+    }
+
+    /// Send totals of every channel with subscribers
+    pub fn all_channel_stats(&self) -> BTreeMap<String, SendStats> {
+        let mut stats = BTreeMap::new();
+        for shard in self.shards.iter() {
+            stats.extend(
+                read(shard)
+                    .iter()
+                    .map(|(channel_id, channel)| (channel_id.to_string(), channel.counters.snapshot())),
+            );
         }
+        stats
     }
 
     /// Get the instance ID
     pub fn instance_id(&self) -> &str {
         &self.instance_id
     }
+
+    /// Get the shared gateway metrics
+    pub fn metrics(&self) -> &GatewayMetrics {
+        &self.metrics
+    }
+
+    /// The metrics, for components that outlive a borrow of the manager
+    #[cfg(feature = "server")]
+    pub(crate) fn shared_metrics(&self) -> Arc<GatewayMetrics> {
+        self.metrics.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(manager: &ConnectionManager, channel_id: &str) -> Vec<Arc<str>> {
+        manager
+            .channel_connections(channel_id)
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    fn snapshot(manager: &ConnectionManager, channel_id: &str) -> Arc<[Arc<SseConnection>]> {
+        read(manager.shard(channel_id))
+            .get(channel_id)
+            .map(Channel::snapshot)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn update_keeps_fanout_position() {
+        let manager = ConnectionManager::new("test");
+        let registered: Vec<_> = (0..3)
+            .map(|_| manager.register("channel".to_string(), None, None))
+            .collect();
+        let before = ids(&manager, "channel");
+
+        let first = &registered[0].0.id;
+        assert!(manager.update(first, |connection| connection.metadata.identity = Some("alice".into())).is_some());
+
+        assert_eq!(ids(&manager, "channel"), before);
+        assert_eq!(snapshot(&manager, "channel")[0].metadata.identity.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn update_of_unknown_connection_is_none() {
+        let manager = ConnectionManager::new("test");
+        assert!(manager.update("missing", |_| {}).is_none());
+    }
+
+    #[test]
+    fn snapshot_is_shared_until_subscribers_change() {
+        let manager = ConnectionManager::new("test");
+        let (first, _rx) = manager.register("channel".to_string(), None, None);
+
+        let a = snapshot(&manager, "channel");
+        assert!(Arc::ptr_eq(&a, &snapshot(&manager, "channel")));
+
+        let (_second, _rx2) = manager.register("channel".to_string(), None, None);
+        let b = snapshot(&manager, "channel");
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(b.len(), 2);
+
+        manager.unregister(&first.id);
+        assert_eq!(snapshot(&manager, "channel").len(), 1);
+    }
+
+    #[test]
+    fn joining_a_group_leaves_the_snapshot() {
+        let manager = ConnectionManager::new("test");
+        let (first, _rx) = manager.register("channel".to_string(), None, None);
+        let (_second, _rx2) = manager.register("channel".to_string(), None, None);
+
+        assert!(manager.join_group(&first.id, "workers"));
+        assert!(!manager.join_group(&first.id, "others"));
+
+        assert_eq!(snapshot(&manager, "channel").len(), 1);
+        assert_eq!(manager.channel_connection_count("channel"), 2);
+    }
 }
@@ -0,0 +1,368 @@
+//! Gateway metrics
+//!
+//! Lightweight atomic counters shared by the dispatcher and HTTP handlers,
+//! plus a [`MetricsHistory`] ring buffer of periodic samples for charts.
+//! Each sample also takes the subscriber counts of the busiest channels,
+//! which are kept as gauges in [`GatewayMetrics`] between samples.
+
+#[cfg(feature = "server")]
+use axum::{extract::State, response::Json, routing::get, Router};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+#[cfg(feature = "server")]
+use tokio_util::sync::CancellationToken;
+
+use crate::connection::CloseReason;
+use crate::manager::ConnectionManager;
+
+/// Counters collected by the gateway
+#[derive(Debug, Default)]
+pub struct GatewayMetrics {
+    /// Messages dispatched to local connections
+    pub messages_dispatched: AtomicU64,
+    /// Messages dropped by the throttle
+    pub throttled_dropped: AtomicU64,
+    /// Messages delayed by the throttle
+    pub throttled_delayed: AtomicU64,
+    /// Messages rejected by the throttle
+    pub throttled_rejected: AtomicU64,
+    /// Deliveries dropped because a subscriber's queue was full
+    pub deliveries_dropped: AtomicU64,
+    /// Messages that failed schema validation
+    pub validation_failed: AtomicU64,
+    /// Invalid messages handed to the dead-letter callback
+    pub dead_lettered: AtomicU64,
+    /// Payloads sources couldn't decode, by source name
+    pub decode_failures: DashMap<String, u64>,
+    /// Payloads over a payload limit
+    pub oversized_payloads: AtomicU64,
+    /// Acknowledgements received from clients
+    pub acks: AtomicU64,
+    /// Stored events behind each consumer's last ack: (channel, consumer) -> lag
+    pub consumer_lag: DashMap<(String, String), u64>,
+    /// Closed subscriber connections by reason
+    pub disconnects: DashMap<CloseReason, u64>,
+    /// Messages waiting in the dispatch queue
+    pub dispatch_queue_depth: AtomicU64,
+    /// Messages sent while their dispatch queue was full
+    pub dispatch_queue_overflows: AtomicU64,
+    /// Overflowing messages discarded under `QueueOverflow::Drop`
+    pub dispatch_queue_dropped: AtomicU64,
+    /// Queued messages whose dispatch returned an error
+    pub dispatch_failures: AtomicU64,
+    /// Messages handed to the offline fallback
+    pub offline_fallbacks: AtomicU64,
+    /// Offline fallback calls that returned an error
+    pub offline_fallback_failures: AtomicU64,
+    /// Subscribers of the busiest channels, as of the latest history sample
+    pub channel_subscribers: DashMap<String, u64>,
+    /// Subscribers of the other channels, as of the latest history sample
+    pub other_channel_subscribers: AtomicU64,
+}
+
+impl GatewayMetrics {
+    /// Increment a counter by one
+    pub fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a consumer's lag as of its latest acknowledgement
+    pub fn record_lag(&self, channel_id: &str, consumer_id: &str, lag: u64) {
+        self.consumer_lag
+            .insert((channel_id.to_string(), consumer_id.to_string()), lag);
+    }
+
+    /// Count a payload `source` couldn't decode
+    pub fn record_decode_failure(&self, source: &str) {
+        *self.decode_failures.entry(source.to_string()).or_insert(0) += 1;
+    }
+
+    /// Count a closed subscriber connection
+    pub fn record_disconnect(&self, reason: CloseReason) {
+        *self.disconnects.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Replace the per-channel subscriber gauges
+    pub fn record_channel_subscribers(&self, top: &[ChannelSubscribers], other: usize) {
+        self.channel_subscribers
+            .retain(|channel_id, _| top.iter().any(|channel| &channel.channel_id == channel_id));
+        for channel in top {
+            self.channel_subscribers
+                .insert(channel.channel_id.clone(), channel.subscribers as u64);
+        }
+        self.other_channel_subscribers.store(other as u64, Ordering::Relaxed);
+    }
+
+    /// Take a point-in-time snapshot of all counters
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            messages_dispatched: self.messages_dispatched.load(Ordering::Relaxed),
+            throttled_dropped: self.throttled_dropped.load(Ordering::Relaxed),
+            throttled_delayed: self.throttled_delayed.load(Ordering::Relaxed),
+            throttled_rejected: self.throttled_rejected.load(Ordering::Relaxed),
+            deliveries_dropped: self.deliveries_dropped.load(Ordering::Relaxed),
+            validation_failed: self.validation_failed.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            decode_failures: self
+                .decode_failures
+                .iter()
+                .map(|entry| (entry.key().clone(), *entry.value()))
+                .collect(),
+            oversized_payloads: self.oversized_payloads.load(Ordering::Relaxed),
+            acks: self.acks.load(Ordering::Relaxed),
+            consumer_lag: self.consumer_lag.iter().fold(BTreeMap::new(), |mut lag, entry| {
+                let (channel_id, consumer_id) = entry.key();
+                lag.entry(channel_id.clone())
+                    .or_insert_with(BTreeMap::new)
+                    .insert(consumer_id.clone(), *entry.value());
+                lag
+            }),
+            disconnects: self
+                .disconnects
+                .iter()
+                .map(|entry| (entry.key().as_str().to_string(), *entry.value()))
+                .collect(),
+            dispatch_queue_depth: self.dispatch_queue_depth.load(Ordering::Relaxed),
+            dispatch_queue_overflows: self.dispatch_queue_overflows.load(Ordering::Relaxed),
+            dispatch_queue_dropped: self.dispatch_queue_dropped.load(Ordering::Relaxed),
+            dispatch_failures: self.dispatch_failures.load(Ordering::Relaxed),
+            offline_fallbacks: self.offline_fallbacks.load(Ordering::Relaxed),
+            offline_fallback_failures: self.offline_fallback_failures.load(Ordering::Relaxed),
+            channel_subscribers: self
+                .channel_subscribers
+                .iter()
+                .map(|entry| (entry.key().clone(), *entry.value()))
+                .collect(),
+            other_channel_subscribers: self.other_channel_subscribers.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Serializable snapshot of [`GatewayMetrics`]
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub messages_dispatched: u64,
+    pub throttled_dropped: u64,
+    pub throttled_delayed: u64,
+    pub throttled_rejected: u64,
+    pub deliveries_dropped: u64,
+    pub validation_failed: u64,
+    pub dead_lettered: u64,
+    /// Source -> payloads it couldn't decode
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub decode_failures: BTreeMap<String, u64>,
+    pub oversized_payloads: u64,
+    pub acks: u64,
+    /// Channel -> consumer -> stored events after its last ack
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub consumer_lag: BTreeMap<String, BTreeMap<String, u64>>,
+    /// Close reason -> closed subscriber connections
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub disconnects: BTreeMap<String, u64>,
+    pub dispatch_queue_depth: u64,
+    pub dispatch_queue_overflows: u64,
+    pub dispatch_queue_dropped: u64,
+    pub dispatch_failures: u64,
+    pub offline_fallbacks: u64,
+    pub offline_fallback_failures: u64,
+    /// Channel -> subscribers, for the busiest channels at the latest history sample
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub channel_subscribers: BTreeMap<String, u64>,
+    /// Subscribers of all other channels at the latest history sample
+    pub other_channel_subscribers: u64,
+}
+
+/// Default time between history samples
+pub const DEFAULT_HISTORY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default number of samples kept (10 minutes at the default interval)
+pub const DEFAULT_HISTORY_CAPACITY: usize = 300;
+
+/// Default number of channels whose subscriber counts are sampled individually
+pub const DEFAULT_TOP_CHANNELS: usize = 10;
+
+/// Subscriber count of one channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChannelSubscribers {
+    pub channel_id: String,
+    pub subscribers: usize,
+}
+
+/// One point of [`MetricsHistory`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsSample {
+    /// Sample time, milliseconds since the Unix epoch
+    pub timestamp: i64,
+    /// Open connections on this instance
+    pub connections: usize,
+    /// Channels with at least one subscriber
+    pub channels: usize,
+    /// Messages dispatched per second since the previous sample
+    pub events_per_sec: f64,
+    /// Messages throttled away or dropped at full subscriber queues, per second
+    pub drops_per_sec: f64,
+    /// Channels with the most subscribers, busiest first
+    pub top_channels: Vec<ChannelSubscribers>,
+    /// Subscribers of the channels not in `top_channels`
+    pub other_subscribers: usize,
+}
+
+/// `GET /api/metrics/history` response body
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsHistoryResponse {
+    pub interval_secs: f64,
+    /// Oldest first
+    pub samples: Vec<MetricsSample>,
+}
+
+struct HistoryState {
+    samples: VecDeque<MetricsSample>,
+    /// Counter totals at the previous sample
+    last: Option<(Instant, u64, u64)>,
+}
+
+/// Ring buffer of periodic metric samples
+///
+/// The gateway samples its counters every `interval` and keeps the newest
+/// `capacity` samples in memory, so the dashboard can chart recent activity
+/// without an external metrics system. Clones share the buffer.
+#[derive(Clone)]
+pub struct MetricsHistory {
+    interval: Duration,
+    capacity: usize,
+    top_channels: usize,
+    state: Arc<Mutex<HistoryState>>,
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_INTERVAL, DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl MetricsHistory {
+    /// Sample every `interval`, keeping the newest `capacity` samples
+    pub fn new(interval: Duration, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(Duration::from_millis(100)),
+            capacity: capacity.max(1),
+            top_channels: DEFAULT_TOP_CHANNELS,
+            state: Arc::new(Mutex::new(HistoryState {
+                samples: VecDeque::with_capacity(capacity.max(1)),
+                last: None,
+            })),
+        }
+    }
+
+    /// Sample the subscriber counts of the `n` busiest channels (default: 10)
+    ///
+    /// Subscribers of the remaining channels are summed into one count, so
+    /// a sample stays small however many channels there are.
+    pub fn top_channels(mut self, n: usize) -> Self {
+        self.top_channels = n;
+        self
+    }
+
+    /// Time between samples
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Samples currently held, oldest first
+    pub fn samples(&self) -> Vec<MetricsSample> {
+        self.state.lock().unwrap().samples.iter().cloned().collect()
+    }
+
+    /// Take a sample of `manager` now
+    ///
+    /// Rates are computed against the previous sample; the first sample
+    /// reports zero rates.
+    pub fn record(&self, manager: &ConnectionManager) -> MetricsSample {
+        let metrics = manager.metrics();
+        let dispatched = metrics.messages_dispatched.load(Ordering::Relaxed);
+        let dropped = metrics.throttled_dropped.load(Ordering::Relaxed)
+            + metrics.throttled_rejected.load(Ordering::Relaxed)
+            + metrics.deliveries_dropped.load(Ordering::Relaxed);
+        let now = Instant::now();
+
+        let mut state = self.state.lock().unwrap();
+        let (events_per_sec, drops_per_sec) = match state.last {
+            Some((at, last_dispatched, last_dropped)) => {
+                let secs = now.duration_since(at).as_secs_f64().max(f64::EPSILON);
+                (
+                    dispatched.saturating_sub(last_dispatched) as f64 / secs,
+                    dropped.saturating_sub(last_dropped) as f64 / secs,
+                )
+            }
+            None => (0.0, 0.0),
+        };
+        state.last = Some((now, dispatched, dropped));
+
+        let mut counts = manager.channel_subscriber_counts();
+        let channels = counts.len();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let other_subscribers = counts.iter().skip(self.top_channels).map(|(_, n)| n).sum();
+        counts.truncate(self.top_channels);
+        let top_channels: Vec<_> = counts
+            .into_iter()
+            .map(|(channel_id, subscribers)| ChannelSubscribers { channel_id, subscribers })
+            .collect();
+        metrics.record_channel_subscribers(&top_channels, other_subscribers);
+
+        let sample = MetricsSample {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            connections: manager.connection_count(),
+            channels,
+            events_per_sec,
+            drops_per_sec,
+            top_channels,
+            other_subscribers,
+        };
+        if state.samples.len() == self.capacity {
+            state.samples.pop_front();
+        }
+        state.samples.push_back(sample.clone());
+        sample
+    }
+
+    /// Record a sample every `interval` until cancelled
+    #[cfg(feature = "server")]
+    pub(crate) fn spawn_sampler(
+        &self,
+        manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> tokio::task::JoinHandle<()> {
+        let history = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(history.interval);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {
+                        history.record(&manager);
+                    }
+                }
+            }
+        })
+    }
+
+    /// `GET /api/metrics/history`
+    #[cfg(feature = "server")]
+    pub(crate) fn router(&self) -> Router {
+        Router::new()
+            .route("/api/metrics/history", get(get_history))
+            .with_state(self.clone())
+    }
+}
+
+#[cfg(feature = "server")]
+async fn get_history(State(history): State<MetricsHistory>) -> Json<MetricsHistoryResponse> {
+    Json(MetricsHistoryResponse {
+        interval_secs: history.interval.as_secs_f64(),
+        samples: history.samples(),
+    })
+}
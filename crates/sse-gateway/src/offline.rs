@@ -0,0 +1,52 @@
+//! Fallback delivery for channels nobody is subscribed to
+//!
+//! When a message for a channel finds no subscriber on any instance, an
+//! [`OfflineFallback`] gets a chance to reach the user another way, such as
+//! a mobile push notification or an email.
+//!
+//! ```rust,ignore
+//! use sse_gateway::offline::OfflineFallback;
+//!
+//! struct Fcm { /* ... */ }
+//!
+//! #[async_trait]
+//! impl OfflineFallback for Fcm {
+//!     async fn on_offline(&self, channel_id: &str, message: &IncomingMessage) -> anyhow::Result<()> {
+//!         if message.event_type == "chat.message" {
+//!             self.notify(channel_id, &message.data).await?;
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn name(&self) -> &'static str { "fcm" }
+//! }
+//!
+//! Gateway::builder().offline_fallback(Fcm { /* ... */ })
+//! ```
+//!
+//! The fallback runs after the message has been stored, so the client can
+//! still replay it when it comes back. Broadcasts never trigger it.
+
+use async_trait::async_trait;
+
+use crate::source::IncomingMessage;
+
+/// Called for messages whose channel has no subscribers
+///
+/// "No subscribers" means none on this instance and, in cluster mode, none
+/// on any other instance. With only a [`ClusterFanout`](crate::ClusterFanout)
+/// the gateway can't see other instances' subscribers, so the fallback runs
+/// whenever the channel has none locally.
+#[async_trait]
+pub trait OfflineFallback: Send + Sync + 'static {
+    /// Handle `message`, which no subscriber of `channel_id` received
+    ///
+    /// Runs in its own task, so slow calls don't hold up dispatch. Errors
+    /// are logged and counted under `offline_fallback_failures`.
+    async fn on_offline(&self, channel_id: &str, message: &IncomingMessage) -> anyhow::Result<()>;
+
+    /// Return the fallback name (for logging)
+    fn name(&self) -> &'static str {
+        "OfflineFallback"
+    }
+}
@@ -0,0 +1,463 @@
+//! OpenAPI document of the gateway's HTTP endpoints
+//!
+//! With the `openapi` feature, `GET /api/openapi.json` describes the routes
+//! this gateway actually serves: the SSE path and channel parameter as
+//! configured, the push endpoint if enabled, and the dashboard, cluster and
+//! webhook endpoints when they are on. API gateways and client generators
+//! can consume it directly:
+//!
+//! ```bash
+//! curl http://localhost:8080/api/openapi.json | npx @openapitools/openapi-generator-cli generate -i /dev/stdin -g typescript-fetch -o client
+//! ```
+//!
+//! Request bodies and the smaller responses have full schemas; statistics,
+//! metrics and cluster views are described as plain objects. Routes added
+//! with [`GatewayBuilder::route`](crate::GatewayBuilder::route) are not
+//! included.
+
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use utoipa::openapi::path::{OperationBuilder, ParameterBuilder, ParameterIn};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::{
+    ArrayBuilder, ComponentsBuilder, ContentBuilder, HttpMethod, InfoBuilder, ObjectBuilder, OpenApi, OpenApiBuilder,
+    PathItem, PathsBuilder, Ref, RefOr, Required, ResponseBuilder, Schema, Type,
+};
+use utoipa::ToSchema;
+
+use crate::handler::{
+    AckRequest, AckResponse, BroadcastRequest, CursorResponse, ImportResponse, PauseRequest, PurgeResponse,
+    ResumeResponse, SendMessageRequest, SendMessageResponse, UsageResponse,
+};
+use crate::push::{PushBatchResponse, PushRequest, PushResponse};
+
+/// Path the document is served at
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Routes the gateway serves, as configured on the builder
+pub(crate) struct ApiSurface<'a> {
+    pub sse_path: &'a str,
+    pub channel_param: &'a str,
+    pub channel_in_path: bool,
+    pub push_path: Option<&'a str>,
+    pub ws_path: Option<&'a str>,
+    pub metrics: bool,
+    pub dashboard: bool,
+    pub cluster: bool,
+    pub webhooks: bool,
+}
+
+/// Paths and schemas collected while describing the routes
+struct Spec {
+    paths: PathsBuilder,
+    schemas: Vec<(String, RefOr<Schema>)>,
+}
+
+impl Spec {
+    fn add(&mut self, path: &str, method: HttpMethod, operation: OperationBuilder) {
+        let paths = std::mem::take(&mut self.paths);
+        self.paths = paths.path(path, PathItem::new(method, operation));
+    }
+
+    /// Reference to `T`'s schema, registering it and the schemas it uses
+    fn schema<T: ToSchema>(&mut self) -> RefOr<Schema> {
+        self.schemas.push((T::name().into_owned(), T::schema()));
+        T::schemas(&mut self.schemas);
+        Ref::from_schema_name(T::name()).into()
+    }
+}
+
+fn operation(tag: &str, summary: &str) -> OperationBuilder {
+    OperationBuilder::new().tag(tag).summary(Some(summary))
+}
+
+fn content(content_type: &str, schema: impl Into<RefOr<Schema>>) -> (String, utoipa::openapi::Content) {
+    (content_type.to_string(), ContentBuilder::new().schema(Some(schema)).build())
+}
+
+fn json_body(schema: impl Into<RefOr<Schema>>) -> Option<utoipa::openapi::request_body::RequestBody> {
+    let (content_type, content) = content("application/json", schema);
+    Some(RequestBodyBuilder::new().content(content_type, content).required(Some(Required::True)).build())
+}
+
+fn json_response(description: &str, schema: impl Into<RefOr<Schema>>) -> utoipa::openapi::Response {
+    let (content_type, content) = content("application/json", schema);
+    ResponseBuilder::new().description(description).content(content_type, content).build()
+}
+
+fn response(description: &str) -> utoipa::openapi::Response {
+    ResponseBuilder::new().description(description).build()
+}
+
+/// A JSON object whose fields aren't described
+fn object(description: &str) -> Schema {
+    ObjectBuilder::new().schema_type(Type::Object).description(Some(description)).into()
+}
+
+fn string() -> Schema {
+    ObjectBuilder::new().schema_type(Type::String).into()
+}
+
+fn path_param(name: &str) -> utoipa::openapi::path::Parameter {
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Path)
+        .required(Required::True)
+        .schema(Some(string()))
+        .build()
+}
+
+fn query_param(name: &str, description: &str) -> utoipa::openapi::path::Parameter {
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Query)
+        .required(Required::False)
+        .description(Some(description))
+        .schema(Some(string()))
+        .build()
+}
+
+/// Query parameters every SSE connection accepts
+fn connect_params(operation: OperationBuilder) -> OperationBuilder {
+    operation
+        .parameter(query_param("last_event_id", "Replay cursor, for clients that can't set Last-Event-ID"))
+        .parameter(query_param("resume", "Resume token from an earlier connection's `connected` event"))
+        .parameter(query_param("consumer_id", "Resume from this consumer's acknowledged cursor"))
+        .parameter(query_param("group", "Consumer group to join; each event goes to one member"))
+        .parameter(query_param("replay_batch", "Send replayed events in `replay_batch` events of this size"))
+        .parameter(query_param("compat", "`1` for padding and no-buffering headers, `0` to opt out"))
+        .response(
+            "200",
+            ResponseBuilder::new()
+                .description("Event stream")
+                .content("text/event-stream", ContentBuilder::new().schema(Some(string())).build())
+                .build(),
+        )
+        .response("401", response("Authentication failed"))
+        .response("403", response("Channel not allowed"))
+}
+
+/// Describe the routes of `surface`
+pub(crate) fn document(surface: &ApiSurface) -> OpenApi {
+    let mut spec = Spec {
+        paths: PathsBuilder::new(),
+        schemas: Vec::new(),
+    };
+    let channel = || path_param("channel_id");
+
+    // Subscribers
+    spec.add(
+        surface.sse_path,
+        HttpMethod::Get,
+        connect_params(operation("subscribe", "Open an SSE connection to a channel")).parameter(
+            ParameterBuilder::new()
+                .name(surface.channel_param)
+                .parameter_in(ParameterIn::Query)
+                .required(Required::False)
+                .description(Some("Channel to subscribe to; optional with a resume token or identity channels"))
+                .schema(Some(string())),
+        ),
+    );
+    if surface.channel_in_path {
+        let path = format!("{}/{{channel_id}}", surface.sse_path.trim_end_matches('/'));
+        spec.add(
+            &path,
+            HttpMethod::Get,
+            connect_params(operation("subscribe", "Open an SSE connection, channel in the path")).parameter(channel()),
+        );
+    }
+    if let Some(ws_path) = surface.ws_path {
+        spec.add(
+            ws_path,
+            HttpMethod::Get,
+            operation("subscribe", "Open a WebSocket carrying the same events as JSON frames")
+                .parameter(query_param(surface.channel_param, "Channel to subscribe to"))
+                .response("101", response("Switching to WebSocket")),
+        );
+    }
+    let cursor = spec.schema::<CursorResponse>();
+    spec.add(
+        "/api/channels/{channel_id}/cursor",
+        HttpMethod::Get,
+        operation("subscribe", "Newest stored stream ID of a channel")
+            .parameter(channel())
+            .response("200", json_response("Replay cursor", cursor)),
+    );
+    let (ack, acked) = (spec.schema::<AckRequest>(), spec.schema::<AckResponse>());
+    spec.add(
+        "/sse/ack",
+        HttpMethod::Post,
+        operation("subscribe", "Record a consumer's processed cursor")
+            .request_body(json_body(ack))
+            .response("200", json_response("Recorded cursor and remaining lag", acked)),
+    );
+    spec.add(
+        "/api/presence/{channel_id}",
+        HttpMethod::Get,
+        operation("subscribe", "Subscribers of a channel on every instance")
+            .parameter(channel())
+            .response("200", json_response("Presence", object("Subscriber counts per instance"))),
+    );
+    spec.add(
+        crate::browser_client::CLIENT_SCRIPT_PATH,
+        HttpMethod::Get,
+        operation("subscribe", "Browser client wrapping EventSource").response(
+            "200",
+            ResponseBuilder::new()
+                .description("JavaScript")
+                .content("text/javascript", ContentBuilder::new().schema(Some(string())).build())
+                .build(),
+        ),
+    );
+    if surface.cluster {
+        spec.add(
+            "/route",
+            HttpMethod::Get,
+            operation("subscribe", "Least loaded instance for a new subscriber")
+                .parameter(query_param("channel_id", "Channel the subscriber will join"))
+                .response("200", json_response("Chosen instance", object("Instance URL and load"))),
+        );
+    }
+
+    // Publishing
+    if let Some(push_path) = surface.push_path {
+        let (request, pushed) = (spec.schema::<PushRequest>(), spec.schema::<PushResponse>());
+        spec.add(
+            push_path,
+            HttpMethod::Post,
+            operation("publish", "Publish an event (JSON or a CloudEvents envelope)")
+                .request_body(json_body(request.clone()))
+                .response("200", json_response("Delivery result", pushed))
+                .response("401", response("Authentication failed"))
+                .response("413", response("Payload too large"))
+                .response("429", response("Throttled")),
+        );
+        let batch = spec.schema::<PushBatchResponse>();
+        let batch_path = format!("{}/batch", push_path.trim_end_matches('/'));
+        spec.add(
+            &batch_path,
+            HttpMethod::Post,
+            operation("publish", "Publish several events in one request")
+                .request_body(json_body(ArrayBuilder::new().items(request)))
+                .response("200", json_response("One result per event, in request order", batch)),
+        );
+    }
+
+    // Health and metrics
+    spec.add(
+        "/health",
+        HttpMethod::Get,
+        operation("health", "Liveness check").response("200", response("`OK`")),
+    );
+    spec.add(
+        "/ready",
+        HttpMethod::Get,
+        operation("health", "Readiness of the message source")
+            .response("200", json_response("Ready", object("Source name and supervisor state")))
+            .response("503", json_response("Restarting or failed", object("Source name and supervisor state"))),
+    );
+    if surface.metrics {
+        spec.add(
+            "/api/metrics",
+            HttpMethod::Get,
+            operation("health", "Gateway counters").response("200", json_response("Counters", object("Counters"))),
+        );
+        spec.add(
+            "/api/metrics/history",
+            HttpMethod::Get,
+            operation("health", "Recent metrics samples")
+                .response("200", json_response("Samples", object("Samples, oldest first"))),
+        );
+    }
+
+    // Admin
+    if surface.dashboard {
+        spec.add(
+            "/api/stats",
+            HttpMethod::Get,
+            operation("admin", "Connection statistics")
+                .response("200", json_response("Statistics", object("Connections and channels with send totals"))),
+        );
+        let (send, sent) = (spec.schema::<SendMessageRequest>(), spec.schema::<SendMessageResponse>());
+        spec.add(
+            "/api/send",
+            HttpMethod::Post,
+            operation("admin", "Send an event to a channel, an attribute value or everyone")
+                .request_body(json_body(send))
+                .response("200", json_response("Connections reached", sent.clone())),
+        );
+        let broadcast = spec.schema::<BroadcastRequest>();
+        spec.add(
+            "/admin/broadcast",
+            HttpMethod::Post,
+            operation("admin", "Send an event to targeted connections")
+                .request_body(json_body(broadcast))
+                .response("200", json_response("Connections reached", sent)),
+        );
+        spec.add(
+            "/api/channels",
+            HttpMethod::Get,
+            operation("admin", "Channels with subscriber counts")
+                .response("200", json_response("Channels", object("Channels and totals"))),
+        );
+        spec.add(
+            "/api/channels/{channel_id}/messages",
+            HttpMethod::Get,
+            operation("admin", "Recent stored messages")
+                .parameter(channel())
+                .parameter(query_param("limit", "Most messages returned (default 50)"))
+                .response("200", json_response("Messages", object("Stored messages, newest first"))),
+        );
+        let purged = spec.schema::<PurgeResponse>();
+        spec.add(
+            "/api/channels/{channel_id}/messages",
+            HttpMethod::Delete,
+            operation("admin", "Purge stored messages")
+                .parameter(channel())
+                .parameter(query_param("before", "Only purge events before this stream ID or RFC 3339 time"))
+                .response("200", json_response("Events deleted", purged)),
+        );
+        spec.add(
+            "/api/channels/{channel_id}/export",
+            HttpMethod::Get,
+            operation("admin", "Stored history as NDJSON").parameter(channel()).response(
+                "200",
+                ResponseBuilder::new()
+                    .description("One event per line, oldest first")
+                    .content("application/x-ndjson", ContentBuilder::new().schema(Some(string())).build())
+                    .build(),
+            ),
+        );
+        let imported = spec.schema::<ImportResponse>();
+        spec.add(
+            "/api/channels/{channel_id}/import",
+            HttpMethod::Post,
+            operation("admin", "Store exported NDJSON under its stream IDs")
+                .parameter(channel())
+                .request_body(Some(
+                    RequestBodyBuilder::new()
+                        .content("application/x-ndjson", ContentBuilder::new().schema(Some(string())).build())
+                        .build(),
+                ))
+                .response("200", json_response("Events stored and skipped", imported))
+                .response("400", response("Malformed NDJSON"))
+                .response("413", response("Body over the import limit")),
+        );
+        let usage = spec.schema::<UsageResponse>();
+        spec.add(
+            "/api/channels/{channel_id}/stats",
+            HttpMethod::Get,
+            operation("admin", "Daily usage totals")
+                .parameter(channel())
+                .parameter(query_param("from", "First day, YYYY-MM-DD (default: 29 days before `to`)"))
+                .parameter(query_param("to", "Last day, YYYY-MM-DD (default: today)"))
+                .response("200", json_response("Days with usage, oldest first", usage))
+                .response("400", response("Invalid range")),
+        );
+        let pause = spec.schema::<PauseRequest>();
+        spec.add(
+            "/api/channels/{channel_id}/pause",
+            HttpMethod::Post,
+            operation("admin", "Pause live delivery to a channel")
+                .parameter(channel())
+                .request_body(json_body(pause))
+                .response("200", json_response("The pause", object("Policy, reason and counters"))),
+        );
+        let resumed = spec.schema::<ResumeResponse>();
+        spec.add(
+            "/api/channels/{channel_id}/resume",
+            HttpMethod::Post,
+            operation("admin", "Resume live delivery, sending buffered events")
+                .parameter(channel())
+                .response("200", json_response("Events sent on", resumed)),
+        );
+        let config = || json_response("Override and effective config", object("Channel config"));
+        spec.add(
+            "/api/channels/{channel_id}/config",
+            HttpMethod::Get,
+            operation("admin", "Per-channel config").parameter(channel()).response("200", config()),
+        );
+        spec.add(
+            "/api/channels/{channel_id}/config",
+            HttpMethod::Put,
+            operation("admin", "Override a channel's config")
+                .parameter(channel())
+                .request_body(json_body(object("Channel config")))
+                .response("200", config()),
+        );
+        spec.add(
+            "/api/channels/{channel_id}/config",
+            HttpMethod::Delete,
+            operation("admin", "Remove a channel's override").parameter(channel()).response("200", config()),
+        );
+        if surface.cluster {
+            spec.add(
+                "/api/cluster",
+                HttpMethod::Get,
+                operation("admin", "Cluster instances and channel ownership")
+                    .response("200", json_response("Cluster", object("Instances"))),
+            );
+            spec.add(
+                "/api/cluster/channels/{channel_id}",
+                HttpMethod::Get,
+                operation("admin", "Instances owning a channel")
+                    .parameter(channel())
+                    .response("200", json_response("Owners", object("Owning instances"))),
+            );
+        }
+        if surface.webhooks {
+            let subscription = || object("Webhook subscription with delivery stats");
+            spec.add(
+                "/api/channels/{channel_id}/webhooks",
+                HttpMethod::Get,
+                operation("admin", "Webhook subscribers of a channel")
+                    .parameter(channel())
+                    .response("200", json_response("Subscriptions", ArrayBuilder::new().items(RefOr::T(subscription())))),
+            );
+            spec.add(
+                "/api/channels/{channel_id}/webhooks",
+                HttpMethod::Post,
+                operation("admin", "Subscribe a URL to a channel")
+                    .parameter(channel())
+                    .request_body(json_body(object("`url` and optional `secret`")))
+                    .response("201", json_response("Subscription", subscription()))
+                    .response("400", response("Invalid URL")),
+            );
+            spec.add(
+                "/api/channels/{channel_id}/webhooks/{webhook_id}",
+                HttpMethod::Delete,
+                operation("admin", "Remove a webhook subscriber")
+                    .parameter(channel())
+                    .parameter(path_param("webhook_id"))
+                    .response("204", response("Removed"))
+                    .response("404", response("Unknown subscriber")),
+            );
+        }
+    }
+
+    OpenApiBuilder::new()
+        .info(
+            InfoBuilder::new()
+                .title("SSE Gateway")
+                .version(env!("CARGO_PKG_VERSION"))
+                .description(Some("Server-Sent Events gateway: subscribe, publish and administer channels")),
+        )
+        .paths(spec.paths)
+        .components(Some(ComponentsBuilder::new().schemas_from_iter(spec.schemas).build()))
+        .build()
+}
+
+/// The rendered document, served as is
+#[derive(Clone)]
+pub(crate) struct OpenApiDocument(std::sync::Arc<str>);
+
+impl OpenApiDocument {
+    pub(crate) fn new(surface: &ApiSurface) -> Self {
+        let json = document(surface).to_json().expect("OpenAPI document serializes");
+        Self(json.into())
+    }
+
+    pub(crate) fn response(&self) -> Response {
+        ([(header::CONTENT_TYPE, "application/json")], self.0.to_string()).into_response()
+    }
+}
@@ -0,0 +1,114 @@
+//! Pausing delivery per channel
+//!
+//! During an incident, e.g. a producer flooding a channel with bad data,
+//! operators can pause a channel's live delivery without touching the
+//! producer. Events published meanwhile are still stored; live delivery
+//! either buffers them until the channel is resumed, or drops them:
+//!
+//! ```bash
+//! curl -X POST localhost:8080/api/channels/orders/pause -d '{"policy": "drop", "reason": "bad prices"}'
+//! curl localhost:8080/api/stats          # "paused_channels": [...]
+//! curl -X POST localhost:8080/api/channels/orders/resume
+//! ```
+//!
+//! Or in code, through [`ConnectionManager::pause_channel`](crate::ConnectionManager::pause_channel)
+//! and [`resume_channel`](crate::ConnectionManager::resume_channel). In
+//! cluster mode, pauses made through the endpoints are replicated by the
+//! [`ClusterCoordinator`](crate::ClusterCoordinator), and every instance
+//! follows them.
+//!
+//! Only live delivery to a channel's subscribers is paused: broadcasts,
+//! attribute targets and replay on reconnect are not.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::SseEvent;
+
+/// Events buffered per paused channel; older ones are dropped beyond this
+pub const PAUSE_BUFFER_LIMIT: usize = 10_000;
+
+/// What happens to events published to a paused channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PausePolicy {
+    /// Hold them, and deliver them in order on resume (default)
+    #[default]
+    Buffer,
+    /// Discard them; clients can still replay them from storage
+    Drop,
+}
+
+/// A channel's pause, as set by an operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelPause {
+    #[serde(default)]
+    pub policy: PausePolicy,
+    /// Why the channel was paused, for the dashboard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub paused_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ChannelPause {
+    /// A pause starting now
+    pub fn new(policy: PausePolicy) -> Self {
+        Self {
+            policy,
+            reason: None,
+            paused_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Record why the channel is paused
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// A paused channel, as reported in stats
+#[derive(Debug, Clone, Serialize)]
+pub struct PausedChannelStats {
+    pub channel_id: String,
+    #[serde(flatten)]
+    pub pause: ChannelPause,
+    /// Events held for delivery on resume
+    pub buffered: usize,
+    /// Events discarded while paused
+    pub dropped: u64,
+}
+
+/// A paused channel's held events
+#[derive(Debug)]
+pub(crate) struct PausedChannel {
+    pub(crate) pause: ChannelPause,
+    pub(crate) buffer: VecDeque<SseEvent>,
+    pub(crate) dropped: u64,
+}
+
+impl PausedChannel {
+    pub(crate) fn new(pause: ChannelPause) -> Self {
+        Self {
+            pause,
+            buffer: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Buffer or drop `event` per the policy
+    pub(crate) fn hold(&mut self, event: SseEvent) {
+        match self.pause.policy {
+            PausePolicy::Buffer => {
+                if self.buffer.len() >= PAUSE_BUFFER_LIMIT {
+                    self.buffer.pop_front();
+                    self.dropped += 1;
+                }
+                self.buffer.push_back(event);
+            }
+            PausePolicy::Drop => self.dropped += 1,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Payload size limits
+//!
+//! Caps how large an event's payload may be, so one producer publishing a
+//! huge blob can't fill every subscriber's buffer. A limit applies either to
+//! incoming messages (from the source, the push endpoint or a
+//! [`Publisher`](crate::Publisher)) or to events written to storage, and its
+//! [`OversizedPolicy`] decides what happens to a payload over it:
+//!
+//! - [`Reject`](OversizedPolicy::Reject) refuses the message
+//!   ([`DispatchError::TooLarge`], `413` from the push endpoint). For stored
+//!   events it skips storage; the event is still delivered live.
+//! - [`Truncate`](OversizedPolicy::Truncate) cuts the payload to the limit
+//!   and ends it with a marker (`…[truncated]` by default).
+//! - [`Externalize`](OversizedPolicy::Externalize) uploads the payload to a
+//!   [`BlobStore`] and replaces it with a pointer:
+//!   `{"blob_url": "<url>", "size": <bytes>}`.
+//!
+//! ```rust,ignore
+//! use sse_gateway::payload::{OversizedPolicy, PayloadLimit};
+//!
+//! Gateway::builder()
+//!     // Nothing over 1 MiB reaches subscribers
+//!     .payload_limit(PayloadLimit::new(1 << 20))
+//!     // Keep replay storage small; subscribers fetch big payloads from S3
+//!     .stored_payload_limit(PayloadLimit::new(64 << 10).oversized(OversizedPolicy::externalize(s3)))
+//! ```
+
+#[cfg(feature = "server")]
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+#[cfg(feature = "server")]
+use crate::event::{EventData, SseEvent};
+#[cfg(feature = "server")]
+use crate::metrics::GatewayMetrics;
+#[cfg(feature = "server")]
+use crate::source::DispatchError;
+
+/// Marker appended to truncated payloads unless overridden
+pub const DEFAULT_TRUNCATION_MARKER: &str = "…[truncated]";
+
+/// External store for payloads over a limit
+#[async_trait]
+pub trait BlobStore: Send + Sync + 'static {
+    /// Store `data` under `key`, returning the URL subscribers fetch it from
+    async fn put(&self, key: &str, data: &str) -> anyhow::Result<String>;
+}
+
+/// What to do with a payload over the limit
+#[derive(Clone, Default)]
+pub enum OversizedPolicy {
+    /// Refuse the message (default)
+    #[default]
+    Reject,
+    /// Cut the payload to the limit and append the truncation marker
+    Truncate,
+    /// Upload the payload and send a pointer to it instead
+    ///
+    /// If the upload fails the message is rejected.
+    Externalize(Arc<dyn BlobStore>),
+}
+
+impl OversizedPolicy {
+    /// Externalize oversized payloads to `store`
+    pub fn externalize(store: impl BlobStore) -> Self {
+        Self::Externalize(Arc::new(store))
+    }
+}
+
+impl std::fmt::Debug for OversizedPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reject => f.write_str("Reject"),
+            Self::Truncate => f.write_str("Truncate"),
+            Self::Externalize(_) => f.write_str("Externalize"),
+        }
+    }
+}
+
+/// Maximum payload size and what happens to payloads over it
+#[derive(Debug, Clone)]
+pub struct PayloadLimit {
+    max_bytes: usize,
+    policy: OversizedPolicy,
+    marker: String,
+}
+
+impl PayloadLimit {
+    /// Reject payloads larger than `max_bytes`
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            policy: OversizedPolicy::Reject,
+            marker: DEFAULT_TRUNCATION_MARKER.to_string(),
+        }
+    }
+
+    /// Handle oversized payloads with `policy` instead of rejecting them
+    pub fn oversized(mut self, policy: OversizedPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Marker ending truncated payloads (default: `…[truncated]`)
+    pub fn truncation_marker(mut self, marker: impl Into<String>) -> Self {
+        self.marker = marker.into();
+        self
+    }
+
+    /// The largest payload passed through unchanged, in bytes
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Apply the limit to a payload
+    ///
+    /// Returns `None` if it fits, or the payload to send in its place.
+    #[cfg(feature = "server")]
+    pub(crate) async fn apply(
+        &self,
+        channel_id: Option<&str>,
+        event_type: &str,
+        data: &str,
+        metrics: &GatewayMetrics,
+    ) -> Result<Option<String>, DispatchError> {
+        if data.len() <= self.max_bytes {
+            return Ok(None);
+        }
+        GatewayMetrics::incr(&metrics.oversized_payloads);
+        let too_large = DispatchError::TooLarge {
+            size: data.len(),
+            limit: self.max_bytes,
+        };
+        match &self.policy {
+            OversizedPolicy::Reject => {
+                tracing::warn!(
+                    channel_id = ?channel_id,
+                    event_type = %event_type,
+                    size = data.len(),
+                    limit = self.max_bytes,
+                    "Oversized payload rejected"
+                );
+                Err(too_large)
+            }
+            OversizedPolicy::Truncate => Ok(Some(self.truncate(data))),
+            OversizedPolicy::Externalize(store) => {
+                let key = format!(
+                    "{}/{}/{}",
+                    channel_id.unwrap_or("broadcast"),
+                    event_type,
+                    uuid::Uuid::new_v4()
+                );
+                match store.put(&key, data).await {
+                    Ok(url) => {
+                        let pointer = serde_json::json!({ "blob_url": url, "size": data.len() });
+                        Ok(Some(pointer.to_string()))
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            channel_id = ?channel_id,
+                            event_type = %event_type,
+                            error = %e,
+                            "Failed to externalize oversized payload"
+                        );
+                        Err(too_large)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Apply the limit to an event about to be stored
+    ///
+    /// Returns `None` if the event must not be stored.
+    #[cfg(feature = "server")]
+    pub(crate) async fn apply_to_event<'a>(
+        &self,
+        channel_id: &str,
+        event: &'a SseEvent,
+        metrics: &GatewayMetrics,
+    ) -> Option<Cow<'a, SseEvent>> {
+        let data = match &event.data {
+            EventData::Raw(data) => Cow::Borrowed(data.as_str()),
+            value => Cow::Owned(value.to_string()),
+        };
+        match self.apply(Some(channel_id), &event.event_type, &data, metrics).await {
+            Ok(None) => Some(Cow::Borrowed(event)),
+            // Built field by field so the oversized payload isn't copied
+            Ok(Some(data)) => Some(Cow::Owned(SseEvent {
+                event_type: event.event_type.clone(),
+                data: EventData::Raw(data),
+                id: event.id.clone(),
+                stream_id: event.stream_id.clone(),
+                retry: event.retry,
+                expires_at: event.expires_at,
+                priority: event.priority,
+            })),
+            Err(_) => None,
+        }
+    }
+
+    #[cfg(feature = "server")]
+    fn truncate(&self, data: &str) -> String {
+        let mut end = self.max_bytes.saturating_sub(self.marker.len());
+        while !data.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut truncated = String::with_capacity(end + self.marker.len());
+        truncated.push_str(&data[..end]);
+        truncated.push_str(&self.marker);
+        truncated
+    }
+}
@@ -0,0 +1,107 @@
+//! Synthetic presence events on connect and disconnect
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::cluster::PresenceConnection;
+use crate::manager::ConnectionManager;
+use crate::source::{ConnectionInfo, IncomingMessage, MessageHandler};
+
+/// Event type published when a connection joins a channel
+pub const PRESENCE_JOIN: &str = "presence.join";
+/// Event type published when a connection leaves a channel
+pub const PRESENCE_LEAVE: &str = "presence.leave";
+
+const CHANNEL_PLACEHOLDER: &str = "{channel_id}";
+
+#[derive(Serialize)]
+struct PresencePayload<'a> {
+    channel_id: &'a str,
+    #[serde(flatten)]
+    connection: &'a PresenceConnection,
+}
+
+/// Publishes `presence.join` / `presence.leave` through the dispatcher
+///
+/// Events are queued and dispatched one at a time, so a short-lived
+/// connection's leave never overtakes its join.
+pub(crate) struct PresenceEvents {
+    template: String,
+    /// Connections announced so far; their metadata is gone by the time they leave
+    joined: DashMap<String, PresenceConnection>,
+    queue: mpsc::UnboundedSender<IncomingMessage>,
+}
+
+impl PresenceEvents {
+    /// Start publishing to the channel named by `template`
+    pub(crate) fn spawn(
+        template: impl Into<String>,
+        handler: MessageHandler,
+        cancel: CancellationToken,
+    ) -> (Self, JoinHandle<()>) {
+        let (queue, mut receiver) = mpsc::unbounded_channel::<IncomingMessage>();
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    msg = receiver.recv() => {
+                        let Some(msg) = msg else { break };
+                        if let Err(e) = handler.dispatch(msg).await {
+                            tracing::warn!(error = %e, "Failed to publish presence event");
+                        }
+                    }
+                }
+            }
+        });
+        let events = Self {
+            template: template.into(),
+            joined: DashMap::new(),
+            queue,
+        };
+        (events, task)
+    }
+
+    /// Presence channel for `channel_id`, or `None` if it is itself a presence channel
+    fn target(&self, channel_id: &str) -> Option<String> {
+        match self.template.split_once(CHANNEL_PLACEHOLDER) {
+            Some((prefix, suffix)) => {
+                let is_presence = channel_id.len() >= prefix.len() + suffix.len()
+                    && channel_id.starts_with(prefix)
+                    && channel_id.ends_with(suffix);
+                (!is_presence).then(|| format!("{}{}{}", prefix, channel_id, suffix))
+            }
+            None => (channel_id != self.template).then(|| self.template.clone()),
+        }
+    }
+
+    pub(crate) fn join(&self, connection_manager: &ConnectionManager, info: &ConnectionInfo) {
+        let Some(target) = self.target(&info.channel_id) else { return };
+        let Some(connection) = connection_manager
+            .channel_connections(&info.channel_id)
+            .iter()
+            .find(|c| *c.id == *info.connection_id)
+            .map(PresenceConnection::from)
+        else {
+            return;
+        };
+        self.publish(PRESENCE_JOIN, &target, &info.channel_id, &connection);
+        self.joined.insert(info.connection_id.clone(), connection);
+    }
+
+    pub(crate) fn leave(&self, info: &ConnectionInfo) {
+        let Some((_, connection)) = self.joined.remove(&info.connection_id) else { return };
+        let Some(target) = self.target(&info.channel_id) else { return };
+        self.publish(PRESENCE_LEAVE, &target, &info.channel_id, &connection);
+    }
+
+    fn publish(&self, event_type: &str, target: &str, channel_id: &str, connection: &PresenceConnection) {
+        let payload = PresencePayload { channel_id, connection };
+        let Ok(data) = serde_json::to_string(&payload) else { return };
+        let _ = self
+            .queue
+            .send(IncomingMessage::new(event_type, data).with_channel(target));
+    }
+}
@@ -0,0 +1,69 @@
+//! In-process publishing
+//!
+//! [`ConnectionManager::send_to_channel`](crate::ConnectionManager::send_to_channel)
+//! writes straight to local connections: the event gets no stream ID, isn't
+//! stored and can't be replayed. A [`Publisher`] instead runs the same path
+//! as messages from the [`MessageSource`](crate::MessageSource): stream ID
+//! assignment, throttling, interceptors, fan-out (including other cluster
+//! instances) and storage.
+//!
+//! ```rust,ignore
+//! let gateway = Gateway::builder().source(NoopSource).storage(storage).build()?.start().await?;
+//! let publisher = gateway.publisher();
+//!
+//! let report = publisher.to_channel("orders", "order.created", r#"{"id":7}"#).await?;
+//! println!("stored as {:?}", report.stream_id);
+//! ```
+
+use crate::source::{DispatchResult, IncomingMessage, MessageHandler};
+
+/// Publishes events through the gateway's dispatcher
+///
+/// Obtain one from [`GatewayHandle::publisher`](crate::GatewayHandle::publisher);
+/// the dashboard's `POST /api/send` publishes through one as well. Clones
+/// share the dispatcher.
+#[derive(Clone)]
+pub struct Publisher {
+    handler: MessageHandler,
+}
+
+impl Publisher {
+    /// Publish through `handler`
+    pub fn new(handler: MessageHandler) -> Self {
+        Self { handler }
+    }
+
+    /// Publish a message and wait until it has been delivered and stored
+    pub async fn publish(&self, msg: IncomingMessage) -> DispatchResult {
+        self.handler.dispatch(msg).await
+    }
+
+    /// Publish an event to `channel_id`
+    pub async fn to_channel(
+        &self,
+        channel_id: impl Into<String>,
+        event_type: impl Into<String>,
+        data: impl Into<String>,
+    ) -> DispatchResult {
+        self.publish(IncomingMessage::new(event_type, data).with_channel(channel_id))
+            .await
+    }
+
+    /// Publish an event to every connection
+    pub async fn broadcast(&self, event_type: impl Into<String>, data: impl Into<String>) -> DispatchResult {
+        self.publish(IncomingMessage::broadcast(event_type, data)).await
+    }
+
+    /// Publish a message without waiting for the outcome
+    ///
+    /// Queued by priority like a source's [`MessageHandler::send`]. Must be
+    /// called from within a Tokio runtime.
+    pub fn send(&self, msg: IncomingMessage) {
+        self.handler.send(msg);
+    }
+
+    /// The underlying handler
+    pub fn handler(&self) -> &MessageHandler {
+        &self.handler
+    }
+}
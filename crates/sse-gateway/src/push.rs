@@ -0,0 +1,491 @@
+//! Built-in HTTP push endpoint
+//!
+//! Lets backends publish events with a plain `POST` instead of running a
+//! separate push server inside a custom [`MessageSource`](crate::MessageSource).
+//!
+//! ```bash
+//! curl -X POST http://localhost:8080/push \
+//!   -H "Content-Type: application/json" \
+//!   -d '{"channel_id": "user123", "event_type": "notification", "data": {"msg": "Hello!"}}'
+//! ```
+//!
+//! `POST {path}/batch` accepts a JSON array of the same objects and returns
+//! one result per message.
+//!
+//! Both endpoints also accept [CloudEvents](crate::cloudevents): structured
+//! (`application/cloudevents+json`, or `application/cloudevents-batch+json`
+//! for batches) and binary mode (`ce-*` headers).
+
+use axum::{
+    body::Bytes,
+    extract::{OriginalUri, State},
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::auth::{AuthFn, AuthRequest};
+use crate::client_ip::PeerAddr;
+use crate::cloudevents::{self, CloudEvent};
+use crate::event::{Priority, SseEvent};
+use crate::handler::{self, GatewayState};
+use crate::id::next_stream_id;
+use crate::interceptor::Decision;
+use crate::metrics::GatewayMetrics;
+use crate::source::IncomingMessage;
+use crate::storage::{store_event, MessageStorage};
+
+/// Maximum number of messages accepted by the batch endpoint
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// When pushed events are written to storage for replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PushStore {
+    /// Store every channel event (default)
+    #[default]
+    Always,
+    /// Store only when the channel has no local connections
+    IfOffline,
+    /// Never store; events are delivered live only
+    Never,
+}
+
+/// Configuration for the push endpoint
+#[derive(Clone)]
+pub struct PushEndpoint {
+    pub(crate) path: String,
+    pub(crate) auth: Option<AuthFn>,
+    pub(crate) store: PushStore,
+}
+
+impl PushEndpoint {
+    /// Push endpoint mounted at `path`
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            auth: None,
+            store: PushStore::default(),
+        }
+    }
+
+    /// Authenticate publishers
+    ///
+    /// Uses the same callback shape as [`GatewayBuilder::auth`](crate::GatewayBuilder::auth);
+    /// `channel_id` is empty for broadcasts.
+    pub fn auth<F, Fut>(mut self, auth_fn: F) -> Self
+    where
+        F: Fn(AuthRequest) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = crate::auth::AuthResponse> + Send + 'static,
+    {
+        self.auth = Some(crate::auth::auth_fn(auth_fn));
+        self
+    }
+
+    /// Set the storage behavior
+    pub fn store(mut self, store: PushStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Path the endpoint is mounted at
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Push request body
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PushRequest {
+    /// Target channel (omit to broadcast)
+    pub channel_id: Option<String>,
+    /// SSE event type (default: `message`)
+    #[serde(default = "default_event_type")]
+    pub event_type: String,
+    /// Event payload
+    pub data: serde_json::Value,
+    /// Business ID, used as the SSE `id` when the event is not stored
+    pub id: Option<String>,
+    /// Drop the event after this time (RFC 3339)
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Drop the event this many seconds after it was pushed (ignored if `expires_at` is set)
+    pub ttl_secs: Option<u64>,
+    /// Delivery priority (`high`, `normal` or `low`)
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+impl From<PushRequest> for IncomingMessage {
+    fn from(req: PushRequest) -> Self {
+        let mut msg = IncomingMessage {
+            channel_id: req.channel_id.filter(|c| !c.is_empty()).map(Into::into),
+            event_type: req.event_type,
+            data: req.data.to_string(),
+            id: req.id,
+            expires_at: req.expires_at,
+            priority: req.priority,
+            report: None,
+        };
+        if let (None, Some(ttl)) = (msg.expires_at, req.ttl_secs) {
+            msg = msg.with_ttl(std::time::Duration::from_secs(ttl));
+        }
+        msg
+    }
+}
+
+fn default_event_type() -> String {
+    "message".to_string()
+}
+
+/// Push response body
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PushResponse {
+    /// Event was delivered to at least one connection or stored for replay
+    pub success: bool,
+    /// The channel had connections when the event arrived (on any instance in cluster mode)
+    pub online: bool,
+    /// Number of connections the event was sent to
+    pub delivered: usize,
+    /// Replay cursor, if the event was stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    /// Event was written to storage
+    pub stored: bool,
+    /// Why the event was not accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PushResponse {
+    fn not_sent(reason: &str) -> Self {
+        Self {
+            success: false,
+            online: false,
+            delivered: 0,
+            stream_id: None,
+            stored: false,
+            error: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Batch push response body
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PushBatchResponse {
+    /// One result per request, in request order
+    pub results: Vec<PushResponse>,
+}
+
+/// `POST {path}` handler
+pub(crate) async fn push_message<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    PeerAddr(peer): PeerAddr,
+    body: Bytes,
+) -> Response {
+    let Some(config) = state.push.clone() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let client_ip = handler::client_ip(&state, &headers, peer);
+    let msg = match parse_message(&headers, &body) {
+        Ok(msg) => msg,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let channels = [channel_of(&msg)];
+    if let Err(response) = authorize(&config, &method, &uri, &headers, client_ip.as_deref(), channels).await {
+        return response;
+    }
+    let mut messages = vec![msg];
+    if let Err(rejection) = scope_to_tenant(&state, &method, &uri, &headers, client_ip.as_deref(), &mut messages) {
+        return rejection.into_response();
+    }
+
+    let (status, mut results) = publish(&state, &config, messages).await;
+    (status, Json(results.remove(0))).into_response()
+}
+
+/// `POST {path}/batch` handler
+pub(crate) async fn push_batch<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    PeerAddr(peer): PeerAddr,
+    body: Bytes,
+) -> Response {
+    let Some(config) = state.push.clone() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let client_ip = handler::client_ip(&state, &headers, peer);
+    let messages = match parse_batch(&headers, &body) {
+        Ok(messages) => messages,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if messages.len() > MAX_BATCH_SIZE {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Batch exceeds {} messages", MAX_BATCH_SIZE),
+        )
+            .into_response();
+    }
+
+    let channels: HashSet<_> = messages.iter().map(channel_of).collect();
+    if let Err(response) = authorize(&config, &method, &uri, &headers, client_ip.as_deref(), channels).await {
+        return response;
+    }
+    let mut messages = messages;
+    if let Err(rejection) = scope_to_tenant(&state, &method, &uri, &headers, client_ip.as_deref(), &mut messages) {
+        return rejection.into_response();
+    }
+
+    let (_, results) = publish(&state, &config, messages).await;
+    Json(PushBatchResponse { results }).into_response()
+}
+
+fn channel_of(msg: &IncomingMessage) -> Option<Arc<str>> {
+    msg.channel_id.clone().filter(|c| !c.is_empty())
+}
+
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok())
+}
+
+/// Parse a single push body: structured or binary CloudEvent, or a [`PushRequest`]
+fn parse_message(headers: &HeaderMap, body: &[u8]) -> anyhow::Result<IncomingMessage> {
+    let content_type = content_type(headers);
+    if content_type.is_some_and(|ct| ct.starts_with(cloudevents::CONTENT_TYPE)) {
+        let event = CloudEvent::from_json(&String::from_utf8_lossy(body))?;
+        return Ok(event.into());
+    }
+    if headers.contains_key("ce-specversion") {
+        let attribute = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        return Ok(CloudEvent::from_binary(attribute, content_type, body)?.into());
+    }
+    let req: PushRequest =
+        serde_json::from_slice(body).map_err(|e| anyhow::anyhow!("Invalid push request: {}", e))?;
+    Ok(req.into())
+}
+
+/// Parse a batch push body: a CloudEvents batch or an array of [`PushRequest`]s
+fn parse_batch(headers: &HeaderMap, body: &[u8]) -> anyhow::Result<Vec<IncomingMessage>> {
+    if content_type(headers).is_some_and(|ct| ct.starts_with(cloudevents::BATCH_CONTENT_TYPE)) {
+        let events = CloudEvent::batch_from_json(&String::from_utf8_lossy(body))?;
+        return Ok(events.into_iter().map(Into::into).collect());
+    }
+    let requests: Vec<PushRequest> =
+        serde_json::from_slice(body).map_err(|e| anyhow::anyhow!("Invalid push batch: {}", e))?;
+    Ok(requests.into_iter().map(Into::into).collect())
+}
+
+/// Run the auth hook once per distinct target channel
+///
+/// A batch is rejected as a whole if any of its channels is denied.
+async fn authorize(
+    config: &PushEndpoint,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    client_ip: Option<&str>,
+    channels: impl IntoIterator<Item = Option<Arc<str>>>,
+) -> Result<(), Response> {
+    let Some(auth_fn) = &config.auth else {
+        return Ok(());
+    };
+    for channel_id in channels {
+        let auth_request = AuthRequest {
+            method: method.clone(),
+            uri: uri.clone(),
+            headers: headers.clone(),
+            channel_id: channel_id.as_deref().unwrap_or_default().to_string(),
+            client_ip: client_ip.map(str::to_string),
+        };
+        if let Some(response) = auth_fn(auth_request).await {
+            tracing::warn!(channel_id = ?channel_id, "Push denied");
+            return Err(response);
+        }
+    }
+    Ok(())
+}
+
+/// Move every message into the publisher's tenant namespace
+///
+/// With tenancy enabled, the tenant is resolved from the push request itself
+/// and broadcasts are refused, as they would reach every tenant.
+fn scope_to_tenant<S: MessageStorage>(
+    state: &GatewayState<S>,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    client_ip: Option<&str>,
+    messages: &mut [IncomingMessage],
+) -> Result<(), (StatusCode, &'static str)> {
+    if state.tenancy.is_none() {
+        return Ok(());
+    }
+    if messages.iter().any(|msg| channel_of(msg).is_none()) {
+        return Err((StatusCode::BAD_REQUEST, "channel_id is required when tenancy is enabled"));
+    }
+    let auth_request = AuthRequest {
+        method: method.clone(),
+        uri: uri.clone(),
+        headers: headers.clone(),
+        channel_id: String::new(),
+        client_ip: client_ip.map(str::to_string),
+    };
+    for msg in messages.iter_mut() {
+        let channel_id = msg.channel_id.take().as_deref().unwrap_or_default().to_string();
+        msg.channel_id = Some(handler::tenant_channel(state, Some(&auth_request), channel_id)?.into());
+    }
+    Ok(())
+}
+
+/// Validate, intercept, limit, throttle, store and deliver pushed events
+///
+/// Returns the status of the last refused event (or 200) and one result per request.
+async fn publish<S: MessageStorage>(
+    state: &GatewayState<S>,
+    push: &PushEndpoint,
+    messages: Vec<IncomingMessage>,
+) -> (StatusCode, Vec<PushResponse>) {
+    let mut status = StatusCode::OK;
+    let mut results = Vec::with_capacity(messages.len());
+    let mut batch = Vec::with_capacity(messages.len());
+
+    let interceptors = state.connection_manager.interceptors();
+
+    for mut msg in messages {
+        #[cfg(feature = "schema")]
+        if let Some(schema) = &state.schema {
+            if let Err(error) = schema.check(&msg, state.connection_manager.metrics()) {
+                status = StatusCode::UNPROCESSABLE_ENTITY;
+                results.push(PushResponse::not_sent(&format!("invalid: {}", error)));
+                continue;
+            }
+        }
+
+        if msg.is_expired() {
+            results.push(PushResponse::not_sent("expired"));
+            continue;
+        }
+
+        if interceptors.before_dispatch(&mut msg) == Decision::Drop {
+            results.push(PushResponse::not_sent("filtered"));
+            continue;
+        }
+
+        let channel_id = msg.channel_id.filter(|c| !c.is_empty());
+        let config = channel_id
+            .as_deref()
+            .map(|channel_id| state.connection_manager.channel_configs().resolve(channel_id))
+            .unwrap_or_default();
+        if !config.allows(&msg.event_type) {
+            status = StatusCode::UNPROCESSABLE_ENTITY;
+            results.push(PushResponse::not_sent("event type not allowed"));
+            continue;
+        }
+
+        if let Some(limit) = &state.payload_limit {
+            let metrics = state.connection_manager.metrics();
+            match limit.apply(channel_id.as_deref(), &msg.event_type, &msg.data, metrics).await {
+                Ok(Some(data)) => msg.data = data,
+                Ok(None) => {}
+                Err(error) => {
+                    status = StatusCode::PAYLOAD_TOO_LARGE;
+                    results.push(PushResponse::not_sent(&error.to_string()));
+                    continue;
+                }
+            }
+        }
+
+        if let Err((throttled, _)) = handler::throttle(state, channel_id.as_deref(), msg.data.len()) {
+            status = throttled;
+            results.push(PushResponse::not_sent("throttled"));
+            continue;
+        }
+
+        let mut event = SseEvent::raw(&msg.event_type, msg.data);
+        if let Some(id) = msg.id {
+            event = event.with_id(id);
+        }
+        event.expires_at = msg.expires_at;
+        event.priority = msg.priority;
+
+        let local_online = match &channel_id {
+            Some(channel_id) => state.connection_manager.channel_connection_count(channel_id) > 0,
+            None => state.connection_manager.connection_count() > 0,
+        };
+        let peers = match &state.cluster {
+            Some(cluster) => cluster.peers_for(channel_id.as_deref()).await,
+            None => Vec::new(),
+        };
+        let online = local_online || !peers.is_empty();
+        let store = channel_id.is_some()
+            && config.stores()
+            && match push.store {
+                PushStore::Always => true,
+                PushStore::IfOffline => !online,
+                PushStore::Never => false,
+            };
+        let stream_id = if store {
+            Some(next_stream_id(&state.ids, &state.storage)).filter(|id| !id.is_empty())
+        } else {
+            None
+        };
+        event.stream_id = stream_id.clone();
+
+        results.push(PushResponse {
+            success: false,
+            online,
+            delivered: 0,
+            stream_id,
+            stored: false,
+            error: None,
+        });
+        batch.push((results.len() - 1, channel_id, event, peers));
+    }
+
+    // Store before delivering so a client reconnecting mid-batch can replay
+    for (index, channel_id, event, _) in &batch {
+        if let (Some(channel_id), Some(stream_id)) = (channel_id, &results[*index].stream_id) {
+            results[*index].stored = store_event(
+                &state.storage,
+                &state.compact_on,
+                state.stored_payload_limit.as_ref(),
+                state.connection_manager.metrics(),
+                channel_id,
+                stream_id,
+                event,
+            )
+            .await;
+        }
+    }
+
+    if let Some(cluster) = &state.cluster {
+        for (index, channel_id, event, peers) in &batch {
+            cluster.forward(peers, channel_id.as_deref(), event).await;
+            results[*index].success = !peers.is_empty();
+        }
+    }
+
+    let (indices, events): (Vec<_>, Vec<_>) = batch
+        .into_iter()
+        .map(|(index, channel_id, event, _)| (index, (channel_id, event)))
+        .unzip();
+    let delivered = state.connection_manager.send_batch(events).await;
+
+    let metrics = state.connection_manager.metrics();
+    for (index, delivered) in indices.into_iter().zip(delivered) {
+        let result = &mut results[index];
+        result.delivered = delivered;
+        result.success |= delivered > 0 || result.stored;
+        GatewayMetrics::incr(&metrics.messages_dispatched);
+    }
+
+    (status, results)
+}
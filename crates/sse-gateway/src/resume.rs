@@ -0,0 +1,215 @@
+//! Resume tokens for SSE subscribers
+//!
+//! With [`GatewayBuilder::resume_tokens`](crate::GatewayBuilder::resume_tokens),
+//! every SSE connection starts with a `connected` event carrying an opaque
+//! token. Reconnecting with `?resume=<token>` restores the channel, query
+//! attributes, consumer, group and cursor of the previous connection, so the
+//! client doesn't have to specify them again. Parameters sent along with the
+//! token override the restored ones.
+//!
+//! Sessions are kept in memory by the instance that issued them, and expire
+//! once their last connection has been closed for longer than the TTL.
+//!
+//! With [`GatewayBuilder::persist_resume_sessions`](crate::GatewayBuilder::persist_resume_sessions)
+//! sessions are also saved in the storage backend when connections open and
+//! close, periodically while they stay open, and at shutdown. A token this
+//! instance doesn't know is then looked up in storage, so clients resume
+//! after a restart or on another instance sharing the storage. Persisted
+//! sessions also cache the outcome of the auth, identity, attribute and
+//! event type callbacks; resuming on the same channel reuses it instead of
+//! calling them again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::EventTypeFilter;
+
+/// Query parameter carrying a resume token
+pub(crate) const RESUME_PARAM: &str = "resume";
+
+/// Event announcing the resume token at the start of each connection
+pub(crate) const CONNECTED_EVENT: &str = "connected";
+
+/// What a resumed connection restores
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ResumeSession {
+    /// Channel as requested, before tenant scoping
+    pub channel_id: String,
+    /// Attributes taken from query parameters
+    pub attributes: HashMap<String, String>,
+    pub consumer_id: Option<String>,
+    pub group: Option<String>,
+    /// Replayed events per `replay_batch` event, if batching was requested
+    pub replay_batch: Option<usize>,
+    /// Stream ID of the last event written to the client
+    pub cursor: Option<String>,
+    /// Callback results for the session's channel, if cached
+    pub auth: Option<CachedAuth>,
+}
+
+/// What the subscriber callbacks decided for a session's channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CachedAuth {
+    pub identity: Option<String>,
+    /// Attributes from the attribute callback
+    pub attributes: HashMap<String, String>,
+    pub event_types: Option<EventTypeFilter>,
+}
+
+/// Writes a session, as JSON, to storage under its token
+pub(crate) type SessionSaver = Arc<dyn Fn(String, String) -> BoxFuture<'static, ()> + Send + Sync>;
+
+struct Entry {
+    session: ResumeSession,
+    /// Open connections using the session
+    connections: usize,
+    /// When the last connection closed
+    idle_since: Option<Instant>,
+}
+
+/// Resume sessions by token
+pub(crate) struct ResumeSessions {
+    ttl: Duration,
+    entries: DashMap<String, Arc<Mutex<Entry>>>,
+    saver: Option<SessionSaver>,
+}
+
+impl ResumeSessions {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: DashMap::new(),
+            saver: None,
+        }
+    }
+
+    /// Also save sessions with `saver`
+    pub(crate) fn persisted(mut self, saver: SessionSaver) -> Self {
+        self.saver = Some(saver);
+        self
+    }
+
+    /// Whether sessions are saved to storage
+    pub(crate) fn is_persisted(&self) -> bool {
+        self.saver.is_some()
+    }
+
+    /// A session as saved by [`persist`](Self::persist), if it parses
+    pub(crate) fn parse(json: &str) -> Option<ResumeSession> {
+        serde_json::from_str(json).ok()
+    }
+
+    /// The session `token` refers to, unless unknown or expired
+    pub(crate) fn get(&self, token: &str) -> Option<ResumeSession> {
+        let entry = self.entries.get(token)?.clone();
+        let entry = entry.lock().ok()?;
+        (!self.expired(&entry)).then(|| entry.session.clone())
+    }
+
+    /// Start a connection of the session `token`, or of a new session
+    pub(crate) fn open(&self, token: Option<String>, session: ResumeSession) -> SessionLease {
+        let token = token.unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+        let entry = self
+            .entries
+            .entry(token.clone())
+            .or_insert_with(|| {
+                Arc::new(Mutex::new(Entry {
+                    session: ResumeSession::default(),
+                    connections: 0,
+                    idle_since: None,
+                }))
+            })
+            .clone();
+        if let Ok(mut entry) = entry.lock() {
+            entry.session = session;
+            entry.connections += 1;
+            entry.idle_since = None;
+            spawn_save(self.saver.as_ref(), &token, &entry.session);
+        }
+        SessionLease {
+            token,
+            entry,
+            saver: self.saver.clone(),
+        }
+    }
+
+    /// Save sessions to storage, all of them or only those with open connections
+    pub(crate) async fn persist(&self, active_only: bool) {
+        let Some(saver) = &self.saver else {
+            return;
+        };
+        let saves: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|item| {
+                let entry = item.value().lock().ok()?;
+                if (active_only && entry.connections == 0) || self.expired(&entry) {
+                    return None;
+                }
+                let json = serde_json::to_string(&entry.session).ok()?;
+                Some(saver(item.key().clone(), json))
+            })
+            .collect();
+        futures::future::join_all(saves).await;
+    }
+
+    /// Forget sessions idle for longer than the TTL
+    pub(crate) fn cleanup_expired(&self) {
+        self.entries
+            .retain(|_, entry| entry.lock().map(|entry| !self.expired(&entry)).unwrap_or(false));
+    }
+
+    fn expired(&self, entry: &Entry) -> bool {
+        entry.idle_since.is_some_and(|since| since.elapsed() > self.ttl)
+    }
+}
+
+/// Save `session` in the background, if sessions are persisted
+fn spawn_save(saver: Option<&SessionSaver>, token: &str, session: &ResumeSession) {
+    let (Some(saver), Ok(runtime)) = (saver, tokio::runtime::Handle::try_current()) else {
+        return;
+    };
+    if let Ok(json) = serde_json::to_string(session) {
+        runtime.spawn(saver(token.to_string(), json));
+    }
+}
+
+/// A connection's hold on its session; starts the TTL when dropped
+pub(crate) struct SessionLease {
+    token: String,
+    entry: Arc<Mutex<Entry>>,
+    saver: Option<SessionSaver>,
+}
+
+impl SessionLease {
+    pub(crate) fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Remember `stream_id` as written to the client
+    pub(crate) fn record(&self, stream_id: &str) {
+        if let Ok(mut entry) = self.entry.lock() {
+            entry.session.cursor = Some(stream_id.to_string());
+        }
+    }
+}
+
+impl Drop for SessionLease {
+    fn drop(&mut self) {
+        if let Ok(mut entry) = self.entry.lock() {
+            entry.connections = entry.connections.saturating_sub(1);
+            if entry.connections == 0 {
+                entry.idle_since = Some(Instant::now());
+                // Save the final cursor; the stored copy's TTL starts now too
+                spawn_save(self.saver.as_ref(), &self.token, &entry.session);
+            }
+        }
+    }
+}
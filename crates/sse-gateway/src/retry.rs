@@ -0,0 +1,76 @@
+//! Reconnect delay advertised to clients
+//!
+//! `EventSource` reconnects after a dropped stream using the last `retry:`
+//! value it received, or a browser default of a few seconds. When an
+//! instance restarts, all its subscribers reconnect at once; with a
+//! [`RetryPolicy`] every stream starts with its own `retry:` picked from a
+//! range, spreading those reconnects out:
+//!
+//! ```rust,ignore
+//! Gateway::builder()
+//!     .retry_policy(RetryPolicy::jittered(Duration::from_secs(1), Duration::from_secs(5)))
+//!     .migration_hints(Duration::from_millis(500))   // `migrate` events use the policy too
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Reconnect delay sent in the SSE `retry:` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    min: Duration,
+    max: Duration,
+    on_connect: bool,
+}
+
+impl RetryPolicy {
+    /// Always `retry`
+    pub fn fixed(retry: Duration) -> Self {
+        Self::jittered(retry, retry)
+    }
+
+    /// A random delay between `min` and `max`, picked per connection
+    pub fn jittered(min: Duration, max: Duration) -> Self {
+        Self {
+            min: min.min(max),
+            max: max.max(min),
+            on_connect: true,
+        }
+    }
+
+    /// Start each SSE stream with a `retry:` field (default: true)
+    ///
+    /// When disabled, the policy only applies to `migrate` events.
+    pub fn on_connect(mut self, enable: bool) -> Self {
+        self.on_connect = enable;
+        self
+    }
+
+    /// Whether streams start with a `retry:` field
+    #[cfg(feature = "server")]
+    pub(crate) fn sends_on_connect(&self) -> bool {
+        self.on_connect
+    }
+
+    /// Pick a delay from the range
+    pub fn pick(&self) -> Duration {
+        let spread = (self.max - self.min).as_secs_f64();
+        self.min + Duration::from_secs_f64(spread * random())
+    }
+
+    /// [`pick`](Self::pick) in milliseconds, as sent in `retry:`
+    pub fn pick_millis(&self) -> u32 {
+        self.pick().as_millis().min(u32::MAX as u128) as u32
+    }
+}
+
+/// Uniform random number in `[0, 1)`
+pub(crate) fn random() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    // RandomState is randomly keyed; hashing a counter gives a fresh value per call
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
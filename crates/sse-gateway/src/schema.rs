@@ -0,0 +1,88 @@
+//! JSON Schema validation of incoming payloads
+//!
+//! Schemas are registered per event type. Messages whose event type has a
+//! schema must carry a JSON payload that validates against it; other event
+//! types pass through unchecked.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+#[cfg(feature = "server")]
+use crate::metrics::GatewayMetrics;
+use crate::source::IncomingMessage;
+
+/// Callback receiving invalid messages and the validation error
+pub type DeadLetterFn = Arc<dyn Fn(&IncomingMessage, &str) + Send + Sync>;
+
+/// Per-event-type payload validator
+#[derive(Default)]
+pub struct SchemaValidator {
+    validators: HashMap<String, jsonschema::Validator>,
+    dead_letter: Option<DeadLetterFn>,
+}
+
+impl SchemaValidator {
+    /// Create a validator with no schemas
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `schema` for `event_type`, replacing any previous schema
+    pub fn register(&mut self, event_type: impl Into<String>, schema: &Value) -> anyhow::Result<()> {
+        let event_type = event_type.into();
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| anyhow::anyhow!("Invalid schema for event type '{}': {}", event_type, e))?;
+        self.validators.insert(event_type, validator);
+        Ok(())
+    }
+
+    /// Hand invalid messages to `f` instead of only discarding them
+    pub fn dead_letter(mut self, f: DeadLetterFn) -> Self {
+        self.dead_letter = Some(f);
+        self
+    }
+
+    /// Whether any schemas are registered
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// Validate a message against the schema for its event type
+    pub fn validate(&self, msg: &IncomingMessage) -> Result<(), String> {
+        let Some(validator) = self.validators.get(&msg.event_type) else {
+            return Ok(());
+        };
+        let payload: Value = serde_json::from_str(&msg.data)
+            .map_err(|e| format!("payload is not valid JSON: {}", e))?;
+        validator.validate(&payload).map_err(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{} at {}", e, path)
+            }
+        })
+    }
+
+    /// Validate, counting failures and dead-lettering invalid messages
+    #[cfg(feature = "server")]
+    pub(crate) fn check(&self, msg: &IncomingMessage, metrics: &GatewayMetrics) -> Result<(), String> {
+        let result = self.validate(msg);
+        if let Err(error) = &result {
+            GatewayMetrics::incr(&metrics.validation_failed);
+            tracing::warn!(
+                channel_id = ?msg.channel_id,
+                event_type = %msg.event_type,
+                error = %error,
+                "Payload failed schema validation"
+            );
+            if let Some(dead_letter) = &self.dead_letter {
+                GatewayMetrics::incr(&metrics.dead_lettered);
+                dead_letter(msg, error);
+            }
+        }
+        result
+    }
+}
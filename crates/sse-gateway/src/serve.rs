@@ -0,0 +1,347 @@
+//! HTTP server tuning for the built-in listeners
+//!
+//! Long-lived SSE responses are easily cut off by intermediaries that drop
+//! idle TCP flows, and stalled clients can pin connections forever. These
+//! options apply to listeners started by [`Gateway::run`](crate::Gateway::run);
+//! routers obtained from `into_router` are served however the host app chooses.
+//!
+//! ```rust,ignore
+//! use sse_gateway::ServerOptions;
+//!
+//! Gateway::builder().server_options(
+//!     ServerOptions::new()
+//!         .tcp_keepalive(Duration::from_secs(30))
+//!         .http2_keep_alive_interval(Duration::from_secs(20))
+//!         .http2_max_concurrent_streams(500)
+//!         .write_timeout(Duration::from_secs(15)),
+//! )
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::{Extension, Router};
+use hyper::server::conn::http1::Builder as Http1Builder;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder as AutoBuilder;
+use hyper_util::service::TowerToHyperService;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::time::Sleep;
+use tower::Layer;
+
+/// Server tuning knobs
+///
+/// By default both HTTP/1.1 and HTTP/2 are served (h2c in the clear, ALPN
+/// with TLS), TCP keepalive is off, Nagle's algorithm is left on, and there
+/// is no write timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerOptions {
+    http2: bool,
+    http2_max_concurrent_streams: Option<u32>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_keep_alive_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    tcp_nodelay: bool,
+    header_read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            http2: true,
+            http2_max_concurrent_streams: None,
+            http2_keep_alive_interval: None,
+            http2_keep_alive_timeout: None,
+            tcp_keepalive: None,
+            tcp_nodelay: false,
+            header_read_timeout: None,
+            write_timeout: None,
+        }
+    }
+}
+
+impl ServerOptions {
+    /// Default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept HTTP/2 alongside HTTP/1.1 (default: true)
+    ///
+    /// With TLS, HTTP/2 is negotiated via ALPN; when disabling it, make sure a
+    /// custom [`tls_config`](crate::GatewayBuilder::tls_config) doesn't offer `h2`.
+    pub fn http2(mut self, enable: bool) -> Self {
+        self.http2 = enable;
+        self
+    }
+
+    /// Maximum concurrent streams per HTTP/2 connection (hyper default: 200)
+    ///
+    /// Each SSE subscription holds a stream open, so raise this for clients
+    /// that multiplex many channels over one connection.
+    pub fn http2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.http2_max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Send HTTP/2 PING frames at this interval to keep idle connections open
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Close the connection if a keep-alive PING isn't acknowledged in time (hyper default: 20s)
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable TCP keepalive probes after this much idle time
+    ///
+    /// Set on each accepted TCP connection, with or without TLS.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Disable Nagle's algorithm so small events are sent without delay
+    ///
+    /// Set on each accepted TCP connection, with or without TLS.
+    pub fn tcp_nodelay(mut self, enable: bool) -> Self {
+        self.tcp_nodelay = enable;
+        self
+    }
+
+    /// Close HTTP/1 connections whose request headers take longer than this (hyper default: 30s)
+    pub fn header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.header_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Drop connections whose socket stays unwritable for this long
+    ///
+    /// Frees slow or vanished clients whose receive window has filled up.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Apply the socket-level options to an accepted connection
+    fn configure_stream(&self, stream: &TcpStream) -> io::Result<()> {
+        let socket = socket2::SockRef::from(stream);
+        if let Some(idle) = self.tcp_keepalive {
+            socket.set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(idle))?;
+        }
+        if self.tcp_nodelay {
+            socket.set_tcp_nodelay(true)?;
+        }
+        Ok(())
+    }
+
+    fn auto_builder(&self) -> AutoBuilder<TokioExecutor> {
+        let mut builder = AutoBuilder::new(TokioExecutor::new());
+        builder.http1().timer(TokioTimer::new());
+        builder.http2().timer(TokioTimer::new());
+
+        if let Some(timeout) = self.header_read_timeout {
+            builder.http1().header_read_timeout(timeout);
+        }
+        if let Some(max) = self.http2_max_concurrent_streams {
+            builder.http2().max_concurrent_streams(max);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder.http2().keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder.http2().keep_alive_timeout(timeout);
+        }
+        builder
+    }
+
+    fn http1_builder(&self) -> Http1Builder {
+        let mut builder = Http1Builder::new();
+        builder.timer(TokioTimer::new());
+        if let Some(timeout) = self.header_read_timeout {
+            builder.header_read_timeout(timeout);
+        }
+        builder
+    }
+}
+
+/// Accepted streams the socket-level [`ServerOptions`] apply to
+pub(crate) trait AcceptedStream {
+    /// The TCP connection underneath, if there is one
+    fn tcp(&self) -> Option<&TcpStream>;
+}
+
+impl AcceptedStream for TcpStream {
+    fn tcp(&self) -> Option<&TcpStream> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl AcceptedStream for tokio_rustls::server::TlsStream<TcpStream> {
+    fn tcp(&self) -> Option<&TcpStream> {
+        Some(self.get_ref().0)
+    }
+}
+
+#[cfg(unix)]
+impl AcceptedStream for tokio::net::UnixStream {
+    fn tcp(&self) -> Option<&TcpStream> {
+        None
+    }
+}
+
+/// Serve `app` on `listener` until `shutdown` completes, then drain connections
+pub(crate) async fn serve<L>(
+    mut listener: L,
+    app: Router,
+    options: ServerOptions,
+    shutdown: impl Future<Output = ()> + Send,
+) -> io::Result<()>
+where
+    L: axum::serve::Listener,
+    L::Io: AcceptedStream,
+    L::Addr: Clone + Sync,
+{
+    // The auto builder ignores `http1_only` when upgrades are enabled, so
+    // HTTP/1-only serving goes through hyper's builder directly
+    let auto = options.http2.then(|| options.auto_builder());
+    let http1 = options.http1_builder();
+
+    // Every connection holds a receiver; the sender closes once all have drained
+    let (drain_tx, drain_rx) = watch::channel(());
+    tokio::pin!(shutdown);
+
+    loop {
+        let (io, addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+
+        if let Some(stream) = io.tcp() {
+            if let Err(e) = options.configure_stream(stream) {
+                tracing::debug!(error = %e, "Failed to set socket options");
+            }
+        }
+        let io = TokioIo::new(WriteTimeout::new(io, options.write_timeout));
+        // Handlers read the peer as `ConnectInfo<SocketAddr>` (TCP and TLS listeners)
+        let service = TowerToHyperService::new(Extension(ConnectInfo(addr)).layer(app.clone()));
+        let drain = drain_rx.clone();
+        // Upgrades are needed for the WebSocket endpoint
+        match &auto {
+            Some(builder) => {
+                let connection = builder.serve_connection_with_upgrades(io, service).into_owned();
+                tokio::spawn(drive(connection, drain, |c| c.graceful_shutdown()));
+            }
+            None => {
+                let connection = http1.serve_connection(io, service).with_upgrades();
+                tokio::spawn(drive(connection, drain, |c| c.graceful_shutdown()));
+            }
+        }
+    }
+
+    drop(listener);
+    drop(drain_rx);
+    let _ = drain_tx.send(());
+    drain_tx.closed().await;
+    Ok(())
+}
+
+/// Run a connection, shutting it down gracefully when the drain signal fires
+async fn drive<C, E>(connection: C, mut drain: watch::Receiver<()>, graceful_shutdown: fn(Pin<&mut C>))
+where
+    C: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    tokio::pin!(connection);
+    let result = tokio::select! {
+        result = connection.as_mut() => result,
+        _ = drain.changed() => {
+            graceful_shutdown(connection.as_mut());
+            connection.await
+        }
+    };
+    if let Err(e) = result {
+        tracing::debug!(error = %e, "Connection closed with error");
+    }
+}
+
+/// IO wrapper failing writes that stay pending longer than a timeout
+struct WriteTimeout<T> {
+    inner: T,
+    timeout: Option<Duration>,
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> WriteTimeout<T> {
+    fn new(inner: T, timeout: Option<Duration>) -> Self {
+        Self {
+            inner,
+            timeout,
+            deadline: None,
+        }
+    }
+
+    /// Track how long a write has been pending; error once it exceeds the timeout
+    fn check<R>(&mut self, cx: &mut Context<'_>, poll: Poll<io::Result<R>>) -> Poll<io::Result<R>> {
+        let Some(timeout) = self.timeout else {
+            return poll;
+        };
+        if poll.is_ready() {
+            self.deadline = None;
+            return poll;
+        }
+        let deadline = self
+            .deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        match deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(io::Error::new(io::ErrorKind::TimedOut, "write timed out"))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for WriteTimeout<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for WriteTimeout<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        self.check(cx, poll)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        self.check(cx, poll)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_flush(cx);
+        self.check(cx, poll)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
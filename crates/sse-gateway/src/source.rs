@@ -3,22 +3,38 @@
 //! Implement `MessageSource` to receive messages from any backend.
 
 use async_trait::async_trait;
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
 
+use crate::cloudevents::{self, CloudEvent};
+use crate::connection::CloseReason;
+use crate::dispatch_queue::{DispatchQueue, QueueSender};
+use crate::event::Priority;
 use crate::manager::ConnectionManager;
 
 /// Incoming message from a source
 #[derive(Debug, Clone)]
 pub struct IncomingMessage {
     /// Target channel ID. None means broadcast to all.
-    pub channel_id: Option<String>,
+    pub channel_id: Option<Arc<str>>,
     /// Event type (e.g., "message", "notification")
     pub event_type: String,
     /// Message data (usually JSON string)
     pub data: String,
     /// Optional business ID
     pub id: Option<String>,
+    /// Drop the message instead of delivering or replaying it after this time
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Delivery priority
+    pub priority: Priority,
+    /// Receives the delivery outcome, if the publisher asked for one
+    pub report: Option<DeliveryReporter>,
 }
 
 impl IncomingMessage {
@@ -29,12 +45,15 @@ impl IncomingMessage {
             event_type: event_type.into(),
             data: data.into(),
             id: None,
+            expires_at: None,
+            priority: Priority::Normal,
+            report: None,
         }
     }
 
     /// Set the target channel
     pub fn with_channel(mut self, channel_id: impl Into<String>) -> Self {
-        self.channel_id = Some(channel_id.into());
+        self.channel_id = Some(channel_id.into().into());
         self
     }
 
@@ -44,14 +63,385 @@ impl IncomingMessage {
         self
     }
 
+    /// Drop the message after `expires_at`
+    ///
+    /// Stale messages (typing indicators, quotes) are skipped when published
+    /// late, when still queued for a slow subscriber, and on replay.
+    pub fn with_expiry(mut self, expires_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Drop the message once `ttl` has passed from now
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.expires_at = chrono::Duration::from_std(ttl)
+            .ok()
+            .and_then(|ttl| chrono::Utc::now().checked_add_signed(ttl));
+        self
+    }
+
+    /// Set the delivery priority
+    ///
+    /// High priority alerts overtake queued normal and low priority messages.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Whether the message's expiry has passed
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= chrono::Utc::now())
+    }
+
+    /// Build a message from broker attributes and a payload
+    ///
+    /// Used by adapters whose brokers carry string attributes next to the
+    /// body (Pub/Sub attributes, Service Bus application properties):
+    ///
+    /// - `channel_id`: target channel (omit to broadcast)
+    /// - `event_type`: event type (defaults to `message`)
+    /// - `id`: business message ID
+    /// - `expires_at`: RFC 3339 expiry
+    /// - `priority`: `high`, `normal` or `low`
+    ///
+    /// Binary-mode CloudEvents (`ce-*` attributes) and structured-mode ones
+    /// (`content-type: application/cloudevents+json`) are recognized too.
+    ///
+    /// Fails on malformed CloudEvents and on bodies that aren't UTF-8; report
+    /// those with [`MessageHandler::decode_failed`].
+    pub fn from_attributes(attributes: &HashMap<String, String>, data: &[u8]) -> anyhow::Result<Self> {
+        if attributes.contains_key("ce-specversion") {
+            return Ok(CloudEvent::from_attributes(attributes, data)?.into());
+        }
+        let data = std::str::from_utf8(data).map_err(|e| anyhow::anyhow!("payload is not valid UTF-8: {}", e))?;
+        let structured = attributes
+            .get("content-type")
+            .is_some_and(|ct| ct.starts_with(cloudevents::CONTENT_TYPE));
+        if structured {
+            return Ok(CloudEvent::from_json(data)?.into());
+        }
+
+        Ok(Self {
+            channel_id: attributes.get("channel_id").map(|channel_id| channel_id.as_str().into()),
+            event_type: attributes
+                .get("event_type")
+                .map(|s| s.as_str())
+                .unwrap_or("message")
+                .to_string(),
+            data: data.to_string(),
+            id: attributes.get("id").cloned(),
+            expires_at: attributes
+                .get("expires_at")
+                .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+                .map(|at| at.with_timezone(&chrono::Utc)),
+            priority: attributes
+                .get("priority")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or_default(),
+            report: None,
+        })
+    }
+
     /// Create a broadcast message
     pub fn broadcast(event_type: impl Into<String>, data: impl Into<String>) -> Self {
         Self::new(event_type, data)
     }
+
+    /// Request a [`DeliveryReport`] once the message has been dispatched
+    ///
+    /// ```rust,ignore
+    /// let (msg, report) = IncomingMessage::new("message", "hi").with_channel("user1").with_report();
+    /// handler.send(msg);
+    /// let report = report.await?;
+    /// println!("delivered to {} connections", report.delivered);
+    /// ```
+    pub fn with_report(mut self) -> (Self, oneshot::Receiver<DeliveryReport>) {
+        let (reporter, receiver) = DeliveryReporter::new();
+        self.report = Some(reporter);
+        (self, receiver)
+    }
+}
+
+/// Outcome of dispatching a single message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeliveryReport {
+    /// Number of local connections the event was sent to
+    pub delivered: usize,
+    /// The channel had local connections at dispatch time
+    pub online: bool,
+    /// The channel has subscribers on any gateway instance
+    ///
+    /// `None` when the gateway has no view of other instances.
+    pub cluster_online: Option<bool>,
+    /// Stream ID assigned for replay, if any
+    pub stream_id: Option<String>,
+    /// The message was dropped or rejected by the publish throttle, or
+    /// dropped by a full dispatch queue
+    pub throttled: bool,
+    /// The message was dropped by an interceptor
+    pub filtered: bool,
+    /// The message had expired and was dropped
+    pub expired: bool,
+    /// Why the message was rejected, if it was
+    pub error: Option<DispatchError>,
+}
+
+/// One-shot slot for returning a [`DeliveryReport`] to the publisher
+///
+/// Cloning shares the slot; only the first report is delivered.
+#[derive(Clone)]
+pub struct DeliveryReporter {
+    slot: Arc<Mutex<Option<oneshot::Sender<DeliveryReport>>>>,
+}
+
+impl DeliveryReporter {
+    /// Create a reporter and the receiver the publisher awaits
+    pub fn new() -> (Self, oneshot::Receiver<DeliveryReport>) {
+        let (tx, rx) = oneshot::channel();
+        (
+            Self {
+                slot: Arc::new(Mutex::new(Some(tx))),
+            },
+            rx,
+        )
+    }
+
+    /// Send the report (ignored after the first call or if nobody is waiting)
+    pub fn report(&self, report: DeliveryReport) {
+        let sender = self.slot.lock().map(|mut slot| slot.take()).unwrap_or(None);
+        if let Some(sender) = sender {
+            let _ = sender.send(report);
+        }
+    }
+}
+
+impl std::fmt::Debug for DeliveryReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeliveryReporter").finish_non_exhaustive()
+    }
+}
+
+/// Why a message could not be dispatched
+///
+/// Sources with acknowledgements should NACK (or not ack) on error so the
+/// message is redelivered later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchError {
+    /// Rejected by the publish throttle
+    #[error("rejected by publish throttle")]
+    Throttled {
+        /// When the channel's quota allows the message, under a
+        /// [`Delay`](crate::ThrottleAction::Delay) policy
+        retry_after: Option<Duration>,
+    },
+    /// The gateway is shutting down
+    #[error("gateway is shutting down")]
+    ShuttingDown,
+    /// The payload failed schema validation; redelivery will not help
+    #[error("invalid payload: {0}")]
+    Invalid(String),
+    /// The payload exceeds the configured limit; redelivery will not help
+    #[error("payload of {size} bytes exceeds the {limit} byte limit")]
+    TooLarge {
+        /// Payload size in bytes
+        size: usize,
+        /// The limit in bytes
+        limit: usize,
+    },
+}
+
+/// A payload a source received but couldn't decode into a message
+///
+/// Sources report these with [`MessageHandler::decode_failed`] rather than
+/// forwarding a lossy or half-parsed message.
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    /// Name of the source that received the payload
+    pub source: String,
+    /// Channel the payload was headed for, if the envelope said
+    pub channel_id: Option<String>,
+    /// The payload as received
+    pub payload: Vec<u8>,
+    /// Why it couldn't be decoded
+    pub reason: String,
+}
+
+impl DecodeError {
+    pub fn new(source: impl Into<String>, payload: impl Into<Vec<u8>>, reason: impl std::fmt::Display) -> Self {
+        Self {
+            source: source.into(),
+            channel_id: None,
+            payload: payload.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Record the channel the payload was headed for
+    pub fn with_channel(mut self, channel_id: impl Into<String>) -> Self {
+        self.channel_id = Some(channel_id.into());
+        self
+    }
+}
+
+/// Callback receiving payloads sources couldn't decode
+pub type DecodeErrorFn = Arc<dyn Fn(&DecodeError) + Send + Sync>;
+
+/// Result of dispatching a message
+pub type DispatchResult = std::result::Result<DeliveryReport, DispatchError>;
+
+/// Fire-and-forget message callback (the original `MessageHandler` shape)
+pub type MessageCallback = Arc<dyn Fn(IncomingMessage) + Send + Sync>;
+
+pub(crate) type DispatchFn =
+    dyn Fn(IncomingMessage) -> Pin<Box<dyn Future<Output = DispatchResult> + Send>> + Send + Sync;
+
+#[derive(Clone)]
+enum HandlerKind {
+    Callback(MessageCallback),
+    Dispatch(Arc<DispatchFn>),
+}
+
+/// Handle a source uses to hand messages to the gateway
+///
+/// Use [`dispatch`](Self::dispatch) to wait until the event has been fanned
+/// out and persisted (e.g. before acking upstream), or [`send`](Self::send)
+/// for fire-and-forget delivery.
+#[derive(Clone)]
+pub struct MessageHandler {
+    kind: HandlerKind,
+    /// Queue feeding `send`s to dispatch workers
+    queue: Option<Arc<QueueSender>>,
+    /// Where `decode_failed` reports go, besides the log
+    decode_errors: Option<DecodeErrorFn>,
 }
 
-/// Message handler callback type
-pub type MessageHandler = Arc<dyn Fn(IncomingMessage) + Send + Sync>;
+impl MessageHandler {
+    /// Create a handler from an async dispatch function
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn(IncomingMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DispatchResult> + Send + 'static,
+    {
+        Self {
+            kind: HandlerKind::Dispatch(Arc::new(move |msg| Box::pin(f(msg)))),
+            queue: None,
+            decode_errors: None,
+        }
+    }
+
+    /// Create a handler from a plain callback
+    ///
+    /// The callback runs inline; `dispatch` reports an empty [`DeliveryReport`].
+    pub fn from_fn(f: impl Fn(IncomingMessage) + Send + Sync + 'static) -> Self {
+        Self {
+            kind: HandlerKind::Callback(Arc::new(f)),
+            queue: None,
+            decode_errors: None,
+        }
+    }
+
+    /// Queue [`send`](Self::send)s by priority, running at most `concurrency`
+    /// dispatches at once
+    ///
+    /// When dispatch falls behind, queued high priority messages go out before
+    /// normal ones, and normal before low, so alerts are not stuck behind a
+    /// telemetry backlog. [`dispatch`](Self::dispatch) is not queued. Must be
+    /// called from within a Tokio runtime.
+    pub fn with_priority_lanes(self, concurrency: usize) -> Self {
+        self.with_dispatch_queue(DispatchQueue::new(concurrency))
+    }
+
+    /// Feed [`send`](Self::send)s through a bounded [`DispatchQueue`]
+    ///
+    /// Messages for the same channel are dispatched in order, one at a time,
+    /// by the channel's worker. Must be called from within a Tokio runtime.
+    pub fn with_dispatch_queue(mut self, queue: DispatchQueue) -> Self {
+        let HandlerKind::Dispatch(dispatch) = self.kind.clone() else {
+            return self;
+        };
+        self.queue = Some(Arc::new(queue.spawn(dispatch)));
+        self
+    }
+
+    /// Pass payloads reported with [`decode_failed`](Self::decode_failed) to `f`
+    pub fn with_decode_errors(mut self, f: impl Fn(&DecodeError) + Send + Sync + 'static) -> Self {
+        self.decode_errors = Some(Arc::new(f));
+        self
+    }
+
+    /// Report a payload that couldn't be decoded, instead of dispatching it
+    ///
+    /// The gateway counts it per source and hands it to its decode
+    /// dead-letter sink, if one is configured.
+    pub fn decode_failed(&self, error: DecodeError) {
+        tracing::warn!(
+            source = %error.source,
+            channel_id = ?error.channel_id,
+            bytes = error.payload.len(),
+            reason = %error.reason,
+            "Source payload could not be decoded"
+        );
+        if let Some(decode_errors) = &self.decode_errors {
+            decode_errors(&error);
+        }
+    }
+
+    /// Dispatch a message and wait for the outcome
+    pub async fn dispatch(&self, msg: IncomingMessage) -> DispatchResult {
+        match &self.kind {
+            HandlerKind::Callback(callback) => {
+                callback(msg);
+                Ok(DeliveryReport::default())
+            }
+            HandlerKind::Dispatch(dispatch) => dispatch(msg).await,
+        }
+    }
+
+    /// Dispatch a message without waiting for the outcome
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn send(&self, msg: IncomingMessage) {
+        match &self.kind {
+            HandlerKind::Callback(callback) => callback(msg),
+            HandlerKind::Dispatch(dispatch) => match &self.queue {
+                Some(queue) => queue.send(msg),
+                None => {
+                    let dispatch = dispatch(msg);
+                    tokio::spawn(async move {
+                        let _ = dispatch.await;
+                    });
+                }
+            },
+        }
+    }
+
+    /// Convert into a fire-and-forget callback, for sources written against
+    /// the old `handler(msg)` style
+    pub fn into_fn(self) -> MessageCallback {
+        match self.kind {
+            HandlerKind::Callback(callback) => callback,
+            kind => {
+                let handler = Self {
+                    kind,
+                    queue: self.queue,
+                    decode_errors: self.decode_errors,
+                };
+                Arc::new(move |msg| handler.send(msg))
+            }
+        }
+    }
+}
+
+impl From<MessageCallback> for MessageHandler {
+    fn from(callback: MessageCallback) -> Self {
+        Self {
+            kind: HandlerKind::Callback(callback),
+            queue: None,
+            decode_errors: None,
+        }
+    }
+}
 
 /// Trait for message sources
 ///
@@ -82,7 +472,7 @@ pub type MessageHandler = Arc<dyn Fn(IncomingMessage) + Send + Sync>;
 ///             tokio::select! {
 ///                 _ = cancel.cancelled() => break,
 ///                 msg = receive_message(&self.url) => {
-///                     handler(IncomingMessage::new("message", msg));
+///                     handler.send(IncomingMessage::new("message", msg));
 ///                 }
 ///             }
 ///         }
@@ -101,6 +491,8 @@ pub struct ConnectionInfo {
     pub connection_id: String,
     /// Gateway instance ID
     pub instance_id: String,
+    /// Why the connection closed; set for disconnect callbacks only
+    pub close_reason: Option<CloseReason>,
 }
 
 #[async_trait]
@@ -108,7 +500,9 @@ pub trait MessageSource: Send + Sync + 'static {
     /// Start receiving messages
     ///
     /// This method should run until the cancellation token is triggered.
-    /// Call the handler for each received message.
+    /// Pass each received message to the handler: `handler.send(msg)` for
+    /// fire-and-forget, or `handler.dispatch(msg).await` to ack only after
+    /// the event has been delivered.
     /// The connection_manager can be used to check local connection status.
     async fn start(
         &self,
@@ -120,18 +514,22 @@ pub trait MessageSource: Send + Sync + 'static {
     /// Return the source name (for logging)
     fn name(&self) -> &'static str;
 
-    /// Called when a new SSE connection is established
+    /// Called when a new connection is established, before any event is sent
     ///
-    /// Override this to register channel-to-gateway mappings for direct push.
-    fn on_connect(&self, _info: &ConnectionInfo) {
-        // Default: do nothing
+    /// The subscriber handler awaits this, so it can do I/O (e.g. register
+    /// channel-to-gateway mappings for direct push). Errors are logged; with
+    /// [`GatewayBuilder::reject_on_connect_error`](crate::GatewayBuilder::reject_on_connect_error)
+    /// they refuse the connection with `503 Service Unavailable` instead.
+    async fn on_connect(&self, _info: &ConnectionInfo) -> anyhow::Result<()> {
+        Ok(())
     }
 
-    /// Called when an SSE connection is closed
+    /// Called after a connection is closed
     ///
-    /// Override this to clean up channel-to-gateway mappings.
-    fn on_disconnect(&self, _info: &ConnectionInfo) {
-        // Default: do nothing
+    /// Runs in a spawned task, since disconnects are detected on drop;
+    /// errors are logged. Override this to clean up channel-to-gateway mappings.
+    async fn on_disconnect(&self, _info: &ConnectionInfo) -> anyhow::Result<()> {
+        Ok(())
     }
 }
 
@@ -194,7 +592,7 @@ impl MessageSource for ChannelSource {
                 _ = cancel.cancelled() => break,
                 msg = receiver.recv() => {
                     match msg {
-                        Some(msg) => handler(msg),
+                        Some(msg) => handler.send(msg),
                         None => break,
                     }
                 }
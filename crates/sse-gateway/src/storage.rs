@@ -3,11 +3,20 @@
 //! Implement `MessageStorage` for message replay on reconnection.
 
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "server")]
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::channel_usage::{DailyUsage, UsageDelta};
 use crate::event::SseEvent;
+use crate::id::{IdGenerator, StreamIds};
+#[cfg(feature = "server")]
+use crate::{metrics::GatewayMetrics, payload::PayloadLimit};
 
 /// Trait for message storage
 ///
@@ -58,6 +67,102 @@ pub trait MessageStorage: Send + Sync + Clone + 'static {
     /// Used when a client reconnects with a `last-event-id` header.
     async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent>;
 
+    /// Stream ID of the newest stored message on a channel
+    ///
+    /// Served by the cursor endpoint so clients can checkpoint explicitly.
+    /// Returns `None` if the channel is empty or cursors aren't supported.
+    async fn latest_id(&self, _channel_id: &str) -> Option<String> {
+        None
+    }
+
+    /// The newest `limit` stored messages on a channel, oldest first
+    ///
+    /// Used by the dashboard's message history viewer; messages carry their
+    /// `stream_id`. Returns an empty list if listing isn't supported.
+    async fn recent_messages(&self, _channel_id: &str, _limit: usize) -> Vec<SseEvent> {
+        Vec::new()
+    }
+
+    /// Record the last stream ID `consumer_id` processed on a channel
+    ///
+    /// Called when a client acknowledges events. Storages without cursor
+    /// support ignore it.
+    async fn save_cursor(&self, _channel_id: &str, _consumer_id: &str, _stream_id: &str) {}
+
+    /// Last stream ID `consumer_id` acknowledged on a channel
+    ///
+    /// Used as the replay cursor when a subscriber names its consumer but
+    /// sends no `Last-Event-ID`.
+    async fn get_cursor(&self, _channel_id: &str, _consumer_id: &str) -> Option<String> {
+        None
+    }
+
+    /// Save a resume session under its token, replacing any saved before
+    ///
+    /// `session` is opaque JSON written by the gateway; it may be dropped
+    /// once `ttl` has passed. Used when resume sessions are persisted, so a
+    /// client can resume after a restart or on another instance. Storages
+    /// without session support ignore it.
+    async fn save_session(&self, _token: &str, _session: &str, _ttl: Duration) {}
+
+    /// The resume session saved under `token`, unless it has expired
+    async fn load_session(&self, _token: &str) -> Option<String> {
+        None
+    }
+
+    /// Drop saved sessions whose TTL has passed
+    ///
+    /// Called on the gateway's cleanup interval, for storages that don't
+    /// expire sessions on their own.
+    async fn cleanup_expired_sessions(&self) {}
+
+    /// Add `delta` to a channel's usage totals for `delta.date`
+    ///
+    /// Counts are added, the peak is the larger of the two and each distinct
+    /// subscriber counts once per day. Called by every instance when channel
+    /// usage is enabled. Storages without usage support ignore it.
+    async fn record_channel_usage(&self, _channel_id: &str, _delta: &UsageDelta) {}
+
+    /// A channel's usage totals for the days from `from` to `to`, inclusive
+    ///
+    /// Days without usage are left out; the rest come oldest first.
+    async fn channel_usage(&self, _channel_id: &str, _from: NaiveDate, _to: NaiveDate) -> Vec<DailyUsage> {
+        Vec::new()
+    }
+
+    /// The snapshot a channel was last compacted into
+    ///
+    /// Storages without compaction support return `None`.
+    async fn snapshot(&self, _channel_id: &str) -> Option<SseEvent> {
+        None
+    }
+
+    /// Collapse a channel's history up to `up_to_id` into a snapshot
+    ///
+    /// The event stored as `up_to_id` becomes the channel's snapshot and
+    /// older events are discarded. A replay from a cursor before the snapshot
+    /// returns the snapshot first, then the events stored after it. Meant for
+    /// state-style channels whose events carry the full state. Storages
+    /// without compaction support ignore it.
+    async fn compact(&self, _channel_id: &str, _up_to_id: &str) {}
+
+    /// Delete everything stored for a channel: events, snapshot and consumer cursors
+    ///
+    /// Returns the number of events deleted. Used for data deletion requests
+    /// (`DELETE /api/channels/{id}/messages`). Storages without purge support
+    /// return 0.
+    async fn purge(&self, _channel_id: &str) -> usize {
+        0
+    }
+
+    /// Delete a channel's events stored before `before`
+    ///
+    /// A snapshot taken before the cutoff is deleted too. Returns the number
+    /// of events deleted; storages without purge support return 0.
+    async fn purge_before(&self, _channel_id: &str, _before: &PurgeBefore) -> usize {
+        0
+    }
+
     /// Check if storage is available
     async fn is_available(&self) -> bool;
 
@@ -65,13 +170,65 @@ pub trait MessageStorage: Send + Sync + Clone + 'static {
     fn name(&self) -> &'static str;
 }
 
+/// Cutoff for [`MessageStorage::purge_before`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurgeBefore {
+    /// Events with a stream ID lower than this one
+    Id(String),
+    /// Events stored before this time
+    Time(chrono::DateTime<chrono::Utc>),
+}
+
+impl PurgeBefore {
+    /// The cutoff as a `<millis>-<seq>` stream ID
+    ///
+    /// Returns `None` for an ID that isn't in that form.
+    pub fn stream_id(&self) -> Option<String> {
+        match self {
+            Self::Id(id) => stream_id_order(id).map(|_| id.clone()),
+            Self::Time(time) => Some(format!("{}-0", time.timestamp_millis().max(0))),
+        }
+    }
+
+    /// Whether an event stored as `stream_id` falls before the cutoff
+    pub fn covers(&self, stream_id: &str) -> bool {
+        let cutoff = self.stream_id().as_deref().and_then(stream_id_order);
+        matches!((stream_id_order(stream_id), cutoff), (Some(id), Some(cutoff)) if id < cutoff)
+    }
+}
+
+impl std::str::FromStr for PurgeBefore {
+    type Err = String;
+
+    /// Parse a stream ID (`1700000000000-0`) or an RFC 3339 time
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if stream_id_order(s).is_some() {
+            return Ok(Self::Id(s.to_string()));
+        }
+        chrono::DateTime::parse_from_rfc3339(s)
+            .map(|time| Self::Time(time.with_timezone(&chrono::Utc)))
+            .map_err(|_| format!("expected a stream ID or RFC 3339 time, got {:?}", s))
+    }
+}
+
+/// A day's usage totals and the subscribers counted towards them
+type DayUsage = (DailyUsage, HashSet<String>);
+
 /// In-memory message storage
 ///
 /// Suitable for development and testing. Not suitable for multi-instance deployments.
 #[derive(Clone)]
 pub struct MemoryStorage {
     streams: Arc<DashMap<String, Vec<(String, SseEvent)>>>,
-    counter: Arc<AtomicU64>,
+    /// (channel, consumer) -> acknowledged stream ID
+    cursors: Arc<DashMap<(String, String), String>>,
+    /// Channel -> event its history was compacted into
+    snapshots: Arc<DashMap<String, SseEvent>>,
+    /// Resume token -> session and its expiry
+    sessions: Arc<DashMap<String, (String, Instant)>>,
+    /// (channel, day) -> usage totals and the subscribers counted
+    usage: Arc<DashMap<(String, NaiveDate), DayUsage>>,
+    ids: Arc<StreamIds>,
     max_per_channel: usize,
 }
 
@@ -80,7 +237,11 @@ impl MemoryStorage {
     pub fn new(max_per_channel: usize) -> Self {
         Self {
             streams: Arc::new(DashMap::new()),
-            counter: Arc::new(AtomicU64::new(0)),
+            cursors: Arc::new(DashMap::new()),
+            snapshots: Arc::new(DashMap::new()),
+            sessions: Arc::new(DashMap::new()),
+            usage: Arc::new(DashMap::new()),
+            ids: Arc::new(StreamIds::new()),
             max_per_channel,
         }
     }
@@ -95,9 +256,7 @@ impl Default for MemoryStorage {
 #[async_trait]
 impl MessageStorage for MemoryStorage {
     fn generate_id(&self) -> String {
-        let ts = chrono::Utc::now().timestamp_millis();
-        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
-        format!("{}-{}", ts, seq)
+        self.ids.next_id()
     }
 
     async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
@@ -126,25 +285,137 @@ impl MessageStorage for MemoryStorage {
             None => return vec![],
         };
 
+        let entries = self.streams.get(channel_id);
+        let after = |position: usize| -> Vec<SseEvent> {
+            entries
+                .as_ref()
+                .map(|entries| entries[position..].iter().map(|(_, event)| event.clone()).collect())
+                .unwrap_or_default()
+        };
+        if let Some(position) = entries
+            .as_ref()
+            .and_then(|entries| entries.iter().position(|(id, _)| id == after_id))
+        {
+            return after(position + 1);
+        }
+
+        // The cursor may have been compacted into the snapshot
+        let Some(snapshot) = self.snapshots.get(channel_id) else {
+            return vec![];
+        };
+        let snapshot_id = snapshot.stream_id.as_deref().unwrap_or_default();
+        if after_id == snapshot_id {
+            return after(0);
+        }
+        match (stream_id_order(after_id), stream_id_order(snapshot_id)) {
+            (Some(cursor), Some(snapshot_at)) if cursor < snapshot_at => {
+                let mut events = vec![snapshot.clone()];
+                events.extend(after(0));
+                events
+            }
+            _ => vec![],
+        }
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        self.snapshots.get(channel_id).map(|snapshot| snapshot.clone())
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        let Some(mut entries) = self.streams.get_mut(channel_id) else {
+            return;
+        };
+        let Some(position) = entries.iter().position(|(id, _)| id == up_to_id) else {
+            return;
+        };
+        let (_, snapshot) = entries.drain(..=position).next_back().expect("position is in range");
+        self.snapshots.insert(channel_id.to_string(), snapshot);
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        let entries = self.streams.get(channel_id)?;
+        entries.last().map(|(id, _)| id.clone())
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
         let Some(entries) = self.streams.get(channel_id) else {
             return vec![];
         };
+        let skip = entries.len().saturating_sub(limit);
+        entries.iter().skip(skip).map(|(_, event)| event.clone()).collect()
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        self.cursors
+            .insert((channel_id.to_string(), consumer_id.to_string()), stream_id.to_string());
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        self.cursors
+            .get(&(channel_id.to_string(), consumer_id.to_string()))
+            .map(|id| id.clone())
+    }
 
-        let mut found = false;
-        entries
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        self.sessions
+            .insert(token.to_string(), (session.to_string(), Instant::now() + ttl));
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        let now = Instant::now();
+        // Evict an expired session on lookup; the rest go in the cleanup sweep
+        self.sessions.remove_if(token, |_, (_, expires)| *expires <= now);
+        self.sessions.get(token).map(|entry| entry.0.clone())
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        let now = Instant::now();
+        self.sessions.retain(|_, (_, expires)| *expires > now);
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        let mut entry = self
+            .usage
+            .entry((channel_id.to_string(), delta.date))
+            .or_insert_with(|| (DailyUsage::new(delta.date), HashSet::new()));
+        let (usage, subscribers) = &mut *entry;
+        let new_subscribers = delta
+            .subscribers
             .iter()
-            .filter_map(|(id, event)| {
-                if found {
-                    return Some(event.clone());
-                }
-                if id == after_id {
-                    found = true;
-                }
-                None
-            })
+            .filter(|subscriber| subscribers.insert(subscriber.to_string()))
+            .count();
+        usage.add(delta, new_subscribers as u64);
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: NaiveDate, to: NaiveDate) -> Vec<DailyUsage> {
+        from.iter_days()
+            .take_while(|date| *date <= to)
+            .filter_map(|date| self.usage.get(&(channel_id.to_string(), date)).map(|entry| entry.0.clone()))
             .collect()
     }
 
+    async fn purge(&self, channel_id: &str) -> usize {
+        self.snapshots.remove(channel_id);
+        self.cursors.retain(|(channel, _), _| channel != channel_id);
+        self.streams
+            .remove(channel_id)
+            .map(|(_, entries)| entries.len())
+            .unwrap_or(0)
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        self.snapshots
+            .remove_if(channel_id, |_, snapshot| {
+                snapshot.stream_id.as_deref().is_some_and(|id| before.covers(id))
+            });
+        let Some(mut entries) = self.streams.get_mut(channel_id) else {
+            return 0;
+        };
+        let len = entries.len();
+        entries.retain(|(id, _)| !before.covers(id));
+        len - entries.len()
+    }
+
     async fn is_available(&self) -> bool {
         true
     }
@@ -154,6 +425,110 @@ impl MessageStorage for MemoryStorage {
     }
 }
 
+/// Time encoded in a `<millis>-<seq>` stream ID
+///
+/// The built-in storages and the generators in [`id`](crate::id) produce IDs
+/// in this form. Returns `None` for IDs that don't start with a millisecond
+/// timestamp.
+pub fn stream_id_timestamp(stream_id: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let (millis, _) = stream_id.split_once('-')?;
+    chrono::DateTime::from_timestamp_millis(millis.parse().ok()?)
+}
+
+/// `(millis, seq)` of a stream ID, for ordering IDs the way Redis does
+pub(crate) fn stream_id_order(stream_id: &str) -> Option<(u64, u64)> {
+    let (millis, seq) = stream_id.split_once('-')?;
+    Some((millis.parse().ok()?, seq.parse().ok()?))
+}
+
+/// Everything stored for a channel, oldest first
+///
+/// The compaction snapshot, if any, comes first. Stored events keep their
+/// stream IDs, so [`import_history`] can recreate the channel elsewhere.
+pub async fn export_history<S: MessageStorage>(storage: &S, channel_id: &str) -> Vec<SseEvent> {
+    let mut events: Vec<SseEvent> = storage.snapshot(channel_id).await.into_iter().collect();
+    events.extend(storage.recent_messages(channel_id, usize::MAX).await);
+    events
+}
+
+/// Store exported events under their original stream IDs
+///
+/// Events without a stream ID get a new one from the storage. Events that
+/// aren't newer than the channel's latest stored event are skipped, so an
+/// import can be re-run without duplicating history. Returns how many events
+/// were stored.
+pub async fn import_history<S: MessageStorage>(
+    storage: &S,
+    channel_id: &str,
+    events: impl IntoIterator<Item = SseEvent>,
+) -> usize {
+    let mut latest = storage.latest_id(channel_id).await.as_deref().and_then(stream_id_order);
+    let mut imported = 0;
+    for event in events {
+        let stream_id = event.stream_id.clone().unwrap_or_else(|| storage.generate_id());
+        if let Some(order) = stream_id_order(&stream_id) {
+            if latest.is_some_and(|latest| order <= latest) {
+                continue;
+            }
+            latest = Some(order);
+        }
+        storage.store(channel_id, &stream_id, &event).await;
+        imported += 1;
+    }
+    imported
+}
+
+/// Serialize events as NDJSON, one event per line
+pub fn to_ndjson(events: &[SseEvent]) -> String {
+    events
+        .iter()
+        .filter_map(|event| serde_json::to_string(event).ok())
+        .fold(String::new(), |mut out, line| {
+            out.push_str(&line);
+            out.push('\n');
+            out
+        })
+}
+
+/// Parse NDJSON written by [`to_ndjson`], skipping blank lines
+///
+/// The error names the first line that isn't an event.
+pub fn from_ndjson(ndjson: &str) -> Result<Vec<SseEvent>, String> {
+    ndjson
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e)))
+        .collect()
+}
+
+/// Store `event`, then compact the channel up to it if its type is in `compact_on`
+///
+/// Returns false if `limit` kept the event out of storage.
+#[cfg(feature = "server")]
+pub(crate) async fn store_event<S: MessageStorage>(
+    storage: &S,
+    compact_on: &[String],
+    limit: Option<&PayloadLimit>,
+    metrics: &GatewayMetrics,
+    channel_id: &str,
+    stream_id: &str,
+    event: &SseEvent,
+) -> bool {
+    let event = match limit {
+        Some(limit) => match limit.apply_to_event(channel_id, event, metrics).await {
+            Some(event) => event,
+            None => return false,
+        },
+        None => Cow::Borrowed(event),
+    };
+    storage.store(channel_id, stream_id, &event).await;
+    if compact_on.contains(&event.event_type) {
+        storage.compact(channel_id, stream_id).await;
+    }
+    true
+}
+
 /// No-op storage (disabled)
 #[derive(Clone, Default)]
 pub struct NoopStorage;
@@ -180,3 +555,445 @@ impl MessageStorage for NoopStorage {
         "Noop (disabled)"
     }
 }
+
+/// Storage that falls back to a second store while the primary is down
+///
+/// Reads and writes go to `primary` while its [`is_available`](MessageStorage::is_available)
+/// check passes, and to `fallback` otherwise. The primary is probed at most
+/// once per [probe interval](Self::probe_interval), on the next storage call,
+/// so it is picked up again once it recovers. Events written to the fallback
+/// during an outage stay there: replay keeps roughly working, but isn't
+/// complete across the switch.
+///
+/// ```rust,ignore
+/// // Redis for replay, with in-memory history while Redis is unreachable
+/// Gateway::builder().storage_with_fallback(RedisStorage::new(config).await?, MemoryStorage::default())
+/// ```
+#[derive(Clone)]
+pub struct FailoverStorage<P, F> {
+    primary: P,
+    fallback: F,
+    probe_interval: Duration,
+    state: Arc<FailoverState>,
+}
+
+struct FailoverState {
+    primary_up: AtomicBool,
+    /// Milliseconds since `epoch` of the last probe
+    last_probe: AtomicU64,
+    epoch: Instant,
+}
+
+impl<P: MessageStorage, F: MessageStorage> FailoverStorage<P, F> {
+    /// Use `primary`, falling back to `fallback`
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self {
+            primary,
+            fallback,
+            probe_interval: Duration::from_secs(5),
+            state: Arc::new(FailoverState {
+                primary_up: AtomicBool::new(true),
+                last_probe: AtomicU64::new(0),
+                epoch: Instant::now(),
+            }),
+        }
+    }
+
+    /// How often the primary's availability is checked (default: 5s)
+    pub fn probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// Whether calls currently go to the fallback
+    pub fn is_failed_over(&self) -> bool {
+        !self.state.primary_up.load(Ordering::Acquire)
+    }
+
+    /// The primary store
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// The fallback store
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+
+    /// Probe the primary if the interval has passed; returns whether it is up
+    async fn primary_up(&self) -> bool {
+        let state = &self.state;
+        let now = state.epoch.elapsed().as_millis() as u64;
+        let last = state.last_probe.load(Ordering::Acquire);
+        let due = last == 0 || now.saturating_sub(last) >= self.probe_interval.as_millis() as u64;
+        // One caller probes; the rest use the last result
+        if due
+            && state
+                .last_probe
+                .compare_exchange(last, now.max(1), Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            let up = self.primary.is_available().await;
+            let was_up = state.primary_up.swap(up, Ordering::AcqRel);
+            if was_up && !up {
+                tracing::warn!(
+                    primary = self.primary.name(),
+                    fallback = self.fallback.name(),
+                    "Primary storage unavailable, failing over"
+                );
+            } else if !was_up && up {
+                tracing::info!(primary = self.primary.name(), "Primary storage recovered");
+            }
+            return up;
+        }
+        state.primary_up.load(Ordering::Acquire)
+    }
+}
+
+#[async_trait]
+impl<P: MessageStorage, F: MessageStorage> MessageStorage for FailoverStorage<P, F> {
+    fn generate_id(&self) -> String {
+        if self.is_failed_over() {
+            self.fallback.generate_id()
+        } else {
+            self.primary.generate_id()
+        }
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        if self.primary_up().await {
+            self.primary.store(channel_id, stream_id, event).await
+        } else {
+            self.fallback.store(channel_id, stream_id, event).await
+        }
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        if self.primary_up().await {
+            self.primary.get_messages_after(channel_id, after_id).await
+        } else {
+            self.fallback.get_messages_after(channel_id, after_id).await
+        }
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        if self.primary_up().await {
+            self.primary.latest_id(channel_id).await
+        } else {
+            self.fallback.latest_id(channel_id).await
+        }
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        if self.primary_up().await {
+            self.primary.recent_messages(channel_id, limit).await
+        } else {
+            self.fallback.recent_messages(channel_id, limit).await
+        }
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        if self.primary_up().await {
+            self.primary.save_cursor(channel_id, consumer_id, stream_id).await
+        } else {
+            self.fallback.save_cursor(channel_id, consumer_id, stream_id).await
+        }
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        if self.primary_up().await {
+            self.primary.get_cursor(channel_id, consumer_id).await
+        } else {
+            self.fallback.get_cursor(channel_id, consumer_id).await
+        }
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        if self.primary_up().await {
+            self.primary.save_session(token, session, ttl).await
+        } else {
+            self.fallback.save_session(token, session, ttl).await
+        }
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        if self.primary_up().await {
+            self.primary.load_session(token).await
+        } else {
+            self.fallback.load_session(token).await
+        }
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        self.primary.cleanup_expired_sessions().await;
+        self.fallback.cleanup_expired_sessions().await;
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        if self.primary_up().await {
+            self.primary.record_channel_usage(channel_id, delta).await
+        } else {
+            self.fallback.record_channel_usage(channel_id, delta).await
+        }
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: NaiveDate, to: NaiveDate) -> Vec<DailyUsage> {
+        if self.primary_up().await {
+            self.primary.channel_usage(channel_id, from, to).await
+        } else {
+            self.fallback.channel_usage(channel_id, from, to).await
+        }
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        if self.primary_up().await {
+            self.primary.snapshot(channel_id).await
+        } else {
+            self.fallback.snapshot(channel_id).await
+        }
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        if self.primary_up().await {
+            self.primary.compact(channel_id, up_to_id).await
+        } else {
+            self.fallback.compact(channel_id, up_to_id).await
+        }
+    }
+
+    /// Purges both stores, since either may hold the channel's events
+    async fn purge(&self, channel_id: &str) -> usize {
+        self.primary.purge(channel_id).await + self.fallback.purge(channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        self.primary.purge_before(channel_id, before).await
+            + self.fallback.purge_before(channel_id, before).await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.primary_up().await || self.fallback.is_available().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Failover"
+    }
+}
+
+/// Storage that writes every event to several stores and reads from one
+///
+/// Replay, cursors and snapshots are served by the store given to
+/// [`new`](Self::new); each event is also written to every store added with
+/// [`write_to`](Self::write_to), e.g. Redis for replay plus Postgres as an
+/// audit log. Writes run concurrently and are isolated: a store that hangs
+/// is given up on after the [write timeout](Self::write_timeout) without
+/// holding up or failing the others. Per-store counters are available from
+/// [`stats`](Self::stats). Compaction only applies to the read store, so
+/// the others keep the full history; purges apply to every store.
+///
+/// ```rust,ignore
+/// let storage = TeeStorage::new(RedisStorage::new(config).await?).write_to(audit_log);
+/// Gateway::builder().storage(storage)
+/// ```
+#[derive(Clone)]
+pub struct TeeStorage<R> {
+    read: R,
+    read_stats: Arc<TeeCounters>,
+    writes: Vec<(Arc<dyn TeeTarget>, Arc<TeeCounters>)>,
+    write_timeout: Duration,
+}
+
+/// Write counters of one store behind a [`TeeStorage`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TeeStoreStats {
+    /// The store's [`name`](MessageStorage::name)
+    pub name: &'static str,
+    /// Whether replay reads are served by this store
+    pub reads: bool,
+    /// Events written
+    pub writes: u64,
+    /// Writes given up on after the write timeout
+    pub timeouts: u64,
+}
+
+#[derive(Default)]
+struct TeeCounters {
+    writes: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+/// The write side of a [`MessageStorage`], as a trait object
+#[async_trait]
+trait TeeTarget: Send + Sync {
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent);
+    async fn purge(&self, channel_id: &str) -> usize;
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize;
+    fn name(&self) -> &'static str;
+}
+
+#[async_trait]
+impl<S: MessageStorage> TeeTarget for S {
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        MessageStorage::store(self, channel_id, stream_id, event).await
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        MessageStorage::purge(self, channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        MessageStorage::purge_before(self, channel_id, before).await
+    }
+
+    fn name(&self) -> &'static str {
+        MessageStorage::name(self)
+    }
+}
+
+impl<R: MessageStorage> TeeStorage<R> {
+    /// Read from and write to `read`
+    pub fn new(read: R) -> Self {
+        Self {
+            read,
+            read_stats: Arc::default(),
+            writes: Vec::new(),
+            write_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Also write every event to `store` (may be called repeatedly)
+    pub fn write_to(mut self, store: impl MessageStorage) -> Self {
+        self.writes.push((Arc::new(store), Arc::default()));
+        self
+    }
+
+    /// Give up on a store's write after `timeout` (default: 5s)
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
+
+    /// Write counters per store, the read store first
+    pub fn stats(&self) -> Vec<TeeStoreStats> {
+        let stats = |name, reads, counters: &TeeCounters| TeeStoreStats {
+            name,
+            reads,
+            writes: counters.writes.load(Ordering::Relaxed),
+            timeouts: counters.timeouts.load(Ordering::Relaxed),
+        };
+        std::iter::once(stats(self.read.name(), true, &self.read_stats))
+            .chain(
+                self.writes
+                    .iter()
+                    .map(|(store, counters)| stats(store.name(), false, counters)),
+            )
+            .collect()
+    }
+
+    /// Run `write` against every store concurrently, each bounded by the timeout
+    async fn fan_out<'a, W, Fut>(&'a self, write: W)
+    where
+        W: Fn(&'a dyn TeeTarget) -> Fut,
+        Fut: std::future::Future<Output = ()> + 'a,
+    {
+        let targets = std::iter::once((&self.read as &dyn TeeTarget, &self.read_stats))
+            .chain(self.writes.iter().map(|(store, counters)| (store.as_ref(), counters)));
+        let writes = targets.map(|(store, counters)| {
+            let write = tokio::time::timeout(self.write_timeout, write(store));
+            async move {
+                match write.await {
+                    Ok(()) => {
+                        counters.writes.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(_) => {
+                        counters.timeouts.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(storage = store.name(), "Tee storage write timed out");
+                    }
+                }
+            }
+        });
+        futures::future::join_all(writes).await;
+    }
+}
+
+#[async_trait]
+impl<R: MessageStorage> MessageStorage for TeeStorage<R> {
+    fn generate_id(&self) -> String {
+        self.read.generate_id()
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        self.fan_out(|store| store.store(channel_id, stream_id, event)).await
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        self.read.get_messages_after(channel_id, after_id).await
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        self.read.latest_id(channel_id).await
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        self.read.recent_messages(channel_id, limit).await
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        self.read.save_cursor(channel_id, consumer_id, stream_id).await
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        self.read.get_cursor(channel_id, consumer_id).await
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        self.read.save_session(token, session, ttl).await
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        self.read.load_session(token).await
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        self.read.cleanup_expired_sessions().await
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        self.read.record_channel_usage(channel_id, delta).await
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: NaiveDate, to: NaiveDate) -> Vec<DailyUsage> {
+        self.read.channel_usage(channel_id, from, to).await
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        self.read.snapshot(channel_id).await
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        self.read.compact(channel_id, up_to_id).await
+    }
+
+    /// Purges every store, returning the count from the read store
+    async fn purge(&self, channel_id: &str) -> usize {
+        for (store, _) in &self.writes {
+            store.purge(channel_id).await;
+        }
+        self.read.purge(channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        for (store, _) in &self.writes {
+            store.purge_before(channel_id, before).await;
+        }
+        self.read.purge_before(channel_id, before).await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.read.is_available().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Tee"
+    }
+}
@@ -0,0 +1,229 @@
+//! Message source supervision
+//!
+//! The gateway runs its [`MessageSource`] under a supervisor: when
+//! [`start`](MessageSource::start) returns an error (e.g. the broker is
+//! unreachable at boot), it is restarted with exponential backoff instead of
+//! leaving the gateway serving with no source.
+//!
+//! ```rust,ignore
+//! use sse_gateway::supervisor::{RestartPolicy, SourceState};
+//!
+//! Gateway::builder()
+//!     .source(redis_source)
+//!     // Give up after 5 attempts and stop the gateway, so the orchestrator restarts it
+//!     .restart_policy(RestartPolicy::default().max_retries(5).fail_fast(true))
+//!     .on_source_state(|state: &SourceState| tracing::info!(?state, "source state changed"))
+//! ```
+//!
+//! The current state is reported by `GET /ready`, which answers
+//! `503 Service Unavailable` while the source is restarting or has failed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::manager::ConnectionManager;
+use crate::source::{MessageHandler, MessageSource};
+
+/// Callback invoked on every source state change
+pub type SourceStateHook = Arc<dyn Fn(&SourceState) + Send + Sync>;
+
+/// How a failed source is restarted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestartPolicy {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retries: Option<u32>,
+    pub(crate) fail_fast: bool,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            max_retries: None,
+            fail_fast: false,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Never restart; a failed source stays failed
+    pub fn never() -> Self {
+        Self::default().max_retries(0)
+    }
+
+    /// Wait `initial` before the first restart, doubling up to `max` (default: 1s, 30s)
+    ///
+    /// A source that ran for longer than `max` before failing starts the
+    /// backoff over.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Give up after `retries` consecutive restarts (default: unlimited)
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    /// Shut the gateway down once the source has failed for good (default: false)
+    ///
+    /// [`Gateway::run`](crate::Gateway::run) then returns the source's error,
+    /// so a process supervisor can restart the whole instance. Otherwise the
+    /// gateway keeps serving and `/ready` reports the failure.
+    pub fn fail_fast(mut self, enable: bool) -> Self {
+        self.fail_fast = enable;
+        self
+    }
+}
+
+/// Lifecycle state of the message source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SourceState {
+    /// `start` is running
+    Running,
+    /// `start` failed and is waiting to be called again
+    Restarting {
+        /// Consecutive restarts so far, starting at 1
+        attempt: u32,
+        /// The error that stopped the source
+        error: String,
+    },
+    /// `start` failed and the restart policy gave up
+    Failed {
+        /// The last error
+        error: String,
+    },
+    /// `start` returned without error
+    Stopped,
+}
+
+impl SourceState {
+    /// Whether the gateway should be considered ready while in this state
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Self::Running | Self::Stopped)
+    }
+}
+
+/// Shared view of the source's state
+///
+/// Clones observe the same source.
+#[derive(Clone)]
+pub struct SourceHealth {
+    name: &'static str,
+    state: Arc<watch::Sender<SourceState>>,
+}
+
+impl SourceHealth {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            state: Arc::new(watch::Sender::new(SourceState::Running)),
+        }
+    }
+
+    /// The source's name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The current state
+    pub fn state(&self) -> SourceState {
+        self.state.borrow().clone()
+    }
+
+    /// Receive every subsequent state change
+    pub fn subscribe(&self) -> watch::Receiver<SourceState> {
+        self.state.subscribe()
+    }
+
+    fn set(&self, state: SourceState, hook: Option<&SourceStateHook>) {
+        self.state.send_replace(state.clone());
+        if let Some(hook) = hook {
+            hook(&state);
+        }
+    }
+}
+
+/// Run `source` until `cancel` fires, restarting it according to `policy`
+///
+/// With [`RestartPolicy::fail_fast`], giving up cancels `cancel` so the whole
+/// gateway stops.
+pub(crate) async fn supervise<S: MessageSource>(
+    source: Arc<S>,
+    handler: MessageHandler,
+    connection_manager: ConnectionManager,
+    policy: RestartPolicy,
+    health: SourceHealth,
+    hook: Option<SourceStateHook>,
+    cancel: CancellationToken,
+) {
+    let name = source.name();
+    let mut delay = policy.initial_backoff;
+    let mut attempt = 0u32;
+    loop {
+        let started = Instant::now();
+        let result = source
+            .start(handler.clone(), connection_manager.clone(), cancel.clone())
+            .await;
+        let error = match result {
+            Ok(()) => {
+                if !cancel.is_cancelled() {
+                    tracing::info!(source = name, "Message source stopped");
+                }
+                health.set(SourceState::Stopped, hook.as_ref());
+                return;
+            }
+            Err(e) if cancel.is_cancelled() => {
+                tracing::debug!(error = %e, source = name, "Message source error during shutdown");
+                health.set(SourceState::Stopped, hook.as_ref());
+                return;
+            }
+            Err(e) => format!("{:#}", e),
+        };
+
+        // A source that held up for a while starts the backoff over
+        if started.elapsed() > policy.max_backoff {
+            delay = policy.initial_backoff;
+            attempt = 0;
+        }
+
+        if policy.max_retries.is_some_and(|max| attempt >= max) {
+            tracing::error!(error = %error, source = name, attempts = attempt, "Message source failed, giving up");
+            health.set(SourceState::Failed { error }, hook.as_ref());
+            if policy.fail_fast {
+                tracing::error!(source = name, "Shutting down: message source failed");
+                cancel.cancel();
+            }
+            return;
+        }
+
+        attempt += 1;
+        tracing::warn!(
+            error = %error,
+            source = name,
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            "Message source failed, restarting"
+        );
+        health.set(SourceState::Restarting { attempt, error }, hook.as_ref());
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                health.set(SourceState::Stopped, hook.as_ref());
+                return;
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+        delay = (delay * 2).min(policy.max_backoff);
+        health.set(SourceState::Running, hook.as_ref());
+    }
+}
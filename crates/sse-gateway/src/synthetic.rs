@@ -0,0 +1,308 @@
+//! Synthetic traffic source for demos and soak tests
+//!
+//! A [`SyntheticSource`] publishes generated events at a steady rate with
+//! optional periodic bursts, spread over a set of channels and a weighted
+//! mix of event types, with payloads whose size follows a configurable
+//! distribution. It lets dashboards, demos and capacity tests run without
+//! an external producer.
+//!
+//! ```rust,ignore
+//! use sse_gateway::synthetic::{Burst, PayloadSize, SyntheticSettings, SyntheticSource};
+//!
+//! let source = SyntheticSource::new(SyntheticSettings {
+//!     channels: vec!["demo".into(), "prices".into()],
+//!     event_types: [("tick".into(), 9), ("alert".into(), 1)].into(),
+//!     rate: 50.0,
+//!     payload: PayloadSize::Exponential { mean: 256 },
+//!     burst: Some(Burst { every_ms: 10_000, events: 500 }),
+//! });
+//!
+//! Gateway::builder()
+//!     .merge(source.router())
+//!     .source(source)
+//! ```
+//!
+//! Settings can be changed at runtime through the admin endpoint:
+//!
+//! ```bash
+//! curl localhost:8080/api/synthetic
+//! curl -X PUT localhost:8080/api/synthetic \
+//!   -d '{"rate": 1000, "channels": ["load"], "payload": {"uniform": {"min": 100, "max": 4000}}}'
+//! ```
+//!
+//! `PUT` replaces all settings; fields left out take their defaults. A rate
+//! of `0` without a burst pauses the source.
+//!
+//! Each event's data is a JSON object with a sequence number, the send time
+//! and a `padding` string of the sampled payload size:
+//! `{"seq": 42, "sent_at": "2024-01-01T00:00:00Z", "padding": "xxxx"}`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::response::Json;
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::manager::ConnectionManager;
+use crate::source::{IncomingMessage, MessageHandler, MessageSource};
+
+/// Time between scheduling rounds; settings changes apply within one
+const TICK: Duration = Duration::from_millis(10);
+
+/// Largest padding generated, whatever the distribution says
+const MAX_PADDING: usize = 1024 * 1024;
+
+/// Distribution of payload padding sizes, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadSize {
+    /// Always the same size
+    Fixed(usize),
+    /// Uniform between `min` and `max`, inclusive
+    Uniform { min: usize, max: usize },
+    /// Exponential with the given mean: mostly small, occasionally large
+    Exponential { mean: usize },
+}
+
+impl PayloadSize {
+    fn sample(&self) -> usize {
+        let size = match *self {
+            Self::Fixed(size) => size,
+            Self::Uniform { min, max } if max > min => min + (random() * (max - min + 1) as f64) as usize,
+            Self::Uniform { min, .. } => min,
+            Self::Exponential { mean } => (-(mean as f64) * (1.0 - random()).ln()) as usize,
+        };
+        size.min(MAX_PADDING)
+    }
+}
+
+/// Extra events published at once, on a fixed period
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Burst {
+    /// Time between bursts
+    pub every_ms: u64,
+    /// Events in each burst
+    pub events: u64,
+}
+
+/// What to generate, and how fast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyntheticSettings {
+    /// Channels to publish to, picked at random per event; empty broadcasts
+    pub channels: Vec<String>,
+    /// Event types with their relative weights
+    pub event_types: BTreeMap<String, u32>,
+    /// Steady events per second, across all channels; `0` pauses the stream
+    pub rate: f64,
+    /// Size of each event's padding, capped at 1 MiB
+    pub payload: PayloadSize,
+    /// Bursts on top of the steady rate
+    pub burst: Option<Burst>,
+}
+
+impl Default for SyntheticSettings {
+    fn default() -> Self {
+        Self {
+            channels: vec!["demo".to_string()],
+            event_types: BTreeMap::from([("tick".to_string(), 1)]),
+            rate: 1.0,
+            payload: PayloadSize::Fixed(0),
+            burst: None,
+        }
+    }
+}
+
+impl SyntheticSettings {
+    fn pick_channel(&self) -> Option<&str> {
+        if self.channels.is_empty() {
+            return None;
+        }
+        let index = (random() * self.channels.len() as f64) as usize;
+        self.channels.get(index).map(String::as_str)
+    }
+
+    fn pick_event_type(&self) -> &str {
+        let total: u64 = self.event_types.values().map(|&w| u64::from(w)).sum();
+        let mut target = (random() * total as f64) as u64;
+        for (event_type, &weight) in &self.event_types {
+            if target < u64::from(weight) {
+                return event_type;
+            }
+            target -= u64::from(weight);
+        }
+        "message"
+    }
+}
+
+/// Events generated so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SyntheticStats {
+    pub sent: u64,
+    pub bursts: u64,
+    /// Padding bytes generated
+    pub payload_bytes: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    bursts: AtomicU64,
+    payload_bytes: AtomicU64,
+}
+
+/// Source publishing generated traffic
+///
+/// Clones share settings and counters, so keep one to change the settings
+/// or to serve [`router`](Self::router) after handing the source to the
+/// gateway.
+#[derive(Clone, Default)]
+pub struct SyntheticSource {
+    settings: Arc<RwLock<SyntheticSettings>>,
+    counters: Arc<Counters>,
+}
+
+impl SyntheticSource {
+    /// Create a source generating per `settings`
+    pub fn new(settings: SyntheticSettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Current settings
+    pub fn settings(&self) -> SyntheticSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replace the settings; a running source picks them up within 10ms
+    pub fn set(&self, settings: SyntheticSettings) {
+        *self.settings.write().unwrap() = settings;
+    }
+
+    pub fn stats(&self) -> SyntheticStats {
+        SyntheticStats {
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            bursts: self.counters.bursts.load(Ordering::Relaxed),
+            payload_bytes: self.counters.payload_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// `GET`/`PUT /api/synthetic`, to merge into the gateway with
+    /// [`GatewayBuilder::merge`](crate::GatewayBuilder::merge)
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/api/synthetic", get(get_synthetic).put(put_synthetic))
+            .with_state(self.clone())
+    }
+
+    fn message(&self, settings: &SyntheticSettings) -> IncomingMessage {
+        let seq = self.counters.sent.fetch_add(1, Ordering::Relaxed) + 1;
+        let padding = settings.payload.sample();
+        self.counters.payload_bytes.fetch_add(padding as u64, Ordering::Relaxed);
+        let data = serde_json::json!({
+            "seq": seq,
+            "sent_at": chrono::Utc::now().to_rfc3339(),
+            "padding": "x".repeat(padding),
+        });
+        let message = IncomingMessage::new(settings.pick_event_type(), data.to_string());
+        match settings.pick_channel() {
+            Some(channel_id) => message.with_channel(channel_id),
+            None => message,
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSource for SyntheticSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        tracing::info!(settings = ?self.settings(), "Synthetic source started");
+
+        let mut ticker = tokio::time::interval(TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last_tick = Instant::now();
+        let mut last_burst = Instant::now();
+        // Fractional events carried between ticks
+        let mut credit = 0.0;
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            let settings = self.settings();
+            let now = Instant::now();
+            credit += settings.rate.max(0.0) * now.duration_since(last_tick).as_secs_f64();
+            last_tick = now;
+            let mut due = credit.floor() as u64;
+            credit -= due as f64;
+
+            if let Some(burst) = settings.burst.filter(|burst| burst.every_ms > 0) {
+                if now.duration_since(last_burst) >= Duration::from_millis(burst.every_ms) {
+                    last_burst = now;
+                    due += burst.events;
+                    self.counters.bursts.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            for _ in 0..due {
+                handler.send(self.message(&settings));
+            }
+        }
+
+        tracing::info!(stats = ?self.stats(), "Synthetic source stopped");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Synthetic"
+    }
+}
+
+/// `GET /api/synthetic` and `PUT /api/synthetic` response body
+#[derive(Debug, Serialize)]
+pub struct SyntheticResponse {
+    pub settings: SyntheticSettings,
+    pub stats: SyntheticStats,
+}
+
+fn describe(source: &SyntheticSource) -> Json<SyntheticResponse> {
+    Json(SyntheticResponse {
+        settings: source.settings(),
+        stats: source.stats(),
+    })
+}
+
+async fn get_synthetic(State(source): State<SyntheticSource>) -> Json<SyntheticResponse> {
+    describe(&source)
+}
+
+async fn put_synthetic(
+    State(source): State<SyntheticSource>,
+    Json(settings): Json<SyntheticSettings>,
+) -> Json<SyntheticResponse> {
+    source.set(settings);
+    describe(&source)
+}
+
+/// Uniform random number in `[0, 1)`
+fn random() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    // A randomly keyed hasher over a counter; plenty for synthetic traffic
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
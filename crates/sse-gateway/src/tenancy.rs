@@ -0,0 +1,142 @@
+//! Multi-tenant channel isolation
+//!
+//! With tenancy enabled, every channel lives in a tenant namespace: a
+//! subscriber asking for `orders` on behalf of tenant `acme` is attached to
+//! `acme/orders`. The tenant comes from the authenticated request, never from
+//! the channel name, so a client cannot reach another tenant's channels.
+//! Storage keys, presence and lifecycle events all see the scoped name.
+//!
+//! ```rust,ignore
+//! use sse_gateway::{Tenancy, ThrottleAction, ThrottlePolicy};
+//!
+//! Gateway::builder()
+//!     .tenancy(
+//!         Tenancy::new(|req: &AuthRequest| req.header("x-tenant").map(str::to_string))
+//!             .max_connections(10_000)
+//!             .throttle(ThrottlePolicy::new(ThrottleAction::Reject).events_per_sec(500)),
+//!     )
+//! ```
+//!
+//! Sources publish to scoped channels, e.g. with [`scoped_channel`].
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+use crate::auth::AuthRequest;
+#[cfg(feature = "server")]
+use crate::metrics::GatewayMetrics;
+use crate::throttle::{Throttle, ThrottlePolicy};
+#[cfg(feature = "server")]
+use crate::throttle::ThrottleDecision;
+
+/// Separates the tenant from the channel in scoped channel IDs
+pub const TENANT_SEPARATOR: char = '/';
+
+/// Resolves the tenant of an allowed request; `None` refuses it
+pub type TenantFn = Arc<dyn Fn(&AuthRequest) -> Option<String> + Send + Sync>;
+
+/// Channel ID of `channel_id` in `tenant`'s namespace
+pub fn scoped_channel(tenant: &str, channel_id: &str) -> String {
+    format!("{}{}{}", tenant, TENANT_SEPARATOR, channel_id)
+}
+
+/// Tenant of a scoped channel ID
+pub fn tenant_of(channel_id: &str) -> Option<&str> {
+    channel_id
+        .split_once(TENANT_SEPARATOR)
+        .map(|(tenant, _)| tenant)
+        .filter(|tenant| !tenant.is_empty())
+}
+
+/// Tenant resolution and per-tenant quotas
+#[derive(Clone)]
+pub struct Tenancy {
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    resolve: TenantFn,
+    max_connections: Option<usize>,
+    throttle: Option<Throttle>,
+    published: Arc<DashMap<String, AtomicU64>>,
+}
+
+impl Tenancy {
+    /// Scope channels by the tenant `resolve` returns for each request
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(&AuthRequest) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            resolve: Arc::new(resolve),
+            max_connections: None,
+            throttle: None,
+            published: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Refuse subscribers beyond `limit` connections per tenant on this instance
+    pub fn max_connections(mut self, limit: usize) -> Self {
+        self.max_connections = Some(limit);
+        self
+    }
+
+    /// Limit publishing per tenant, across all of its channels
+    ///
+    /// Applies on top of the per-channel [`GatewayBuilder::throttle`](crate::GatewayBuilder::throttle).
+    pub fn throttle(mut self, policy: ThrottlePolicy) -> Self {
+        self.throttle = Some(Throttle::new(policy));
+        self
+    }
+
+    /// Tenant of `req`, if it resolves to a valid name
+    #[cfg(feature = "server")]
+    pub(crate) fn resolve(&self, req: &AuthRequest) -> Option<String> {
+        (self.resolve)(req).filter(|tenant| !tenant.is_empty() && !tenant.contains(TENANT_SEPARATOR))
+    }
+
+    /// Whether `tenant` may open another connection, given its current count
+    #[cfg(feature = "server")]
+    pub(crate) fn admits(&self, connections: usize) -> bool {
+        self.max_connections.is_none_or(|limit| connections < limit)
+    }
+
+    /// Check (and consume) the tenant quota for an event published to `channel_id`
+    #[cfg(feature = "server")]
+    pub(crate) fn check(&self, channel_id: &str, size: usize, metrics: &GatewayMetrics) -> ThrottleDecision {
+        let Some(tenant) = tenant_of(channel_id) else {
+            return ThrottleDecision::Allow;
+        };
+        let decision = match &self.throttle {
+            Some(throttle) => throttle.check_and_record(tenant, size, metrics),
+            None => ThrottleDecision::Allow,
+        };
+        if decision == ThrottleDecision::Allow {
+            self.published
+                .entry(tenant.to_string())
+                .or_default()
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        decision
+    }
+
+    /// Events published to `tenant`'s channels so far
+    pub fn published(&self, tenant: &str) -> u64 {
+        self.published
+            .get(tenant)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// Tenants that have published at least once
+    #[cfg(feature = "server")]
+    pub(crate) fn publishing_tenants(&self) -> Vec<String> {
+        self.published.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn cleanup_idle(&self, idle: Duration) {
+        if let Some(throttle) = &self.throttle {
+            throttle.cleanup_idle(idle);
+        }
+    }
+}
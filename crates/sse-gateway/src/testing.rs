@@ -0,0 +1,914 @@
+//! In-process harness for gateway integration tests
+//!
+//! [`TestGateway`] runs a fully configured gateway inside the test: requests
+//! are served in memory through the router, and the same router listens on an
+//! ephemeral port for real clients. Subscriptions come back as
+//! [`TestConnection`]s that parse the SSE stream and assert on events with
+//! timeouts.
+//!
+//! Test doubles cover the adapter side: [`MockSource`] publishes a scripted
+//! sequence of messages, [`RecordingStorage`] captures storage calls for
+//! assertions, and [`FlakyStorage`] injects failures and latency.
+//!
+//! ```rust,ignore
+//! use sse_gateway::testing::TestGateway;
+//!
+//! #[tokio::test]
+//! async fn delivers_orders() {
+//!     let gateway = TestGateway::start(
+//!         Gateway::builder()
+//!             .source(MySource::new())
+//!             .storage(MemoryStorage::default())
+//!             .build()
+//!             .unwrap(),
+//!     )
+//!     .await;
+//!
+//!     let mut conn = gateway.connect("user123").await;
+//!     gateway.push(IncomingMessage::new("order", r#"{"id":1}"#).with_channel("user123")).await;
+//!
+//!     let event = conn.expect_event("order").await;
+//!     assert_eq!(event.data.to_string(), r#"{"id":1}"#);
+//!     conn.expect_no_event(Duration::from_millis(100)).await;
+//!
+//!     gateway.shutdown().await;
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use axum::body::{Body, BodyDataStream};
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use futures::StreamExt;
+use tokio_util::sync::CancellationToken;
+use tower::Service;
+
+use crate::channel_usage::{DailyUsage, UsageDelta};
+use crate::event::SseEvent;
+use crate::gateway::{Gateway, GatewayHandle};
+use crate::manager::ConnectionManager;
+use crate::source::{ConnectionInfo, DeliveryReport, IncomingMessage, MessageHandler, MessageSource};
+use crate::storage::{MemoryStorage, MessageStorage, PurgeBefore};
+
+/// How long assertions wait for an event by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A gateway running inside the test
+///
+/// Dropping it stops the gateway; [`shutdown`](Self::shutdown) also waits for
+/// the background tasks to finish.
+pub struct TestGateway {
+    app: Router,
+    handle: Option<GatewayHandle>,
+    cancel: CancellationToken,
+    addr: SocketAddr,
+    server: tokio::task::JoinHandle<()>,
+    sse_path: String,
+    channel_param: String,
+    heartbeat_event: Option<String>,
+}
+
+impl TestGateway {
+    /// Start `gateway` and listen on an ephemeral localhost port
+    ///
+    /// The gateway's own port and listeners are ignored. Panics if the
+    /// listener can't be bound.
+    pub async fn start<Source: MessageSource, Storage: MessageStorage>(
+        gateway: Gateway<Source, Storage>,
+    ) -> Self {
+        let sse_path = gateway.sse_path.clone();
+        let channel_param = gateway.channel_param.clone();
+        let heartbeat_event = gateway.heartbeat.event_name().map(str::to_string);
+
+        let (app, handle) = gateway.into_router();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind test listener");
+        let addr = listener.local_addr().expect("test listener has an address");
+        let cancel = handle.cancellation_token();
+        let shutdown = cancel.clone().cancelled_owned();
+        let server_app = app.clone();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, server_app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                .with_graceful_shutdown(shutdown)
+                .await
+                .ok();
+        });
+
+        Self {
+            app,
+            handle: Some(handle),
+            cancel,
+            addr,
+            server,
+            sse_path,
+            channel_param,
+            heartbeat_event,
+        }
+    }
+
+    /// Address the gateway listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL for real HTTP clients, e.g. `http://127.0.0.1:54321`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The gateway's router, to drive as an in-memory tower service
+    pub fn router(&self) -> Router {
+        self.app.clone()
+    }
+
+    /// Handle to the gateway's background tasks
+    pub fn handle(&self) -> &GatewayHandle {
+        self.handle.as_ref().expect("gateway is running")
+    }
+
+    /// Connection manager shared with the gateway routes
+    pub fn connection_manager(&self) -> &ConnectionManager {
+        self.handle().connection_manager()
+    }
+
+    /// Send a request through the router in memory
+    pub async fn request(&self, request: Request<Body>) -> Response {
+        let mut app = self.app.clone();
+        std::future::poll_fn(|cx| <Router as Service<Request<Body>>>::poll_ready(&mut app, cx))
+            .await
+            .unwrap_or_else(|e: Infallible| match e {});
+        app.call(request)
+            .await
+            .unwrap_or_else(|e: Infallible| match e {})
+    }
+
+    /// Subscribe to `channel_id` and wait until the subscription is registered
+    ///
+    /// Panics if the gateway doesn't answer `200 OK`; use
+    /// [`connect_with`](Self::connect_with) to test rejections.
+    pub async fn connect(&self, channel_id: &str) -> TestConnection {
+        let uri = format!("{}?{}={}", self.sse_path, self.channel_param, channel_id);
+        let request = Request::get(uri)
+            .body(Body::empty())
+            .expect("valid SSE request");
+        let conn = self.connect_with(request).await;
+        assert_eq!(conn.status(), StatusCode::OK, "subscription to {} rejected", channel_id);
+        conn
+    }
+
+    /// Open an SSE stream with a custom request (headers, `Last-Event-ID`, query parameters)
+    pub async fn connect_with(&self, request: Request<Body>) -> TestConnection {
+        let response = self.request(request).await;
+        let status = response.status();
+        let headers = response.headers().clone();
+        TestConnection {
+            status,
+            headers,
+            body: response.into_body().into_data_stream(),
+            buffer: Vec::new(),
+            pending: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            heartbeat_event: self.heartbeat_event.clone(),
+        }
+    }
+
+    /// Publish through the gateway's dispatcher, as the message source would
+    ///
+    /// Panics if dispatching fails.
+    pub async fn push(&self, msg: IncomingMessage) -> DeliveryReport {
+        self.handle()
+            .message_handler()
+            .dispatch(msg)
+            .await
+            .expect("dispatch failed")
+    }
+
+    /// Wait until `channel_id` has `count` local connections
+    ///
+    /// Panics after [`DEFAULT_TIMEOUT`].
+    pub async fn wait_for_connections(&self, channel_id: &str, count: usize) {
+        let manager = self.connection_manager();
+        let wait = async {
+            while manager.channel_connection_count(channel_id) != count {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        if tokio::time::timeout(DEFAULT_TIMEOUT, wait).await.is_err() {
+            panic!(
+                "channel {} has {} connections, expected {}",
+                channel_id,
+                manager.channel_connection_count(channel_id),
+                count
+            );
+        }
+    }
+
+    /// Stop the gateway and wait for its background tasks
+    pub async fn shutdown(mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.shutdown().await;
+        }
+        self.server.abort();
+    }
+}
+
+impl Drop for TestGateway {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        self.server.abort();
+    }
+}
+
+/// A subscription opened by [`TestGateway::connect`]
+///
+/// Heartbeats are skipped by the `expect_*` helpers. Events carry the SSE
+/// `id:` field in [`SseEvent::id`].
+pub struct TestConnection {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: BodyDataStream,
+    buffer: Vec<u8>,
+    pending: Vec<SseEvent>,
+    timeout: Duration,
+    heartbeat_event: Option<String>,
+}
+
+impl TestConnection {
+    /// Response status
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Response headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Wait this long in the `expect_*` helpers (default 5s)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Next event, heartbeats included, or `None` if the stream ended or `timeout` passed
+    pub async fn next_event(&mut self, timeout: Duration) -> Option<SseEvent> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if !self.pending.is_empty() {
+                return Some(self.pending.remove(0));
+            }
+            let chunk = tokio::time::timeout_at(deadline, self.body.next()).await.ok()??;
+            let chunk = chunk.ok()?;
+            self.buffer.extend_from_slice(&chunk);
+            self.parse();
+        }
+    }
+
+    /// Wait for the next non-heartbeat event and assert its type
+    pub async fn expect_event(&mut self, event_type: &str) -> SseEvent {
+        let event = self.expect_any().await;
+        assert_eq!(event.event_type, event_type, "unexpected event: {:?}", event);
+        event
+    }
+
+    /// Wait for the next non-heartbeat event
+    pub async fn expect_any(&mut self) -> SseEvent {
+        match self.next_non_heartbeat(self.timeout).await {
+            Some(event) => event,
+            None => panic!("no event within {:?}", self.timeout),
+        }
+    }
+
+    /// Wait for the next `count` non-heartbeat events
+    pub async fn expect_events(&mut self, count: usize) -> Vec<SseEvent> {
+        let mut events = Vec::with_capacity(count);
+        for _ in 0..count {
+            events.push(self.expect_any().await);
+        }
+        events
+    }
+
+    /// Assert that no non-heartbeat event arrives for `duration`
+    pub async fn expect_no_event(&mut self, duration: Duration) {
+        if let Some(event) = self.next_non_heartbeat(duration).await {
+            panic!("unexpected event: {:?}", event);
+        }
+    }
+
+    async fn next_non_heartbeat(&mut self, timeout: Duration) -> Option<SseEvent> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let event = self.next_event(remaining).await?;
+            if Some(event.event_type.as_str()) != self.heartbeat_event.as_deref() {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Move complete messages from the buffer to `pending`
+    fn parse(&mut self) {
+        // Only complete messages are decoded, so multi-byte characters can't be split
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let bytes: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let message = String::from_utf8_lossy(&bytes);
+            let mut event_type = None;
+            let mut data: Vec<&str> = Vec::new();
+            let mut id = None;
+            let mut retry = None;
+            for line in message.lines() {
+                let (field, value) = match line.split_once(':') {
+                    Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                    None => (line, ""),
+                };
+                match field {
+                    "event" => event_type = Some(value.to_string()),
+                    "data" => data.push(value),
+                    "id" => id = Some(value.to_string()),
+                    "retry" => retry = value.parse().ok(),
+                    _ => {}
+                }
+            }
+            if data.is_empty() {
+                continue;
+            }
+            let mut event = SseEvent::raw(
+                event_type.unwrap_or_else(|| "message".to_string()),
+                data.join("\n"),
+            );
+            event.id = id;
+            event.retry = retry;
+            self.pending.push(event);
+        }
+    }
+}
+
+/// A message source that publishes a scripted sequence of messages
+///
+/// Messages are dispatched at their offset from [`start`](MessageSource::start),
+/// in order, each awaited before the next. Clones share the script and the
+/// recorded lifecycle calls, so keep a clone to inspect after handing the
+/// source to the builder.
+///
+/// ```rust,ignore
+/// let source = MockSource::new()
+///     .emit(IncomingMessage::new("hello", "1").with_channel("room"))
+///     .emit_at(Duration::from_millis(200), IncomingMessage::new("late", "2").with_channel("room"));
+/// let gateway = TestGateway::start(
+///     Gateway::builder().source(source.clone()).storage(MemoryStorage::default()).build()?,
+/// ).await;
+/// ```
+#[derive(Clone, Default)]
+pub struct MockSource {
+    script: Arc<Mutex<Vec<(Duration, IncomingMessage)>>>,
+    error: Option<String>,
+    emitted: Arc<AtomicUsize>,
+    reject_connections: Arc<AtomicBool>,
+    connects: Arc<Mutex<Vec<ConnectionInfo>>>,
+    disconnects: Arc<Mutex<Vec<ConnectionInfo>>>,
+}
+
+impl MockSource {
+    /// Create a source with an empty script
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish `msg` right after the previous scripted message
+    pub fn emit(self, msg: IncomingMessage) -> Self {
+        let at = self.script.lock().unwrap().last().map(|(at, _)| *at).unwrap_or_default();
+        self.emit_at(at, msg)
+    }
+
+    /// Publish `msg` at `at` after the source starts
+    pub fn emit_at(self, at: Duration, msg: IncomingMessage) -> Self {
+        {
+            let mut script = self.script.lock().unwrap();
+            script.push((at, msg));
+            script.sort_by_key(|(at, _)| *at);
+        }
+        self
+    }
+
+    /// Fail with `error` once the script has been published, to test source error handling
+    pub fn fail_after_script(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Make `on_connect` fail, as a source whose registry is down would
+    pub fn reject_connections(&self, reject: bool) {
+        self.reject_connections.store(reject, Ordering::SeqCst);
+    }
+
+    /// Number of scripted messages published so far
+    pub fn emitted(&self) -> usize {
+        self.emitted.load(Ordering::SeqCst)
+    }
+
+    /// Connections reported to `on_connect`, in order
+    pub fn connects(&self) -> Vec<ConnectionInfo> {
+        self.connects.lock().unwrap().clone()
+    }
+
+    /// Connections reported to `on_disconnect`, in order
+    pub fn disconnects(&self) -> Vec<ConnectionInfo> {
+        self.disconnects.lock().unwrap().clone()
+    }
+
+    async fn play(&self, handler: &MessageHandler, cancel: &CancellationToken) {
+        let started = tokio::time::Instant::now();
+        let script = self.script.lock().unwrap().clone();
+        for (at, msg) in script {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep_until(started + at) => {}
+            }
+            if let Err(e) = handler.dispatch(msg).await {
+                tracing::debug!(error = %e, "Scripted message not dispatched");
+            }
+            self.emitted.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSource for MockSource {
+    async fn start(
+        &self,
+        handler: MessageHandler,
+        _connection_manager: ConnectionManager,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        self.play(&handler, &cancel).await;
+        if let Some(error) = &self.error {
+            anyhow::bail!("{}", error);
+        }
+        cancel.cancelled().await;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    async fn on_connect(&self, info: &ConnectionInfo) -> anyhow::Result<()> {
+        self.connects.lock().unwrap().push(info.clone());
+        if self.reject_connections.load(Ordering::SeqCst) {
+            anyhow::bail!("connections rejected by MockSource");
+        }
+        Ok(())
+    }
+
+    async fn on_disconnect(&self, info: &ConnectionInfo) -> anyhow::Result<()> {
+        self.disconnects.lock().unwrap().push(info.clone());
+        Ok(())
+    }
+}
+
+/// A storage call captured by [`RecordingStorage`]
+#[derive(Debug, Clone)]
+pub enum StorageCall {
+    /// `store`
+    Store {
+        channel_id: String,
+        stream_id: String,
+        event: SseEvent,
+    },
+    /// `get_messages_after`
+    GetMessagesAfter {
+        channel_id: String,
+        after_id: Option<String>,
+    },
+    /// `latest_id`
+    LatestId { channel_id: String },
+    /// `recent_messages`
+    RecentMessages { channel_id: String, limit: usize },
+    /// `save_cursor`
+    SaveCursor {
+        channel_id: String,
+        consumer_id: String,
+        stream_id: String,
+    },
+    /// `get_cursor`
+    GetCursor {
+        channel_id: String,
+        consumer_id: String,
+    },
+    /// `save_session`
+    SaveSession { token: String, session: String },
+    /// `load_session`
+    LoadSession { token: String },
+    /// `record_channel_usage`
+    RecordChannelUsage { channel_id: String, delta: UsageDelta },
+    /// `channel_usage`
+    ChannelUsage { channel_id: String },
+    /// `snapshot`
+    Snapshot { channel_id: String },
+    /// `compact`
+    Compact { channel_id: String, up_to_id: String },
+    /// `purge`
+    Purge { channel_id: String },
+    /// `purge_before`
+    PurgeBefore { channel_id: String, before: PurgeBefore },
+}
+
+/// Storage wrapper that records every read and write
+///
+/// Calls are forwarded to the wrapped storage (in-memory by default). Clones
+/// share the log.
+#[derive(Clone, Default)]
+pub struct RecordingStorage<S = MemoryStorage> {
+    inner: S,
+    calls: Arc<Mutex<Vec<StorageCall>>>,
+}
+
+impl<S: MessageStorage> RecordingStorage<S> {
+    /// Record calls made to `inner`
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            calls: Arc::default(),
+        }
+    }
+
+    /// Every call so far, in order
+    pub fn calls(&self) -> Vec<StorageCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Events written, with their channel
+    pub fn stored(&self) -> Vec<(String, SseEvent)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                StorageCall::Store { channel_id, event, .. } => Some((channel_id, event)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replay queries, as `(channel_id, after_id)`
+    pub fn replays(&self) -> Vec<(String, Option<String>)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                StorageCall::GetMessagesAfter { channel_id, after_id } => Some((channel_id, after_id)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forget the calls recorded so far
+    pub fn clear(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    fn record(&self, call: StorageCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[async_trait]
+impl<S: MessageStorage> MessageStorage for RecordingStorage<S> {
+    fn generate_id(&self) -> String {
+        self.inner.generate_id()
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        self.record(StorageCall::Store {
+            channel_id: channel_id.to_string(),
+            stream_id: stream_id.to_string(),
+            event: event.clone(),
+        });
+        self.inner.store(channel_id, stream_id, event).await;
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        self.record(StorageCall::GetMessagesAfter {
+            channel_id: channel_id.to_string(),
+            after_id: after_id.map(str::to_string),
+        });
+        self.inner.get_messages_after(channel_id, after_id).await
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        self.record(StorageCall::LatestId {
+            channel_id: channel_id.to_string(),
+        });
+        self.inner.latest_id(channel_id).await
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        self.record(StorageCall::RecentMessages {
+            channel_id: channel_id.to_string(),
+            limit,
+        });
+        self.inner.recent_messages(channel_id, limit).await
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        self.record(StorageCall::SaveCursor {
+            channel_id: channel_id.to_string(),
+            consumer_id: consumer_id.to_string(),
+            stream_id: stream_id.to_string(),
+        });
+        self.inner.save_cursor(channel_id, consumer_id, stream_id).await;
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        self.record(StorageCall::GetCursor {
+            channel_id: channel_id.to_string(),
+            consumer_id: consumer_id.to_string(),
+        });
+        self.inner.get_cursor(channel_id, consumer_id).await
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        self.record(StorageCall::SaveSession {
+            token: token.to_string(),
+            session: session.to_string(),
+        });
+        self.inner.save_session(token, session, ttl).await;
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        self.record(StorageCall::LoadSession {
+            token: token.to_string(),
+        });
+        self.inner.load_session(token).await
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        self.inner.cleanup_expired_sessions().await
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        self.record(StorageCall::RecordChannelUsage {
+            channel_id: channel_id.to_string(),
+            delta: delta.clone(),
+        });
+        self.inner.record_channel_usage(channel_id, delta).await;
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Vec<DailyUsage> {
+        self.record(StorageCall::ChannelUsage {
+            channel_id: channel_id.to_string(),
+        });
+        self.inner.channel_usage(channel_id, from, to).await
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        self.record(StorageCall::Snapshot {
+            channel_id: channel_id.to_string(),
+        });
+        self.inner.snapshot(channel_id).await
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        self.record(StorageCall::Compact {
+            channel_id: channel_id.to_string(),
+            up_to_id: up_to_id.to_string(),
+        });
+        self.inner.compact(channel_id, up_to_id).await;
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        self.record(StorageCall::Purge {
+            channel_id: channel_id.to_string(),
+        });
+        self.inner.purge(channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        self.record(StorageCall::PurgeBefore {
+            channel_id: channel_id.to_string(),
+            before: before.clone(),
+        });
+        self.inner.purge_before(channel_id, before).await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.inner.is_available().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Recording"
+    }
+}
+
+#[derive(Default)]
+struct Faults {
+    fail_next: AtomicUsize,
+    fail_every: AtomicUsize,
+    latency_ms: AtomicU64,
+    unavailable: AtomicBool,
+    calls: AtomicUsize,
+    failures: AtomicUsize,
+}
+
+/// Storage wrapper that injects failures and latency
+///
+/// `MessageStorage` methods can't return errors, so a failed call behaves
+/// the way a broken backend looks to the gateway: writes are lost, replay
+/// queries come back empty and cursor lookups find nothing. Settings can be
+/// changed while the gateway runs; clones share them.
+///
+/// ```rust,ignore
+/// let storage = FlakyStorage::new(MemoryStorage::default())
+///     .latency(Duration::from_millis(20))
+///     .fail_every(3);
+/// storage.fail_next(2);
+/// storage.set_available(false);
+/// ```
+#[derive(Clone, Default)]
+pub struct FlakyStorage<S = MemoryStorage> {
+    inner: S,
+    faults: Arc<Faults>,
+}
+
+impl<S: MessageStorage> FlakyStorage<S> {
+    /// Wrap `inner`, initially without faults
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            faults: Arc::default(),
+        }
+    }
+
+    /// Delay every call by `latency`
+    pub fn latency(self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    /// Fail every `n`th call (`0` disables)
+    pub fn fail_every(self, n: usize) -> Self {
+        self.faults.fail_every.store(n, Ordering::SeqCst);
+        self
+    }
+
+    /// Change the per-call latency
+    pub fn set_latency(&self, latency: Duration) {
+        self.faults
+            .latency_ms
+            .store(latency.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Fail the next `n` calls
+    pub fn fail_next(&self, n: usize) {
+        self.faults.fail_next.store(n, Ordering::SeqCst);
+    }
+
+    /// Report the storage as down and fail every call until set back
+    pub fn set_available(&self, available: bool) {
+        self.faults.unavailable.store(!available, Ordering::SeqCst);
+    }
+
+    /// Number of calls failed so far
+    pub fn failures(&self) -> usize {
+        self.faults.failures.load(Ordering::SeqCst)
+    }
+
+    /// Wait out the latency, then decide whether this call fails
+    async fn fault(&self) -> bool {
+        let latency = self.faults.latency_ms.load(Ordering::SeqCst);
+        if latency > 0 {
+            tokio::time::sleep(Duration::from_millis(latency)).await;
+        }
+        let faults = &self.faults;
+        let call = faults.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let every = faults.fail_every.load(Ordering::SeqCst);
+        let failed = faults.unavailable.load(Ordering::SeqCst)
+            || faults
+                .fail_next
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            || (every > 0 && call.is_multiple_of(every));
+        if failed {
+            faults.failures.fetch_add(1, Ordering::SeqCst);
+        }
+        failed
+    }
+}
+
+#[async_trait]
+impl<S: MessageStorage> MessageStorage for FlakyStorage<S> {
+    fn generate_id(&self) -> String {
+        self.inner.generate_id()
+    }
+
+    async fn store(&self, channel_id: &str, stream_id: &str, event: &SseEvent) {
+        if !self.fault().await {
+            self.inner.store(channel_id, stream_id, event).await;
+        }
+    }
+
+    async fn get_messages_after(&self, channel_id: &str, after_id: Option<&str>) -> Vec<SseEvent> {
+        if self.fault().await {
+            return Vec::new();
+        }
+        self.inner.get_messages_after(channel_id, after_id).await
+    }
+
+    async fn latest_id(&self, channel_id: &str) -> Option<String> {
+        if self.fault().await {
+            return None;
+        }
+        self.inner.latest_id(channel_id).await
+    }
+
+    async fn recent_messages(&self, channel_id: &str, limit: usize) -> Vec<SseEvent> {
+        if self.fault().await {
+            return Vec::new();
+        }
+        self.inner.recent_messages(channel_id, limit).await
+    }
+
+    async fn save_cursor(&self, channel_id: &str, consumer_id: &str, stream_id: &str) {
+        if !self.fault().await {
+            self.inner.save_cursor(channel_id, consumer_id, stream_id).await;
+        }
+    }
+
+    async fn get_cursor(&self, channel_id: &str, consumer_id: &str) -> Option<String> {
+        if self.fault().await {
+            return None;
+        }
+        self.inner.get_cursor(channel_id, consumer_id).await
+    }
+
+    async fn save_session(&self, token: &str, session: &str, ttl: Duration) {
+        if !self.fault().await {
+            self.inner.save_session(token, session, ttl).await;
+        }
+    }
+
+    async fn load_session(&self, token: &str) -> Option<String> {
+        if self.fault().await {
+            return None;
+        }
+        self.inner.load_session(token).await
+    }
+
+    async fn cleanup_expired_sessions(&self) {
+        self.inner.cleanup_expired_sessions().await
+    }
+
+    async fn record_channel_usage(&self, channel_id: &str, delta: &UsageDelta) {
+        if !self.fault().await {
+            self.inner.record_channel_usage(channel_id, delta).await;
+        }
+    }
+
+    async fn channel_usage(&self, channel_id: &str, from: chrono::NaiveDate, to: chrono::NaiveDate) -> Vec<DailyUsage> {
+        if self.fault().await {
+            return Vec::new();
+        }
+        self.inner.channel_usage(channel_id, from, to).await
+    }
+
+    async fn snapshot(&self, channel_id: &str) -> Option<SseEvent> {
+        if self.fault().await {
+            return None;
+        }
+        self.inner.snapshot(channel_id).await
+    }
+
+    async fn compact(&self, channel_id: &str, up_to_id: &str) {
+        if !self.fault().await {
+            self.inner.compact(channel_id, up_to_id).await;
+        }
+    }
+
+    async fn purge(&self, channel_id: &str) -> usize {
+        if self.fault().await {
+            return 0;
+        }
+        self.inner.purge(channel_id).await
+    }
+
+    async fn purge_before(&self, channel_id: &str, before: &PurgeBefore) -> usize {
+        if self.fault().await {
+            return 0;
+        }
+        self.inner.purge_before(channel_id, before).await
+    }
+
+    async fn is_available(&self) -> bool {
+        !self.faults.unavailable.load(Ordering::SeqCst) && self.inner.is_available().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Flaky"
+    }
+}
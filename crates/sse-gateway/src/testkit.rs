@@ -0,0 +1,495 @@
+//! Conformance test kit for adapter authors
+//!
+//! Runs a standard battery of checks against any `MessageStorage` or
+//! `MessageSource` implementation. Use the macros from a test file:
+//!
+//! ```rust,ignore
+//! use sse_gateway::{MemoryStorage, source::ChannelSource};
+//! use sse_gateway::testkit::SourceFixture;
+//!
+//! sse_gateway::storage_conformance!(memory, async { MemoryStorage::new(100) });
+//!
+//! sse_gateway::source_conformance!(channel, async {
+//!     let (source, tx) = ChannelSource::new();
+//!     SourceFixture::new(source, move |msg| {
+//!         let tx = tx.clone();
+//!         async move { Ok(tx.send(msg).await?) }
+//!     })
+//! });
+//!
+//! // Adapters that need external services can pass test attributes through
+//! sse_gateway::storage_conformance!(#[ignore = "requires Redis"] redis, async { ... });
+//! ```
+//!
+//! Each macro expands to a module of `#[test]` functions named after the check.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::source::{IncomingMessage, MessageSource};
+
+/// How long checks wait for asynchronous effects (batched writes, delivery)
+pub const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Channel used by the battery; adapters should accept arbitrary channel names
+pub const TEST_CHANNEL: &str = "conformance";
+
+/// Run a future to completion on a fresh multi-threaded runtime
+#[doc(hidden)]
+pub fn block_on<F: Future<Output = ()>>(fut: F) {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build test runtime")
+        .block_on(fut)
+}
+
+/// Poll `check` until it returns true or the settle timeout expires
+pub async fn eventually<F, Fut>(mut check: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = tokio::time::Instant::now() + SETTLE_TIMEOUT;
+    loop {
+        if check().await {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Publish function injecting a message into the backend a source reads from
+pub type PublishFn = Arc<
+    dyn Fn(IncomingMessage) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// A source under test plus a way to feed it messages
+pub struct SourceFixture<S: MessageSource> {
+    /// The source to exercise
+    pub source: S,
+    /// Publishes a message to the source's backend
+    pub publish: PublishFn,
+}
+
+impl<S: MessageSource> SourceFixture<S> {
+    /// Create a fixture from a source and a publish closure
+    pub fn new<F, Fut>(source: S, publish: F) -> Self
+    where
+        F: Fn(IncomingMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            source,
+            publish: Arc::new(move |msg| Box::pin(publish(msg))),
+        }
+    }
+}
+
+/// Checks for `MessageStorage` implementations
+pub mod storage {
+    use super::{eventually, TEST_CHANNEL};
+    use std::time::Duration;
+    use crate::channel_usage::{DailyUsage, UsageDelta};
+    use crate::event::SseEvent;
+    use crate::storage::{MessageStorage, PurgeBefore};
+
+    /// Store `count` events on `channel_id` and return their stream IDs
+    async fn store_many<S: MessageStorage>(storage: &S, channel_id: &str, count: usize) -> Vec<String> {
+        let mut ids = Vec::with_capacity(count);
+        for i in 0..count {
+            let id = storage.generate_id();
+            let event = SseEvent::raw("conformance", format!("msg-{}", i)).with_id(format!("biz-{}", i));
+            storage.store(channel_id, &id, &event).await;
+            ids.push(id);
+        }
+        ids
+    }
+
+    /// Storage reports itself available and has a name
+    pub async fn is_available<S: MessageStorage>(storage: S) {
+        assert!(storage.is_available().await, "storage should be available");
+        assert!(!storage.name().is_empty(), "storage name should not be empty");
+    }
+
+    /// Generated IDs are non-empty and unique
+    pub async fn generates_unique_ids<S: MessageStorage>(storage: S) {
+        let ids: Vec<String> = (0..1000).map(|_| storage.generate_id()).collect();
+        assert!(ids.iter().all(|id| !id.is_empty()), "IDs must not be empty");
+        let unique: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len(), "IDs must be unique");
+    }
+
+    /// Replay returns events after the given ID, oldest first
+    pub async fn replays_in_order<S: MessageStorage>(storage: S) {
+        let ids = store_many(&storage, TEST_CHANNEL, 5).await;
+
+        let replayed = eventually(|| async {
+            storage.get_messages_after(TEST_CHANNEL, Some(&ids[0])).await.len() == 4
+        })
+        .await;
+        assert!(replayed, "expected 4 events after the first ID");
+
+        let events = storage.get_messages_after(TEST_CHANNEL, Some(&ids[0])).await;
+        let data: Vec<String> = events.iter().map(|e| e.data.to_string()).collect();
+        assert_eq!(data, vec!["msg-1", "msg-2", "msg-3", "msg-4"]);
+    }
+
+    /// Replayed events carry their stream ID, type and business ID
+    pub async fn replay_preserves_fields<S: MessageStorage>(storage: S) {
+        let ids = store_many(&storage, TEST_CHANNEL, 3).await;
+
+        let replayed = eventually(|| async {
+            storage.get_messages_after(TEST_CHANNEL, Some(&ids[0])).await.len() == 2
+        })
+        .await;
+        assert!(replayed, "expected 2 events after the first ID");
+
+        let events = storage.get_messages_after(TEST_CHANNEL, Some(&ids[0])).await;
+        for (event, (expected_id, index)) in events.iter().zip(ids[1..].iter().zip(1..)) {
+            assert_eq!(event.stream_id.as_deref(), Some(expected_id.as_str()));
+            assert_eq!(event.event_type, "conformance");
+            assert_eq!(event.id, Some(format!("biz-{}", index)));
+        }
+    }
+
+    /// Replay after the newest ID returns nothing
+    pub async fn replay_after_latest_is_empty<S: MessageStorage>(storage: S) {
+        let ids = store_many(&storage, TEST_CHANNEL, 3).await;
+        let last = ids.last().unwrap();
+
+        // Wait until the write is visible before asserting emptiness
+        let visible = eventually(|| async {
+            storage.get_messages_after(TEST_CHANNEL, Some(&ids[0])).await.len() == 2
+        })
+        .await;
+        assert!(visible, "stored events never became visible");
+        assert!(storage.get_messages_after(TEST_CHANNEL, Some(last)).await.is_empty());
+    }
+
+    /// No last-event-id means no replay
+    pub async fn no_cursor_no_replay<S: MessageStorage>(storage: S) {
+        store_many(&storage, TEST_CHANNEL, 3).await;
+        assert!(storage.get_messages_after(TEST_CHANNEL, None).await.is_empty());
+    }
+
+    /// Malformed or unknown IDs never cause errors or foreign data
+    pub async fn unknown_cursor_is_empty<S: MessageStorage>(storage: S) {
+        store_many(&storage, TEST_CHANNEL, 3).await;
+        assert!(storage
+            .get_messages_after(TEST_CHANNEL, Some("not-a-stream-id"))
+            .await
+            .is_empty());
+    }
+
+    /// Events on one channel are never replayed on another
+    pub async fn channels_are_isolated<S: MessageStorage>(storage: S) {
+        let a = store_many(&storage, "conformance-a", 2).await;
+        let b = store_many(&storage, "conformance-b", 3).await;
+
+        let visible = eventually(|| async {
+            storage.get_messages_after("conformance-b", Some(&b[0])).await.len() == 2
+        })
+        .await;
+        assert!(visible, "expected 2 events on channel b");
+        assert_eq!(storage.get_messages_after("conformance-a", Some(&a[0])).await.len(), 1);
+    }
+
+    /// The cursor is the newest stored ID, and replaying from it yields nothing
+    pub async fn reports_latest_cursor<S: MessageStorage>(storage: S) {
+        assert_eq!(storage.latest_id("conformance-empty").await, None);
+
+        let ids = store_many(&storage, TEST_CHANNEL, 3).await;
+        let last = ids.last().cloned();
+        let visible = eventually(|| async { storage.latest_id(TEST_CHANNEL).await == last }).await;
+        assert!(visible, "expected the cursor to reach the last stored ID");
+        assert!(storage
+            .get_messages_after(TEST_CHANNEL, last.as_deref())
+            .await
+            .is_empty());
+    }
+
+    /// Consumer cursors round-trip and are isolated per channel and consumer
+    pub async fn saves_consumer_cursors<S: MessageStorage>(storage: S) {
+        assert_eq!(storage.get_cursor(TEST_CHANNEL, "tab-1").await, None);
+
+        let ids = store_many(&storage, TEST_CHANNEL, 2).await;
+        storage.save_cursor(TEST_CHANNEL, "tab-1", &ids[0]).await;
+        storage.save_cursor(TEST_CHANNEL, "tab-2", &ids[1]).await;
+        storage.save_cursor(TEST_CHANNEL, "tab-1", &ids[1]).await;
+
+        assert_eq!(storage.get_cursor(TEST_CHANNEL, "tab-1").await.as_ref(), Some(&ids[1]));
+        assert_eq!(storage.get_cursor(TEST_CHANNEL, "tab-2").await.as_ref(), Some(&ids[1]));
+        assert_eq!(storage.get_cursor("conformance-b", "tab-1").await, None);
+    }
+
+    /// Resume sessions round-trip, are replaced on save and expire after their
+    /// TTL, and the expiry sweep keeps live ones
+    pub async fn saves_resume_sessions<S: MessageStorage>(storage: S) {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        assert_eq!(storage.load_session(&token).await, None);
+
+        storage.save_session(&token, r#"{"channel_id":"a"}"#, Duration::from_secs(60)).await;
+        storage.save_session(&token, r#"{"channel_id":"b"}"#, Duration::from_secs(60)).await;
+        assert_eq!(storage.load_session(&token).await.as_deref(), Some(r#"{"channel_id":"b"}"#));
+
+        let short = uuid::Uuid::new_v4().simple().to_string();
+        storage.save_session(&short, "{}", Duration::from_millis(50)).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        storage.cleanup_expired_sessions().await;
+        assert_eq!(storage.load_session(&short).await, None);
+        assert!(storage.load_session(&token).await.is_some());
+    }
+
+    /// Purges delete events before a cutoff, then everything including cursors
+    pub async fn purges_channels<S: MessageStorage>(storage: S) {
+        let channel = "conformance-purge";
+        let ids = store_many(&storage, channel, 3).await;
+        storage.save_cursor(channel, "tab-1", &ids[0]).await;
+        let last = ids.last().cloned();
+        let visible = eventually(|| async { storage.latest_id(channel).await == last }).await;
+        assert!(visible, "stored events never became visible");
+
+        let before = PurgeBefore::Id(ids[1].clone());
+        assert_eq!(storage.purge_before(channel, &before).await, 1);
+        let remaining: Vec<_> = storage
+            .recent_messages(channel, 10)
+            .await
+            .into_iter()
+            .filter_map(|e| e.stream_id)
+            .collect();
+        assert_eq!(remaining, ids[1..]);
+
+        assert_eq!(storage.purge(channel).await, 2);
+        assert_eq!(storage.latest_id(channel).await, None);
+        assert_eq!(storage.get_cursor(channel, "tab-1").await, None);
+        assert!(storage.get_messages_after(channel, Some(&ids[0])).await.is_empty());
+    }
+
+    /// Usage deltas add up per day, keeping the peak and counting each subscriber once
+    pub async fn records_channel_usage<S: MessageStorage>(storage: S) {
+        let channel = format!("conformance-usage-{}", uuid::Uuid::new_v4().simple());
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let delta = |published, delivered, subscribers: &[&str], peak_subscribers| UsageDelta {
+            published,
+            delivered,
+            subscribers: subscribers.iter().map(|s| s.to_string()).collect(),
+            peak_subscribers,
+            ..UsageDelta::new(date)
+        };
+        storage.record_channel_usage(&channel, &delta(2, 5, &["alice", "bob"], 2)).await;
+        storage.record_channel_usage(&channel, &delta(1, 3, &["bob", "carol"], 1)).await;
+
+        let days = storage.channel_usage(&channel, date.pred_opt().unwrap(), date.succ_opt().unwrap()).await;
+        let expected = DailyUsage {
+            published: 3,
+            delivered: 8,
+            unique_subscribers: 3,
+            peak_subscribers: 2,
+            ..DailyUsage::new(date)
+        };
+        assert_eq!(days, vec![expected]);
+
+        let later = date.succ_opt().unwrap();
+        assert!(storage.channel_usage(&channel, later, later).await.is_empty());
+    }
+}
+
+/// Checks for `MessageSource` implementations
+pub mod source {
+    use super::{SourceFixture, SETTLE_TIMEOUT, TEST_CHANNEL};
+    use crate::manager::ConnectionManager;
+    use crate::source::{ConnectionInfo, IncomingMessage, MessageHandler, MessageSource};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    struct Running {
+        cancel: CancellationToken,
+        handle: tokio::task::JoinHandle<anyhow::Result<()>>,
+        received: mpsc::UnboundedReceiver<IncomingMessage>,
+    }
+
+    fn spawn<S: MessageSource>(source: S) -> Running {
+        let cancel = CancellationToken::new();
+        let (tx, received) = mpsc::unbounded_channel();
+        let handler = MessageHandler::from_fn(move |msg| {
+            let _ = tx.send(msg);
+        });
+        let source_cancel = cancel.clone();
+        let handle = tokio::spawn(async move {
+            source
+                .start(handler, ConnectionManager::new("conformance"), source_cancel)
+                .await
+        });
+        Running {
+            cancel,
+            handle,
+            received,
+        }
+    }
+
+    async fn stop(running: Running) {
+        running.cancel.cancel();
+        let result = tokio::time::timeout(SETTLE_TIMEOUT, running.handle)
+            .await
+            .expect("source did not stop after cancellation")
+            .expect("source task panicked");
+        assert!(result.is_ok(), "source returned an error: {:?}", result.err());
+    }
+
+    async fn publish_and_collect<S: MessageSource>(
+        fixture: SourceFixture<S>,
+        count: usize,
+    ) -> (Running, Vec<IncomingMessage>) {
+        let mut running = spawn(fixture.source);
+        // Give subscription-based backends a moment to subscribe
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        for i in 0..count {
+            let msg = IncomingMessage::new("conformance", format!("msg-{}", i)).with_channel(TEST_CHANNEL);
+            (fixture.publish)(msg).await.expect("publish failed");
+        }
+
+        let mut received = Vec::with_capacity(count);
+        while received.len() < count {
+            match tokio::time::timeout(SETTLE_TIMEOUT, running.received.recv()).await {
+                Ok(Some(msg)) => received.push(msg),
+                _ => break,
+            }
+        }
+        (running, received)
+    }
+
+    /// Source has a name for logging
+    pub async fn has_name<S: MessageSource>(fixture: SourceFixture<S>) {
+        assert!(!fixture.source.name().is_empty(), "source name should not be empty");
+    }
+
+    /// Published messages arrive once each, in publish order, with fields intact
+    pub async fn delivers_in_order<S: MessageSource>(fixture: SourceFixture<S>) {
+        let (running, received) = publish_and_collect(fixture, 20).await;
+        assert_eq!(received.len(), 20, "not all messages were delivered");
+        for (i, msg) in received.iter().enumerate() {
+            assert_eq!(msg.data, format!("msg-{}", i));
+            assert_eq!(msg.channel_id.as_deref(), Some(TEST_CHANNEL));
+        }
+        stop(running).await;
+    }
+
+    /// `start` returns promptly and cleanly once cancelled
+    pub async fn stops_on_cancel<S: MessageSource>(fixture: SourceFixture<S>) {
+        let running = spawn(fixture.source);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop(running).await;
+    }
+
+    /// A fresh instance works after a previous one was cancelled (reconnect)
+    pub async fn restarts_after_cancel<S, F, Fut>(factory: F)
+    where
+        S: MessageSource,
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = SourceFixture<S>>,
+    {
+        let running = spawn(factory().await.source);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop(running).await;
+
+        let (running, received) = publish_and_collect(factory().await, 3).await;
+        assert_eq!(received.len(), 3, "restarted source did not deliver messages");
+        stop(running).await;
+    }
+
+    /// Lifecycle hooks can be called at any time without panicking
+    ///
+    /// They may return errors, e.g. before the source has connected.
+    pub async fn lifecycle_hooks_are_safe<S: MessageSource>(fixture: SourceFixture<S>) {
+        let info = ConnectionInfo {
+            channel_id: TEST_CHANNEL.to_string(),
+            connection_id: "conformance-connection".to_string(),
+            instance_id: "conformance".to_string(),
+            close_reason: None,
+        };
+        let _ = fixture.source.on_connect(&info).await;
+        let _ = fixture.source.on_disconnect(&info).await;
+    }
+}
+
+/// Generate the storage conformance battery as `#[test]` functions
+///
+/// `$factory` is an expression evaluating to a future that yields a fresh storage.
+#[macro_export]
+macro_rules! storage_conformance {
+    ($(#[$meta:meta])* $name:ident, $factory:expr) => {
+        #[allow(unused_imports)]
+        mod $name {
+            use super::*;
+
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, is_available);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, generates_unique_ids);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, replays_in_order);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, replay_preserves_fields);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, replay_after_latest_is_empty);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, no_cursor_no_replay);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, unknown_cursor_is_empty);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, channels_are_isolated);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, reports_latest_cursor);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, saves_consumer_cursors);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, saves_resume_sessions);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, purges_channels);
+            $crate::storage_conformance!(@test [$(#[$meta])*] $factory, records_channel_usage);
+        }
+    };
+    (@test [$(#[$meta:meta])*] $factory:expr, $check:ident) => {
+        #[test]
+        $(#[$meta])*
+        fn $check() {
+            $crate::testkit::block_on(async {
+                $crate::testkit::storage::$check($factory.await).await;
+            });
+        }
+    };
+}
+
+/// Generate the source conformance battery as `#[test]` functions
+///
+/// `$factory` is an expression evaluating to a future that yields a fresh
+/// [`SourceFixture`]. It is evaluated once per check (twice for the restart check).
+#[macro_export]
+macro_rules! source_conformance {
+    ($(#[$meta:meta])* $name:ident, $factory:expr) => {
+        #[allow(unused_imports)]
+        mod $name {
+            use super::*;
+
+            $crate::source_conformance!(@test [$(#[$meta])*] $factory, has_name);
+            $crate::source_conformance!(@test [$(#[$meta])*] $factory, delivers_in_order);
+            $crate::source_conformance!(@test [$(#[$meta])*] $factory, stops_on_cancel);
+            $crate::source_conformance!(@test [$(#[$meta])*] $factory, lifecycle_hooks_are_safe);
+
+            #[test]
+            $(#[$meta])*
+            fn restarts_after_cancel() {
+                $crate::testkit::block_on(async {
+                    $crate::testkit::source::restarts_after_cancel(|| $factory).await;
+                });
+            }
+        }
+    };
+    (@test [$(#[$meta:meta])*] $factory:expr, $check:ident) => {
+        #[test]
+        $(#[$meta])*
+        fn $check() {
+            $crate::testkit::block_on(async {
+                $crate::testkit::source::$check($factory.await).await;
+            });
+        }
+    };
+}
@@ -0,0 +1,247 @@
+//! Per-channel publish throttling
+//!
+//! Limits how fast events can be published to a single channel so one
+//! misbehaving producer can't flood its subscribers.
+
+use arc_swap::ArcSwap;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::metrics::GatewayMetrics;
+
+/// What to do with an event that exceeds the channel quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleAction {
+    /// Silently discard the event
+    Drop,
+    /// Turn the event away with the time until the quota allows it, for the
+    /// publisher to retry then (up to `max_delay`, otherwise it is dropped)
+    Delay,
+    /// Discard the event and report an error to the publisher if possible
+    Reject,
+}
+
+/// Per-channel rate/quota policy
+#[derive(Debug, Clone)]
+pub struct ThrottlePolicy {
+    /// Maximum events per second per channel (None = unlimited)
+    pub events_per_sec: Option<u32>,
+    /// Maximum payload bytes per second per channel (None = unlimited)
+    pub bytes_per_sec: Option<u64>,
+    /// Behavior when the quota is exceeded
+    pub action: ThrottleAction,
+    /// Longest retry-after a `Delay` may ask for before dropping instead
+    pub max_delay: Duration,
+}
+
+impl ThrottlePolicy {
+    /// Create a policy with the given action and no limits
+    pub fn new(action: ThrottleAction) -> Self {
+        Self {
+            events_per_sec: None,
+            bytes_per_sec: None,
+            action,
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Limit events per second
+    pub fn events_per_sec(mut self, limit: u32) -> Self {
+        self.events_per_sec = Some(limit);
+        self
+    }
+
+    /// Limit payload bytes per second
+    pub fn bytes_per_sec(mut self, limit: u64) -> Self {
+        self.bytes_per_sec = Some(limit);
+        self
+    }
+
+    /// Set the maximum delay for `ThrottleAction::Delay`
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Whether the policy sets no limits; such a throttle keeps no state
+    pub fn is_unlimited(&self) -> bool {
+        self.events_per_sec.is_none() && self.bytes_per_sec.is_none()
+    }
+}
+
+/// Outcome of a throttle check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleDecision {
+    /// Deliver immediately
+    Allow,
+    /// Don't deliver now; the quota allows it after waiting
+    Delay(Duration),
+    /// Discard silently
+    Drop,
+    /// Discard and report to the publisher
+    Reject,
+}
+
+/// Token bucket state for one channel
+struct Bucket {
+    events: f64,
+    bytes: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    /// Take an event's quota from the limited dimensions only, so unlimited
+    /// ones don't drift negative and throttle once a limit is configured
+    fn consume(&mut self, event_rate: Option<f64>, byte_rate: Option<f64>, size: f64) {
+        if event_rate.is_some() {
+            self.events -= 1.0;
+        }
+        if byte_rate.is_some() {
+            self.bytes -= size;
+        }
+    }
+}
+
+/// Fit one dimension of a bucket to a new rate
+fn refit(level: f64, old_rate: Option<f64>, new_rate: Option<f64>) -> f64 {
+    match (old_rate, new_rate) {
+        (_, None) => 0.0,
+        (None, Some(rate)) => rate,
+        (Some(_), Some(rate)) => level.min(rate),
+    }
+}
+
+/// Per-channel throttle shared by the dispatcher and push handlers
+#[derive(Clone)]
+pub struct Throttle {
+    policy: Arc<ArcSwap<ThrottlePolicy>>,
+    buckets: Arc<DashMap<String, Bucket>>,
+}
+
+impl Throttle {
+    /// Create a throttle enforcing `policy` independently on each channel
+    pub fn new(policy: ThrottlePolicy) -> Self {
+        Self {
+            policy: Arc::new(ArcSwap::from_pointee(policy)),
+            buckets: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Get the current policy
+    pub fn policy(&self) -> Arc<ThrottlePolicy> {
+        self.policy.load_full()
+    }
+
+    /// Replace the policy; channels keep their buckets, capped by the new limits
+    ///
+    /// A dimension that was unlimited before starts with a full bucket.
+    pub fn set_policy(&self, policy: ThrottlePolicy) {
+        let old = self.policy.swap(Arc::new(policy.clone()));
+        if policy.is_unlimited() {
+            self.buckets.clear();
+            return;
+        }
+        for mut bucket in self.buckets.iter_mut() {
+            bucket.events = refit(
+                bucket.events,
+                old.events_per_sec.map(|r| r as f64),
+                policy.events_per_sec.map(|r| r as f64),
+            );
+            bucket.bytes = refit(
+                bucket.bytes,
+                old.bytes_per_sec.map(|r| r as f64),
+                policy.bytes_per_sec.map(|r| r as f64),
+            );
+        }
+    }
+
+    /// Check (and consume) quota for an event of `size` bytes on `channel_id`
+    pub fn check(&self, channel_id: &str, size: usize) -> ThrottleDecision {
+        let policy = self.policy.load();
+        if policy.is_unlimited() {
+            return ThrottleDecision::Allow;
+        }
+        let event_rate = policy.events_per_sec.map(|r| r as f64);
+        let byte_rate = policy.bytes_per_sec.map(|r| r as f64);
+        let size = size as f64;
+
+        let now = Instant::now();
+        let mut bucket = self
+            .buckets
+            .entry(channel_id.to_string())
+            .or_insert_with(|| Bucket {
+                events: event_rate.unwrap_or(0.0),
+                bytes: byte_rate.unwrap_or(0.0),
+                last_refill: now,
+            });
+
+        // Refill, allowing at most one second of burst
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.last_refill = now;
+        if let Some(rate) = event_rate {
+            bucket.events = (bucket.events + elapsed * rate).min(rate);
+        }
+        if let Some(rate) = byte_rate {
+            bucket.bytes = (bucket.bytes + elapsed * rate).min(rate);
+        }
+
+        let event_wait = event_rate
+            .filter(|_| bucket.events < 1.0)
+            .map(|rate| (1.0 - bucket.events) / rate)
+            .unwrap_or(0.0);
+        // An event larger than a second's quota passes once the bucket is full
+        let byte_wait = byte_rate
+            .filter(|&rate| bucket.bytes < size.min(rate))
+            .map(|rate| (size.min(rate) - bucket.bytes) / rate)
+            .unwrap_or(0.0);
+        let wait = event_wait.max(byte_wait);
+
+        if wait <= 0.0 {
+            bucket.consume(event_rate, byte_rate, size);
+            return ThrottleDecision::Allow;
+        }
+
+        match policy.action {
+            ThrottleAction::Drop => ThrottleDecision::Drop,
+            ThrottleAction::Reject => ThrottleDecision::Reject,
+            ThrottleAction::Delay => {
+                let wait = Duration::from_secs_f64(wait);
+                if wait > policy.max_delay {
+                    return ThrottleDecision::Drop;
+                }
+                // Nothing is reserved: the event comes back as a retry
+                ThrottleDecision::Delay(wait)
+            }
+        }
+    }
+
+    /// Check quota and record the outcome in `metrics`
+    pub fn check_and_record(
+        &self,
+        channel_id: &str,
+        size: usize,
+        metrics: &GatewayMetrics,
+    ) -> ThrottleDecision {
+        let decision = self.check(channel_id, size);
+        match decision {
+            ThrottleDecision::Allow => {}
+            ThrottleDecision::Delay(_) => GatewayMetrics::incr(&metrics.throttled_delayed),
+            ThrottleDecision::Drop => GatewayMetrics::incr(&metrics.throttled_dropped),
+            ThrottleDecision::Reject => GatewayMetrics::incr(&metrics.throttled_rejected),
+        }
+        if decision != ThrottleDecision::Allow {
+            tracing::debug!(channel_id, ?decision, "Channel throttled");
+        }
+        decision
+    }
+
+    /// Forget buckets for channels with no recent traffic
+    pub fn cleanup_idle(&self, idle: Duration) {
+        let now = Instant::now();
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.last_refill) < idle);
+    }
+}
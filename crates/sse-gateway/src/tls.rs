@@ -0,0 +1,259 @@
+//! Native TLS termination for the built-in server
+//!
+//! Certificates can be loaded from PEM files (with reload on SIGHUP or on an
+//! interval) or supplied as a ready-made rustls `ServerConfig`.
+
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+
+pub use rustls::ServerConfig;
+
+/// How long a client may take to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS settings for the gateway server
+#[derive(Clone)]
+pub enum TlsConfig {
+    /// Load certificate chain and private key from PEM files
+    Files {
+        cert_path: PathBuf,
+        key_path: PathBuf,
+        /// Re-read the files on this interval (SIGHUP always triggers a reload on Unix)
+        reload_interval: Option<Duration>,
+    },
+    /// Use a pre-built rustls configuration (no reload)
+    Rustls(Arc<ServerConfig>),
+}
+
+impl TlsConfig {
+    /// TLS from PEM certificate and key files
+    pub fn from_pem_files(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        TlsConfig::Files {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            reload_interval: None,
+        }
+    }
+}
+
+/// Load a rustls server configuration from PEM certificate and key files
+///
+/// ALPN is set to advertise both `h2` and `http/1.1`.
+pub fn load_server_config(
+    cert_path: impl AsRef<Path>,
+    key_path: impl AsRef<Path>,
+) -> anyhow::Result<Arc<ServerConfig>> {
+    let cert_path = cert_path.as_ref();
+    let key_path = key_path.as_ref();
+
+    let mut cert_reader = BufReader::new(std::fs::File::open(cert_path).map_err(|e| {
+        anyhow::anyhow!("Failed to open certificate {}: {}", cert_path.display(), e)
+    })?);
+    let certs = rustls_pemfile::certs(&mut cert_reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in {}", cert_path.display());
+    }
+
+    let mut key_reader = BufReader::new(std::fs::File::open(key_path).map_err(|e| {
+        anyhow::anyhow!("Failed to open private key {}: {}", key_path.display(), e)
+    })?);
+    let key = rustls_pemfile::private_key(&mut key_reader)?
+        .ok_or_else(|| anyhow::anyhow!("No private key found in {}", key_path.display()))?;
+
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+/// TLS acceptor that can be swapped out when certificates are reloaded
+#[derive(Clone)]
+struct ReloadableAcceptor {
+    current: Arc<RwLock<TlsAcceptor>>,
+}
+
+impl ReloadableAcceptor {
+    fn new(config: Arc<ServerConfig>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(TlsAcceptor::from(config))),
+        }
+    }
+
+    fn get(&self) -> TlsAcceptor {
+        self.current.read().expect("TLS acceptor lock poisoned").clone()
+    }
+
+    fn reload(&self, cert_path: &Path, key_path: &Path) {
+        match load_server_config(cert_path, key_path) {
+            Ok(config) => {
+                *self.current.write().expect("TLS acceptor lock poisoned") =
+                    TlsAcceptor::from(config);
+                tracing::info!(cert = %cert_path.display(), "TLS certificate reloaded");
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "TLS certificate reload failed, keeping previous certificate");
+            }
+        }
+    }
+}
+
+/// Listener that terminates TLS before handing connections to axum
+///
+/// Handshakes run on their own tasks so a slow client can't stall accepts.
+pub(crate) struct TlsListener {
+    rx: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    /// Wrap a bound TCP listener with TLS
+    pub(crate) fn new(
+        tcp: TcpListener,
+        config: TlsConfig,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<Self> {
+        let local_addr = tcp.local_addr()?;
+
+        let acceptor = match &config {
+            TlsConfig::Files {
+                cert_path,
+                key_path,
+                reload_interval,
+            } => {
+                let acceptor = ReloadableAcceptor::new(load_server_config(cert_path, key_path)?);
+                spawn_reload_task(
+                    acceptor.clone(),
+                    cert_path.clone(),
+                    key_path.clone(),
+                    *reload_interval,
+                    cancel.clone(),
+                );
+                acceptor
+            }
+            TlsConfig::Rustls(config) => ReloadableAcceptor::new(config.clone()),
+        };
+
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(async move {
+            loop {
+                let (stream, addr) = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    accepted = tcp.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            tracing::debug!(error = %e, "TCP accept failed");
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            continue;
+                        }
+                    },
+                };
+
+                let acceptor = acceptor.get();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(tls_stream)) => {
+                            let _ = tx.send((tls_stream, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!(error = %e, %addr, "TLS handshake failed"),
+                        Err(_) => tracing::debug!(%addr, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self { rx, local_addr })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.rx.recv().await {
+            Some(accepted) => accepted,
+            // Accept loop stopped (shutdown); let graceful shutdown finish
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Reload certificates on SIGHUP and/or a fixed interval
+fn spawn_reload_task(
+    acceptor: ReloadableAcceptor,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    reload_interval: Option<Duration>,
+    cancel: CancellationToken,
+) {
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        let mut hangup =
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(signal) => Some(signal),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to install SIGHUP handler for TLS reload");
+                    None
+                }
+            };
+
+        let mut interval = reload_interval.map(|period| {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval
+        });
+        if let Some(interval) = interval.as_mut() {
+            // The first tick completes immediately
+            interval.tick().await;
+        }
+
+        loop {
+            let tick = async {
+                match interval.as_mut() {
+                    Some(interval) => {
+                        interval.tick().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
+            #[cfg(unix)]
+            let sighup = async {
+                match hangup.as_mut() {
+                    Some(signal) => {
+                        signal.recv().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+            #[cfg(not(unix))]
+            let sighup = std::future::pending::<()>();
+
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tick => {}
+                _ = sighup => tracing::info!("Received SIGHUP, reloading TLS certificate"),
+            }
+
+            acceptor.reload(&cert_path, &key_path);
+        }
+    });
+}
@@ -0,0 +1,610 @@
+//! Connection lifecycle webhooks
+//!
+//! POSTs connect and disconnect notifications to an HTTP endpoint, so a
+//! backend can track who is online without implementing a `MessageSource`.
+//!
+//! ```rust,ignore
+//! use sse_gateway::webhook::LifecycleWebhook;
+//!
+//! Gateway::builder()
+//!     .identity(|req| req.header("x-user-id").map(str::to_string))
+//!     .lifecycle_webhook(
+//!         LifecycleWebhook::new("https://backend.internal/hooks/sse")
+//!             .secret("shared-secret"),
+//!     )
+//! ```
+//!
+//! Each request carries a batch, `{"events": [LifecycleEvent, ...]}`. With a
+//! secret, the `X-Gateway-Signature` header is `sha256=` followed by the hex
+//! HMAC-SHA256 of the raw body (see [`signature`]).
+//!
+//! [`WebhookSubscriber`] goes the other way: it subscribes a URL to a channel,
+//! for consumers such as serverless functions that can't hold a stream open.
+//!
+//! ```rust,ignore
+//! use sse_gateway::webhook::WebhookSubscriber;
+//!
+//! Gateway::builder()
+//!     .webhook_subscriber(
+//!         "orders",
+//!         WebhookSubscriber::new("https://fn.example.com/orders").secret("shared-secret"),
+//!     )
+//! ```
+//!
+//! Each event is POSTed on its own as a [`WebhookDelivery`], signed the same way.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::cluster::Cluster;
+use crate::connection::{CloseReason, ConnectionCounters, ConnectionMetadata, SendStats};
+use crate::event::SseEvent;
+use crate::manager::ConnectionManager;
+use crate::source::ConnectionInfo;
+
+/// Header carrying the body signature
+pub const SIGNATURE_HEADER: &str = "x-gateway-signature";
+
+/// User agent and connection attribute identifying webhook subscribers
+pub const WEBHOOK_SUBSCRIBER: &str = "webhook";
+
+/// Where and how lifecycle notifications are delivered
+#[derive(Debug, Clone)]
+pub struct LifecycleWebhook {
+    url: String,
+    secret: Option<String>,
+    max_batch: usize,
+    flush_interval: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+    timeout: Duration,
+}
+
+impl LifecycleWebhook {
+    /// Notify `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            max_batch: 100,
+            flush_interval: Duration::from_secs(1),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Sign request bodies with HMAC-SHA256
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Send up to `max_events` per request, at most `interval` after the first (default: 100, 1s)
+    pub fn batch(mut self, max_events: usize, interval: Duration) -> Self {
+        self.max_batch = max_events.max(1);
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Retry failed requests `max_retries` times, doubling `backoff` each time (default: 3, 500ms)
+    ///
+    /// A batch is dropped (and logged) once retries are exhausted.
+    pub fn retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Per-request timeout (default: 10s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Lifecycle transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEventKind {
+    /// A subscriber connected
+    Connect,
+    /// A subscriber disconnected
+    Disconnect,
+}
+
+/// One notification in a webhook batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    /// Connect or disconnect
+    #[serde(rename = "type")]
+    pub kind: LifecycleEventKind,
+    /// The channel ID
+    pub channel_id: String,
+    /// Unique connection ID
+    pub connection_id: String,
+    /// Connection metadata (instance, identity, client IP, user agent, connect time)
+    #[serde(flatten)]
+    pub metadata: ConnectionMetadata,
+    /// When the transition happened
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// How long the connection lasted (disconnect only)
+    pub duration_ms: Option<u64>,
+    /// Why the connection closed (disconnect only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<CloseReason>,
+}
+
+#[derive(Serialize)]
+struct Batch<'a> {
+    events: &'a [LifecycleEvent],
+}
+
+/// `sha256=<hex>` signature of `body`, as sent in [`SIGNATURE_HEADER`]
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Queues lifecycle events and delivers them in the background
+pub(crate) struct WebhookNotifier {
+    /// Metadata of open connections; it is gone from the manager by disconnect time
+    open: DashMap<String, ConnectionMetadata>,
+    queue: mpsc::UnboundedSender<LifecycleEvent>,
+}
+
+impl WebhookNotifier {
+    pub(crate) fn spawn(config: LifecycleWebhook, cancel: CancellationToken) -> (Self, JoinHandle<()>) {
+        let (queue, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(deliver(config, receiver, cancel));
+        let notifier = Self {
+            open: DashMap::new(),
+            queue,
+        };
+        (notifier, task)
+    }
+
+    pub(crate) fn connect(&self, connection_manager: &ConnectionManager, info: &ConnectionInfo) {
+        let Some(metadata) = connection_manager
+            .channel_connections(&info.channel_id)
+            .into_iter()
+            .find(|c| *c.id == *info.connection_id)
+            .map(|c| c.metadata)
+        else {
+            return;
+        };
+        self.open.insert(info.connection_id.clone(), metadata.clone());
+        self.push(LifecycleEventKind::Connect, info, metadata, None);
+    }
+
+    pub(crate) fn disconnect(&self, info: &ConnectionInfo) {
+        let Some((_, metadata)) = self.open.remove(&info.connection_id) else { return };
+        let duration = (chrono::Utc::now() - metadata.connected_at)
+            .to_std()
+            .unwrap_or_default();
+        self.push(
+            LifecycleEventKind::Disconnect,
+            info,
+            metadata,
+            Some(duration.as_millis() as u64),
+        );
+    }
+
+    fn push(
+        &self,
+        kind: LifecycleEventKind,
+        info: &ConnectionInfo,
+        metadata: ConnectionMetadata,
+        duration_ms: Option<u64>,
+    ) {
+        let _ = self.queue.send(LifecycleEvent {
+            kind,
+            channel_id: info.channel_id.clone(),
+            connection_id: info.connection_id.clone(),
+            metadata,
+            timestamp: chrono::Utc::now(),
+            duration_ms,
+            close_reason: info.close_reason,
+        });
+    }
+}
+
+/// Batch queued events and POST them until cancelled, then flush what's left
+async fn deliver(
+    config: LifecycleWebhook,
+    mut receiver: mpsc::UnboundedReceiver<LifecycleEvent>,
+    cancel: CancellationToken,
+) {
+    let client = match reqwest::Client::builder().timeout(config.timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to create webhook client");
+            return;
+        }
+    };
+
+    let mut batch = Vec::with_capacity(config.max_batch);
+    loop {
+        // Wait for the first event, then collect until the batch is full or due
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            event = receiver.recv() => match event {
+                Some(event) => batch.push(event),
+                None => break,
+            },
+        }
+        let deadline = tokio::time::sleep(config.flush_interval);
+        tokio::pin!(deadline);
+        while batch.len() < config.max_batch {
+            tokio::select! {
+                _ = &mut deadline => break,
+                _ = cancel.cancelled() => break,
+                event = receiver.recv() => match event {
+                    Some(event) => batch.push(event),
+                    None => break,
+                },
+            }
+        }
+        post(&client, &config, &batch).await;
+        batch.clear();
+    }
+
+    // Connections closed during shutdown still get their disconnect
+    while let Ok(event) = receiver.try_recv() {
+        batch.push(event);
+    }
+    for chunk in batch.chunks(config.max_batch) {
+        post(&client, &config, chunk).await;
+    }
+}
+
+async fn post(client: &reqwest::Client, config: &LifecycleWebhook, events: &[LifecycleEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let body = match serde_json::to_vec(&Batch { events }) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to encode webhook batch");
+            return;
+        }
+    };
+
+    let secret = config.secret.as_deref();
+    match post_signed(client, &config.url, secret, body, config.max_retries, config.retry_backoff).await {
+        Ok(()) => tracing::debug!(events = events.len(), "Lifecycle webhook delivered"),
+        Err(error) => {
+            tracing::warn!(error = %error, events = events.len(), url = %config.url, "Lifecycle webhook failed, dropping batch");
+        }
+    }
+}
+
+/// POST a JSON `body`, signed if there is a secret, retrying with doubling backoff
+async fn post_signed(
+    client: &reqwest::Client,
+    url: &str,
+    secret: Option<&str>,
+    body: Vec<u8>,
+    max_retries: u32,
+    backoff: Duration,
+) -> Result<(), String> {
+    let signature = secret.map(|secret| signature(secret, &body));
+    let mut backoff = backoff;
+    for attempt in 0..=max_retries {
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => format!("status {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt == max_retries {
+            return Err(error);
+        }
+        tracing::debug!(error = %error, attempt, url, "Webhook request failed, retrying");
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    unreachable!("the last attempt returns")
+}
+
+/// An HTTP endpoint subscribed to a channel
+///
+/// It joins the channel like an SSE connection: it counts as a subscriber,
+/// receives forwarded cluster traffic and follows the channel's
+/// [`Backpressure`](crate::Backpressure) policy while a request is in flight.
+/// Events are POSTed one at a time, in order.
+#[derive(Debug, Clone)]
+pub struct WebhookSubscriber {
+    url: String,
+    secret: Option<String>,
+    max_retries: u32,
+    retry_backoff: Duration,
+    timeout: Duration,
+}
+
+impl WebhookSubscriber {
+    /// POST events to `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Sign request bodies with HMAC-SHA256
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Retry failed requests `max_retries` times, doubling `backoff` each time (default: 3, 500ms)
+    ///
+    /// An event is dropped (and counted) once retries are exhausted.
+    pub fn retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Per-request timeout (default: 10s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Body of a request to a [`WebhookSubscriber`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Channel the event was published to
+    pub channel_id: String,
+    /// ID of the subscription, as listed by the admin API
+    pub subscriber_id: String,
+    /// The event, with the fields of an SSE event
+    #[serde(flatten)]
+    pub event: SseEvent,
+}
+
+/// A registered webhook subscriber, as listed by the admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    /// Subscription ID, also its connection ID
+    pub id: String,
+    pub channel_id: String,
+    pub url: String,
+    /// Events delivered (`events_sent`) and given up on (`events_dropped`)
+    pub stats: SendStats,
+}
+
+struct Subscription {
+    channel_id: Arc<str>,
+    url: String,
+    counters: Arc<ConnectionCounters>,
+}
+
+/// Webhook subscribers of all channels, each delivered by its own task
+#[derive(Clone)]
+pub(crate) struct WebhookSubscribers {
+    connection_manager: ConnectionManager,
+    cluster: Option<Cluster>,
+    cancel: CancellationToken,
+    subscriptions: Arc<DashMap<Arc<str>, Subscription>>,
+}
+
+impl WebhookSubscribers {
+    pub(crate) fn new(
+        connection_manager: ConnectionManager,
+        cluster: Option<Cluster>,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            connection_manager,
+            cluster,
+            cancel,
+            subscriptions: Arc::default(),
+        }
+    }
+
+    /// Register `subscriber` on `channel_id`, returning the subscription ID
+    pub(crate) fn subscribe(&self, channel_id: &str, subscriber: WebhookSubscriber) -> anyhow::Result<String> {
+        let client = reqwest::Client::builder().timeout(subscriber.timeout).build()?;
+        let attributes = HashMap::from([(WEBHOOK_SUBSCRIBER.to_string(), subscriber.url.clone())]);
+        let (connection, receiver) = self.connection_manager.register_with_attributes(
+            channel_id.to_string(),
+            None,
+            Some(WEBHOOK_SUBSCRIBER.to_string()),
+            None,
+            attributes,
+        );
+        // Only the manager keeps a sender, so closing the connection ends the task
+        let id = connection.id.clone();
+        let counters = connection.counters.clone();
+        drop(connection);
+
+        self.subscriptions.insert(
+            id.clone(),
+            Subscription {
+                channel_id: channel_id.into(),
+                url: subscriber.url.clone(),
+                counters: counters.clone(),
+            },
+        );
+        if let Some(cluster) = &self.cluster {
+            cluster.on_connect(&self.connection_manager, channel_id);
+        }
+        tracing::info!(subscriber_id = %id, channel_id, url = %subscriber.url, "Webhook subscriber registered");
+
+        tokio::spawn(self.clone().deliver(id.clone(), channel_id.into(), subscriber, client, receiver, counters));
+        Ok(id.to_string())
+    }
+
+    /// Remove a subscription from `channel_id`; events already queued are still delivered
+    pub(crate) fn unsubscribe(&self, channel_id: &str, id: &str) -> bool {
+        let removed = self
+            .subscriptions
+            .remove_if(id, |_, subscription| *subscription.channel_id == *channel_id);
+        if removed.is_none() {
+            return false;
+        }
+        self.connection_manager.close(id, CloseReason::Kicked);
+        true
+    }
+
+    /// Subscriptions on `channel_id`
+    pub(crate) fn list(&self, channel_id: &str) -> Vec<WebhookSubscription> {
+        let mut subscriptions: Vec<_> = self
+            .subscriptions
+            .iter()
+            .filter(|entry| *entry.channel_id == *channel_id)
+            .map(|entry| WebhookSubscription {
+                id: entry.key().to_string(),
+                channel_id: channel_id.to_string(),
+                url: entry.url.clone(),
+                stats: entry.counters.snapshot(),
+            })
+            .collect();
+        subscriptions.sort_by(|a, b| a.id.cmp(&b.id));
+        subscriptions
+    }
+
+    /// POST each event queued for the subscription until it is closed or the gateway stops
+    async fn deliver(
+        self,
+        id: Arc<str>,
+        channel_id: Arc<str>,
+        subscriber: WebhookSubscriber,
+        client: reqwest::Client,
+        mut receiver: mpsc::Receiver<SseEvent>,
+        counters: Arc<ConnectionCounters>,
+    ) {
+        loop {
+            let event = tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                event = receiver.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+            let delivery = WebhookDelivery {
+                channel_id: channel_id.to_string(),
+                subscriber_id: id.to_string(),
+                event,
+            };
+            let body = match serde_json::to_vec(&delivery) {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to encode webhook delivery");
+                    counters.record_drop();
+                    continue;
+                }
+            };
+            let size = body.len();
+            let secret = subscriber.secret.as_deref();
+            match post_signed(&client, &subscriber.url, secret, body, subscriber.max_retries, subscriber.retry_backoff).await {
+                Ok(()) => counters.record_write(true, size),
+                Err(error) => {
+                    counters.record_drop();
+                    tracing::warn!(error = %error, subscriber_id = %id, url = %subscriber.url, "Webhook subscriber failed, dropping event");
+                }
+            }
+        }
+
+        self.subscriptions.remove(&id);
+        self.connection_manager.close(&id, CloseReason::ServerShutdown);
+        if let Some(cluster) = &self.cluster {
+            cluster.on_disconnect(&self.connection_manager, &channel_id);
+        }
+        tracing::info!(subscriber_id = %id, channel_id = %channel_id, "Webhook subscriber removed");
+    }
+
+    /// `GET`/`POST /api/channels/{id}/webhooks` and `DELETE /api/channels/{id}/webhooks/{webhook_id}`
+    pub(crate) fn router(&self) -> Router {
+        Router::new()
+            .route(
+                "/api/channels/{channel_id}/webhooks",
+                get(list_subscriptions).post(create_subscription),
+            )
+            .route(
+                "/api/channels/{channel_id}/webhooks/{webhook_id}",
+                delete(delete_subscription),
+            )
+            .with_state(self.clone())
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeRequest {
+    url: String,
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+async fn list_subscriptions(
+    State(subscribers): State<WebhookSubscribers>,
+    Path(channel_id): Path<String>,
+) -> Json<Vec<WebhookSubscription>> {
+    Json(subscribers.list(&channel_id))
+}
+
+async fn create_subscription(
+    State(subscribers): State<WebhookSubscribers>,
+    Path(channel_id): Path<String>,
+    Json(req): Json<SubscribeRequest>,
+) -> Result<(StatusCode, Json<WebhookSubscription>), (StatusCode, String)> {
+    match reqwest::Url::parse(&req.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => return Err((StatusCode::BAD_REQUEST, format!("invalid webhook URL: {}", req.url))),
+    }
+    let mut subscriber = WebhookSubscriber::new(req.url.clone());
+    if let Some(secret) = req.secret {
+        subscriber = subscriber.secret(secret);
+    }
+    let id = subscribers
+        .subscribe(&channel_id, subscriber)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let subscription = WebhookSubscription {
+        id,
+        channel_id,
+        url: req.url,
+        stats: SendStats::default(),
+    };
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+async fn delete_subscription(
+    State(subscribers): State<WebhookSubscribers>,
+    Path((channel_id, webhook_id)): Path<(String, String)>,
+) -> StatusCode {
+    if subscribers.unsubscribe(&channel_id, &webhook_id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
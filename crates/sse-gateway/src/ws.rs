@@ -0,0 +1,159 @@
+//! WebSocket fallback transport
+//!
+//! Serves the same event stream as the SSE endpoint for clients behind
+//! proxies that buffer `text/event-stream` responses. Each event is sent as
+//! a JSON text frame mirroring the SSE fields:
+//!
+//! ```json
+//! {"event": "notification", "data": "{\"msg\":\"Hello!\"}", "id": "1700000000000-0"}
+//! ```
+//!
+//! `data` is the same string an SSE client would receive. Since browsers
+//! cannot set headers on WebSocket requests, the replay cursor is passed as a
+//! `last_event_id` query parameter (the `Last-Event-ID` header also works).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        OriginalUri, Query, State,
+    },
+    http::{HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::client_ip::PeerAddr;
+use crate::event::SseEvent;
+use crate::handler::{self, GatewayState, Subscription, LAST_EVENT_ID_PARAM};
+use crate::heartbeat::Outgoing;
+use crate::storage::MessageStorage;
+
+/// JSON frame sent for each event
+#[derive(Debug, Serialize)]
+struct Frame<'a> {
+    event: &'a str,
+    data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<u32>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(event: &'a SseEvent) -> Self {
+        Self {
+            event: &event.event_type,
+            data: event.data.to_string(),
+            id: event.stream_id.as_deref().or(event.id.as_deref()),
+            retry: event.retry,
+        }
+    }
+}
+
+fn to_message(event: &SseEvent) -> Message {
+    let json = serde_json::to_string(&Frame::new(event)).unwrap_or_default();
+    Message::Text(json.into())
+}
+
+/// Payload size of a frame, for the connection's byte counter
+fn payload_len(message: &Message) -> usize {
+    match message {
+        Message::Text(text) => text.len(),
+        Message::Binary(data) | Message::Ping(data) | Message::Pong(data) => data.len(),
+        Message::Close(_) => 0,
+    }
+}
+
+/// WebSocket connection endpoint (channel in the query string)
+pub(crate) async fn ws_connect<S: MessageStorage>(
+    State(state): State<GatewayState<S>>,
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    PeerAddr(peer): PeerAddr,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let Some(channel_id) = query.get(&*state.channel_param).filter(|c| !c.is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Missing {} parameter", state.channel_param),
+        )
+            .into_response();
+    };
+
+    let last_event_id = query.get(LAST_EVENT_ID_PARAM).cloned().or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+    });
+
+    let subscription = match handler::subscribe(
+        &state,
+        method,
+        uri,
+        channel_id.clone(),
+        &headers,
+        peer,
+        last_event_id,
+        None,
+    )
+    .await
+    {
+        Ok(subscription) => subscription,
+        Err(response) => return response,
+    };
+
+    upgrade.on_upgrade(move |socket| serve(socket, subscription))
+}
+
+/// Pump replayed, live and heartbeat events into the socket until either side closes
+async fn serve(mut socket: WebSocket, subscription: Subscription) {
+    let Subscription {
+        replay,
+        mut live,
+        guard,
+        ..
+    } = subscription;
+    let counters = guard.counters();
+
+    for event in &replay {
+        let message = to_message(event);
+        let len = payload_len(&message);
+        if socket.send(message).await.is_err() {
+            return;
+        }
+        counters.record_write(true, len);
+    }
+
+    // Same cadence as the SSE keep-alive comment
+    let mut keep_alive = tokio::time::interval(Duration::from_secs(10));
+    keep_alive.tick().await;
+
+    loop {
+        let message = tokio::select! {
+            outgoing = live.next() => match outgoing {
+                Some(Outgoing::Event(event)) => to_message(&event),
+                // Comment heartbeats have no frame equivalent
+                Some(Outgoing::Comment(_)) => Message::Ping(Default::default()),
+                None => break,
+            },
+            _ = keep_alive.tick() => Message::Ping(Default::default()),
+            incoming = socket.recv() => match incoming {
+                // Subscribers only listen; pings are answered by axum
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let (is_event, len) = (matches!(message, Message::Text(_)), payload_len(&message));
+        if socket.send(message).await.is_err() {
+            break;
+        }
+        counters.record_write(is_event, len);
+    }
+}
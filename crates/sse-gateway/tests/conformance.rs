@@ -0,0 +1,15 @@
+//! Conformance battery for the built-in adapters
+
+use sse_gateway::source::ChannelSource;
+use sse_gateway::testkit::SourceFixture;
+use sse_gateway::MemoryStorage;
+
+sse_gateway::storage_conformance!(memory_storage, async { MemoryStorage::new(100) });
+
+sse_gateway::source_conformance!(channel_source, async {
+    let (source, tx) = ChannelSource::new();
+    SourceFixture::new(source, move |msg| {
+        let tx = tx.clone();
+        async move { Ok(tx.send(msg).await?) }
+    })
+});
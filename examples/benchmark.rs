@@ -16,6 +16,7 @@
 
 use futures::StreamExt;
 use reqwest::Client;
+use sse_gateway_client::SseClient;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -98,13 +99,13 @@ fn compute_stats(name: &str, total_time: Duration, latencies: Vec<Duration>, err
     sorted.sort();
 
     let success_count = sorted.len() as u64;
-    let avg = if success_count > 0 {
-        Duration::from_nanos(
-            sorted.iter().map(|d| d.as_nanos() as u64).sum::<u64>() / success_count,
-        )
-    } else {
-        Duration::ZERO
-    };
+    let avg = sorted
+        .iter()
+        .map(|d| d.as_nanos() as u64)
+        .sum::<u64>()
+        .checked_div(success_count)
+        .map(Duration::from_nanos)
+        .unwrap_or(Duration::ZERO);
 
     BenchResults {
         name: name.to_string(),
@@ -249,16 +250,19 @@ async fn bench_e2e_latency(config: &BenchConfig) -> BenchResults {
     let num_messages = config.num_messages.min(100); // Limit for E2E test
 
     // Start SSE connection
-    let sse_url = format!(
-        "{}/sse/connect?channel_id={}",
-        config.gateway_url, channel_id
-    );
-
-    let sse_client = client.clone();
-    let sse_response = match sse_client.get(&sse_url).send().await {
-        Ok(resp) => resp,
-        Err(e) => {
-            println!("      Failed to connect SSE: {}", e);
+    let mut events = SseClient::new(&config.gateway_url, &channel_id)
+        .max_retries(0)
+        .connect();
+    let connected = async {
+        while !events.is_connected() && events.error().is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let _ = tokio::time::timeout(Duration::from_secs(5), connected).await;
+    match events.error() {
+        None if events.is_connected() => {}
+        error => {
+            println!("      Failed to connect SSE: {:?}", error);
             return BenchResults {
                 name: "End-to-End Latency".to_string(),
                 total_time: Duration::ZERO,
@@ -272,10 +276,7 @@ async fn bench_e2e_latency(config: &BenchConfig) -> BenchResults {
                 p99_latency: Duration::ZERO,
             };
         }
-    };
-
-    // Wait for connection to be established
-    tokio::time::sleep(Duration::from_millis(100)).await;
+    }
 
     let latencies = Arc::new(tokio::sync::Mutex::new(Vec::new()));
     let errors = Arc::new(AtomicU64::new(0));
@@ -290,48 +291,25 @@ async fn bench_e2e_latency(config: &BenchConfig) -> BenchResults {
     let num_messages_clone = num_messages;
 
     let receiver_handle = tokio::spawn(async move {
-        let mut stream = sse_response.bytes_stream();
-        let mut buffer = String::new();
-
         barrier_clone.wait().await;
 
-        while let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(bytes) => {
-                    buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                    // Parse SSE events
-                    while let Some(pos) = buffer.find("\n\n") {
-                        let event_str = buffer[..pos].to_string();
-                        buffer = buffer[pos + 2..].to_string();
-
-                        // Extract timestamp from data
-                        if let Some(data_line) = event_str
-                            .lines()
-                            .find(|l| l.starts_with("data:"))
-                        {
-                            let data = data_line.trim_start_matches("data:");
-                            if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                                if let Some(ts) = json.get("send_ts").and_then(|v| v.as_i64()) {
-                                    let now = chrono::Utc::now().timestamp_millis();
-                                    let latency = Duration::from_millis((now - ts).max(0) as u64);
-                                    latencies_clone.lock().await.push(latency);
-                                    received_clone.fetch_add(1, Ordering::SeqCst);
-                                }
-                            }
-                        }
-                    }
-
-                    if received_clone.load(Ordering::SeqCst) >= num_messages_clone as u64 {
-                        break;
-                    }
-                }
-                Err(_) => {
-                    errors_clone.fetch_add(1, Ordering::SeqCst);
-                    break;
+        while let Some(event) = events.next().await {
+            // Extract timestamp from data
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&event.data.to_string()) {
+                if let Some(ts) = json.get("send_ts").and_then(|v| v.as_i64()) {
+                    let now = chrono::Utc::now().timestamp_millis();
+                    let latency = Duration::from_millis((now - ts).max(0) as u64);
+                    latencies_clone.lock().await.push(latency);
+                    received_clone.fetch_add(1, Ordering::SeqCst);
                 }
             }
+
+            if received_clone.load(Ordering::SeqCst) >= num_messages_clone as u64 {
+                return;
+            }
         }
+        // The stream only ends if the gateway went away for good
+        errors_clone.fetch_add(1, Ordering::SeqCst);
     });
 
     // Sender task
@@ -351,7 +329,7 @@ async fn bench_e2e_latency(config: &BenchConfig) -> BenchResults {
             }
         });
 
-        if let Err(_) = client.post(&push_url).json(&payload).send().await {
+        if client.post(&push_url).json(&payload).send().await.is_err() {
             errors.fetch_add(1, Ordering::SeqCst);
         }
 
@@ -49,7 +49,7 @@ impl MessageSource for TimerSource {
                         }).to_string()
                     ).with_channel(&self.channel_id);
 
-                    handler(msg);
+                    handler.send(msg);
                     tracing::info!(count, "Sent tick");
                 }
             }
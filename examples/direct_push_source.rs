@@ -93,7 +93,7 @@ impl MessageSource for DirectPushSource {
                 _ = cancel.cancelled() => break,
                 msg = receiver.recv() => {
                     match msg {
-                        Some(msg) => handler(msg),
+                        Some(msg) => handler.send(msg),
                         None => break,
                     }
                 }
@@ -107,8 +107,11 @@ impl MessageSource for DirectPushSource {
         "DirectPush"
     }
 
-    /// Called when a new SSE connection is established
-    fn on_connect(&self, info: &ConnectionInfo) {
+    /// Called when a new SSE connection is established, before events flow
+    ///
+    /// Returning an error refuses the connection when the gateway is built
+    /// with `reject_on_connect_error(true)`.
+    async fn on_connect(&self, info: &ConnectionInfo) -> anyhow::Result<()> {
         tracing::info!(
             channel_id = %info.channel_id,
             connection_id = %info.connection_id,
@@ -116,25 +119,25 @@ impl MessageSource for DirectPushSource {
             "Registering channel -> gateway mapping"
         );
 
-        // In production: redis.set_ex(f"channel:{channel_id}:gateway", gateway_addr, 60)
-        // Note: using try_write to avoid blocking in sync context
-        if let Ok(mut registry) = self.channel_registry.try_write() {
-            registry.insert(info.channel_id.clone(), self.gateway_addr.clone());
-        }
+        // In production: redis.set_ex(f"channel:{channel_id}:gateway", gateway_addr, 60).await?
+        self.channel_registry
+            .write()
+            .await
+            .insert(info.channel_id.clone(), self.gateway_addr.clone());
+        Ok(())
     }
 
     /// Called when an SSE connection is closed
-    fn on_disconnect(&self, info: &ConnectionInfo) {
+    async fn on_disconnect(&self, info: &ConnectionInfo) -> anyhow::Result<()> {
         tracing::info!(
             channel_id = %info.channel_id,
             connection_id = %info.connection_id,
             "Cleaning up channel -> gateway mapping"
         );
 
-        // In production: redis.del(f"channel:{channel_id}:gateway")
-        if let Ok(mut registry) = self.channel_registry.try_write() {
-            registry.remove(&info.channel_id);
-        }
+        // In production: redis.del(f"channel:{channel_id}:gateway").await?
+        self.channel_registry.write().await.remove(&info.channel_id);
+        Ok(())
     }
 }
 
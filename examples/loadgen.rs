@@ -0,0 +1,370 @@
+//! Sustained load generator for soak tests
+//!
+//! Holds `CONNECTIONS` SSE subscribers spread over `CHANNELS` channels, publishes
+//! at `RATE` events per second, and prints delivery latency percentiles and
+//! drop counts every `REPORT_INTERVAL` seconds until `DURATION` elapses or
+//! Ctrl+C is pressed. Unlike the one-shot `benchmark` example it keeps
+//! subscribers connected, so reconnects, replay gaps and slow drift show up.
+//!
+//! Prerequisites:
+//!   1. Start Redis: docker run -d -p 6379:6379 redis
+//!   2. Start Gateway: cargo run
+//!
+//! Run:
+//!   cargo run --example loadgen --release
+//!
+//! Options (via env vars):
+//!   GATEWAY_URL=http://localhost:8080
+//!   PUSH_URL=http://localhost:9000
+//!   CONNECTIONS=100
+//!   CHANNELS=10
+//!   RATE=100              (events per second, across all channels)
+//!   DURATION=0            (seconds; 0 runs until Ctrl+C)
+//!   REPORT_INTERVAL=5     (seconds)
+//!
+//! Every event carries its channel sequence number and send time. A subscriber
+//! counts a drop for each sequence number it skips; latency is measured from
+//! the publish call to receipt, so run the generator on a single host.
+
+use futures::StreamExt;
+use sse_gateway_client::{GatewayPublisher, PushMessage, SseClient};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+
+const EVENT_TYPE: &str = "loadgen";
+
+#[derive(Clone)]
+struct LoadConfig {
+    gateway_url: String,
+    push_url: String,
+    connections: usize,
+    channels: usize,
+    rate: f64,
+    duration: Option<Duration>,
+    report_interval: Duration,
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default)
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        let duration: u64 = env_or("DURATION", 0);
+        Self {
+            gateway_url: env_or("GATEWAY_URL", "http://localhost:8080".to_string()),
+            push_url: env_or("PUSH_URL", "http://localhost:9000".to_string()),
+            connections: env_or("CONNECTIONS", 100),
+            channels: env_or("CHANNELS", 10_usize).max(1),
+            rate: env_or("RATE", 100.0),
+            duration: (duration > 0).then(|| Duration::from_secs(duration)),
+            report_interval: Duration::from_secs(env_or("REPORT_INTERVAL", 5_u64).max(1)),
+        }
+    }
+}
+
+impl LoadConfig {
+    fn channel(&self, index: usize) -> String {
+        format!("loadgen_{}", index % self.channels)
+    }
+
+    /// Subscribers on channel `index`
+    fn subscribers_on(&self, index: usize) -> u64 {
+        (self.connections / self.channels + usize::from(index < self.connections % self.channels))
+            as u64
+    }
+}
+
+/// Counters shared by subscribers, publishers and the reporter
+#[derive(Default)]
+struct Stats {
+    connected: AtomicU64,
+    disconnects: AtomicU64,
+    published: AtomicU64,
+    publish_errors: AtomicU64,
+    /// Deliveries the published events should have produced
+    expected: AtomicU64,
+    received: AtomicU64,
+    dropped: AtomicU64,
+    duplicates: AtomicU64,
+    /// Delivery latencies (µs) since the last report
+    latencies: Mutex<Vec<u64>>,
+}
+
+/// Counter values at the last report, for per-interval rates
+#[derive(Default, Clone, Copy)]
+struct Snapshot {
+    published: u64,
+    received: u64,
+}
+
+fn percentile(sorted: &[u64], percentile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let idx = ((sorted.len() as f64 * percentile / 100.0) as usize).min(sorted.len() - 1);
+    Duration::from_micros(sorted[idx])
+}
+
+fn now_micros() -> i64 {
+    chrono::Utc::now().timestamp_micros()
+}
+
+/// One subscriber: track connection state, sequence gaps and latency
+async fn subscribe(config: LoadConfig, index: usize, stats: Arc<Stats>) {
+    let mut events = SseClient::new(&config.gateway_url, config.channel(index)).connect();
+    let mut connected = false;
+    let mut last_seq: Option<u64> = None;
+    let mut check = tokio::time::interval(Duration::from_millis(500));
+
+    loop {
+        let event = tokio::select! {
+            event = events.next() => match event {
+                Some(event) => Some(event),
+                None => break,
+            },
+            _ = check.tick() => None,
+        };
+
+        if events.is_connected() != connected {
+            connected = !connected;
+            if connected {
+                stats.connected.fetch_add(1, Ordering::Relaxed);
+            } else {
+                stats.connected.fetch_sub(1, Ordering::Relaxed);
+                stats.disconnects.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let Some(event) = event.filter(|e| e.event_type == EVENT_TYPE) else {
+            continue;
+        };
+        let Ok(data) = event.data_as::<serde_json::Value>() else {
+            continue;
+        };
+        let (Some(seq), Some(sent_at)) = (data["seq"].as_u64(), data["sent_at"].as_i64()) else {
+            continue;
+        };
+
+        stats.received.fetch_add(1, Ordering::Relaxed);
+        let latency = (now_micros() - sent_at).max(0) as u64;
+        stats.latencies.lock().unwrap().push(latency);
+        match last_seq {
+            Some(last) if seq <= last => {
+                stats.duplicates.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            Some(last) => {
+                stats.dropped.fetch_add(seq - last - 1, Ordering::Relaxed);
+            }
+            None => {}
+        }
+        last_seq = Some(seq);
+    }
+
+    if connected {
+        stats.connected.fetch_sub(1, Ordering::Relaxed);
+    }
+    tracing::warn!(subscriber = index, error = ?events.error(), "Subscriber stopped");
+}
+
+/// Publish to channel `index` at its share of the total rate
+///
+/// Pushes to one channel are sequential so sequence numbers arrive in order.
+async fn publish(config: LoadConfig, index: usize, publisher: GatewayPublisher, stats: Arc<Stats>) {
+    let per_channel = config.rate / config.channels as f64;
+    if per_channel <= 0.0 {
+        return;
+    }
+    let channel = config.channel(index);
+    let subscribers = config.subscribers_on(index);
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / per_channel));
+    // Falling behind shows up as a lower publish rate instead of a burst
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    for seq in 0_u64.. {
+        ticker.tick().await;
+        let data = serde_json::json!({ "seq": seq, "sent_at": now_micros() });
+        match publisher
+            .push(&PushMessage::new(&channel, EVENT_TYPE, data))
+            .await
+        {
+            Ok(_) => {
+                stats.published.fetch_add(1, Ordering::Relaxed);
+                stats.expected.fetch_add(subscribers, Ordering::Relaxed);
+            }
+            Err(e) => {
+                stats.publish_errors.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(error = %e, channel = %channel, "Publish failed");
+            }
+        }
+    }
+}
+
+fn report(
+    stats: &Stats,
+    last: &mut Snapshot,
+    interval: Duration,
+    elapsed: Duration,
+    config: &LoadConfig,
+) {
+    let mut latencies = std::mem::take(&mut *stats.latencies.lock().unwrap());
+    latencies.sort_unstable();
+
+    let published = stats.published.load(Ordering::Relaxed);
+    let received = stats.received.load(Ordering::Relaxed);
+    let secs = interval.as_secs_f64();
+    println!(
+        "[{:>6.0}s] conns {}/{} | pub {:.0}/s ({} err) | recv {:.0}/s | p50 {:?} p95 {:?} p99 {:?} max {:?} | dropped {} dup {} disconnects {}",
+        elapsed.as_secs_f64(),
+        stats.connected.load(Ordering::Relaxed),
+        config.connections,
+        (published - last.published) as f64 / secs,
+        stats.publish_errors.load(Ordering::Relaxed),
+        (received - last.received) as f64 / secs,
+        percentile(&latencies, 50.0),
+        percentile(&latencies, 95.0),
+        percentile(&latencies, 99.0),
+        percentile(&latencies, 100.0),
+        stats.dropped.load(Ordering::Relaxed),
+        stats.duplicates.load(Ordering::Relaxed),
+        stats.disconnects.load(Ordering::Relaxed),
+    );
+    *last = Snapshot {
+        published,
+        received,
+    };
+}
+
+fn summary(stats: &Stats, elapsed: Duration) {
+    let published = stats.published.load(Ordering::Relaxed);
+    let expected = stats.expected.load(Ordering::Relaxed);
+    let received = stats.received.load(Ordering::Relaxed);
+    let delivery = if expected == 0 {
+        100.0
+    } else {
+        received as f64 / expected as f64 * 100.0
+    };
+
+    println!("\n=== Load Generation Summary ===");
+    println!("Duration:       {:?}", elapsed);
+    println!(
+        "Published:      {} ({:.2}/s)",
+        published,
+        published as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "Publish errors: {}",
+        stats.publish_errors.load(Ordering::Relaxed)
+    );
+    println!(
+        "Received:       {} of {} expected ({:.2}%)",
+        received, expected, delivery
+    );
+    println!("Dropped:        {}", stats.dropped.load(Ordering::Relaxed));
+    println!(
+        "Duplicates:     {}",
+        stats.duplicates.load(Ordering::Relaxed)
+    );
+    println!(
+        "Disconnects:    {}",
+        stats.disconnects.load(Ordering::Relaxed)
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .init();
+
+    let config = LoadConfig::default();
+    println!("Load generator");
+    println!("  Gateway URL:  {}", config.gateway_url);
+    println!("  Push URL:     {}", config.push_url);
+    println!(
+        "  Connections:  {} across {} channels",
+        config.connections, config.channels
+    );
+    println!("  Rate:         {} events/s", config.rate);
+    match config.duration {
+        Some(duration) => println!("  Duration:     {:?}", duration),
+        None => println!("  Duration:     until Ctrl+C"),
+    }
+
+    let stats = Arc::new(Stats::default());
+    let mut subscribers = Vec::new();
+    for index in 0..config.connections {
+        subscribers.push(tokio::spawn(subscribe(
+            config.clone(),
+            index,
+            stats.clone(),
+        )));
+    }
+
+    // Publish only once subscribers are in, so early events aren't counted as missing
+    let ready = async {
+        while (stats.connected.load(Ordering::Relaxed) as usize) < config.connections {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+    if tokio::time::timeout(Duration::from_secs(30), ready)
+        .await
+        .is_err()
+    {
+        println!(
+            "Only {}/{} subscribers connected after 30s; publishing anyway",
+            stats.connected.load(Ordering::Relaxed),
+            config.connections
+        );
+    }
+
+    let publisher = GatewayPublisher::new(&config.push_url).timeout(Duration::from_secs(5));
+    let mut publishers = Vec::new();
+    for index in 0..config.channels {
+        publishers.push(tokio::spawn(publish(
+            config.clone(),
+            index,
+            publisher.clone(),
+            stats.clone(),
+        )));
+    }
+
+    let start = Instant::now();
+    let mut last = Snapshot::default();
+    let mut ticker = tokio::time::interval(config.report_interval);
+    ticker.tick().await;
+    let deadline = async {
+        match config.duration {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => report(&stats, &mut last, config.report_interval, start.elapsed(), &config),
+            _ = &mut deadline => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    let elapsed = start.elapsed();
+    for task in publishers {
+        task.abort();
+    }
+    // Let in-flight events land before the final tally
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    for task in subscribers {
+        task.abort();
+    }
+    summary(&stats, elapsed);
+}
@@ -0,0 +1,38 @@
+//! Example: Synthetic traffic
+//!
+//! Publishes generated events to a few channels, with a burst every 10
+//! seconds, so the dashboard has something to show without a producer.
+//! Run with: cargo run --example synthetic
+//!
+//! Then open http://localhost:8080/dashboard and connect to channel "demo",
+//! and change the traffic while it runs:
+//!
+//!   curl -X PUT localhost:8080/api/synthetic -d '{"rate": 200, "channels": ["demo"]}'
+
+use sse_gateway::synthetic::{Burst, PayloadSize, SyntheticSettings, SyntheticSource};
+use sse_gateway::{Gateway, MemoryStorage};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let source = SyntheticSource::new(SyntheticSettings {
+        channels: vec!["demo".into(), "prices".into(), "alerts".into()],
+        event_types: [("tick".into(), 8), ("update".into(), 3), ("alert".into(), 1)].into(),
+        rate: 5.0,
+        payload: PayloadSize::Exponential { mean: 128 },
+        burst: Some(Burst { every_ms: 10_000, events: 50 }),
+    });
+
+    println!("Starting SSE Gateway with synthetic traffic");
+    println!("Open http://localhost:8080/dashboard and connect to channel 'demo'");
+
+    Gateway::builder()
+        .port(8080)
+        .merge(source.router())
+        .source(source)
+        .storage(MemoryStorage::default())
+        .build()?
+        .run()
+        .await
+}
@@ -55,7 +55,7 @@ impl MessageSource for WebhookSource {
             .ok_or_else(|| anyhow::anyhow!("Source already started"))?;
 
         // Start webhook HTTP server
-        let sender = self.sender.clone();
+        let sender = self.sender();
         let webhook_router = Router::new()
             .route("/webhook", post(handle_webhook))
             .with_state(sender);
@@ -81,7 +81,7 @@ impl MessageSource for WebhookSource {
                 _ = cancel.cancelled() => break,
                 msg = receiver.recv() => {
                     match msg {
-                        Some(msg) => handler(msg),
+                        Some(msg) => handler.send(msg),
                         None => break,
                     }
                 }
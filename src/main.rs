@@ -1,357 +1,30 @@
 //! SSE Gateway with Service Discovery and Heartbeat
 //!
 //! Architecture:
-//!   1. Gateway registers itself to Redis on startup (with heartbeat)
-//!   2. Channel → Instance mapping stored in Redis
-//!   3. Agent queries instance info, then pushes directly
+//!   1. Gateway joins the cluster in Redis on startup (with heartbeat)
+//!   2. Channel → Instance ownership stored in Redis
+//!   3. Agent pushes to any instance; messages are forwarded to the owning instances
+//!   4. Instances advertise their load; `GET /route?channel_id=x` on the gateway
+//!      port names the least loaded instance a new subscriber should use
 //!
-//! Redis keys:
-//!   - gateway:instances (SET)           - All active instance IDs
-//!   - gateway:instance:{id} (HASH)      - Instance details {address, last_seen}
-//!   - channel:{channel_id}:instance     - Channel → Instance ID mapping
+//! Redis keys are managed by `RedisCluster` (see its docs):
+//!   - gateway:instances (ZSET)                  - Active instance IDs
+//!   - gateway:instance:{id} (HASH)              - Instance details {address, last_seen, connections, capacity, cpu}
+//!   - gateway:channel:{channel_id}:instances    - Channel → owning instance IDs
 
 use async_trait::async_trait;
 use axum::{extract::State, routing::post, Json, Router};
-use redis::aio::ConnectionManager;
 use sse_gateway::{
-    CancellationToken, ConnectionInfo, Gateway, IncomingMessage, MessageHandler, MessageSource,
+    CancellationToken, ClusterCoordinator, Gateway, IncomingMessage, MessageHandler, MessageSource,
     MessageStorage,
 };
-use sse_gateway_redis::RedisStorage;
+use sse_gateway_redis::{RedisCluster, RedisStorage};
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::mpsc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-// ============================================================================
-// Service Registry - Gateway instance registration with heartbeat
-// ============================================================================
-
-/// Service registry for gateway instances
-#[derive(Clone)]
-struct ServiceRegistry {
-    redis: Arc<RwLock<Option<ConnectionManager>>>,
-    instance_id: String,
-    instance_addr: String,
-    /// Heartbeat interval in seconds
-    heartbeat_interval: u64,
-    /// Instance TTL in seconds (should be > heartbeat_interval * 2)
-    instance_ttl: u64,
-}
-
-impl ServiceRegistry {
-    fn new(instance_id: String, instance_addr: String) -> Self {
-        Self {
-            redis: Arc::new(RwLock::new(None)),
-            instance_id,
-            instance_addr,
-            heartbeat_interval: 10,
-            instance_ttl: 30,
-        }
-    }
-
-    async fn connect(&self, redis_url: &str) -> anyhow::Result<()> {
-        let client = redis::Client::open(redis_url)?;
-        let manager = ConnectionManager::new(client).await?;
-        *self.redis.write().await = Some(manager);
-        Ok(())
-    }
-
-    /// Register this instance and start heartbeat
-    async fn register_and_start_heartbeat(&self, cancel: CancellationToken) {
-        // Initial registration
-        self.register_instance().await;
-
-        // Start heartbeat loop
-        let registry = self.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(registry.heartbeat_interval));
-            loop {
-                tokio::select! {
-                    _ = cancel.cancelled() => {
-                        // Unregister on shutdown
-                        registry.unregister_instance().await;
-                        break;
-                    }
-                    _ = interval.tick() => {
-                        registry.heartbeat().await;
-                    }
-                }
-            }
-        });
-    }
-
-    /// Register this gateway instance
-    async fn register_instance(&self) {
-        let Some(ref mut conn) = *self.redis.write().await else { return };
-
-        let now = chrono::Utc::now().timestamp();
-
-        // Use pipeline for atomic registration
-        let mut pipe = redis::pipe();
-        
-        // Add to instances set
-        pipe.cmd("SADD")
-            .arg("gateway:instances")
-            .arg(&self.instance_id)
-            .ignore();
-
-        // Set instance details with TTL
-        let instance_key = format!("gateway:instance:{}", self.instance_id);
-        pipe.cmd("HSET")
-            .arg(&instance_key)
-            .arg("address")
-            .arg(&self.instance_addr)
-            .arg("last_seen")
-            .arg(now)
-            .arg("registered_at")
-            .arg(now)
-            .ignore();
-
-        pipe.cmd("EXPIRE")
-            .arg(&instance_key)
-            .arg(self.instance_ttl)
-            .ignore();
-
-        if let Err(e) = pipe.query_async::<()>(conn).await {
-            tracing::warn!(error = %e, "Failed to register instance");
-        } else {
-            tracing::info!(
-                instance_id = %self.instance_id,
-                address = %self.instance_addr,
-                "Instance registered"
-            );
-        }
-    }
-
-    /// Send heartbeat to keep instance alive
-    async fn heartbeat(&self) {
-        let Some(ref mut conn) = *self.redis.write().await else { return };
-
-        let now = chrono::Utc::now().timestamp();
-        let instance_key = format!("gateway:instance:{}", self.instance_id);
-
-        let mut pipe = redis::pipe();
-        
-        // Update last_seen
-        pipe.cmd("HSET")
-            .arg(&instance_key)
-            .arg("last_seen")
-            .arg(now)
-            .ignore();
-
-        // Refresh TTL
-        pipe.cmd("EXPIRE")
-            .arg(&instance_key)
-            .arg(self.instance_ttl)
-            .ignore();
-
-        if let Err(e) = pipe.query_async::<()>(conn).await {
-            tracing::warn!(error = %e, "Heartbeat failed");
-        } else {
-            tracing::debug!(instance_id = %self.instance_id, "Heartbeat sent");
-        }
-    }
-
-    /// Unregister this instance (on shutdown)
-    async fn unregister_instance(&self) {
-        let Some(ref mut conn) = *self.redis.write().await else { return };
-
-        let instance_key = format!("gateway:instance:{}", self.instance_id);
-
-        let mut pipe = redis::pipe();
-        pipe.cmd("SREM")
-            .arg("gateway:instances")
-            .arg(&self.instance_id)
-            .ignore();
-        pipe.cmd("DEL")
-            .arg(&instance_key)
-            .ignore();
-
-        if let Err(e) = pipe.query_async::<()>(conn).await {
-            tracing::warn!(error = %e, "Failed to unregister instance");
-        } else {
-            tracing::info!(instance_id = %self.instance_id, "Instance unregistered");
-        }
-    }
-
-    /// Get all active instances (for debugging/monitoring)
-    async fn get_all_instances(&self) -> anyhow::Result<Vec<InstanceInfo>> {
-        let Some(ref mut conn) = *self.redis.write().await else {
-            anyhow::bail!("Redis not connected");
-        };
-
-        // Get all instance IDs
-        let instance_ids: Vec<String> = redis::cmd("SMEMBERS")
-            .arg("gateway:instances")
-            .query_async(conn)
-            .await?;
-
-        let mut instances = Vec::new();
-        for id in instance_ids {
-            let key = format!("gateway:instance:{}", id);
-            let info: Option<(String, i64)> = redis::cmd("HMGET")
-                .arg(&key)
-                .arg("address")
-                .arg("last_seen")
-                .query_async(conn)
-                .await
-                .ok();
-
-            if let Some((address, last_seen)) = info {
-                instances.push(InstanceInfo {
-                    id: id.clone(),
-                    address,
-                    last_seen,
-                });
-            }
-        }
-
-        Ok(instances)
-    }
-
-    /// Get instance address by ID
-    async fn get_instance_address(&self, instance_id: &str) -> Option<String> {
-        let Some(ref mut conn) = *self.redis.write().await else { return None };
-
-        let key = format!("gateway:instance:{}", instance_id);
-        redis::cmd("HGET")
-            .arg(&key)
-            .arg("address")
-            .query_async(conn)
-            .await
-            .ok()
-    }
-}
-
-#[derive(serde::Serialize)]
-struct InstanceInfo {
-    id: String,
-    address: String,
-    last_seen: i64,
-}
-
-// ============================================================================
-// Channel Registry - Channel → Instance mapping
-// ============================================================================
-
-/// Channel to instance mapping
-#[derive(Clone)]
-struct ChannelRegistry {
-    redis: Arc<RwLock<Option<ConnectionManager>>>,
-    instance_id: String,
-    /// TTL for channel mappings
-    channel_ttl: u64,
-}
-
-impl ChannelRegistry {
-    fn new(instance_id: String, channel_ttl: u64) -> Self {
-        Self {
-            redis: Arc::new(RwLock::new(None)),
-            instance_id,
-            channel_ttl,
-        }
-    }
-
-    async fn connect(&self, redis_url: &str) -> anyhow::Result<()> {
-        let client = redis::Client::open(redis_url)?;
-        let manager = ConnectionManager::new(client).await?;
-        *self.redis.write().await = Some(manager);
-        Ok(())
-    }
-
-    fn channel_key(channel_id: &str) -> String {
-        format!("channel:{}:instance", channel_id)
-    }
-
-    /// Register channel → instance mapping
-    async fn register_channel(&self, channel_id: &str) {
-        let Some(ref mut conn) = *self.redis.write().await else { return };
-
-        let key = Self::channel_key(channel_id);
-
-        let result: Result<(), _> = redis::cmd("SET")
-            .arg(&key)
-            .arg(&self.instance_id)
-            .arg("EX")
-            .arg(self.channel_ttl)
-            .query_async(conn)
-            .await;
-
-        match result {
-            Ok(_) => tracing::debug!(channel_id, instance_id = %self.instance_id, "Channel registered"),
-            Err(e) => tracing::warn!(error = %e, channel_id, "Failed to register channel"),
-        }
-    }
-
-    /// Unregister channel
-    async fn unregister_channel(&self, channel_id: &str) {
-        let Some(ref mut conn) = *self.redis.write().await else { return };
-
-        let key = Self::channel_key(channel_id);
-
-        // Only delete if it's our instance (prevent race condition)
-        let script = redis::Script::new(
-            r#"
-            if redis.call('GET', KEYS[1]) == ARGV[1] then
-                return redis.call('DEL', KEYS[1])
-            end
-            return 0
-            "#,
-        );
-
-        let _: Result<i32, _> = script
-            .key(&key)
-            .arg(&self.instance_id)
-            .invoke_async(conn)
-            .await;
-
-        tracing::debug!(channel_id, "Channel unregistered");
-    }
-
-    /// Get instance ID for a channel
-    async fn get_channel_instance(&self, channel_id: &str) -> Option<String> {
-        let Some(ref mut conn) = *self.redis.write().await else { return None };
-
-        let key = Self::channel_key(channel_id);
-        redis::cmd("GET")
-            .arg(&key)
-            .query_async(conn)
-            .await
-            .ok()
-    }
-
-    /// Get all channel mappings (for debugging)
-    async fn get_all_channels(&self) -> anyhow::Result<std::collections::HashMap<String, String>> {
-        let Some(ref mut conn) = *self.redis.write().await else {
-            anyhow::bail!("Redis not connected");
-        };
-
-        let keys: Vec<String> = redis::cmd("KEYS")
-            .arg("channel:*:instance")
-            .query_async(conn)
-            .await?;
-
-        let mut result = std::collections::HashMap::new();
-        for key in keys {
-            if let Ok(instance_id) = redis::cmd("GET")
-                .arg(&key)
-                .query_async::<String>(conn)
-                .await
-            {
-                if let Some(channel_id) = key
-                    .strip_prefix("channel:")
-                    .and_then(|s| s.strip_suffix(":instance"))
-                {
-                    result.insert(channel_id.to_string(), instance_id);
-                }
-            }
-        }
-
-        Ok(result)
-    }
-}
-
 // ============================================================================
 // Direct Push Source
 // ============================================================================
@@ -360,25 +33,18 @@ struct DirectPushSource {
     push_port: u16,
     receiver: tokio::sync::Mutex<Option<mpsc::Receiver<IncomingMessage>>>,
     sender: mpsc::Sender<IncomingMessage>,
-    service_registry: ServiceRegistry,
-    channel_registry: ChannelRegistry,
+    cluster: RedisCluster,
     storage: RedisStorage,
 }
 
 impl DirectPushSource {
-    fn new(
-        push_port: u16,
-        service_registry: ServiceRegistry,
-        channel_registry: ChannelRegistry,
-        storage: RedisStorage,
-    ) -> Self {
+    fn new(push_port: u16, cluster: RedisCluster, storage: RedisStorage) -> Self {
         let (sender, receiver) = mpsc::channel(1000);
         Self {
             push_port,
             receiver: tokio::sync::Mutex::new(Some(receiver)),
             sender,
-            service_registry,
-            channel_registry,
+            cluster,
             storage,
         }
     }
@@ -389,7 +55,7 @@ impl MessageSource for DirectPushSource {
     async fn start(
         &self,
         handler: MessageHandler,
-        connection_manager: sse_gateway::ConnectionManager,
+        _connection_manager: sse_gateway::ConnectionManager,
         cancel: CancellationToken,
     ) -> anyhow::Result<()> {
         let mut receiver = self
@@ -399,16 +65,11 @@ impl MessageSource for DirectPushSource {
             .take()
             .ok_or_else(|| anyhow::anyhow!("Source already started"))?;
 
-        // Start heartbeat
-        self.service_registry.register_and_start_heartbeat(cancel.clone()).await;
-
         // Start HTTP server
         let state = AppState {
             sender: self.sender.clone(),
-            service_registry: self.service_registry.clone(),
-            channel_registry: self.channel_registry.clone(),
+            cluster: self.cluster.clone(),
             storage: self.storage.clone(),
-            connection_manager,
         };
 
         let push_router = Router::new()
@@ -437,7 +98,7 @@ impl MessageSource for DirectPushSource {
                 _ = cancel.cancelled() => break,
                 msg = receiver.recv() => {
                     match msg {
-                        Some(msg) => handler(msg),
+                        Some(msg) => handler.send(msg),
                         None => break,
                     }
                 }
@@ -450,24 +111,6 @@ impl MessageSource for DirectPushSource {
     fn name(&self) -> &'static str {
         "DirectPush+ServiceDiscovery"
     }
-
-    fn on_connect(&self, info: &ConnectionInfo) {
-        let registry = self.channel_registry.clone();
-        let channel_id = info.channel_id.clone();
-
-        tokio::spawn(async move {
-            registry.register_channel(&channel_id).await;
-        });
-    }
-
-    fn on_disconnect(&self, info: &ConnectionInfo) {
-        let registry = self.channel_registry.clone();
-        let channel_id = info.channel_id.clone();
-
-        tokio::spawn(async move {
-            registry.unregister_channel(&channel_id).await;
-        });
-    }
 }
 
 // ============================================================================
@@ -477,10 +120,8 @@ impl MessageSource for DirectPushSource {
 #[derive(Clone)]
 struct AppState {
     sender: mpsc::Sender<IncomingMessage>,
-    service_registry: ServiceRegistry,
-    channel_registry: ChannelRegistry,
+    cluster: RedisCluster,
     storage: RedisStorage,
-    connection_manager: sse_gateway::ConnectionManager,
 }
 
 #[derive(serde::Deserialize)]
@@ -516,37 +157,38 @@ struct ChannelStatus {
     online: bool,
     instance_id: Option<String>,
     instance_address: Option<String>,
+    /// Every instance holding subscribers on the channel
+    instance_ids: Vec<String>,
 }
 
 /// Push message to channel
+///
+/// The gateway stores the message and forwards it to the instances that hold
+/// the channel's subscribers.
 async fn handle_push(
     State(state): State<AppState>,
     Json(payload): Json<PushPayload>,
 ) -> Json<PushResponse> {
-    use sse_gateway::SseEvent;
-
-    let channel_id = payload.channel_id.clone().unwrap_or_default();
-    let stream_id = state.storage.generate_id();
-
-    let online = if !channel_id.is_empty() {
-        state.connection_manager.channel_connection_count(&channel_id) > 0
-    } else {
-        false
-    };
-
     let mut msg = IncomingMessage::new(&payload.event_type, payload.data.to_string());
     if let Some(cid) = payload.channel_id {
         msg = msg.with_channel(cid);
     }
+    let (msg, report) = msg.with_report();
 
-    let success = state.sender.send(msg).await.is_ok();
-
-    if !channel_id.is_empty() {
-        let event = SseEvent::raw(&payload.event_type, payload.data.to_string());
-        state.storage.store(&channel_id, &stream_id, &event).await;
+    if state.sender.send(msg).await.is_err() {
+        return Json(PushResponse {
+            success: false,
+            online: false,
+            stream_id: String::new(),
+        });
     }
+    let report = report.await.unwrap_or_default();
 
-    Json(PushResponse { success, online, stream_id })
+    Json(PushResponse {
+        success: !report.throttled && !report.filtered && !report.expired,
+        online: report.cluster_online.unwrap_or(report.online),
+        stream_id: report.stream_id.unwrap_or_default(),
+    })
 }
 
 /// Store message for offline user
@@ -568,10 +210,18 @@ async fn handle_channel_status(
     State(state): State<AppState>,
     axum::extract::Path(channel_id): axum::extract::Path<String>,
 ) -> Json<ChannelStatus> {
-    let instance_id = state.channel_registry.get_channel_instance(&channel_id).await;
-    
+    let instance_ids = state.cluster.channel_owners(&channel_id).await.unwrap_or_default();
+    let instance_id = instance_ids.first().cloned();
+
     let instance_address = match &instance_id {
-        Some(id) => state.service_registry.get_instance_address(id).await,
+        Some(id) => state
+            .cluster
+            .instances()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .find(|instance| instance.id == *id)
+            .and_then(|instance| instance.address),
         None => None,
     };
 
@@ -580,12 +230,13 @@ async fn handle_channel_status(
         online: instance_id.is_some(),
         instance_id,
         instance_address,
+        instance_ids,
     })
 }
 
 /// List all gateway instances
 async fn get_instances(State(state): State<AppState>) -> Json<serde_json::Value> {
-    match state.service_registry.get_all_instances().await {
+    match state.cluster.instances().await {
         Ok(instances) => Json(serde_json::json!({
             "instances": instances,
             "count": instances.len()
@@ -596,12 +247,31 @@ async fn get_instances(State(state): State<AppState>) -> Json<serde_json::Value>
 
 /// List all channel mappings
 async fn get_channels(State(state): State<AppState>) -> Json<serde_json::Value> {
-    match state.channel_registry.get_all_channels().await {
+    match state.cluster.channels().await {
         Ok(channels) => Json(serde_json::json!(channels)),
         Err(e) => Json(serde_json::json!({"error": e.to_string()})),
     }
 }
 
+/// Sample the 1-minute load average as a share of the available cores
+///
+/// Linux only; elsewhere the hint stays at 0.
+fn spawn_cpu_sampler(cpu: Arc<AtomicU8>) {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            let load = std::fs::read_to_string("/proc/loadavg")
+                .ok()
+                .and_then(|s| s.split_whitespace().next()?.parse::<f64>().ok());
+            if let Some(load) = load {
+                cpu.store((load / cores * 100.0).clamp(0.0, 100.0) as u8, Ordering::Relaxed);
+            }
+        }
+    });
+}
+
 // ============================================================================
 // Main
 // ============================================================================
@@ -642,27 +312,20 @@ async fn main() -> anyhow::Result<()> {
     let instance_addr = std::env::var("GATEWAY_ADDR")
         .unwrap_or_else(|_| format!("localhost:{}", push_port));
 
+    // How long registrations and channel claims outlive a dead instance
     let channel_ttl: u64 = std::env::var("CHANNEL_TTL")
         .ok()
         .and_then(|t| t.parse().ok())
-        .unwrap_or(60);
-
-    // Initialize registries
-    let service_registry = ServiceRegistry::new(instance_id.clone(), instance_addr.clone());
-    service_registry.connect(&redis_url).await?;
+        .unwrap_or(30);
 
-    let channel_registry = ChannelRegistry::new(instance_id.clone(), channel_ttl);
-    channel_registry.connect(&redis_url).await?;
+    let cluster = RedisCluster::new(&redis_url)
+        .address(&instance_addr)
+        .instance_ttl(Duration::from_secs(channel_ttl));
 
     let storage = RedisStorage::new();
     storage.connect(&redis_url).await?;
 
-    let source = DirectPushSource::new(
-        push_port,
-        service_registry,
-        channel_registry,
-        storage.clone(),
-    );
+    let source = DirectPushSource::new(push_port, cluster.clone(), storage.clone());
 
     tracing::info!(
         gateway_port,
@@ -680,6 +343,7 @@ async fn main() -> anyhow::Result<()> {
     println!("Instance Addr:    {}", instance_addr);
     println!();
     println!("SSE endpoint:     http://localhost:{}/sse/connect?channel_id=test", gateway_port);
+    println!("Routing:          http://localhost:{}/route?channel_id=test", gateway_port);
     println!("Dashboard:        http://localhost:{}/dashboard", gateway_port);
     println!();
     println!("Push API (port {}):", push_port);
@@ -690,10 +354,59 @@ async fn main() -> anyhow::Result<()> {
     println!("  GET  /channels          List all channel mappings");
     println!();
 
-    Gateway::builder()
+    let cpu = Arc::new(AtomicU8::new(0));
+    spawn_cpu_sampler(cpu.clone());
+
+    let mut builder = Gateway::builder()
         .port(gateway_port)
         .instance_id(instance_id)
+        .cluster(cluster)
         .dashboard(true)
+        .grpc(std::env::var("GRPC_ENABLED").is_ok_and(|v| v == "true" || v == "1"))
+        .cpu_hint(move || cpu.load(Ordering::Relaxed));
+
+    // Connections this instance is sized for, advertised for load-aware routing
+    if let Some(max) = std::env::var("MAX_CONNECTIONS").ok().and_then(|m| m.parse().ok()) {
+        builder = builder.capacity(max);
+    }
+
+    // Optional connect/disconnect notifications; USER_ID_HEADER names the caller
+    if let Ok(url) = std::env::var("LIFECYCLE_WEBHOOK_URL") {
+        tracing::info!(url = %url, "Lifecycle webhook enabled");
+        let mut webhook = sse_gateway::webhook::LifecycleWebhook::new(url);
+        if let Ok(secret) = std::env::var("LIFECYCLE_WEBHOOK_SECRET") {
+            webhook = webhook.secret(secret);
+        }
+        builder = builder.lifecycle_webhook(webhook);
+    }
+    if let Ok(header) = std::env::var("USER_ID_HEADER") {
+        builder = builder.identity(move |req| req.header(&header).map(str::to_string));
+    }
+
+    if std::env::var("SSE_COMPRESSION").is_ok_and(|v| v == "true" || v == "1") {
+        builder = builder.compression(sse_gateway::Compression::default());
+    }
+
+    // Optional HTTPS termination (no fronting proxy)
+    if let (Ok(cert), Ok(key)) = (std::env::var("TLS_CERT_PATH"), std::env::var("TLS_KEY_PATH")) {
+        tracing::info!(cert = %cert, "TLS enabled");
+        builder = builder.tls(cert, key);
+        if let Some(secs) = std::env::var("TLS_RELOAD_SECS").ok().and_then(|s| s.parse().ok()) {
+            builder = builder.tls_reload_interval(Duration::from_secs(secs));
+        }
+    }
+
+    // Optional payload validation: JSON object of event_type -> JSON Schema
+    if let Ok(path) = std::env::var("PAYLOAD_SCHEMAS") {
+        let schemas: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        tracing::info!(path = %path, count = schemas.len(), "Payload schemas loaded");
+        for (event_type, schema) in schemas {
+            builder = builder.schema(event_type, schema);
+        }
+    }
+
+    builder
         .source(source)
         .storage(storage)
         .build()?